@@ -0,0 +1,238 @@
+//! 命令行参数解析（子命令 + 少量选项，不引入参数解析库）
+
+use std::path::PathBuf;
+
+use ai_disk_scanner::{GitignoreOptions, WalkOptions};
+
+/// `top` 默认列出的文件数
+pub const DEFAULT_TOP_N: usize = 20;
+
+pub const USAGE: &str = "\
+用法: ai-disk-cli <命令> <路径> [选项]
+
+命令:
+  scan <路径>              按大小列出路径下的各项
+  top <路径> [-n 数量]     列出最大的文件（默认 20 个）
+  export <路径> [-o 文件]  输出完整扫描结果 JSON（默认写到标准输出）
+                           加 --jsonl 时边扫描边逐行输出（JSON Lines），不在内存中保留整棵树
+
+选项:
+  -j, --threads <数量>     扫描线程数（默认按 CPU 核数与磁盘类型决定）
+      --deep               递归进入 node_modules、.git 等目录（默认只计大小）
+      --gitignore          跳过各级 .gitignore 忽略的文件（不能与 --jsonl 同用）
+      --ignore-file <名称> 同时读取各目录中该名称的忽略文件（隐含 --gitignore）
+  -x, --one-file-system    不进入其他文件系统的挂载点（扫描卷根时默认开启）
+      --cross-mounts       进入其他文件系统的挂载点
+  -q, --quiet              不在标准错误输出进度
+  -h, --help               显示本帮助";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Scan,
+    Top {
+        n: usize,
+    },
+    Export {
+        output: Option<PathBuf>,
+        /// 输出 JSON Lines 流而非单个 ScanResult
+        jsonl: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub command: Command,
+    pub path: String,
+    pub threads: Option<usize>,
+    pub shallow_dirs: bool,
+    /// 忽略规则与是否跨越挂载点
+    pub walk: WalkOptions,
+    pub quiet: bool,
+}
+
+/// 解析结果：`Help` 表示打印用法后正常退出
+#[derive(Debug, PartialEq, Eq)]
+pub enum Parsed {
+    Run(Args),
+    Help,
+}
+
+/// 解析不含程序名的参数列表；出错时返回面向用户的错误信息
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Parsed, String> {
+    let mut args = args.into_iter();
+    let mut command = match args.next().as_deref() {
+        None | Some("-h" | "--help" | "help") => return Ok(Parsed::Help),
+        Some("scan") => Command::Scan,
+        Some("top") => Command::Top { n: DEFAULT_TOP_N },
+        Some("export") => Command::Export {
+            output: None,
+            jsonl: false,
+        },
+        Some(other) => return Err(format!("未知命令: {}", other)),
+    };
+
+    let mut path = None;
+    let mut threads = None;
+    let mut shallow_dirs = true;
+    let mut walk = WalkOptions::default();
+    let mut quiet = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Parsed::Help),
+            "-q" | "--quiet" => quiet = true,
+            "--deep" => shallow_dirs = false,
+            "--gitignore" => {
+                walk.gitignore.get_or_insert_with(GitignoreOptions::default);
+            }
+            "--ignore-file" => {
+                let name = args.next().ok_or_else(|| format!("{} 需要文件名", arg))?;
+                walk.gitignore
+                    .get_or_insert_with(GitignoreOptions::default)
+                    .ignore_file_name = Some(name);
+            }
+            "-x" | "--one-file-system" => walk.same_filesystem_only = Some(true),
+            "--cross-mounts" => walk.same_filesystem_only = Some(false),
+            "-j" | "--threads" => threads = Some(parse_count(&arg, args.next().as_deref())?),
+            "-n" | "--count" => match &mut command {
+                Command::Top { n } => *n = parse_count(&arg, args.next().as_deref())?,
+                _ => return Err(format!("{} 只能用于 top 命令", arg)),
+            },
+            "-o" | "--output" => match &mut command {
+                Command::Export { output, .. } => {
+                    let file = args.next().ok_or_else(|| format!("{} 需要文件路径", arg))?;
+                    *output = Some(PathBuf::from(file));
+                }
+                _ => return Err(format!("{} 只能用于 export 命令", arg)),
+            },
+            "--jsonl" => match &mut command {
+                Command::Export { jsonl, .. } => *jsonl = true,
+                _ => return Err(format!("{} 只能用于 export 命令", arg)),
+            },
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("未知选项: {}", arg));
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("多余的参数: {}", arg)),
+        }
+    }
+
+    if walk.gitignore.is_some() && matches!(command, Command::Export { jsonl: true, .. }) {
+        return Err("--gitignore 不能与 --jsonl 同时使用".to_string());
+    }
+
+    Ok(Parsed::Run(Args {
+        command,
+        path: path.ok_or_else(|| "缺少要扫描的路径".to_string())?,
+        threads,
+        shallow_dirs,
+        walk,
+        quiet,
+    }))
+}
+
+/// 解析选项后的正整数
+fn parse_count(option: &str, value: Option<&str>) -> Result<usize, String> {
+    match value.map(str::parse::<usize>) {
+        Some(Ok(n)) if n > 0 => Ok(n),
+        Some(_) => Err(format!("{} 需要正整数", option)),
+        None => Err(format!("{} 缺少数值", option)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Parsed, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    fn run(args: &str) -> Args {
+        match parse_str(args).unwrap() {
+            Parsed::Run(args) => args,
+            Parsed::Help => panic!("unexpected help for {:?}", args),
+        }
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let args = run("scan /tmp");
+        assert_eq!(args.command, Command::Scan);
+        assert_eq!(args.path, "/tmp");
+        assert_eq!(args.threads, None);
+        assert!(args.shallow_dirs);
+        assert!(!args.quiet);
+
+        assert_eq!(run("top /tmp").command, Command::Top { n: DEFAULT_TOP_N });
+        assert_eq!(run("top -n 5 /tmp").command, Command::Top { n: 5 });
+        assert_eq!(
+            run("export /tmp -o out.json").command,
+            Command::Export {
+                output: Some(PathBuf::from("out.json")),
+                jsonl: false,
+            }
+        );
+        assert_eq!(
+            run("export --jsonl /tmp").command,
+            Command::Export {
+                output: None,
+                jsonl: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args = run("scan -j 4 --deep -q /data");
+        assert_eq!(args.threads, Some(4));
+        assert!(!args.shallow_dirs);
+        assert!(args.quiet);
+        assert_eq!(args.path, "/data");
+    }
+
+    #[test]
+    fn test_parse_gitignore() {
+        assert_eq!(run("scan /data").walk, WalkOptions::default());
+        assert_eq!(
+            run("scan --gitignore /data").walk.gitignore,
+            Some(GitignoreOptions::default())
+        );
+        assert_eq!(
+            run("top /data --ignore-file .scanignore").walk.gitignore,
+            Some(GitignoreOptions {
+                ignore_file_name: Some(".scanignore".to_string()),
+            })
+        );
+        assert!(parse_str("scan /data --ignore-file").is_err());
+        assert!(parse_str("export --jsonl --gitignore /data").is_err());
+    }
+
+    #[test]
+    fn test_parse_mount_options() {
+        assert_eq!(run("scan /").walk.same_filesystem_only, None);
+        assert_eq!(run("scan -x /").walk.same_filesystem_only, Some(true));
+        assert_eq!(
+            run("scan --cross-mounts /").walk.same_filesystem_only,
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert_eq!(parse_str(""), Ok(Parsed::Help));
+        assert_eq!(parse_str("--help"), Ok(Parsed::Help));
+        assert_eq!(parse_str("scan -h"), Ok(Parsed::Help));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_str("remove /tmp").is_err());
+        assert!(parse_str("scan").is_err());
+        assert!(parse_str("scan /a /b").is_err());
+        assert!(parse_str("scan -n 5 /tmp").is_err());
+        assert!(parse_str("top -n 0 /tmp").is_err());
+        assert!(parse_str("top /tmp -n").is_err());
+        assert!(parse_str("export /tmp --bogus").is_err());
+        assert!(parse_str("scan --jsonl /tmp").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! AI Disk Analyzer 命令行：复用 ai-disk-scanner 扫描目录，不依赖桌面端。
+//! 结果写到标准输出，进度写到标准错误。
+
+mod args;
+mod report;
+
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ai_disk_domain::ScanResult;
+use ai_disk_scanner::{scan_path_with_progress, scan_to_writer, StreamOptions};
+
+use args::{Args, Command, Parsed, USAGE};
+
+/// 两次进度输出的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 进度行中路径的最大字符数
+const PROGRESS_PATH_CHARS: usize = 60;
+
+type Progress = Arc<Box<dyn Fn(u64, &str) + Send + Sync>>;
+
+fn main() -> ExitCode {
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(Parsed::Run(args)) => args,
+        Ok(Parsed::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("错误: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    if let Command::Export {
+        output,
+        jsonl: true,
+    } = &args.command
+    {
+        return export_jsonl(args, output.as_deref());
+    }
+    let result = scan(args)?;
+    match &args.command {
+        Command::Scan => print!("{}", report::render_listing(&result)),
+        Command::Top { n } => print!("{}", report::render_top(&result, *n)),
+        Command::Export { output, .. } => {
+            let json = serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?;
+            match output {
+                Some(file) => std::fs::write(file, json)
+                    .map_err(|e| format!("无法写入 {}: {}", file.display(), e))?,
+                None => println!("{}", json),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 边扫描边写出 JSON Lines（顺序遍历，不建树）
+fn export_jsonl(args: &Args, output: Option<&Path>) -> Result<(), String> {
+    let options = StreamOptions {
+        tree: false,
+        shallow_dirs: args.shallow_dirs,
+    };
+    let written = match output {
+        Some(file) => {
+            let writer = std::fs::File::create(file)
+                .map_err(|e| format!("无法写入 {}: {}", file.display(), e))?;
+            scan_to_writer(&args.path, writer, &options)
+        }
+        None => scan_to_writer(&args.path, std::io::stdout().lock(), &options),
+    };
+    written.map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn scan(args: &Args) -> Result<ScanResult, String> {
+    // 输出被重定向时不刷进度行
+    let progress = (!args.quiet && std::io::stderr().is_terminal()).then(progress_reporter);
+    let scanned = scan_path_with_progress(
+        &args.path,
+        progress.as_ref(),
+        args.shallow_dirs,
+        false,
+        args.threads,
+        None,
+        &args.walk,
+    );
+    if progress.is_some() {
+        eprint!("\r\x1b[K");
+    }
+    let (result, _) = scanned.map_err(|e| e.to_string())?;
+    if let Some(warning) = &result.scan_warning {
+        eprintln!("警告: {}", warning);
+    }
+    if let Some(ignored) = result.ignored_bytes {
+        eprintln!("已按忽略规则跳过 {}", report::format_size(ignored));
+    }
+    Ok(result)
+}
+
+/// 在标准错误的同一行上刷新已扫描文件数与当前路径，按 [`PROGRESS_INTERVAL`] 限频
+fn progress_reporter() -> Progress {
+    let last = Mutex::new(None::<Instant>);
+    Arc::new(Box::new(move |count: u64, path: &str| {
+        let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+        if last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r\x1b[K已扫描 {} 个文件  {}",
+            count,
+            tail_chars(path, PROGRESS_PATH_CHARS)
+        );
+        let _ = stderr.flush();
+    }))
+}
+
+/// 超长时只保留末尾的字符，前面加上省略号；省略号计入 `max`，结果恰好 `max` 个字符
+fn tail_chars(s: &str, max: usize) -> String {
+    let len = s.chars().count();
+    if len <= max {
+        return s.to_string();
+    }
+    let tail: String = s.chars().skip(len - max + 1).collect();
+    format!("…{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_chars() {
+        assert_eq!(tail_chars("/a/b", 10), "/a/b");
+        assert_eq!(tail_chars("/home/user/项目/文件", 6), "…项目/文件");
+        assert_eq!(tail_chars("abcdef", 6), "abcdef");
+        assert_eq!(tail_chars("abcdefg", 6), "…cdefg");
+    }
+
+    #[test]
+    fn test_scan_then_list() {
+        let dir = std::env::temp_dir().join(format!("ai-disk-cli-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("big.bin"), vec![0u8; 4096]).unwrap();
+        std::fs::write(dir.join("small.txt"), b"hi").unwrap();
+
+        let parsed = args::parse(["scan".to_string(), dir.display().to_string(), "-q".into()]);
+        let Ok(Parsed::Run(args)) = parsed else {
+            panic!("unexpected parse result");
+        };
+        let result = scan(&args);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let result = result.unwrap();
+
+        assert_eq!(result.file_count, 2);
+        let listing = report::render_listing(&result);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert!(lines[1].ends_with(&format!("sub{}", std::path::MAIN_SEPARATOR)));
+        assert!(lines[2].ends_with("small.txt"));
+        let top = report::largest_files(&result.root, 1);
+        assert_eq!(top[0].name, "big.bin");
+    }
+}
@@ -0,0 +1,158 @@
+//! 扫描结果的文本输出：按大小排序的目录列表与最大文件列表
+
+use std::cmp::Reverse;
+use std::fmt::Write;
+
+use ai_disk_common::{format_bytes, ByteStyle};
+use ai_disk_domain::{FileNode, ScanResult};
+
+/// 人类可读的大小（1024 进制，如 `1.5 GiB`），与界面、提示词的写法一致
+pub fn format_size(bytes: u64) -> String {
+    format_bytes(bytes, ByteStyle::Binary)
+}
+
+/// 摘要行：路径、总大小、文件数与耗时
+fn summary(result: &ScanResult) -> String {
+    format!(
+        "{}  {}  {} 个文件  {} ms",
+        result.root.path,
+        format_size(result.total_size),
+        result.file_count,
+        result.scan_time_ms
+    )
+}
+
+/// 根目录下各项按大小降序列出，附占比；目录名后加路径分隔符
+pub fn render_listing(result: &ScanResult) -> String {
+    let mut children: Vec<&FileNode> = result.root.children.iter().collect();
+    children.sort_by_key(|c| (Reverse(c.size), &c.name));
+
+    let mut out = summary(result);
+    out.push('\n');
+    for child in children {
+        let percent = if result.total_size == 0 {
+            0.0
+        } else {
+            child.size as f64 * 100.0 / result.total_size as f64
+        };
+        let suffix = if child.is_dir {
+            std::path::MAIN_SEPARATOR_STR
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "{:>10}  {:>5.1}%  {}{}",
+            format_size(child.size),
+            percent,
+            child.name,
+            suffix
+        );
+    }
+    out
+}
+
+/// 树中最大的 `n` 个文件（大小降序，同大小按路径）
+pub fn largest_files(root: &FileNode, n: usize) -> Vec<&FileNode> {
+    let mut files = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.is_dir {
+            stack.extend(node.children.iter());
+        } else {
+            files.push(node);
+        }
+    }
+    files.sort_by_key(|f| (Reverse(f.size), &f.path));
+    files.truncate(n);
+    files
+}
+
+/// 最大文件列表
+pub fn render_top(result: &ScanResult, n: usize) -> String {
+    let mut out = summary(result);
+    out.push('\n');
+    for file in largest_files(&result.root, n) {
+        let _ = writeln!(out, "{:>10}  {}", format_size(file.size), file.path);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, size: u64, is_dir: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(format!("/r/{}", name), size, is_dir).with_children(children)
+    }
+
+    fn result() -> ScanResult {
+        let root = node(
+            "r",
+            1000,
+            true,
+            vec![
+                node("small.txt", 100, false, vec![]),
+                node(
+                    "big",
+                    900,
+                    true,
+                    vec![
+                        node("a.bin", 600, false, vec![]),
+                        node("b.bin", 300, false, vec![]),
+                    ],
+                ),
+            ],
+        );
+        ScanResult {
+            scan_id: None,
+            root,
+            scan_time_ms: 7,
+            file_count: 3,
+            total_size: 1000,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1,023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_listing_is_size_sorted() {
+        let out = render_listing(&result());
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("3 个文件"));
+        assert!(lines[1].contains("90.0%"));
+        assert!(lines[1].ends_with(&format!("big{}", std::path::MAIN_SEPARATOR)));
+        assert!(lines[2].contains("10.0%"));
+        assert!(lines[2].ends_with("small.txt"));
+    }
+
+    #[test]
+    fn test_largest_files() {
+        let result = result();
+        let names: Vec<&str> = largest_files(&result.root, 2)
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(names, ["a.bin", "b.bin"]);
+        assert_eq!(largest_files(&result.root, 10).len(), 3);
+        assert!(render_top(&result, 1).ends_with("/r/a.bin\n"));
+    }
+}
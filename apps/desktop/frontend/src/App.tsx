@@ -2,6 +2,7 @@ import { useState, useEffect, useMemo, useCallback, useRef } from 'react'
 import { useTranslation } from 'react-i18next'
 import { getCurrentWindow } from '@tauri-apps/api/window'
 import { open } from '@tauri-apps/plugin-shell'
+import { ask } from '@tauri-apps/plugin-dialog'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { Settings, Github, Mail, ExternalLink, Minus, Copy, X, ListTodo } from 'lucide-react'
@@ -15,15 +16,18 @@ import type { Snapshot } from './services/snapshot'
 import { readStorageFile, writeStorageFile } from './services/storage'
 import { type Task, createMigrateTask } from './services/taskQueue'
 import type { CloudStorageConfig } from './services/settings'
-import { notifyMigrateSuccess, notifyMigrateFailed } from './services/notification'
+import { notifyMigrateSuccess, notifyMigrateFailed, onOpenScanResults } from './services/notification'
+import { forceClose, onConfirmClose } from './services/busy'
 
 // 上传进度事件类型
 interface UploadProgressEvent {
   task_id: string
   provider: string
+  config_name: string
   progress: number
   uploaded_bytes: number
   total_bytes: number
+  bytes_per_sec: number  // 后端按滑动窗口计算的当前速率
 }
 
 const THEME_STORAGE_FILE = 'theme.txt'
@@ -87,6 +91,10 @@ function App() {
       controller.abort()
       abortControllersRef.current.delete(taskId)
     }
+    // 同时中止后端正在进行的传输
+    invoke('cancel_uploads', { taskId }).catch(error => {
+      console.error('中止上传失败:', error)
+    })
     updateTask(taskId, { status: 'cancelled' })
   }, [updateTask])
 
@@ -110,33 +118,32 @@ function App() {
     setIsPaused(false)
   }, [])
 
+  // 后端刷新 token 失败（refresh token 已失效）时打开设置，提示重新授权
+  useEffect(() => {
+    const unlisten = listen<{ provider: string; account_id: string }>('cloud-auth-expired', (event) => {
+      console.warn('云存储登录已过期，需要重新授权:', event.payload.provider)
+      setShowSettings(true)
+    })
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   // 监听上传进度事件
   useEffect(() => {
     const unlisten = listen<UploadProgressEvent>('upload-progress', (event) => {
-      const { task_id, progress, uploaded_bytes } = event.payload
+      const { task_id, progress, uploaded_bytes, bytes_per_sec } = event.payload
       const now = Date.now()
       
-      // 更新对应任务的进度，并计算上传速度
+      // 更新对应任务的进度与上传速度
       setTasks(prev => prev.map(t => {
         if (t.id !== task_id) return t
-        
-        // 计算上传速度
-        let uploadSpeed = t.uploadSpeed || 0
-        if (t.lastProgressTime && t.lastUploadedBytes !== undefined) {
-          const timeDiff = (now - t.lastProgressTime) / 1000 // 秒
-          const bytesDiff = uploaded_bytes - t.lastUploadedBytes
-          if (timeDiff > 0 && bytesDiff > 0) {
-            // 使用移动平均来平滑速度显示
-            const newSpeed = bytesDiff / timeDiff
-            uploadSpeed = uploadSpeed > 0 ? uploadSpeed * 0.3 + newSpeed * 0.7 : newSpeed
-          }
-        }
-        
         return {
           ...t,
           progress,
           uploadedBytes: uploaded_bytes,
-          uploadSpeed,
+          uploadSpeed: bytes_per_sec,
           lastProgressTime: now,
           lastUploadedBytes: uploaded_bytes,
         }
@@ -166,41 +173,15 @@ function App() {
 
       try {
         const config = pendingTask.targetConfigs[0]
-        if (!config || !config.accessToken) {
+        if (!config || !config.accountId) {
           throw new Error('未找到有效的云存储配置')
         }
 
-        // 动态导入 refreshGoogleToken
-        const { refreshGoogleToken } = await import('./services/settings')
-
-        let accessToken = config.accessToken
-
-        // 检查 token 是否即将过期
-        if (config.tokenExpiry) {
-          const expiryBuffer = 5 * 60 * 1000
-          if (config.tokenExpiry - Date.now() < expiryBuffer) {
-            if (!config.refreshToken) {
-              throw new Error(`${config.name} 登录已过期，请重新登录`)
-            }
-            try {
-              const newTokenData = await refreshGoogleToken(config.refreshToken)
-              accessToken = newTokenData.access_token
-            } catch (e) {
-              // token 刷新失败，可能是网络问题或 token 已失效
-              const errorMsg = String(e)
-              if (errorMsg.includes('401') || errorMsg.includes('403') || errorMsg.includes('invalid_grant')) {
-                throw new Error(`${config.name} 登录已过期，请重新登录`)
-              }
-              throw new Error(`${config.name} token 刷新失败: ${errorMsg}`)
-            }
-          }
-        }
-
         // 准备上传配置
         const uploadConfigs = [{
           provider: config.provider,
           name: config.name,
-          access_token: accessToken,
+          account_id: config.accountId,
           target_path: pendingTask.targetPath,
         }]
 
@@ -292,6 +273,38 @@ function App() {
     }
   }, [themePreference])
 
+  // 点击扫描完成通知后回到结果视图：关闭遮挡结果的对话框与已加载的快照
+  useEffect(() => {
+    const unlisten = onOpenScanResults(
+      () => {
+        setShowSettings(false)
+        setShowSnapshots(false)
+        setShowTaskQueue(false)
+        setLoadedSnapshot(null)
+      },
+      (handler) => getCurrentWindow().onFocusChanged(({ payload }) => handler(payload))
+    )
+    return () => {
+      void unlisten.then(fn => fn())
+    }
+  }, [])
+
+  // 执行计划、上传或扫描进行中时关闭窗口：后端拦下关闭，确认后强制关闭
+  useEffect(() => {
+    const unlisten = onConfirmClose(async (busy) => {
+      const confirmed = await ask(t('closeGuard.message', { ...busy }), {
+        title: t('closeGuard.title'),
+        kind: 'warning',
+        okLabel: t('closeGuard.forceClose'),
+        cancelLabel: t('common.cancel'),
+      })
+      if (confirmed) await forceClose()
+    })
+    return () => {
+      void unlisten.then(fn => fn())
+    }
+  }, [t])
+
   // 加载主题设置
   useEffect(() => {
     readStorageFile(THEME_STORAGE_FILE).then(stored => {
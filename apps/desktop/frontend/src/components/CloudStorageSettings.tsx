@@ -55,6 +55,12 @@ import {
   getDropboxUserInfo,
   getDropboxQuota,
   revokeDropboxToken,
+  cancelOAuthFlow,
+  deleteCloudCredentials,
+  startOneDriveOAuth,
+  getOneDriveUserInfo,
+  getOneDriveQuota,
+  revokeOneDriveToken,
   type CloudStorageSettings as CloudStorageSettingsType,
   type CloudStorageConfig,
   type CloudStorageProvider,
@@ -62,6 +68,7 @@ import {
   type BaiduNetdiskQuota,
   type AliyunDriveQuota,
   type DropboxQuota,
+  type OneDriveQuota,
 } from '../services/settings'
 
 // 统一的用户信息类型
@@ -119,7 +126,7 @@ function ProviderIcon({ provider, size = 20 }: { provider: CloudStorageProvider;
 }
 
 // 支持 OAuth 的提供商
-const OAUTH_PROVIDERS: CloudStorageProvider[] = ['google_drive', 'baidu_netdisk', 'aliyun_drive', 'dropbox']
+const OAUTH_PROVIDERS: CloudStorageProvider[] = ['google_drive', 'onedrive', 'baidu_netdisk', 'aliyun_drive', 'dropbox']
 
 // 添加/编辑配置对话框
 function ConfigDialog({
@@ -145,8 +152,8 @@ function ConfigDialog({
   const [isAuthenticating, setIsAuthenticating] = useState(false)
   const [authError, setAuthError] = useState<string | null>(null)
   const [userInfo, setUserInfo] = useState<UserInfo | null>(null)
-  const [tokens, setTokens] = useState<{ accessToken: string; refreshToken?: string; tokenExpiry: number } | null>(null)
-  const [driveQuota, setDriveQuota] = useState<GoogleDriveQuota | BaiduNetdiskQuota | AliyunDriveQuota | DropboxQuota | null>(null)
+  const [tokens, setTokens] = useState<{ accountId: string; tokenExpiry: number } | null>(null)
+  const [driveQuota, setDriveQuota] = useState<GoogleDriveQuota | BaiduNetdiskQuota | AliyunDriveQuota | DropboxQuota | OneDriveQuota | null>(null)
 
   useEffect(() => {
     if (dialogOpen) {
@@ -159,37 +166,36 @@ function ConfigDialog({
         setTargetFolder(config.targetFolder || '/DiskRookie')
         
         // 如果已经有 token，尝试获取用户信息和存储配额
-        if (config.accessToken) {
+        if (config.accountId) {
           setTokens({
-            accessToken: config.accessToken,
-            refreshToken: config.refreshToken,
+            accountId: config.accountId,
             tokenExpiry: config.tokenExpiry || 0,
           })
           // 根据提供商获取用户信息和存储配额
           if (config.provider === 'baidu_netdisk') {
-            getBaiduUserInfo(config.accessToken)
+            getBaiduUserInfo(config.accountId)
               .then(info => setUserInfo({
                 id: info.openid || '',
                 email: '',
                 name: info.username || t('cloudStorage.defaultUser.baidu'),
               }))
               .catch(() => setUserInfo(null))
-            getBaiduNetdiskQuota(config.accessToken)
+            getBaiduNetdiskQuota(config.accountId)
               .then(setDriveQuota)
               .catch(() => setDriveQuota(null))
           } else if (config.provider === 'aliyun_drive') {
-            getAliyunUserInfo(config.accessToken)
+            getAliyunUserInfo(config.accountId)
               .then(info => setUserInfo({
                 id: info.user_id || '',
                 email: info.email || '',
                 name: info.nick_name || info.user_name || t('cloudStorage.defaultUser.aliyun'),
               }))
               .catch(() => setUserInfo(null))
-            getAliyunDriveQuota(config.accessToken)
+            getAliyunDriveQuota(config.accountId)
               .then(setDriveQuota)
               .catch(() => setDriveQuota(null))
           } else if (config.provider === 'dropbox') {
-            getDropboxUserInfo(config.accessToken)
+            getDropboxUserInfo(config.accountId)
               .then(info => setUserInfo({
                 id: info.account_id || '',
                 email: info.email || '',
@@ -197,14 +203,25 @@ function ConfigDialog({
                 picture: info.profile_photo_url,
               }))
               .catch(() => setUserInfo(null))
-            getDropboxQuota(config.accessToken)
+            getDropboxQuota(config.accountId)
+              .then(setDriveQuota)
+              .catch(() => setDriveQuota(null))
+          } else if (config.provider === 'onedrive') {
+            getOneDriveUserInfo(config.accountId)
+              .then(info => setUserInfo({
+                id: info.id || '',
+                email: info.mail || info.userPrincipalName || '',
+                name: info.displayName || t('cloudStorage.defaultUser.onedrive'),
+              }))
+              .catch(() => setUserInfo(null))
+            getOneDriveQuota(config.accountId)
               .then(setDriveQuota)
               .catch(() => setDriveQuota(null))
           } else if (config.provider === 'google_drive') {
-            getGoogleUserInfo(config.accessToken)
+            getGoogleUserInfo(config.accountId)
               .then(setUserInfo)
               .catch(() => setUserInfo(null))
-            getGoogleDriveQuota(config.accessToken)
+            getGoogleDriveQuota(config.accountId)
               .then(setDriveQuota)
               .catch(() => setDriveQuota(null))
           }
@@ -235,18 +252,13 @@ function ConfigDialog({
     setAuthError(null)
     
     try {
-      const oauthTokens = await startGoogleOAuth()
-      
-      const now = Date.now()
-      setTokens({
-        accessToken: oauthTokens.access_token,
-        refreshToken: oauthTokens.refresh_token,
-        tokenExpiry: now + oauthTokens.expires_in * 1000,
-      })
+      // token 由后端保存到凭据存储，前端只保留账号 ID
+      const { account_id: accountId, status } = await startGoogleOAuth(config?.accountId)
+      setTokens({ accountId, tokenExpiry: status.expires_at })
       
       // 获取用户信息（失败不影响授权成功）
       try {
-        const info = await getGoogleUserInfo(oauthTokens.access_token)
+        const info = await getGoogleUserInfo(accountId)
         setUserInfo(info)
         
         // 自动设置名称
@@ -265,7 +277,7 @@ function ConfigDialog({
       
       // 获取存储配额
       try {
-        const quota = await getGoogleDriveQuota(oauthTokens.access_token)
+        const quota = await getGoogleDriveQuota(accountId)
         setDriveQuota(quota)
       } catch (quotaErr) {
         console.warn('获取存储配额失败:', quotaErr)
@@ -284,18 +296,13 @@ function ConfigDialog({
     setAuthError(null)
     
     try {
-      const oauthTokens = await startBaiduOAuth()
-      
-      const now = Date.now()
-      setTokens({
-        accessToken: oauthTokens.access_token,
-        refreshToken: oauthTokens.refresh_token,
-        tokenExpiry: now + oauthTokens.expires_in * 1000,
-      })
+      // token 由后端保存到凭据存储，前端只保留账号 ID
+      const { account_id: accountId, status } = await startBaiduOAuth(config?.accountId)
+      setTokens({ accountId, tokenExpiry: status.expires_at })
       
       // 获取用户信息（失败不影响授权成功）
       try {
-        const info = await getBaiduUserInfo(oauthTokens.access_token)
+        const info = await getBaiduUserInfo(accountId)
         setUserInfo({
           id: info.openid || '',
           email: '',
@@ -318,7 +325,7 @@ function ConfigDialog({
       
       // 获取存储配额
       try {
-        const quota = await getBaiduNetdiskQuota(oauthTokens.access_token)
+        const quota = await getBaiduNetdiskQuota(accountId)
         setDriveQuota(quota)
       } catch (quotaErr) {
         console.warn('获取存储配额失败:', quotaErr)
@@ -337,18 +344,13 @@ function ConfigDialog({
     setAuthError(null)
     
     try {
-      const oauthTokens = await startAliyunOAuth()
-      
-      const now = Date.now()
-      setTokens({
-        accessToken: oauthTokens.access_token,
-        refreshToken: oauthTokens.refresh_token,
-        tokenExpiry: now + oauthTokens.expires_in * 1000,
-      })
+      // token 由后端保存到凭据存储，前端只保留账号 ID
+      const { account_id: accountId, status } = await startAliyunOAuth(config?.accountId)
+      setTokens({ accountId, tokenExpiry: status.expires_at })
       
       // 获取用户信息（失败不影响授权成功）
       try {
-        const info = await getAliyunUserInfo(oauthTokens.access_token)
+        const info = await getAliyunUserInfo(accountId)
         setUserInfo({
           id: info.user_id || '',
           email: info.email || '',
@@ -370,7 +372,7 @@ function ConfigDialog({
       
       // 获取存储配额
       try {
-        const quota = await getAliyunDriveQuota(oauthTokens.access_token)
+        const quota = await getAliyunDriveQuota(accountId)
         setDriveQuota(quota)
       } catch (quotaErr) {
         console.warn('获取存储配额失败:', quotaErr)
@@ -389,18 +391,13 @@ function ConfigDialog({
     setAuthError(null)
     
     try {
-      const oauthTokens = await startDropboxOAuth()
-      
-      const now = Date.now()
-      setTokens({
-        accessToken: oauthTokens.access_token,
-        refreshToken: oauthTokens.refresh_token,
-        tokenExpiry: now + oauthTokens.expires_in * 1000,
-      })
+      // token 由后端保存到凭据存储，前端只保留账号 ID
+      const { account_id: accountId, status } = await startDropboxOAuth(config?.accountId)
+      setTokens({ accountId, tokenExpiry: status.expires_at })
       
       // 获取用户信息（失败不影响授权成功）
       try {
-        const info = await getDropboxUserInfo(oauthTokens.access_token)
+        const info = await getDropboxUserInfo(accountId)
         setUserInfo({
           id: info.account_id || '',
           email: info.email || '',
@@ -423,7 +420,54 @@ function ConfigDialog({
       
       // 获取存储配额
       try {
-        const quota = await getDropboxQuota(oauthTokens.access_token)
+        const quota = await getDropboxQuota(accountId)
+        setDriveQuota(quota)
+      } catch (quotaErr) {
+        console.warn('获取存储配额失败:', quotaErr)
+        setDriveQuota(null)
+      }
+    } catch (err) {
+      setAuthError(err instanceof Error ? err.message : t('cloudStorage.authFailed'))
+    } finally {
+      setIsAuthenticating(false)
+    }
+  }
+
+  // 处理 OneDrive OAuth 登录
+  const handleOneDriveLogin = async () => {
+    setIsAuthenticating(true)
+    setAuthError(null)
+    
+    try {
+      // token 由后端保存到凭据存储，前端只保留账号 ID
+      const { account_id: accountId, status } = await startOneDriveOAuth(config?.accountId)
+      setTokens({ accountId, tokenExpiry: status.expires_at })
+      
+      // 获取用户信息（失败不影响授权成功）
+      try {
+        const info = await getOneDriveUserInfo(accountId)
+        setUserInfo({
+          id: info.id || '',
+          email: info.mail || info.userPrincipalName || '',
+          name: info.displayName || t('cloudStorage.defaultUser.onedrive'),
+        })
+        
+        // 自动设置名称
+        if (!name && info.displayName) {
+          setName(info.displayName)
+        }
+      } catch (userInfoErr) {
+        console.warn('获取用户信息失败，但授权已成功:', userInfoErr)
+        setUserInfo({
+          id: '',
+          email: '',
+          name: t('cloudStorage.defaultUser.onedrive'),
+        })
+      }
+      
+      // 获取存储配额
+      try {
+        const quota = await getOneDriveQuota(accountId)
         setDriveQuota(quota)
       } catch (quotaErr) {
         console.warn('获取存储配额失败:', quotaErr)
@@ -444,6 +488,8 @@ function ConfigDialog({
       await handleAliyunLogin()
     } else if (provider === 'dropbox') {
       await handleDropboxLogin()
+    } else if (provider === 'onedrive') {
+      await handleOneDriveLogin()
     } else if (provider === 'google_drive') {
       await handleGoogleLogin()
     }
@@ -451,20 +497,24 @@ function ConfigDialog({
 
   // 处理断开连接
   const handleDisconnect = async () => {
-    if (tokens?.accessToken) {
+    if (tokens?.accountId) {
+      // 撤销在后端用已保存的凭据完成
       try {
         if (provider === 'baidu_netdisk') {
-          await revokeBaiduToken(tokens.accessToken)
+          await revokeBaiduToken(tokens.accountId)
         } else if (provider === 'aliyun_drive') {
-          await revokeAliyunToken(tokens.accessToken)
+          await revokeAliyunToken(tokens.accountId)
         } else if (provider === 'dropbox') {
-          await revokeDropboxToken(tokens.accessToken)
+          await revokeDropboxToken(tokens.accountId)
+        } else if (provider === 'onedrive') {
+          await revokeOneDriveToken(tokens.accountId)
         } else if (provider === 'google_drive') {
-          await revokeGoogleToken(tokens.accessToken)
+          await revokeGoogleToken(tokens.accountId)
         }
       } catch {
         // 忽略撤销错误
       }
+      await deleteCloudCredentials(provider, tokens.accountId).catch(() => {})
     }
     setTokens(null)
     setUserInfo(null)
@@ -495,8 +545,7 @@ function ConfigDialog({
         ? { webdavUrl, webdavUsername, webdavPassword }
         : isOAuthProvider && tokens
         ? {
-            accessToken: tokens.accessToken,
-            refreshToken: tokens.refreshToken,
+            accountId: tokens.accountId,
             tokenExpiry: tokens.tokenExpiry,
           }
         : {}),
@@ -766,6 +815,53 @@ function ConfigDialog({
                             </>
                           )
                         }
+                        // OneDrive 格式
+                        else if ('quota' in driveQuota && driveQuota.quota && 'remaining' in driveQuota.quota) {
+                          const quota = (driveQuota as OneDriveQuota).quota
+                          const used = quota.used || 0
+                          const total = quota.total || 0
+                          const free = quota.remaining || (total - used)
+                          const percentage = total > 0 ? (used / total) * 100 : 0
+                          
+                          return (
+                            <>
+                              <Box sx={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', mb: 1 }}>
+                                <Typography variant="caption" sx={{ fontWeight: 600, color: 'text.secondary' }}>
+                                  {t('cloudStorage.storageSpace')}
+                                </Typography>
+                                <Typography variant="caption" sx={{ color: 'text.secondary' }}>
+                                  {formatStorageSize(used)} / {formatStorageSize(total)}
+                                </Typography>
+                              </Box>
+                              <Box
+                                sx={{
+                                  width: '100%',
+                                  height: 6,
+                                  borderRadius: 3,
+                                  bgcolor: 'divider',
+                                  overflow: 'hidden',
+                                }}
+                              >
+                                <Box
+                                  sx={{
+                                    width: `${Math.min(100, percentage)}%`,
+                                    height: '100%',
+                                    borderRadius: 3,
+                                    bgcolor: percentage > 90 
+                                      ? 'error.main' 
+                                      : percentage > 70 
+                                      ? 'warning.main' 
+                                      : 'primary.main',
+                                    transition: 'width 0.3s ease',
+                                  }}
+                                />
+                              </Box>
+                              <Typography variant="caption" sx={{ color: 'text.secondary', mt: 0.5, display: 'block', fontSize: '10px' }}>
+                                {t('cloudStorage.available')}: {formatStorageSize(free)}
+                              </Typography>
+                            </>
+                          )
+                        }
                         // Dropbox 格式
                         else if ('used' in driveQuota && 'allocation' in driveQuota) {
                           const quota = driveQuota as DropboxQuota
@@ -844,6 +940,7 @@ function ConfigDialog({
                         : provider === 'baidu_netdisk' ? '#409EFF'
                         : provider === 'aliyun_drive' ? '#0052FF'
                         : provider === 'dropbox' ? '#0061FF'
+                        : provider === 'onedrive' ? '#0078D4'
                         : 'primary.main',
                       color: 'white',
                       fontSize: '14px',
@@ -853,12 +950,23 @@ function ConfigDialog({
                           : provider === 'baidu_netdisk' ? '#2CA6E0'
                           : provider === 'aliyun_drive' ? '#0033CC'
                           : provider === 'dropbox' ? '#0047CC'
+                          : provider === 'onedrive' ? '#005A9E'
                           : 'primary.dark',
                       },
                     }}
                   >
                     {isAuthenticating ? t('cloudStorage.authorizing') : t('cloudStorage.loginWith', { name: providerInfo?.name || '' })}
                   </Button>
+
+                  {isAuthenticating && (
+                    <Button
+                      variant="text"
+                      onClick={() => { cancelOAuthFlow().catch(() => {}) }}
+                      sx={{ textTransform: 'none', borderRadius: '10px' }}
+                    >
+                      {t('cloudStorage.cancelAuth')}
+                    </Button>
+                  )}
                   
                   {authError && (
                     <Box
@@ -1109,6 +1217,11 @@ export function CloudStorageSettings({ onConfigured }: Props) {
   }
 
   const handleDeleteConfig = async (config: CloudStorageConfig) => {
+    if (config.accountId) {
+      await deleteCloudCredentials(config.provider, config.accountId).catch((err) => {
+        console.warn('删除云存储凭据失败:', err)
+      })
+    }
     const newConfigs = settings.configs.filter(
       c => !(c.provider === config.provider && c.name === config.name)
     )
@@ -4,6 +4,7 @@ import { invoke } from '@tauri-apps/api/core'
 import { getCurrentWindow } from '@tauri-apps/api/window'
 import { open } from '@tauri-apps/plugin-dialog'
 import { showNotification } from '../services/notification'
+import { errorMessage, localizeBackendMessage } from '../services/errors'
 import { Folder, Cpu, MessageSquare, Copy, CheckCircle2, AlertCircle, Settings, Clock, FileStack, HardDrive, Sparkles, Save, Cloud, Play, Shield } from 'lucide-react'
 import { Button, TextField, Typography, Fade, Tooltip, Dialog, DialogTitle, DialogContent, DialogActions, Slider, Box, FormHelperText, Checkbox, FormControlLabel } from '@mui/material'
 import { PieChart, Pie, Cell, ResponsiveContainer } from 'recharts'
@@ -14,16 +15,37 @@ import { analyzeWithAI, deleteItem, type AnalysisResult } from '../services/ai-a
 import { SuggestionCard } from './SuggestionCard'
 import { saveSnapshot, type Snapshot } from '../services/snapshot'
 import { readStorageFile, writeStorageFile } from '../services/storage'
-import { loadAppSettings, saveAppSettings, getEnabledCloudStorageConfigs, CLOUD_STORAGE_PROVIDERS, type CloudStorageConfig } from '../services/settings'
+import { loadAppSettings, saveAppSettings, preflightScan, getEnabledCloudStorageConfigs, CLOUD_STORAGE_PROVIDERS, type CloudStorageConfig } from '../services/settings'
 import { loadSafeListPaths, isPathInSafeList, addToSafeList } from '../services/safeList'
 import { CloudStorageSelector } from './CloudStorageSelector'
 import type { Task } from '../services/taskQueue'
+import { onExternalScanRequest, frontendReady } from '../services/externalScan'
+import type { ScanProgressPayload } from '../services/scanControl'
 
 /** 按大小排序的前 N 大文件条目（MFT 扫描时由后端填充） */
 interface TopFileEntry {
     path: string
     size: number
     modified?: number | null
+    created?: number | null
+    accessed?: number | null
+    /** 文件所有者，仅在开启所有者归属时填充 */
+    owner?: string | null
+}
+
+/** 某个所有者名下的占用字节数 */
+interface OwnerUsage {
+    owner: string
+    bytes: number
+}
+
+/** 某个顶层目录下最近修改的文件汇总（磁盘活动热度） */
+interface RecentActivityEntry {
+    path: string
+    bytes_24h: number
+    /** 包含 bytes_24h */
+    bytes_7d: number
+    file_count_24h: number
 }
 
 interface ScanResult {
@@ -39,6 +61,20 @@ interface ScanResult {
     volume_free_bytes?: number | null
     /** 按大小排序的前 N 大文件，供摘要与 AI 分析用，避免遍历整棵树 */
     top_files?: TopFileEntry[] | null
+    /** 按所有者汇总的占用（字节降序），仅在开启所有者归属时填充 */
+    owner_usage?: OwnerUsage[] | null
+    /** 后台扫描时目录读取是否被限速 */
+    throttled?: boolean
+    /** 按 .gitignore 跳过的大小（字节），仅开启忽略规则时存在，不计入 total_size */
+    ignored_bytes?: number | null
+    /** 扫描范围内用户废纸篓的大小（字节），已计入 total_size */
+    trash_bytes?: number | null
+    /** 扫描期间被暂停的总时长（毫秒），不计入 scan_time_ms */
+    paused_ms?: number
+    /** 有 NTFS 压缩、重复数据删除或稀疏文件时的逻辑大小与实际占用 */
+    physical_usage?: { logical_bytes: number; physical_bytes: number } | null
+    /** 各顶层目录最近 24 小时、7 天内修改的文件大小，按 24 小时字节数降序，最多 50 项；没有时省略 */
+    recent_activity?: RecentActivityEntry[]
 }
 
 const PROMPT_INSTRUCTION_FILE = 'prompt-instruction.txt'
@@ -297,6 +333,13 @@ function formatModified(ts: number | undefined | null): string {
     }
 }
 
+/** 最近使用时间：访问时间与修改时间中较晚的一个（与后端 FileNode::last_used 一致） */
+function lastUsed(n: { modified?: number | null; accessed?: number | null }): number | null {
+    if (n.accessed == null) return n.modified ?? null
+    if (n.modified == null) return n.accessed
+    return Math.max(n.accessed, n.modified)
+}
+
 async function buildFileListSummary(result: ScanResult, fileCount?: number): Promise<string> {
     // 如果没有指定 fileCount，则从设置中读取
     if (fileCount === undefined) {
@@ -305,20 +348,20 @@ async function buildFileListSummary(result: ScanResult, fileCount?: number): Pro
     }
 
     const count = fileCount
-    let candidates: { path: string; size: number; modified?: number | null }[]
+    let candidates: { path: string; size: number; lastUsed: number | null }[]
 
     // 优先使用后端提供的 top_files（MFT 扫描时已按大小排序），先取全部候选，再按安全名单过滤并补足行数
     if (result.top_files && result.top_files.length > 0) {
         candidates = result.top_files.map((n) => ({
             path: n.path,
             size: n.size,
-            modified: n.modified ?? null,
+            lastUsed: lastUsed(n),
         }))
     } else {
-        const nodes: { path: string; size: number; modified?: number | null }[] = []
+        const nodes: { path: string; size: number; lastUsed: number | null }[] = []
         function collect(n: TreemapNode, depth: number) {
             if (depth > 20) return
-            if (!n.is_dir) nodes.push({ path: n.path || n.name, size: n.size, modified: n.modified })
+            if (!n.is_dir) nodes.push({ path: n.path || n.name, size: n.size, lastUsed: lastUsed(n) })
             if (n.children?.length) {
                 [...n.children].sort((a, b) => b.size - a.size).slice(0, 10).forEach((c) => collect(c, depth + 1))
             }
@@ -333,8 +376,8 @@ async function buildFileListSummary(result: ScanResult, fileCount?: number): Pro
         .filter((n) => !isPathInSafeList(n.path, safeList))
         .slice(0, count)
 
-    const header = '| 路径 | 大小 | 最近修改时间 |\n| --- | --- | --- |\n'
-    const rows = items.map((n) => `| ${displayPath(n.path)} | ${formatBytes(n.size)} | ${formatModified(n.modified)} |`).join('\n')
+    const header = '| 路径 | 大小 | 最近使用时间 |\n| --- | --- | --- |\n'
+    const rows = items.map((n) => `| ${displayPath(n.path)} | ${formatBytes(n.size)} | ${formatModified(n.lastUsed)} |`).join('\n')
     return `[磁盘分析结果]\n总大小: ${formatBytes(result.total_size)}，文件数: ${result.file_count}\n\n${header}${rows}`
 }
 
@@ -391,17 +434,20 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
     useEffect(() => {
         let unlistenProgress: (() => void) | undefined
         let unlistenMftStatus: (() => void) | undefined
-        getCurrentWindow().listen<[number, string]>('scan-progress', (ev) => {
+        getCurrentWindow().listen<ScanProgressPayload>('scan-progress', (ev) => {
             setProgressFiles(ev.payload[0])
-            if (ev.payload[1]) setProgressMessage(ev.payload[1])
+            const [, text, phase] = ev.payload
+            // 阶段提示按前端当前语言显示，普通进度为当前路径
+            const message = (phase && localizeBackendMessage(`phase.${phase}`)) || text
+            if (message) setProgressMessage(message)
         })
             .then((fn) => { unlistenProgress = fn })
-        getCurrentWindow().listen<[string, boolean]>('scan-mft-status', (ev) => {
-            const [path, usedMft] = ev.payload
+        getCurrentWindow().listen<[string, boolean, string | null]>('scan-mft-status', (ev) => {
+            const [path, usedMft, reason] = ev.payload
             if (usedMft) {
                 console.log('[DiskRookie] 本次扫描已成功使用 MFT 技术，路径:', path)
             } else {
-                console.log('[DiskRookie] 本次扫描未使用 MFT（普通目录遍历），路径:', path)
+                console.log('[DiskRookie] 本次扫描未使用 MFT（普通目录遍历），路径:', path, reason ? `原因: ${reason}` : '')
             }
         }).then((fn) => { unlistenMftStatus = fn })
         return () => {
@@ -417,6 +463,15 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
         try {
             const appSettings = await loadAppSettings()
             const useMft = appSettings.useMftScan !== false
+            try {
+                const preflight = await preflightScan(pathToScan, useMft)
+                console.log('[DiskRookie] 扫描预检:', preflight.will_use_mft ? '将使用 MFT' : `不使用 MFT（${preflight.mft_reason ?? ''}）`,
+                    '推荐方式:', preflight.recommended_mode, '预计条目数:', preflight.estimated_entries ?? '未知')
+                if (useMft && !preflight.will_use_mft && preflight.mft_reason) setProgressMessage(preflight.mft_reason)
+            } catch (preflightError) {
+                // 预检只用于提示，失败时不影响扫描
+                console.warn('[DiskRookie] 扫描预检失败:', preflightError)
+            }
             const res = await invoke<ScanResult>('scan_path_command', { path: pathToScan, shallowDirs, useMft })
             setResult(res); setStatus('done');
 
@@ -444,7 +499,7 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
                 }
             }
         } catch (e) {
-            setStatus('error'); setErrorMsg(String(e));
+            setStatus('error'); setErrorMsg(errorMessage(e));
         }
     }, [shallowDirs, isAdmin])
 
@@ -456,6 +511,22 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
         }
     }, [runScan])
 
+    // 带 --scan 启动或再次启动应用时扫描传入的路径；正在扫描时不打断当前扫描
+    const statusRef = useRef(status)
+    statusRef.current = status
+    useEffect(() => {
+        const unlisten = onExternalScanRequest((request) => {
+            if (statusRef.current === 'scanning') {
+                console.warn('[DiskRookie] 正在扫描，忽略外部扫描请求:', request.path)
+                return
+            }
+            setPath(request.path)
+            void runScan(request.path)
+        })
+        void unlisten.then(() => frontendReady())
+        return () => { void unlisten.then((fn) => fn()) }
+    }, [runScan])
+
     const handleDelete = useCallback(async (itemPath: string) => {
         await deleteItem(itemPath)
         setDeletedPaths(prev => new Set([...prev, itemPath]))
@@ -581,40 +652,17 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
         // 否则直接执行（兼容旧逻辑）
         console.log('迁移文件到云存储:', { itemPath, targetConfigs, cloudPath })
 
-        // 动态导入 refreshGoogleToken
-        const { refreshGoogleToken } = await import('../services/settings')
-
-        // 构建上传配置，并检查/刷新 token
+        // 构建上传配置（token 由后端按账号取用并在临近过期时自动刷新）
         const uploadConfigs = []
         for (const config of targetConfigs) {
-            if (!config.accessToken) {
+            if (!config.accountId) {
                 throw new Error(`${config.name} 未登录，请先在设置中配置云存储`)
             }
 
-            let accessToken = config.accessToken
-
-            // 检查 token 是否即将过期（5分钟内）
-            if (config.tokenExpiry) {
-                const expiryBuffer = 5 * 60 * 1000 // 5 分钟
-                if (config.tokenExpiry - Date.now() < expiryBuffer) {
-                    if (!config.refreshToken) {
-                        throw new Error(`${config.name} 登录已过期，请重新登录`)
-                    }
-                    // 刷新 token
-                    console.log(`刷新 ${config.name} 的 token...`)
-                    try {
-                        const newTokens = await refreshGoogleToken(config.refreshToken)
-                        accessToken = newTokens.access_token
-                    } catch (e) {
-                        throw new Error(`${config.name} token 刷新失败: ${e}`)
-                    }
-                }
-            }
-
             uploadConfigs.push({
                 provider: config.provider,
                 name: config.name,
-                access_token: accessToken,
+                account_id: config.accountId,
                 target_path: cloudPath,
             })
         }
@@ -956,6 +1004,15 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
                             { label: t('expertMode.diskUsage'), val: formatBytes(result.total_size), Icon: HardDrive },
                             ...(result.volume_total_bytes != null && result.volume_total_bytes > 0
                                 ? [{ label: t('expertMode.volumeCapacity'), val: formatBytes(result.volume_total_bytes), Icon: HardDrive }]
+                                : []),
+                            ...(result.ignored_bytes != null
+                                ? [{ label: t('expertMode.ignoredBytes'), val: formatBytes(result.ignored_bytes), Icon: HardDrive }]
+                                : []),
+                            ...(result.trash_bytes != null
+                                ? [{ label: t('expertMode.trashBytes'), val: formatBytes(result.trash_bytes), Icon: HardDrive }]
+                                : []),
+                            ...(result.physical_usage != null
+                                ? [{ label: t('expertMode.physicalBytes'), val: formatBytes(result.physical_usage.physical_bytes), Icon: HardDrive }]
                                 : [])
                         ]
                         const tooltipTitle = stats.map(({ label, val }) => `${label}: ${val}`).join(' · ')
@@ -1522,7 +1579,7 @@ export function ExpertMode({ onOpenSettings, loadedSnapshot, onSnapshotLoaded, s
                                 {hoverNode && viewMode === 'disk' && (
                                     <div className="px-3 py-1.5 bg-secondary text-primary rounded-lg text-[11px] font-semibold flex gap-2 items-center">
                                         <span className="truncate max-w-[200px] text-white/90">{hoverNode.name}</span>
-                                        <span className="bg-primary/20 px-1.5 rounded text-[10px] shrink-0">{formatBytes(hoverNode.size)}</span>
+                                        <span className="bg-primary/20 px-1.5 rounded text-[10px] shrink-0">{hoverNode.size_source === 'estimated' ? '≈' : ''}{formatBytes(hoverNode.size)}</span>
                                     </div>
                                 )}
                             </div>
@@ -1,12 +1,44 @@
 import { useMemo, useState, useEffect, useRef } from 'react'
+import type { KnownFolder } from '../services/knownFolders'
 
 export interface TreemapNode {
+  /** 由规范化路径计算的稳定 id，重新扫描后不变 */
+  id?: number
   name: string
   path: string
   size: number
   is_dir?: boolean
   /** Unix 时间戳（秒），最近修改时间 */
   modified?: number | null
+  /** Unix 时间戳（秒），创建时间；文件系统不记录时省略 */
+  created?: number | null
+  /** Unix 时间戳（秒），最近访问时间；文件系统不记录时省略 */
+  accessed?: number | null
+  /** 隐藏/系统/只读属性，无任何标志时省略 */
+  attributes?: { hidden: boolean; system: boolean; readonly: boolean; sparse: boolean; compressed: boolean }
+  /** 其他卷或网络共享的挂载点（未展开，大小为 0），仅为 true 时出现 */
+  is_mount_point?: boolean
+  /** 扫描时按位置识别出的类别，目前只有废纸篓 `'Trash'` */
+  category?: string
+  /** 大小的来源：无权限目录为 `'unknown'`，由缓存扫描补上或子树含估算值时为 `'estimated'`；实际大小时省略 */
+  size_source?: 'estimated' | 'unknown'
+  /** NTFS 备用数据流的大小合计（不含在 size 中，目录为子树合计）；仅 MFT 扫描统计，没有时省略 */
+  streams_size?: number
+  /** NTFS 压缩、重复数据删除或稀疏后的实际占用（目录为子树合计）；与 size 相同时省略 */
+  physical_size?: number
+  /** 按类型着色：文件为按扩展名识别的类型，目录为子树中字节数最多的类型；仅在开启 treemap_rollups 的扫描中出现 */
+  dominant_category?: 'Video' | 'Audio' | 'Image' | 'Document' | 'Archive' | 'Other'
+  /** 按时间着色：目录子树中文件最近、最早的修改时间（Unix 秒）；仅在开启 treemap_rollups 的扫描中出现 */
+  newest_modified?: number
+  oldest_modified?: number
+  /** 目录子树中最近的修改时间（Unix 秒，含只计大小的目录中的内容）；文件与空目录省略 */
+  latest_child_modified?: number
+  /** children 没有列出目录的全部子项（超过上限、被忽略规则过滤、只计大小的目录等），仅为 true 时出现 */
+  partial_children?: boolean
+  /** 子树中有内容没有计入 size（子项被截断或过滤、超过深度上限），仅为 true 时出现 */
+  partial_size?: boolean
+  /** 系统解析出的已知目录（目录被移动后同样能识别），其他节点省略 */
+  known_folder?: KnownFolder
   children?: TreemapNode[]
 }
 
@@ -180,13 +212,14 @@ export function Treemap({ root, width, height, onHover }: TreemapProps) {
           const color = colorsByDepth[block.depth % colorsByDepth.length]
           const showLabel = block.w > 40 && block.h > 22
           return (
-            <g key={`${block.node.path}-${i}`}>
+            <g key={block.node.id ?? `${block.node.path}-${i}`}>
               <rect
                 x={block.x}
                 y={block.y}
                 width={block.w}
                 height={block.h}
                 fill={color}
+                fillOpacity={block.node.attributes?.hidden ? 0.5 : 1}
                 stroke="rgba(0,0,0,0.12)"
                 strokeWidth={1}
                 onMouseEnter={() => onHover?.(block.node)}
@@ -0,0 +1,26 @@
+// 关闭保护 - 对应后端 get_busy_state / force_close 命令与 confirm-close 事件
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+/** 进行中的计划执行、上传与扫描数量 */
+export interface BusySnapshot {
+  executing: number
+  uploading: number
+  scanning: number
+}
+
+export async function getBusyState(): Promise<BusySnapshot> {
+  return invoke<BusySnapshot>('get_busy_state')
+}
+
+/** 中止上传、把转存日志落盘后关闭窗口；上传可在下次启动后续传 */
+export async function forceClose(): Promise<void> {
+  await invoke('force_close')
+}
+
+/** 任务进行中时关闭窗口会被拦下并发出 confirm-close，由前端确认后调用 forceClose */
+export async function onConfirmClose(
+  onConfirm: (busy: BusySnapshot) => void
+): Promise<UnlistenFn> {
+  return listen<BusySnapshot>('confirm-close', (event) => onConfirm(event.payload))
+}
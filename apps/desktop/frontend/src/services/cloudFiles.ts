@@ -0,0 +1,85 @@
+// 云端文件浏览服务 - 对应后端 list_cloud_files / delete_cloud_file / verify_remote_file / find_backed_up_files
+import { invoke } from '@tauri-apps/api/core'
+import type { CloudStorageProvider } from './settings'
+import type { PlannedAction } from './savedPlans'
+
+export interface CloudFileEntry {
+  id: string
+  name: string
+  size: number  // 文件夹为 0
+  modified: number | null  // Unix 时间戳（秒）
+  is_dir: boolean
+  provider: CloudStorageProvider
+}
+
+export interface CloudFilePage {
+  entries: CloudFileEntry[]
+  next_page_token: string | null  // 还有下一页时传给下一次 listCloudFiles
+}
+
+export type RemoteChecksum =
+  | { algorithm: 'md5'; value: string }
+  | { algorithm: 'sha1'; value: string }
+  | { algorithm: 'dropbox_content_hash'; value: string }
+
+export interface IntegrityReport {
+  provider: CloudStorageProvider
+  remote_id: string
+  local_size: number
+  remote_size: number | null
+  remote_checksum: RemoteChecksum | null
+  local_checksum: RemoteChecksum | null
+  verified: boolean  // 大小和校验和都与本地一致
+  message: string
+}
+
+export async function listCloudFiles(
+  provider: CloudStorageProvider,
+  accountId: string,
+  path: string,
+  pageToken?: string,
+): Promise<CloudFilePage> {
+  return await invoke<CloudFilePage>('list_cloud_files', { provider, accountId, path, pageToken })
+}
+
+export async function deleteCloudFile(
+  provider: CloudStorageProvider,
+  accountId: string,
+  fileId: string,
+): Promise<void> {
+  await invoke('delete_cloud_file', { provider, accountId, fileId })
+}
+
+export async function verifyRemoteFile(
+  provider: CloudStorageProvider,
+  accountId: string,
+  remoteId: string,
+  localPath: string,
+): Promise<IntegrityReport> {
+  return await invoke<IntegrityReport>('verify_remote_file', { provider, accountId, remoteId, localPath })
+}
+
+export interface BackedUpMatch {
+  local_path: string
+  size: number
+  remote: CloudFileEntry
+  verified: boolean  // 已比对云端校验和
+}
+
+export interface BackedUpFiles {
+  indexed: number  // 索引中的云端文件数
+  truncated: boolean  // 云端文件过多，索引不完整
+  matches: BackedUpMatch[]
+  actions: PlannedAction[]  // 低风险删除建议，与 matches 一一对应
+}
+
+/** 对照云端 path 下的文件，找出扫描中已备份的本地文件；verify 时逐个比对校验和 */
+export async function findBackedUpFiles(
+  provider: CloudStorageProvider,
+  accountId: string,
+  path: string,
+  scanId: string,
+  verify = false,
+): Promise<BackedUpFiles> {
+  return await invoke<BackedUpFiles>('find_backed_up_files', { provider, accountId, path, scanId, verify })
+}
@@ -0,0 +1,19 @@
+// 云存储容量汇总服务 - 对应后端 get_all_cloud_quotas
+import { invoke } from '@tauri-apps/api/core'
+import type { CloudStorageProvider } from './settings'
+
+export interface CloudQuota {
+  provider: CloudStorageProvider
+  account_id: string
+  display_name: string
+  total_bytes: number | null  // 不限容量时为 null
+  used_bytes: number
+  fetched_at: number  // Unix 时间戳（秒）
+  stale: boolean  // 本次获取失败，返回的是上次成功的缓存值
+  error?: string
+}
+
+/** 获取所有已连接账号的容量；获取失败且没有缓存的账号不会出现在结果中 */
+export async function getAllCloudQuotas(): Promise<CloudQuota[]> {
+  return invoke<CloudQuota[]>('get_all_cloud_quotas')
+}
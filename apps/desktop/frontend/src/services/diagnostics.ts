@@ -0,0 +1,32 @@
+// 运行指标诊断 - 对应后端 get_diagnostics / reset_diagnostics
+// 指标只在本机内存中，应用重启或重置后清零
+import { invoke } from '@tauri-apps/api/core'
+
+export interface HistogramBucket {
+  le: number | null  // 桶的上界（含），溢出桶为 null
+  count: number
+}
+
+export interface HistogramSnapshot {
+  count: number
+  sum: number
+  min: number
+  max: number
+  buckets: HistogramBucket[]  // 只含非空桶，按上界升序
+}
+
+export interface MetricsSnapshot {
+  enabled: boolean
+  since_ms: number  // 开始统计的时间：应用启动或上次重置
+  counters: Record<string, Record<string, number>>  // 指标名 → 标签 → 计数
+  histograms: Record<string, Record<string, HistogramSnapshot>>  // 如 scan.duration_ms → mft / walk
+}
+
+export async function getDiagnostics(): Promise<MetricsSnapshot> {
+  return invoke<MetricsSnapshot>('get_diagnostics')
+}
+
+/** 返回重置后的空快照 */
+export async function resetDiagnostics(): Promise<MetricsSnapshot> {
+  return invoke<MetricsSnapshot>('reset_diagnostics')
+}
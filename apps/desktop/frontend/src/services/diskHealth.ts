@@ -0,0 +1,32 @@
+// 磁盘健康服务 - 对应后端 get_disk_health
+import { invoke } from '@tauri-apps/api/core'
+
+export type MediaType = 'ssd' | 'hdd' | 'unknown'
+
+export interface SmartAttribute {
+  id: number
+  name: string
+  current: number
+  worst: number
+  threshold: number  // 0 表示没有阈值
+  raw: number
+}
+
+export interface DiskHealth {
+  volume: string
+  device: string | null
+  model: string | null
+  serial_redacted: string | null  // 只保留末 4 位
+  media_type: MediaType
+  temperature_celsius: number | null
+  power_on_hours: number | null
+  reallocated_sectors: number | null
+  predicted_failure: boolean
+  raw_attributes: SmartAttribute[]
+  warnings: string[]  // 部分信息取不到时的原因
+}
+
+/** 查询卷（Windows 盘符或挂载路径）所在磁盘的健康摘要，取不到的字段为 null */
+export async function getDiskHealth(volume: string): Promise<DiskHealth> {
+  return invoke<DiskHealth>('get_disk_health', { volume })
+}
@@ -0,0 +1,56 @@
+// 后端命令错误 - 与 Rust 端 ai_disk_common::CommandError 对应
+import i18n from '../i18n'
+
+export type ErrorCode =
+  | 'PermissionDenied'
+  | 'PathNotFound'
+  | 'NotInScan'
+  | 'InvalidInput'
+  | 'NeedsElevation'
+  | 'OAuthCancelled'
+  | 'OAuthTimeout'
+  | 'OAuthFailed'
+  | 'Unauthorized'
+  | 'ReauthRequired'
+  | 'NetworkTimeout'
+  | 'Network'
+  | 'LlmUnavailable'
+  | 'Busy'
+  | 'PlanConflict'
+  | 'Cancelled'
+  | 'ChecksumMismatch'
+  | 'VersionConflict'
+  | 'ConfirmationRequired'
+  | 'Io'
+  | 'Config'
+  | 'Internal'
+
+export interface CommandError {
+  code: ErrorCode
+  message: string                   // 后端默认语言（中文）渲染的文本
+  message_id?: string               // 稳定的消息 ID，如 'error.scan_not_found'；有值时可按 ID 本地化
+  params?: Record<string, string>   // 消息模板参数
+  details?: unknown
+}
+
+export function isCommandError(e: unknown): e is CommandError {
+  return typeof e === 'object' && e !== null && 'code' in e && 'message' in e
+}
+
+/**
+ * 获取可展示的错误信息（兼容后端 CommandError 与普通 Error/字符串）
+ */
+export function errorMessage(e: unknown): string {
+  if (isCommandError(e)) return localizeBackendMessage(e.message_id, e.params) ?? e.message
+  if (e instanceof Error) return e.message
+  return String(e)
+}
+
+/**
+ * 按消息 ID 本地化后端文案（译文位于 locales 的 backend 节点下）；没有 ID 或缺少译文时返回 undefined
+ */
+export function localizeBackendMessage(messageId?: string | null, params?: Record<string, string>): string | undefined {
+  if (!messageId) return undefined
+  const key = `backend.${messageId}`
+  return i18n.exists(key) ? i18n.t(key, params) : undefined
+}
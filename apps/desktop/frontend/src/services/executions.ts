@@ -0,0 +1,70 @@
+// 部分执行、中断执行的续做与回滚 - 对应后端 execute_plan_selection / list_interrupted_executions /
+// resume_execution / rollback_execution
+import { invoke } from '@tauri-apps/api/core'
+import type { PlannedAction } from './savedPlans'
+import type { ScheduleTarget } from './scheduledExecutions'
+import type { ExecutionReport } from './sessions'
+
+/** 勾选的动作：计划中的下标，或动作的源路径 */
+export type ActionRef = number | string
+
+/**
+ * 只执行计划中勾选的动作。dryRun 时返回实际执行部分的模拟结果（JSON），
+ * 否则返回执行结果 ExecutionReport（JSON），其中 changed 列出因目标已改变而跳过的动作。
+ * 勾选的父目录与其下路径只删除父目录；无法同时执行的动作抛出 PlanConflict；
+ * 预计释放量超过二次确认阈值且 confirmed 为 false 时抛出 ConfirmationRequired，
+ * details 为 { bytes, threshold }，用户确认后以 confirmed 为 true 重试。
+ * 目标在扫描后已改变的动作跳过，ignoreChanges 为 true 时照常执行
+ */
+export async function executePlanSelection(
+  source: ScheduleTarget,
+  selected: ActionRef[],
+  dryRun: boolean,
+  confirmed = false,
+  ignoreChanges = false
+): Promise<string> {
+  return invoke<string>('execute_plan_selection', { source, selected, dryRun, confirmed, ignoreChanges })
+}
+
+/** 动作对照文件系统核对后的进度；Partial 为跨卷移动复制到一半 */
+export type ActionProgress = 'Done' | 'Partial' | 'NotStarted' | 'Skipped'
+
+export interface InterruptedExecution {
+  execution_id: string
+  started_at: number  // Unix 秒
+  actions: PlannedAction[]
+  progress: ActionProgress[]
+  resume_from: number | null  // 续做时的第一个动作下标；全部已完成时为 null
+}
+
+/** 崩溃或出错中断、尚未完成的执行，按开始时间排序 */
+export async function listInterruptedExecutions(): Promise<InterruptedExecution[]> {
+  return invoke<InterruptedExecution[]>('list_interrupted_executions')
+}
+
+/**
+ * 从第一个未完成的动作继续执行，返回执行结果。
+ * 复制到一半的移动：completePartialMoves 为 true 时补完，否则回滚并跳过；
+ * 目标在扫描后已改变的动作跳过，ignoreChanges 为 true 时照常执行；传入 sessionId 时结果追加到该会话
+ */
+export async function resumeExecution(
+  executionId: string,
+  completePartialMoves: boolean,
+  sessionId?: string,
+  ignoreChanges = false
+): Promise<ExecutionReport> {
+  return invoke<ExecutionReport>('resume_execution', { executionId, completePartialMoves, sessionId, ignoreChanges })
+}
+
+export interface RollbackOutcome {
+  restored: string[]  // 由执行前快照恢复的文件
+  conflicts: string[]  // 原位置已有文件而未恢复的路径
+}
+
+/**
+ * 用执行前快照恢复该次执行删除的小文件（文档、代码、配置等，默认不超过 1 MB），
+ * 永久删除的同样可以恢复；原位置已有文件时不覆盖
+ */
+export async function rollbackExecution(executionId: string): Promise<RollbackOutcome> {
+  return invoke<RollbackOutcome>('rollback_execution', { executionId })
+}
@@ -0,0 +1,39 @@
+// 启动参数扫描请求 - 对应后端 startup-scan-request / external-scan-request 事件与 frontend_ready 等命令
+//
+// `diskrookie --scan D:\` 或资源管理器右键「使用 DiskRookie 分析」启动时，后端在前端调用
+// frontendReady() 后发出 startup-scan-request；应用已在运行时再次启动，后启动的进程把命令行参数
+// 转发给已运行的实例后退出，由已运行的实例发出 external-scan-request
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface ExternalScanRequest {
+  path: string  // 已校验存在的目录
+  args: string[]
+}
+
+/** 同时订阅启动时与运行中再次启动的扫描请求 */
+export async function onExternalScanRequest(
+  onRequest: (request: ExternalScanRequest) => void
+): Promise<UnlistenFn> {
+  const unlistenStartup = await listen<ExternalScanRequest>('startup-scan-request', (event) => onRequest(event.payload))
+  const unlistenExternal = await listen<ExternalScanRequest>('external-scan-request', (event) => onRequest(event.payload))
+  return () => {
+    unlistenStartup()
+    unlistenExternal()
+  }
+}
+
+/** 订阅事件后调用，通知后端发出暂存的启动扫描请求 */
+export async function frontendReady(): Promise<void> {
+  await invoke('frontend_ready')
+}
+
+/** 在资源管理器右键菜单中添加「使用 DiskRookie 分析」（仅 Windows） */
+export async function registerContextMenu(): Promise<void> {
+  await invoke('register_context_menu')
+}
+
+/** 移除右键菜单；未注册时同样成功 */
+export async function unregisterContextMenu(): Promise<void> {
+  await invoke('unregister_context_menu')
+}
@@ -0,0 +1,27 @@
+// 文件预览 - 对应后端 preview_file_command 命令
+import { invoke } from '@tauri-apps/api/core'
+
+export type PreviewContent =
+  | { kind: 'text'; text: string; encoding: 'utf8' | 'utf16_le' | 'utf16_be'; truncated: boolean }
+  | { kind: 'image'; width: number; height: number; thumbnail_base64: string }  // PNG 缩略图，长边 ≤ 256
+  | { kind: 'metadata' }
+
+export interface FilePreview {
+  path: string
+  size: number
+  modified: number | null  // Unix 秒
+  mime: string | null
+  content: PreviewContent
+}
+
+/**
+ * 预览待删除的文件：文本返回开头内容，图片返回缩略图，其余只返回元数据。
+ * 默认只能预览最近扫描范围内的文件，allowAnyPath 为 true 时不限制
+ */
+export async function previewFile(
+  path: string,
+  maxBytes?: number,
+  allowAnyPath?: boolean
+): Promise<FilePreview> {
+  return invoke<FilePreview>('preview_file_command', { path, maxBytes, allowAnyPath })
+}
@@ -0,0 +1,33 @@
+// 文件哈希 - 对应后端 hash_files 命令
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type HashAlgorithm = 'blake3' | 'sha256'
+
+export interface FileHash {
+  path: string
+  algorithm: HashAlgorithm
+  digest: string | null  // 十六进制小写；读取失败时为 null
+  size: number
+  cached: boolean  // 命中缓存，未读取文件内容
+  error?: string
+}
+
+export interface HashProgress {
+  done: number
+  total: number
+  file: FileHash
+}
+
+/**
+ * 计算一组文件的摘要，结果与 paths 顺序一致。
+ * 文件大小与修改时间未变时直接使用缓存的摘要
+ */
+export async function hashFiles(paths: string[], algorithm: HashAlgorithm): Promise<FileHash[]> {
+  return invoke<FileHash[]>('hash_files', { paths, algorithm })
+}
+
+/** 监听每个文件的完成进度 */
+export async function onHashProgress(handler: (progress: HashProgress) => void): Promise<UnlistenFn> {
+  return listen<HashProgress>('hash-progress', (event) => handler(event.payload))
+}
@@ -0,0 +1,38 @@
+// 连接测试服务 - 对应后端 check_integrations
+import { invoke } from '@tauri-apps/api/core'
+import type { AISettings } from './ai'
+import type { CommandError } from './errors'
+import type { CloudStorageProvider } from './settings'
+
+export type IntegrationStatus = 'Ok' | 'AuthExpired' | 'NetworkError' | 'NotConfigured'
+
+export interface AccountCheck {
+  provider: CloudStorageProvider
+  account_id: string
+  display_name: string
+  status: IntegrationStatus
+  latency_ms: number
+  expires_in_secs: number | null  // access token 剩余有效秒数，已过期为负
+  error?: CommandError
+}
+
+export interface LlmCheck {
+  provider: string
+  model: string
+  status: IntegrationStatus
+  latency_ms: number | null  // 未配置时为 null
+  error?: CommandError
+}
+
+export interface IntegrationReport {
+  accounts: AccountCheck[]
+  llm: LlmCheck
+}
+
+/** 检查所有已连接的云存储账号与 LLM 服务；未传入 AI 设置时使用应用配置中的 LLM 连接 */
+export async function checkIntegrations(settings?: AISettings): Promise<IntegrationReport> {
+  const llm = settings
+    ? { api_url: settings.apiUrl, api_key: settings.apiKey, model: settings.model }
+    : null
+  return invoke<IntegrationReport>('check_integrations', { llm })
+}
@@ -0,0 +1,40 @@
+// 进程读写采样服务 - 对应后端 start_io_sampling / stop_io_sampling 与 disk-io-sample 事件
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface ProcessIoDelta {
+  pid: number
+  name: string
+  read_bytes: number
+  write_bytes: number
+}
+
+export interface ProcessIoTotal {
+  name: string
+  read_bytes: number
+  write_bytes: number
+}
+
+export interface DiskIoSample {
+  interval_ms: number
+  top: ProcessIoDelta[]  // 本次间隔内写入最多的进程
+  totals: ProcessIoTotal[]  // 自开始采样起按进程名累计
+}
+
+/** 开始采样并订阅结果；返回的函数会取消订阅并停止采样 */
+export async function startIoSampling(
+  onSample: (sample: DiskIoSample) => void,
+  intervalMs?: number
+): Promise<UnlistenFn> {
+  const unlisten = await listen<DiskIoSample>('disk-io-sample', (event) => onSample(event.payload))
+  try {
+    await invoke('start_io_sampling', { intervalMs })
+  } catch (e) {
+    unlisten()
+    throw e
+  }
+  return async () => {
+    unlisten()
+    await invoke('stop_io_sampling')
+  }
+}
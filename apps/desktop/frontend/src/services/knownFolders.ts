@@ -0,0 +1,20 @@
+// 已知目录 - 对应后端 get_known_folders
+// 位置由系统解析（Windows Known Folders、XDG 用户目录），目录被用户移动后同样准确
+import { invoke } from '@tauri-apps/api/core'
+
+export type KnownFolder =
+  | 'desktop'
+  | 'documents'
+  | 'downloads'
+  | 'pictures'
+  | 'music'
+  | 'videos'
+  | 'one_drive'  // 仅 Windows
+
+/** 系统中不存在的目录省略 */
+export type KnownFolderMap = Partial<Record<KnownFolder, string>>
+
+/** 侧边栏快捷方式使用的已知目录位置 */
+export async function getKnownFolders(): Promise<KnownFolderMap> {
+  return invoke<KnownFolderMap>('get_known_folders')
+}
@@ -0,0 +1,24 @@
+// 低空间提醒 - 对应后端 low-disk-space 与 low-disk-space-prepared 事件
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface LowSpaceAlert {
+  volume: string
+  total_bytes: number
+  free_bytes: number
+  free_percent: number
+  scan_id?: string  // 开启自动扫描时，后台扫描完成后的扫描结果 id
+}
+
+/** 订阅卷剩余空间跌破阈值的提醒；同一卷回升到回差之上前只提醒一次 */
+export function onLowDiskSpace(handler: (alert: LowSpaceAlert) => void): Promise<UnlistenFn> {
+  return listen<LowSpaceAlert>('low-disk-space', (event) => handler(event.payload))
+}
+
+/** 订阅自动扫描与启发式分析完成事件，可按 scan_id 直接打开扫描结果与建议 */
+export function onLowSpaceSuggestionsPrepared(
+  handler: (alert: LowSpaceAlert, analysis: unknown) => void
+): Promise<UnlistenFn> {
+  return listen<[LowSpaceAlert, unknown]>('low-disk-space-prepared', (event) =>
+    handler(event.payload[0], event.payload[1])
+  )
+}
@@ -4,6 +4,15 @@ import {
   requestPermission,
   sendNotification,
 } from '@tauri-apps/plugin-notification'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+/** 扫描完成通知的深链载荷 - 对应后端 open-scan-results 事件 */
+export interface ScanNotificationLink {
+  title: string
+  body: string
+  scan_id: string | null
+  path: string
+}
 
 /**
  * 检查并请求通知权限
@@ -106,3 +115,29 @@ export async function notifyBatchComplete(
     )
   }
 }
+
+/**
+ * 订阅扫描完成通知的深链事件。
+ * 桌面端通知没有可靠的点击回调：事件到达时记下待打开的结果，
+ * 窗口重新获得焦点（通常即用户点击了通知）时再交给 onOpen
+ */
+export async function onOpenScanResults(
+  onOpen: (link: ScanNotificationLink) => void,
+  onFocusChanged: (handler: (focused: boolean) => void) => Promise<UnlistenFn>
+): Promise<UnlistenFn> {
+  let pending: ScanNotificationLink | null = null
+  const unlistenEvent = await listen<ScanNotificationLink>('open-scan-results', (event) => {
+    pending = event.payload
+  })
+  const unlistenFocus = await onFocusChanged((focused) => {
+    if (focused && pending) {
+      const link = pending
+      pending = null
+      onOpen(link)
+    }
+  })
+  return () => {
+    unlistenEvent()
+    unlistenFocus()
+  }
+}
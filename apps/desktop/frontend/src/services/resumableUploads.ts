@@ -0,0 +1,43 @@
+// 可续传的上传 - 对应后端 list_resumable_uploads / resume_upload
+// 上传中断（含应用退出）后会话保存在存储根目录，续传时从服务端已确认的偏移量继续；
+// 进度通过 upload-progress 事件报告，与 upload_to_cloud 相同
+import { invoke } from '@tauri-apps/api/core'
+import type { CloudStorageProvider } from './settings'
+import type { RemoteChecksum } from './cloudFiles'
+
+export interface ResumableUpload {
+  upload_id: string  // 传给 resumeUpload
+  provider: CloudStorageProvider
+  config_name: string
+  account_id: string
+  target_path: string
+  file_path: string
+  total_bytes: number
+  committed_bytes: number  // 服务端已确认接收的字节数
+  created_at_ms: number
+  expires_at_ms: number  // 超过后会话失效，列出时清理
+}
+
+export interface UploadResult {
+  success: boolean
+  provider: CloudStorageProvider
+  config_name: string
+  file_id: string | null
+  remote_path?: string
+  message: string
+  source_deleted: boolean
+  error_code?: string
+  elapsed_ms: number
+  bytes_transferred: number
+  checksum?: RemoteChecksum
+}
+
+/** 新的在前；超过服务商有效期的会话不再列出 */
+export async function listResumableUploads(): Promise<ResumableUpload[]> {
+  return invoke<ResumableUpload[]>('list_resumable_uploads')
+}
+
+/** 本地文件的大小或修改时间变化、会话过期时抛出错误，该会话随之删除 */
+export async function resumeUpload(uploadId: string, taskId?: string): Promise<UploadResult> {
+  return invoke<UploadResult>('resume_upload', { uploadId, taskId })
+}
@@ -0,0 +1,115 @@
+// 清理计划的保存、加载与导出 - 对应后端 save_plan / load_plan / export_plan / import_plan
+import { invoke } from '@tauri-apps/api/core'
+
+export type PlanAction =
+  | { Delete: { path: string } }
+  | { Move: { from: string; to: string } }
+  | { Offload: { path: string; provider: string; account_id: string; target_path: string } }
+  | { EmptyTrash: { path: string } }
+  | { CompactVhd: { path: string } }  // 压缩 WSL/Docker 虚拟磁盘，仅 Windows
+
+/** 计划动作的执行方式：高风险动作始终为 Skip */
+export type ExecutionMode = 'Permanent' | 'Trash' | 'Skip'
+
+/** 扫描时动作目标的状态，执行前据此核对目标是否已改变 */
+export interface TargetState {
+  is_dir: boolean
+  size: number  // 目录为其下所有文件大小之和
+  modified?: number  // Unix 秒，只用于文件
+  child_count?: number  // 目录的直接子项数；扫描没有完整列出子项时没有
+}
+
+export interface PlannedAction {
+  action: PlanAction
+  bytes: number  // 预计释放的字节数；从 v1 迁移的计划为 0
+  risk: 'Low' | 'Medium' | 'High'
+  reason: string
+  scanned?: TargetState  // 没有时按覆盖其路径的最近一次扫描补上
+}
+
+/** 后端 get_cleanup_plan 的结果 */
+export interface CleanupPlan {
+  actions: PlanAction[]
+  estimated_space: number
+  targets?: (TargetState | null)[]  // 校验时按所依据的扫描记录的目标状态，与 actions 一一对应
+}
+
+export interface PlanFile {
+  schema_version: number
+  name: string
+  saved_at: number  // Unix 秒
+  estimated_space: number
+  actions: PlannedAction[]
+}
+
+/** 执行后的剩余空间预测；移入回收站的删除在清空回收站后才释放，单独列出 */
+export interface FreeSpaceProjection {
+  current_free_bytes?: number | null  // 扫描时卷的剩余空间
+  immediate_bytes: number  // 执行后立即释放
+  deferred_bytes: number  // 清空回收站后才释放
+  projected_free_bytes?: number | null
+  projected_free_after_trash_bytes?: number | null
+}
+
+export interface PlanSimulation {
+  delete_count: number
+  move_count: number
+  offload_count: number
+  compact_count: number
+  upload_bytes: Record<string, number>
+  missing: string[]  // 保存之后已不存在的路径
+  previews?: { path: string; mode: ExecutionMode }[]  // 按执行策略解析出的每个动作的执行方式
+  projection?: FreeSpaceProjection  // 有覆盖这些动作的扫描时存在
+}
+
+export interface LoadedPlan {
+  plan: PlanFile
+  migrated_from?: number  // 从旧 schema 版本升级而来时存在
+  simulation: PlanSimulation
+}
+
+/** 计划名只允许字母、数字、`-`、`_`、`.`，同名计划会被覆盖 */
+export async function savePlan(name: string, actions: PlannedAction[]): Promise<PlanFile> {
+  return invoke<PlanFile>('save_plan', { name, actions })
+}
+
+/** 加载计划并按当前文件系统重新模拟执行 */
+export async function loadPlan(name: string): Promise<LoadedPlan> {
+  return invoke<LoadedPlan>('load_plan', { name })
+}
+
+/** 写出 JSON 与 Markdown 摘要，返回 Markdown 文件路径；不指定目录时写在计划目录中 */
+export async function exportPlan(name: string, targetDir?: string): Promise<string> {
+  return invoke<string>('export_plan', { name, targetDir })
+}
+
+/** 导入他人分享的计划文件，以文件名保存 */
+export async function importPlan(sourcePath: string): Promise<LoadedPlan> {
+  return invoke<LoadedPlan>('import_plan', { sourcePath })
+}
+
+export interface PlanGroup {
+  path: string  // 组内动作路径的最近公共祖先；未知分组为空
+  bytes: number
+  action_count: number
+  actions?: number[]  // 路径恰为 path 的动作下标
+  children?: PlanGroup[]
+}
+
+export interface PlanSummary {
+  total_bytes: number
+  action_count: number
+  by_risk: { risk: PlannedAction['risk']; bytes: number; action_count: number }[]
+  by_category: { category: string; bytes: number; entry_count: number }[]
+  tree: PlanGroup | null
+  unknown: PlanGroup | null  // 路径不在扫描树中的动作
+  largest: number[]  // 预计释放量最大的动作下标
+}
+
+/** 按风险、类别与目录汇总计划；下标均指向传入的 actions */
+export async function summarizePlan(
+  actions: PlannedAction[],
+  scanId: string
+): Promise<PlanSummary> {
+  return invoke<PlanSummary>('summarize_plan', { actions, scanId })
+}
@@ -0,0 +1,30 @@
+// 扫描暂停与继续 - 对应后端 pause_scan / resume_scan 命令与 scan-started 事件
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+/** 暂停进行中的扫描；已暂停时返回 false。暂停后 scan-progress 会发出一条阶段为 scan.paused 的进度 */
+export async function pauseScan(scanId: string): Promise<boolean> {
+  return invoke<boolean>('pause_scan', { scanId })
+}
+
+/** 继续已暂停的扫描；未暂停时返回 false。继续后 scan-progress 会发出一条阶段为 scan.resumed 的进度 */
+export async function resumeScan(scanId: string): Promise<boolean> {
+  return invoke<boolean>('resume_scan', { scanId })
+}
+
+/** scan-progress 事件载荷：[已处理条目数, 当前路径或已本地化的阶段提示, 阶段标识（普通进度为 null）] */
+export type ScanProgressPayload = [number, string, ScanPhase | null]
+
+export type ScanPhase =
+  | 'scan.resolving_owners'
+  | 'scan.paused'
+  | 'scan.resumed'
+  | 'scan.mft_fallback'
+  | 'mft.opening_volume'
+  | 'mft.volume_busy'
+  | 'mft.building_tree'
+
+/** 扫描开始时后端分配 scan_id 并发出 scan-started，扫描完成前可据此暂停、继续 */
+export function onScanStarted(handler: (scanId: string, path: string) => void): Promise<UnlistenFn> {
+  return listen<[string, string]>('scan-started', (event) => handler(event.payload[0], event.payload[1]))
+}
@@ -0,0 +1,23 @@
+// 扫描树定位服务 - 对应后端 locate_in_scan 与 open_in_file_manager
+import { invoke } from '@tauri-apps/api/core'
+
+export interface NodeLocator {
+  id: number
+  name: string
+  path: string
+}
+
+export interface ScanLocation {
+  chain: NodeLocator[]  // 从根到目标（或最近的已扫描祖先）的节点链
+  exact: boolean        // false 表示目标位于浅扫描子树中，链止于最近的祖先
+}
+
+/** 查询路径在某次扫描树中的祖先链；不在扫描范围内时抛出 NotInScan */
+export async function locateInScan(scanId: string, path: string): Promise<ScanLocation> {
+  return invoke<ScanLocation>('locate_in_scan', { scanId, path })
+}
+
+/** 在文件管理器中显示路径；路径已不存在时打开最近的仍存在的祖先目录 */
+export async function openContainingFolder(path: string, isFile: boolean): Promise<void> {
+  return invoke('open_in_file_manager', { path, isFile })
+}
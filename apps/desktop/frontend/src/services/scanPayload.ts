@@ -0,0 +1,32 @@
+// 扫描结果载荷 - 对应后端 scan_path_command 的 compress 参数
+//
+// 传 compress: true 时后端返回 { compressed: true, data }：data 为 zstd 压缩后 base64 的 ScanResult JSON，
+// 可显著减小大树经 IPC 传输的体积。解码需要一个 zstd 解压函数（例如 fzstd 的 decompress），
+// 由调用方传入，本模块不直接依赖解压库：
+//
+//   import { decompress } from 'fzstd'
+//   const payload = await invoke<ScanPayload<ScanResult>>('scan_path_command', { path, compress: true })
+//   const result = decodeScanPayload(payload, decompress)
+
+export interface CompressedScanPayload {
+  compressed: true
+  data: string  // base64(zstd(JSON))
+}
+
+/** 未压缩时载荷就是 ScanResult 本身 */
+export type ScanPayload<T> = T | CompressedScanPayload
+
+export type ZstdDecompress = (data: Uint8Array) => Uint8Array
+
+export function isCompressedScanPayload<T>(payload: ScanPayload<T>): payload is CompressedScanPayload {
+  return typeof payload === 'object' && payload !== null && (payload as CompressedScanPayload).compressed === true
+}
+
+/** base64 → zstd 解压 → UTF-8 → JSON.parse；未压缩的载荷原样返回 */
+export function decodeScanPayload<T>(payload: ScanPayload<T>, decompress: ZstdDecompress): T {
+  if (!isCompressedScanPayload(payload)) return payload
+  const binary = atob(payload.data)
+  const bytes = new Uint8Array(binary.length)
+  for (let i = 0; i < binary.length; i++) bytes[i] = binary.charCodeAt(i)
+  return JSON.parse(new TextDecoder().decode(decompress(bytes))) as T
+}
@@ -0,0 +1,19 @@
+// 扫描过期服务 - 对应后端 get_scan_staleness 与 scan-dirty 事件
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface ScanStaleness {
+  scan_id: string
+  dirty_paths: string[]  // 扫描后已删除、移动或转存的路径
+  changed_bytes: number  // 这些路径在扫描树中的原大小之和
+}
+
+/** 查询某次扫描中已变化的路径 */
+export async function getScanStaleness(scanId: string): Promise<ScanStaleness> {
+  return invoke<ScanStaleness>('get_scan_staleness', { scanId })
+}
+
+/** 订阅执行删除、转存等操作后的 scan-dirty 事件 */
+export function onScanDirty(handler: (staleness: ScanStaleness) => void): Promise<UnlistenFn> {
+  return listen<ScanStaleness>('scan-dirty', (event) => handler(event.payload))
+}
@@ -0,0 +1,65 @@
+// 定时执行计划 - 对应后端 schedule_plan_execution / list_scheduled_executions /
+// cancel_scheduled_execution / run_scheduled_execution_now
+// 执行结束时后端发送系统通知并发出 scheduled-execution-finished 事件，负载为 [scheduleId, report]
+import { invoke } from '@tauri-apps/api/core'
+import type { ExecutionReport } from './sessions'
+
+export const SCHEDULED_EXECUTION_FINISHED_EVENT = 'scheduled-execution-finished'
+
+/** 已保存的计划，或会话中已批准的动作；执行时按当时的文件重新读取 */
+export type ScheduleTarget =
+  | { kind: 'plan'; name: string }
+  | { kind: 'session'; session_id: string }
+
+/** 无法检测的条件（如 Linux 上的输入空闲与全屏）视为满足 */
+export interface ExecutionConditions {
+  require_ac_power: boolean
+  idle_minutes?: number | null  // 键鼠至少空闲的分钟数
+  no_fullscreen: boolean
+  give_up_after_minutes: number  // 超过计划时间这么多分钟仍未满足条件时放弃，0 表示一直等待；默认 360
+}
+
+export type ScheduleStatus =
+  | 'pending'
+  | 'running'
+  | 'completed'
+  | 'cancelled'
+  | 'expired'
+  | 'interrupted'  // 执行中应用退出，可用 resumeExecution 续做
+
+export type WaitReason = 'on_battery' | 'user_active' | 'fullscreen_app'
+
+export interface ScheduledExecution {
+  schedule_id: string
+  created_at: number  // Unix 秒
+  target: ScheduleTarget
+  run_at: number  // Unix 秒
+  conditions: ExecutionConditions
+  status: ScheduleStatus
+  run_now: boolean
+  waiting_for?: WaitReason  // 已到时间但未满足的条件
+  report?: ExecutionReport
+}
+
+export async function schedulePlanExecution(
+  target: ScheduleTarget,
+  runAt: number,
+  conditions?: ExecutionConditions
+): Promise<ScheduledExecution> {
+  return invoke<ScheduledExecution>('schedule_plan_execution', { target, runAt, conditions })
+}
+
+/** 按计划时间排序，含已结束的任务 */
+export async function listScheduledExecutions(): Promise<ScheduledExecution[]> {
+  return invoke<ScheduledExecution[]>('list_scheduled_executions')
+}
+
+/** 只能取消等待中的任务 */
+export async function cancelScheduledExecution(scheduleId: string): Promise<ScheduledExecution> {
+  return invoke<ScheduledExecution>('cancel_scheduled_execution', { scheduleId })
+}
+
+/** 不再等待时间与条件，立即在后台执行 */
+export async function runScheduledExecutionNow(scheduleId: string): Promise<ScheduledExecution> {
+  return invoke<ScheduledExecution>('run_scheduled_execution_now', { scheduleId })
+}
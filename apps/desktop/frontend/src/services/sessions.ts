@@ -0,0 +1,105 @@
+// 分析会话 - 对应后端 create_session / get_session / update_session_decisions / list_sessions
+// analyze_disk、get_cleanup_plan、execute_plan 与 resume_execution 传入 sessionId 时把结果写入会话
+import { invoke } from '@tauri-apps/api/core'
+import type { CleanupPlan, FreeSpaceProjection } from './savedPlans'
+import type { ScheduleTarget } from './scheduledExecutions'
+
+export type SessionStage = 'Scanned' | 'Analyzed' | 'Planned' | 'Executed'
+
+export type ActionDecision = 'Pending' | 'Approved' | 'Rejected'
+
+/** 扫描树不随会话保存，重启后按 root_path 重新扫描 */
+export interface ScanReference {
+  scan_id: string
+  root_path: string
+  total_size: number
+  file_count: number
+  scan_time_ms: number
+}
+
+/** 后端 analyze_disk 的结果 */
+export interface DiskAnalysis {
+  root_path: string
+  total_size: number
+  findings: { category: string; headline: string; bytes: number; risk: 'Low' | 'Medium' | 'High'; paths: string[] }[]
+  category_totals: { category: string; bytes: number; entry_count: number }[]
+  reclaimable_low_risk: number
+  reclaimable_medium_risk: number
+  projection?: FreeSpaceProjection  // 分析时传入了所选动作才有
+  narrative?: string
+}
+
+export interface SessionPlan {
+  plan: CleanupPlan  // 只含通过校验的动作
+  decisions: ActionDecision[]  // 与 plan.actions 一一对应
+}
+
+export interface ExecutionReport {
+  execution_id: string
+  finished_at: number  // Unix 秒
+  resumed: boolean
+  executed: number
+  skipped: number
+  freed: number
+  summary: string  // 出错时为错误信息
+  failed: boolean
+  selection?: PlanSelection  // 只执行了勾选的部分动作
+  changed?: ChangedTarget[]  // 因目标在扫描后已改变而跳过的动作，不计入 skipped
+}
+
+/** 因目标在扫描后已改变而跳过的动作 */
+export interface ChangedTarget {
+  index: number  // 动作在本次执行中的下标
+  path: string
+  reason: string
+}
+
+/** 部分执行的勾选，下标均为原计划中的下标 */
+export interface PlanSelection {
+  source: ScheduleTarget
+  total_actions: number
+  selected: number[]
+  covered?: number[]  // 被同时勾选的父目录删除覆盖、不再单独执行的动作
+  bytes: number  // 实际执行部分的预计释放量
+}
+
+export interface Session {
+  session_id: string
+  created_at: number  // Unix 秒
+  updated_at: number
+  scan: ScanReference
+  analysis?: DiskAnalysis
+  plan?: SessionPlan
+  executions: ExecutionReport[]
+}
+
+export interface SessionSummary {
+  session_id: string
+  created_at: number
+  updated_at: number
+  scan: ScanReference
+  stage: SessionStage
+  freed_bytes: number
+}
+
+/** 为缓存中的扫描结果创建会话 */
+export async function createSession(scanId: string): Promise<Session> {
+  return invoke<Session>('create_session', { scanId })
+}
+
+export async function getSession(sessionId: string): Promise<Session> {
+  return invoke<Session>('get_session', { sessionId })
+}
+
+/** decisions 须与会话计划中的动作一一对应 */
+export async function updateSessionDecisions(
+  sessionId: string,
+  decisions: ActionDecision[]
+): Promise<Session> {
+  return invoke<Session>('update_session_decisions', { sessionId, decisions })
+}
+
+/** 最近更新的会话在前 */
+export async function listSessions(): Promise<SessionSummary[]> {
+  return invoke<SessionSummary[]>('list_sessions')
+}
@@ -1,6 +1,7 @@
 // 应用设置服务
 import { readJSON, writeJSON } from './storage'
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
 export interface AppSettings {
   promptFileCount: number  // AI Prompt 中显示的文件数量
@@ -8,13 +9,10 @@ export interface AppSettings {
   useMftScan?: boolean
 }
 
-// OAuth Token 响应
-export interface OAuthTokens {
-  access_token: string
-  refresh_token?: string
-  expires_in: number
-  token_type: string
-  scope?: string
+// OAuth 授权结果：token 已由后端保存到凭据存储，这里只有账号 ID 与凭据状态
+export interface OAuthConnection {
+  account_id: string
+  status: CloudCredentialsStatus
 }
 
 // Google 用户信息
@@ -58,6 +56,14 @@ export interface DropboxUserInfo {
   profile_photo_url?: string
 }
 
+// OneDrive 用户信息（Microsoft Graph /me）
+export interface OneDriveUserInfo {
+  id: string
+  displayName?: string
+  mail?: string | null
+  userPrincipalName?: string
+}
+
 // 云存储服务类型
 export type CloudStorageProvider = 
   | 'google_drive'
@@ -74,10 +80,10 @@ export interface CloudStorageConfig {
   enabled: boolean
   
   // OAuth 相关（用于 Google Drive, OneDrive, Dropbox, 阿里云盘, 百度网盘）
+  // token 保存在系统钥匙串中，以 provider + accountId 为键；设置文件里只保留 accountId
+  accountId?: string
   clientId?: string
   clientSecret?: string
-  accessToken?: string
-  refreshToken?: string
   tokenExpiry?: number
   
   // WebDAV 相关（用于坚果云等）
@@ -194,16 +200,79 @@ export async function saveAppSettings(settings: AppSettings): Promise<void> {
   await writeJSON(SETTINGS_FILE, settings)
 }
 
-// 加载云存储设置
+// MFT 可用性（勾选「使用 MFT」时提示为何不生效）
+export interface MftAvailability {
+  available: boolean
+  reason?: string  // 不可用原因：未以管理员身份运行、非 NTFS、非磁盘根目录等
+}
+
+export async function explainMftAvailability(path: string): Promise<MftAvailability> {
+  return invoke<MftAvailability>('explain_mft_availability_command', { path })
+}
+
+// 扫描前预检（对应后端 preflight_scan）：是否会使用 MFT 及原因、预计条目数与推荐的扫描方式
+export type MftBlocker =
+  | 'not_requested'
+  | 'path_not_found'
+  | 'unsupported_platform'
+  | 'native_backend_disabled'
+  | 'not_volume_root'
+  | 'not_ntfs'
+  | 'not_elevated'
+
+export interface ScanPreflight {
+  will_use_mft: boolean
+  mft_blocker?: MftBlocker
+  mft_reason?: string
+  is_volume_root: boolean
+  filesystem?: string
+  is_elevated: boolean
+  estimated_entries?: number  // 该路径最近一次扫描的文件数
+  recommended_mode: 'mft' | 'walk'
+}
+
+export async function preflightScan(path: string, useMft?: boolean): Promise<ScanPreflight> {
+  return invoke<ScanPreflight>('preflight_scan', { path, useMft })
+}
+
+// 加载云存储设置（token 保存在后端凭据存储中，这里只有 accountId）
 export async function loadCloudStorageSettings(): Promise<CloudStorageSettings> {
   return await readJSON<CloudStorageSettings>(CLOUD_STORAGE_SETTINGS_FILE, DEFAULT_CLOUD_STORAGE_SETTINGS)
 }
 
-// 保存云存储设置
+// 保存云存储设置：token 在授权时已由后端保存，设置文件中只有 accountId
 export async function saveCloudStorageSettings(settings: CloudStorageSettings): Promise<void> {
   await writeJSON(CLOUD_STORAGE_SETTINGS_FILE, settings)
 }
 
+// ===== 凭据存储 =====
+
+// 后端返回的凭据视图（不含任何 token）
+export interface CloudCredentialsStatus {
+  expires_at: number  // 过期时间（Unix 毫秒）
+  has_refresh_token: boolean
+  backend: 'keyring' | 'encrypted_file'
+}
+
+export async function getCloudCredentials(
+  provider: CloudStorageProvider,
+  accountId: string,
+): Promise<CloudCredentialsStatus | null> {
+  return await invoke<CloudCredentialsStatus | null>('get_cloud_credentials', { provider, accountId })
+}
+
+export async function deleteCloudCredentials(provider: CloudStorageProvider, accountId: string): Promise<void> {
+  await invoke('delete_cloud_credentials', { provider, accountId })
+}
+
+// 用后端保存的 refresh token 刷新
+export async function refreshCloudCredentials(
+  provider: CloudStorageProvider,
+  accountId: string,
+): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_cloud_credentials', { provider, accountId })
+}
+
 // 检查是否有可用的云存储配置
 export async function hasCloudStorageConfig(): Promise<boolean> {
   const settings = await loadCloudStorageSettings()
@@ -227,24 +296,49 @@ export async function getEnabledCloudStorageConfigs(): Promise<CloudStorageConfi
 
 // ===== OAuth 相关函数 =====
 
+// 等待授权回调的倒计时（oauth-waiting 每秒一次；oauth-timeout 时 seconds_remaining 为 0）
+export interface OAuthWaiting {
+  provider: string
+  port: number
+  seconds_remaining: number
+}
+
+/** 订阅等待授权回调的倒计时 */
+export function onOAuthWaiting(handler: (status: OAuthWaiting) => void): Promise<UnlistenFn> {
+  return listen<OAuthWaiting>('oauth-waiting', (event) => handler(event.payload))
+}
+
+/** 订阅等待超时；随后对应的 start*OAuth 以 OAuthTimeout 失败，界面可据此提供重试 */
+export function onOAuthTimeout(handler: (status: OAuthWaiting) => void): Promise<UnlistenFn> {
+  return listen<OAuthWaiting>('oauth-timeout', (event) => handler(event.payload))
+}
+
+// start*OAuth 的 accountId 为重新授权的已有账号（新连接时省略），timeoutSecs 为等待回调的超时
+// （默认 300 秒，限制在 30 秒到 30 分钟之间）；token 由后端保存，只返回账号 ID 与凭据状态
+
+// 取消进行中的 OAuth 授权（对应的 start*OAuth 会以 OAuthCancelled 失败并释放回调端口）
+export async function cancelOAuthFlow(): Promise<boolean> {
+  return await invoke<boolean>('cancel_oauth_flow')
+}
+
 // 启动 Google OAuth 授权流程（打开浏览器并等待回调）
-export async function startGoogleOAuth(): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('complete_google_oauth')
+export async function startGoogleOAuth(accountId?: string, timeoutSecs?: number): Promise<OAuthConnection> {
+  return await invoke<OAuthConnection>('complete_google_oauth', { accountId, timeoutSecs })
 }
 
-// 刷新 Google OAuth access token
-export async function refreshGoogleToken(refreshToken: string): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('refresh_google_token', { refreshToken })
+// 用后端保存的 refresh token 刷新 Google access token
+export async function refreshGoogleToken(accountId: string): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_google_token', { accountId })
 }
 
 // 撤销 Google OAuth 授权
-export async function revokeGoogleToken(token: string): Promise<void> {
-  return await invoke('revoke_google_token', { token })
+export async function revokeGoogleToken(accountId: string): Promise<void> {
+  return await invoke('revoke_google_token', { accountId })
 }
 
 // 获取 Google 用户信息
-export async function getGoogleUserInfo(accessToken: string): Promise<GoogleUserInfo> {
-  return await invoke<GoogleUserInfo>('get_google_user_info', { accessToken })
+export async function getGoogleUserInfo(accountId: string): Promise<GoogleUserInfo> {
+  return await invoke<GoogleUserInfo>('get_google_user_info', { accountId })
 }
 
 // Google Drive 存储配额信息
@@ -263,30 +357,30 @@ export interface GoogleDriveQuota {
 }
 
 // 获取 Google Drive 存储配额
-export async function getGoogleDriveQuota(accessToken: string): Promise<GoogleDriveQuota> {
-  return await invoke<GoogleDriveQuota>('get_google_drive_quota', { accessToken })
+export async function getGoogleDriveQuota(accountId: string): Promise<GoogleDriveQuota> {
+  return await invoke<GoogleDriveQuota>('get_google_drive_quota', { accountId })
 }
 
 // ===== 百度网盘 OAuth 相关函数 =====
 
 // 启动百度网盘 OAuth 授权流程（打开浏览器并等待回调）
-export async function startBaiduOAuth(): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('complete_baidu_oauth')
+export async function startBaiduOAuth(accountId?: string, timeoutSecs?: number): Promise<OAuthConnection> {
+  return await invoke<OAuthConnection>('complete_baidu_oauth', { accountId, timeoutSecs })
 }
 
-// 刷新百度网盘 OAuth access token
-export async function refreshBaiduToken(refreshToken: string): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('refresh_baidu_token', { refreshToken })
+// 用后端保存的 refresh token 刷新百度网盘 access token
+export async function refreshBaiduToken(accountId: string): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_baidu_token', { accountId })
 }
 
 // 撤销百度网盘 OAuth 授权
-export async function revokeBaiduToken(token: string): Promise<void> {
-  return await invoke('revoke_baidu_token', { token })
+export async function revokeBaiduToken(accountId: string): Promise<void> {
+  return await invoke('revoke_baidu_token', { accountId })
 }
 
 // 获取百度网盘用户信息
-export async function getBaiduUserInfo(accessToken: string): Promise<BaiduUserInfo> {
-  const userInfo = await invoke<BaiduUserInfo>('get_baidu_user_info', { accessToken })
+export async function getBaiduUserInfo(accountId: string): Promise<BaiduUserInfo> {
+  const userInfo = await invoke<BaiduUserInfo>('get_baidu_user_info', { accountId })
   return userInfo
 }
 
@@ -298,30 +392,30 @@ export interface BaiduNetdiskQuota {
 }
 
 // 获取百度网盘存储配额
-export async function getBaiduNetdiskQuota(accessToken: string): Promise<BaiduNetdiskQuota> {
-  return await invoke<BaiduNetdiskQuota>('get_baidu_netdisk_quota', { accessToken })
+export async function getBaiduNetdiskQuota(accountId: string): Promise<BaiduNetdiskQuota> {
+  return await invoke<BaiduNetdiskQuota>('get_baidu_netdisk_quota', { accountId })
 }
 
 // ===== 阿里云盘 OAuth 相关函数 =====
 
 // 启动阿里云盘 OAuth 授权流程（打开浏览器并等待回调）
-export async function startAliyunOAuth(): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('complete_aliyun_oauth')
+export async function startAliyunOAuth(accountId?: string, timeoutSecs?: number): Promise<OAuthConnection> {
+  return await invoke<OAuthConnection>('complete_aliyun_oauth', { accountId, timeoutSecs })
 }
 
-// 刷新阿里云盘 OAuth access token
-export async function refreshAliyunToken(refreshToken: string): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('refresh_aliyun_token', { refreshToken })
+// 用后端保存的 refresh token 刷新阿里云盘 access token
+export async function refreshAliyunToken(accountId: string): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_aliyun_token', { accountId })
 }
 
 // 撤销阿里云盘 OAuth 授权
-export async function revokeAliyunToken(token: string): Promise<void> {
-  return await invoke('revoke_aliyun_token', { token })
+export async function revokeAliyunToken(accountId: string): Promise<void> {
+  return await invoke('revoke_aliyun_token', { accountId })
 }
 
 // 获取阿里云盘用户信息
-export async function getAliyunUserInfo(accessToken: string): Promise<AliyunUserInfo> {
-  const userInfo = await invoke<AliyunUserInfo>('get_aliyun_user_info', { accessToken })
+export async function getAliyunUserInfo(accountId: string): Promise<AliyunUserInfo> {
+  const userInfo = await invoke<AliyunUserInfo>('get_aliyun_user_info', { accountId })
   return userInfo
 }
 
@@ -333,30 +427,30 @@ export interface AliyunDriveQuota {
 }
 
 // 获取阿里云盘存储配额
-export async function getAliyunDriveQuota(accessToken: string): Promise<AliyunDriveQuota> {
-  return await invoke<AliyunDriveQuota>('get_aliyun_drive_quota', { accessToken })
+export async function getAliyunDriveQuota(accountId: string): Promise<AliyunDriveQuota> {
+  return await invoke<AliyunDriveQuota>('get_aliyun_drive_quota', { accountId })
 }
 
 // ===== Dropbox OAuth 相关函数 =====
 
 // 启动 Dropbox OAuth 授权流程（打开浏览器并等待回调）
-export async function startDropboxOAuth(): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('complete_dropbox_oauth')
+export async function startDropboxOAuth(accountId?: string, timeoutSecs?: number): Promise<OAuthConnection> {
+  return await invoke<OAuthConnection>('complete_dropbox_oauth', { accountId, timeoutSecs })
 }
 
-// 刷新 Dropbox OAuth access token
-export async function refreshDropboxToken(refreshToken: string): Promise<OAuthTokens> {
-  return await invoke<OAuthTokens>('refresh_dropbox_token', { refreshToken })
+// 用后端保存的 refresh token 刷新 Dropbox access token
+export async function refreshDropboxToken(accountId: string): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_dropbox_token', { accountId })
 }
 
 // 撤销 Dropbox OAuth 授权
-export async function revokeDropboxToken(token: string): Promise<void> {
-  return await invoke('revoke_dropbox_token', { token })
+export async function revokeDropboxToken(accountId: string): Promise<void> {
+  return await invoke('revoke_dropbox_token', { accountId })
 }
 
 // 获取 Dropbox 用户信息
-export async function getDropboxUserInfo(accessToken: string): Promise<DropboxUserInfo> {
-  const userInfo = await invoke<DropboxUserInfo>('get_dropbox_user_info', { accessToken })
+export async function getDropboxUserInfo(accountId: string): Promise<DropboxUserInfo> {
+  const userInfo = await invoke<DropboxUserInfo>('get_dropbox_user_info', { accountId })
   return userInfo
 }
 
@@ -370,45 +464,54 @@ export interface DropboxQuota {
 }
 
 // 获取 Dropbox 存储配额
-export async function getDropboxQuota(accessToken: string): Promise<DropboxQuota> {
-  return await invoke<DropboxQuota>('get_dropbox_quota', { accessToken })
+export async function getDropboxQuota(accountId: string): Promise<DropboxQuota> {
+  return await invoke<DropboxQuota>('get_dropbox_quota', { accountId })
 }
 
-// 检查并刷新 token（如果快过期）
-export async function ensureValidToken(config: CloudStorageConfig): Promise<CloudStorageConfig> {
-  if (!config.accessToken || !config.tokenExpiry) {
-    throw new Error('配置中缺少 access token')
-  }
+// ===== OneDrive OAuth 相关函数 =====
 
-  // 如果 token 在 5 分钟内过期，刷新它
-  const now = Date.now()
-  const expiryBuffer = 5 * 60 * 1000 // 5 minutes
-  
-  if (config.tokenExpiry - now < expiryBuffer) {
-    if (!config.refreshToken) {
-      throw new Error('Token 已过期且没有 refresh token')
-    }
-    
-    // 根据提供商选择不同的刷新函数
-    let tokens: OAuthTokens
-    if (config.provider === 'baidu_netdisk') {
-      tokens = await refreshBaiduToken(config.refreshToken)
-    } else if (config.provider === 'aliyun_drive') {
-      tokens = await refreshAliyunToken(config.refreshToken)
-    } else if (config.provider === 'dropbox') {
-      tokens = await refreshDropboxToken(config.refreshToken)
-    } else {
-      // 默认使用 Google（或其他已实现的提供商）
-      tokens = await refreshGoogleToken(config.refreshToken)
-    }
-    
-    return {
-      ...config,
-      accessToken: tokens.access_token,
-      refreshToken: tokens.refresh_token || config.refreshToken,
-      tokenExpiry: now + tokens.expires_in * 1000,
-    }
+// 启动 OneDrive OAuth 授权流程（打开浏览器并等待回调）
+export async function startOneDriveOAuth(accountId?: string, timeoutSecs?: number): Promise<OAuthConnection> {
+  return await invoke<OAuthConnection>('complete_onedrive_oauth', { accountId, timeoutSecs })
+}
+
+// 用后端保存的 refresh token 刷新 OneDrive access token
+export async function refreshOneDriveToken(accountId: string): Promise<CloudCredentialsStatus> {
+  return await invoke<CloudCredentialsStatus>('refresh_onedrive_token', { accountId })
+}
+
+// 撤销 OneDrive OAuth 授权
+export async function revokeOneDriveToken(accountId: string): Promise<void> {
+  return await invoke('revoke_onedrive_token', { accountId })
+}
+
+// 获取 OneDrive 用户信息
+export async function getOneDriveUserInfo(accountId: string): Promise<OneDriveUserInfo> {
+  const userInfo = await invoke<OneDriveUserInfo>('get_onedrive_user_info', { accountId })
+  return userInfo
+}
+
+// OneDrive 存储配额信息（Microsoft Graph /me/drive）
+export interface OneDriveQuota {
+  quota: {
+    total: number      // 总容量（字节）
+    used: number       // 已使用（字节）
+    remaining: number  // 剩余容量（字节）
+    deleted?: number   // 回收站占用（字节）
+    state?: string
   }
+}
+
+// 获取 OneDrive 存储配额
+export async function getOneDriveQuota(accountId: string): Promise<OneDriveQuota> {
+  return await invoke<OneDriveQuota>('get_onedrive_quota', { accountId })
+}
 
+// 检查登录状态：token 的刷新由后端 TokenManager 在每次云存储调用前自动完成，
+// 这里只确认该配置已有保存的凭据
+export async function ensureValidToken(config: CloudStorageConfig): Promise<CloudStorageConfig> {
+  if (!config.accountId || !(await getCloudCredentials(config.provider, config.accountId))) {
+    throw new Error(`${config.name} 未登录，请先在设置中配置云存储`)
+  }
   return config
 }
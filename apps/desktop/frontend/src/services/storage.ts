@@ -82,3 +82,73 @@ export async function writeJSON(filename: string, data: unknown): Promise<void>
   const content = JSON.stringify(data, null, 2)
   await writeStorageFile(filename, content)
 }
+
+// ========== 带版本的 JSON 文档 ==========
+
+export interface StoredDocument<T = unknown> {
+  schema_version: number
+  version: number  // 修订号，写入时作为 expectedVersion 传回
+  updated_at: number  // Unix 毫秒
+  value: T
+}
+
+export interface DocumentRead<T = unknown> {
+  document: StoredDocument<T> | null
+  corrupt: boolean  // 文件已损坏，原文件已移到 backup_path
+  raw?: string
+  backup_path?: string
+}
+
+/**
+ * 读取文档（.disk-rookie/<namespace>/<key>.json）
+ */
+export async function getDocument<T = unknown>(namespace: string, key: string): Promise<DocumentRead<T>> {
+  return invoke<DocumentRead<T>>('get_document', { namespace, key })
+}
+
+/**
+ * 写入文档；新文档的 expectedVersion 为 0，版本不一致时抛出 VersionConflict
+ */
+export async function putDocument<T = unknown>(
+  namespace: string,
+  key: string,
+  value: T,
+  expectedVersion: number,
+): Promise<StoredDocument<T>> {
+  return invoke<StoredDocument<T>>('put_document', { namespace, key, value, expectedVersion })
+}
+
+/**
+ * 把命名空间中 schema 为 fromVersion 的文档迁移到当前版本，返回迁移的文档数
+ */
+export async function migrateDocuments(namespace: string, fromVersion: number): Promise<number> {
+  return invoke<number>('migrate_documents', { namespace, fromVersion })
+}
+
+// ========== 应用数据备份与恢复 ==========
+
+export interface ExportResult {
+  files: number
+  tokens: number
+  warning?: string  // 包含明文 token 时的安全提示，应展示给用户
+}
+
+export interface ImportResult {
+  files: number
+  skipped: number  // overwrite 为 false 时因已存在而跳过的文件数
+  tokens: number
+}
+
+/**
+ * 把 .disk-rookie 导出为 zip；includeTokens 为 true 时备份包含明文凭据
+ */
+export async function exportAppData(targetZip: string, includeTokens: boolean): Promise<ExportResult> {
+  return invoke<ExportResult>('export_app_data', { targetZip, includeTokens })
+}
+
+/**
+ * 从 zip 备份恢复应用数据
+ */
+export async function importAppData(zipPath: string, overwrite: boolean): Promise<ImportResult> {
+  return invoke<ImportResult>('import_app_data', { zipPath, overwrite })
+}
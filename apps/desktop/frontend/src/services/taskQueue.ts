@@ -82,32 +82,14 @@ export async function executeUploadTask(
     // 准备上传配置
     const uploadConfigs = []
     for (const config of task.targetConfigs) {
-      if (!config.accessToken) {
+      if (!config.accountId) {
         throw new Error(`${config.name} 未登录`)
       }
 
-      // 动态导入 refreshGoogleToken
-      const { refreshGoogleToken } = await import('./settings')
-
-      let accessToken = config.accessToken
-
-      // 检查 token 是否即将过期（5分钟内）
-      if (config.tokenExpiry) {
-        const expiryBuffer = 5 * 60 * 1000
-        if (config.tokenExpiry - Date.now() < expiryBuffer) {
-          if (!config.refreshToken) {
-            throw new Error(`${config.name} 登录已过期，请重新登录`)
-          }
-          // 刷新 token
-          const newTokenData = await refreshGoogleToken(config.refreshToken)
-          accessToken = newTokenData.access_token
-        }
-      }
-
       uploadConfigs.push({
         provider: config.provider,
         name: config.name,
-        access_token: accessToken,
+        account_id: config.accountId,
         target_path: task.targetPath,
       })
     }
@@ -199,3 +181,8 @@ export function formatTime(ms: number): string {
     return `${seconds}秒`
   }
 }
+
+/** 设置所有上传共享的带宽上限（字节/秒，0 表示不限），对进行中的上传立即生效 */
+export async function setUploadBandwidthLimit(bytesPerSec: number): Promise<void> {
+  await invoke('set_upload_bandwidth_limit', { bytesPerSec })
+}
@@ -0,0 +1,55 @@
+// Windows 组件清理服务 - 对应后端 get_windows_cleanup_estimate / run_windows_cleanup / get_vhd_compact_commands
+import { invoke } from '@tauri-apps/api/core'
+
+export type WindowsCleanupCategory =
+  | 'component_store'
+  | 'update_cleanup'
+  | 'delivery_optimization'
+  | 'previous_installations'
+  | 'temporary_setup_files'
+  | 'upgrade_logs'
+  | 'error_reports'
+  | 'temporary_files'
+  | 'thumbnail_cache'
+
+export interface ComponentStoreAnalysis {
+  explorer_reported_bytes: number
+  actual_bytes: number
+  shared_with_windows_bytes: number
+  backups_and_disabled_bytes: number
+  cache_and_temporary_bytes: number
+  reclaimable_packages: number | null
+  cleanup_recommended: boolean | null
+  reclaimable_bytes: number  // 组件清理预计可释放的空间
+}
+
+/** 清理过程中通过 `windows-cleanup-progress` 事件推送 */
+export interface WindowsCleanupProgress {
+  stage: 'component_store' | 'cleanmgr'
+  percent: number | null
+  message: string
+}
+
+export interface WindowsCleanupReport {
+  completed: WindowsCleanupCategory[]
+  unavailable: WindowsCleanupCategory[]  // 本机没有对应内容的类别
+}
+
+/** 估算组件存储可回收空间；未以管理员身份运行时返回 NeedsElevation 错误 */
+export async function getWindowsCleanupEstimate(): Promise<ComponentStoreAnalysis> {
+  return invoke<ComponentStoreAnalysis>('get_windows_cleanup_estimate')
+}
+
+export async function runWindowsCleanup(
+  categories: WindowsCleanupCategory[]
+): Promise<WindowsCleanupReport> {
+  return invoke<WindowsCleanupReport>('run_windows_cleanup', { categories })
+}
+
+/**
+ * 压缩 WSL/Docker 虚拟磁盘（.vhdx）需要依次执行的命令行，供用户复制后自行运行。
+ * 加入计划执行时使用 `{ CompactVhd: { path } }` 动作
+ */
+export async function getVhdCompactCommands(path: string): Promise<string[]> {
+  return invoke<string[]>('get_vhd_compact_commands', { path })
+}
@@ -1,9 +1,32 @@
+const BINARY_UNITS = ['B', 'KiB', 'MiB', 'GiB', 'TiB', 'PiB', 'EiB']
+
+/** 整数按千位加逗号，如 1,048,576 */
+function groupThousands(n: number): string {
+  return Math.round(n).toString().replace(/\B(?=(\d{3})+(?!\d))/g, ',')
+}
+
+/**
+ * 人类可读的大小（1024 进制），与后端 `ai_disk_common::format_bytes` 的规则一致：
+ * 不足 1 KiB 显示字节数；换算后小于 10 保留一位小数，否则取整；取整达到 1024 时进位到下一单位
+ */
 export function formatBytes(bytes: number): string {
-  if (bytes === 0) return '0 B'
-  const k = 1024
-  const sizes = ['B', 'KB', 'MB', 'GB', 'TB']
-  const i = Math.floor(Math.log(bytes) / Math.log(k))
-  return `${parseFloat((bytes / Math.pow(k, i)).toFixed(2))} ${sizes[i]}`
+  if (bytes < 1024) return `${groupThousands(bytes)} B`
+  let value = bytes / 1024
+  let unit = 1
+  while (value >= 1024 && unit < BINARY_UNITS.length - 1) {
+    value /= 1024
+    unit++
+  }
+  for (;;) {
+    const oneDecimal = Math.round(value * 10) / 10
+    if (oneDecimal < 10) return `${oneDecimal.toFixed(1)} ${BINARY_UNITS[unit]}`
+    const whole = Math.round(value)
+    if (whole < 1024 || unit === BINARY_UNITS.length - 1) {
+      return `${groupThousands(whole)} ${BINARY_UNITS[unit]}`
+    }
+    value /= 1024
+    unit++
+  }
 }
 
 export function formatDuration(ms: number): string {
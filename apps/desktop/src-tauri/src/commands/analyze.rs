@@ -1,5 +1,55 @@
+//! 磁盘分析命令：对扫描结果做确定性归类分析；传入 LLM 配置时额外生成自然语言总结。
+//! 传入所选动作时附上执行后的剩余空间预测（执行方式按配置中的执行策略解析）。
+//! 传入 `session_id` 时把分析结果写入该会话。
+
+use ai_disk_common::{CommandError, ExecutionMode};
+use ai_disk_domain::{Action, DiskAnalysis, PlannedAction, ScanResult};
+use ai_disk_engine::llm::{OpenAiConfig, OpenAiProvider};
+use ai_disk_executor::resolve_action_mode;
+use tauri::{async_runtime, AppHandle, State};
+
+use super::config::ConfigState;
+use super::scan::ScanStore;
+use super::sessions::record_analysis;
+
 #[tauri::command]
-pub async fn analyze_disk(path: String) -> Result<String, String> {
-    let _ = path;
-    Ok(r#"{"status":"ok","message":"分析功能待实现"}"#.to_string())
+pub async fn analyze_disk(
+    app: AppHandle,
+    config_state: State<'_, ConfigState>,
+    scan_store: State<'_, ScanStore>,
+    scan_id: Option<String>,
+    scan_result: Option<ScanResult>,
+    actions: Option<Vec<PlannedAction>>,
+    llm: Option<OpenAiConfig>,
+    session_id: Option<String>,
+) -> Result<DiskAnalysis, CommandError> {
+    let scan = scan_store.resolve(scan_id, scan_result)?;
+    let policy = config_state.get().executor.policy;
+
+    let mut analysis = async_runtime::spawn_blocking(move || {
+        let mut analysis = ai_disk_engine::analyze_scan(&scan);
+        if let Some(actions) = actions {
+            let modes: Vec<ExecutionMode> = actions
+                .iter()
+                .map(|planned| resolve_action_mode(&policy, planned))
+                .collect();
+            let actions: Vec<Action> = actions.into_iter().map(|p| p.action).collect();
+            analysis.projection = Some(ai_disk_engine::project_free_space(&scan, &actions, &modes));
+        }
+        analysis
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?;
+
+    if let Some(config) = llm {
+        let provider = OpenAiProvider::new(config);
+        if let Err(e) = ai_disk_engine::narrate_analysis(&mut analysis, &provider).await {
+            log::warn!("生成分析总结失败: {}", e);
+            ai_disk_common::record_error(CommandError::from(e).code);
+        }
+    }
+    if let Some(session_id) = session_id {
+        record_analysis(&app, &session_id, &analysis)?;
+    }
+    Ok(analysis)
 }
@@ -0,0 +1,622 @@
+//! 应用数据备份与恢复：把 `.disk-rookie` 打包为 zip，或从备份恢复到存储根目录。
+//!
+//! 归档结构：`manifest.json`（格式版本与文件清单）、`data/`（存储根目录下的文件），
+//! 以及仅在明确要求时才包含的 `tokens.json`（明文凭据）。日志、遥测与机器绑定的
+//! 加密凭据文件不会被导出。恢复时先完整解压到暂存目录并校验，再逐个替换目标文件，
+//! 任一替换失败时回滚已替换的文件。
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::{CommandError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::credentials::{connected_accounts, CredentialStore, StoredCredentials};
+use super::storage::{get_storage_root, resolve_storage_path};
+use super::token_manager::TokenManager;
+
+/// 当前备份格式版本；导入时拒绝更高的版本
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const TOKENS_NAME: &str = "tokens.json";
+const DATA_PREFIX: &str = "data/";
+
+/// 恢复时的暂存目录（位于存储根目录内，保证与目标在同一文件系统上以便 rename）
+const STAGING_DIR: &str = ".import-staging";
+
+/// 不导出的顶层目录：日志、遥测与本机的上传会话都与当前机器绑定
+const EXCLUDED_DIRS: &[&str] = &["logs", "telemetry", "upload_sessions"];
+
+/// 不导出的凭据文件：加密密钥由机器标识派生，换机器后无法解密
+const CREDENTIAL_FILES: &[&str] = &["credentials.enc.json", "credentials.enc.tmp"];
+
+const TOKENS_WARNING: &str =
+    "备份中包含明文的云存储 token，任何拿到该文件的人都可以访问你的云盘，请妥善保管";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    format_version: u32,
+    app_version: String,
+    /// Unix 时间戳（秒）
+    created_at: u64,
+    /// `data/` 下的文件，相对存储根目录、以 `/` 分隔
+    files: Vec<String>,
+    includes_tokens: bool,
+}
+
+/// 导出的一个账号的凭据
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedCredentials {
+    provider: String,
+    account_id: String,
+    credentials: StoredCredentials,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportResult {
+    pub files: usize,
+    pub tokens: usize,
+    /// 包含 token 时提示备份文件敏感
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub files: usize,
+    /// `overwrite` 为 false 时因目标已存在而跳过的文件数
+    pub skipped: usize,
+    pub tokens: usize,
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn zip_error(context: &str, e: zip::result::ZipError) -> CommandError {
+    CommandError::internal(format!("{}: {}", context, e))
+}
+
+fn invalid_archive(reason: impl Into<String>) -> CommandError {
+    CommandError::new(
+        ErrorCode::InvalidInput,
+        format!("备份文件无效: {}", reason.into()),
+    )
+}
+
+/// 是否导出存储根目录下的相对路径 `rel`（以 `/` 分隔）
+fn is_exported(rel: &str) -> bool {
+    let first = rel.split('/').next().unwrap_or_default();
+    !first.starts_with('.') && !EXCLUDED_DIRS.contains(&first) && !CREDENTIAL_FILES.contains(&rel)
+}
+
+/// 递归收集要导出的文件；跳过符号链接
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), CommandError> {
+    let entries = fs::read_dir(dir).map_err(|e| CommandError::io("读取存储目录失败", &e))?;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        let Some(rel) = path
+            .strip_prefix(root)
+            .ok()
+            .and_then(|p| p.to_str())
+            .map(|p| p.replace('\\', "/"))
+        else {
+            continue;
+        };
+        if !is_exported(&rel) {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// 把存储根目录打包到 `target`；`store` 为 Some 时同时导出已连接账号的凭据
+fn export_to(
+    root: &Path,
+    target: &Path,
+    store: Option<&CredentialStore>,
+) -> Result<ExportResult, CommandError> {
+    if target.starts_with(root) {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            "备份文件不能保存在存储目录内",
+        ));
+    }
+
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    let mut tokens = Vec::new();
+    if let Some(store) = store {
+        for account in connected_accounts(root)? {
+            if let Some(credentials) = store.load(&account.provider, &account.account_id)? {
+                tokens.push(ExportedCredentials {
+                    provider: account.provider,
+                    account_id: account.account_id,
+                    credentials,
+                });
+            }
+        }
+    }
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now_secs(),
+        files: files.clone(),
+        includes_tokens: store.is_some(),
+    };
+
+    // 先写临时文件，完整写完后再替换目标
+    let tmp = target.with_extension("zip.tmp");
+    let _in_flight = ai_disk_common::register_in_flight(&tmp);
+    let write = || -> Result<(), CommandError> {
+        let file = File::create(&tmp).map_err(|e| CommandError::io("创建备份文件失败", &e))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| CommandError::internal(format!("序列化备份清单失败: {}", e)))?;
+        zip.start_file(MANIFEST_NAME, options)
+            .map_err(|e| zip_error("写入备份失败", e))?;
+        zip.write_all(&manifest_json)
+            .map_err(|e| CommandError::io("写入备份失败", &e))?;
+
+        for rel in &files {
+            let content =
+                fs::read(root.join(rel)).map_err(|e| CommandError::io("读取存储文件失败", &e))?;
+            zip.start_file(format!("{}{}", DATA_PREFIX, rel), options)
+                .map_err(|e| zip_error("写入备份失败", e))?;
+            zip.write_all(&content)
+                .map_err(|e| CommandError::io("写入备份失败", &e))?;
+        }
+
+        if store.is_some() {
+            let tokens_json = serde_json::to_vec_pretty(&tokens)
+                .map_err(|e| CommandError::internal(format!("序列化凭据失败: {}", e)))?;
+            zip.start_file(TOKENS_NAME, options)
+                .map_err(|e| zip_error("写入备份失败", e))?;
+            zip.write_all(&tokens_json)
+                .map_err(|e| CommandError::io("写入备份失败", &e))?;
+        }
+
+        let file = zip.finish().map_err(|e| zip_error("写入备份失败", e))?;
+        file.sync_all()
+            .map_err(|e| CommandError::io("写入备份失败", &e))
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    fs::rename(&tmp, target).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        CommandError::io("保存备份文件失败", &e)
+    })?;
+
+    if store.is_some() {
+        tracing::warn!(tokens = tokens.len(), "导出的备份包含明文凭据");
+    }
+    Ok(ExportResult {
+        files: files.len(),
+        tokens: tokens.len(),
+        warning: store.map(|_| TOKENS_WARNING.to_string()),
+    })
+}
+
+/// 校验归档结构并解压到暂存目录，返回暂存的相对路径与导出的凭据
+fn stage_archive(
+    zip_path: &Path,
+    staging: &Path,
+) -> Result<(Vec<String>, Vec<ExportedCredentials>), CommandError> {
+    let file = File::open(zip_path).map_err(|e| CommandError::io("打开备份文件失败", &e))?;
+    let mut archive = ZipArchive::new(file).map_err(|_| invalid_archive("不是 zip 文件"))?;
+
+    let manifest: BackupManifest = {
+        let entry = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| invalid_archive("缺少 manifest.json"))?;
+        serde_json::from_reader(entry)
+            .map_err(|e| invalid_archive(format!("manifest.json 无法解析: {}", e)))?
+    };
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(invalid_archive(format!(
+            "备份格式版本 {} 高于当前支持的 {}，请先升级应用",
+            manifest.format_version, BACKUP_FORMAT_VERSION
+        )));
+    }
+
+    let mut staged = Vec::new();
+    let mut tokens = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| zip_error("读取备份失败", e))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || name == MANIFEST_NAME {
+            continue;
+        }
+        if name == TOKENS_NAME {
+            tokens = serde_json::from_reader(&mut entry)
+                .map_err(|e| invalid_archive(format!("tokens.json 无法解析: {}", e)))?;
+            continue;
+        }
+        let Some(rel) = name.strip_prefix(DATA_PREFIX) else {
+            return Err(invalid_archive(format!("未知的条目 {}", name)));
+        };
+        if entry.enclosed_name().is_none()
+            || !is_exported(rel)
+            || !manifest.files.iter().any(|f| f == rel)
+        {
+            return Err(invalid_archive(format!("非法的条目 {}", name)));
+        }
+
+        let dest = resolve_storage_path(staging, rel)?;
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| CommandError::io("创建暂存目录失败", &e))?;
+        }
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| invalid_archive(format!("{} 已损坏: {}", name, e)))?;
+        fs::write(&dest, content).map_err(|e| CommandError::io("写入暂存文件失败", &e))?;
+        staged.push(rel.to_string());
+    }
+
+    if staged.len() != manifest.files.len() {
+        return Err(invalid_archive("文件清单与归档内容不一致"));
+    }
+    Ok((staged, tokens))
+}
+
+/// 把暂存目录中的文件逐个替换到存储根目录；失败时按相反顺序回滚
+fn swap_into(
+    root: &Path,
+    staging: &Path,
+    files: &[String],
+    overwrite: bool,
+) -> Result<(usize, usize), CommandError> {
+    let replaced_dir = staging.join(".replaced");
+    // (目标, 被替换文件的备份位置)
+    let mut moved: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+    let mut skipped = 0;
+
+    let result = (|| -> Result<(), CommandError> {
+        for rel in files {
+            let target = resolve_storage_path(root, rel)?;
+            let existing = target.exists();
+            if existing && !overwrite {
+                skipped += 1;
+                continue;
+            }
+            if target.is_dir() {
+                return Err(CommandError::new(
+                    ErrorCode::InvalidInput,
+                    format!("备份中的文件与已有目录同名: {}", rel),
+                ));
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| CommandError::io("创建目录失败", &e))?;
+            }
+            let backup = if existing {
+                let backup = replaced_dir.join(rel);
+                if let Some(parent) = backup.parent() {
+                    fs::create_dir_all(parent).map_err(|e| CommandError::io("创建目录失败", &e))?;
+                }
+                fs::rename(&target, &backup).map_err(|e| CommandError::io("替换文件失败", &e))?;
+                Some(backup)
+            } else {
+                None
+            };
+            moved.push((target.clone(), backup));
+            fs::rename(staging.join(rel), &target)
+                .map_err(|e| CommandError::io("替换文件失败", &e))?;
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for (target, backup) in moved.iter().rev() {
+            let _ = fs::remove_file(target);
+            if let Some(backup) = backup {
+                if let Err(e) = fs::rename(backup, target) {
+                    tracing::error!(path = %target.display(), error = %e, "回滚恢复失败");
+                }
+            }
+        }
+        return Err(e);
+    }
+    Ok((moved.len(), skipped))
+}
+
+/// 从备份恢复到存储根目录；`overwrite` 为 false 时保留已存在的文件
+fn import_from(
+    root: &Path,
+    zip_path: &Path,
+    overwrite: bool,
+    store: &CredentialStore,
+) -> Result<ImportResult, CommandError> {
+    let staging = root.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| CommandError::io("清理暂存目录失败", &e))?;
+    }
+    fs::create_dir_all(&staging).map_err(|e| CommandError::io("创建暂存目录失败", &e))?;
+
+    let result = stage_archive(zip_path, &staging).and_then(|(files, tokens)| {
+        let (imported, skipped) = swap_into(root, &staging, &files, overwrite)?;
+        for exported in &tokens {
+            store.save(
+                &exported.provider,
+                &exported.account_id,
+                &exported.credentials,
+            )?;
+        }
+        Ok(ImportResult {
+            files: imported,
+            skipped,
+            tokens: tokens.len(),
+        })
+    });
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        tracing::warn!(error = %e, "清理暂存目录失败");
+    }
+    result
+}
+
+/// 把应用数据导出为 zip；`include_tokens` 为 true 时包含明文凭据
+#[tauri::command]
+pub async fn export_app_data(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    target_zip: String,
+    include_tokens: bool,
+) -> Result<ExportResult, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let store = include_tokens.then(|| tokens.store());
+    export_to(&storage_root, Path::new(&target_zip), store)
+}
+
+/// 从 zip 备份恢复应用数据
+#[tauri::command]
+pub async fn import_app_data(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    zip_path: String,
+    overwrite: bool,
+) -> Result<ImportResult, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    import_from(
+        &storage_root,
+        Path::new(&zip_path),
+        overwrite,
+        tokens.store(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn populate(root: &Path) {
+        fs::create_dir_all(root.join("plans")).unwrap();
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::create_dir_all(root.join("telemetry")).unwrap();
+        fs::write(
+            root.join("cloud-storage-settings.json"),
+            r#"{"configs":[{"provider":"dropbox","name":"D","accountId":"acc-1"}]}"#,
+        )
+        .unwrap();
+        fs::write(root.join("plans").join("today.json"), "{\"a\":1}").unwrap();
+        fs::write(root.join("logs").join("app.log"), "log line").unwrap();
+        fs::write(root.join("telemetry").join("events.jsonl"), "{}").unwrap();
+        fs::write(root.join("credentials.enc.json"), "{\"k\":\"secret\"}").unwrap();
+        fs::write(root.join(".settings.json.1.tmp"), "partial").unwrap();
+    }
+
+    fn credentials() -> StoredCredentials {
+        StoredCredentials {
+            access_token: "sl.access".into(),
+            refresh_token: Some("refresh".into()),
+            expires_at: 1,
+            token_type: "Bearer".into(),
+            scope: None,
+        }
+    }
+
+    fn entry_names(zip_path: &Path) -> Vec<String> {
+        let mut archive = ZipArchive::new(File::open(zip_path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_export_excludes_logs_and_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".disk-rookie");
+        populate(&root);
+        let zip_path = dir.path().join("backup.zip");
+
+        let result = export_to(&root, &zip_path, None).unwrap();
+        assert_eq!(result.files, 2);
+        assert!(result.warning.is_none());
+        let mut names = entry_names(&zip_path);
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "data/cloud-storage-settings.json",
+                "data/plans/today.json",
+                "manifest.json"
+            ]
+        );
+
+        assert!(export_to(&root, &root.join("inside.zip"), None).is_err());
+    }
+
+    #[test]
+    fn test_tokens_only_exported_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".disk-rookie");
+        populate(&root);
+        let store = CredentialStore::mock();
+        store.save("dropbox", "acc-1", &credentials()).unwrap();
+
+        let zip_path = dir.path().join("with-tokens.zip");
+        let result = export_to(&root, &zip_path, Some(&store)).unwrap();
+        assert_eq!(result.tokens, 1);
+        assert!(result.warning.is_some());
+        assert!(entry_names(&zip_path).contains(&TOKENS_NAME.to_string()));
+
+        let zip_path = dir.path().join("without-tokens.zip");
+        export_to(&root, &zip_path, None).unwrap();
+        assert!(!entry_names(&zip_path).contains(&TOKENS_NAME.to_string()));
+    }
+
+    #[test]
+    fn test_round_trip_restores_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        populate(&source);
+        let store = CredentialStore::mock();
+        store.save("dropbox", "acc-1", &credentials()).unwrap();
+        let zip_path = dir.path().join("backup.zip");
+        export_to(&source, &zip_path, Some(&store)).unwrap();
+
+        let target = dir.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("cloud-storage-settings.json"), "{}").unwrap();
+
+        let restored_store = CredentialStore::mock();
+        let result = import_from(&target, &zip_path, false, &restored_store).unwrap();
+        assert_eq!((result.files, result.skipped, result.tokens), (1, 1, 1));
+        assert_eq!(
+            fs::read_to_string(target.join("plans").join("today.json")).unwrap(),
+            "{\"a\":1}"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("cloud-storage-settings.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            restored_store
+                .load("dropbox", "acc-1")
+                .unwrap()
+                .unwrap()
+                .access_token,
+            "sl.access"
+        );
+
+        let result = import_from(&target, &zip_path, true, &restored_store).unwrap();
+        assert_eq!((result.files, result.skipped), (2, 0));
+        assert!(
+            fs::read_to_string(target.join("cloud-storage-settings.json"))
+                .unwrap()
+                .contains("acc-1")
+        );
+        assert!(!target.join("logs").exists());
+        assert!(!target.join("credentials.enc.json").exists());
+        assert!(!target.join(STAGING_DIR).exists());
+    }
+
+    #[test]
+    fn test_rejects_invalid_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("keep.json"), "old").unwrap();
+        let store = CredentialStore::mock();
+
+        let write_zip = |name: &str, entries: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            let mut zip = ZipWriter::new(File::create(&path).unwrap());
+            for (entry, content) in entries {
+                zip.start_file(*entry, SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+            path
+        };
+        let manifest = |version: u32, files: &[&str]| {
+            serde_json::json!({
+                "format_version": version,
+                "app_version": "0.0.0",
+                "created_at": 0,
+                "files": files,
+                "includes_tokens": false
+            })
+            .to_string()
+        };
+
+        let v1 = manifest(1, &["keep.json"]);
+        let v2 = manifest(2, &["keep.json"]);
+        let two_files = manifest(1, &["keep.json", "gone.json"]);
+        let escape = manifest(1, &["../escape.json"]);
+        let logs = manifest(1, &["logs/app.log"]);
+        let cases = [
+            write_zip("no-manifest.zip", &[("data/keep.json", "new")]),
+            write_zip(
+                "future.zip",
+                &[("manifest.json", v2.as_str()), ("data/keep.json", "new")],
+            ),
+            write_zip(
+                "unlisted.zip",
+                &[
+                    ("manifest.json", v1.as_str()),
+                    ("data/keep.json", "new"),
+                    ("data/extra.json", "x"),
+                ],
+            ),
+            write_zip(
+                "missing.zip",
+                &[
+                    ("manifest.json", two_files.as_str()),
+                    ("data/keep.json", "new"),
+                ],
+            ),
+            write_zip(
+                "escape.zip",
+                &[
+                    ("manifest.json", escape.as_str()),
+                    ("data/../escape.json", "x"),
+                ],
+            ),
+            write_zip(
+                "unknown.zip",
+                &[
+                    ("manifest.json", v1.as_str()),
+                    ("data/keep.json", "new"),
+                    ("other/x", "x"),
+                ],
+            ),
+            write_zip(
+                "logs.zip",
+                &[("manifest.json", logs.as_str()), ("data/logs/app.log", "x")],
+            ),
+        ];
+        for zip_path in cases {
+            let err = import_from(&root, &zip_path, true, &store).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidInput, "{}", zip_path.display());
+            assert_eq!(fs::read_to_string(root.join("keep.json")).unwrap(), "old");
+        }
+        assert!(!dir.path().join("escape.json").exists());
+        assert!(!root.join(STAGING_DIR).exists());
+    }
+}
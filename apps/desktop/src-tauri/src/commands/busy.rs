@@ -0,0 +1,197 @@
+//! 关闭保护：执行计划、上传或扫描进行中时拦截窗口关闭。
+//!
+//! 忙碌时关闭请求被拦下并发出 `confirm-close` 事件，由前端弹窗确认；用户确认后调用 `force_close`，
+//! 先中止上传（可续传会话按块落盘，重启后可从已确认的位置继续）并把转存日志落盘，再关闭窗口。
+
+use std::sync::Mutex;
+
+use ai_disk_common::CommandError;
+use serde::Serialize;
+use tauri::{AppHandle, CloseRequestApi, Emitter, Manager, State, WebviewWindow, Window};
+
+use super::cloud_upload::UploadState;
+use super::execute::OFFLOAD_JOURNAL;
+use super::storage::get_storage_root;
+
+/// 需要在关闭前确认的任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyKind {
+    Execution,
+    Upload,
+    Scan,
+}
+
+/// 当前进行中的任务数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BusySnapshot {
+    pub executing: usize,
+    pub uploading: usize,
+    pub scanning: usize,
+}
+
+impl BusySnapshot {
+    pub fn is_busy(&self) -> bool {
+        self.executing + self.uploading + self.scanning > 0
+    }
+}
+
+/// 窗口关闭请求的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseDecision {
+    Allow,
+    /// 拦下关闭，请前端确认
+    Confirm(BusySnapshot),
+}
+
+#[derive(Default)]
+struct BusyInner {
+    busy: BusySnapshot,
+    /// 用户已确认强制关闭
+    force_close: bool,
+}
+
+/// 进行中的任务计数与关闭确认状态
+#[derive(Default)]
+pub struct BusyState {
+    inner: Mutex<BusyInner>,
+}
+
+impl BusyState {
+    fn update(&self, kind: BusyKind, f: impl FnOnce(&mut usize)) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let busy = &mut inner.busy;
+        f(match kind {
+            BusyKind::Execution => &mut busy.executing,
+            BusyKind::Upload => &mut busy.uploading,
+            BusyKind::Scan => &mut busy.scanning,
+        });
+    }
+
+    /// 登记一个进行中的任务，返回的守卫 drop 时注销
+    pub fn begin(&self, kind: BusyKind) -> BusyGuard<'_> {
+        self.update(kind, |count| *count += 1);
+        BusyGuard { state: self, kind }
+    }
+
+    pub fn snapshot(&self) -> BusySnapshot {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).busy
+    }
+
+    /// 窗口请求关闭时调用：空闲或用户已确认强制关闭时放行
+    pub fn on_close_requested(&self) -> CloseDecision {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.force_close || !inner.busy.is_busy() {
+            CloseDecision::Allow
+        } else {
+            CloseDecision::Confirm(inner.busy)
+        }
+    }
+
+    fn confirm_force_close(&self) {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .force_close = true;
+    }
+}
+
+/// 进行中任务的登记，drop 时计数减一
+pub struct BusyGuard<'a> {
+    state: &'a BusyState,
+    kind: BusyKind,
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.state
+            .update(self.kind, |count| *count = count.saturating_sub(1));
+    }
+}
+
+/// 当前进行中的执行、上传与扫描数量
+#[tauri::command]
+pub fn get_busy_state(busy: State<'_, BusyState>) -> BusySnapshot {
+    busy.snapshot()
+}
+
+/// 用户确认后强制关闭：中止上传、把转存日志落盘，再关闭窗口
+#[tauri::command]
+pub async fn force_close(
+    app: AppHandle,
+    window: WebviewWindow,
+    busy: State<'_, BusyState>,
+    uploads: State<'_, UploadState>,
+) -> Result<(), CommandError> {
+    busy.confirm_force_close();
+    let cancelled = uploads.cancel(None);
+    tracing::info!(cancelled = cancelled.len(), "force close, uploads aborted");
+    let journal = get_storage_root(&app)?.join(OFFLOAD_JOURNAL);
+    if let Err(e) = ai_disk_executor::sync_journal(&journal) {
+        tracing::warn!(error = %e, "转存日志落盘失败");
+    }
+    window
+        .close()
+        .map_err(|e| CommandError::internal(format!("关闭窗口失败: {}", e)))
+}
+
+/// 窗口关闭请求：忙碌且未确认时拦下并发出 `confirm-close`
+pub(crate) fn handle_close_requested(window: &Window, api: &CloseRequestApi) {
+    if let CloseDecision::Confirm(snapshot) = window.state::<BusyState>().on_close_requested() {
+        api.prevent_close();
+        let _ = window.emit("confirm-close", snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_close_is_allowed() {
+        let busy = BusyState::default();
+        assert_eq!(busy.on_close_requested(), CloseDecision::Allow);
+        {
+            let _scan = busy.begin(BusyKind::Scan);
+        }
+        assert_eq!(busy.snapshot(), BusySnapshot::default());
+        assert_eq!(busy.on_close_requested(), CloseDecision::Allow);
+    }
+
+    #[test]
+    fn test_busy_close_needs_confirmation() {
+        let busy = BusyState::default();
+        let execution = busy.begin(BusyKind::Execution);
+        let upload_a = busy.begin(BusyKind::Upload);
+        let upload_b = busy.begin(BusyKind::Upload);
+        let expected = BusySnapshot {
+            executing: 1,
+            uploading: 2,
+            scanning: 0,
+        };
+        assert_eq!(busy.snapshot(), expected);
+        assert_eq!(busy.on_close_requested(), CloseDecision::Confirm(expected));
+
+        // 任务陆续结束后仍需确认，直到全部结束
+        drop(execution);
+        drop(upload_a);
+        assert!(matches!(
+            busy.on_close_requested(),
+            CloseDecision::Confirm(s) if s.uploading == 1
+        ));
+        drop(upload_b);
+        assert_eq!(busy.on_close_requested(), CloseDecision::Allow);
+    }
+
+    #[test]
+    fn test_force_close_allows_while_busy() {
+        let busy = BusyState::default();
+        let _scan = busy.begin(BusyKind::Scan);
+        assert!(matches!(
+            busy.on_close_requested(),
+            CloseDecision::Confirm(_)
+        ));
+        busy.confirm_force_close();
+        assert_eq!(busy.on_close_requested(), CloseDecision::Allow);
+        assert!(busy.snapshot().is_busy());
+    }
+}
@@ -0,0 +1,440 @@
+//! 汇总所有已连接云存储账号的容量：并发请求各服务商的配额接口并统一成 [`CloudQuota`]。
+//! 每次成功的结果写入存储根目录下的缓存，服务商不可达时返回缓存值并标记 `stale`。
+
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use super::credentials::{connected_accounts, ConnectedAccount};
+use super::errors::{parse_error, request_error, status_error};
+use super::storage::get_storage_root;
+use super::token_manager::TokenManager;
+
+/// 配额缓存文件（位于存储根目录）
+const QUOTA_CACHE_FILE: &str = "cloud_quota_cache.json";
+
+/// 单个账号配额请求的超时时间
+const QUOTA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 统一后的账号容量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudQuota {
+    pub provider: String,
+    pub account_id: String,
+    pub display_name: String,
+    /// 总容量；服务商不限容量（如部分 Google Workspace 账号）时为 None
+    pub total_bytes: Option<u64>,
+    pub used_bytes: u64,
+    /// 数据获取时间（Unix 秒）
+    pub fetched_at: u64,
+    /// 本次请求失败，返回的是上次成功获取的缓存值
+    #[serde(default)]
+    pub stale: bool,
+    /// 本次请求失败的原因，仅 `stale` 时存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 服务商配额响应中解析出的 (总容量, 已用容量)
+type QuotaUsage = (Option<u64>, u64);
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 数字字段：兼容数值与字符串（Google Drive 以字符串返回 int64）
+fn json_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn missing_field(provider: &str, field: &str) -> CommandError {
+    CommandError::internal(format!("{} 配额响应缺少 {}", provider, field))
+}
+
+/// Google Drive `about?fields=storageQuota`：`limit` 缺失表示不限容量
+fn normalize_google(body: &Value) -> Result<QuotaUsage, CommandError> {
+    let quota = &body["storageQuota"];
+    let used = json_u64(&quota["usage"]).ok_or_else(|| missing_field("google_drive", "usage"))?;
+    Ok((json_u64(&quota["limit"]), used))
+}
+
+/// Dropbox `users/get_space_usage`：`allocation` 为 individual / team 联合类型，
+/// team 账号的总容量是团队共享的配额
+fn normalize_dropbox(body: &Value) -> Result<QuotaUsage, CommandError> {
+    let used = json_u64(&body["used"]).ok_or_else(|| missing_field("dropbox", "used"))?;
+    let allocation = &body["allocation"];
+    let total = match allocation[".tag"].as_str() {
+        Some("individual") | Some("team") => json_u64(&allocation["allocated"]),
+        _ => None,
+    };
+    Ok((total, used))
+}
+
+/// 百度网盘 `/api/quota`：`errno` 非 0 表示请求失败
+fn normalize_baidu(body: &Value) -> Result<QuotaUsage, CommandError> {
+    let errno = body["errno"].as_i64().unwrap_or(0);
+    if errno != 0 {
+        return Err(CommandError::internal(format!(
+            "百度网盘配额请求失败 (errno {})",
+            errno
+        )));
+    }
+    let total = json_u64(&body["total"]).ok_or_else(|| missing_field("baidu_netdisk", "total"))?;
+    let used = json_u64(&body["used"]).ok_or_else(|| missing_field("baidu_netdisk", "used"))?;
+    Ok((Some(total), used))
+}
+
+/// 阿里云盘 `user/getSpaceInfo`：容量位于 `personal_space_info`
+fn normalize_aliyun(body: &Value) -> Result<QuotaUsage, CommandError> {
+    let space = &body["personal_space_info"];
+    let total = json_u64(&space["total_size"])
+        .ok_or_else(|| missing_field("aliyun_drive", "total_size"))?;
+    let used =
+        json_u64(&space["used_size"]).ok_or_else(|| missing_field("aliyun_drive", "used_size"))?;
+    Ok((Some(total), used))
+}
+
+/// OneDrive Graph `/me/drive`：容量位于 `quota`
+fn normalize_onedrive(body: &Value) -> Result<QuotaUsage, CommandError> {
+    let quota = &body["quota"];
+    let used = json_u64(&quota["used"]).ok_or_else(|| missing_field("onedrive", "used"))?;
+    Ok((json_u64(&quota["total"]), used))
+}
+
+/// 按服务商解析配额响应
+fn normalize_quota(provider: &str, body: &Value) -> Result<QuotaUsage, CommandError> {
+    match provider {
+        "google_drive" => normalize_google(body),
+        "dropbox" => normalize_dropbox(body),
+        "baidu_netdisk" => normalize_baidu(body),
+        "aliyun_drive" => normalize_aliyun(body),
+        "onedrive" => normalize_onedrive(body),
+        _ => Err(CommandError::internal(format!(
+            "不支持查询 {} 的配额",
+            provider
+        ))),
+    }
+}
+
+/// 请求服务商的配额接口
+async fn request_quota(provider: &str, access_token: &str) -> Result<Value, CommandError> {
+    let client = reqwest::Client::new();
+    let request = match provider {
+        "google_drive" => client
+            .get("https://www.googleapis.com/drive/v3/about?fields=storageQuota")
+            .bearer_auth(access_token),
+        // get_space_usage 不接受请求体，必须以 JSON null 调用
+        "dropbox" => client
+            .post("https://api.dropboxapi.com/2/users/get_space_usage")
+            .bearer_auth(access_token)
+            .header("Content-Type", "application/json")
+            .body("null"),
+        "baidu_netdisk" => client
+            .get("https://pan.baidu.com/api/quota")
+            .query(&[("access_token", access_token), ("checkfree", "1")]),
+        "aliyun_drive" => client
+            .post("https://openapi.alipan.com/adrive/v1.0/user/getSpaceInfo")
+            .bearer_auth(access_token),
+        "onedrive" => client
+            .get("https://graph.microsoft.com/v1.0/me/drive")
+            .bearer_auth(access_token),
+        _ => {
+            return Err(CommandError::internal(format!(
+                "不支持查询 {} 的配额",
+                provider
+            )))
+        }
+    };
+
+    let context = format!("获取 {} 存储配额失败", provider);
+    let response = request
+        .send()
+        .await
+        .map_err(|e| request_error(&context, &e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(status_error(&context, status, &error_text));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| parse_error("解析存储配额失败", e))
+}
+
+/// 上次成功获取的配额，键为 `provider:account_id`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuotaCache {
+    entries: HashMap<String, CloudQuota>,
+}
+
+impl QuotaCache {
+    fn key(provider: &str, account_id: &str) -> String {
+        format!("{}:{}", provider, account_id)
+    }
+
+    /// 缓存损坏或不存在时视为空缓存
+    fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), CommandError> {
+        let text = serde_json::to_vec_pretty(self)
+            .map_err(|e| CommandError::internal(format!("序列化配额缓存失败: {}", e)))?;
+        ai_disk_common::write_atomic(path, &text)
+            .map_err(|e| CommandError::io("写入配额缓存失败", &e))
+    }
+}
+
+/// 并发获取所有账号的配额，每个请求单独限时；失败时回退到缓存值（标记 `stale`），
+/// 没有缓存的账号被跳过。成功的结果写回 `cache`
+async fn collect_quotas<F, Fut>(
+    accounts: &[ConnectedAccount],
+    cache: &mut QuotaCache,
+    timeout: Duration,
+    fetch: F,
+) -> Vec<CloudQuota>
+where
+    F: Fn(ConnectedAccount) -> Fut,
+    Fut: Future<Output = Result<Value, CommandError>>,
+{
+    let results = futures::future::join_all(accounts.iter().map(|account| {
+        let request = fetch(account.clone());
+        async move {
+            match tokio::time::timeout(timeout, request).await {
+                Ok(Ok(body)) => normalize_quota(&account.provider, &body),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(CommandError::new(
+                    ErrorCode::NetworkTimeout,
+                    "获取存储配额超时",
+                )),
+            }
+        }
+    }))
+    .await;
+
+    let mut quotas = Vec::with_capacity(accounts.len());
+    for (account, result) in accounts.iter().zip(results) {
+        let key = QuotaCache::key(&account.provider, &account.account_id);
+        match result {
+            Ok((total_bytes, used_bytes)) => {
+                let quota = CloudQuota {
+                    provider: account.provider.clone(),
+                    account_id: account.account_id.clone(),
+                    display_name: account.name.clone(),
+                    total_bytes,
+                    used_bytes,
+                    fetched_at: now_secs(),
+                    stale: false,
+                    error: None,
+                };
+                cache.entries.insert(key, quota.clone());
+                quotas.push(quota);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    provider = %account.provider,
+                    account_id = %account.account_id,
+                    error = %e,
+                    "获取存储配额失败"
+                );
+                if let Some(cached) = cache.entries.get(&key) {
+                    quotas.push(CloudQuota {
+                        display_name: account.name.clone(),
+                        stale: true,
+                        error: Some(e.message),
+                        ..cached.clone()
+                    });
+                }
+            }
+        }
+    }
+    quotas
+}
+
+/// 获取所有已连接云存储账号的容量
+#[tauri::command]
+pub async fn get_all_cloud_quotas(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+) -> Result<Vec<CloudQuota>, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let accounts = connected_accounts(&storage_root)?;
+    let cache_path = storage_root.join(QUOTA_CACHE_FILE);
+    let mut cache = QuotaCache::load(&cache_path);
+
+    let tokens = tokens.inner();
+    let quotas = collect_quotas(
+        &accounts,
+        &mut cache,
+        QUOTA_TIMEOUT,
+        move |account: ConnectedAccount| async move {
+            let access_token = tokens
+                .get_valid_access_token(&account.provider, &account.account_id)
+                .await?;
+            request_quota(&account.provider, &access_token).await
+        },
+    )
+    .await;
+
+    if quotas.iter().any(|q| !q.stale) {
+        if let Err(e) = cache.save(&cache_path) {
+            tracing::warn!(error = %e, "保存配额缓存失败");
+        }
+    }
+    Ok(quotas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn account(provider: &str, id: &str) -> ConnectedAccount {
+        ConnectedAccount {
+            provider: provider.into(),
+            account_id: id.into(),
+            name: format!("{} 账号", provider),
+        }
+    }
+
+    #[test]
+    fn test_normalizes_provider_responses() {
+        let cases = [
+            (
+                "google_drive",
+                json!({"storageQuota": {"limit": "16106127360", "usage": "5368709120",
+                    "usageInDrive": "4000000000"}}),
+                (Some(16_106_127_360), 5_368_709_120),
+            ),
+            (
+                "google_drive",
+                json!({"storageQuota": {"usage": "42"}}),
+                (None, 42),
+            ),
+            (
+                "dropbox",
+                json!({"used": 314159265, "allocation": {".tag": "individual", "allocated": 2147483648u64}}),
+                (Some(2_147_483_648), 314_159_265),
+            ),
+            (
+                "dropbox",
+                json!({"used": 1000, "allocation": {".tag": "team", "used": 9000,
+                    "allocated": 5497558138880u64, "user_within_team_space_allocated": 0}}),
+                (Some(5_497_558_138_880), 1000),
+            ),
+            (
+                "baidu_netdisk",
+                json!({"errno": 0, "total": 2205465706496u64, "used": 686653888910u64,
+                    "free": 1518811817586u64, "request_id": 1}),
+                (Some(2_205_465_706_496), 686_653_888_910),
+            ),
+            (
+                "aliyun_drive",
+                json!({"personal_space_info": {"used_size": 1024, "total_size": 107374182400u64}}),
+                (Some(107_374_182_400), 1024),
+            ),
+            (
+                "onedrive",
+                json!({"id": "b!x", "quota": {"total": 1099511627776u64, "used": 123,
+                    "remaining": 1099511627653u64, "state": "normal"}}),
+                (Some(1_099_511_627_776), 123),
+            ),
+        ];
+        for (provider, body, expected) in cases {
+            assert_eq!(
+                normalize_quota(provider, &body).unwrap(),
+                expected,
+                "{}",
+                provider
+            );
+        }
+
+        assert!(normalize_quota("baidu_netdisk", &json!({"errno": -6})).is_err());
+        assert!(normalize_quota("dropbox", &json!({"allocation": {}})).is_err());
+        assert!(normalize_quota("webdav", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_failed_fetch_returns_stale_cache() {
+        let accounts = [
+            account("google_drive", "g"),
+            account("dropbox", "d"),
+            account("baidu_netdisk", "b"),
+        ];
+        let mut cache = QuotaCache::default();
+        let first = tauri::async_runtime::block_on(collect_quotas(
+            &accounts,
+            &mut cache,
+            QUOTA_TIMEOUT,
+            |account: ConnectedAccount| {
+                let body = match account.provider.as_str() {
+                    "google_drive" => json!({"storageQuota": {"limit": "100", "usage": "10"}}),
+                    "dropbox" => {
+                        json!({"used": 20, "allocation": {".tag": "individual", "allocated": 200}})
+                    }
+                    _ => json!({"errno": 0, "total": 300, "used": 30}),
+                };
+                async move { Ok(body) }
+            },
+        ));
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|q| !q.stale));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUOTA_CACHE_FILE);
+        cache.save(&path).unwrap();
+        let mut cache = QuotaCache::load(&path);
+
+        // Google 正常，Dropbox 返回错误，百度超时
+        let accounts = [
+            accounts[0].clone(),
+            accounts[1].clone(),
+            accounts[2].clone(),
+            account("aliyun_drive", "never-fetched"),
+        ];
+        let second = tauri::async_runtime::block_on(collect_quotas(
+            &accounts,
+            &mut cache,
+            Duration::from_millis(50),
+            |account: ConnectedAccount| async move {
+                match account.provider.as_str() {
+                    "google_drive" => Ok(json!({"storageQuota": {"limit": "100", "usage": "11"}})),
+                    "baidu_netdisk" => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        Ok(json!({}))
+                    }
+                    _ => Err(CommandError::internal("unreachable")),
+                }
+            },
+        ));
+
+        assert_eq!(second.len(), 3, "没有缓存的账号被跳过");
+        assert_eq!(second[0].used_bytes, 11);
+        assert!(!second[0].stale);
+        assert!(second[1].stale);
+        assert_eq!(second[1].used_bytes, 20);
+        assert_eq!(second[1].total_bytes, Some(200));
+        assert_eq!(second[1].fetched_at, first[1].fetched_at);
+        assert_eq!(second[1].error.as_deref(), Some("unreachable"));
+        assert!(second[2].stale);
+        assert_eq!(second[2].total_bytes, Some(300));
+    }
+}
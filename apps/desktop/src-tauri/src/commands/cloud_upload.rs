@@ -1,27 +1,237 @@
-use futures::future;
+use ai_disk_common::{
+    metrics, rate_per_sec, CommandError, DiskAnalyzerError, ErrorCode, METRIC_UPLOAD_BYTES,
+    METRIC_UPLOAD_BYTES_PER_SEC,
+};
+pub use ai_disk_domain::CloudFileEntry;
+use ai_disk_domain::{BackedUpMatch, PlannedAction};
+use ai_disk_executor::{CloudUploader, RemoteChecksum, UploadedFile};
+use ai_disk_scanner::DisplayPath;
+use futures::future::{self, AbortHandle, Abortable, BoxFuture};
 use log::{debug, error, info, warn};
-use reqwest;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
-use std::path::Path;
-use tauri::{AppHandle, Emitter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::Semaphore;
+
+use super::busy::{BusyKind, BusyState};
+use super::config::ConfigState;
+use super::scan::ScanStore;
+use super::storage::get_storage_root;
+use super::token_manager::TokenManager;
+
+mod aliyun;
+mod baidu;
+pub(crate) mod bandwidth;
+mod dropbox;
+mod google_drive;
+mod integrity;
+mod remote;
+mod session;
+
+use bandwidth::{upload_limiter, ThroughputMeter};
+pub use session::ResumableUpload;
+use session::UploadSessionStore;
+
+/// 可续传上传会话的目录（位于存储根目录下）
+const UPLOAD_SESSIONS_DIR: &str = "upload_sessions";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadConfig {
     pub provider: String,
     pub name: String,
-    pub access_token: String,
+    /// 凭据存储中的账号 ID，token 由 TokenManager 提供
+    pub account_id: String,
     pub target_path: String,
+    /// 上传前由 TokenManager 填充，不从前端接收
+    #[serde(skip)]
+    pub access_token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadResult {
     pub success: bool,
     pub provider: String,
+    /// 对应 UploadConfig.name，区分同一提供商的多个账号
+    pub config_name: String,
     pub file_id: Option<String>,
+    /// 云端路径（服务商以路径寻址时返回，如 Dropbox 的 path_display）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_path: Option<String>,
     pub message: String,
     pub source_deleted: bool,
+    /// 失败时的错误码（如 Unauthorized 表示需要刷新 token）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// 从开始排队到结束的耗时
+    pub elapsed_ms: u64,
+    /// 已确认传输的字节数（失败或取消时为中断前的进度）
+    pub bytes_transferred: u64,
+    /// 与本地文件核对一致的云端校验和
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<RemoteChecksum>,
+}
+
+impl UploadResult {
+    fn failed(config: &UploadConfig, message: String, code: ErrorCode) -> Self {
+        Self {
+            success: false,
+            provider: config.provider.clone(),
+            config_name: config.name.clone(),
+            file_id: None,
+            remote_path: None,
+            message,
+            source_deleted: false,
+            error_code: Some(code),
+            elapsed_ms: 0,
+            bytes_transferred: 0,
+            checksum: None,
+        }
+    }
+
+    fn from_outcome(
+        config: &UploadConfig,
+        result: Result<RemoteFile, CommandError>,
+        elapsed: Duration,
+        bytes_transferred: u64,
+    ) -> Self {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        metrics().increment(METRIC_UPLOAD_BYTES, &config.provider, bytes_transferred);
+        match result {
+            Ok(file) => {
+                if let Some(rate) = rate_per_sec(bytes_transferred, elapsed) {
+                    metrics().observe(METRIC_UPLOAD_BYTES_PER_SEC, &config.provider, rate);
+                }
+                info!(
+                    "成功上传到 {} ({})，文件ID: {}，耗时 {} ms",
+                    config.name, config.provider, file.id, elapsed_ms
+                );
+                Self {
+                    success: true,
+                    provider: config.provider.clone(),
+                    config_name: config.name.clone(),
+                    file_id: Some(file.id),
+                    remote_path: file.path,
+                    message: format!("成功上传到 {}", config.name),
+                    source_deleted: false,
+                    error_code: None,
+                    elapsed_ms,
+                    bytes_transferred,
+                    checksum: file.checksum,
+                }
+            }
+            Err(e) => {
+                error!("上传到 {} ({}) 失败: {}", config.name, config.provider, e);
+                let message = if e.code == ErrorCode::Cancelled {
+                    e.message.clone()
+                } else {
+                    format!("上传失败: {}", e)
+                };
+                Self {
+                    elapsed_ms,
+                    bytes_transferred,
+                    ..Self::failed(config, message, e.code)
+                }
+            }
+        }
+    }
+}
+
+/// 上传成功后云端文件的标识
+#[derive(Debug)]
+pub(crate) struct RemoteFile {
+    pub id: String,
+    pub path: Option<String>,
+    /// 云端记录的文件大小
+    pub size: Option<u64>,
+    /// 云端记录的校验和
+    pub checksum: Option<RemoteChecksum>,
+}
+
+/// 同时进行的上传数上限
+const MAX_CONCURRENT_UPLOADS: usize = 3;
+
+/// 进度回调：`(已上传字节数, 总字节数)`
+type ProgressFn = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// 执行单个配置的上传
+type UploadFn = Arc<
+    dyn Fn(Arc<UploadConfig>, ProgressFn) -> BoxFuture<'static, Result<RemoteFile, CommandError>>
+        + Send
+        + Sync,
+>;
+
+/// 被 `cancel_uploads` 中止的上传
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelledUpload {
+    pub task_id: String,
+    pub provider: String,
+    pub config_name: String,
+}
+
+struct InFlightUpload {
+    task_id: String,
+    provider: String,
+    config_name: String,
+    handle: AbortHandle,
+}
+
+/// 进行中的上传，供 `cancel_uploads` 中止
+#[derive(Default)]
+pub struct UploadState {
+    next_id: AtomicU64,
+    in_flight: Mutex<HashMap<u64, InFlightUpload>>,
+}
+
+impl UploadState {
+    fn register(&self, task_id: &str, config: &UploadConfig, handle: AbortHandle) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                id,
+                InFlightUpload {
+                    task_id: task_id.to_string(),
+                    provider: config.provider.clone(),
+                    config_name: config.name.clone(),
+                    handle,
+                },
+            );
+        id
+    }
+
+    fn finish(&self, id: u64) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id);
+    }
+
+    /// 中止属于 `task_id`（为 None 时为全部）的上传
+    pub fn cancel(&self, task_id: Option<&str>) -> Vec<CancelledUpload> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        let ids: Vec<u64> = in_flight
+            .iter()
+            .filter(|(_, upload)| task_id.is_none() || task_id == Some(upload.task_id.as_str()))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| in_flight.remove(&id))
+            .map(|upload| {
+                upload.handle.abort();
+                CancelledUpload {
+                    task_id: upload.task_id,
+                    provider: upload.provider,
+                    config_name: upload.config_name,
+                }
+            })
+            .collect()
+    }
 }
 
 /// 上传进度事件的数据结构
@@ -29,120 +239,85 @@ pub struct UploadResult {
 pub struct UploadProgressEvent {
     pub task_id: String,
     pub provider: String,
+    pub config_name: String,
     pub progress: u32, // 0-100
     pub uploaded_bytes: u64,
     pub total_bytes: u64,
+    /// 最近几秒的平均上传速率（字节/秒）
+    pub bytes_per_sec: u64,
 }
 
-/// 上传文件到云存储
+/// 上传文件到云存储；各配置并发上传（数量有上限），单个失败或被取消不影响其他配置
 #[tauri::command]
 pub async fn upload_to_cloud(
     app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    uploads: State<'_, UploadState>,
+    busy: State<'_, BusyState>,
     file_path: String,
     configs: Vec<UploadConfig>,
     delete_source: Option<bool>,
     task_id: Option<String>,
-) -> Result<Vec<UploadResult>, String> {
+) -> Result<Vec<UploadResult>, CommandError> {
+    let _busy = busy.begin(BusyKind::Upload);
     info!("开始上传文件到云存储: {}", file_path);
     info!("目标云存储数量: {}", configs.len());
     info!("任务ID: {:?}", task_id);
     debug!("删除源文件选项: {:?}", delete_source);
 
-    let task_id = task_id.unwrap_or_else(|| {
-        format!(
-            "upload_{}",
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_millis())
-                .unwrap_or(0)
-        )
-    });
+    let task_id = task_id.unwrap_or_else(new_task_id);
 
-    // 并行上传到所有配置的云存储
-    let upload_futures: Vec<_> = configs
-        .into_iter()
-        .map(|config| {
-            let file_path_clone = file_path.clone();
-            let app_clone = app.clone();
-            let task_id_clone = task_id.clone();
-            tokio::spawn(async move {
-                info!("开始上传到 {} ({})", config.name, config.provider);
-                let result = match config.provider.as_str() {
-                    "google_drive" => {
-                        upload_to_google_drive_resumable(
-                            &file_path_clone,
-                            &config,
-                            &app_clone,
-                            &task_id_clone,
-                        )
-                        .await
-                    }
-                    _ => Err(format!("不支持的云存储提供商: {}", config.provider)),
-                };
-
-                match &result {
-                    Ok(file_id) => {
-                        info!(
-                            "成功上传到 {} ({})，文件ID: {}",
-                            config.name, config.provider, file_id
-                        );
-                    }
-                    Err(e) => {
-                        error!("上传到 {} ({}) 失败: {}", config.name, config.provider, e);
-                    }
-                }
-
-                let upload_result = match result {
-                    Ok(file_id) => UploadResult {
-                        success: true,
-                        provider: config.provider.clone(),
-                        file_id: Some(file_id),
-                        message: format!("成功上传到 {}", config.name),
-                        source_deleted: false,
-                    },
-                    Err(e) => UploadResult {
-                        success: false,
-                        provider: config.provider.clone(),
-                        file_id: None,
-                        message: format!("上传失败: {}", e),
-                        source_deleted: false,
-                    },
-                };
-
-                (config.name.clone(), upload_result)
-            })
-        })
-        .collect();
-
-    // 等待所有上传任务完成
-    let upload_results: Vec<_> = future::join_all(upload_futures).await;
+    // 可续传会话保存在存储根目录下，上传中断后重试可从已提交的位置继续
+    let sessions = Arc::new(UploadSessionStore::new(
+        get_storage_root(&app)?.join(UPLOAD_SESSIONS_DIR),
+    ));
 
+    // 上传前统一取有效 token（临近过期时自动刷新）；取不到的配置直接记为失败
+    let mut ready_configs = Vec::new();
     let mut results = Vec::new();
-    let mut all_success = true;
-
-    for result in upload_results {
-        match result {
-            Ok((_name, upload_result)) => {
-                if !upload_result.success {
-                    all_success = false;
-                }
-                results.push(upload_result);
+    for mut config in configs {
+        match tokens
+            .get_valid_access_token(&config.provider, &config.account_id)
+            .await
+        {
+            Ok(access_token) => {
+                config.access_token = access_token;
+                ready_configs.push(config);
             }
             Err(e) => {
-                error!("上传任务执行失败: {:?}", e);
-                all_success = false;
-                // 创建一个失败的结果
-                results.push(UploadResult {
-                    success: false,
-                    provider: "unknown".to_string(),
-                    file_id: None,
-                    message: format!("任务执行失败: {:?}", e),
-                    source_deleted: false,
-                });
+                error!(
+                    "获取 {} ({}) 的 token 失败: {}",
+                    config.name, config.provider, e
+                );
+                results.push(UploadResult::failed(
+                    &config,
+                    format!("获取授权失败: {}", e),
+                    e.code,
+                ));
             }
         }
     }
 
+    results.extend(
+        upload_all(
+            ready_configs,
+            &uploads,
+            &task_id,
+            MAX_CONCURRENT_UPLOADS,
+            provider_uploader(file_path.clone(), sessions),
+            |config: &UploadConfig| -> ProgressFn {
+                Arc::new(progress_emitter(
+                    app.clone(),
+                    task_id.clone(),
+                    config.provider.clone(),
+                    config.name.clone(),
+                ))
+            },
+        )
+        .await,
+    );
+    let all_success = results.iter().all(|r| r.success);
+
     // 如果所有上传都成功且需要删除源文件
     if all_success && delete_source.unwrap_or(false) {
         info!("所有上传成功，准备删除源文件: {}", file_path);
@@ -189,322 +364,840 @@ pub async fn upload_to_cloud(
     Ok(results)
 }
 
-/// 使用 Resumable Upload API 上传文件到 Google Drive（支持进度回调）
-async fn upload_to_google_drive_resumable(
-    file_path: &str,
-    config: &UploadConfig,
-    app: &AppHandle,
-    task_id: &str,
-) -> Result<String, String> {
-    let path = Path::new(file_path);
+/// 之前中断、可以续传的上传（含应用重启前的），新的在前；超过服务商有效期的会话在此清理
+#[tauri::command]
+pub fn list_resumable_uploads(app: AppHandle) -> Result<Vec<ResumableUpload>, CommandError> {
+    let sessions = UploadSessionStore::new(get_storage_root(&app)?.join(UPLOAD_SESSIONS_DIR));
+    Ok(sessions.list(session_max_age_ms))
+}
+
+/// 续传 `list_resumable_uploads` 列出的上传：确认本地文件的大小和修改时间未变后，
+/// 按会话记录的账号与目标位置重新发起上传，服务商从已提交的偏移量继续。
+/// 文件已变化、会话已过期时返回错误；进度事件与 `upload_to_cloud` 相同
+#[tauri::command]
+pub async fn resume_upload(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    uploads: State<'_, UploadState>,
+    busy: State<'_, BusyState>,
+    upload_id: String,
+    task_id: Option<String>,
+) -> Result<UploadResult, CommandError> {
+    let _busy = busy.begin(BusyKind::Upload);
+    let sessions = Arc::new(UploadSessionStore::new(
+        get_storage_root(&app)?.join(UPLOAD_SESSIONS_DIR),
+    ));
+    let session = sessions.resumable(&upload_id, session_max_age_ms)?;
+    info!(
+        "续传 {} 到 {} ({})，已提交 {} / {} 字节",
+        session.file_path,
+        session.origin.config_name,
+        session.origin.provider,
+        session.committed,
+        session.file.size
+    );
+
+    let origin = session.origin;
+    let access_token = tokens
+        .get_valid_access_token(&origin.provider, &origin.account_id)
+        .await?;
+    let config = UploadConfig {
+        provider: origin.provider,
+        name: origin.config_name,
+        account_id: origin.account_id,
+        target_path: origin.target_path,
+        access_token,
+    };
+    let task_id = task_id.unwrap_or_else(new_task_id);
+    upload_all(
+        vec![config],
+        &uploads,
+        &task_id,
+        1,
+        provider_uploader(session.file_path, sessions),
+        |config: &UploadConfig| -> ProgressFn {
+            Arc::new(progress_emitter(
+                app.clone(),
+                task_id.clone(),
+                config.provider.clone(),
+                config.name.clone(),
+            ))
+        },
+    )
+    .await
+    .pop()
+    .ok_or_else(|| CommandError::internal("上传任务没有返回结果"))
+}
+
+/// 设置所有上传共享的带宽上限（字节/秒，0 表示不限），对进行中的上传立即生效并写入配置
+#[tauri::command]
+pub fn set_upload_bandwidth_limit(
+    app: AppHandle,
+    config_state: State<'_, ConfigState>,
+    bytes_per_sec: u64,
+) -> Result<(), CommandError> {
+    let mut config = config_state.get();
+    config.upload.max_bytes_per_sec = bytes_per_sec;
+    config_state.replace(&app, config)?;
+    info!("上传带宽上限: {} 字节/秒", upload_limiter().limit());
+    Ok(())
+}
+
+/// 中止进行中的上传：指定 `task_id` 时只中止该任务，否则中止全部；返回被中止的上传
+#[tauri::command]
+pub async fn cancel_uploads(
+    uploads: State<'_, UploadState>,
+    task_id: Option<String>,
+) -> Result<Vec<CancelledUpload>, CommandError> {
+    let cancelled = uploads.cancel(task_id.as_deref());
+    info!("已中止 {} 个上传", cancelled.len());
+    Ok(cancelled)
+}
 
-    debug!("准备上传文件到 Google Drive (Resumable): {}", file_path);
-    debug!("目标路径: {}", config.target_path);
+fn new_task_id() -> String {
+    format!(
+        "upload_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0)
+    )
+}
 
-    // 检查文件是否存在
-    if !path.exists() {
-        error!("文件不存在: {}", file_path);
-        return Err(format!("文件不存在: {}", file_path));
+/// 各服务商上传会话的有效期；不支持续传的服务商为 0
+fn session_max_age_ms(provider: &str) -> u64 {
+    match provider {
+        "google_drive" => google_drive::SESSION_MAX_AGE_MS,
+        "dropbox" => dropbox::SESSION_MAX_AGE_MS,
+        "baidu_netdisk" => baidu::SESSION_MAX_AGE_MS,
+        "aliyun_drive" => aliyun::SESSION_MAX_AGE_MS,
+        _ => 0,
     }
+}
 
-    // 获取文件大小
-    let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
-    info!(
-        "文件大小: {} 字节 ({:.2} MB)",
-        file_size,
-        file_size as f64 / 1024.0 / 1024.0
-    );
+/// 按提供商分派到具体的上传实现；上传的同时计算本地校验和，完成后与云端报告的校验和比对
+fn provider_uploader(file_path: String, sessions: Arc<UploadSessionStore>) -> UploadFn {
+    Arc::new(
+        move |config: Arc<UploadConfig>, on_progress: ProgressFn| -> BoxFuture<'static, _> {
+            let file_path = file_path.clone();
+            let sessions = sessions.clone();
+            Box::pin(async move {
+                let local = integrity::checksum_algorithm(&config.provider).map(|algorithm| {
+                    tokio::spawn(integrity::local_checksum(
+                        PathBuf::from(&file_path),
+                        algorithm,
+                    ))
+                });
+                let file =
+                    upload_with_provider(&file_path, &config, &sessions, on_progress.as_ref())
+                        .await?;
+                let Some(local) = local else {
+                    return Ok(file);
+                };
+                let local = local
+                    .await
+                    .map_err(|e| CommandError::internal(format!("计算本地校验和失败: {}", e)))??;
+                integrity::check_integrity(file, &local, |remote_id| async move {
+                    remote::delete_remote(&config.provider, &config.access_token, &remote_id).await
+                })
+                .await
+            })
+        },
+    )
+}
 
-    // 获取文件名
-    let file_name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| "无法获取文件名".to_string())?;
+async fn upload_with_provider(
+    file_path: &str,
+    config: &UploadConfig,
+    sessions: &UploadSessionStore,
+    on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+) -> Result<RemoteFile, CommandError> {
+    match config.provider.as_str() {
+        "google_drive" => {
+            google_drive::upload_to_google_drive(file_path, config, sessions, on_progress).await
+        }
+        "aliyun_drive" => aliyun::upload_to_aliyun(file_path, config, sessions, on_progress).await,
+        "baidu_netdisk" => baidu::upload_to_baidu(file_path, config, sessions, on_progress).await,
+        "dropbox" => dropbox::upload_to_dropbox(file_path, config, sessions, on_progress).await,
+        _ => Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("不支持的云存储提供商: {}", config.provider),
+        )),
+    }
+}
 
-    info!("文件名: {}", file_name);
+/// `verify_remote_file` 的核对结果
+#[derive(Debug, Serialize)]
+pub struct IntegrityReport {
+    pub provider: String,
+    pub remote_id: String,
+    pub local_size: u64,
+    pub remote_size: Option<u64>,
+    /// 云端报告的校验和；服务商未提供时为 None
+    pub remote_checksum: Option<RemoteChecksum>,
+    /// 按同一算法计算的本地校验和
+    pub local_checksum: Option<RemoteChecksum>,
+    /// 大小和校验和都与本地一致
+    pub verified: bool,
+    pub message: String,
+}
 
-    // 第一步：获取或创建目标文件夹
-    debug!("获取或创建目标文件夹: {}", config.target_path);
-    let folder_id = if config.target_path == "/" {
-        debug!("使用根目录");
-        "root".to_string()
-    } else {
-        create_or_get_folder(&config.access_token, &config.target_path).await?
+impl IntegrityReport {
+    fn new(
+        provider: String,
+        remote: RemoteFile,
+        local_size: u64,
+        local_checksum: Option<RemoteChecksum>,
+    ) -> Self {
+        let size_ok = remote.size == Some(local_size);
+        let checksum_ok = match (&remote.checksum, &local_checksum) {
+            (Some(remote), Some(local)) => Some(remote.matches(local)),
+            _ => None,
+        };
+        let message = match (size_ok, checksum_ok) {
+            (false, _) => format!(
+                "大小不一致（本地 {} 字节，云端 {:?}）",
+                local_size, remote.size
+            ),
+            (true, Some(true)) => "大小和校验和均一致".to_string(),
+            (true, Some(false)) => "校验和不一致".to_string(),
+            (true, None) => "大小一致，但云端未提供校验和".to_string(),
+        };
+        Self {
+            provider,
+            remote_id: remote.id,
+            local_size,
+            remote_size: remote.size,
+            remote_checksum: remote.checksum,
+            local_checksum,
+            verified: size_ok && checksum_ok == Some(true),
+            message,
+        }
+    }
+}
+
+/// 事后核对云端文件与本地文件是否一致（大小和服务商提供的校验和）
+#[tauri::command]
+pub async fn verify_remote_file(
+    tokens: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+    remote_id: String,
+    local_path: String,
+) -> Result<IntegrityReport, CommandError> {
+    let access_token = tokens
+        .get_valid_access_token(&provider, &account_id)
+        .await?;
+    let remote = remote::remote_file(&provider, &access_token, &remote_id).await?;
+    let local_size = fs::metadata(&local_path)
+        .map_err(|e| CommandError::io("读取本地文件信息失败", &e))?
+        .len();
+    let local_checksum = match &remote.checksum {
+        Some(checksum) => {
+            Some(integrity::local_checksum(PathBuf::from(&local_path), checksum.algorithm()).await?)
+        }
+        None => None,
     };
-    info!("目标文件夹ID: {}", folder_id);
+    let report = IntegrityReport::new(provider, remote, local_size, local_checksum);
+    info!("核对云端文件 {}: {}", report.remote_id, report.message);
+    Ok(report)
+}
 
-    // 发送初始进度 0%
-    let _ = app.emit(
-        "upload-progress",
-        UploadProgressEvent {
-            task_id: task_id.to_string(),
-            provider: config.provider.clone(),
-            progress: 0,
-            uploaded_bytes: 0,
-            total_bytes: file_size,
-        },
+/// 一页列表结果
+#[derive(Debug, Serialize)]
+pub struct CloudFilePage {
+    pub entries: Vec<CloudFileEntry>,
+    /// 还有下一页时传给 `list_cloud_files` 的 `page_token`
+    pub next_page_token: Option<String>,
+}
+
+/// 每页请求的条目数
+const LIST_PAGE_SIZE: u32 = 100;
+
+/// 列出云端 `path` 目录下的文件；`page_token` 为上一页返回的 `next_page_token`
+#[tauri::command]
+pub async fn list_cloud_files(
+    tokens: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+    path: String,
+    page_token: Option<String>,
+) -> Result<CloudFilePage, CommandError> {
+    let access_token = tokens
+        .get_valid_access_token(&provider, &account_id)
+        .await?;
+    let page = remote::list_files(&provider, &access_token, &path, page_token.as_deref()).await?;
+    debug!(
+        "列出 {} 的 {}: {} 项，有下一页: {}",
+        provider,
+        path,
+        page.entries.len(),
+        page.next_page_token.is_some()
     );
+    Ok(page)
+}
+
+/// 删除云端文件（不经过服务商的回收站）
+#[tauri::command]
+pub async fn delete_cloud_file(
+    tokens: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+    file_id: String,
+) -> Result<(), CommandError> {
+    let access_token = tokens
+        .get_valid_access_token(&provider, &account_id)
+        .await?;
+    remote::delete_remote(&provider, &access_token, &file_id).await?;
+    info!("已删除 {} 上的文件 {}", provider, file_id);
+    Ok(())
+}
+
+/// 建立备份索引时最多收录的云端文件数，避免对很大的网盘无休止地翻页
+const BACKUP_INDEX_MAX_ENTRIES: usize = 20_000;
+
+/// 已备份文件的查找结果
+#[derive(Debug, Serialize)]
+pub struct BackedUpFiles {
+    /// 索引中的云端文件数
+    pub indexed: usize,
+    /// 云端文件超过上限，索引不完整，可能漏掉部分匹配
+    pub truncated: bool,
+    pub matches: Vec<BackedUpMatch>,
+    /// 低风险的删除建议，与 `matches` 一一对应
+    pub actions: Vec<PlannedAction>,
+}
 
-    // 第二步：初始化 Resumable Upload Session
-    debug!("初始化 Resumable Upload Session");
-    let client = reqwest::Client::new();
-
-    let metadata = serde_json::json!({
-        "name": file_name,
-        "parents": [folder_id]
-    });
-
-    let init_response = client
-        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable")
-        .header("Authorization", format!("Bearer {}", config.access_token))
-        .header("Content-Type", "application/json; charset=UTF-8")
-        .header("X-Upload-Content-Type", "application/octet-stream")
-        .header("X-Upload-Content-Length", file_size.to_string())
-        .json(&metadata)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("初始化上传会话失败: {}", e);
-            format!("初始化上传会话失败: {}", e)
-        })?;
-
-    if !init_response.status().is_success() {
-        let error_text = init_response.text().await.unwrap_or_default();
-        error!("初始化上传会话失败: {}", error_text);
-        return Err(format!("初始化上传会话失败: {}", error_text));
+/// 递归列出云端 `path` 下的所有文件（不含文件夹本身），返回文件列表与是否因达到上限而截断
+async fn build_remote_index(
+    provider: &str,
+    access_token: &str,
+    path: &str,
+) -> Result<(Vec<CloudFileEntry>, bool), CommandError> {
+    let mut files = Vec::new();
+    let mut folders = vec![path.to_string()];
+    while let Some(folder) = folders.pop() {
+        let mut page_token = None;
+        loop {
+            let page =
+                remote::list_files(provider, access_token, &folder, page_token.as_deref()).await?;
+            for entry in page.entries {
+                if entry.is_dir {
+                    folders.push(format!("{}/{}", folder.trim_end_matches('/'), entry.name));
+                } else {
+                    files.push(entry);
+                }
+            }
+            if files.len() >= BACKUP_INDEX_MAX_ENTRIES {
+                files.truncate(BACKUP_INDEX_MAX_ENTRIES);
+                return Ok((files, true));
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
     }
+    Ok((files, false))
+}
 
-    // 获取上传 URI
-    let upload_uri = init_response
-        .headers()
-        .get("location")
-        .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| {
-            error!("响应中没有上传 URI");
-            "响应中没有上传 URI".to_string()
-        })?
-        .to_string();
-
-    info!("获取到上传 URI: {}", upload_uri);
-
-    // 第三步：分块上传文件
-    let chunk_size: u64 = 5 * 1024 * 1024; // 5MB 每块
-    let mut uploaded: u64 = 0;
-
-    let mut file = std::fs::File::open(path).map_err(|e| {
-        error!("打开文件失败: {}", e);
-        format!("打开文件失败: {}", e)
+/// 对照云端 `path` 下的文件，找出扫描结果中已经备份过的本地文件并生成删除建议。
+/// `verify` 时逐个比对云端校验和与本地文件内容，不一致或无法读取的匹配会被丢弃；
+/// 服务商没有报告校验和的匹配保留，但不标记为已校验。删除建议带有目标在这次扫描中的状态
+#[tauri::command]
+pub async fn find_backed_up_files(
+    tokens: State<'_, TokenManager>,
+    scan_store: State<'_, ScanStore>,
+    provider: String,
+    account_id: String,
+    path: String,
+    scan_id: String,
+    verify: bool,
+) -> Result<BackedUpFiles, CommandError> {
+    let scan = scan_store.get(&scan_id).ok_or_else(|| {
+        CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("扫描结果不存在或已过期: {}", scan_id),
+        )
     })?;
+    let access_token = tokens
+        .get_valid_access_token(&provider, &account_id)
+        .await?;
+    let (index, truncated) = build_remote_index(&provider, &access_token, &path).await?;
+    let mut matches = ai_disk_engine::find_backed_up_files(&scan, &index);
 
-    let mut last_progress: u32 = 0;
+    if verify {
+        let mut verified = Vec::with_capacity(matches.len());
+        for mut backed_up in matches {
+            let remote =
+                remote::remote_file(&provider, &access_token, &backed_up.remote.id).await?;
+            if let Some(checksum) = remote.checksum {
+                let local_path = DisplayPath::new(&backed_up.local_path).to_os_path();
+                match integrity::local_checksum(local_path, checksum.algorithm()).await {
+                    Ok(local) if checksum.matches(&local) => backed_up.verified = true,
+                    Ok(_) => {
+                        info!("{} 与云端副本内容不一致，不建议删除", backed_up.local_path);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("校验 {} 失败: {}", backed_up.local_path, e.message);
+                        continue;
+                    }
+                }
+            }
+            verified.push(backed_up);
+        }
+        matches = verified;
+    }
 
-    while uploaded < file_size {
-        let remaining = file_size - uploaded;
-        let current_chunk_size = std::cmp::min(chunk_size, remaining);
+    let mut actions = ai_disk_engine::plan_backed_up_cleanup(&matches);
+    ai_disk_engine::record_target_states(&mut actions, &scan);
+    info!(
+        "对照 {} 的 {}（{} 个文件{}）找到 {} 个已备份文件",
+        provider,
+        path,
+        index.len(),
+        if truncated { "，已截断" } else { "" },
+        matches.len()
+    );
+    Ok(BackedUpFiles {
+        indexed: index.len(),
+        truncated,
+        matches,
+        actions,
+    })
+}
 
-        // 读取当前块
-        let mut buffer = vec![0u8; current_chunk_size as usize];
-        file.read_exact(&mut buffer).map_err(|e| {
-            error!("读取文件块失败: {}", e);
-            format!("读取文件块失败: {}", e)
-        })?;
+/// 解析 RFC 3339 时间（如 `2024-01-02T03:04:05.123Z`、`2024-01-02T11:04:05+08:00`）为 Unix 秒
+fn parse_rfc3339(text: &str) -> Option<u64> {
+    let (date, time) = text.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
 
-        let start_byte = uploaded;
-        let end_byte = uploaded + current_chunk_size - 1;
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let (clock, zone) = time.split_at(i);
+        let (hours, minutes) = zone[1..].split_once(':')?;
+        let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (
+            clock,
+            if zone.starts_with('-') {
+                -offset
+            } else {
+                offset
+            },
+        )
+    } else {
+        (time, 0)
+    };
+    let mut clock = clock
+        .split('.')
+        .next()?
+        .splitn(3, ':')
+        .map(str::parse::<i64>);
+    let (hour, minute, second) = (
+        clock.next()?.ok()?,
+        clock.next()?.ok()?,
+        clock.next()?.ok()?,
+    );
 
-        debug!("上传块: bytes {}-{}/{}", start_byte, end_byte, file_size);
+    // 公历日期到 1970-01-01 的天数（Howard Hinnant 的 days_from_civil）
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    u64::try_from(days * 86_400 + hour * 3600 + minute * 60 + second - offset).ok()
+}
 
-        let response = client
-            .put(&upload_uri)
-            .header("Content-Length", current_chunk_size.to_string())
-            .header(
-                "Content-Range",
-                format!("bytes {}-{}/{}", start_byte, end_byte, file_size),
-            )
-            .body(buffer)
-            .send()
-            .await
-            .map_err(|e| {
-                error!("上传块失败: {}", e);
-                format!("上传块失败: {}", e)
-            })?;
-
-        let status = response.status();
-
-        // 308 Resume Incomplete 表示还需要继续上传
-        // 200 或 201 表示上传完成
-        if status == reqwest::StatusCode::OK || status == reqwest::StatusCode::CREATED {
-            // 上传完成
-            info!("上传完成!");
-
-            // 发送 100% 进度
-            let _ = app.emit(
-                "upload-progress",
-                UploadProgressEvent {
-                    task_id: task_id.to_string(),
-                    provider: config.provider.clone(),
-                    progress: 100,
-                    uploaded_bytes: file_size,
-                    total_bytes: file_size,
-                },
-            );
+/// 供执行器的 Offload 动作使用的上传实现：取 token 后走与 `upload_to_cloud` 相同的分派
+pub(crate) struct PlanUploader<'a> {
+    tokens: &'a TokenManager,
+    sessions: Arc<UploadSessionStore>,
+}
 
-            // 解析响应获取文件 ID
-            let result: serde_json::Value = response.json().await.map_err(|e| {
-                error!("解析响应失败: {}", e);
-                format!("解析响应失败: {}", e)
-            })?;
-
-            let file_id = result["id"]
-                .as_str()
-                .ok_or_else(|| {
-                    error!("响应中没有文件 ID，响应内容: {:?}", result);
-                    "响应中没有文件 ID".to_string()
-                })?
-                .to_string();
-
-            info!("上传成功，文件ID: {}", file_id);
-            return Ok(file_id);
-        } else if status == reqwest::StatusCode::PERMANENT_REDIRECT || status.as_u16() == 308 {
-            // 308 Resume Incomplete - 继续上传
-            uploaded += current_chunk_size;
-
-            // 计算并发送进度
-            let progress = ((uploaded as f64 / file_size as f64) * 100.0) as u32;
-            if progress > last_progress {
-                last_progress = progress;
-                info!("上传进度: {}% ({}/{} bytes)", progress, uploaded, file_size);
-
-                let _ = app.emit(
-                    "upload-progress",
-                    UploadProgressEvent {
-                        task_id: task_id.to_string(),
-                        provider: config.provider.clone(),
-                        progress,
-                        uploaded_bytes: uploaded,
-                        total_bytes: file_size,
-                    },
-                );
-            }
-        } else {
-            // 其他状态码表示错误
-            let error_text = response.text().await.unwrap_or_default();
-            error!("上传块失败，状态码: {}，错误: {}", status, error_text);
-            return Err(format!("上传失败 ({}): {}", status, error_text));
+impl<'a> PlanUploader<'a> {
+    pub fn new(tokens: &'a TokenManager, storage_root: PathBuf) -> Self {
+        Self {
+            tokens,
+            sessions: Arc::new(UploadSessionStore::new(
+                storage_root.join(UPLOAD_SESSIONS_DIR),
+            )),
         }
     }
+}
 
-    Err("上传异常结束".to_string())
+impl CloudUploader for PlanUploader<'_> {
+    async fn upload(
+        &self,
+        path: &str,
+        provider: &str,
+        account_id: &str,
+        target_path: &str,
+    ) -> Result<UploadedFile, DiskAnalyzerError> {
+        let access_token = self
+            .tokens
+            .get_valid_access_token(provider, account_id)
+            .await
+            .map_err(|e| DiskAnalyzerError::Upload(format!("获取授权失败: {}", e)))?;
+        let config = Arc::new(UploadConfig {
+            provider: provider.to_string(),
+            name: provider.to_string(),
+            account_id: account_id.to_string(),
+            target_path: target_path.to_string(),
+            access_token,
+        });
+        let upload = provider_uploader(path.to_string(), self.sessions.clone());
+        let file = upload(config, Arc::new(|_, _| {}))
+            .await
+            .map_err(|e| DiskAnalyzerError::Upload(e.to_string()))?;
+        Ok(UploadedFile {
+            remote_id: file.id,
+            size: file.size,
+            checksum: file.checksum,
+        })
+    }
 }
 
-/// 创建或获取文件夹
-async fn create_or_get_folder(access_token: &str, path: &str) -> Result<String, String> {
-    debug!("创建或获取文件夹: {}", path);
-    let client = reqwest::Client::new();
+/// 以有界并发上传到所有配置，结果顺序与 `configs` 一致；
+/// 上传期间登记到 `uploads`，被中止的配置记为 Cancelled，不影响其他配置
+async fn upload_all(
+    configs: Vec<UploadConfig>,
+    uploads: &UploadState,
+    task_id: &str,
+    max_concurrent: usize,
+    upload: UploadFn,
+    progress_for: impl Fn(&UploadConfig) -> ProgressFn,
+) -> Vec<UploadResult> {
+    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let mut registrations = Vec::new();
+    let mut labels = Vec::new();
 
-    // 分割路径
-    let parts: Vec<&str> = path
-        .trim_matches('/')
-        .split('/')
-        .filter(|p| !p.is_empty())
+    let tasks: Vec<_> = configs
+        .into_iter()
+        .map(|config| {
+            let config = Arc::new(config);
+            let (handle, registration) = AbortHandle::new_pair();
+            registrations.push(uploads.register(task_id, &config, handle));
+            labels.push((config.provider.clone(), config.name.clone()));
+
+            // 记录最近一次进度，作为该配置已传输的字节数
+            let transferred = Arc::new(AtomicU64::new(0));
+            let emit = progress_for(config.as_ref());
+            let on_progress: ProgressFn = {
+                let transferred = transferred.clone();
+                Arc::new(move |sent, total| {
+                    transferred.store(sent, Ordering::Relaxed);
+                    emit(sent, total);
+                })
+            };
+            let work = upload(config.clone(), on_progress);
+            let semaphore = semaphore.clone();
+
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let queued = async {
+                    let _permit = semaphore.acquire_owned().await;
+                    info!("开始上传到 {} ({})", config.name, config.provider);
+                    work.await
+                };
+                let result = Abortable::new(queued, registration)
+                    .await
+                    .unwrap_or_else(|_| Err(CommandError::new(ErrorCode::Cancelled, "上传已取消")));
+                UploadResult::from_outcome(
+                    &config,
+                    result,
+                    started.elapsed(),
+                    transferred.load(Ordering::Relaxed),
+                )
+            })
+        })
         .collect();
 
-    debug!("路径分割为 {} 个部分: {:?}", parts.len(), parts);
+    let joined = future::join_all(tasks).await;
+    for id in registrations {
+        uploads.finish(id);
+    }
 
-    let mut parent_id = "root".to_string();
+    joined
+        .into_iter()
+        .zip(labels)
+        .map(|(joined, (provider, config_name))| {
+            joined.unwrap_or_else(|e| {
+                error!("上传任务执行失败: {:?}", e);
+                UploadResult {
+                    success: false,
+                    provider,
+                    config_name,
+                    file_id: None,
+                    remote_path: None,
+                    message: format!("任务执行失败: {:?}", e),
+                    source_deleted: false,
+                    error_code: Some(ErrorCode::Internal),
+                    elapsed_ms: 0,
+                    bytes_transferred: 0,
+                    checksum: None,
+                }
+            })
+        })
+        .collect()
+}
 
-    // 逐级创建或查找文件夹
-    for folder_name in parts {
-        debug!("处理文件夹: {}，父文件夹ID: {}", folder_name, parent_id);
-        // 查找是否已存在
-        debug!("查询文件夹是否存在: {}", folder_name);
-        let query = format!(
-            "name='{}' and '{}' in parents and mimeType='application/vnd.google-apps.folder' and trashed=false",
-            folder_name, parent_id
+/// 生成进度回调：每块确认后发送 `upload-progress` 事件
+fn progress_emitter(
+    app: AppHandle,
+    task_id: String,
+    provider: String,
+    config_name: String,
+) -> impl Fn(u64, u64) + Send + Sync {
+    let meter = ThroughputMeter::new();
+    move |uploaded_bytes, total_bytes| {
+        let bytes_per_sec = meter.record(uploaded_bytes);
+        let progress = if total_bytes == 0 {
+            100
+        } else {
+            (uploaded_bytes.saturating_mul(100) / total_bytes) as u32
+        };
+        debug!(
+            "{} 上传进度: {}% ({}/{} bytes)",
+            config_name, progress, uploaded_bytes, total_bytes
         );
-
-        let search_url = format!(
-            "https://www.googleapis.com/drive/v3/files?q={}&fields=files(id)",
-            urlencoding::encode(&query)
+        let _ = app.emit(
+            "upload-progress",
+            UploadProgressEvent {
+                task_id: task_id.clone(),
+                provider: provider.clone(),
+                config_name: config_name.clone(),
+                progress,
+                uploaded_bytes,
+                total_bytes,
+                bytes_per_sec,
+            },
         );
+    }
+}
 
-        let response = client
-            .get(&search_url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await
-            .map_err(|e| {
-                error!("查询文件夹失败: {}", e);
-                format!("查询文件夹失败: {}", e)
-            })?;
-
-        if !response.status().is_success() {
-            error!("查询文件夹失败，状态码: {}", response.status());
-            return Err(format!("查询文件夹失败: {}", response.status()));
-        }
+/// 单个请求（如一个分块）遇到瞬时错误时的最大尝试次数
+const MAX_ATTEMPTS: u32 = 3;
 
-        let result: serde_json::Value = response.json().await.map_err(|e| {
-            error!("解析查询响应失败: {}", e);
-            format!("解析查询响应失败: {}", e)
-        })?;
-
-        // 如果找到了，使用现有的
-        if let Some(files) = result["files"].as_array() {
-            if !files.is_empty() {
-                parent_id = files[0]["id"]
-                    .as_str()
-                    .ok_or_else(|| {
-                        error!("无效的文件夹 ID");
-                        "无效的文件夹 ID".to_string()
-                    })?
-                    .to_string();
-                debug!("找到现有文件夹，ID: {}", parent_id);
-                continue;
+/// 网络失败、超时、429 和 5xx 视为瞬时错误，可以原样重试
+fn is_transient(e: &CommandError) -> bool {
+    if !matches!(e.code, ErrorCode::Network | ErrorCode::NetworkTimeout) {
+        return false;
+    }
+    match e.details.as_ref().and_then(|d| d["status"].as_u64()) {
+        Some(status) => status == 429 || status >= 500,
+        None => true,
+    }
+}
+
+/// 执行 `op`，遇到瞬时错误按线性退避重试，最多 `MAX_ATTEMPTS` 次
+async fn with_retries<T, F, Fut>(
+    what: &str,
+    backoff: std::time::Duration,
+    mut op: F,
+) -> Result<T, CommandError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, CommandError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e) => {
+                warn!("{}失败（第 {} 次），稍后重试: {}", what, attempt, e);
+                tokio::time::sleep(backoff * attempt).await;
+                attempt += 1;
             }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: &str, name: &str) -> UploadConfig {
+        UploadConfig {
+            provider: provider.to_string(),
+            name: name.to_string(),
+            account_id: "acc".to_string(),
+            target_path: "/".to_string(),
+            access_token: String::new(),
         }
+    }
+
+    /// 模拟上传：按配置名决定耗时，期间报告一次进度
+    fn delayed_uploader(delays: HashMap<&'static str, u64>) -> UploadFn {
+        Arc::new(
+            move |config: Arc<UploadConfig>, on_progress: ProgressFn| -> BoxFuture<'static, _> {
+                let delay = delays[config.name.as_str()];
+                Box::pin(async move {
+                    on_progress(50, 100);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    on_progress(100, 100);
+                    Ok(RemoteFile {
+                        id: format!("{}-file", config.name),
+                        path: None,
+                        size: None,
+                        checksum: None,
+                    })
+                })
+            },
+        )
+    }
 
-        // 没找到，创建新文件夹
-        debug!("文件夹不存在，创建新文件夹: {}", folder_name);
-        let metadata = serde_json::json!({
-            "name": folder_name,
-            "mimeType": "application/vnd.google-apps.folder",
-            "parents": [parent_id]
+    fn no_progress(_: &UploadConfig) -> ProgressFn {
+        Arc::new(|_: u64, _: u64| {})
+    }
+
+    #[test]
+    fn test_uploads_run_concurrently() {
+        let uploads = UploadState::default();
+        let uploader = delayed_uploader(HashMap::from([("drive", 300), ("box", 500)]));
+        let started = Instant::now();
+
+        let results = tauri::async_runtime::block_on(upload_all(
+            vec![config("google_drive", "drive"), config("dropbox", "box")],
+            &uploads,
+            "task-1",
+            MAX_CONCURRENT_UPLOADS,
+            uploader,
+            no_progress,
+        ));
+        let elapsed = started.elapsed();
+
+        // 总耗时接近最慢的一个，而不是两者之和
+        assert!(elapsed >= Duration::from_millis(500));
+        assert!(elapsed < Duration::from_millis(750), "耗时 {:?}", elapsed);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].config_name, "drive");
+        assert_eq!(results[1].file_id.as_deref(), Some("box-file"));
+        assert!(results
+            .iter()
+            .all(|r| r.success && r.bytes_transferred == 100));
+        assert!(results[1].elapsed_ms >= 500);
+        assert!(uploads.cancel(None).is_empty());
+    }
+
+    #[test]
+    fn test_concurrency_is_bounded() {
+        let uploads = UploadState::default();
+        let uploader = delayed_uploader(HashMap::from([("a", 200), ("b", 200), ("c", 200)]));
+        let started = Instant::now();
+
+        let results = tauri::async_runtime::block_on(upload_all(
+            vec![
+                config("dropbox", "a"),
+                config("dropbox", "b"),
+                config("dropbox", "c"),
+            ],
+            &uploads,
+            "task-1",
+            2,
+            uploader,
+            no_progress,
+        ));
+        assert!(results.iter().all(|r| r.success));
+        // 上限为 2 时第三个需要排队
+        assert!(started.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_cancel_aborts_in_flight_and_keeps_completed() {
+        let uploads = UploadState::default();
+        let uploader = delayed_uploader(HashMap::from([("fast", 50), ("slow", 5_000)]));
+        let started = Instant::now();
+
+        let (results, cancelled) = tauri::async_runtime::block_on(async {
+            futures::join!(
+                upload_all(
+                    vec![config("google_drive", "fast"), config("dropbox", "slow")],
+                    &uploads,
+                    "task-1",
+                    MAX_CONCURRENT_UPLOADS,
+                    uploader,
+                    no_progress,
+                ),
+                async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    uploads.cancel(None)
+                }
+            )
         });
 
-        let response = client
-            .post("https://www.googleapis.com/drive/v3/files")
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json")
-            .json(&metadata)
-            .send()
-            .await
-            .map_err(|e| {
-                error!("创建文件夹请求失败: {}", e);
-                format!("创建文件夹失败: {}", e)
-            })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            error!("创建文件夹失败，状态码: {}，错误: {}", status, error_text);
-            return Err(format!("创建文件夹失败: {}", error_text));
-        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(results[0].success);
+        assert!(!results[1].success);
+        assert_eq!(results[1].error_code, Some(ErrorCode::Cancelled));
+        assert_eq!(results[1].bytes_transferred, 50);
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].config_name, "slow");
+        assert_eq!(cancelled[0].task_id, "task-1");
+    }
+
+    #[test]
+    fn test_cancel_filters_by_task() {
+        let uploads = UploadState::default();
+        let (a, _) = AbortHandle::new_pair();
+        let (b, _) = AbortHandle::new_pair();
+        uploads.register("task-1", &config("dropbox", "a"), a);
+        uploads.register("task-2", &config("dropbox", "b"), b);
 
-        let result: serde_json::Value = response.json().await.map_err(|e| {
-            error!("解析创建响应失败: {}", e);
-            format!("解析创建响应失败: {}", e)
-        })?;
-
-        parent_id = result["id"]
-            .as_str()
-            .ok_or_else(|| {
-                error!("创建的文件夹没有 ID，响应: {:?}", result);
-                "创建的文件夹没有 ID".to_string()
-            })?
-            .to_string();
-        info!("成功创建文件夹: {}，ID: {}", folder_name, parent_id);
+        let cancelled = uploads.cancel(Some("task-2"));
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].config_name, "b");
+        assert_eq!(uploads.cancel(None).len(), 1);
     }
 
-    info!("文件夹路径处理完成，最终文件夹ID: {}", parent_id);
-    Ok(parent_id)
+    #[test]
+    fn test_integrity_report() {
+        let remote = |size, checksum: &str| RemoteFile {
+            id: "f1".into(),
+            path: None,
+            size,
+            checksum: Some(RemoteChecksum::Md5(checksum.into())),
+        };
+        let local = || Some(RemoteChecksum::Md5("abc".into()));
+
+        let report =
+            IntegrityReport::new("google_drive".into(), remote(Some(3), "ABC"), 3, local());
+        assert!(report.verified);
+        let report =
+            IntegrityReport::new("google_drive".into(), remote(Some(3), "def"), 3, local());
+        assert!(!report.verified);
+        assert_eq!(report.message, "校验和不一致");
+        let report =
+            IntegrityReport::new("google_drive".into(), remote(Some(4), "abc"), 3, local());
+        assert!(!report.verified);
+        let report = IntegrityReport::new(
+            "baidu_netdisk".into(),
+            RemoteFile {
+                checksum: None,
+                ..remote(Some(3), "")
+            },
+            3,
+            None,
+        );
+        assert!(!report.verified);
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-01-02T03:04:05Z"), Some(1_704_164_645));
+        assert_eq!(
+            parse_rfc3339("2024-01-02T03:04:05.123Z"),
+            Some(1_704_164_645)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-01-02T11:04:05+08:00"),
+            Some(1_704_164_645)
+        );
+        assert_eq!(parse_rfc3339("2000-02-29T23:59:59Z"), Some(951_868_799));
+        assert_eq!(parse_rfc3339("not a date"), None);
+    }
 }
@@ -0,0 +1,963 @@
+//! 阿里云盘上传：create 获取分片上传地址 → 逐个 PUT 分片 → complete。
+//! 创建时先提交 pre_hash，服务端提示可能已有相同内容时再提交完整 content_hash 尝试秒传，命中则跳过数据传输。
+//! upload_id、file_id 与已上传的偏移量持久化到存储根目录，中断后重试为剩余分片重新申请上传地址并继续。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::RemoteChecksum;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{debug, error, info, warn};
+use md5::Md5;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use super::bandwidth::upload_limiter;
+use super::session::{FileIdentity, SessionSlot, UploadSession, UploadSessionStore};
+use super::{
+    parse_rfc3339, with_retries, CloudFileEntry, CloudFilePage, RemoteFile, UploadConfig,
+    LIST_PAGE_SIZE,
+};
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+const ALIYUN_API_URL: &str = "https://openapi.alipan.com";
+
+/// 默认分片大小
+const PART_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 单个文件的分片数上限
+const MAX_PARTS: u64 = 10_000;
+
+/// pre_hash 取文件开头的字节数
+const PRE_HASH_BYTES: u64 = 1024;
+
+/// 同一分片的上传地址最多重新申请的次数
+const MAX_URL_REFRESHES: u32 = 2;
+
+/// upload_id 的有效期没有公开说明，按一天保守估计（分片上传地址本身约一小时过期，续传时重新申请）
+pub(super) const SESSION_MAX_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// PUT 分片的结果
+enum PartOutcome {
+    Uploaded,
+    /// 预签名地址已过期，需要重新申请
+    UrlExpired,
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+    Sha1::digest(data)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect()
+}
+
+/// 接口返回的错误码，如 `PreHashMatched`
+fn api_code(e: &CommandError) -> Option<&str> {
+    e.details.as_ref()?["code"].as_str()
+}
+
+/// 秒传所需的 proof_code（v1）：以 access token 的 MD5 决定偏移，取文件该位置的最多 8 个字节
+fn proof_code(access_token: &str, file: &mut File, total: u64) -> Result<String, CommandError> {
+    if total == 0 {
+        return Ok(String::new());
+    }
+    let digest: String = Md5::digest(access_token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let seed = u64::from_str_radix(&digest[..16], 16)
+        .map_err(|e| CommandError::internal(format!("计算 proof_code 失败: {}", e)))?;
+    let offset = seed % total;
+    let bytes = read_range(file, offset, 8u64.min(total - offset))?;
+    Ok(STANDARD.encode(bytes))
+}
+
+fn read_range(file: &mut File, start: u64, len: u64) -> Result<Vec<u8>, CommandError> {
+    let mut buffer = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut buffer))
+        .map_err(|e| CommandError::io("读取文件分片失败", &e))?;
+    Ok(buffer)
+}
+
+/// 整个文件的 SHA1（大写十六进制），流式计算
+fn content_hash(file: &mut File) -> Result<String, CommandError> {
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| CommandError::io("读取文件失败", &e))?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| CommandError::io("读取文件失败", &e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect())
+}
+
+/// 把 complete / openFile/get 返回的文件信息转成 `RemoteFile`；content_hash 为 SHA1
+fn aliyun_remote(file: &Value) -> Result<RemoteFile, CommandError> {
+    let id = file["file_id"]
+        .as_str()
+        .ok_or_else(|| CommandError::internal("响应中没有 file_id"))?
+        .to_string();
+    let is_sha1 = file["content_hash_name"]
+        .as_str()
+        .unwrap_or("sha1")
+        .eq_ignore_ascii_case("sha1");
+    let checksum = file["content_hash"]
+        .as_str()
+        .filter(|_| is_sha1)
+        .map(|hash| RemoteChecksum::Sha1(hash.to_string()));
+    Ok(RemoteFile {
+        id,
+        path: None,
+        size: file["size"].as_u64(),
+        checksum,
+    })
+}
+
+/// create / getUploadUrl 返回的 `part_number -> upload_url`
+fn upload_urls(response: &Value) -> HashMap<u64, String> {
+    response["part_info_list"]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| {
+                    Some((
+                        p["part_number"].as_u64()?,
+                        p["upload_url"].as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) struct AliyunUploader<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    api_url: String,
+    part_size: u64,
+    retry_backoff: Duration,
+}
+
+impl<'a> AliyunUploader<'a> {
+    pub fn new(access_token: &'a str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            api_url: ALIYUN_API_URL.to_string(),
+            part_size: PART_SIZE,
+            retry_backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// 调用开放平台接口；错误响应中的 `code` 保留在 details 里供调用方判断
+    async fn post(&self, endpoint: &str, body: &Value) -> Result<Value, CommandError> {
+        let context = format!("阿里云盘 {} 失败", endpoint);
+        let response = self
+            .client
+            .post(format!("{}{}", self.api_url, endpoint))
+            .bearer_auth(self.access_token)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| request_error(&context, &e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            let error_body: Value = serde_json::from_str(&text).unwrap_or_default();
+            let code = error_body["code"].as_str().unwrap_or_default();
+            let message = error_body["message"].as_str().unwrap_or(&text);
+            return Err(
+                status_error(&context, status, &format!("{} {}", code, message))
+                    .with_details(json!({ "status": status.as_u16(), "code": code })),
+            );
+        }
+        serde_json::from_str(&text).map_err(|e| parse_error(&context, e))
+    }
+
+    pub async fn default_drive_id(&self) -> Result<String, CommandError> {
+        let info = self
+            .post("/adrive/v1.0/user/getDriveInfo", &json!({}))
+            .await?;
+        info["default_drive_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| CommandError::internal("响应中没有 default_drive_id"))
+    }
+
+    pub async fn get_file(
+        &self,
+        drive_id: &str,
+        file_id: &str,
+    ) -> Result<RemoteFile, CommandError> {
+        let file = self
+            .post(
+                "/adrive/v1.0/openFile/get",
+                &json!({ "drive_id": drive_id, "file_id": file_id }),
+            )
+            .await?;
+        aliyun_remote(&file)
+    }
+
+    /// 永久删除文件（不进入回收站）
+    pub async fn delete_file(&self, drive_id: &str, file_id: &str) -> Result<(), CommandError> {
+        self.post(
+            "/adrive/v1.0/openFile/delete",
+            &json!({ "drive_id": drive_id, "file_id": file_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// 列出 `path` 目录下的一页文件；`page_token` 是上一页返回的 next_marker
+    pub async fn list(
+        &self,
+        path: &str,
+        page_token: Option<&str>,
+    ) -> Result<CloudFilePage, CommandError> {
+        let drive_id = self.default_drive_id().await?;
+        let dir = path.trim_matches('/');
+        let parent_id = if dir.is_empty() {
+            "root".to_string()
+        } else {
+            let folder = self
+                .post(
+                    "/adrive/v1.0/openFile/get_by_path",
+                    &json!({ "drive_id": drive_id, "file_path": format!("/{}", dir) }),
+                )
+                .await?;
+            folder["file_id"]
+                .as_str()
+                .ok_or_else(|| CommandError::internal("响应中没有 file_id"))?
+                .to_string()
+        };
+
+        let mut request = json!({
+            "drive_id": drive_id,
+            "parent_file_id": parent_id,
+            "limit": LIST_PAGE_SIZE,
+            "order_by": "name"
+        });
+        if let Some(marker) = page_token {
+            request["marker"] = json!(marker);
+        }
+        let result = self.post("/adrive/v1.0/openFile/list", &request).await?;
+        let entries = result["items"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        Some(CloudFileEntry {
+                            id: item["file_id"].as_str()?.to_string(),
+                            name: item["name"].as_str()?.to_string(),
+                            size: item["size"].as_u64().unwrap_or(0),
+                            modified: item["updated_at"].as_str().and_then(parse_rfc3339),
+                            is_dir: item["type"] == "folder",
+                            provider: "aliyun_drive".to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(CloudFilePage {
+            entries,
+            next_page_token: result["next_marker"]
+                .as_str()
+                .filter(|marker| !marker.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    /// 逐级查找或创建目标文件夹（同名时复用已有文件夹），返回最后一级的 file_id
+    pub async fn ensure_folder(
+        &self,
+        drive_id: &str,
+        target_path: &str,
+    ) -> Result<String, CommandError> {
+        let mut parent_id = "root".to_string();
+        for name in target_path.split('/').filter(|p| !p.is_empty()) {
+            let folder = self
+                .post(
+                    "/adrive/v1.0/openFile/create",
+                    &json!({
+                        "drive_id": drive_id,
+                        "parent_file_id": parent_id,
+                        "name": name,
+                        "type": "folder",
+                        "check_name_mode": "refuse"
+                    }),
+                )
+                .await?;
+            parent_id = folder["file_id"]
+                .as_str()
+                .ok_or_else(|| CommandError::internal("创建的文件夹没有 file_id"))?
+                .to_string();
+            debug!("文件夹 {} 的 ID: {}", name, parent_id);
+        }
+        Ok(parent_id)
+    }
+
+    /// 分片大小：默认 10 MB，文件过大时放大以满足分片数上限
+    fn part_size_for(&self, total: u64) -> u64 {
+        self.part_size.max(total.div_ceil(MAX_PARTS))
+    }
+
+    async fn put_part(&self, url: &str, data: &[u8]) -> Result<PartOutcome, CommandError> {
+        upload_limiter().acquire(data.len() as u64).await;
+        let response = self
+            .client
+            .put(url)
+            .body(data.to_vec())
+            .send()
+            .await
+            .map_err(|e| request_error("上传分片失败", &e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(PartOutcome::Uploaded);
+        }
+        let text = response.text().await.unwrap_or_default();
+        match status.as_u16() {
+            403 if text.to_ascii_lowercase().contains("expired") => Ok(PartOutcome::UrlExpired),
+            // 上次请求已送达但响应丢失
+            409 if text.contains("PartAlreadyExist") => Ok(PartOutcome::Uploaded),
+            _ => Err(status_error("上传分片失败", status, &text)),
+        }
+    }
+
+    /// 为 `parts` 重新申请上传地址
+    async fn refresh_urls(
+        &self,
+        drive_id: &str,
+        file_id: &str,
+        upload_id: &str,
+        parts: std::ops::RangeInclusive<u64>,
+    ) -> Result<HashMap<u64, String>, CommandError> {
+        let part_info_list: Vec<Value> = parts.map(|n| json!({ "part_number": n })).collect();
+        let response = self
+            .post(
+                "/adrive/v1.0/openFile/getUploadUrl",
+                &json!({
+                    "drive_id": drive_id,
+                    "file_id": file_id,
+                    "upload_id": upload_id,
+                    "part_info_list": part_info_list
+                }),
+            )
+            .await?;
+        Ok(upload_urls(&response))
+    }
+
+    /// 创建文件并取得分片上传地址：先提交 pre_hash，命中时再提交完整哈希尝试秒传
+    async fn create(
+        &self,
+        file: &mut File,
+        drive_id: &str,
+        parent_id: &str,
+        name: &str,
+        total: u64,
+        part_count: u64,
+    ) -> Result<Value, CommandError> {
+        let part_info_list: Vec<Value> = (1..=part_count)
+            .map(|n| json!({ "part_number": n }))
+            .collect();
+        let mut request = json!({
+            "drive_id": drive_id,
+            "parent_file_id": parent_id,
+            "name": name,
+            "type": "file",
+            "check_name_mode": "auto_rename",
+            "size": total,
+            "part_info_list": part_info_list
+        });
+        request["pre_hash"] = json!(sha1_hex(&read_range(file, 0, PRE_HASH_BYTES.min(total))?));
+
+        let first_attempt = self.post("/adrive/v1.0/openFile/create", &request).await;
+        match first_attempt {
+            // 开头 1KB 与已有文件一致，提交完整哈希尝试秒传
+            Err(e) if api_code(&e) == Some("PreHashMatched") => {
+                info!("pre_hash 命中，尝试秒传: {}", name);
+                let obj = request.as_object_mut().expect("请求体是 JSON 对象");
+                obj.remove("pre_hash");
+                obj.insert("content_hash".into(), json!(content_hash(file)?));
+                obj.insert("content_hash_name".into(), json!("sha1"));
+                obj.insert(
+                    "proof_code".into(),
+                    json!(proof_code(self.access_token, file, total)?),
+                );
+                obj.insert("proof_version".into(), json!("v1"));
+                self.post("/adrive/v1.0/openFile/create", &request).await
+            }
+            result => result,
+        }
+    }
+
+    /// 续传保存的会话：为剩余分片重新申请上传地址。会话已失效时删除并返回 None
+    async fn resume_session(
+        &self,
+        drive_id: &str,
+        saved: UploadSession,
+        parts: std::ops::RangeInclusive<u64>,
+        sessions: &UploadSessionStore,
+        slot: &SessionSlot,
+    ) -> Option<(UploadSession, HashMap<u64, String>)> {
+        let file_id = saved.remote_id.as_deref()?;
+        if parts.is_empty() {
+            return Some((saved, HashMap::new()));
+        }
+        match self
+            .refresh_urls(drive_id, file_id, &saved.session, parts)
+            .await
+        {
+            Ok(urls) => Some((saved, urls)),
+            Err(e) => {
+                warn!("上传会话已失效，重新开始上传: {}", e);
+                sessions.remove(&slot.key);
+                None
+            }
+        }
+    }
+
+    /// 上传单个文件到 `parent_id`（重名时自动改名）；会话键相同的中断上传会被续传。
+    /// `on_progress(bytes_sent, total)` 在每个分片完成后调用。
+    /// 命中秒传时返回的结果中 `rapid_upload` 为 true
+    pub async fn upload_file(
+        &self,
+        path: &Path,
+        drive_id: &str,
+        parent_id: &str,
+        sessions: &UploadSessionStore,
+        slot: &SessionSlot,
+        on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<Value, CommandError> {
+        let identity = FileIdentity::of(path)?;
+        let total = identity.size;
+        let mut file = File::open(path).map_err(|e| CommandError::io("打开文件失败", &e))?;
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| CommandError::new(ErrorCode::InvalidInput, "无法获取文件名"))?;
+        let part_size = self.part_size_for(total);
+        let part_count = total.div_ceil(part_size).max(1);
+
+        // 会话的 committed 是已上传分片的末尾偏移量，分片按序上传
+        let resumed = match sessions.load(&slot.key, &identity, SESSION_MAX_AGE_MS) {
+            Some(saved) => {
+                let first = saved.committed / part_size + 1;
+                self.resume_session(drive_id, saved, first..=part_count, sessions, slot)
+                    .await
+            }
+            None => None,
+        };
+        let (mut session, mut urls) = match resumed {
+            Some((session, urls)) => {
+                info!("从 {} / {} 字节处续传: {}", session.committed, total, name);
+                (session, urls)
+            }
+            None => {
+                let created = self
+                    .create(&mut file, drive_id, parent_id, name, total, part_count)
+                    .await?;
+                if created["rapid_upload"].as_bool() == Some(true) {
+                    info!("秒传成功，跳过数据传输: {}", name);
+                    on_progress(total, total);
+                    return Ok(created);
+                }
+                let file_id = created["file_id"]
+                    .as_str()
+                    .ok_or_else(|| CommandError::internal("create 响应中没有 file_id"))?;
+                let upload_id = created["upload_id"]
+                    .as_str()
+                    .ok_or_else(|| CommandError::internal("create 响应中没有 upload_id"))?;
+                let mut session = UploadSessionStore::new_session(
+                    &slot.origin,
+                    &path.to_string_lossy(),
+                    identity,
+                    upload_id.to_string(),
+                );
+                session.remote_id = Some(file_id.to_string());
+                (session, upload_urls(&created))
+            }
+        };
+        sessions.save(&slot.key, &session)?;
+        let file_id = session.remote_id.clone().unwrap_or_default();
+        let upload_id = session.session.clone();
+        let (file_id, upload_id) = (file_id.as_str(), upload_id.as_str());
+
+        let first = session.committed / part_size + 1;
+        let mut sent = session.committed;
+        on_progress(sent, total);
+        for part in first..=part_count {
+            let start = (part - 1) * part_size;
+            let data = read_range(&mut file, start, part_size.min(total - start))?;
+            let mut refreshes = 0;
+            loop {
+                let url = urls.get(&part).cloned().ok_or_else(|| {
+                    CommandError::internal(format!("缺少第 {} 个分片的上传地址", part))
+                })?;
+                let (url, data) = (url.as_str(), data.as_slice());
+                let outcome = with_retries("上传分片", self.retry_backoff, move || {
+                    self.put_part(url, data)
+                })
+                .await?;
+                match outcome {
+                    PartOutcome::Uploaded => break,
+                    PartOutcome::UrlExpired if refreshes < MAX_URL_REFRESHES => {
+                        warn!("第 {} 个分片的上传地址已过期，重新申请", part);
+                        refreshes += 1;
+                        urls.extend(
+                            self.refresh_urls(drive_id, file_id, upload_id, part..=part_count)
+                                .await?,
+                        );
+                    }
+                    PartOutcome::UrlExpired => {
+                        return Err(CommandError::new(
+                            ErrorCode::Network,
+                            format!("第 {} 个分片的上传地址反复过期，请重试", part),
+                        ));
+                    }
+                }
+            }
+            sent += data.len() as u64;
+            session.committed = sent;
+            sessions.save(&slot.key, &session)?;
+            on_progress(sent, total);
+        }
+
+        let completed = self
+            .post(
+                "/adrive/v1.0/openFile/complete",
+                &json!({ "drive_id": drive_id, "file_id": file_id, "upload_id": upload_id }),
+            )
+            .await?;
+        if let Some(size) = completed["size"].as_u64().filter(|&size| size != total) {
+            error!("上传后大小不一致: 本地 {}，远端 {}", total, size);
+            return Err(CommandError::internal(format!(
+                "上传后文件大小不一致（本地 {} 字节，远端 {} 字节）",
+                total, size
+            )));
+        }
+        sessions.remove(&slot.key);
+        Ok(completed)
+    }
+}
+
+/// 上传文件到阿里云盘的 `config.target_path` 下，返回 file_id
+pub(crate) async fn upload_to_aliyun(
+    file_path: &str,
+    config: &UploadConfig,
+    sessions: &UploadSessionStore,
+    on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+) -> Result<RemoteFile, CommandError> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        error!("文件不存在: {}", file_path);
+        return Err(CommandError::new(
+            ErrorCode::PathNotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+
+    let uploader = AliyunUploader::new(&config.access_token);
+    let drive_id = uploader.default_drive_id().await?;
+    let parent_id = uploader
+        .ensure_folder(&drive_id, &config.target_path)
+        .await?;
+    let slot = SessionSlot::new(config, file_path, &parent_id);
+    let uploaded = uploader
+        .upload_file(path, &drive_id, &parent_id, sessions, &slot, on_progress)
+        .await?;
+
+    let mut file = aliyun_remote(&uploaded)?;
+    // 秒传的响应不含大小和 content_hash，再查询一次文件信息
+    if file.checksum.is_none() || file.size.is_none() {
+        file = uploader.get_file(&drive_id, &file.id).await?;
+    }
+    info!("上传成功，文件ID: {}", file.id);
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// 模拟阿里云盘开放接口和分片存储的服务端状态
+    #[derive(Default)]
+    struct MockAliyun {
+        /// 文件夹创建请求的 (parent_file_id, name)
+        folders: Vec<(String, String)>,
+        /// 文件 create 请求体
+        creates: Vec<Value>,
+        /// 按请求顺序记录的 PUT 分片序号
+        puts: Vec<u64>,
+        parts: HashMap<u64, Vec<u8>>,
+        /// getUploadUrl 请求的分片序号
+        url_refreshes: Vec<Vec<u64>>,
+        completed: bool,
+        /// 这些分片第一次拿到的地址已过期
+        expire_once: Vec<u64>,
+        /// 服务端已有相同内容（秒传）
+        rapid: bool,
+        url_generation: u64,
+        list_requests: Vec<Value>,
+    }
+
+    impl MockAliyun {
+        /// 按分片序号拼接的文件内容
+        fn content(&self) -> Vec<u8> {
+            let mut parts: Vec<_> = self.parts.iter().collect();
+            parts.sort_by_key(|(n, _)| **n);
+            parts
+                .into_iter()
+                .flat_map(|(_, data)| data.clone())
+                .collect()
+        }
+    }
+
+    fn part_list(base: &str, generation: u64, parts: impl Iterator<Item = u64>) -> Vec<Value> {
+        parts
+            .map(|n| {
+                json!({
+                    "part_number": n,
+                    "upload_url": format!("{}/part/{}?v={}", base, n, generation)
+                })
+            })
+            .collect()
+    }
+
+    fn start_mock_aliyun(state: Arc<Mutex<MockAliyun>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let base_clone = base.clone();
+        std::thread::spawn(move || {
+            let base = base_clone;
+            for mut request in server.incoming_requests() {
+                let mut body = Vec::new();
+                let _ = request.as_reader().read_to_end(&mut body);
+                let url = request.url().to_string();
+                let mut s = state.lock().unwrap();
+
+                let (status, reply) = if let Some(rest) = url.strip_prefix("/part/") {
+                    let (part, generation) = rest.split_once("?v=").unwrap();
+                    let part: u64 = part.parse().unwrap();
+                    s.puts.push(part);
+                    if generation == "0" && s.expire_once.contains(&part) {
+                        (
+                            403,
+                            "<Error><Code>AccessDenied</Code><Message>Request has expired.</Message></Error>".to_string(),
+                        )
+                    } else {
+                        s.parts.insert(part, body);
+                        (200, String::new())
+                    }
+                } else {
+                    let req: Value = serde_json::from_slice(&body).unwrap_or_default();
+                    let reply = match url.as_str() {
+                        "/adrive/v1.0/user/getDriveInfo" => Ok(json!({ "default_drive_id": "d1" })),
+                        "/adrive/v1.0/openFile/create" if req["type"] == "folder" => {
+                            assert_eq!(req["check_name_mode"], "refuse");
+                            let name = req["name"].as_str().unwrap().to_string();
+                            s.folders.push((
+                                req["parent_file_id"].as_str().unwrap().to_string(),
+                                name.clone(),
+                            ));
+                            Ok(json!({ "file_id": format!("folder-{}", name) }))
+                        }
+                        "/adrive/v1.0/openFile/create" => {
+                            s.creates.push(req.clone());
+                            if s.rapid && req.get("content_hash").is_none() {
+                                Err(
+                                    json!({ "code": "PreHashMatched", "message": "Pre hash matched." }),
+                                )
+                            } else if s.rapid {
+                                Ok(
+                                    json!({ "file_id": "f1", "upload_id": "u1", "rapid_upload": true }),
+                                )
+                            } else {
+                                let count = req["part_info_list"].as_array().unwrap().len() as u64;
+                                Ok(json!({
+                                    "file_id": "f1",
+                                    "upload_id": "u1",
+                                    "rapid_upload": false,
+                                    "part_info_list": part_list(&base, 0, 1..=count)
+                                }))
+                            }
+                        }
+                        "/adrive/v1.0/openFile/get_by_path" => {
+                            assert_eq!(req["file_path"], "/备份");
+                            Ok(json!({ "file_id": "folder-备份" }))
+                        }
+                        "/adrive/v1.0/openFile/list" => {
+                            s.list_requests.push(req.clone());
+                            if req.get("marker").is_none() {
+                                Ok(json!({
+                                    "items": [{
+                                        "file_id": "f1",
+                                        "name": "a.mp4",
+                                        "type": "file",
+                                        "size": 4096,
+                                        "updated_at": "2024-01-02T03:04:05.367Z"
+                                    }],
+                                    "next_marker": "m2"
+                                }))
+                            } else {
+                                Ok(json!({
+                                    "items": [{ "file_id": "d1", "name": "照片", "type": "folder" }],
+                                    "next_marker": ""
+                                }))
+                            }
+                        }
+                        "/adrive/v1.0/openFile/getUploadUrl" => {
+                            s.url_generation += 1;
+                            let parts: Vec<u64> = req["part_info_list"]
+                                .as_array()
+                                .unwrap()
+                                .iter()
+                                .map(|p| p["part_number"].as_u64().unwrap())
+                                .collect();
+                            s.url_refreshes.push(parts.clone());
+                            Ok(json!({
+                                "part_info_list": part_list(&base, s.url_generation, parts.into_iter())
+                            }))
+                        }
+                        "/adrive/v1.0/openFile/complete" => {
+                            s.completed = true;
+                            let data = s.content();
+                            Ok(json!({
+                                "file_id": "f1",
+                                "size": data.len(),
+                                "content_hash": sha1_hex(&data),
+                                "content_hash_name": "sha1"
+                            }))
+                        }
+                        "/adrive/v1.0/openFile/get" => {
+                            // 秒传命中时服务端已有的内容
+                            Ok(json!({
+                                "file_id": req["file_id"],
+                                "size": 10,
+                                "content_hash": "87ACEC17CD9DCD20A716CC2CF67417B71C8A7016",
+                                "content_hash_name": "sha1"
+                            }))
+                        }
+                        _ => Err(json!({ "code": "NotFound" })),
+                    };
+                    match reply {
+                        Ok(v) => (200, v.to_string()),
+                        Err(v) => (409, v.to_string()),
+                    }
+                };
+                let _ = request
+                    .respond(tiny_http::Response::from_string(reply).with_status_code(status));
+            }
+        });
+        base
+    }
+
+    fn test_uploader(base: &str) -> AliyunUploader<'static> {
+        AliyunUploader {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base.to_string(),
+            part_size: 4,
+            retry_backoff: Duration::ZERO,
+        }
+    }
+
+    fn sample_file(dir: &Path) -> std::path::PathBuf {
+        let path = dir.join("data.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_part_upload_refreshes_expired_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = sample_file(dir.path());
+        let state = Arc::new(Mutex::new(MockAliyun {
+            expire_once: vec![2],
+            ..Default::default()
+        }));
+        let base = start_mock_aliyun(state.clone());
+        let uploader = test_uploader(&base);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let progress = Mutex::new(Vec::new());
+
+        let uploaded = tauri::async_runtime::block_on(async {
+            let drive_id = uploader.default_drive_id().await.unwrap();
+            let parent = uploader
+                .ensure_folder(&drive_id, "/备份/2024/")
+                .await
+                .unwrap();
+            assert_eq!(parent, "folder-2024");
+            uploader
+                .upload_file(
+                    &path,
+                    &drive_id,
+                    &parent,
+                    &sessions,
+                    &SessionSlot::for_test("key"),
+                    &|sent, _| progress.lock().unwrap().push(sent),
+                )
+                .await
+        })
+        .unwrap();
+        assert_eq!(uploaded["file_id"], "f1");
+        let remote = aliyun_remote(&uploaded).unwrap();
+        assert_eq!(remote.size, Some(10));
+        assert_eq!(
+            remote.checksum,
+            Some(RemoteChecksum::Sha1(
+                "87ACEC17CD9DCD20A716CC2CF67417B71C8A7016".into()
+            ))
+        );
+
+        let s = state.lock().unwrap();
+        assert_eq!(
+            s.folders,
+            vec![
+                ("root".to_string(), "备份".to_string()),
+                ("folder-备份".to_string(), "2024".to_string()),
+            ]
+        );
+        assert_eq!(s.creates.len(), 1);
+        assert_eq!(s.creates[0]["parent_file_id"], "folder-2024");
+        assert_eq!(
+            s.creates[0]["pre_hash"],
+            "87ACEC17CD9DCD20A716CC2CF67417B71C8A7016"
+        );
+        assert_eq!(s.creates[0]["part_info_list"].as_array().unwrap().len(), 3);
+        // 第 2 片地址过期后为剩余分片重新申请地址并续传
+        assert_eq!(s.puts, vec![1, 2, 2, 3]);
+        assert_eq!(s.url_refreshes, vec![vec![2, 3]]);
+        assert_eq!(s.parts[&1], b"0123");
+        assert_eq!(s.parts[&2], b"4567");
+        assert_eq!(s.parts[&3], b"89");
+        assert!(s.completed);
+        assert_eq!(*progress.lock().unwrap(), vec![0, 4, 8, 10]);
+        let identity = FileIdentity::of(&path).unwrap();
+        assert!(sessions
+            .load("key", &identity, SESSION_MAX_AGE_MS)
+            .is_none());
+    }
+
+    #[test]
+    fn test_resume_requests_urls_for_remaining_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = sample_file(dir.path());
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        // 上次上传完第 1 片后中断
+        let mut saved = UploadSessionStore::new_session(
+            &SessionSlot::for_test("key").origin,
+            &path.to_string_lossy(),
+            FileIdentity::of(&path).unwrap(),
+            "u1".to_string(),
+        );
+        saved.remote_id = Some("f1".to_string());
+        saved.committed = 4;
+        sessions.save("key", &saved).unwrap();
+        let state = Arc::new(Mutex::new(MockAliyun::default()));
+        state.lock().unwrap().parts.insert(1, b"0123".to_vec());
+        let base = start_mock_aliyun(state.clone());
+        let progress = Mutex::new(Vec::new());
+
+        tauri::async_runtime::block_on(test_uploader(&base).upload_file(
+            &path,
+            "d1",
+            "root",
+            &sessions,
+            &SessionSlot::for_test("key"),
+            &|sent, _| progress.lock().unwrap().push(sent),
+        ))
+        .unwrap();
+
+        let s = state.lock().unwrap();
+        assert!(s.creates.is_empty());
+        assert_eq!(s.url_refreshes, vec![vec![2, 3]]);
+        assert_eq!(s.puts, vec![2, 3]);
+        assert!(s.completed);
+        assert_eq!(*progress.lock().unwrap(), vec![4, 8, 10]);
+    }
+
+    #[test]
+    fn test_rapid_upload_skips_transfer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = sample_file(dir.path());
+        let state = Arc::new(Mutex::new(MockAliyun {
+            rapid: true,
+            ..Default::default()
+        }));
+        let base = start_mock_aliyun(state.clone());
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+
+        let uploaded = tauri::async_runtime::block_on(test_uploader(&base).upload_file(
+            &path,
+            "d1",
+            "root",
+            &sessions,
+            &SessionSlot::for_test("key"),
+            &|_, _| {},
+        ))
+        .unwrap();
+        assert_eq!(uploaded["rapid_upload"], true);
+        // 秒传响应没有校验和，需要再查询文件信息
+        assert_eq!(aliyun_remote(&uploaded).unwrap().checksum, None);
+        let remote =
+            tauri::async_runtime::block_on(test_uploader(&base).get_file("d1", "f1")).unwrap();
+        assert!(remote.checksum.unwrap().matches_file(&path).unwrap());
+
+        let s = state.lock().unwrap();
+        assert_eq!(s.creates.len(), 2);
+        assert!(s.creates[0].get("content_hash").is_none());
+        let second = &s.creates[1];
+        assert!(second.get("pre_hash").is_none());
+        assert_eq!(
+            second["content_hash"],
+            "87ACEC17CD9DCD20A716CC2CF67417B71C8A7016"
+        );
+        assert_eq!(second["content_hash_name"], "sha1");
+        // md5("at") 前 16 位对 10 取模为 9，取第 9 个字节起的 1 个字节
+        assert_eq!(second["proof_code"], "OQ==");
+        assert_eq!(second["proof_version"], "v1");
+        assert!(s.puts.is_empty());
+        assert!(!s.completed);
+    }
+
+    #[test]
+    fn test_list_follows_next_marker() {
+        let state = Arc::new(Mutex::new(MockAliyun::default()));
+        let base = start_mock_aliyun(state.clone());
+        let uploader = test_uploader(&base);
+
+        let first = tauri::async_runtime::block_on(uploader.list("/备份/", None)).unwrap();
+        assert_eq!(first.next_page_token.as_deref(), Some("m2"));
+        assert_eq!(first.entries[0].size, 4096);
+        assert_eq!(first.entries[0].modified, Some(1_704_164_645));
+        assert!(!first.entries[0].is_dir);
+        let second = tauri::async_runtime::block_on(uploader.list("/备份/", Some("m2"))).unwrap();
+        assert!(second.next_page_token.is_none());
+        assert!(second.entries[0].is_dir);
+
+        let s = state.lock().unwrap();
+        assert_eq!(s.list_requests[0]["parent_file_id"], "folder-备份");
+        assert_eq!(s.list_requests[0]["drive_id"], "d1");
+        assert_eq!(s.list_requests[1]["marker"], "m2");
+    }
+}
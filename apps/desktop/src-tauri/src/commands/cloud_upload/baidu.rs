@@ -0,0 +1,931 @@
+//! 百度网盘上传：precreate（提交分片 MD5 列表）→ superfile2（按 4 MB 分片上传）→ create（合并）。
+//! 第三方应用只能写入 `/apps/<应用名>/` 目录，目标路径会被限制在该目录下。
+//! uploadid 与已按序上传到的偏移量持久化到存储根目录，中断后重试跳过 precreate 和已上传的分片。
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::RemoteChecksum;
+use log::{debug, error, info};
+use md5::{Digest, Md5};
+use serde_json::{json, Value};
+
+use super::bandwidth::upload_limiter;
+use super::session::{FileIdentity, SessionSlot, UploadSessionStore};
+use super::{
+    is_transient, with_retries, CloudFileEntry, CloudFilePage, RemoteFile, UploadConfig,
+    LIST_PAGE_SIZE,
+};
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+const BAIDU_XPAN_URL: &str = "https://pan.baidu.com/rest/2.0/xpan";
+const BAIDU_PCS_URL: &str = "https://d.pcs.baidu.com/rest/2.0/pcs";
+
+/// 百度网盘要求的 User-Agent
+const BAIDU_USER_AGENT: &str = "pan.baidu.com";
+
+/// 第三方应用的可写目录
+const BAIDU_APP_DIR: &str = "/apps/DiskRookie";
+
+/// 普通用户的分片大小上限
+const SLICE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// uploadid 的有效期没有公开说明，按一天保守估计
+pub(super) const SESSION_MAX_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// 文件名中百度网盘不允许的字符
+const FORBIDDEN_CHARS: &[char] = &['\\', '?', '|', '"', '>', '<', ':', '*'];
+
+/// 把百度网盘的 errno 转成可读的错误
+fn errno_error(context: &str, errno: i64, raw: &str) -> CommandError {
+    let (code, reason) = match errno {
+        -6 | 111 => (ErrorCode::Unauthorized, "身份验证失败，请重新授权"),
+        -7 => (ErrorCode::InvalidInput, "文件名非法或无权访问该目录"),
+        -8 => (ErrorCode::InvalidInput, "文件或目录已存在"),
+        -10 => (ErrorCode::Network, "网盘空间不足"),
+        2 => (ErrorCode::InvalidInput, "参数错误"),
+        10 => (ErrorCode::Network, "创建文件失败"),
+        31024 => (ErrorCode::PermissionDenied, "应用没有上传权限"),
+        31034 | 42000 => (ErrorCode::Network, "请求过于频繁，请稍后再试"),
+        31190 => (ErrorCode::PathNotFound, "文件不存在"),
+        31299 => (ErrorCode::InvalidInput, "分片大小不符合要求"),
+        31363 => (ErrorCode::Network, "分片缺失，请重试"),
+        31364 => (ErrorCode::InvalidInput, "超出分片大小限制"),
+        _ => (ErrorCode::Network, "未知错误"),
+    };
+    let mut details = json!({ "errno": errno, "response": raw });
+    // 频控错误按 429 处理，便于统一的重试逻辑识别
+    if matches!(errno, 31034 | 42000) {
+        details["status"] = json!(429);
+    }
+    CommandError::new(code, format!("{}: {}（errno {}）", context, reason, errno))
+        .with_details(details)
+}
+
+/// 检查响应中的 errno（xpan 接口）或 error_code（pcs 接口），非 0 时转成错误
+fn check_errno(context: &str, body: &Value) -> Result<(), CommandError> {
+    let errno = body["errno"]
+        .as_i64()
+        .or_else(|| body["error_code"].as_i64())
+        .unwrap_or(0);
+    if errno == 0 {
+        Ok(())
+    } else {
+        error!("{}: errno {}，响应: {}", context, errno, body);
+        Err(errno_error(context, errno, &body.to_string()))
+    }
+}
+
+/// 把目标目录限制在应用目录下，返回云端目录路径
+fn baidu_dir(target_path: &str) -> Result<String, CommandError> {
+    let dir = target_path.trim_matches('/');
+    let app_dir = BAIDU_APP_DIR.trim_start_matches('/');
+    let relative = if dir == app_dir {
+        ""
+    } else if let Some(rest) = dir.strip_prefix(app_dir).and_then(|r| r.strip_prefix('/')) {
+        rest
+    } else if dir == "apps" || dir.starts_with("apps/") {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("百度网盘只允许访问 {} 目录下的文件", BAIDU_APP_DIR),
+        ));
+    } else {
+        dir
+    };
+
+    if relative.is_empty() {
+        Ok(BAIDU_APP_DIR.to_string())
+    } else {
+        Ok(format!("{}/{}", BAIDU_APP_DIR, relative))
+    }
+}
+
+/// 目标目录（限制在应用目录下）+ 文件名，返回云端完整路径
+fn baidu_path(target_path: &str, file_name: &str) -> Result<String, CommandError> {
+    if file_name.contains(FORBIDDEN_CHARS) {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("文件名包含百度网盘不支持的字符: {}", file_name),
+        ));
+    }
+    Ok(format!("{}/{}", baidu_dir(target_path)?, file_name))
+}
+
+/// 把 create / filemetas 返回的文件信息转成 `RemoteFile`。
+/// 多分片文件的 md5 不是文件内容的 MD5，只有不超过一个分片的文件才报告校验和；
+/// 多分片文件的完整性由上传时逐片核对的 MD5 保证
+fn baidu_remote(meta: &Value, slice_size: u64) -> Result<RemoteFile, CommandError> {
+    let id = meta["fs_id"]
+        .as_u64()
+        .map(|id| id.to_string())
+        .ok_or_else(|| CommandError::internal("响应中没有 fs_id"))?;
+    let size = meta["size"].as_u64();
+    let checksum = meta["md5"]
+        .as_str()
+        .filter(|_| size.is_some_and(|size| size <= slice_size))
+        .map(|md5| RemoteChecksum::Md5(md5.to_string()));
+    Ok(RemoteFile {
+        id,
+        path: meta["path"].as_str().map(str::to_string),
+        size,
+        checksum,
+    })
+}
+
+/// 服务端以 errno 拒绝了请求（频控等可重试的错误除外）
+fn is_rejected(e: &CommandError) -> bool {
+    !is_transient(e) && e.details.as_ref().is_some_and(|d| d["errno"].is_i64())
+}
+
+fn md5_hex(data: &[u8]) -> String {
+    Md5::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn read_slice(file: &mut File, start: u64, len: u64) -> Result<Vec<u8>, CommandError> {
+    let mut buffer = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut buffer))
+        .map_err(|e| CommandError::io("读取文件分片失败", &e))?;
+    Ok(buffer)
+}
+
+/// 每个分片的 MD5（十六进制）；空文件视为一个空分片
+fn slice_md5_list(
+    file: &mut File,
+    total: u64,
+    slice_size: u64,
+) -> Result<Vec<String>, CommandError> {
+    let count = total.div_ceil(slice_size).max(1);
+    (0..count)
+        .map(|i| {
+            let start = i * slice_size;
+            let len = slice_size.min(total - start);
+            read_slice(file, start, len).map(|slice| md5_hex(&slice))
+        })
+        .collect()
+}
+
+pub(crate) struct BaiduUploader<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    xpan_url: String,
+    pcs_url: String,
+    slice_size: u64,
+    retry_backoff: Duration,
+}
+
+impl<'a> BaiduUploader<'a> {
+    pub fn new(access_token: &'a str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            xpan_url: BAIDU_XPAN_URL.to_string(),
+            pcs_url: BAIDU_PCS_URL.to_string(),
+            slice_size: SLICE_SIZE,
+            retry_backoff: Duration::from_secs(1),
+        }
+    }
+
+    async fn read_json(context: &str, response: reqwest::Response) -> Result<Value, CommandError> {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(status_error(context, status, &text));
+        }
+        let body: Value = serde_json::from_str(&text).map_err(|e| parse_error(context, e))?;
+        check_errno(context, &body)?;
+        Ok(body)
+    }
+
+    /// xpan 文件接口（precreate / create），参数以表单提交
+    async fn file_method(
+        &self,
+        method: &str,
+        form: &[(&str, &str)],
+    ) -> Result<Value, CommandError> {
+        let context = format!("百度网盘 {} 失败", method);
+        let response = self
+            .client
+            .post(format!("{}/file", self.xpan_url))
+            .query(&[("method", method), ("access_token", self.access_token)])
+            .header("User-Agent", BAIDU_USER_AGENT)
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| request_error(&context, &e))?;
+        Self::read_json(&context, response).await
+    }
+
+    /// 按 fs_id 查询文件信息
+    pub async fn file_meta(&self, fs_id: &str) -> Result<RemoteFile, CommandError> {
+        let context = "查询百度网盘文件信息失败";
+        let fsids = format!("[{}]", fs_id);
+        let response = self
+            .client
+            .get(format!("{}/multimedia", self.xpan_url))
+            .query(&[
+                ("method", "filemetas"),
+                ("access_token", self.access_token),
+                ("fsids", fsids.as_str()),
+                ("dlink", "0"),
+            ])
+            .header("User-Agent", BAIDU_USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| request_error(context, &e))?;
+        let body = Self::read_json(context, response).await?;
+        match body["list"].as_array().and_then(|list| list.first()) {
+            Some(meta) => baidu_remote(meta, self.slice_size),
+            None => Err(errno_error(context, 31190, &body.to_string())),
+        }
+    }
+
+    /// 列出应用目录下 `path` 的一页文件；`page_token` 是下一页的起始偏移
+    pub async fn list(
+        &self,
+        path: &str,
+        page_token: Option<&str>,
+    ) -> Result<CloudFilePage, CommandError> {
+        let context = "列出百度网盘文件失败";
+        let dir = baidu_dir(path)?;
+        let start: u64 = match page_token {
+            Some(token) => token.parse().map_err(|_| {
+                CommandError::new(
+                    ErrorCode::InvalidInput,
+                    format!("无效的分页标记: {}", token),
+                )
+            })?,
+            None => 0,
+        };
+        let (start_text, limit_text) = (start.to_string(), LIST_PAGE_SIZE.to_string());
+        let response = self
+            .client
+            .get(format!("{}/file", self.xpan_url))
+            .query(&[
+                ("method", "list"),
+                ("access_token", self.access_token),
+                ("dir", dir.as_str()),
+                ("start", start_text.as_str()),
+                ("limit", limit_text.as_str()),
+                ("order", "name"),
+                ("web", "0"),
+            ])
+            .header("User-Agent", BAIDU_USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| request_error(context, &e))?;
+        let body = Self::read_json(context, response).await?;
+
+        let list = body["list"].as_array().cloned().unwrap_or_default();
+        // 接口不返回是否还有更多，取满一页时认为可能还有下一页
+        let next_page_token = (list.len() as u64 >= u64::from(LIST_PAGE_SIZE))
+            .then(|| (start + list.len() as u64).to_string());
+        let entries = list
+            .iter()
+            .filter_map(|f| {
+                Some(CloudFileEntry {
+                    id: f["fs_id"].as_u64()?.to_string(),
+                    name: f["server_filename"].as_str()?.to_string(),
+                    size: f["size"].as_u64().unwrap_or(0),
+                    modified: f["server_mtime"].as_u64(),
+                    is_dir: f["isdir"].as_u64() == Some(1),
+                    provider: "baidu_netdisk".to_string(),
+                })
+            })
+            .collect();
+        Ok(CloudFilePage {
+            entries,
+            next_page_token,
+        })
+    }
+
+    /// 按 fs_id 删除文件（filemanager 接口以路径寻址，先查询路径）
+    pub async fn delete(&self, fs_id: &str) -> Result<(), CommandError> {
+        let context = "删除百度网盘文件失败";
+        let path = self
+            .file_meta(fs_id)
+            .await?
+            .path
+            .ok_or_else(|| CommandError::internal("文件信息中没有路径"))?;
+        let filelist = serde_json::to_string(&[path])
+            .map_err(|e| CommandError::internal(format!("序列化文件列表失败: {}", e)))?;
+        let response = self
+            .client
+            .post(format!("{}/file", self.xpan_url))
+            .query(&[
+                ("method", "filemanager"),
+                ("opera", "delete"),
+                ("access_token", self.access_token),
+            ])
+            .header("User-Agent", BAIDU_USER_AGENT)
+            .form(&[("async", "0"), ("filelist", filelist.as_str())])
+            .send()
+            .await
+            .map_err(|e| request_error(context, &e))?;
+        Self::read_json(context, response).await?;
+        Ok(())
+    }
+
+    /// 上传一个分片，并核对服务端返回的 MD5
+    async fn upload_slice(
+        &self,
+        remote_path: &str,
+        upload_id: &str,
+        partseq: usize,
+        slice: &[u8],
+        expected_md5: &str,
+    ) -> Result<(), CommandError> {
+        let context = format!("上传第 {} 个分片失败", partseq);
+        let partseq = partseq.to_string();
+        let form = reqwest::multipart::Form::new().part(
+            "file",
+            reqwest::multipart::Part::bytes(slice.to_vec()).file_name("blob"),
+        );
+        upload_limiter().acquire(slice.len() as u64).await;
+        let response = self
+            .client
+            .post(format!("{}/superfile2", self.pcs_url))
+            .query(&[
+                ("method", "upload"),
+                ("access_token", self.access_token),
+                ("type", "tmpfile"),
+                ("path", remote_path),
+                ("uploadid", upload_id),
+                ("partseq", partseq.as_str()),
+            ])
+            .header("User-Agent", BAIDU_USER_AGENT)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| request_error(&context, &e))?;
+        let body = Self::read_json(&context, response).await?;
+
+        // MD5 不一致说明传输中数据损坏，作为网络错误重试
+        match body["md5"].as_str() {
+            Some(md5) if md5.eq_ignore_ascii_case(expected_md5) => Ok(()),
+            other => Err(CommandError::new(
+                ErrorCode::Network,
+                format!(
+                    "{}: 分片校验失败（期望 {}，实际 {:?}）",
+                    context, expected_md5, other
+                ),
+            )),
+        }
+    }
+
+    /// precreate 创建上传会话，返回 uploadid 与需要上传的分片序号（升序）
+    async fn precreate(
+        &self,
+        remote_path: &str,
+        size: &str,
+        block_list: &str,
+        slice_count: usize,
+    ) -> Result<(String, Vec<usize>), CommandError> {
+        // rtype=1：重名时自动改名
+        let precreate = self
+            .file_method(
+                "precreate",
+                &[
+                    ("path", remote_path),
+                    ("size", size),
+                    ("isdir", "0"),
+                    ("autoinit", "1"),
+                    ("rtype", "1"),
+                    ("block_list", block_list),
+                ],
+            )
+            .await?;
+        let upload_id = precreate["uploadid"]
+            .as_str()
+            .ok_or_else(|| CommandError::internal("precreate 响应中没有 uploadid"))?;
+        // 返回需要上传的分片序号；为空表示服务端已有全部分片
+        let mut pending: Vec<usize> = precreate["block_list"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .filter_map(|i| i.as_u64().map(|i| i as usize))
+                    .collect()
+            })
+            .unwrap_or_default();
+        pending.sort_unstable();
+        if let Some(&invalid) = pending.iter().find(|&&i| i >= slice_count) {
+            return Err(CommandError::internal(format!(
+                "precreate 返回了无效的分片序号: {}",
+                invalid
+            )));
+        }
+        Ok((upload_id.to_string(), pending))
+    }
+
+    /// 上传单个文件到 `remote_path`（重名时自动改名）；会话键相同的中断上传会被续传。
+    /// `on_progress(bytes_sent, total)` 在每个分片完成后调用
+    pub async fn upload_file(
+        &self,
+        path: &Path,
+        remote_path: &str,
+        sessions: &UploadSessionStore,
+        slot: &SessionSlot,
+        on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<Value, CommandError> {
+        let identity = FileIdentity::of(path)?;
+        let total = identity.size;
+        let mut file = File::open(path).map_err(|e| CommandError::io("打开文件失败", &e))?;
+
+        let md5_list = slice_md5_list(&mut file, total, self.slice_size)?;
+        let block_list = serde_json::to_string(&md5_list)
+            .map_err(|e| CommandError::internal(format!("序列化分片列表失败: {}", e)))?;
+        let size = total.to_string();
+        debug!("共 {} 个分片", md5_list.len());
+
+        // 会话的 committed 是按序上传到的偏移量：之前的分片服务端都已有
+        let saved = sessions.load(&slot.key, &identity, SESSION_MAX_AGE_MS);
+        let resumed = saved.is_some();
+        let (mut session, pending) = match saved {
+            Some(saved) => {
+                info!(
+                    "从 {} / {} 字节处续传: {}",
+                    saved.committed, total, saved.session
+                );
+                let pending: Vec<usize> = (0..md5_list.len())
+                    .filter(|&i| i as u64 * self.slice_size >= saved.committed)
+                    .collect();
+                (saved, pending)
+            }
+            None => {
+                let (upload_id, pending) = self
+                    .precreate(remote_path, &size, &block_list, md5_list.len())
+                    .await?;
+                let mut session = UploadSessionStore::new_session(
+                    &slot.origin,
+                    &path.to_string_lossy(),
+                    identity,
+                    upload_id,
+                );
+                session.committed = pending
+                    .first()
+                    .map_or(total, |&i| i as u64 * self.slice_size);
+                (session, pending)
+            }
+        };
+        sessions.save(&slot.key, &session)?;
+        info!(
+            "百度网盘上传会话 {}，需上传 {} / {} 个分片",
+            session.session,
+            pending.len(),
+            md5_list.len()
+        );
+
+        let result = async {
+            let mut sent = total
+                - pending
+                    .iter()
+                    .map(|&i| self.slice_size.min(total - i as u64 * self.slice_size))
+                    .sum::<u64>();
+            on_progress(sent, total);
+            for &partseq in &pending {
+                let expected = &md5_list[partseq];
+                let start = partseq as u64 * self.slice_size;
+                let slice = read_slice(&mut file, start, self.slice_size.min(total - start))?;
+                let (slice, upload_id) = (&slice, session.session.as_str());
+                with_retries("上传分片", self.retry_backoff, move || {
+                    self.upload_slice(remote_path, upload_id, partseq, slice, expected)
+                })
+                .await?;
+                sent += slice.len() as u64;
+                session.committed = start + slice.len() as u64;
+                sessions.save(&slot.key, &session)?;
+                on_progress(sent, total);
+            }
+            self.file_method(
+                "create",
+                &[
+                    ("path", remote_path),
+                    ("size", size.as_str()),
+                    ("isdir", "0"),
+                    ("rtype", "1"),
+                    ("uploadid", session.session.as_str()),
+                    ("block_list", block_list.as_str()),
+                ],
+            )
+            .await
+        }
+        .await;
+        match result {
+            Ok(created) => {
+                sessions.remove(&slot.key);
+                on_progress(total, total);
+                Ok(created)
+            }
+            // 服务端拒绝了续传的 uploadid（已失效或已合并），下次重新 precreate
+            Err(e) if resumed && is_rejected(&e) => {
+                sessions.remove(&slot.key);
+                Err(CommandError::new(
+                    ErrorCode::Network,
+                    format!("上传会话已失效，请重试: {}", e),
+                ))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 上传文件到百度网盘应用目录下的 `config.target_path`，返回 fs_id 和最终路径
+pub(crate) async fn upload_to_baidu(
+    file_path: &str,
+    config: &UploadConfig,
+    sessions: &UploadSessionStore,
+    on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+) -> Result<RemoteFile, CommandError> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        error!("文件不存在: {}", file_path);
+        return Err(CommandError::new(
+            ErrorCode::PathNotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CommandError::new(ErrorCode::InvalidInput, "无法获取文件名"))?;
+
+    let remote_path = baidu_path(&config.target_path, file_name)?;
+    info!("上传到百度网盘路径: {}", remote_path);
+    let slot = SessionSlot::new(config, file_path, &remote_path);
+    let created = BaiduUploader::new(&config.access_token)
+        .upload_file(path, &remote_path, sessions, &slot, on_progress)
+        .await?;
+
+    let file = baidu_remote(&created, SLICE_SIZE)?;
+    info!("上传成功，fs_id: {}", file.id);
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// 模拟百度网盘上传接口的服务端状态
+    #[derive(Default)]
+    struct MockBaidu {
+        precreate: HashMap<String, String>,
+        create: HashMap<String, String>,
+        /// 按请求顺序记录的分片序号
+        partseqs: Vec<usize>,
+        slices: HashMap<usize, Vec<u8>>,
+        /// 这些分片序号的第一次上传返回 500
+        fail_once: Vec<usize>,
+        precreate_errno: i64,
+        /// filemanager 删除请求的表单
+        deleted: HashMap<String, String>,
+        list_queries: Vec<HashMap<String, String>>,
+    }
+
+    fn parse_pairs(text: &str) -> HashMap<String, String> {
+        text.split('&')
+            .filter_map(|pair| {
+                let (k, v) = pair.split_once('=')?;
+                let decode = |s: &str| {
+                    urlencoding::decode(&s.replace('+', " "))
+                        .ok()
+                        .map(|s| s.into_owned())
+                };
+                Some((decode(k)?, decode(v)?))
+            })
+            .collect()
+    }
+
+    /// 从 multipart 请求体中取出 `file` 字段的内容
+    fn multipart_file(body: &[u8]) -> Vec<u8> {
+        let find = |haystack: &[u8], needle: &[u8], from: usize| {
+            haystack[from..]
+                .windows(needle.len())
+                .position(|w| w == needle)
+                .map(|p| p + from)
+        };
+        let boundary_end = find(body, b"\r\n", 0).unwrap();
+        let boundary = [b"\r\n", &body[..boundary_end]].concat();
+        let start = find(body, b"\r\n\r\n", 0).unwrap() + 4;
+        let end = find(body, &boundary, start).unwrap();
+        body[start..end].to_vec()
+    }
+
+    fn start_mock_baidu(state: Arc<Mutex<MockBaidu>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = Vec::new();
+                let _ = request.as_reader().read_to_end(&mut body);
+                let (route, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+                let (route, query) = (route.to_string(), parse_pairs(query));
+                let user_agent_ok = request
+                    .headers()
+                    .iter()
+                    .any(|h| h.field.equiv("User-Agent") && h.value.as_str() == BAIDU_USER_AGENT);
+                assert!(user_agent_ok);
+                assert_eq!(query["access_token"], "at");
+
+                let mut s = state.lock().unwrap();
+                let (status, reply) = match (route.as_str(), query["method"].as_str()) {
+                    ("/xpan/file", "precreate") => {
+                        s.precreate = parse_pairs(&String::from_utf8_lossy(&body));
+                        if s.precreate_errno != 0 {
+                            (200, json!({ "errno": s.precreate_errno }))
+                        } else {
+                            let blocks: Vec<String> =
+                                serde_json::from_str(&s.precreate["block_list"]).unwrap();
+                            (
+                                200,
+                                json!({
+                                    "errno": 0,
+                                    "uploadid": "up-1",
+                                    "return_type": 1,
+                                    "block_list": (0..blocks.len()).rev().collect::<Vec<_>>()
+                                }),
+                            )
+                        }
+                    }
+                    ("/pcs/superfile2", "upload") => {
+                        let partseq: usize = query["partseq"].parse().unwrap();
+                        assert_eq!(query["uploadid"], "up-1");
+                        assert_eq!(query["type"], "tmpfile");
+                        s.partseqs.push(partseq);
+                        if let Some(i) = s.fail_once.iter().position(|&p| p == partseq) {
+                            s.fail_once.remove(i);
+                            (500, json!({}))
+                        } else {
+                            let data = multipart_file(&body);
+                            let md5 = md5_hex(&data);
+                            s.slices.insert(partseq, data);
+                            (200, json!({ "md5": md5, "request_id": 1 }))
+                        }
+                    }
+                    ("/xpan/file", "create") => {
+                        s.create = parse_pairs(&String::from_utf8_lossy(&body));
+                        (
+                            200,
+                            json!({
+                                "errno": 0,
+                                "fs_id": 123456,
+                                "path": s.create["path"],
+                                "size": s.create["size"].parse::<u64>().unwrap()
+                            }),
+                        )
+                    }
+                    ("/xpan/multimedia", "filemetas") => {
+                        assert_eq!(query["fsids"], "[123456]");
+                        (
+                            200,
+                            json!({
+                                "errno": 0,
+                                "list": [{
+                                    "fs_id": 123456,
+                                    "path": "/apps/DiskRookie/a.txt",
+                                    "size": 3,
+                                    "md5": "900150983cd24fb0d6963f7d28e17f72"
+                                }]
+                            }),
+                        )
+                    }
+                    ("/xpan/file", "list") => {
+                        s.list_queries.push(query.clone());
+                        let start: u64 = query["start"].parse().unwrap();
+                        let limit: u64 = query["limit"].parse().unwrap();
+                        // 共 limit + 1 个文件，第二页只有一个
+                        let count = if start == 0 { limit } else { 1 };
+                        let list: Vec<Value> = (start..start + count)
+                            .map(|i| {
+                                json!({
+                                    "fs_id": i,
+                                    "server_filename": format!("f{}", i),
+                                    "size": 10,
+                                    "server_mtime": 1_704_164_645,
+                                    "isdir": u64::from(i == 0)
+                                })
+                            })
+                            .collect();
+                        (200, json!({ "errno": 0, "list": list }))
+                    }
+                    ("/xpan/file", "filemanager") => {
+                        assert_eq!(query["opera"], "delete");
+                        s.deleted = parse_pairs(&String::from_utf8_lossy(&body));
+                        (200, json!({ "errno": 0 }))
+                    }
+                    _ => (404, json!({})),
+                };
+                let _ = request.respond(
+                    tiny_http::Response::from_string(reply.to_string()).with_status_code(status),
+                );
+            }
+        });
+        base
+    }
+
+    fn test_uploader(base: &str) -> BaiduUploader<'static> {
+        BaiduUploader {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            xpan_url: format!("{}/xpan", base),
+            pcs_url: format!("{}/pcs", base),
+            slice_size: 4,
+            retry_backoff: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_baidu_path_stays_in_app_dir() {
+        assert_eq!(baidu_path("/", "a.txt").unwrap(), "/apps/DiskRookie/a.txt");
+        assert_eq!(
+            baidu_path("/备份/2024", "a.txt").unwrap(),
+            "/apps/DiskRookie/备份/2024/a.txt"
+        );
+        assert_eq!(
+            baidu_path("/apps/DiskRookie/备份", "a.txt").unwrap(),
+            "/apps/DiskRookie/备份/a.txt"
+        );
+        assert_eq!(
+            baidu_path("/apps/其他应用", "a.txt").unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+        assert!(baidu_path("/", "a?.txt").is_err());
+    }
+
+    #[test]
+    fn test_upload_sends_md5_list_and_slices_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+        let state = Arc::new(Mutex::new(MockBaidu {
+            fail_once: vec![1],
+            ..Default::default()
+        }));
+        let base = start_mock_baidu(state.clone());
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let progress = Mutex::new(Vec::new());
+
+        let created = tauri::async_runtime::block_on(test_uploader(&base).upload_file(
+            &path,
+            "/apps/DiskRookie/data.bin",
+            &sessions,
+            &SessionSlot::for_test("key"),
+            &|sent, _| progress.lock().unwrap().push(sent),
+        ))
+        .unwrap();
+        assert_eq!(created["fs_id"], 123456);
+
+        let s = state.lock().unwrap();
+        let expected_md5 = r#"["eb62f6b9306db575c2d596b1279627a4","6562c5c1f33db6e05a082a88cddab5ea","7647966b7343c29048673252e490f736"]"#;
+        assert_eq!(s.precreate["block_list"], expected_md5);
+        assert_eq!(s.precreate["size"], "10");
+        assert_eq!(s.precreate["autoinit"], "1");
+        // 乱序返回的分片按序号上传，失败的分片单独重试
+        assert_eq!(s.partseqs, vec![0, 1, 1, 2]);
+        assert_eq!(s.slices[&0], b"0123");
+        assert_eq!(s.slices[&1], b"4567");
+        assert_eq!(s.slices[&2], b"89");
+        assert_eq!(s.create["uploadid"], "up-1");
+        assert_eq!(s.create["block_list"], expected_md5);
+        assert_eq!(s.create["path"], "/apps/DiskRookie/data.bin");
+        assert_eq!(*progress.lock().unwrap(), vec![0, 4, 8, 10, 10]);
+        // 合并后会话删除
+        let identity = FileIdentity::of(&path).unwrap();
+        assert!(sessions
+            .load("key", &identity, SESSION_MAX_AGE_MS)
+            .is_none());
+    }
+
+    #[test]
+    fn test_resume_skips_precreate_and_committed_slices() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        // 上次上传完第一个分片后应用退出
+        let mut saved = UploadSessionStore::new_session(
+            &SessionSlot::for_test("key").origin,
+            &path.to_string_lossy(),
+            FileIdentity::of(&path).unwrap(),
+            "up-1".to_string(),
+        );
+        saved.committed = 4;
+        UploadSessionStore::new(dir.path().join("sessions"))
+            .save("key", &saved)
+            .unwrap();
+
+        // 重启后新建的会话目录读到上次的 uploadid
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let state = Arc::new(Mutex::new(MockBaidu::default()));
+        let base = start_mock_baidu(state.clone());
+        let progress = Mutex::new(Vec::new());
+
+        tauri::async_runtime::block_on(test_uploader(&base).upload_file(
+            &path,
+            "/apps/DiskRookie/data.bin",
+            &sessions,
+            &SessionSlot::for_test("key"),
+            &|sent, _| progress.lock().unwrap().push(sent),
+        ))
+        .unwrap();
+
+        let s = state.lock().unwrap();
+        assert!(s.precreate.is_empty());
+        assert_eq!(s.partseqs, vec![1, 2]);
+        assert_eq!(s.slices[&2], b"89");
+        assert_eq!(s.create["uploadid"], "up-1");
+        assert_eq!(*progress.lock().unwrap(), vec![4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn test_errno_is_reported_readably() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        std::fs::write(&path, b"0123").unwrap();
+        let state = Arc::new(Mutex::new(MockBaidu {
+            precreate_errno: -10,
+            ..Default::default()
+        }));
+        let base = start_mock_baidu(state);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+
+        let err = tauri::async_runtime::block_on(test_uploader(&base).upload_file(
+            &path,
+            "/apps/DiskRookie/data.bin",
+            &sessions,
+            &SessionSlot::for_test("key"),
+            &|_, _| {},
+        ))
+        .unwrap_err();
+        assert!(err.message.contains("网盘空间不足"));
+        assert!(err.message.contains("errno -10"));
+        assert_eq!(errno_error("x", 111, "").code, ErrorCode::Unauthorized);
+    }
+
+    #[test]
+    fn test_only_single_slice_md5_is_reported() {
+        let meta = json!({ "fs_id": 1, "size": 4, "md5": "abc" });
+        assert_eq!(
+            baidu_remote(&meta, 4).unwrap().checksum,
+            Some(RemoteChecksum::Md5("abc".into()))
+        );
+        let meta = json!({ "fs_id": 1, "size": 5, "md5": "abc" });
+        assert_eq!(baidu_remote(&meta, 4).unwrap().checksum, None);
+    }
+
+    #[test]
+    fn test_file_meta_and_delete_by_fs_id() {
+        let state = Arc::new(Mutex::new(MockBaidu::default()));
+        let base = start_mock_baidu(state.clone());
+        let uploader = test_uploader(&base);
+
+        let file = tauri::async_runtime::block_on(uploader.file_meta("123456")).unwrap();
+        assert_eq!(file.size, Some(3));
+        assert_eq!(
+            file.checksum,
+            Some(RemoteChecksum::Md5(
+                "900150983cd24fb0d6963f7d28e17f72".into()
+            ))
+        );
+        tauri::async_runtime::block_on(uploader.delete("123456")).unwrap();
+        let s = state.lock().unwrap();
+        assert_eq!(s.deleted["async"], "0");
+        assert_eq!(s.deleted["filelist"], r#"["/apps/DiskRookie/a.txt"]"#);
+    }
+
+    #[test]
+    fn test_list_pages_by_offset() {
+        let state = Arc::new(Mutex::new(MockBaidu::default()));
+        let base = start_mock_baidu(state.clone());
+        let uploader = test_uploader(&base);
+
+        let first = tauri::async_runtime::block_on(uploader.list("/备份", None)).unwrap();
+        assert_eq!(first.entries.len(), LIST_PAGE_SIZE as usize);
+        assert!(first.entries[0].is_dir);
+        assert_eq!(first.entries[1].modified, Some(1_704_164_645));
+        let token = first.next_page_token.unwrap();
+        assert_eq!(token, "100");
+
+        let second = tauri::async_runtime::block_on(uploader.list("/备份", Some(&token))).unwrap();
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.entries[0].id, "100");
+        assert!(second.next_page_token.is_none());
+        assert_eq!(
+            tauri::async_runtime::block_on(uploader.list("/", Some("x")))
+                .unwrap_err()
+                .code,
+            ErrorCode::InvalidInput
+        );
+
+        let s = state.lock().unwrap();
+        assert_eq!(s.list_queries[0]["dir"], "/apps/DiskRookie/备份");
+        assert_eq!(s.list_queries[1]["start"], "100");
+    }
+}
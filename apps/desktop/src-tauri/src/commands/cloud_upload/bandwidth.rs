@@ -0,0 +1,210 @@
+//! 上传带宽限制：所有服务商的上传实现在发送每个分块前从同一个令牌桶取额度，合计速率不超过上限。
+//!
+//! 额度按切片（约 50 ms 的传输量）依次预约：每次预约在共享的时间线上顺延，先到先得，
+//! 多个并发上传的切片交替排队，任何一个都不会被饿死；等待使用 `tokio::time::sleep_until`，不占用 CPU。
+//! 上限可随时修改，正在等待的分块最多再按旧速率完成一个切片。
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// 切片的最小值，避免低速率下预约过于频繁
+const MIN_SLICE: u64 = 16 * 1024;
+
+/// 计算当前吞吐量的滑动窗口
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(3);
+
+struct Bucket {
+    /// 字节/秒，0 表示不限
+    rate: u64,
+    /// 已预约额度的理论发送完成时间
+    next_free: Option<Instant>,
+}
+
+pub(crate) struct BandwidthLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+/// 所有上传共享的限速器；启动时与配置保存时按 `upload.max_bytes_per_sec` 设置
+static UPLOAD_LIMITER: BandwidthLimiter = BandwidthLimiter::new(0);
+
+pub(crate) fn upload_limiter() -> &'static BandwidthLimiter {
+    &UPLOAD_LIMITER
+}
+
+impl BandwidthLimiter {
+    pub const fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bucket: Mutex::new(Bucket {
+                rate: bytes_per_sec,
+                next_free: None,
+            }),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.lock().rate
+    }
+
+    /// 修改上限，立即作用于后续切片
+    pub fn set_limit(&self, bytes_per_sec: u64) {
+        let mut bucket = self.lock();
+        bucket.rate = bytes_per_sec;
+        bucket.next_free = None;
+    }
+
+    /// 等待到可以发送 `bytes` 字节
+    pub async fn acquire(&self, bytes: u64) {
+        let mut remaining = bytes;
+        while remaining > 0 {
+            let (reserved, ready) = self.reserve(remaining);
+            if let Some(ready) = ready {
+                tokio::time::sleep_until(ready).await;
+            }
+            remaining -= reserved;
+        }
+    }
+
+    /// 预约至多一个切片，返回 (预约的字节数, 需要等待到的时刻)；
+    /// 不限速时一次预约全部，额度充足时无需等待
+    fn reserve(&self, bytes: u64) -> (u64, Option<Instant>) {
+        let mut bucket = self.lock();
+        if bucket.rate == 0 {
+            return (bytes, None);
+        }
+        let slice = slice_for(bucket.rate);
+        let reserved = bytes.min(slice);
+        let now = Instant::now();
+        let start = bucket.next_free.map_or(now, |t| t.max(now));
+        let next_free = start + transfer_time(reserved, bucket.rate);
+        bucket.next_free = Some(next_free);
+        // 允许提前一个切片的突发：空闲后的第一个切片无需等待
+        let ready = next_free
+            .checked_sub(transfer_time(slice, bucket.rate))
+            .filter(|ready| *ready > now);
+        (reserved, ready)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn slice_for(rate: u64) -> u64 {
+    (rate / 20).max(MIN_SLICE)
+}
+
+fn transfer_time(bytes: u64, rate: u64) -> Duration {
+    Duration::from_secs_f64(bytes as f64 / rate as f64)
+}
+
+/// 按滑动窗口计算单个上传的当前吞吐量
+pub(crate) struct ThroughputMeter {
+    /// (时刻, 累计已上传字节)
+    samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl ThroughputMeter {
+    pub fn new() -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 记录累计上传字节数，返回窗口内的平均速率（字节/秒）
+    pub fn record(&self, uploaded_bytes: u64) -> u64 {
+        let now = Instant::now();
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        // 续传或重新开始时累计值会回退，此前的样本不再可比
+        if samples
+            .back()
+            .is_some_and(|(_, bytes)| *bytes > uploaded_bytes)
+        {
+            samples.clear();
+        }
+        samples.push_back((now, uploaded_bytes));
+        while samples.len() > 2 && now - samples[1].0 >= THROUGHPUT_WINDOW {
+            samples.pop_front();
+        }
+        let (oldest_at, oldest_bytes) = samples[0];
+        let elapsed = (now - oldest_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        ((uploaded_bytes - oldest_bytes) as f64 / elapsed) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// 模拟上传：每块发送前取额度，发送本身不耗时；返回完成时刻
+    async fn mock_upload(limiter: Arc<BandwidthLimiter>, total: u64, chunk: u64) -> Instant {
+        let mut sent = 0;
+        while sent < total {
+            let len = chunk.min(total - sent);
+            limiter.acquire(len).await;
+            sent += len;
+        }
+        Instant::now()
+    }
+
+    #[test]
+    fn test_concurrent_uploads_share_cap() {
+        // 切片为 16 KiB
+        const CAP: u64 = 320 * 1024;
+        let limiter = Arc::new(BandwidthLimiter::new(CAP));
+        let started = Instant::now();
+        let (a, b) = tauri::async_runtime::block_on(async {
+            let a = tokio::spawn(mock_upload(limiter.clone(), 256 * 1024, 128 * 1024));
+            let b = tokio::spawn(mock_upload(limiter.clone(), 256 * 1024, 32 * 1024));
+            (a.await.unwrap(), b.await.unwrap())
+        });
+
+        // 共 512 KiB，扣除一个切片的突发后应耗时约 1.55 秒
+        let elapsed = a.max(b) - started;
+        let expected = transfer_time(512 * 1024 - slice_for(CAP), CAP);
+        assert!(elapsed >= expected.mul_f64(0.9), "耗时 {:?}", elapsed);
+        assert!(elapsed <= expected.mul_f64(1.25), "耗时 {:?}", elapsed);
+        let throughput = (512 * 1024) as f64 / elapsed.as_secs_f64();
+        assert!(throughput <= CAP as f64 * 1.1, "吞吐量 {}", throughput);
+        // 两个上传交替取额度，分块大小不同也几乎同时完成
+        let gap = if a > b { a - b } else { b - a };
+        assert!(gap <= elapsed / 4, "完成时间相差 {:?}", gap);
+    }
+
+    #[test]
+    fn test_limit_changes_apply_mid_transfer() {
+        let limiter = Arc::new(BandwidthLimiter::new(64 * 1024));
+        let started = Instant::now();
+        tauri::async_runtime::block_on(async {
+            let upload = tokio::spawn(mock_upload(limiter.clone(), 4 * 1024 * 1024, 1024 * 1024));
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            // 按 64 KiB/s 需要一分钟以上；放开限制后最多再等一个切片（0.25 秒）
+            limiter.set_limit(0);
+            upload.await.unwrap();
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(limiter.limit(), 0);
+
+        // 不限速时不等待
+        let started = Instant::now();
+        tauri::async_runtime::block_on(limiter.acquire(100 * 1024 * 1024));
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_throughput_over_window() {
+        let meter = ThroughputMeter::new();
+        assert_eq!(meter.record(0), 0);
+        std::thread::sleep(Duration::from_millis(200));
+        let rate = meter.record(100_000);
+        assert!((400_000..=520_000).contains(&rate), "速率 {}", rate);
+        // 重新开始时不产生负值
+        assert_eq!(meter.record(10), 0);
+    }
+}
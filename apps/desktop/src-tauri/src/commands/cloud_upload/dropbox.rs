@@ -0,0 +1,804 @@
+//! Dropbox 上传：小文件直接走 `/files/upload`，大文件使用上传会话
+//! （start → append_v2 → finish）按块上传；会话 ID 和已提交偏移量持久化，中断后可续传。
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::RemoteChecksum;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::bandwidth::upload_limiter;
+use super::session::{FileIdentity, SessionSlot, UploadSession, UploadSessionStore};
+use super::{
+    parse_rfc3339, with_retries, CloudFileEntry, CloudFilePage, RemoteFile, UploadConfig,
+    LIST_PAGE_SIZE,
+};
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+const DROPBOX_API_URL: &str = "https://api.dropboxapi.com/2";
+const DROPBOX_CONTENT_URL: &str = "https://content.dropboxapi.com/2";
+
+/// `/files/upload` 只接受 150 MB 以内的文件
+const SIMPLE_UPLOAD_LIMIT: u64 = 150 * 1024 * 1024;
+
+/// 会话上传的每块大小
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Dropbox 上传会话最长可用 7 天
+pub(super) const SESSION_MAX_AGE_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// 上传完成后 Dropbox 返回的文件元数据
+#[derive(Debug, Deserialize)]
+pub(crate) struct DropboxFile {
+    pub id: String,
+    #[serde(default)]
+    pub path_display: Option<String>,
+    pub size: u64,
+    /// 按 Dropbox 分块方案计算的内容哈希
+    #[serde(default)]
+    pub content_hash: Option<String>,
+}
+
+impl DropboxFile {
+    fn into_remote(self) -> RemoteFile {
+        RemoteFile {
+            id: self.id,
+            path: self.path_display,
+            size: Some(self.size),
+            checksum: self.content_hash.map(RemoteChecksum::DropboxContentHash),
+        }
+    }
+}
+
+/// `Dropbox-API-Arg` 头只能是 ASCII，非 ASCII 字符按 JSON 的 `\uXXXX` 转义
+fn header_arg(arg: &Value) -> String {
+    let mut out = String::new();
+    for c in arg.to_string().chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    out
+}
+
+/// 目标路径 + 文件名；Dropbox 会自动创建不存在的中间目录
+fn dropbox_path(target_path: &str, file_name: &str) -> String {
+    let dir = target_path.trim_matches('/');
+    if dir.is_empty() {
+        format!("/{}", file_name)
+    } else {
+        format!("/{}/{}", dir, file_name)
+    }
+}
+
+/// 409 响应中的会话错误类型，如 `incorrect_offset`、`not_found`
+fn session_error_tag(e: &CommandError) -> Option<&str> {
+    e.details.as_ref()?["error"][".tag"].as_str()
+}
+
+fn read_chunk(file: &mut File, start: u64, len: u64) -> Result<Vec<u8>, CommandError> {
+    let mut buffer = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut buffer))
+        .map_err(|e| CommandError::io("读取文件块失败", &e))?;
+    Ok(buffer)
+}
+
+pub(crate) struct DropboxUploader<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    content_url: String,
+    chunk_size: u64,
+    simple_limit: u64,
+    retry_backoff: Duration,
+    sessions: &'a UploadSessionStore,
+}
+
+impl<'a> DropboxUploader<'a> {
+    pub fn new(access_token: &'a str, sessions: &'a UploadSessionStore) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            content_url: DROPBOX_CONTENT_URL.to_string(),
+            chunk_size: CHUNK_SIZE,
+            simple_limit: SIMPLE_UPLOAD_LIMIT,
+            retry_backoff: Duration::from_secs(1),
+            sessions,
+        }
+    }
+
+    /// 调用 content 端点：参数放在 `Dropbox-API-Arg` 头，请求体是文件数据
+    async fn call(&self, endpoint: &str, arg: &Value, body: &[u8]) -> Result<Value, CommandError> {
+        let context = format!("请求 Dropbox {} 失败", endpoint);
+        upload_limiter().acquire(body.len() as u64).await;
+        let response = self
+            .client
+            .post(format!("{}/{}", self.content_url, endpoint))
+            .bearer_auth(self.access_token)
+            .header("Dropbox-API-Arg", header_arg(arg))
+            .header("Content-Type", "application/octet-stream")
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| request_error(&context, &e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if status.is_success() {
+            if text.trim().is_empty() {
+                return Ok(Value::Null);
+            }
+            return serde_json::from_str(&text).map_err(|e| parse_error("解析上传响应失败", e));
+        }
+
+        // 409 是业务错误，保留结构化的 error 供调用方判断（如 incorrect_offset）
+        if status.as_u16() == 409 {
+            let error_body: Value = serde_json::from_str(&text).unwrap_or_default();
+            let summary = error_body["error_summary"].as_str().unwrap_or(&text);
+            return Err(status_error(&context, status, summary)
+                .with_details(json!({ "status": 409, "error": error_body["error"] })));
+        }
+        Err(status_error(&context, status, &text))
+    }
+
+    /// 上传单个文件到 `dropbox_path`（重名时自动改名）；`on_progress(bytes_sent, total)` 在每块确认后调用
+    pub async fn upload_file(
+        &self,
+        path: &Path,
+        dropbox_path: &str,
+        slot: &SessionSlot,
+        on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<DropboxFile, CommandError> {
+        let identity = FileIdentity::of(path)?;
+        let total = identity.size;
+        let commit = json!({
+            "path": dropbox_path,
+            "mode": "add",
+            "autorename": true,
+            "mute": false
+        });
+
+        let result = if total < self.simple_limit {
+            debug!("文件小于 {} 字节，直接上传", self.simple_limit);
+            let data = std::fs::read(path).map_err(|e| CommandError::io("读取文件失败", &e))?;
+            let (commit, data) = (&commit, &data);
+            with_retries("上传文件", self.retry_backoff, move || {
+                self.call("files/upload", commit, data)
+            })
+            .await?
+        } else {
+            self.upload_in_session(path, identity, slot, &commit, on_progress)
+                .await?
+        };
+
+        let file: DropboxFile =
+            serde_json::from_value(result).map_err(|e| parse_error("解析上传响应失败", e))?;
+        on_progress(total, total);
+        if file.size != total {
+            error!("上传后大小不一致: 本地 {}，远端 {}", total, file.size);
+            return Err(CommandError::internal(format!(
+                "上传后文件大小不一致（本地 {} 字节，远端 {} 字节）",
+                total, file.size
+            )));
+        }
+        Ok(file)
+    }
+
+    async fn start_session(
+        &self,
+        path: &Path,
+        slot: &SessionSlot,
+        file: &mut File,
+        identity: FileIdentity,
+    ) -> Result<UploadSession, CommandError> {
+        let chunk = read_chunk(file, 0, self.chunk_size.min(identity.size))?;
+        let arg = json!({ "close": false });
+        let (arg, body) = (&arg, &chunk);
+        let result = with_retries("开始上传会话", self.retry_backoff, move || {
+            self.call("files/upload_session/start", arg, body)
+        })
+        .await?;
+        let session_id = result["session_id"]
+            .as_str()
+            .ok_or_else(|| CommandError::internal("响应中没有上传会话 ID"))?;
+        info!("已创建 Dropbox 上传会话: {}", session_id);
+
+        let mut session = UploadSessionStore::new_session(
+            &slot.origin,
+            &path.to_string_lossy(),
+            identity,
+            session_id.to_string(),
+        );
+        session.committed = chunk.len() as u64;
+        Ok(session)
+    }
+
+    async fn upload_in_session(
+        &self,
+        path: &Path,
+        identity: FileIdentity,
+        slot: &SessionSlot,
+        commit: &Value,
+        on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<Value, CommandError> {
+        let total = identity.size;
+        let mut file = File::open(path).map_err(|e| CommandError::io("打开文件失败", &e))?;
+
+        let mut session = match self.sessions.load(&slot.key, &identity, SESSION_MAX_AGE_MS) {
+            Some(saved) => {
+                info!("从 {} / {} 字节处续传", saved.committed, total);
+                saved
+            }
+            None => self.start_session(path, slot, &mut file, identity).await?,
+        };
+        self.sessions.save(&slot.key, &session)?;
+        on_progress(session.committed, total);
+
+        while session.committed < total {
+            let len = self.chunk_size.min(total - session.committed);
+            let chunk = read_chunk(&mut file, session.committed, len)?;
+            let arg = json!({
+                "cursor": { "session_id": session.session, "offset": session.committed },
+                "close": false
+            });
+            let (arg_ref, body) = (&arg, &chunk);
+            let appended = with_retries("上传分块", self.retry_backoff, move || {
+                self.call("files/upload_session/append_v2", arg_ref, body)
+            })
+            .await;
+
+            match appended {
+                Ok(_) => session.committed += len,
+                Err(e) => match session_error_tag(&e) {
+                    // 服务端实际接收的字节数与本地记录不一致（例如上次请求已送达但响应丢失）
+                    Some("incorrect_offset") => {
+                        let correct = e.details.as_ref().and_then(|d| {
+                            d["error"]["correct_offset"]
+                                .as_u64()
+                                .filter(|offset| *offset <= total)
+                        });
+                        let Some(correct) = correct else {
+                            return Err(e);
+                        };
+                        warn!("上传偏移量不一致，从服务端记录的 {} 字节处继续", correct);
+                        session.committed = correct;
+                    }
+                    Some("not_found" | "closed") => {
+                        self.sessions.remove(&slot.key);
+                        return Err(CommandError::new(
+                            ErrorCode::Network,
+                            "上传会话已失效，请重试",
+                        ));
+                    }
+                    _ => return Err(e),
+                },
+            }
+            self.sessions.save(&slot.key, &session)?;
+            on_progress(session.committed, total);
+        }
+
+        let arg = json!({
+            "cursor": { "session_id": session.session, "offset": total },
+            "commit": commit
+        });
+        let arg = &arg;
+        let result = with_retries("完成上传会话", self.retry_backoff, move || {
+            self.call("files/upload_session/finish", arg, &[])
+        })
+        .await?;
+        self.sessions.remove(&slot.key);
+        Ok(result)
+    }
+}
+
+/// 上传文件到 Dropbox 的 `config.target_path` 下，返回文件 ID 和最终路径
+pub(crate) async fn upload_to_dropbox(
+    file_path: &str,
+    config: &UploadConfig,
+    sessions: &UploadSessionStore,
+    on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+) -> Result<RemoteFile, CommandError> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        error!("文件不存在: {}", file_path);
+        return Err(CommandError::new(
+            ErrorCode::PathNotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| CommandError::new(ErrorCode::InvalidInput, "无法获取文件名"))?;
+
+    let target = dropbox_path(&config.target_path, file_name);
+    info!("上传到 Dropbox 路径: {}", target);
+    let slot = SessionSlot::new(config, file_path, &target);
+    let file = DropboxUploader::new(&config.access_token, sessions)
+        .upload_file(path, &target, &slot, on_progress)
+        .await?;
+    info!(
+        "上传成功，文件ID: {}，路径: {:?}",
+        file.id, file.path_display
+    );
+    Ok(file.into_remote())
+}
+
+/// 上传以外的文件接口（RPC 端点，参数以 JSON 请求体提交）：查询元数据、删除
+pub(crate) struct DropboxFiles<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    api_url: String,
+}
+
+impl<'a> DropboxFiles<'a> {
+    pub fn new(access_token: &'a str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            api_url: DROPBOX_API_URL.to_string(),
+        }
+    }
+
+    async fn rpc(&self, endpoint: &str, body: &Value) -> Result<Value, CommandError> {
+        let context = format!("请求 Dropbox {} 失败", endpoint);
+        let response = self
+            .client
+            .post(format!("{}/{}", self.api_url, endpoint))
+            .bearer_auth(self.access_token)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| request_error(&context, &e))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(status_error(&context, status, &text));
+        }
+        serde_json::from_str(&text).map_err(|e| parse_error(&context, e))
+    }
+
+    /// `id` 可以是文件 ID（`id:...`）或路径
+    pub async fn get(&self, id: &str) -> Result<RemoteFile, CommandError> {
+        let metadata = self
+            .rpc("files/get_metadata", &json!({ "path": id }))
+            .await?;
+        let file: DropboxFile =
+            serde_json::from_value(metadata).map_err(|e| parse_error("解析文件信息失败", e))?;
+        Ok(file.into_remote())
+    }
+
+    /// 列出 `path` 目录下的一页文件；`page_token` 是 list_folder 返回的 cursor
+    pub async fn list(
+        &self,
+        path: &str,
+        page_token: Option<&str>,
+    ) -> Result<CloudFilePage, CommandError> {
+        let result = match page_token {
+            Some(cursor) => {
+                self.rpc("files/list_folder/continue", &json!({ "cursor": cursor }))
+                    .await?
+            }
+            None => {
+                // 根目录用空字符串表示
+                let dir = path.trim_matches('/');
+                let path = if dir.is_empty() {
+                    String::new()
+                } else {
+                    format!("/{}", dir)
+                };
+                self.rpc(
+                    "files/list_folder",
+                    &json!({ "path": path, "limit": LIST_PAGE_SIZE }),
+                )
+                .await?
+            }
+        };
+
+        let entries = result["entries"]
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|e| {
+                        let is_dir = match e[".tag"].as_str()? {
+                            "folder" => true,
+                            "file" => false,
+                            // deleted 等其他条目不展示
+                            _ => return None,
+                        };
+                        Some(CloudFileEntry {
+                            id: e["id"].as_str()?.to_string(),
+                            name: e["name"].as_str()?.to_string(),
+                            size: e["size"].as_u64().unwrap_or(0),
+                            modified: e["server_modified"].as_str().and_then(parse_rfc3339),
+                            is_dir,
+                            provider: "dropbox".to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let next_page_token = if result["has_more"].as_bool().unwrap_or(false) {
+            result["cursor"].as_str().map(str::to_string)
+        } else {
+            None
+        };
+        Ok(CloudFilePage {
+            entries,
+            next_page_token,
+        })
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), CommandError> {
+        self.rpc("files/delete_v2", &json!({ "path": id })).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// 模拟 Dropbox content 端点的服务端状态
+    #[derive(Default)]
+    struct MockDropbox {
+        /// 每次请求的 (端点, Dropbox-API-Arg, 请求体长度)
+        calls: Vec<(String, Value, usize)>,
+        /// 会话 ID -> 已接收的数据
+        sessions: HashMap<String, Vec<u8>>,
+        /// 前 N 个 append_v2 请求返回 503
+        fail_appends: usize,
+    }
+
+    fn start_mock_dropbox(state: Arc<Mutex<MockDropbox>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = Vec::new();
+                let _ = request.as_reader().read_to_end(&mut body);
+                let arg: Value = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Dropbox-API-Arg"))
+                    .map(|h| serde_json::from_str(h.value.as_str()).unwrap())
+                    .unwrap_or_default();
+                let endpoint = request.url().trim_start_matches('/').to_string();
+
+                let mut s = state.lock().unwrap();
+                s.calls.push((endpoint.clone(), arg.clone(), body.len()));
+                let (status, reply) = match endpoint.as_str() {
+                    "files/upload" => (
+                        200,
+                        json!({ "id": "id:simple", "path_display": arg["path"], "size": body.len() }),
+                    ),
+                    "files/upload_session/start" => {
+                        s.sessions.insert("sess-1".to_string(), body);
+                        (200, json!({ "session_id": "sess-1" }))
+                    }
+                    "files/upload_session/append_v2" if s.fail_appends > 0 => {
+                        s.fail_appends -= 1;
+                        (503, json!({}))
+                    }
+                    "files/upload_session/append_v2" => {
+                        let session_id = arg["cursor"]["session_id"].as_str().unwrap();
+                        let data = s.sessions.get_mut(session_id).unwrap();
+                        let offset = arg["cursor"]["offset"].as_u64().unwrap() as usize;
+                        if offset == data.len() {
+                            data.extend_from_slice(&body);
+                            (200, Value::Null)
+                        } else {
+                            (
+                                409,
+                                json!({
+                                    "error_summary": "incorrect_offset/..",
+                                    "error": { ".tag": "incorrect_offset", "correct_offset": data.len() }
+                                }),
+                            )
+                        }
+                    }
+                    "files/upload_session/finish" => {
+                        let session_id = arg["cursor"]["session_id"].as_str().unwrap();
+                        let data = &s.sessions[session_id];
+                        // 测试数据小于 4 MB，只有一个分块
+                        let content_hash = format!("{:x}", Sha256::digest(Sha256::digest(data)));
+                        (
+                            200,
+                            json!({
+                                "id": "id:session",
+                                "path_display": arg["commit"]["path"],
+                                "size": data.len(),
+                                "content_hash": content_hash
+                            }),
+                        )
+                    }
+                    _ => (404, json!({})),
+                };
+                let _ = request.respond(
+                    tiny_http::Response::from_string(reply.to_string()).with_status_code(status),
+                );
+            }
+        });
+        base
+    }
+
+    fn test_uploader<'a>(base: &str, sessions: &'a UploadSessionStore) -> DropboxUploader<'a> {
+        DropboxUploader {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            content_url: base.to_string(),
+            chunk_size: 4,
+            simple_limit: 5,
+            retry_backoff: Duration::ZERO,
+            sessions,
+        }
+    }
+
+    fn sample_file(dir: &Path, data: &[u8]) -> std::path::PathBuf {
+        let path = dir.join("data.bin");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn endpoints(state: &Mutex<MockDropbox>) -> Vec<(String, u64, usize)> {
+        state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .map(|(endpoint, arg, len)| {
+                let offset = arg["cursor"]["offset"].as_u64().unwrap_or_default();
+                (endpoint.clone(), offset, *len)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_header_arg_and_path() {
+        assert_eq!(
+            header_arg(&json!({ "path": "/备份/😀.txt" })),
+            r#"{"path":"/\u5907\u4efd/\ud83d\ude00.txt"}"#
+        );
+        assert_eq!(dropbox_path("/", "a.txt"), "/a.txt");
+        assert_eq!(dropbox_path("/备份/2024/", "a.txt"), "/备份/2024/a.txt");
+    }
+
+    #[test]
+    fn test_small_file_uses_simple_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = sample_file(dir.path(), b"abc");
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let state = Arc::new(Mutex::new(MockDropbox::default()));
+        let base = start_mock_dropbox(state.clone());
+
+        let file = tauri::async_runtime::block_on(test_uploader(&base, &sessions).upload_file(
+            &path,
+            "/备份/data.bin",
+            &SessionSlot::for_test("key"),
+            &|_, _| {},
+        ))
+        .unwrap();
+        assert_eq!(file.id, "id:simple");
+        let calls = &state.lock().unwrap().calls;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "files/upload");
+        assert_eq!(calls[0].1["path"], "/备份/data.bin");
+        assert_eq!(calls[0].1["mode"], "add");
+    }
+
+    #[test]
+    fn test_session_upload_retries_transient_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = b"0123456789";
+        let path = sample_file(dir.path(), data);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let state = Arc::new(Mutex::new(MockDropbox {
+            fail_appends: 1,
+            ..Default::default()
+        }));
+        let base = start_mock_dropbox(state.clone());
+        let progress = Mutex::new(Vec::new());
+
+        let file = tauri::async_runtime::block_on(test_uploader(&base, &sessions).upload_file(
+            &path,
+            "/备份/data.bin",
+            &SessionSlot::for_test("key"),
+            &|sent, _| progress.lock().unwrap().push(sent),
+        ))
+        .unwrap();
+        assert_eq!(file.id, "id:session");
+        assert_eq!(file.path_display.as_deref(), Some("/备份/data.bin"));
+        assert_eq!(
+            file.into_remote().checksum,
+            Some(RemoteChecksum::DropboxContentHash(
+                ai_disk_executor::dropbox_content_hash_file(&path).unwrap()
+            ))
+        );
+
+        // 4 字节一块：start 带第一块，503 的那块原样重试，finish 不带数据
+        assert_eq!(
+            endpoints(&state),
+            vec![
+                ("files/upload_session/start".to_string(), 0, 4),
+                ("files/upload_session/append_v2".to_string(), 4, 4),
+                ("files/upload_session/append_v2".to_string(), 4, 4),
+                ("files/upload_session/append_v2".to_string(), 8, 2),
+                ("files/upload_session/finish".to_string(), 10, 0),
+            ]
+        );
+        let s = state.lock().unwrap();
+        let finish_arg = &s.calls.last().unwrap().1;
+        assert_eq!(
+            finish_arg["commit"],
+            json!({ "path": "/备份/data.bin", "mode": "add", "autorename": true, "mute": false })
+        );
+        assert_eq!(s.sessions["sess-1"], data);
+        assert_eq!(*progress.lock().unwrap(), vec![4, 8, 10, 10]);
+    }
+
+    #[test]
+    fn test_resume_follows_correct_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = b"0123456789";
+        let path = sample_file(dir.path(), data);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+
+        // 本地记录只提交到 4 字节，但服务端实际已收到 8 字节（响应丢失）
+        let mut saved = UploadSessionStore::new_session(
+            &SessionSlot::for_test("key").origin,
+            "data.bin",
+            FileIdentity::of(&path).unwrap(),
+            "sess-1".to_string(),
+        );
+        saved.committed = 4;
+        sessions.save("key", &saved).unwrap();
+        let state = Arc::new(Mutex::new(MockDropbox::default()));
+        state
+            .lock()
+            .unwrap()
+            .sessions
+            .insert("sess-1".to_string(), data[..8].to_vec());
+        let base = start_mock_dropbox(state.clone());
+
+        tauri::async_runtime::block_on(test_uploader(&base, &sessions).upload_file(
+            &path,
+            "/data.bin",
+            &SessionSlot::for_test("key"),
+            &|_, _| {},
+        ))
+        .unwrap();
+        assert_eq!(
+            endpoints(&state),
+            vec![
+                ("files/upload_session/append_v2".to_string(), 4, 4),
+                ("files/upload_session/append_v2".to_string(), 8, 2),
+                ("files/upload_session/finish".to_string(), 10, 0),
+            ]
+        );
+        assert_eq!(state.lock().unwrap().sessions["sess-1"], data);
+    }
+
+    #[test]
+    fn test_dropbox_files_get_and_delete() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let seen = calls.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let body: Value = serde_json::from_str(&body).unwrap();
+                seen.lock()
+                    .unwrap()
+                    .push((request.url().to_string(), body["path"].clone()));
+                let reply = json!({
+                    "id": "id:1",
+                    "path_display": "/a.txt",
+                    "size": 3,
+                    "content_hash": "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+                });
+                let reply = if request.url().ends_with("delete_v2") {
+                    json!({ "metadata": reply })
+                } else {
+                    reply
+                };
+                let _ = request.respond(tiny_http::Response::from_string(reply.to_string()));
+            }
+        });
+        let files = DropboxFiles {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base,
+        };
+
+        let file = tauri::async_runtime::block_on(files.get("id:1")).unwrap();
+        assert_eq!(file.size, Some(3));
+        assert_eq!(file.path.as_deref(), Some("/a.txt"));
+        assert_eq!(
+            file.checksum.unwrap().value(),
+            "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+        );
+        tauri::async_runtime::block_on(files.delete("id:1")).unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                ("/files/get_metadata".to_string(), json!("id:1")),
+                ("/files/delete_v2".to_string(), json!("id:1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_folder_continues_with_cursor() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let seen = calls.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let body: Value = serde_json::from_str(&body).unwrap();
+                seen.lock().unwrap().push((request.url().to_string(), body));
+                let reply = if request.url().ends_with("/continue") {
+                    json!({
+                        "entries": [
+                            { ".tag": "folder", "id": "id:d", "name": "照片" },
+                            { ".tag": "deleted", "name": "old.txt" }
+                        ],
+                        "cursor": "c2",
+                        "has_more": false
+                    })
+                } else {
+                    json!({
+                        "entries": [{
+                            ".tag": "file",
+                            "id": "id:f",
+                            "name": "a.mp4",
+                            "size": 2048,
+                            "server_modified": "2024-01-02T03:04:05Z"
+                        }],
+                        "cursor": "c1",
+                        "has_more": true
+                    })
+                };
+                let _ = request.respond(tiny_http::Response::from_string(reply.to_string()));
+            }
+        });
+        let files = DropboxFiles {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base,
+        };
+
+        let first = tauri::async_runtime::block_on(files.list("/备份/", None)).unwrap();
+        assert_eq!(first.next_page_token.as_deref(), Some("c1"));
+        assert_eq!(first.entries[0].size, 2048);
+        assert_eq!(first.entries[0].modified, Some(1_704_164_645));
+        let second = tauri::async_runtime::block_on(files.list("/备份/", Some("c1"))).unwrap();
+        assert!(second.next_page_token.is_none());
+        assert_eq!(second.entries.len(), 1);
+        assert!(second.entries[0].is_dir);
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[0].0, "/files/list_folder");
+        assert_eq!(calls[0].1["path"], "/备份");
+        assert_eq!(calls[1].0, "/files/list_folder/continue");
+        assert_eq!(calls[1].1["cursor"], "c1");
+    }
+}
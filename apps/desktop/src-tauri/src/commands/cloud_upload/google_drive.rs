@@ -0,0 +1,951 @@
+//! Google Drive 上传：使用 Resumable Upload 协议按块流式上传，内存占用与文件大小无关；
+//! 会话 URI 和已确认偏移量持久化到存储根目录，中断后重试从服务端已接收的位置继续。
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::RemoteChecksum;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+
+use super::bandwidth::upload_limiter;
+use super::session::{FileIdentity, SessionSlot, UploadSession, UploadSessionStore};
+use super::{
+    parse_rfc3339, CloudFileEntry, CloudFilePage, RemoteFile, UploadConfig, LIST_PAGE_SIZE,
+};
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+const DRIVE_API_URL: &str = "https://www.googleapis.com/drive/v3";
+const DRIVE_UPLOAD_URL: &str = "https://www.googleapis.com/upload/drive/v3";
+
+/// 每块大小；Drive 要求除最后一块外必须是 256 KiB 的整数倍
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Drive 上传会话约一周后失效
+pub(super) const SESSION_MAX_AGE_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// 上传完成后 Drive 返回的文件信息
+#[derive(Debug, Deserialize)]
+pub(crate) struct DriveFile {
+    pub id: String,
+    /// Drive 以字符串返回字节数
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default, rename = "md5Checksum")]
+    pub md5_checksum: Option<String>,
+}
+
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+/// 在 `parent_id` 下按名称查找文件夹
+async fn find_folder(
+    client: &reqwest::Client,
+    api_url: &str,
+    access_token: &str,
+    parent_id: &str,
+    name: &str,
+) -> Result<Option<String>, CommandError> {
+    let query = format!(
+        "name='{}' and '{}' in parents and mimeType='{}' and trashed=false",
+        name.replace('\'', "\\'"),
+        parent_id,
+        FOLDER_MIME_TYPE
+    );
+    let response = client
+        .get(format!("{}/files", api_url))
+        .query(&[("q", query.as_str()), ("fields", "files(id)")])
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| request_error("查询文件夹失败", &e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(status_error("查询文件夹失败", status, &error_text));
+    }
+    let result: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| parse_error("解析查询响应失败", e))?;
+    Ok(result["files"]
+        .as_array()
+        .and_then(|files| files.first())
+        .and_then(|f| f["id"].as_str())
+        .map(str::to_string))
+}
+
+/// 上传和查询元数据时请求的字段
+const FILE_FIELDS: &str = "id,size,md5Checksum";
+
+impl DriveFile {
+    fn into_remote(self) -> RemoteFile {
+        RemoteFile {
+            size: self.size.as_deref().and_then(|s| s.parse().ok()),
+            checksum: self.md5_checksum.map(RemoteChecksum::Md5),
+            id: self.id,
+            path: None,
+        }
+    }
+}
+
+/// 一次 PUT（上传块或查询状态）的结果
+enum ChunkOutcome {
+    /// 308 Resume Incomplete：服务端已确认接收的字节数
+    Incomplete(u64),
+    Complete(DriveFile),
+    /// 会话已失效（404/410），需要重新发起
+    Expired,
+}
+
+/// 解析 308 响应的 `Range: bytes=0-N`，返回已接收的字节数；没有 Range 表示尚未接收任何字节
+fn committed_from_range(range: Option<&str>) -> u64 {
+    range
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.split_once('-'))
+        .and_then(|(_, end)| end.trim().parse::<u64>().ok())
+        .map_or(0, |end| end + 1)
+}
+
+/// 把上传块或查询状态的响应归类
+async fn read_outcome(response: reqwest::Response) -> Result<ChunkOutcome, CommandError> {
+    let status = response.status();
+    match status.as_u16() {
+        200 | 201 => {
+            let file = response
+                .json()
+                .await
+                .map_err(|e| parse_error("解析上传响应失败", e))?;
+            Ok(ChunkOutcome::Complete(file))
+        }
+        308 => Ok(ChunkOutcome::Incomplete(committed_from_range(
+            response
+                .headers()
+                .get("range")
+                .and_then(|v| v.to_str().ok()),
+        ))),
+        404 | 410 => Ok(ChunkOutcome::Expired),
+        _ => {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(status_error("上传块失败", status, &error_text))
+        }
+    }
+}
+
+pub(crate) struct DriveUploader<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    api_url: String,
+    upload_url: String,
+    chunk_size: u64,
+    sessions: &'a UploadSessionStore,
+}
+
+impl<'a> DriveUploader<'a> {
+    pub fn new(access_token: &'a str, sessions: &'a UploadSessionStore) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            api_url: DRIVE_API_URL.to_string(),
+            upload_url: DRIVE_UPLOAD_URL.to_string(),
+            chunk_size: CHUNK_SIZE,
+            sessions,
+        }
+    }
+
+    /// 逐级查找或创建目标文件夹，返回最后一级的文件夹 ID
+    pub async fn create_or_get_folder(&self, path: &str) -> Result<String, CommandError> {
+        debug!("创建或获取文件夹: {}", path);
+
+        let mut parent_id = "root".to_string();
+        for folder_name in path.trim_matches('/').split('/').filter(|p| !p.is_empty()) {
+            debug!("处理文件夹: {}，父文件夹ID: {}", folder_name, parent_id);
+            if let Some(id) = find_folder(
+                &self.client,
+                &self.api_url,
+                self.access_token,
+                &parent_id,
+                folder_name,
+            )
+            .await?
+            {
+                parent_id = id;
+                debug!("找到现有文件夹，ID: {}", parent_id);
+                continue;
+            }
+
+            debug!("文件夹不存在，创建新文件夹: {}", folder_name);
+            let metadata = serde_json::json!({
+                "name": folder_name,
+                "mimeType": FOLDER_MIME_TYPE,
+                "parents": [parent_id]
+            });
+            let response = self
+                .client
+                .post(format!("{}/files", self.api_url))
+                .bearer_auth(self.access_token)
+                .json(&metadata)
+                .send()
+                .await
+                .map_err(|e| request_error("创建文件夹失败", &e))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(status_error("创建文件夹失败", status, &error_text));
+            }
+            let result: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| parse_error("解析创建响应失败", e))?;
+            parent_id = result["id"]
+                .as_str()
+                .ok_or_else(|| CommandError::internal("创建的文件夹没有 ID"))?
+                .to_string();
+            info!("成功创建文件夹: {}，ID: {}", folder_name, parent_id);
+        }
+
+        Ok(parent_id)
+    }
+
+    /// 发起上传会话，返回会话 URI
+    async fn start_session(
+        &self,
+        file_name: &str,
+        folder_id: &str,
+        size: u64,
+    ) -> Result<String, CommandError> {
+        let metadata = serde_json::json!({
+            "name": file_name,
+            "parents": [folder_id]
+        });
+        let response = self
+            .client
+            .post(format!("{}/files", self.upload_url))
+            .query(&[("uploadType", "resumable"), ("fields", FILE_FIELDS)])
+            .bearer_auth(self.access_token)
+            .header("X-Upload-Content-Type", "application/octet-stream")
+            .header("X-Upload-Content-Length", size.to_string())
+            .json(&metadata)
+            .send()
+            .await
+            .map_err(|e| request_error("初始化上传会话失败", &e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(status_error("初始化上传会话失败", status, &error_text));
+        }
+
+        response
+            .headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| CommandError::internal("响应中没有上传 URI"))
+    }
+
+    /// 查询会话已接收的字节数（空 PUT + `Content-Range: bytes */total`）
+    async fn query_status(&self, uri: &str, total: u64) -> Result<ChunkOutcome, CommandError> {
+        let response = self
+            .client
+            .put(uri)
+            .header("Content-Range", format!("bytes */{}", total))
+            .header("Content-Length", "0")
+            .send()
+            .await
+            .map_err(|e| request_error("查询上传进度失败", &e))?;
+        read_outcome(response).await
+    }
+
+    /// 从 `start` 处读取一块并上传
+    async fn put_chunk(
+        &self,
+        uri: &str,
+        file: &mut File,
+        start: u64,
+        total: u64,
+    ) -> Result<ChunkOutcome, CommandError> {
+        let len = self.chunk_size.min(total - start);
+        let mut buffer = vec![0u8; len as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut buffer))
+            .map_err(|e| CommandError::io("读取文件块失败", &e))?;
+
+        let content_range = if total == 0 {
+            "bytes */0".to_string()
+        } else {
+            format!("bytes {}-{}/{}", start, start + len - 1, total)
+        };
+        debug!("上传块: {}", content_range);
+
+        upload_limiter().acquire(len).await;
+        let response = self
+            .client
+            .put(uri)
+            .header("Content-Range", content_range)
+            .header("Content-Length", len.to_string())
+            .body(buffer)
+            .send()
+            .await
+            .map_err(|e| request_error("上传块失败", &e))?;
+        read_outcome(response).await
+    }
+
+    /// 上传单个文件到 `folder_id`；会话键相同的中断上传会被续传。
+    /// `on_progress(bytes_sent, total)` 在每块确认后调用
+    pub async fn upload_file(
+        &self,
+        path: &Path,
+        folder_id: &str,
+        slot: &SessionSlot,
+        on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<DriveFile, CommandError> {
+        let identity = FileIdentity::of(path)?;
+        let total = identity.size;
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| CommandError::new(ErrorCode::InvalidInput, "无法获取文件名"))?;
+        let file_path = path.to_string_lossy();
+
+        // 有未完成的会话时先向服务端确认实际已接收的字节数
+        let mut session = None;
+        if let Some(saved) = self.sessions.load(&slot.key, &identity, SESSION_MAX_AGE_MS) {
+            info!("发现未完成的上传会话，查询已上传进度: {}", file_path);
+            match self.query_status(&saved.session, total).await? {
+                ChunkOutcome::Complete(file) => {
+                    self.sessions.remove(&slot.key);
+                    return verify_size(file, total);
+                }
+                ChunkOutcome::Incomplete(committed) => {
+                    info!("从 {} / {} 字节处续传", committed, total);
+                    session = Some(UploadSession { committed, ..saved });
+                }
+                ChunkOutcome::Expired => {
+                    warn!("上传会话已失效，重新开始上传");
+                    self.sessions.remove(&slot.key);
+                }
+            }
+        }
+        let mut session = match session {
+            Some(session) => session,
+            None => {
+                let uri = self.start_session(file_name, folder_id, total).await?;
+                let session = UploadSessionStore::new_session(
+                    &slot.origin,
+                    &file_path,
+                    identity.clone(),
+                    uri,
+                );
+                self.sessions.save(&slot.key, &session)?;
+                session
+            }
+        };
+
+        let mut file = File::open(path).map_err(|e| CommandError::io("打开文件失败", &e))?;
+        on_progress(session.committed, total);
+
+        loop {
+            match self
+                .put_chunk(&session.session, &mut file, session.committed, total)
+                .await?
+            {
+                ChunkOutcome::Complete(drive_file) => {
+                    self.sessions.remove(&slot.key);
+                    on_progress(total, total);
+                    return verify_size(drive_file, total);
+                }
+                ChunkOutcome::Incomplete(committed) => {
+                    if committed <= session.committed && total > 0 {
+                        // 服务端没有接收任何新字节，避免死循环
+                        error!("上传块未被服务端接收: offset {}", session.committed);
+                        return Err(CommandError::new(
+                            ErrorCode::Network,
+                            "上传块未被服务端接收，请重试",
+                        ));
+                    }
+                    session.committed = committed;
+                    self.sessions.save(&slot.key, &session)?;
+                    on_progress(committed, total);
+                }
+                ChunkOutcome::Expired => {
+                    self.sessions.remove(&slot.key);
+                    return Err(CommandError::new(
+                        ErrorCode::Network,
+                        "上传会话已失效，请重试",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 校验服务端记录的大小与本地一致
+fn verify_size(file: DriveFile, expected: u64) -> Result<DriveFile, CommandError> {
+    match file.size.as_deref().map(str::parse::<u64>) {
+        Some(Ok(size)) if size != expected => {
+            error!("上传后大小不一致: 本地 {}，远端 {}", expected, size);
+            Err(CommandError::internal(format!(
+                "上传后文件大小不一致（本地 {} 字节，远端 {} 字节）",
+                expected, size
+            )))
+        }
+        _ => Ok(file),
+    }
+}
+
+/// 上传文件到 Google Drive 的 `config.target_path` 下，返回文件 ID
+pub(crate) async fn upload_to_google_drive(
+    file_path: &str,
+    config: &UploadConfig,
+    sessions: &UploadSessionStore,
+    on_progress: &(dyn Fn(u64, u64) + Send + Sync),
+) -> Result<RemoteFile, CommandError> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        error!("文件不存在: {}", file_path);
+        return Err(CommandError::new(
+            ErrorCode::PathNotFound,
+            format!("文件不存在: {}", file_path),
+        ));
+    }
+
+    let uploader = DriveUploader::new(&config.access_token, sessions);
+    let folder_id = if config.target_path.trim_matches('/').is_empty() {
+        "root".to_string()
+    } else {
+        uploader.create_or_get_folder(&config.target_path).await?
+    };
+    info!("目标文件夹ID: {}", folder_id);
+
+    let slot = SessionSlot::new(config, file_path, &folder_id);
+    let file = uploader
+        .upload_file(path, &folder_id, &slot, on_progress)
+        .await?;
+    info!("上传成功，文件ID: {}", file.id);
+    Ok(file.into_remote())
+}
+
+/// 上传以外的文件接口：查询元数据、删除
+pub(crate) struct DriveFiles<'a> {
+    client: reqwest::Client,
+    access_token: &'a str,
+    api_url: String,
+}
+
+impl<'a> DriveFiles<'a> {
+    pub fn new(access_token: &'a str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            access_token,
+            api_url: DRIVE_API_URL.to_string(),
+        }
+    }
+
+    pub async fn get(&self, file_id: &str) -> Result<RemoteFile, CommandError> {
+        let response = self
+            .client
+            .get(format!("{}/files/{}", self.api_url, file_id))
+            .query(&[("fields", FILE_FIELDS)])
+            .bearer_auth(self.access_token)
+            .send()
+            .await
+            .map_err(|e| request_error("查询文件信息失败", &e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(status_error("查询文件信息失败", status, &error_text));
+        }
+        let file: DriveFile = response
+            .json()
+            .await
+            .map_err(|e| parse_error("解析文件信息失败", e))?;
+        Ok(file.into_remote())
+    }
+
+    /// 按路径查找文件夹 ID（不创建）
+    async fn folder_id(&self, path: &str) -> Result<String, CommandError> {
+        let mut parent_id = "root".to_string();
+        for name in path.split('/').filter(|p| !p.is_empty()) {
+            parent_id = find_folder(
+                &self.client,
+                &self.api_url,
+                self.access_token,
+                &parent_id,
+                name,
+            )
+            .await?
+            .ok_or_else(|| {
+                CommandError::new(ErrorCode::PathNotFound, format!("文件夹不存在: {}", path))
+            })?;
+        }
+        Ok(parent_id)
+    }
+
+    /// 列出 `path` 目录下的一页文件，文件夹排在前面
+    pub async fn list(
+        &self,
+        path: &str,
+        page_token: Option<&str>,
+    ) -> Result<CloudFilePage, CommandError> {
+        let folder_id = self.folder_id(path).await?;
+        let query = format!("'{}' in parents and trashed=false", folder_id);
+        let page_size = LIST_PAGE_SIZE.to_string();
+        let mut params = vec![
+            ("q", query.as_str()),
+            (
+                "fields",
+                "nextPageToken,files(id,name,size,modifiedTime,mimeType)",
+            ),
+            ("pageSize", page_size.as_str()),
+            ("orderBy", "folder,name"),
+        ];
+        if let Some(token) = page_token {
+            params.push(("pageToken", token));
+        }
+        let response = self
+            .client
+            .get(format!("{}/files", self.api_url))
+            .query(&params)
+            .bearer_auth(self.access_token)
+            .send()
+            .await
+            .map_err(|e| request_error("列出文件失败", &e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(status_error("列出文件失败", status, &error_text));
+        }
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| parse_error("解析文件列表失败", e))?;
+
+        let entries = result["files"]
+            .as_array()
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(|f| {
+                        Some(CloudFileEntry {
+                            id: f["id"].as_str()?.to_string(),
+                            name: f["name"].as_str()?.to_string(),
+                            size: f["size"].as_str().and_then(|s| s.parse().ok()).unwrap_or(0),
+                            modified: f["modifiedTime"].as_str().and_then(parse_rfc3339),
+                            is_dir: f["mimeType"] == FOLDER_MIME_TYPE,
+                            provider: "google_drive".to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(CloudFilePage {
+            entries,
+            next_page_token: result["nextPageToken"].as_str().map(str::to_string),
+        })
+    }
+
+    /// 永久删除文件（不经过回收站）
+    pub async fn delete(&self, file_id: &str) -> Result<(), CommandError> {
+        let response = self
+            .client
+            .delete(format!("{}/files/{}", self.api_url, file_id))
+            .bearer_auth(self.access_token)
+            .send()
+            .await
+            .map_err(|e| request_error("删除文件失败", &e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(status_error("删除文件失败", status, &error_text));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use md5::Digest;
+    use std::sync::{Arc, Mutex};
+
+    const TEST_CHUNK: u64 = 256 * 1024;
+
+    /// 模拟 Drive 上传端点的服务端状态
+    #[derive(Default)]
+    struct MockDrive {
+        received: Vec<u8>,
+        /// 每个 PUT 请求的 Content-Range
+        ranges: Vec<String>,
+        sessions_started: usize,
+        /// 第 N 个数据块请求返回 503（模拟网络中断）
+        fail_chunk: Option<usize>,
+        chunk_requests: usize,
+        /// 完成时报告的大小偏移（模拟远端大小不一致）
+        size_skew: u64,
+    }
+
+    fn start_mock_drive(state: Arc<Mutex<MockDrive>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let session_uri = format!("{}/session/1", base);
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = Vec::new();
+                let _ = request.as_reader().read_to_end(&mut body);
+                let header = |name: &str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.as_str().to_string())
+                };
+                let mut s = state.lock().unwrap();
+
+                if request.url().starts_with("/files") {
+                    s.sessions_started += 1;
+                    let response = tiny_http::Response::empty(200).with_header(
+                        tiny_http::Header::from_bytes(&b"Location"[..], session_uri.as_bytes())
+                            .unwrap(),
+                    );
+                    let _ = request.respond(response);
+                    continue;
+                }
+
+                let range = header("Content-Range").unwrap_or_default();
+                s.ranges.push(range.clone());
+                let total: u64 = range.rsplit('/').next().unwrap().parse().unwrap();
+
+                if !range.starts_with("bytes */") {
+                    s.chunk_requests += 1;
+                    if s.fail_chunk == Some(s.chunk_requests) {
+                        let _ = request.respond(tiny_http::Response::empty(503));
+                        continue;
+                    }
+                    let start: u64 = range["bytes ".len()..]
+                        .split('-')
+                        .next()
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    assert_eq!(start, s.received.len() as u64, "块必须从已提交的位置开始");
+                    s.received.extend_from_slice(&body);
+                }
+
+                let received = s.received.len() as u64;
+                if received == total {
+                    let json = serde_json::json!({
+                        "id": "file-1",
+                        "size": (total + s.size_skew).to_string(),
+                        "md5Checksum": format!("{:x}", md5::Md5::digest(&s.received)),
+                    });
+                    let _ = request.respond(tiny_http::Response::from_string(json.to_string()));
+                } else {
+                    let mut response = tiny_http::Response::empty(308);
+                    if received > 0 {
+                        let value = format!("bytes=0-{}", received - 1);
+                        response = response.with_header(
+                            tiny_http::Header::from_bytes(&b"Range"[..], value.as_bytes()).unwrap(),
+                        );
+                    }
+                    let _ = request.respond(response);
+                }
+            }
+        });
+        base
+    }
+
+    fn test_uploader<'a>(base: &str, sessions: &'a UploadSessionStore) -> DriveUploader<'a> {
+        DriveUploader {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base.to_string(),
+            upload_url: base.to_string(),
+            chunk_size: TEST_CHUNK,
+            sessions,
+        }
+    }
+
+    fn sample_file(dir: &Path, len: usize) -> (std::path::PathBuf, Vec<u8>) {
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let path = dir.join("video.bin");
+        std::fs::write(&path, &data).unwrap();
+        (path, data)
+    }
+
+    #[test]
+    fn test_committed_from_range() {
+        assert_eq!(committed_from_range(None), 0);
+        assert_eq!(committed_from_range(Some("bytes=0-262143")), 262_144);
+        assert_eq!(committed_from_range(Some("garbage")), 0);
+    }
+
+    #[test]
+    fn test_resumes_after_interruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, data) = sample_file(dir.path(), 600 * 1024);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let state = Arc::new(Mutex::new(MockDrive {
+            fail_chunk: Some(2),
+            ..Default::default()
+        }));
+        let base = start_mock_drive(state.clone());
+        let uploader = test_uploader(&base, &sessions);
+        let progress = Mutex::new(Vec::new());
+        let on_progress = |sent: u64, total: u64| progress.lock().unwrap().push((sent, total));
+
+        // 第一次：第二块遇到 503，会话保留在磁盘上
+        let err = tauri::async_runtime::block_on(uploader.upload_file(
+            &path,
+            "root",
+            &SessionSlot::for_test("key-1"),
+            &on_progress,
+        ))
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Network);
+        let identity = FileIdentity::of(&path).unwrap();
+        let saved = sessions
+            .load("key-1", &identity, SESSION_MAX_AGE_MS)
+            .unwrap();
+        assert_eq!(saved.committed, TEST_CHUNK);
+
+        // 重试：先查询状态（308 + Range），再从已提交的偏移继续
+        let file = tauri::async_runtime::block_on(uploader.upload_file(
+            &path,
+            "root",
+            &SessionSlot::for_test("key-1"),
+            &on_progress,
+        ))
+        .unwrap();
+        assert_eq!(file.id, "file-1");
+        assert_eq!(
+            file.into_remote().checksum,
+            Some(RemoteChecksum::Md5(
+                ai_disk_executor::md5_file(&path).unwrap()
+            ))
+        );
+
+        let s = state.lock().unwrap();
+        assert_eq!(s.sessions_started, 1);
+        assert_eq!(s.received, data);
+        let total = data.len();
+        assert_eq!(
+            s.ranges,
+            vec![
+                format!("bytes 0-262143/{}", total),
+                format!("bytes 262144-524287/{}", total),
+                format!("bytes */{}", total),
+                format!("bytes 262144-524287/{}", total),
+                format!("bytes 524288-{}/{}", total - 1, total),
+            ]
+        );
+        assert!(sessions
+            .load("key-1", &identity, SESSION_MAX_AGE_MS)
+            .is_none());
+        assert_eq!(
+            progress.lock().unwrap().last(),
+            Some(&(total as u64, total as u64))
+        );
+    }
+
+    #[test]
+    fn test_resumes_session_saved_before_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, data) = sample_file(dir.path(), 600 * 1024);
+        let state = Arc::new(Mutex::new(MockDrive {
+            received: data[..TEST_CHUNK as usize].to_vec(),
+            ..Default::default()
+        }));
+        let base = start_mock_drive(state.clone());
+
+        // 上次运行提交了第一块后退出
+        let mut saved = UploadSessionStore::new_session(
+            &SessionSlot::for_test("key-4").origin,
+            &path.to_string_lossy(),
+            FileIdentity::of(&path).unwrap(),
+            format!("{}/session/1", base),
+        );
+        saved.committed = TEST_CHUNK;
+        UploadSessionStore::new(dir.path().join("sessions"))
+            .save("key-4", &saved)
+            .unwrap();
+
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        tauri::async_runtime::block_on(test_uploader(&base, &sessions).upload_file(
+            &path,
+            "root",
+            &SessionSlot::for_test("key-4"),
+            &|_, _| {},
+        ))
+        .unwrap();
+
+        let s = state.lock().unwrap();
+        assert_eq!(s.sessions_started, 0);
+        assert_eq!(s.received, data);
+        let total = data.len();
+        assert_eq!(
+            s.ranges,
+            vec![
+                format!("bytes */{}", total),
+                format!("bytes 262144-524287/{}", total),
+                format!("bytes 524288-{}/{}", total - 1, total),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_size_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _) = sample_file(dir.path(), 1000);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let state = Arc::new(Mutex::new(MockDrive {
+            size_skew: 1,
+            ..Default::default()
+        }));
+        let base = start_mock_drive(state);
+        let uploader = test_uploader(&base, &sessions);
+
+        let err = tauri::async_runtime::block_on(uploader.upload_file(
+            &path,
+            "root",
+            &SessionSlot::for_test("key-2"),
+            &|_, _| {},
+        ))
+        .unwrap_err();
+        assert!(err.message.contains("大小不一致"));
+    }
+
+    #[test]
+    fn test_changed_file_discards_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _) = sample_file(dir.path(), 1000);
+        let sessions = UploadSessionStore::new(dir.path().join("sessions"));
+        let identity = FileIdentity::of(&path).unwrap();
+        let session = UploadSessionStore::new_session(
+            &SessionSlot::for_test("key-3").origin,
+            "video.bin",
+            identity.clone(),
+            "http://127.0.0.1:1/session".into(),
+        );
+        sessions.save("key-3", &session).unwrap();
+
+        let changed = FileIdentity {
+            size: identity.size + 1,
+            ..identity
+        };
+        assert!(sessions
+            .load("key-3", &changed, SESSION_MAX_AGE_MS)
+            .is_none());
+        // 作废的会话文件已被删除
+        assert!(sessions
+            .load("key-3", &identity, SESSION_MAX_AGE_MS)
+            .is_none());
+    }
+
+    #[test]
+    fn test_drive_files_get_and_delete() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                seen.lock()
+                    .unwrap()
+                    .push(format!("{} {}", request.method(), request.url()));
+                let response = match request.method() {
+                    tiny_http::Method::Get => tiny_http::Response::from_string(
+                        r#"{"id":"f1","size":"3","md5Checksum":"900150983cd24fb0d6963f7d28e17f72"}"#,
+                    ),
+                    _ => tiny_http::Response::from_string("").with_status_code(204),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        let files = DriveFiles {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base,
+        };
+
+        let file = tauri::async_runtime::block_on(files.get("f1")).unwrap();
+        assert_eq!(file.size, Some(3));
+        assert_eq!(
+            file.checksum,
+            Some(RemoteChecksum::Md5(
+                "900150983cd24fb0d6963f7d28e17f72".into()
+            ))
+        );
+        tauri::async_runtime::block_on(files.delete("f1")).unwrap();
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "GET /files/f1?fields=id%2Csize%2Cmd5Checksum".to_string(),
+                "DELETE /files/f1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_follows_page_token() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let seen = queries.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let url = urlencoding::decode(&request.url().replace('+', " "))
+                    .unwrap()
+                    .into_owned();
+                seen.lock().unwrap().push(url.clone());
+                let reply = if url.contains("fields=files(id)") {
+                    serde_json::json!({ "files": [{ "id": "folder-1" }] })
+                } else if url.contains("pageToken=p2") {
+                    serde_json::json!({ "files": [{
+                        "id": "d1",
+                        "name": "子目录",
+                        "mimeType": FOLDER_MIME_TYPE,
+                        "modifiedTime": "2024-01-02T03:04:05.000Z"
+                    }] })
+                } else {
+                    serde_json::json!({
+                        "nextPageToken": "p2",
+                        "files": [{
+                            "id": "f1",
+                            "name": "a.mp4",
+                            "mimeType": "video/mp4",
+                            "size": "1024",
+                            "modifiedTime": "2024-01-02T03:04:05Z"
+                        }]
+                    })
+                };
+                let _ = request.respond(tiny_http::Response::from_string(reply.to_string()));
+            }
+        });
+        let files = DriveFiles {
+            client: reqwest::Client::new(),
+            access_token: "at",
+            api_url: base,
+        };
+
+        let first = tauri::async_runtime::block_on(files.list("/备份", None)).unwrap();
+        assert_eq!(first.next_page_token.as_deref(), Some("p2"));
+        assert_eq!(first.entries.len(), 1);
+        assert_eq!(first.entries[0].name, "a.mp4");
+        assert_eq!(first.entries[0].size, 1024);
+        assert_eq!(first.entries[0].modified, Some(1_704_164_645));
+        assert!(!first.entries[0].is_dir);
+
+        let second = tauri::async_runtime::block_on(files.list("/备份", Some("p2"))).unwrap();
+        assert!(second.next_page_token.is_none());
+        assert!(second.entries[0].is_dir);
+        assert_eq!(second.entries[0].size, 0);
+
+        let queries = queries.lock().unwrap();
+        assert!(queries[0].contains("name='备份' and 'root' in parents"));
+        assert!(queries[1].contains("q='folder-1' in parents and trashed=false"));
+    }
+}
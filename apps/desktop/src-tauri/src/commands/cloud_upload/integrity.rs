@@ -0,0 +1,158 @@
+//! 上传完整性校验：与上传并行地流式计算本地校验和，上传完成后与服务商报告的校验和比对；
+//! 不一致时删除云端文件，避免留下一个看似可用的损坏副本。
+
+use std::future::Future;
+use std::path::PathBuf;
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::{ChecksumAlgorithm, RemoteChecksum};
+use log::{error, warn};
+use serde_json::json;
+
+use super::RemoteFile;
+
+/// 服务商报告的校验和算法
+pub(crate) fn checksum_algorithm(provider: &str) -> Option<ChecksumAlgorithm> {
+    match provider {
+        "google_drive" | "baidu_netdisk" => Some(ChecksumAlgorithm::Md5),
+        "dropbox" => Some(ChecksumAlgorithm::DropboxContentHash),
+        "aliyun_drive" => Some(ChecksumAlgorithm::Sha1),
+        _ => None,
+    }
+}
+
+/// 在阻塞线程池中计算本地文件的校验和
+pub(crate) async fn local_checksum(
+    path: PathBuf,
+    algorithm: ChecksumAlgorithm,
+) -> Result<RemoteChecksum, CommandError> {
+    tokio::task::spawn_blocking(move || algorithm.compute(&path))
+        .await
+        .map_err(|e| CommandError::internal(format!("计算本地校验和失败: {}", e)))?
+        .map_err(|e| CommandError::io("计算本地校验和失败", &e))
+}
+
+/// 比对云端与本地校验和；不一致时调用 `delete_remote` 删除云端文件并返回 ChecksumMismatch。
+/// 服务商没有报告校验和时只记录警告
+pub(crate) async fn check_integrity<F, Fut>(
+    file: RemoteFile,
+    local: &RemoteChecksum,
+    delete_remote: F,
+) -> Result<RemoteFile, CommandError>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<(), CommandError>>,
+{
+    let remote = match &file.checksum {
+        None => {
+            warn!("云端未返回校验和，跳过完整性校验: {}", file.id);
+            return Ok(file);
+        }
+        Some(remote) if remote.matches(local) => return Ok(file),
+        Some(remote) => remote.clone(),
+    };
+
+    error!(
+        "上传后校验和不一致: 本地 {}，远端 {}（文件ID: {}）",
+        local.value(),
+        remote.value(),
+        file.id
+    );
+    let deleted = match delete_remote(file.id.clone()).await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("删除校验失败的云端文件 {} 失败: {}", file.id, e);
+            false
+        }
+    };
+    let message = if deleted {
+        "上传后校验和不一致，已删除云端文件"
+    } else {
+        "上传后校验和不一致，且删除云端文件失败，请手动删除"
+    };
+    Err(
+        CommandError::new(ErrorCode::ChecksumMismatch, message).with_details(json!({
+            "remote_id": file.id,
+            "expected": local,
+            "actual": remote,
+            "remote_deleted": deleted
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn remote(checksum: Option<RemoteChecksum>) -> RemoteFile {
+        RemoteFile {
+            id: "remote-1".into(),
+            path: None,
+            size: Some(3),
+            checksum,
+        }
+    }
+
+    #[test]
+    fn test_provider_algorithms_match_known_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        std::fs::write(&path, b"abc").unwrap();
+        let cases = [
+            ("google_drive", "900150983cd24fb0d6963f7d28e17f72"),
+            ("baidu_netdisk", "900150983cd24fb0d6963f7d28e17f72"),
+            ("aliyun_drive", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                "dropbox",
+                "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358",
+            ),
+        ];
+        for (provider, expected) in cases {
+            let algorithm = checksum_algorithm(provider).unwrap();
+            let local =
+                tauri::async_runtime::block_on(local_checksum(path.clone(), algorithm)).unwrap();
+            assert_eq!(local.value(), expected, "{}", provider);
+        }
+        assert!(checksum_algorithm("onedrive").is_none());
+    }
+
+    #[test]
+    fn test_mismatch_deletes_remote_file() {
+        let deleted = Mutex::new(Vec::new());
+        let local = RemoteChecksum::Md5("900150983cd24fb0d6963f7d28e17f72".into());
+        let err = tauri::async_runtime::block_on(check_integrity(
+            remote(Some(RemoteChecksum::Md5("00".into()))),
+            &local,
+            |id| {
+                deleted.lock().unwrap().push(id);
+                async { Ok(()) }
+            },
+        ))
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::ChecksumMismatch);
+        assert_eq!(err.details.unwrap()["remote_deleted"], true);
+        assert_eq!(*deleted.lock().unwrap(), vec!["remote-1".to_string()]);
+    }
+
+    #[test]
+    fn test_match_or_missing_checksum_keeps_remote_file() {
+        let deleted = Mutex::new(Vec::new());
+        let local = RemoteChecksum::Sha1("a9993e364706816aba3e25717850c26c9cd0d89d".into());
+        for checksum in [
+            Some(RemoteChecksum::Sha1(
+                "A9993E364706816ABA3E25717850C26C9CD0D89D".into(),
+            )),
+            None,
+        ] {
+            let file =
+                tauri::async_runtime::block_on(check_integrity(remote(checksum), &local, |id| {
+                    deleted.lock().unwrap().push(id);
+                    async { Ok(()) }
+                }))
+                .unwrap();
+            assert_eq!(file.id, "remote-1");
+        }
+        assert!(deleted.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! 云端文件操作（查询、列目录、删除）按服务商分派到各自的实现。
+
+use ai_disk_common::{CommandError, ErrorCode};
+
+use super::aliyun::AliyunUploader;
+use super::baidu::BaiduUploader;
+use super::dropbox::DropboxFiles;
+use super::google_drive::DriveFiles;
+use super::{CloudFilePage, RemoteFile};
+
+/// 查询云端文件的大小和校验和
+pub(crate) async fn remote_file(
+    provider: &str,
+    access_token: &str,
+    remote_id: &str,
+) -> Result<RemoteFile, CommandError> {
+    match provider {
+        "google_drive" => DriveFiles::new(access_token).get(remote_id).await,
+        "dropbox" => DropboxFiles::new(access_token).get(remote_id).await,
+        "baidu_netdisk" => BaiduUploader::new(access_token).file_meta(remote_id).await,
+        "aliyun_drive" => {
+            let aliyun = AliyunUploader::new(access_token);
+            let drive_id = aliyun.default_drive_id().await?;
+            aliyun.get_file(&drive_id, remote_id).await
+        }
+        _ => Err(unsupported(provider)),
+    }
+}
+
+/// 列出 `path` 目录下的一页文件；`page_token` 为上一页返回的 `next_page_token`
+pub(crate) async fn list_files(
+    provider: &str,
+    access_token: &str,
+    path: &str,
+    page_token: Option<&str>,
+) -> Result<CloudFilePage, CommandError> {
+    match provider {
+        "google_drive" => DriveFiles::new(access_token).list(path, page_token).await,
+        "dropbox" => DropboxFiles::new(access_token).list(path, page_token).await,
+        "baidu_netdisk" => {
+            BaiduUploader::new(access_token)
+                .list(path, page_token)
+                .await
+        }
+        "aliyun_drive" => {
+            AliyunUploader::new(access_token)
+                .list(path, page_token)
+                .await
+        }
+        _ => Err(unsupported(provider)),
+    }
+}
+
+/// 永久删除云端文件
+pub(crate) async fn delete_remote(
+    provider: &str,
+    access_token: &str,
+    remote_id: &str,
+) -> Result<(), CommandError> {
+    match provider {
+        "google_drive" => DriveFiles::new(access_token).delete(remote_id).await,
+        "dropbox" => DropboxFiles::new(access_token).delete(remote_id).await,
+        "baidu_netdisk" => BaiduUploader::new(access_token).delete(remote_id).await,
+        "aliyun_drive" => {
+            let aliyun = AliyunUploader::new(access_token);
+            let drive_id = aliyun.default_drive_id().await?;
+            aliyun.delete_file(&drive_id, remote_id).await
+        }
+        _ => Err(unsupported(provider)),
+    }
+}
+
+fn unsupported(provider: &str) -> CommandError {
+    CommandError::new(
+        ErrorCode::InvalidInput,
+        format!("不支持的云存储提供商: {}", provider),
+    )
+}
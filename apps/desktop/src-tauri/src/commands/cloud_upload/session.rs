@@ -0,0 +1,437 @@
+//! 可续传上传会话的持久化：记录服务商返回的会话标识和已确认的偏移量，
+//! 上传中断后重试时从已提交的位置继续，而不是从头开始。
+//! 会话同时记录上传到的账号与目标位置，应用重启后可由 [`UploadSessionStore::list`] 列出、
+//! 按会话键（即上传 ID）续传；超过服务商有效期的会话在列出时清理。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::{CommandError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::UploadConfig;
+
+/// 本地文件的身份：大小或修改时间变化后旧会话作废
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileIdentity {
+    pub size: u64,
+    pub modified_ms: u64,
+}
+
+impl FileIdentity {
+    pub fn of(path: &Path) -> Result<Self, CommandError> {
+        let metadata = fs::metadata(path).map_err(|e| CommandError::io("读取文件信息失败", &e))?;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        Ok(Self {
+            size: metadata.len(),
+            modified_ms,
+        })
+    }
+}
+
+/// 会话上传到的位置，续传时据此重建上传配置。旧版本保存的会话只有 `provider`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UploadOrigin {
+    pub provider: String,
+    #[serde(default)]
+    pub account_id: String,
+    /// 对应 UploadConfig.name
+    #[serde(default)]
+    pub config_name: String,
+    #[serde(default)]
+    pub target_path: String,
+}
+
+impl UploadOrigin {
+    pub fn of(config: &UploadConfig) -> Self {
+        Self {
+            provider: config.provider.clone(),
+            account_id: config.account_id.clone(),
+            config_name: config.name.clone(),
+            target_path: config.target_path.clone(),
+        }
+    }
+}
+
+/// 一次上传在会话目录中的位置：会话键与上传到的位置
+#[derive(Debug, Clone)]
+pub(crate) struct SessionSlot {
+    pub key: String,
+    pub origin: UploadOrigin,
+}
+
+impl SessionSlot {
+    /// `target` 为服务商侧的目标（目录 ID 或完整路径），同一文件上传到同一位置时得到同一会话键
+    pub fn new(config: &UploadConfig, file_path: &str, target: &str) -> Self {
+        Self {
+            key: UploadSessionStore::key(&config.provider, &config.account_id, file_path, target),
+            origin: UploadOrigin::of(config),
+        }
+    }
+
+    /// 测试用：固定的会话键
+    #[cfg(test)]
+    pub fn for_test(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            origin: UploadOrigin {
+                provider: "test".to_string(),
+                account_id: "acc".to_string(),
+                config_name: "测试".to_string(),
+                target_path: "/".to_string(),
+            },
+        }
+    }
+}
+
+/// 一次可续传上传的会话状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct UploadSession {
+    #[serde(flatten)]
+    pub origin: UploadOrigin,
+    pub file_path: String,
+    pub file: FileIdentity,
+    /// 服务商的会话标识（Drive 为会话 URI，百度网盘与阿里云盘为 uploadid）
+    pub session: String,
+    /// 会话所属的云端文件 ID（阿里云盘续传时申请上传地址需要）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_id: Option<String>,
+    /// 服务端已确认接收的字节数
+    pub committed: u64,
+    pub created_at_ms: u64,
+}
+
+/// `list_resumable_uploads` 返回的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumableUpload {
+    /// 传给 `resume_upload` 的 ID
+    pub upload_id: String,
+    pub provider: String,
+    pub config_name: String,
+    pub account_id: String,
+    pub target_path: String,
+    pub file_path: String,
+    pub total_bytes: u64,
+    pub committed_bytes: u64,
+    pub created_at_ms: u64,
+    /// 超过该时间（Unix 毫秒）后会话失效
+    pub expires_at_ms: u64,
+}
+
+impl ResumableUpload {
+    fn new(upload_id: String, session: UploadSession, max_age_ms: u64) -> Self {
+        Self {
+            upload_id,
+            provider: session.origin.provider,
+            config_name: session.origin.config_name,
+            account_id: session.origin.account_id,
+            target_path: session.origin.target_path,
+            file_path: session.file_path,
+            total_bytes: session.file.size,
+            committed_bytes: session.committed,
+            created_at_ms: session.created_at_ms,
+            expires_at_ms: session.created_at_ms.saturating_add(max_age_ms),
+        }
+    }
+}
+
+/// 会话文件目录（位于存储根目录下）
+pub(crate) struct UploadSessionStore {
+    dir: PathBuf,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl UploadSessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 会话键：同一文件上传到同一账号的同一目标位置时复用会话
+    pub fn key(provider: &str, account_id: &str, file_path: &str, target: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [provider, account_id, file_path, target] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// 会话键是 SHA-256 的十六进制串；来自前端的上传 ID 先经此检查，不会指向会话目录之外
+    fn is_valid_key(key: &str) -> bool {
+        key.len() == 64 && key.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn read(&self, key: &str) -> Option<UploadSession> {
+        fs::read_to_string(self.path(key))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+    }
+
+    /// 读取会话；文件已变化或会话超过 `max_age_ms` 时删除并返回 None
+    pub fn load(&self, key: &str, file: &FileIdentity, max_age_ms: u64) -> Option<UploadSession> {
+        let session = self.read(key)?;
+        if session.file != *file
+            || is_expired(&session, max_age_ms)
+            || session.committed > file.size
+        {
+            log::info!("丢弃过期或文件已变化的上传会话: {}", session.file_path);
+            self.remove(key);
+            return None;
+        }
+        Some(session)
+    }
+
+    /// 列出可续传的会话，新的在前；`max_age_ms(provider)` 为各服务商的会话有效期，
+    /// 超过有效期或无法解析的会话文件在此删除
+    pub fn list(&self, max_age_ms: impl Fn(&str) -> u64) -> Vec<ResumableUpload> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut uploads: Vec<ResumableUpload> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let key = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_suffix(".json")
+                    .filter(|key| Self::is_valid_key(key))?
+                    .to_string();
+                let Some(session) = self.read(&key) else {
+                    log::warn!("删除无法解析的上传会话: {}", path.display());
+                    self.remove(&key);
+                    return None;
+                };
+                let max_age = max_age_ms(&session.origin.provider);
+                if is_expired(&session, max_age) {
+                    log::info!("删除已过期的上传会话: {}", session.file_path);
+                    self.remove(&key);
+                    return None;
+                }
+                Some(ResumableUpload::new(key, session, max_age))
+            })
+            .collect();
+        uploads.sort_by(|a, b| b.created_at_ms.cmp(&a.created_at_ms));
+        uploads
+    }
+
+    /// 取出可以续传的会话：会话未过期、记录了上传位置，且本地文件的大小和修改时间与开始上传时一致。
+    /// 文件已变化的会话无法续传，删除后返回错误
+    pub fn resumable(
+        &self,
+        key: &str,
+        max_age_ms: impl Fn(&str) -> u64,
+    ) -> Result<UploadSession, CommandError> {
+        let session = Self::is_valid_key(key)
+            .then(|| self.read(key))
+            .flatten()
+            .ok_or_else(|| {
+                CommandError::new(
+                    ErrorCode::InvalidInput,
+                    format!("没有可续传的上传: {}", key),
+                )
+            })?;
+        if is_expired(&session, max_age_ms(&session.origin.provider)) {
+            self.remove(key);
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!("上传会话已过期，请重新上传: {}", session.file_path),
+            ));
+        }
+        if session.origin.account_id.is_empty() {
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!(
+                    "上传会话缺少账号信息（由旧版本创建），请重新上传: {}",
+                    session.file_path
+                ),
+            ));
+        }
+        let path = Path::new(&session.file_path);
+        if !path.is_file() {
+            return Err(CommandError::new(
+                ErrorCode::PathNotFound,
+                format!("文件不存在: {}", session.file_path),
+            ));
+        }
+        let current = FileIdentity::of(path)?;
+        if current != session.file {
+            log::info!("文件已变化，丢弃上传会话: {}", session.file_path);
+            self.remove(key);
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!(
+                    "文件自上次上传后已被修改（大小或修改时间不同），无法续传，请重新上传: {}",
+                    session.file_path
+                ),
+            ));
+        }
+        Ok(session)
+    }
+
+    pub fn save(&self, key: &str, session: &UploadSession) -> Result<(), CommandError> {
+        fs::create_dir_all(&self.dir).map_err(|e| CommandError::io("创建上传会话目录失败", &e))?;
+        let text = serde_json::to_string_pretty(session)
+            .map_err(|e| CommandError::internal(format!("序列化上传会话失败: {}", e)))?;
+        let path = self.path(key);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, text).map_err(|e| CommandError::io("保存上传会话失败", &e))?;
+        fs::rename(&tmp, &path).map_err(|e| CommandError::io("保存上传会话失败", &e))
+    }
+
+    pub fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    /// 新会话
+    pub fn new_session(
+        origin: &UploadOrigin,
+        file_path: &str,
+        file: FileIdentity,
+        session: String,
+    ) -> UploadSession {
+        UploadSession {
+            origin: origin.clone(),
+            file_path: file_path.to_string(),
+            file,
+            session,
+            remote_id: None,
+            committed: 0,
+            created_at_ms: now_millis(),
+        }
+    }
+}
+
+fn is_expired(session: &UploadSession, max_age_ms: u64) -> bool {
+    now_millis().saturating_sub(session.created_at_ms) > max_age_ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn config(provider: &str) -> UploadConfig {
+        UploadConfig {
+            provider: provider.to_string(),
+            name: "我的网盘".to_string(),
+            account_id: "acc-1".to_string(),
+            target_path: "/备份".to_string(),
+            access_token: String::new(),
+        }
+    }
+
+    fn saved_session(store: &UploadSessionStore, path: &Path, provider: &str) -> String {
+        let file_path = path.to_string_lossy();
+        let slot = SessionSlot::new(&config(provider), &file_path, "target");
+        let mut session = UploadSessionStore::new_session(
+            &slot.origin,
+            &file_path,
+            FileIdentity::of(path).unwrap(),
+            "session-1".to_string(),
+        );
+        session.committed = 4;
+        store.save(&slot.key, &session).unwrap();
+        slot.key
+    }
+
+    #[test]
+    fn test_sessions_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, b"0123456789").unwrap();
+        let key = saved_session(
+            &UploadSessionStore::new(dir.path().join("sessions")),
+            &path,
+            "dropbox",
+        );
+
+        // 重启后新建的实例读到同一会话
+        let store = UploadSessionStore::new(dir.path().join("sessions"));
+        let listed = store.list(|_| DAY_MS);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].upload_id, key);
+        assert_eq!(listed[0].provider, "dropbox");
+        assert_eq!(listed[0].config_name, "我的网盘");
+        assert_eq!(listed[0].committed_bytes, 4);
+        assert_eq!(listed[0].total_bytes, 10);
+        assert_eq!(listed[0].expires_at_ms, listed[0].created_at_ms + DAY_MS);
+
+        let session = store.resumable(&key, |_| DAY_MS).unwrap();
+        assert_eq!(session.origin.account_id, "acc-1");
+        assert_eq!(session.origin.target_path, "/备份");
+        assert_eq!(session.session, "session-1");
+    }
+
+    #[test]
+    fn test_list_purges_expired_and_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, b"0123456789").unwrap();
+        let store = UploadSessionStore::new(dir.path().join("sessions"));
+        let kept = saved_session(&store, &path, "google_drive");
+        let expired = saved_session(&store, &path, "baidu_netdisk");
+        let mut old = store.read(&expired).unwrap();
+        old.created_at_ms -= 2 * DAY_MS;
+        store.save(&expired, &old).unwrap();
+        let broken = "f".repeat(64);
+        fs::write(store.path(&broken), "not json").unwrap();
+
+        let listed = store.list(|provider| match provider {
+            "google_drive" => 7 * DAY_MS,
+            _ => DAY_MS,
+        });
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].upload_id, kept);
+        assert!(!store.path(&expired).exists());
+        assert!(!store.path(&broken).exists());
+    }
+
+    #[test]
+    fn test_resumable_rejects_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, b"0123456789").unwrap();
+        let store = UploadSessionStore::new(dir.path().join("sessions"));
+        let key = saved_session(&store, &path, "dropbox");
+
+        fs::write(&path, b"01234567890").unwrap();
+        let err = store.resumable(&key, |_| DAY_MS).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert!(err.message.contains("已被修改"));
+        // 无法续传的会话已删除
+        assert!(store.list(|_| DAY_MS).is_empty());
+
+        let key = saved_session(&store, &path, "dropbox");
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            store.resumable(&key, |_| DAY_MS).unwrap_err().code,
+            ErrorCode::PathNotFound
+        );
+        assert_eq!(
+            store.resumable("../config", |_| DAY_MS).unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+    }
+}
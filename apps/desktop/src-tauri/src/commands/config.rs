@@ -0,0 +1,81 @@
+//! 应用配置命令：读取/保存 `.disk-rookie/config.toml`，保存后广播 `config-changed` 事件让所有窗口刷新。
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use ai_disk_common::{AppConfig, CommandError, ErrorCode, CONFIG_FILE_NAME};
+use ai_disk_engine::llm::{llm_limiter, LlmLimits};
+use tauri::{AppHandle, Emitter, State};
+
+use super::cloud_upload::bandwidth::upload_limiter;
+use super::storage::get_storage_root;
+
+/// 已加载的配置及其文件路径
+pub struct ConfigState {
+    path: PathBuf,
+    config: Mutex<AppConfig>,
+}
+
+impl ConfigState {
+    /// 从存储根目录加载配置；文件损坏时回退为默认配置，保存时再覆盖
+    pub fn load(app: &AppHandle) -> Result<Self, CommandError> {
+        let path = get_storage_root(app)?.join(CONFIG_FILE_NAME);
+        let config = AppConfig::load(&path).unwrap_or_else(|e| {
+            log::warn!("加载配置失败，使用默认配置: {}", e);
+            AppConfig::default()
+        });
+        Ok(Self {
+            path,
+            config: Mutex::new(config),
+        })
+    }
+
+    /// 校验、保存并替换当前配置，同步遥测开关、上传带宽上限与 LLM 请求限流后广播 `config-changed`
+    pub fn replace(&self, app: &AppHandle, config: AppConfig) -> Result<AppConfig, CommandError> {
+        let mut config = config;
+        if let Err(errors) = config.validate() {
+            let message = errors
+                .iter()
+                .map(|e| format!("{}: {}", e.field, e.message))
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(CommandError::new(ErrorCode::Config, message)
+                .with_details(serde_json::to_value(&errors).unwrap_or_default()));
+        }
+
+        let mut current = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        config.merge_unknown_keys_from(&current);
+        config.save(&self.path)?;
+        *current = config.clone();
+        drop(current);
+
+        if let Some(telemetry) = ai_disk_common::telemetry() {
+            telemetry.apply_config(&config.telemetry);
+        }
+        upload_limiter().set_limit(config.upload.max_bytes_per_sec);
+        llm_limiter().set_limits(LlmLimits::from_config(&config.llm));
+        let _ = app.emit("config-changed", &config);
+        Ok(config)
+    }
+
+    pub fn get(&self) -> AppConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[tauri::command]
+pub fn get_app_config(state: State<'_, ConfigState>) -> AppConfig {
+    state.get()
+}
+
+#[tauri::command]
+pub fn set_app_config(
+    app: AppHandle,
+    state: State<'_, ConfigState>,
+    config: AppConfig,
+) -> Result<AppConfig, CommandError> {
+    state.replace(&app, config)
+}
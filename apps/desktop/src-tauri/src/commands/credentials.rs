@@ -0,0 +1,718 @@
+//! 云存储凭据安全存储：优先使用系统钥匙串（Windows 凭据管理器 / macOS Keychain / Secret Service），
+//! 不可用时退回到以机器标识派生密钥的加密文件。授权、刷新与撤销都在后端读写这里，
+//! 前端只持有账号 ID，access token 与 refresh token 都不经过前端。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ai_disk_common::{write_atomic, CommandError, ErrorCode};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use super::oauth::{self, OAuthTokens};
+use super::token_manager::TokenManager;
+
+/// 钥匙串中的服务名，与应用 identifier 一致
+const KEYRING_SERVICE: &str = "com.DiskRookie.desktop";
+
+/// 钥匙串不可用时的加密凭据文件（位于存储根目录）
+const ENCRYPTED_FILE_NAME: &str = "credentials.enc.json";
+
+/// 旧版本由前端写入、包含明文 token 的云存储设置文件
+const LEGACY_SETTINGS_FILE: &str = "cloud-storage-settings.json";
+
+/// 支持 OAuth 凭据的云存储服务
+const OAUTH_PROVIDERS: &[&str] = &[
+    "google_drive",
+    "onedrive",
+    "dropbox",
+    "aliyun_drive",
+    "baidu_netdisk",
+];
+
+/// 保存的凭据；`expires_at` 为 access token 过期时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCredentials {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: u64,
+    pub token_type: String,
+    pub scope: Option<String>,
+}
+
+impl StoredCredentials {
+    /// 由 token 响应构造；服务商未返回新的 refresh token 时沿用旧值
+    pub fn from_tokens(tokens: OAuthTokens, previous_refresh: Option<String>) -> Self {
+        Self {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token.or(previous_refresh),
+            expires_at: now_millis().saturating_add(tokens.expires_in.saturating_mul(1000)),
+            token_type: tokens.token_type,
+            scope: tokens.scope,
+        }
+    }
+}
+
+/// 返回给前端的凭据视图，不包含任何 token
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialsStatus {
+    pub expires_at: u64,
+    pub has_refresh_token: bool,
+    /// 实际使用的存储后端：`keyring` 或 `encrypted_file`
+    pub backend: &'static str,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 键值形式的机密存储后端
+trait SecretBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn get(&self, key: &str) -> Result<Option<String>, CommandError>;
+    fn set(&self, key: &str, value: &str) -> Result<(), CommandError>;
+    fn delete(&self, key: &str) -> Result<(), CommandError>;
+}
+
+fn keyring_error(context: &str, e: &keyring::Error) -> CommandError {
+    CommandError::internal(format!("{}: {}", context, e))
+}
+
+/// 系统钥匙串。Entry 按 key 缓存复用（mock 后端的凭据只存在于同一个 Entry 中）
+struct KeyringBackend {
+    entries: Mutex<HashMap<String, keyring::Entry>>,
+}
+
+impl KeyringBackend {
+    fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 探测钥匙串是否可用：能读取（或确认条目不存在）即视为可用
+    fn is_available() -> bool {
+        match keyring::Entry::new(KEYRING_SERVICE, "__probe__") {
+            Ok(entry) => matches!(entry.get_password(), Ok(_) | Err(keyring::Error::NoEntry)),
+            Err(_) => false,
+        }
+    }
+
+    fn with_entry<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&keyring::Entry) -> Result<T, CommandError>,
+    ) -> Result<T, CommandError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if !entries.contains_key(key) {
+            let entry = keyring::Entry::new(KEYRING_SERVICE, key)
+                .map_err(|e| keyring_error("打开钥匙串条目失败", &e))?;
+            entries.insert(key.to_string(), entry);
+        }
+        f(&entries[key])
+    }
+}
+
+impl SecretBackend for KeyringBackend {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, CommandError> {
+        self.with_entry(key, |entry| match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error("读取钥匙串失败", &e)),
+        })
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), CommandError> {
+        self.with_entry(key, |entry| {
+            entry
+                .set_password(value)
+                .map_err(|e| keyring_error("写入钥匙串失败", &e))
+        })
+    }
+
+    fn delete(&self, key: &str) -> Result<(), CommandError> {
+        self.with_entry(key, |entry| match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keyring_error("删除钥匙串条目失败", &e)),
+        })
+    }
+}
+
+/// 加密文件：每个值用 ChaCha20-Poly1305 单独加密，密钥由机器标识派生。
+/// 只能防止凭据以明文形式被拷走或误同步，无法防御同一台机器上的恶意程序
+struct EncryptedFileBackend {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileBackend {
+    fn new(path: PathBuf, machine_id: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"disk-rookie-credentials:");
+        hasher.update(machine_id.as_bytes());
+        let key = hasher.finalize();
+        Self {
+            path,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read_map(&self) -> Result<HashMap<String, String>, CommandError> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) if text.trim().is_empty() => Ok(HashMap::new()),
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| CommandError::internal(format!("凭据文件已损坏: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(CommandError::io("读取凭据文件失败", &e)),
+        }
+    }
+
+    fn write_map(&self, map: &HashMap<String, String>) -> Result<(), CommandError> {
+        let text = serde_json::to_string_pretty(map)
+            .map_err(|e| CommandError::internal(format!("序列化凭据失败: {}", e)))?;
+        write_atomic(&self.path, text.as_bytes())
+            .map_err(|e| CommandError::io("写入凭据文件失败", &e))
+    }
+
+    fn encrypt(&self, value: &str) -> Result<String, CommandError> {
+        let nonce: [u8; 12] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+            .map_err(|_| CommandError::internal("加密凭据失败"))?;
+        let mut blob = nonce.to_vec();
+        blob.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(blob))
+    }
+
+    fn decrypt(&self, encoded: &str) -> Result<String, CommandError> {
+        let blob = STANDARD
+            .decode(encoded)
+            .map_err(|e| CommandError::internal(format!("凭据格式无效: {}", e)))?;
+        if blob.len() < 12 {
+            return Err(CommandError::internal("凭据格式无效"));
+        }
+        let (nonce, ciphertext) = blob.split_at(12);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CommandError::internal("解密凭据失败（机器标识可能已变化）"))?;
+        String::from_utf8(plaintext).map_err(|e| CommandError::internal(e.to_string()))
+    }
+}
+
+impl SecretBackend for EncryptedFileBackend {
+    fn name(&self) -> &'static str {
+        "encrypted_file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, CommandError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.read_map()?
+            .get(key)
+            .map(|v| self.decrypt(v))
+            .transpose()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), CommandError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut map = self.read_map()?;
+        map.insert(key.to_string(), self.encrypt(value)?);
+        self.write_map(&map)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), CommandError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut map = self.read_map()?;
+        if map.remove(key).is_some() {
+            self.write_map(&map)?;
+        }
+        Ok(())
+    }
+}
+
+/// 机器标识；获取失败时退回到存储目录路径（仍然稳定，但强度较弱）
+fn machine_id(storage_root: &Path) -> String {
+    machine_uid::get().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "获取机器标识失败，使用存储路径派生凭据密钥");
+        storage_root.to_string_lossy().into_owned()
+    })
+}
+
+/// 凭据存储（Tauri 托管状态）
+pub struct CredentialStore {
+    backend: Box<dyn SecretBackend>,
+}
+
+impl CredentialStore {
+    /// 钥匙串可用时使用钥匙串，否则使用存储根目录下的加密文件
+    pub fn open(storage_root: &Path) -> Self {
+        let backend: Box<dyn SecretBackend> = if KeyringBackend::is_available() {
+            Box::new(KeyringBackend::new())
+        } else {
+            tracing::warn!("系统钥匙串不可用，凭据将保存到加密文件");
+            Box::new(EncryptedFileBackend::new(
+                storage_root.join(ENCRYPTED_FILE_NAME),
+                &machine_id(storage_root),
+            ))
+        };
+        tracing::info!(backend = backend.name(), "凭据存储已初始化");
+        Self { backend }
+    }
+
+    fn key(provider: &str, account_id: &str) -> Result<String, CommandError> {
+        if !OAUTH_PROVIDERS.contains(&provider) {
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!("不支持的云存储服务: {}", provider),
+            ));
+        }
+        if account_id.trim().is_empty() {
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                "账号 ID 不能为空",
+            ));
+        }
+        Ok(format!("{}:{}", provider, account_id))
+    }
+
+    pub fn load(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<Option<StoredCredentials>, CommandError> {
+        let key = Self::key(provider, account_id)?;
+        self.backend
+            .get(&key)?
+            .map(|text| {
+                serde_json::from_str(&text)
+                    .map_err(|e| CommandError::internal(format!("解析已保存的凭据失败: {}", e)))
+            })
+            .transpose()
+    }
+
+    pub fn save(
+        &self,
+        provider: &str,
+        account_id: &str,
+        credentials: &StoredCredentials,
+    ) -> Result<(), CommandError> {
+        let key = Self::key(provider, account_id)?;
+        let text = serde_json::to_string(credentials)
+            .map_err(|e| CommandError::internal(format!("序列化凭据失败: {}", e)))?;
+        self.backend.set(&key, &text)
+    }
+
+    pub fn delete(&self, provider: &str, account_id: &str) -> Result<(), CommandError> {
+        let key = Self::key(provider, account_id)?;
+        self.backend.delete(&key)
+    }
+
+    /// 使用 keyring mock 后端的存储，仅用于测试
+    #[cfg(test)]
+    pub(crate) fn mock() -> Self {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        Self {
+            backend: Box::new(KeyringBackend::new()),
+        }
+    }
+
+    pub(crate) fn status(&self, credentials: &StoredCredentials) -> CredentialsStatus {
+        CredentialsStatus {
+            expires_at: credentials.expires_at,
+            has_refresh_token: credentials.refresh_token.is_some(),
+            backend: self.backend.name(),
+        }
+    }
+}
+
+/// 把旧版设置文件中的明文 token 迁移到凭据存储，返回迁移的账号数。
+/// 全部写入成功后才从设置文件中删除 token 字段并补上 `accountId`；
+/// 任一账号失败则保留原文件，下次启动重试
+pub fn migrate_plaintext_tokens(
+    storage_root: &Path,
+    store: &CredentialStore,
+) -> Result<usize, CommandError> {
+    let path = storage_root.join(LEGACY_SETTINGS_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(CommandError::io("读取云存储设置失败", &e)),
+    };
+    let mut settings: serde_json::Value = match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(e) => {
+            tracing::warn!(error = %e, "云存储设置不是有效 JSON，跳过凭据迁移");
+            return Ok(0);
+        }
+    };
+
+    let Some(configs) = settings
+        .get_mut("configs")
+        .and_then(serde_json::Value::as_array_mut)
+    else {
+        return Ok(0);
+    };
+
+    let mut migrated = 0;
+    for config in configs.iter_mut() {
+        let Some(obj) = config.as_object_mut() else {
+            continue;
+        };
+        let access_token = obj.get("accessToken").and_then(|v| v.as_str());
+        let refresh_token = obj.get("refreshToken").and_then(|v| v.as_str());
+        if access_token.is_none() && refresh_token.is_none() {
+            continue;
+        }
+        let provider = obj
+            .get("provider")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        if !OAUTH_PROVIDERS.contains(&provider.as_str()) {
+            tracing::warn!(provider = %provider, "未知云存储服务的 token，跳过迁移");
+            continue;
+        }
+        let account_id = obj
+            .get("accountId")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(new_account_id);
+
+        let credentials = StoredCredentials {
+            access_token: access_token.unwrap_or_default().to_string(),
+            refresh_token: refresh_token.map(str::to_string),
+            expires_at: obj.get("tokenExpiry").and_then(|v| v.as_u64()).unwrap_or(0),
+            token_type: "Bearer".to_string(),
+            scope: None,
+        };
+        store.save(&provider, &account_id, &credentials)?;
+
+        obj.remove("accessToken");
+        obj.remove("refreshToken");
+        obj.remove("tokenExpiry");
+        obj.insert(
+            "accountId".to_string(),
+            serde_json::Value::String(account_id),
+        );
+        migrated += 1;
+    }
+
+    if migrated > 0 {
+        let text = serde_json::to_string_pretty(&settings)
+            .map_err(|e| CommandError::internal(format!("序列化云存储设置失败: {}", e)))?;
+        write_atomic(&path, text.as_bytes())
+            .map_err(|e| CommandError::io("写入云存储设置失败", &e))?;
+    }
+    Ok(migrated)
+}
+
+/// 设置文件中已连接的云存储账号
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConnectedAccount {
+    pub provider: String,
+    pub account_id: String,
+    pub name: String,
+}
+
+/// 从云存储设置文件读取已连接（已有 `accountId`）的 OAuth 账号；文件不存在时返回空列表
+pub(crate) fn connected_accounts(
+    storage_root: &Path,
+) -> Result<Vec<ConnectedAccount>, CommandError> {
+    let text = match fs::read_to_string(storage_root.join(LEGACY_SETTINGS_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CommandError::io("读取云存储设置失败", &e)),
+    };
+    let settings: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| CommandError::internal(format!("解析云存储设置失败: {}", e)))?;
+    let configs = settings
+        .get("configs")
+        .and_then(serde_json::Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    Ok(configs
+        .iter()
+        .filter_map(|config| {
+            let provider = config.get("provider")?.as_str()?;
+            let account_id = config.get("accountId")?.as_str()?;
+            if !OAUTH_PROVIDERS.contains(&provider) {
+                return None;
+            }
+            Some(ConnectedAccount {
+                provider: provider.to_string(),
+                account_id: account_id.to_string(),
+                name: config
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(provider)
+                    .to_string(),
+            })
+        })
+        .collect())
+}
+
+/// 新账号 ID（128 位随机十六进制）
+fn new_account_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// 按服务商刷新 token
+pub(crate) async fn refresh_tokens(
+    provider: &str,
+    refresh_token: String,
+) -> Result<OAuthTokens, CommandError> {
+    match provider {
+        "google_drive" => oauth::refresh_google(&refresh_token).await,
+        "baidu_netdisk" => oauth::refresh_baidu(&refresh_token).await,
+        "aliyun_drive" => oauth::refresh_aliyun(&refresh_token).await,
+        "dropbox" => oauth::refresh_dropbox(&refresh_token).await,
+        "onedrive" => oauth::onedrive::refresh_onedrive(&refresh_token).await,
+        _ => Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("不支持的云存储服务: {}", provider),
+        )),
+    }
+}
+
+/// 授权完成后返回给前端的结果：token 已保存在凭据存储中，前端只拿到账号 ID 与凭据状态
+#[derive(Debug, Clone, Serialize)]
+pub struct OAuthConnection {
+    pub account_id: String,
+    pub status: CredentialsStatus,
+}
+
+/// 保存 OAuth 授权得到的 token。`account_id` 为已有账号（重新授权）时沿用并保留旧的 refresh token
+/// （服务商未返回新值时），否则新建账号 ID
+pub(crate) fn save_oauth_tokens(
+    store: &CredentialStore,
+    provider: &str,
+    account_id: Option<String>,
+    tokens: OAuthTokens,
+) -> Result<OAuthConnection, CommandError> {
+    let account_id = account_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(new_account_id);
+    let previous = store
+        .load(provider, &account_id)?
+        .and_then(|c| c.refresh_token);
+    let credentials = StoredCredentials::from_tokens(tokens, previous);
+    store.save(provider, &account_id, &credentials)?;
+    Ok(OAuthConnection {
+        status: store.status(&credentials),
+        account_id,
+    })
+}
+
+/// 读取已保存的凭据（不含 refresh token）；未保存时返回 null
+#[tauri::command]
+pub async fn get_cloud_credentials(
+    tokens_state: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+) -> Result<Option<CredentialsStatus>, CommandError> {
+    let store = tokens_state.store();
+    Ok(store
+        .load(&provider, &account_id)?
+        .map(|c| store.status(&c)))
+}
+
+/// 删除已保存的凭据
+#[tauri::command]
+pub async fn delete_cloud_credentials(
+    tokens_state: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+) -> Result<(), CommandError> {
+    tokens_state.store().delete(&provider, &account_id)
+}
+
+/// 用已保存的 refresh token 刷新并写回凭据
+pub(crate) async fn refresh_stored(
+    tokens: &TokenManager,
+    provider: &str,
+    account_id: &str,
+) -> Result<CredentialsStatus, CommandError> {
+    let credentials = tokens.force_refresh(provider, account_id).await?;
+    tracing::info!(provider, "已刷新并保存凭据");
+    Ok(tokens.store().status(&credentials))
+}
+
+/// 用已保存的 refresh token 刷新并写回凭据
+#[tauri::command]
+pub async fn refresh_cloud_credentials(
+    tokens_state: State<'_, TokenManager>,
+    provider: String,
+    account_id: String,
+) -> Result<CredentialsStatus, CommandError> {
+    refresh_stored(&tokens_state, &provider, &account_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(access: &str) -> StoredCredentials {
+        StoredCredentials {
+            access_token: access.to_string(),
+            refresh_token: Some("refresh-1".to_string()),
+            expires_at: 1_700_000_000_000,
+            token_type: "Bearer".to_string(),
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn test_keyring_round_trip() {
+        let store = CredentialStore::mock();
+        assert!(store.load("dropbox", "acc-1").unwrap().is_none());
+
+        store.save("dropbox", "acc-1", &sample("at-1")).unwrap();
+        let loaded = store.load("dropbox", "acc-1").unwrap().unwrap();
+        assert_eq!(loaded.access_token, "at-1");
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh-1"));
+
+        store.delete("dropbox", "acc-1").unwrap();
+        assert!(store.load("dropbox", "acc-1").unwrap().is_none());
+
+        assert!(store.save("webdav", "acc-1", &sample("x")).is_err());
+        assert!(store.save("dropbox", " ", &sample("x")).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_file_does_not_contain_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ENCRYPTED_FILE_NAME);
+        let store = CredentialStore {
+            backend: Box::new(EncryptedFileBackend::new(path.clone(), "machine-a")),
+        };
+        store
+            .save("google_drive", "acc-1", &sample("secret-access"))
+            .unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("secret-access"));
+        assert!(!raw.contains("refresh-1"));
+        assert_eq!(
+            store
+                .load("google_drive", "acc-1")
+                .unwrap()
+                .unwrap()
+                .access_token,
+            "secret-access"
+        );
+
+        // 换一台机器（不同密钥）无法解密
+        let other = EncryptedFileBackend::new(path, "machine-b");
+        assert!(other.get("google_drive:acc-1").is_err());
+    }
+
+    #[test]
+    fn test_migrates_plaintext_tokens_and_strips_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings_path = dir.path().join(LEGACY_SETTINGS_FILE);
+        fs::write(
+            &settings_path,
+            r#"{
+              "configs": [
+                {"provider":"google_drive","name":"G","enabled":true,
+                 "accessToken":"ya29.plain","refreshToken":"1//plain","tokenExpiry":1700000000000},
+                {"provider":"baidu_netdisk","name":"B","enabled":true,"accountId":"fixed",
+                 "accessToken":"baidu-at","refreshToken":"baidu-rt","tokenExpiry":1700000000001},
+                {"provider":"webdav","name":"W","enabled":true,"webdavUrl":"https://dav.example"}
+              ],
+              "defaultProvider":"google_drive"
+            }"#,
+        )
+        .unwrap();
+
+        let store = CredentialStore::mock();
+        assert_eq!(migrate_plaintext_tokens(dir.path(), &store).unwrap(), 2);
+
+        let text = fs::read_to_string(&settings_path).unwrap();
+        assert!(!text.contains("plain"));
+        assert!(!text.contains("baidu-rt"));
+        let settings: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(settings["defaultProvider"], "google_drive");
+        assert_eq!(settings["configs"][2]["webdavUrl"], "https://dav.example");
+        assert_eq!(settings["configs"][1]["accountId"], "fixed");
+
+        let google_id = settings["configs"][0]["accountId"].as_str().unwrap();
+        let google = store.load("google_drive", google_id).unwrap().unwrap();
+        assert_eq!(google.access_token, "ya29.plain");
+        assert_eq!(google.refresh_token.as_deref(), Some("1//plain"));
+        assert_eq!(google.expires_at, 1_700_000_000_000);
+        let baidu = store.load("baidu_netdisk", "fixed").unwrap().unwrap();
+        assert_eq!(baidu.access_token, "baidu-at");
+
+        // 再次运行无事可做，文件保持不变
+        assert_eq!(migrate_plaintext_tokens(dir.path(), &store).unwrap(), 0);
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), text);
+
+        // 迁移后两个 OAuth 账号都出现在已连接列表中，WebDAV 不在其中
+        let accounts = connected_accounts(dir.path()).unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].account_id, google_id);
+        assert_eq!(accounts[1].name, "B");
+    }
+
+    fn oauth_tokens(access: &str, refresh: Option<&str>) -> OAuthTokens {
+        OAuthTokens {
+            access_token: access.to_string(),
+            refresh_token: refresh.map(str::to_string),
+            expires_in: 3600,
+            token_type: "Bearer".to_string(),
+            scope: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_saved_oauth_tokens_stay_in_the_backend() {
+        let store = CredentialStore::mock();
+        let connection = save_oauth_tokens(
+            &store,
+            "google_drive",
+            None,
+            oauth_tokens("at-1", Some("rt-1")),
+        )
+        .unwrap();
+        assert_eq!(connection.account_id.len(), 32);
+        assert!(connection.status.has_refresh_token);
+        let json = serde_json::to_string(&connection).unwrap();
+        assert!(!json.contains("at-1"));
+        assert!(!json.contains("rt-1"));
+
+        // 重新授权同一账号：服务商未返回新的 refresh token 时沿用旧值
+        let again = save_oauth_tokens(
+            &store,
+            "google_drive",
+            Some(connection.account_id.clone()),
+            oauth_tokens("at-2", None),
+        )
+        .unwrap();
+        assert_eq!(again.account_id, connection.account_id);
+        let saved = store
+            .load("google_drive", &connection.account_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.access_token, "at-2");
+        assert_eq!(saved.refresh_token.as_deref(), Some("rt-1"));
+    }
+}
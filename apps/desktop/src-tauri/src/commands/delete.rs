@@ -1,12 +1,60 @@
+use ai_disk_common::{CommandError, DiskAnalyzerError, ErrorCode};
+use ai_disk_executor::{delete_entry, DeleteOptions, DeleteOutcome};
+use ai_disk_scanner::DisplayPath;
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use super::config::ConfigState;
+use super::scan::{notify_scan_dirty, ScanStore};
 
 #[tauri::command]
-pub async fn delete_item(path: String) -> Result<String, String> {
-    let path_buf = Path::new(&path);
+pub async fn delete_item(
+    app: AppHandle,
+    scan_store: State<'_, ScanStore>,
+    config_state: State<'_, ConfigState>,
+    path: String,
+) -> Result<String, CommandError> {
+    let options = DeleteOptions {
+        force_attributes: config_state.get().executor.force_attributes,
+    };
+    let outcome = delete_path(&path, options)?;
+    notify_scan_dirty(&app, &scan_store, std::slice::from_ref(&path));
+    let kind = if outcome.is_dir { "目录" } else { "文件" };
+    Ok(match outcome.note() {
+        Some(note) => format!("已删除{}: {}（{}）", kind, path, note),
+        None => format!("已删除{}: {}", kind, path),
+    })
+}
+
+/// 删除文件或目录（拒绝系统关键目录）；只读文件清除属性后重试，调整记录在返回结果中
+pub(crate) fn delete_path(
+    path: &str,
+    options: DeleteOptions,
+) -> Result<DeleteOutcome, CommandError> {
+    let os_path = check_deletable(path)?;
+    let context = if os_path.is_dir() {
+        "删除目录失败"
+    } else {
+        "删除文件失败"
+    };
+    delete_entry(&os_path, options).map_err(|e| match e {
+        DiskAnalyzerError::Io(e) => CommandError::io(context, &e),
+        e => e.into(),
+    })
+}
+
+/// 确认路径存在且不在系统关键目录下，返回可直接用于文件操作的系统路径
+pub(crate) fn check_deletable(path: &str) -> Result<PathBuf, CommandError> {
+    // 扫描结果中的路径为展示形式，过长时需转回带 `\\?\` 前缀的系统路径
+    let os_path = DisplayPath::new(path).to_os_path();
+    let path_buf = os_path.as_path();
 
     if !path_buf.exists() {
-        return Err(format!("路径不存在: {}", path));
+        return Err(CommandError::new(
+            ErrorCode::PathNotFound,
+            format!("路径不存在: {}", path),
+        ));
     }
 
     // 安全检查：禁止删除系统关键目录
@@ -23,22 +71,19 @@ pub async fn delete_item(path: String) -> Result<String, String> {
         ]
     };
 
-    let canonical = fs::canonicalize(path_buf).map_err(|e| format!("无法解析路径: {}", e))?;
+    let canonical = fs::canonicalize(path_buf).map_err(|e| CommandError::io("无法解析路径", &e))?;
 
-    let canonical_str = canonical.to_string_lossy().to_string();
+    // canonicalize 在 Windows 上返回 `\\?\C:\...`，统一为展示形式后再与系统目录比较
+    let canonical_str = DisplayPath::new(&canonical.to_string_lossy()).into_string();
 
     for forbidden in forbidden_paths {
         if canonical_str.starts_with(forbidden) {
-            return Err(format!("禁止删除系统目录: {}", forbidden));
+            return Err(CommandError::new(
+                ErrorCode::PermissionDenied,
+                format!("禁止删除系统目录: {}", forbidden),
+            ));
         }
     }
 
-    // 执行删除
-    if path_buf.is_dir() {
-        fs::remove_dir_all(path_buf).map_err(|e| format!("删除目录失败: {}", e))?;
-        Ok(format!("已删除目录: {}", path))
-    } else {
-        fs::remove_file(path_buf).map_err(|e| format!("删除文件失败: {}", e))?;
-        Ok(format!("已删除文件: {}", path))
-    }
+    Ok(os_path)
 }
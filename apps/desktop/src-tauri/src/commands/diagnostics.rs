@@ -0,0 +1,17 @@
+//! 诊断命令：读取与重置进程内的运行指标（扫描耗时、MFT 枚举速度、执行结果、LLM 延迟、上传吞吐量），
+//! 供隐藏的诊断页使用。指标只在本机内存中，不随遥测上报。
+
+use ai_disk_common::{metrics, MetricsSnapshot};
+
+/// 当前指标快照
+#[tauri::command]
+pub fn get_diagnostics() -> MetricsSnapshot {
+    metrics().snapshot()
+}
+
+/// 清空指标，从现在开始重新统计
+#[tauri::command]
+pub fn reset_diagnostics() -> MetricsSnapshot {
+    metrics().reset();
+    metrics().snapshot()
+}
@@ -0,0 +1,16 @@
+//! 磁盘健康命令：读取卷所在磁盘的 SMART 摘要。
+//! 取不到的字段留空并附带警告，不以错误返回，避免前端整块面板无法显示。
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_domain::DiskHealth;
+
+/// 查询 `volume`（Windows 为盘符，其他平台为挂载路径）所在磁盘的健康状态
+#[tauri::command]
+pub async fn get_disk_health(volume: String) -> Result<DiskHealth, CommandError> {
+    if volume.trim().is_empty() {
+        return Err(CommandError::new(ErrorCode::InvalidInput, "卷不能为空"));
+    }
+    tauri::async_runtime::spawn_blocking(move || ai_disk_scanner::get_disk_health(&volume))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
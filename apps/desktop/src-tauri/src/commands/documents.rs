@@ -0,0 +1,432 @@
+//! 带版本的 JSON 文档存储：文档保存在 `.disk-rookie/<namespace>/<key>.json`，
+//! 外层信封记录 schema 版本与修订号。写入使用乐观并发（`expected_version` 不一致时拒绝），
+//! 无法解析的文件被移到备份并把原始内容返回给前端，而不是让整个读取失败。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ai_disk_common::{write_atomic, CommandError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::storage::{get_storage_root, resolve_storage_path};
+
+/// 串行化同一进程内的「读版本 - 写入」，保证版本检查与写入之间不被其他命令插入
+static DOCUMENT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 把某个命名空间的文档从 `from_version` 升级到 `from_version + 1`
+pub(crate) struct DocumentMigration {
+    pub namespace: &'static str,
+    pub from_version: u32,
+    pub migrate: fn(Value) -> Result<Value, String>,
+}
+
+/// 已注册的文档迁移；命名空间的当前 schema 版本为其最高迁移的 `from_version + 1`，没有迁移时为 1
+const MIGRATIONS: &[DocumentMigration] = &[];
+
+/// 磁盘上的文档信封
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub schema_version: u32,
+    /// 修订号，每次写入加一；不存在的文档视为 0
+    pub version: u64,
+    /// 最后写入时间（Unix 毫秒）
+    pub updated_at: u64,
+    pub value: Value,
+}
+
+/// `get_document` 的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DocumentRead {
+    /// 文档不存在或已损坏时为 None
+    pub document: Option<Document>,
+    /// 文件内容不是有效的文档；原文件已移到 `backup_path`
+    pub corrupt: bool,
+    /// 损坏文件的原始内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// 命名空间与键只允许单级的字母、数字、`-`、`_`、`.`，且不能以 `.` 开头
+pub(crate) fn validate_segment(kind: &str, segment: &str) -> Result<(), CommandError> {
+    let valid = !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("非法的文档{}: {}", kind, segment),
+        ))
+    }
+}
+
+fn document_path(root: &Path, namespace: &str, key: &str) -> Result<PathBuf, CommandError> {
+    validate_segment("命名空间", namespace)?;
+    validate_segment("键", key)?;
+    resolve_storage_path(root, &format!("{}/{}.json", namespace, key))
+}
+
+fn current_schema_version(namespace: &str, migrations: &[DocumentMigration]) -> u32 {
+    migrations
+        .iter()
+        .filter(|m| m.namespace == namespace)
+        .map(|m| m.from_version + 1)
+        .max()
+        .unwrap_or(1)
+}
+
+/// 依次应用迁移，把文档升级到当前 schema 版本；缺少中间迁移时报错
+fn upgrade(
+    namespace: &str,
+    mut document: Document,
+    migrations: &[DocumentMigration],
+) -> Result<Document, CommandError> {
+    let target = current_schema_version(namespace, migrations);
+    while document.schema_version < target {
+        let from = document.schema_version;
+        let migration = migrations
+            .iter()
+            .find(|m| m.namespace == namespace && m.from_version == from)
+            .ok_or_else(|| {
+                CommandError::new(
+                    ErrorCode::Config,
+                    format!("缺少 {} 从 schema {} 开始的迁移", namespace, from),
+                )
+            })?;
+        document.value = (migration.migrate)(document.value).map_err(|e| {
+            CommandError::new(
+                ErrorCode::Config,
+                format!("迁移 {} schema {} 失败: {}", namespace, from, e),
+            )
+        })?;
+        document.schema_version = from + 1;
+    }
+    Ok(document)
+}
+
+enum Stored {
+    Missing,
+    Valid(Document),
+    Corrupt { raw: String, backup: PathBuf },
+}
+
+/// 读取磁盘上的文档；损坏的文件被重命名为 `<key>.json.corrupt-<毫秒时间戳>` 保留
+fn load(path: &Path) -> Result<Stored, CommandError> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Stored::Missing),
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => String::from_utf8_lossy(
+            &fs::read(path).map_err(|e| CommandError::io("读取文档失败", &e))?,
+        )
+        .into_owned(),
+        Err(e) => return Err(CommandError::io("读取文档失败", &e)),
+    };
+    match serde_json::from_str::<Document>(&raw) {
+        Ok(document) => Ok(Stored::Valid(document)),
+        Err(e) => {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let backup = path.with_file_name(format!("{}.corrupt-{}", file_name, now_millis()));
+            fs::rename(path, &backup).map_err(|e| CommandError::io("备份损坏的文档失败", &e))?;
+            tracing::warn!(
+                path = %path.display(),
+                backup = %backup.display(),
+                error = %e,
+                "文档已损坏，已移到备份"
+            );
+            Ok(Stored::Corrupt { raw, backup })
+        }
+    }
+}
+
+fn read_document(
+    root: &Path,
+    namespace: &str,
+    key: &str,
+    migrations: &[DocumentMigration],
+) -> Result<DocumentRead, CommandError> {
+    let path = document_path(root, namespace, key)?;
+    let _guard = DOCUMENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(match load(&path)? {
+        Stored::Missing => DocumentRead::default(),
+        Stored::Valid(document) => DocumentRead {
+            document: Some(upgrade(namespace, document, migrations)?),
+            ..Default::default()
+        },
+        Stored::Corrupt { raw, backup } => DocumentRead {
+            document: None,
+            corrupt: true,
+            raw: Some(raw),
+            backup_path: Some(backup.to_string_lossy().into_owned()),
+        },
+    })
+}
+
+/// 写入文档；当前修订号与 `expected_version` 不一致时返回 VersionConflict。
+/// 损坏的文件按不存在处理（先移到备份）
+fn write_document(
+    root: &Path,
+    namespace: &str,
+    key: &str,
+    value: Value,
+    expected_version: u64,
+    migrations: &[DocumentMigration],
+) -> Result<Document, CommandError> {
+    let path = document_path(root, namespace, key)?;
+    let _guard = DOCUMENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let current = match load(&path)? {
+        Stored::Valid(document) => document.version,
+        Stored::Missing | Stored::Corrupt { .. } => 0,
+    };
+    if current != expected_version {
+        return Err(CommandError::new(
+            ErrorCode::VersionConflict,
+            format!("文档 {}/{} 已被修改，请重新读取后再保存", namespace, key),
+        )
+        .with_details(serde_json::json!({
+            "expected_version": expected_version,
+            "current_version": current
+        })));
+    }
+
+    let document = Document {
+        schema_version: current_schema_version(namespace, migrations),
+        version: current + 1,
+        updated_at: now_millis(),
+        value,
+    };
+    save(&path, &document)?;
+    Ok(document)
+}
+
+fn save(path: &Path, document: &Document) -> Result<(), CommandError> {
+    let text = serde_json::to_vec_pretty(document)
+        .map_err(|e| CommandError::internal(format!("序列化文档失败: {}", e)))?;
+    write_atomic(path, &text).map_err(|e| CommandError::io("写入文档失败", &e))
+}
+
+/// 把命名空间中 schema 为 `from_version` 的文档升级到当前版本并写回，返回迁移的文档数。
+/// 迁移同样视为一次写入，修订号加一
+fn migrate_namespace(
+    root: &Path,
+    namespace: &str,
+    from_version: u32,
+    migrations: &[DocumentMigration],
+) -> Result<usize, CommandError> {
+    validate_segment("命名空间", namespace)?;
+    let dir = resolve_storage_path(root, namespace)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(CommandError::io("读取文档目录失败", &e)),
+    };
+
+    let _guard = DOCUMENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut migrated = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Stored::Valid(document) = load(&path)? else {
+            continue;
+        };
+        if document.schema_version != from_version {
+            continue;
+        }
+        let mut upgraded = upgrade(namespace, document, migrations)?;
+        if upgraded.schema_version == from_version {
+            continue;
+        }
+        upgraded.version += 1;
+        upgraded.updated_at = now_millis();
+        save(&path, &upgraded)?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+/// 读取文档
+#[tauri::command]
+pub async fn get_document(
+    app: AppHandle,
+    namespace: String,
+    key: String,
+) -> Result<DocumentRead, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    read_document(&storage_root, &namespace, &key, MIGRATIONS)
+}
+
+/// 写入文档；新文档的 `expected_version` 为 0
+#[tauri::command]
+pub async fn put_document(
+    app: AppHandle,
+    namespace: String,
+    key: String,
+    value: Value,
+    expected_version: u64,
+) -> Result<Document, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    write_document(
+        &storage_root,
+        &namespace,
+        &key,
+        value,
+        expected_version,
+        MIGRATIONS,
+    )
+}
+
+/// 把命名空间中 schema 为 `from_version` 的文档迁移到当前版本
+#[tauri::command]
+pub async fn migrate_documents(
+    app: AppHandle,
+    namespace: String,
+    from_version: u32,
+) -> Result<usize, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    migrate_namespace(&storage_root, &namespace, from_version, MIGRATIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_theme(mut value: Value) -> Result<Value, String> {
+        let obj = value.as_object_mut().ok_or("不是对象")?;
+        if let Some(theme) = obj.remove("theme") {
+            obj.insert("appearance".into(), theme);
+        }
+        Ok(value)
+    }
+
+    const TEST_MIGRATIONS: &[DocumentMigration] = &[DocumentMigration {
+        namespace: "settings",
+        from_version: 1,
+        migrate: rename_theme,
+    }];
+
+    #[test]
+    fn test_version_conflict_rejects_stale_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        let first = write_document(root, "settings", "ui", json!({"a": 1}), 0, &[]).unwrap();
+        assert_eq!(first.version, 1);
+        assert_eq!(first.schema_version, 1);
+
+        // 两个窗口都基于版本 1 修改，后写入的被拒绝
+        write_document(root, "settings", "ui", json!({"a": 2}), 1, &[]).unwrap();
+        let err = write_document(root, "settings", "ui", json!({"a": 3}), 1, &[]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::VersionConflict);
+        assert_eq!(err.details.unwrap()["current_version"], 2);
+
+        // 再次创建同名文档也是冲突
+        let err = write_document(root, "settings", "ui", json!({}), 0, &[]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::VersionConflict);
+
+        let read = read_document(root, "settings", "ui", &[]).unwrap();
+        let document = read.document.unwrap();
+        assert_eq!(document.version, 2);
+        assert_eq!(document.value, json!({"a": 2}));
+        assert!(root.join("settings").join("ui.json").exists());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_backed_up_and_returned_raw() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let path = root.join("settings").join("ui.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{\"theme\": \"dark\",").unwrap();
+
+        let read = read_document(root, "settings", "ui", &[]).unwrap();
+        assert!(read.corrupt);
+        assert!(read.document.is_none());
+        assert_eq!(read.raw.as_deref(), Some("{\"theme\": \"dark\","));
+        let backup = PathBuf::from(read.backup_path.unwrap());
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            "{\"theme\": \"dark\","
+        );
+        assert!(!path.exists());
+
+        // 损坏的文档被移走后可以重新创建
+        let document = write_document(root, "settings", "ui", json!({}), 0, &[]).unwrap();
+        assert_eq!(document.version, 1);
+        assert!(backup.exists());
+    }
+
+    #[test]
+    fn test_migrations_upgrade_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_document(root, "settings", "ui", json!({"theme": "dark"}), 0, &[]).unwrap();
+
+        // 读取时在内存中升级，不写回
+        let read = read_document(root, "settings", "ui", TEST_MIGRATIONS).unwrap();
+        let document = read.document.unwrap();
+        assert_eq!(document.schema_version, 2);
+        assert_eq!(document.value, json!({"appearance": "dark"}));
+        assert_eq!(
+            read_document(root, "settings", "ui", &[])
+                .unwrap()
+                .document
+                .unwrap()
+                .schema_version,
+            1
+        );
+
+        assert_eq!(
+            migrate_namespace(root, "settings", 1, TEST_MIGRATIONS).unwrap(),
+            1
+        );
+        assert_eq!(
+            migrate_namespace(root, "settings", 1, TEST_MIGRATIONS).unwrap(),
+            0
+        );
+        let stored = read_document(root, "settings", "ui", &[])
+            .unwrap()
+            .document
+            .unwrap();
+        assert_eq!(stored.schema_version, 2);
+        assert_eq!(stored.version, 2);
+
+        // 新写入直接使用当前 schema 版本
+        let other = write_document(root, "settings", "new", json!({}), 0, TEST_MIGRATIONS).unwrap();
+        assert_eq!(other.schema_version, 2);
+    }
+
+    #[test]
+    fn test_rejects_invalid_namespace_or_key() {
+        let dir = tempfile::tempdir().unwrap();
+        for (namespace, key) in [
+            ("..", "x"),
+            ("settings", "../x"),
+            ("a/b", "x"),
+            ("settings", ".hidden"),
+            ("", "x"),
+        ] {
+            let err = write_document(dir.path(), namespace, key, json!({}), 0, &[]).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidInput, "{}/{}", namespace, key);
+        }
+    }
+}
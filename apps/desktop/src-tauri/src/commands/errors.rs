@@ -0,0 +1,156 @@
+//! 命令层 HTTP 错误到 CommandError 的转换
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+
+/// 错误的上下文：尚未迁移的调用方传入文本，已迁移的传入消息 ID（模板以 `{error}` 接收原因）
+pub(crate) trait ErrorContext {
+    fn into_error(self, code: ErrorCode, cause: &str) -> CommandError;
+}
+
+impl ErrorContext for &str {
+    fn into_error(self, code: ErrorCode, cause: &str) -> CommandError {
+        CommandError::new(code, format!("{}: {}", self, cause))
+    }
+}
+
+impl ErrorContext for &String {
+    fn into_error(self, code: ErrorCode, cause: &str) -> CommandError {
+        self.as_str().into_error(code, cause)
+    }
+}
+
+impl ErrorContext for MessageId {
+    fn into_error(self, code: ErrorCode, cause: &str) -> CommandError {
+        CommandError::localized(code, self, &[("error", cause)])
+    }
+}
+
+/// 请求未能完成（连接失败、超时等）；超时单独标注，便于前端决定是否重试
+pub(crate) fn request_error(context: impl ErrorContext, e: &reqwest::Error) -> CommandError {
+    let code = if e.is_timeout() {
+        ErrorCode::NetworkTimeout
+    } else {
+        ErrorCode::Network
+    };
+    context.into_error(code, &e.to_string())
+}
+
+/// 服务端返回非成功状态码；401/403 表示凭据失效，需要刷新 token 或重新授权
+pub(crate) fn status_error(
+    context: impl ErrorContext,
+    status: reqwest::StatusCode,
+    body: &str,
+) -> CommandError {
+    let code = match status.as_u16() {
+        401 | 403 => ErrorCode::Unauthorized,
+        408 | 504 => ErrorCode::NetworkTimeout,
+        _ => ErrorCode::Network,
+    };
+    context
+        .into_error(code, body)
+        .with_details(serde_json::json!({ "status": status.as_u16() }))
+}
+
+/// 响应体无法解析
+pub(crate) fn parse_error(context: impl ErrorContext, e: impl std::fmt::Display) -> CommandError {
+    context.into_error(ErrorCode::Internal, &e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 已迁移到消息 ID 的模块：其中构造命令错误、渲染通知的位置不得再出现中文字面量。
+    /// 模块迁移完成后加入此表
+    const MIGRATED_SOURCES: &[(&str, &str)] = &[
+        ("errors.rs", include_str!("errors.rs")),
+        ("hashing.rs", include_str!("hashing.rs")),
+        ("low_space.rs", include_str!("low_space.rs")),
+        ("oauth/flow.rs", include_str!("oauth/flow.rs")),
+        ("oauth/refresh.rs", include_str!("oauth/refresh.rs")),
+        ("scan.rs", include_str!("scan.rs")),
+        ("scan_notification.rs", include_str!("scan_notification.rs")),
+        ("vhd.rs", include_str!("vhd.rs")),
+    ];
+
+    /// 需要检查参数的调用
+    const CHECKED_CALLS: &[&str] = &[
+        "CommandError::new(",
+        "CommandError::internal(",
+        "CommandError::io(",
+        "request_error(",
+        "status_error(",
+        "parse_error(",
+        ".title(",
+        ".body(",
+    ];
+
+    /// 从 `open`（指向左括号）开始截取到匹配的右括号，忽略字符串字面量中的括号
+    fn call_arguments(source: &str, open: usize) -> &str {
+        let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+        for (i, c) in source[open..].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '(' if !in_string => depth += 1,
+                ')' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return &source[open..open + i + 1];
+                    }
+                }
+                _ => {}
+            }
+        }
+        &source[open..]
+    }
+
+    /// 源码中（不含测试模块）上述调用的参数里出现的中文
+    fn raw_chinese_calls(source: &str) -> Vec<String> {
+        let code = source.split("#[cfg(test)]\nmod tests").next().unwrap();
+        let mut found = Vec::new();
+        for call in CHECKED_CALLS {
+            for (start, _) in code.match_indices(call) {
+                let args = call_arguments(code, start + call.len() - 1);
+                if args.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)) {
+                    found.push(format!("{}{}", &call[..call.len() - 1], args));
+                }
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn test_migrated_modules_have_no_raw_chinese_messages() {
+        for (name, source) in MIGRATED_SOURCES {
+            let found = raw_chinese_calls(source);
+            assert!(found.is_empty(), "{} 中仍有中文字面量: {:?}", name, found);
+        }
+    }
+
+    #[test]
+    fn test_audit_detects_multiline_literals() {
+        let source = "fn f() {\n    Err(CommandError::new(\n        ErrorCode::Io,\n        format!(\"读取失败（{}）\", x),\n    ))\n}\n";
+        assert_eq!(raw_chinese_calls(source).len(), 1);
+        let localized =
+            "Err(CommandError::localized(code, MessageId::PathNotFound, &[(\"path\", p)]))";
+        assert!(raw_chinese_calls(localized).is_empty());
+        let context =
+            "request_error(MessageId::TokenRequestFailed, &e); request_error(\"请求失败\", &e)";
+        assert_eq!(raw_chinese_calls(context).len(), 1);
+    }
+
+    #[test]
+    fn test_message_id_context_keeps_cause_as_param() {
+        let err = parse_error(MessageId::TokenParseFailed, "expected value");
+        assert_eq!(err.code, ErrorCode::Internal);
+        assert_eq!(err.message_id, Some(MessageId::TokenParseFailed));
+        assert_eq!(err.params["error"], "expected value");
+        assert_eq!(err.message, "解析 token 响应失败: expected value");
+        assert_eq!(
+            parse_error("解析失败", "expected value").message,
+            "解析失败: expected value"
+        );
+    }
+}
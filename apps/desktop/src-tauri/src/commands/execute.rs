@@ -1,7 +1,627 @@
-use ai_disk_domain::CleanupPlan;
+use std::path::{Path, PathBuf};
 
+use ai_disk_common::{localize, CommandError, ErrorCode, ExecutionMode, Lang, MessageId};
+use ai_disk_domain::{
+    Action, ActionRef, ExecutionReport, PlanSelection, PlannedAction, ScheduleTarget,
+};
+use ai_disk_engine::{normalize_selection, project_free_space, resolve_selection, validate_action};
+use ai_disk_executor::{
+    append_journal, attribute_note, empty_trash, list_interrupted_executions as list_interrupted,
+    move_path, move_to_trash, offload_file, resolve_action_mode, resume_execution as resume,
+    rollback_execution as rollback, run_execution, simulate_planned, ActionExecutor,
+    AttributeChange, DeleteOptions, ExecutionOutcome, ExecutionStep, InterruptedExecution,
+    PlanSimulation, RollbackOutcome,
+};
+use ai_disk_scanner::DisplayPath;
+use tauri::{AppHandle, Manager, State};
+
+use super::busy::{BusyKind, BusyState};
+use super::cloud_upload::PlanUploader;
+use super::config::ConfigState;
+use super::delete::{check_deletable, delete_path};
+use super::saved_plans::saved_plan_actions;
+use super::scan::{notify_scan_dirty, ScanStore};
+use super::sessions::{record_execution, session_plan_actions};
+use super::storage::get_storage_root;
+use super::token_manager::TokenManager;
+
+/// 转存日志文件名（位于存储根目录下），记录已转存文件的云端 ID
+pub(crate) const OFFLOAD_JOURNAL: &str = "offload_journal.jsonl";
+/// 执行日志目录（位于存储根目录下），每次执行一个文件，用于崩溃后续做；
+/// 删除前的快照在 `<execution_id>/snapshots/` 子目录下，用于回滚
+pub(crate) const EXECUTIONS_DIR: &str = "executions";
+
+/// 桌面端逐个动作的执行，并按类型计数用于汇总
+struct DesktopExecutor<'a> {
+    app: &'a AppHandle,
+    scan_store: &'a ScanStore,
+    uploader: PlanUploader<'a>,
+    offload_journal: PathBuf,
+    offloaded: usize,
+    emptied: usize,
+    deleted: usize,
+    moved: usize,
+    compacted: usize,
+    /// 永久删除被拒绝时的处理方式
+    delete_options: DeleteOptions,
+    /// 为删除只读文件所做的属性调整，汇总中说明
+    attribute_changes: Vec<AttributeChange>,
+    /// 已完成动作释放的字节数之和；出错中断时用于会话中的执行结果
+    freed: u64,
+    /// 执行汇总使用的界面语言
+    lang: Lang,
+}
+
+impl<'a> DesktopExecutor<'a> {
+    fn new(
+        app: &'a AppHandle,
+        scan_store: &'a ScanStore,
+        tokens: &'a TokenManager,
+    ) -> Result<Self, CommandError> {
+        let storage_root = get_storage_root(app)?;
+        let config = app.state::<ConfigState>().get();
+        Ok(Self {
+            app,
+            scan_store,
+            offload_journal: storage_root.join(OFFLOAD_JOURNAL),
+            uploader: PlanUploader::new(tokens, storage_root),
+            offloaded: 0,
+            emptied: 0,
+            deleted: 0,
+            moved: 0,
+            compacted: 0,
+            delete_options: DeleteOptions {
+                force_attributes: config.executor.force_attributes,
+            },
+            attribute_changes: Vec::new(),
+            freed: 0,
+            lang: config.ui.language,
+        })
+    }
+
+    fn notify_dirty(&self, paths: &[String]) {
+        notify_scan_dirty(self.app, self.scan_store, paths);
+    }
+
+    /// 按界面语言汇总执行结果，只列出计数大于 0 的部分
+    fn summary(&self, outcome: &ExecutionOutcome) -> String {
+        let part = |id: MessageId, count: usize| {
+            (count > 0).then(|| localize(id, self.lang, &[("count", &count.to_string())]))
+        };
+        let summary: Vec<String> = [
+            part(MessageId::ExecutionOffloaded, self.offloaded),
+            part(MessageId::ExecutionDeleted, self.deleted),
+            part(MessageId::ExecutionMoved, self.moved),
+            part(MessageId::ExecutionTrashEmptied, self.emptied),
+            part(MessageId::ExecutionVhdCompacted, self.compacted),
+            attribute_note(&self.attribute_changes),
+            part(MessageId::ExecutionSkipped, outcome.skipped),
+            part(MessageId::ExecutionTargetsChanged, outcome.changed.len()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if summary.is_empty() {
+            return localize(MessageId::ExecutionNothingDone, self.lang, &[]);
+        }
+        let separator = match self.lang {
+            Lang::Zh => "，",
+            Lang::En => ", ",
+        };
+        summary.join(separator)
+    }
+
+    /// 会话中的执行结果；出错时汇总为错误信息，跳过数未知记为 0
+    fn report(
+        &self,
+        execution_id: &str,
+        resumed: bool,
+        result: &Result<ExecutionOutcome, CommandError>,
+    ) -> ExecutionReport {
+        let (executed, skipped, freed, summary, changed) = match result {
+            Ok(outcome) => (
+                outcome.executed,
+                outcome.skipped,
+                outcome.freed,
+                self.summary(outcome),
+                outcome.changed.clone(),
+            ),
+            Err(e) => (
+                self.offloaded + self.emptied + self.deleted + self.moved + self.compacted,
+                0,
+                self.freed,
+                e.message.clone(),
+                Vec::new(),
+            ),
+        };
+        ExecutionReport {
+            execution_id: execution_id.to_string(),
+            finished_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            resumed,
+            executed,
+            skipped,
+            freed,
+            summary,
+            failed: result.is_err(),
+            selection: None,
+            changed,
+        }
+    }
+
+    async fn execute_action(&mut self, step: &ExecutionStep<'_>) -> Result<u64, CommandError> {
+        let planned = step.planned;
+        match &planned.action {
+            Action::Delete { path } => {
+                if step.mode == ExecutionMode::Permanent {
+                    let outcome = delete_path(path, self.delete_options)?;
+                    if !outcome.changes.is_empty() {
+                        tracing::info!(
+                            path,
+                            changes = outcome.changes.len(),
+                            "attributes cleared to delete"
+                        );
+                    }
+                    self.attribute_changes.extend(outcome.changes);
+                } else {
+                    move_to_trash(&check_deletable(path)?)?;
+                }
+                self.notify_dirty(std::slice::from_ref(path));
+                self.deleted += 1;
+                Ok(planned.bytes)
+            }
+            Action::EmptyTrash { path } => {
+                let report = empty_trash(Path::new(path), false)?;
+                self.notify_dirty(std::slice::from_ref(path));
+                self.emptied += 1;
+                Ok(report.bytes)
+            }
+            Action::CompactVhd { path } => {
+                let freed = compact_vhd_action(path)?;
+                self.notify_dirty(std::slice::from_ref(path));
+                self.compacted += 1;
+                Ok(freed)
+            }
+            Action::Move { from, to } => {
+                move_path(&check_deletable(from)?, Path::new(to), &step.move_manifest)?;
+                self.notify_dirty(&[from.clone(), to.clone()]);
+                self.moved += 1;
+                Ok(planned.bytes)
+            }
+            Action::Offload {
+                path,
+                provider,
+                account_id,
+                target_path,
+            } => {
+                let record = offload_file(
+                    &self.uploader,
+                    path,
+                    provider,
+                    account_id,
+                    target_path,
+                    move_to_trash,
+                )
+                .await?;
+                // 本地文件已移入回收站，即使写日志失败扫描结果也已过期
+                self.notify_dirty(std::slice::from_ref(path));
+                append_journal(&self.offload_journal, &record)?;
+                self.offloaded += 1;
+                Ok(record.size)
+            }
+        }
+    }
+}
+
+impl ActionExecutor for DesktopExecutor<'_> {
+    type Error = CommandError;
+
+    async fn execute(&mut self, step: &ExecutionStep<'_>) -> Result<u64, CommandError> {
+        let freed = self.execute_action(step).await?;
+        self.freed += freed;
+        Ok(freed)
+    }
+}
+
+/// 压缩虚拟磁盘，返回文件缩小的字节数；设为稀疏时空间由 WSL 之后逐步归还，可能为 0
+#[cfg(windows)]
+fn compact_vhd_action(path: &str) -> Result<u64, CommandError> {
+    let report = ai_disk_executor::compact_vhd(Path::new(path))?;
+    tracing::info!(path, method = ?report.method, "virtual disk compacted");
+    Ok(report.bytes_before.saturating_sub(report.bytes_after))
+}
+
+#[cfg(not(windows))]
+fn compact_vhd_action(_path: &str) -> Result<u64, CommandError> {
+    Err(CommandError::new(
+        ErrorCode::InvalidInput,
+        "压缩虚拟磁盘仅支持 Windows",
+    ))
+}
+
+fn executions_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(get_storage_root(app)?.join(EXECUTIONS_DIR))
+}
+
+/// 新执行 ID：秒级时间戳加随机后缀，同时用作日志文件名
+fn new_execution_id() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!("exec_{}_{:08x}", secs, rand::random::<u32>())
+}
+
+/// 执行 ID 只能含字母、数字、`_` 与 `-`，防止拼出日志目录之外的路径
+fn validate_execution_id(execution_id: &str) -> Result<(), CommandError> {
+    let valid = !execution_id.is_empty()
+        && execution_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("无效的执行 ID: {}", execution_id),
+        ))
+    }
+}
+
+/// 模拟结果附上剩余空间预测：按覆盖第一个动作源路径的最近一次扫描，以解析出的执行方式计算
+fn with_projection(
+    mut simulation: PlanSimulation,
+    actions: &[PlannedAction],
+    scan_store: &ScanStore,
+) -> PlanSimulation {
+    let modes: Vec<ExecutionMode> = simulation.previews.iter().map(|p| p.mode).collect();
+    let plain: Vec<Action> = actions.iter().map(|p| p.action.clone()).collect();
+    simulation.projection = actions.first().and_then(|first| {
+        let path = DisplayPath::new(first.action.source_path());
+        let scan = scan_store
+            .covering_scans(path.as_str())
+            .into_iter()
+            .next()?;
+        Some(project_free_space(&scan, &plain, &modes))
+    });
+    simulation
+}
+
+/// 执行计划。每个动作的执行方式按配置中的执行策略由其风险等级决定：
+/// 高风险动作从不执行；`dry_run` 时返回的模拟结果中逐条列出解析出的方式，并附上执行后的剩余空间预测。
+/// 执行过程写入执行日志，中途崩溃或出错后可用 `resume_execution` 续做。
+/// 含作用于应用自身占用路径（存储目录、正在写入的导出文件）的动作时整个计划被拒绝。
+/// 目标在扫描后已改变的动作跳过并在执行结果中单独列出，`ignore_changes` 为 true 时照常执行。
+/// 执行时返回执行结果（`ExecutionReport` 的 JSON，含汇总与跳过的已改变目标）；
+/// 传入 `session_id` 时把执行结果（包括出错中断）追加到该会话
+#[tauri::command]
+pub async fn execute_plan(
+    app: AppHandle,
+    config_state: State<'_, ConfigState>,
+    scan_store: State<'_, ScanStore>,
+    actions: Vec<PlannedAction>,
+    dry_run: bool,
+    session_id: Option<String>,
+    ignore_changes: Option<bool>,
+) -> Result<String, CommandError> {
+    validate_actions(&actions)?;
+    if dry_run {
+        let simulation = simulate_planned(&actions, &config_state.get().executor.policy);
+        let simulation = with_projection(simulation, &actions, &scan_store);
+        return serde_json::to_string(&simulation)
+            .map_err(|e| CommandError::internal(format!("序列化模拟结果失败: {}", e)));
+    }
+    let (report, result) = execute_actions(
+        &app,
+        &actions,
+        session_id.as_deref(),
+        None,
+        ignore_changes.unwrap_or(false),
+    )
+    .await;
+    report_json(report, result)
+}
+
+/// 只执行已保存的计划或会话计划中勾选的动作，勾选可以是动作下标或源路径。
+/// 勾选的子集重新整理：父目录与其下的路径都勾选时只删除父目录，字节数不重复计算；
+/// 无法同时执行的动作返回 `PlanConflict`。整理后的预计释放量超过配置的二次确认阈值且
+/// `confirmed` 为 false 时返回 `ConfirmationRequired`（`details` 中带字节数与阈值）；
+/// 会话计划没有保存每个动作的字节数，不会触发确认。
+/// `dry_run` 时返回实际执行部分的模拟结果，否则与 `execute_plan` 一样返回执行结果；
+/// 执行结果记录原计划与勾选，来源为会话时追加到该会话。
+/// 目标在扫描后已改变的动作与 `execute_plan` 一样跳过，`ignore_changes` 为 true 时照常执行
 #[tauri::command]
-pub async fn execute_plan(plan: CleanupPlan, dry_run: bool) -> Result<String, String> {
-    let _ = (plan, dry_run);
-    Ok("执行功能待实现".to_string())
+pub async fn execute_plan_selection(
+    app: AppHandle,
+    config_state: State<'_, ConfigState>,
+    source: ScheduleTarget,
+    selected: Vec<ActionRef>,
+    dry_run: bool,
+    confirmed: bool,
+    ignore_changes: Option<bool>,
+) -> Result<String, CommandError> {
+    let root = get_storage_root(&app)?;
+    let (actions, session_id) = match &source {
+        ScheduleTarget::Plan { name } => (saved_plan_actions(&root, name)?, None),
+        ScheduleTarget::Session { session_id } => (
+            session_plan_actions(&root, session_id)?,
+            Some(session_id.as_str()),
+        ),
+    };
+    let selected = resolve_selection(&actions, &selected)
+        .map_err(|message| CommandError::new(ErrorCode::InvalidInput, message))?;
+    let normalized = normalize_selection(&actions, &selected)
+        .map_err(|message| CommandError::new(ErrorCode::PlanConflict, message))?;
+    let subset: Vec<PlannedAction> = normalized
+        .kept
+        .iter()
+        .map(|&index| actions[index].clone())
+        .collect();
+    validate_actions(&subset)?;
+
+    let config = config_state.get().executor;
+    if dry_run {
+        let simulation = simulate_planned(&subset, &config.policy);
+        let simulation = with_projection(simulation, &subset, &app.state::<ScanStore>());
+        return serde_json::to_string(&simulation)
+            .map_err(|e| CommandError::internal(format!("序列化模拟结果失败: {}", e)));
+    }
+    if normalized.bytes > config.confirm_threshold_bytes && !confirmed {
+        return Err(CommandError::new(
+            ErrorCode::ConfirmationRequired,
+            format!(
+                "勾选的动作预计释放 {} 字节，超过需要确认的 {} 字节",
+                normalized.bytes, config.confirm_threshold_bytes
+            ),
+        )
+        .with_details(serde_json::json!({
+            "bytes": normalized.bytes,
+            "threshold": config.confirm_threshold_bytes,
+        })));
+    }
+    let selection = PlanSelection {
+        source: source.clone(),
+        total_actions: actions.len(),
+        selected: normalized.selected,
+        covered: normalized.covered,
+        bytes: normalized.bytes,
+    };
+    let (report, result) = execute_actions(
+        &app,
+        &subset,
+        session_id,
+        Some(selection),
+        ignore_changes.unwrap_or(false),
+    )
+    .await;
+    report_json(report, result)
+}
+
+/// 执行成功时把执行结果序列化为 JSON 返回，出错时返回错误
+fn report_json(
+    report: ExecutionReport,
+    result: Result<(), CommandError>,
+) -> Result<String, CommandError> {
+    result?;
+    serde_json::to_string(&report)
+        .map_err(|e| CommandError::internal(format!("序列化执行结果失败: {}", e)))
+}
+
+/// 拒绝含作用于受保护路径或应用自身占用路径的动作的计划
+pub(crate) fn validate_actions(actions: &[PlannedAction]) -> Result<(), CommandError> {
+    for planned in actions {
+        validate_action(&planned.action)
+            .map_err(|message| CommandError::new(ErrorCode::PermissionDenied, message))?;
+    }
+    Ok(())
+}
+
+/// 动作附上目标的扫描时状态。生成或校验计划时已按计划所依据的扫描记录；只有没有记录的动作
+/// （如手工构造的动作、旧版本保存的计划）才按覆盖其路径的最近一次扫描补上，此时重新扫描之后的改变无法发现
+fn with_target_states(actions: &[PlannedAction], scan_store: &ScanStore) -> Vec<PlannedAction> {
+    let mut actions = actions.to_vec();
+    for planned in actions.iter_mut().filter(|p| p.scanned.is_none()) {
+        let path = DisplayPath::new(planned.action.source_path());
+        planned.scanned = scan_store.target_state(path.as_str());
+    }
+    actions
+}
+
+/// 校验并执行动作，`execute_plan`、部分执行与定时执行共用。返回执行结果记录（开始执行前出错时也有）
+/// 与是否出错；传入会话时把开始执行后的结果追加到该会话，部分执行时结果中记录勾选。
+/// 目标在扫描后已改变的动作跳过，`ignore_changes` 时照常执行
+pub(crate) async fn execute_actions(
+    app: &AppHandle,
+    actions: &[PlannedAction],
+    session_id: Option<&str>,
+    selection: Option<PlanSelection>,
+    ignore_changes: bool,
+) -> (ExecutionReport, Result<(), CommandError>) {
+    let execution_id = new_execution_id();
+    let prepared = validate_actions(actions).and_then(|()| {
+        let executor = DesktopExecutor::new(
+            app,
+            app.state::<ScanStore>().inner(),
+            app.state::<TokenManager>().inner(),
+        )?;
+        Ok((executions_dir(app)?, executor))
+    });
+    let (dir, mut executor) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            let report = ExecutionReport {
+                selection,
+                ..failed_report(&e)
+            };
+            return (report, Err(e));
+        }
+    };
+
+    let busy = app.state::<BusyState>();
+    let _busy = busy.begin(BusyKind::Execution);
+    let actions = &with_target_states(actions, &app.state::<ScanStore>());
+    let config = app.state::<ConfigState>().get().executor;
+    let modes: Vec<ExecutionMode> = actions
+        .iter()
+        .map(|planned| resolve_action_mode(&config.policy, planned))
+        .collect();
+    let result = run_execution(
+        &dir,
+        &execution_id,
+        actions,
+        &modes,
+        &config.snapshot,
+        ignore_changes,
+        &mut executor,
+    )
+    .await;
+    finish_execution(
+        app,
+        &executor,
+        session_id,
+        &execution_id,
+        false,
+        selection,
+        result,
+    )
+}
+
+/// 开始执行前出错时的执行结果，没有对应的执行日志
+pub(crate) fn failed_report(error: &CommandError) -> ExecutionReport {
+    ExecutionReport {
+        execution_id: new_execution_id(),
+        finished_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        resumed: false,
+        executed: 0,
+        skipped: 0,
+        freed: 0,
+        summary: error.message.clone(),
+        failed: true,
+        selection: None,
+        changed: Vec::new(),
+    }
+}
+
+/// 传入会话时追加执行结果，再返回执行结果记录与是否出错；部分执行时结果中记录勾选
+fn finish_execution(
+    app: &AppHandle,
+    executor: &DesktopExecutor<'_>,
+    session_id: Option<&str>,
+    execution_id: &str,
+    resumed: bool,
+    selection: Option<PlanSelection>,
+    result: Result<ExecutionOutcome, CommandError>,
+) -> (ExecutionReport, Result<(), CommandError>) {
+    let report = ExecutionReport {
+        selection,
+        ..executor.report(execution_id, resumed, &result)
+    };
+    if let Some(session_id) = session_id {
+        record_execution(app, session_id, report.clone());
+    }
+    let result = result.map(|outcome| ai_disk_common::record_plan_executed(outcome.freed));
+    (report, result)
+}
+
+/// 列出崩溃或出错中断、尚未完成的执行及各动作对照文件系统核对后的进度
+#[tauri::command]
+pub fn list_interrupted_executions(
+    app: AppHandle,
+) -> Result<Vec<InterruptedExecution>, CommandError> {
+    Ok(list_interrupted(&executions_dir(&app)?)?)
+}
+
+/// 从第一个未完成的动作继续中断的执行；跨卷移动复制到一半时，
+/// `complete_partial_moves` 为 true 则补完，否则回滚并跳过该动作。未开始的动作同样先核对目标，
+/// `ignore_changes` 为 true 时不核对。返回执行结果，传入 `session_id` 时把结果追加到该会话
+#[tauri::command]
+pub async fn resume_execution(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    scan_store: State<'_, ScanStore>,
+    config_state: State<'_, ConfigState>,
+    busy: State<'_, BusyState>,
+    execution_id: String,
+    complete_partial_moves: bool,
+    session_id: Option<String>,
+    ignore_changes: Option<bool>,
+) -> Result<ExecutionReport, CommandError> {
+    validate_execution_id(&execution_id)?;
+    let _busy = busy.begin(BusyKind::Execution);
+    let mut executor = DesktopExecutor::new(&app, &scan_store, &tokens)?;
+    let result = resume(
+        &executions_dir(&app)?,
+        &execution_id,
+        complete_partial_moves,
+        &config_state.get().executor.snapshot,
+        ignore_changes.unwrap_or(false),
+        &mut executor,
+    )
+    .await;
+    let (report, result) = finish_execution(
+        &app,
+        &executor,
+        session_id.as_deref(),
+        &execution_id,
+        true,
+        None,
+        result,
+    );
+    result.map(|()| report)
+}
+
+/// 用执行前的快照恢复一次执行删除的小文件（永久删除的同样可以恢复）；原位置已有文件时不覆盖
+#[tauri::command]
+pub fn rollback_execution(
+    app: AppHandle,
+    scan_store: State<'_, ScanStore>,
+    busy: State<'_, BusyState>,
+    execution_id: String,
+) -> Result<RollbackOutcome, CommandError> {
+    validate_execution_id(&execution_id)?;
+    let _busy = busy.begin(BusyKind::Execution);
+    let outcome = rollback(&executions_dir(&app)?, &execution_id)?;
+    notify_scan_dirty(&app, &scan_store, &outcome.restored);
+    Ok(outcome)
+}
+
+/// 启动时记录未完成的执行，便于排查
+pub(crate) fn log_interrupted_executions(app: &AppHandle) {
+    let Ok(dir) = executions_dir(app) else {
+        return;
+    };
+    match list_interrupted(&dir) {
+        Ok(interrupted) if !interrupted.is_empty() => {
+            let ids: Vec<&str> = interrupted
+                .iter()
+                .map(|e| e.execution_id.as_str())
+                .collect();
+            tracing::warn!(?ids, "found interrupted plan executions");
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "检查未完成的执行失败"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_id_is_file_name_safe() {
+        let id = new_execution_id();
+        assert!(id.starts_with("exec_"));
+        assert!(validate_execution_id(&id).is_ok());
+        for bad in ["", "../secrets", "a/b", r"a\b", "exec.jsonl"] {
+            assert_eq!(
+                validate_execution_id(bad).unwrap_err().code,
+                ErrorCode::InvalidInput
+            );
+        }
+    }
 }
@@ -0,0 +1,82 @@
+//! 文件哈希命令：计算一组文件的 BLAKE3 / SHA-256 摘要，每完成一个文件发出 `hash-progress` 事件。
+//!
+//! 摘要缓存在存储根目录下，文件大小与修改时间未变时不再读取文件，见 [`ai_disk_executor::HashCache`]。
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+use ai_disk_executor::{
+    hash_concurrency, FileHash, HashAlgorithm, HashCache, HASH_CACHE_FILE_NAME,
+};
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter, State};
+
+use super::storage::get_storage_root;
+
+/// 进度事件名
+pub const HASH_PROGRESS_EVENT: &str = "hash-progress";
+
+/// 单个文件完成时的进度
+#[derive(Debug, Clone, Serialize)]
+pub struct HashProgress<'a> {
+    pub done: usize,
+    pub total: usize,
+    pub file: &'a FileHash,
+}
+
+/// 应用内共享的哈希缓存，首次使用时从存储根目录加载
+#[derive(Default)]
+pub struct HashCacheState {
+    cache: std::sync::OnceLock<Arc<HashCache>>,
+}
+
+impl HashCacheState {
+    fn get(&self, app: &AppHandle) -> Result<Arc<HashCache>, CommandError> {
+        if let Some(cache) = self.cache.get() {
+            return Ok(cache.clone());
+        }
+        let file = get_storage_root(app)?.join(HASH_CACHE_FILE_NAME);
+        Ok(self
+            .cache
+            .get_or_init(|| Arc::new(HashCache::load(file)))
+            .clone())
+    }
+}
+
+/// 计算 `paths` 的摘要，结果按输入顺序返回；单个文件失败记录在其 `error` 中，不中断其他文件。
+/// 并发数按第一个文件所在磁盘的介质类型选取
+#[tauri::command]
+pub async fn hash_files(
+    app: AppHandle,
+    state: State<'_, HashCacheState>,
+    paths: Vec<String>,
+    algorithm: HashAlgorithm,
+) -> Result<Vec<FileHash>, CommandError> {
+    if paths.is_empty() {
+        return Err(CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::EmptyFileList,
+            &[],
+        ));
+    }
+    let cache = state.get(&app)?;
+    async_runtime::spawn_blocking(move || {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| PathBuf::from(p.trim())).collect();
+        let concurrency = hash_concurrency(ai_disk_scanner::detect_media_type(
+            &paths[0].to_string_lossy(),
+        ));
+        let total = paths.len();
+        let results =
+            ai_disk_executor::hash_files(&cache, &paths, algorithm, concurrency, &|done, file| {
+                let _ = app.emit(HASH_PROGRESS_EVENT, HashProgress { done, total, file });
+            });
+        // 缓存写回失败只影响下次是否命中，不影响本次结果
+        if let Err(e) = cache.save() {
+            tracing::warn!(error = %e, "failed to save hash cache");
+        }
+        results
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))
+}
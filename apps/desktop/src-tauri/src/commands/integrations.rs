@@ -0,0 +1,418 @@
+//! 设置页「测试连接」：并发检查所有已连接云存储账号（用户信息接口）与 LLM 服务（1 token 的探测请求），
+//! 每项检查单独限时，一个服务商无响应不影响其他结果。LLM 探测只发送固定文本，不携带扫描数据。
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+use ai_disk_engine::llm::{LlmError, OpenAiConfig, OpenAiProvider};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use super::config::ConfigState;
+use super::credentials::{connected_accounts, ConnectedAccount};
+use super::errors::{parse_error, request_error, status_error};
+use super::storage::get_storage_root;
+use super::token_manager::TokenManager;
+
+/// 单项检查的超时时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IntegrationStatus {
+    Ok,
+    /// 凭据失效或被拒绝，需要重新授权 / 更换 API Key
+    AuthExpired,
+    /// 不可达、超时或服务端错误
+    NetworkError,
+    /// 未配置（LLM 缺少地址或 API Key）
+    NotConfigured,
+}
+
+/// 云存储账号的检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountCheck {
+    pub provider: String,
+    pub account_id: String,
+    pub display_name: String,
+    pub status: IntegrationStatus,
+    pub latency_ms: u64,
+    /// access token 剩余有效秒数（已过期为负）；未取得凭据时为 None
+    pub expires_in_secs: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CommandError>,
+}
+
+/// LLM 服务的检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmCheck {
+    pub provider: String,
+    pub model: String,
+    pub status: IntegrationStatus,
+    /// 未配置时为 None
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CommandError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrationReport {
+    pub accounts: Vec<AccountCheck>,
+    pub llm: LlmCheck,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn timed_out(timeout: Duration) -> CommandError {
+    CommandError::localized(
+        ErrorCode::NetworkTimeout,
+        MessageId::CheckTimedOut,
+        &[("seconds", &timeout.as_secs().to_string())],
+    )
+}
+
+fn account_status(e: &CommandError) -> IntegrationStatus {
+    match e.code {
+        ErrorCode::Unauthorized | ErrorCode::ReauthRequired => IntegrationStatus::AuthExpired,
+        _ => IntegrationStatus::NetworkError,
+    }
+}
+
+fn llm_status(e: &LlmError) -> IntegrationStatus {
+    match e {
+        LlmError::NotConfigured => IntegrationStatus::NotConfigured,
+        LlmError::Http {
+            status: 401 | 403, ..
+        } => IntegrationStatus::AuthExpired,
+        _ => IntegrationStatus::NetworkError,
+    }
+}
+
+/// 请求服务商的用户信息接口（各服务商最轻量的需鉴权请求）
+async fn request_user_info(provider: &str, access_token: &str) -> Result<(), CommandError> {
+    let client = reqwest::Client::new();
+    let request = match provider {
+        "google_drive" => client
+            .get("https://www.googleapis.com/oauth2/v2/userinfo")
+            .bearer_auth(access_token),
+        "dropbox" => client
+            .post("https://api.dropbox.com/2/users/get_current_account")
+            .bearer_auth(access_token),
+        "baidu_netdisk" => client
+            .get("https://pan.baidu.com/rest/2.0/xpan/nas")
+            .query(&[("method", "uinfo"), ("access_token", access_token)]),
+        "aliyun_drive" => client
+            .get("https://openapi.alipan.com/v2/user/get")
+            .bearer_auth(access_token),
+        "onedrive" => client
+            .get("https://graph.microsoft.com/v1.0/me")
+            .bearer_auth(access_token),
+        _ => {
+            return Err(CommandError::internal(format!(
+                "不支持检查 {} 的连接",
+                provider
+            )))
+        }
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| request_error(MessageId::UserInfoFailed, &e))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(status_error(MessageId::UserInfoFailed, status, &body));
+    }
+    // 百度网盘以 HTTP 200 + errno 表示失败，-6 / 111 为 access token 无效
+    if provider == "baidu_netdisk" {
+        let value: Value =
+            serde_json::from_str(&body).map_err(|e| parse_error(MessageId::UserInfoFailed, e))?;
+        match value["errno"].as_i64().unwrap_or(0) {
+            0 => {}
+            -6 | 111 => {
+                return Err(CommandError::localized(
+                    ErrorCode::ReauthRequired,
+                    MessageId::ReauthRequired,
+                    &[("provider", provider), ("error", &body)],
+                ))
+            }
+            _ => return Err(parse_error(MessageId::UserInfoFailed, &body)),
+        }
+    }
+    Ok(())
+}
+
+/// 并发检查所有账号；`check` 完成一次鉴权请求并返回凭据的过期时间（Unix 毫秒）
+async fn check_accounts<F, Fut>(
+    accounts: &[ConnectedAccount],
+    timeout: Duration,
+    check: F,
+) -> Vec<AccountCheck>
+where
+    F: Fn(ConnectedAccount) -> Fut,
+    Fut: Future<Output = Result<u64, CommandError>>,
+{
+    futures::future::join_all(accounts.iter().map(|account| {
+        let request = check(account.clone());
+        async move {
+            let started = Instant::now();
+            let result = tokio::time::timeout(timeout, request)
+                .await
+                .unwrap_or_else(|_| Err(timed_out(timeout)));
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let now = now_millis() as i64;
+            let (status, expires_in_secs, error) = match result {
+                Ok(expires_at) => (
+                    IntegrationStatus::Ok,
+                    Some((expires_at as i64 - now) / 1000),
+                    None,
+                ),
+                Err(e) => {
+                    tracing::warn!(
+                        provider = %account.provider,
+                        account_id = %account.account_id,
+                        error = %e,
+                        "云存储账号连接检查失败"
+                    );
+                    (account_status(&e), None, Some(e))
+                }
+            };
+            AccountCheck {
+                provider: account.provider.clone(),
+                account_id: account.account_id.clone(),
+                display_name: account.name.clone(),
+                status,
+                latency_ms,
+                expires_in_secs,
+                error,
+            }
+        }
+    }))
+    .await
+}
+
+/// 检查 LLM 服务；未配置时不发出请求
+async fn check_llm<F, Fut>(config: &OpenAiConfig, timeout: Duration, probe: F) -> LlmCheck
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(), LlmError>>,
+{
+    let mut check = LlmCheck {
+        provider: "openai".to_string(),
+        model: config.model.clone(),
+        status: IntegrationStatus::NotConfigured,
+        latency_ms: None,
+        error: None,
+    };
+    if !config.is_configured() {
+        return check;
+    }
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, probe()).await;
+    check.latency_ms = Some(started.elapsed().as_millis() as u64);
+    match result {
+        Ok(Ok(())) => check.status = IntegrationStatus::Ok,
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "LLM 连接检查失败");
+            check.status = llm_status(&e);
+            check.error = Some(e.into());
+        }
+        Err(_) => {
+            check.status = IntegrationStatus::NetworkError;
+            check.error = Some(timed_out(timeout));
+        }
+    }
+    check
+}
+
+/// 检查所有已连接的云存储账号与 LLM 服务。
+/// `llm` 为前端 AI 设置中的连接配置，未传入时使用应用配置中的 `llm` 段
+#[tauri::command]
+pub async fn check_integrations(
+    app: AppHandle,
+    tokens: State<'_, TokenManager>,
+    config_state: State<'_, ConfigState>,
+    llm: Option<OpenAiConfig>,
+) -> Result<IntegrationReport, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let accounts = connected_accounts(&storage_root)?;
+    let llm_config = llm.unwrap_or_else(|| {
+        let config = config_state.get().llm;
+        OpenAiConfig {
+            api_url: config.api_url,
+            api_key: config.api_key,
+            model: config.model,
+        }
+    });
+
+    let tokens = tokens.inner();
+    let account_checks = check_accounts(
+        &accounts,
+        CHECK_TIMEOUT,
+        move |account: ConnectedAccount| async move {
+            let credentials = tokens
+                .get_valid_credentials(&account.provider, &account.account_id)
+                .await?;
+            request_user_info(&account.provider, &credentials.access_token).await?;
+            Ok(credentials.expires_at)
+        },
+    );
+    let provider = OpenAiProvider::new(llm_config.clone());
+    let llm_check = check_llm(&llm_config, CHECK_TIMEOUT, || provider.probe());
+    let (accounts, llm) = futures::join!(account_checks, llm_check);
+    Ok(IntegrationReport { accounts, llm })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    fn account(provider: &str, id: &str) -> ConnectedAccount {
+        ConnectedAccount {
+            provider: provider.into(),
+            account_id: id.into(),
+            name: format!("{} 账号", provider),
+        }
+    }
+
+    fn llm_config(api_url: &str, api_key: &str) -> OpenAiConfig {
+        OpenAiConfig {
+            api_url: api_url.into(),
+            api_key: api_key.into(),
+            model: "gpt-4o-mini".into(),
+        }
+    }
+
+    #[test]
+    fn test_mixed_account_outcomes() {
+        let accounts = [
+            account("google_drive", "g"),
+            account("dropbox", "d"),
+            account("baidu_netdisk", "b"),
+            account("onedrive", "o"),
+        ];
+        let expires_at = now_millis() + 3_600_000;
+        let checks = tauri::async_runtime::block_on(check_accounts(
+            &accounts,
+            Duration::from_millis(200),
+            |account: ConnectedAccount| async move {
+                match account.provider.as_str() {
+                    "google_drive" => Ok(expires_at),
+                    "dropbox" => Err(CommandError::localized(
+                        ErrorCode::ReauthRequired,
+                        MessageId::ReauthRequired,
+                        &[("provider", "dropbox"), ("error", "invalid_grant")],
+                    )),
+                    "baidu_netdisk" => Err(CommandError::new(ErrorCode::Network, "refused")),
+                    _ => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        Ok(expires_at)
+                    }
+                }
+            },
+        ));
+
+        assert_eq!(checks.len(), 4);
+        let statuses: Vec<_> = checks.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                IntegrationStatus::Ok,
+                IntegrationStatus::AuthExpired,
+                IntegrationStatus::NetworkError,
+                IntegrationStatus::NetworkError,
+            ]
+        );
+        let expires = checks[0].expires_in_secs.unwrap();
+        assert!((3590..=3600).contains(&expires));
+        assert!(checks[0].error.is_none());
+        // 超时的账号不拖住其他检查
+        assert!(checks[3].latency_ms < 2000);
+        let timeout = checks[3].error.as_ref().unwrap();
+        assert_eq!(timeout.message_id, Some(MessageId::CheckTimedOut));
+        assert!(checks[3].expires_in_secs.is_none());
+
+        let json = serde_json::to_value(&checks[1]).unwrap();
+        assert_eq!(json["status"], "AuthExpired");
+        assert_eq!(json["error"]["code"], "ReauthRequired");
+        assert!(serde_json::to_value(&checks[0])
+            .unwrap()
+            .get("error")
+            .is_none());
+    }
+
+    #[test]
+    fn test_llm_outcomes() {
+        let run = |config: OpenAiConfig, result: Result<(), LlmError>| {
+            tauri::async_runtime::block_on(check_llm(&config, CHECK_TIMEOUT, move || async move {
+                result
+            }))
+        };
+        let configured = llm_config("https://api.example.com/v1", "sk-test");
+
+        assert_eq!(
+            run(llm_config("https://api.example.com/v1", ""), Ok(())).status,
+            IntegrationStatus::NotConfigured
+        );
+        assert_eq!(
+            run(configured.clone(), Ok(())).status,
+            IntegrationStatus::Ok
+        );
+        let denied = run(
+            configured.clone(),
+            Err(LlmError::Http {
+                status: 401,
+                body: "invalid api key".into(),
+            }),
+        );
+        assert_eq!(denied.status, IntegrationStatus::AuthExpired);
+        assert_eq!(denied.error.unwrap().code, ErrorCode::LlmUnavailable);
+        assert_eq!(
+            run(configured, Err(LlmError::Request("dns".into()))).status,
+            IntegrationStatus::NetworkError
+        );
+    }
+
+    /// 实际探测请求：记录发送到模拟服务的请求体
+    #[test]
+    fn test_llm_probe_never_sends_scan_data() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", server.server_addr().to_ip().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                recorded.lock().unwrap().push(body);
+                let _ = request.respond(tiny_http::Response::from_string(
+                    r#"{"choices":[{"message":{"role":"assistant","content":"p"}}]}"#,
+                ));
+            }
+        });
+
+        let config = llm_config(&url, "sk-test");
+        let provider = OpenAiProvider::new(config.clone());
+        let check =
+            tauri::async_runtime::block_on(check_llm(&config, CHECK_TIMEOUT, || provider.probe()));
+        assert_eq!(check.status, IntegrationStatus::Ok);
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        let body: Value = serde_json::from_str(&bodies[0]).unwrap();
+        assert_eq!(body["max_tokens"], 1);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["content"], "ping");
+    }
+}
@@ -0,0 +1,70 @@
+//! 进程读写采样命令：周期性发送 `disk-io-sample` 事件，展示当前写入磁盘最多的进程。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ai_disk_common::CommandError;
+use ai_disk_scanner::{IoSampler, IO_SAMPLE_MIN_INTERVAL_MS};
+use tauri::{AppHandle, Emitter, State};
+
+/// 未指定间隔时的默认采样间隔（毫秒）
+const DEFAULT_IO_SAMPLE_INTERVAL_MS: u64 = 1_000;
+
+/// 当前采样线程的停止标志；同一时间只运行一个采样线程
+#[derive(Default)]
+pub struct IoSamplingState {
+    stop: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl IoSamplingState {
+    fn replace(&self, next: Option<Arc<AtomicBool>>) {
+        let mut stop = self.stop.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = stop.take() {
+            previous.store(true, Ordering::Relaxed);
+        }
+        *stop = next;
+    }
+}
+
+/// 开始采样；已在采样时以新的间隔重新开始。第一次采样只建立基线，之后每个间隔发送一次事件
+#[tauri::command]
+pub async fn start_io_sampling(
+    app: AppHandle,
+    state: State<'_, IoSamplingState>,
+    interval_ms: Option<u64>,
+) -> Result<(), CommandError> {
+    let interval = Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_IO_SAMPLE_INTERVAL_MS)
+            .max(IO_SAMPLE_MIN_INTERVAL_MS),
+    );
+    let mut sampler = IoSampler::new();
+    // 在当前线程建立基线，不支持的平台直接返回错误
+    sampler.sample()?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    state.replace(Some(stop.clone()));
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        match sampler.sample() {
+            Ok(sample) => {
+                let _ = app.emit("disk-io-sample", sample);
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "进程读写采样失败，停止采样");
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_io_sampling(state: State<'_, IoSamplingState>) -> Result<(), CommandError> {
+    state.replace(None);
+    Ok(())
+}
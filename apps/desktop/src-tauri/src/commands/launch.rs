@@ -0,0 +1,343 @@
+//! 启动参数：`diskrookie --scan D:\` 或资源管理器右键「使用 DiskRookie 分析」打开应用并立即扫描该目录。
+//!
+//! 应用启动时前端尚未加载，直接发出的事件会丢失：请求先存入 [`LaunchState`]，前端挂载后调用
+//! `frontend_ready`，再发出 `startup-scan-request`。应用已在运行时再次启动，参数由单实例转发过来，
+//! 发出 `external-scan-request`。
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use ai_disk_common::{CommandError, ErrorCode, ForwardedArgs, SCAN_FLAG};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// 首次启动携带的扫描请求
+pub const STARTUP_SCAN_EVENT: &str = "startup-scan-request";
+/// 运行中再次启动时转发来的扫描请求
+pub const EXTERNAL_SCAN_EVENT: &str = "external-scan-request";
+
+/// 右键菜单挂载的注册表位置：文件夹与驱动器根
+const CONTEXT_MENU_PARENTS: [&str; 2] = [
+    r"HKCU\Software\Classes\Directory\shell",
+    r"HKCU\Software\Classes\Drive\shell",
+];
+const CONTEXT_MENU_VERB: &str = "DiskRookie";
+const CONTEXT_MENU_LABEL: &str = "使用 DiskRookie 分析";
+
+/// 启动参数中要求扫描的路径
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalScanRequest {
+    /// 校验后的扫描路径
+    pub path: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Default)]
+struct LaunchInner {
+    frontend_ready: bool,
+    pending: Option<ExternalScanRequest>,
+}
+
+/// 前端就绪前暂存的启动扫描请求
+#[derive(Default)]
+pub struct LaunchState {
+    inner: Mutex<LaunchInner>,
+}
+
+impl LaunchState {
+    /// 前端已就绪时返回请求由调用方立即发出，否则暂存
+    fn queue(&self, request: ExternalScanRequest) -> Option<ExternalScanRequest> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.frontend_ready {
+            Some(request)
+        } else {
+            inner.pending = Some(request);
+            None
+        }
+    }
+
+    /// 标记前端就绪，取出暂存的请求
+    fn mark_ready(&self) -> Option<ExternalScanRequest> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.frontend_ready = true;
+        inner.pending.take()
+    }
+}
+
+/// 扫描路径必须是已存在的目录（含驱动器根）
+pub(crate) fn validate_scan_path(path: &str) -> Result<String, CommandError> {
+    let meta = std::fs::metadata(Path::new(path))
+        .map_err(|e| CommandError::io(&format!("无法访问扫描路径 {}", path), &e))?;
+    if !meta.is_dir() {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("扫描路径不是目录: {}", path),
+        ));
+    }
+    Ok(path.to_string())
+}
+
+/// 解析并校验启动参数；没有扫描路径或路径无效（记录警告）时为 None
+pub(crate) fn scan_request(forwarded: ForwardedArgs) -> Option<ExternalScanRequest> {
+    let path = forwarded.scan_path()?;
+    match validate_scan_path(&path) {
+        Ok(path) => Some(ExternalScanRequest {
+            path,
+            args: forwarded.args,
+        }),
+        Err(e) => {
+            tracing::warn!(path = %path, error = %e, "忽略启动参数中的扫描路径");
+            None
+        }
+    }
+}
+
+/// 首次启动：有扫描路径时暂存，等前端就绪后发出
+pub(crate) fn queue_startup_scan(app: &AppHandle, forwarded: ForwardedArgs) {
+    let Some(request) = scan_request(forwarded) else {
+        return;
+    };
+    tracing::info!(path = %request.path, "startup scan request");
+    if let Some(request) = app.state::<LaunchState>().queue(request) {
+        let _ = app.emit(STARTUP_SCAN_EVENT, request);
+    }
+}
+
+/// 处理后启动进程转发的参数：把主窗口带到前台，带有扫描路径时发出 `external-scan-request` 事件由前端开始扫描
+pub(crate) fn handle_forwarded_launch(app: &AppHandle, forwarded: ForwardedArgs) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    let Some(request) = scan_request(forwarded) else {
+        return;
+    };
+    tracing::info!(path = %request.path, "external scan request");
+    // 首个实例刚启动、前端尚未就绪时同样暂存，就绪后作为启动请求发出
+    if let Some(request) = app.state::<LaunchState>().queue(request) {
+        let _ = app.emit(EXTERNAL_SCAN_EVENT, request);
+    }
+}
+
+/// 前端挂载并订阅事件后调用；有暂存的启动扫描请求时发出 `startup-scan-request`
+#[tauri::command]
+pub fn frontend_ready(app: AppHandle, launch_state: State<'_, LaunchState>) {
+    if let Some(request) = launch_state.mark_ready() {
+        let _ = app.emit(STARTUP_SCAN_EVENT, request);
+    }
+}
+
+/// 右键菜单动作所在的注册表键，每个父位置一个
+pub(crate) fn context_menu_keys() -> Vec<String> {
+    CONTEXT_MENU_PARENTS
+        .iter()
+        .map(|parent| format!(r"{}\{}", parent, CONTEXT_MENU_VERB))
+        .collect()
+}
+
+/// 右键菜单执行的命令行：`"<exe>" --scan "%1"`
+pub(crate) fn context_menu_command(exe: &Path) -> String {
+    format!("\"{}\" {} \"%1\"", exe.display(), SCAN_FLAG)
+}
+
+/// 注册右键菜单的 `reg add` 参数列表：菜单文字、图标与命令
+pub(crate) fn register_reg_args(exe: &Path) -> Vec<Vec<String>> {
+    let command = context_menu_command(exe);
+    let icon = format!("\"{}\"", exe.display());
+    let add = |key: String, value: Option<&str>, data: &str| {
+        let mut args = vec!["add".to_string(), key];
+        match value {
+            Some(name) => args.extend(["/v".to_string(), name.to_string()]),
+            None => args.push("/ve".to_string()),
+        }
+        args.extend([
+            "/t".to_string(),
+            "REG_SZ".to_string(),
+            "/d".to_string(),
+            data.to_string(),
+            "/f".to_string(),
+        ]);
+        args
+    };
+    context_menu_keys()
+        .into_iter()
+        .flat_map(|key| {
+            [
+                add(key.clone(), None, CONTEXT_MENU_LABEL),
+                add(key.clone(), Some("Icon"), &icon),
+                add(format!(r"{}\command", key), None, &command),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn run_reg(args: &[String]) -> Result<std::process::Output, CommandError> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| CommandError::io("运行 reg 失败", &e))
+}
+
+fn ensure_windows() -> Result<(), CommandError> {
+    if cfg!(windows) {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            "资源管理器右键菜单仅支持 Windows",
+        ))
+    }
+}
+
+/// 在资源管理器的文件夹与驱动器右键菜单中添加「使用 DiskRookie 分析」（仅当前用户，无需提权）
+#[tauri::command]
+pub async fn register_context_menu() -> Result<(), CommandError> {
+    ensure_windows()?;
+    let exe = std::env::current_exe().map_err(|e| CommandError::io("获取程序路径失败", &e))?;
+    let commands = register_reg_args(&exe);
+    #[cfg(windows)]
+    {
+        for args in commands {
+            let output = run_reg(&args)?;
+            if !output.status.success() {
+                return Err(CommandError::new(
+                    ErrorCode::Io,
+                    format!(
+                        "写入注册表失败: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = commands;
+        unreachable!("ensure_windows rejects non-Windows platforms")
+    }
+}
+
+/// 移除右键菜单；菜单未注册时视为成功
+#[tauri::command]
+pub async fn unregister_context_menu() -> Result<(), CommandError> {
+    ensure_windows()?;
+    let keys = context_menu_keys();
+    #[cfg(windows)]
+    {
+        for key in keys {
+            let exists = run_reg(&["query".to_string(), key.clone()])?
+                .status
+                .success();
+            if !exists {
+                continue;
+            }
+            let output = run_reg(&["delete".to_string(), key, "/f".to_string()])?;
+            if !output.status.success() {
+                return Err(CommandError::new(
+                    ErrorCode::Io,
+                    format!(
+                        "删除注册表项失败: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = keys;
+        unreachable!("ensure_windows rejects non-Windows platforms")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(args: &[&str]) -> ForwardedArgs {
+        ForwardedArgs {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            cwd: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_scan_request_validates_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_string_lossy().into_owned();
+        let request = scan_request(forwarded(&["--scan", &dir_path])).unwrap();
+        assert_eq!(request.path, dir_path);
+        assert_eq!(request.args, vec!["--scan".to_string(), dir_path.clone()]);
+
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, b"x").unwrap();
+        let file_path = file.to_string_lossy().into_owned();
+        assert_eq!(
+            validate_scan_path(&file_path).unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+        assert!(scan_request(forwarded(&["--scan", &file_path])).is_none());
+        assert!(scan_request(forwarded(&["--minimized"])).is_none());
+        let missing = dir.path().join("missing").to_string_lossy().into_owned();
+        assert_eq!(
+            validate_scan_path(&missing).unwrap_err().code,
+            ErrorCode::PathNotFound
+        );
+    }
+
+    #[test]
+    fn test_launch_state_holds_request_until_ready() {
+        let state = LaunchState::default();
+        let request = |path: &str| ExternalScanRequest {
+            path: path.to_string(),
+            args: vec![],
+        };
+        assert!(state.queue(request("/a")).is_none());
+        // 就绪前只保留最近一次请求
+        assert!(state.queue(request("/b")).is_none());
+        assert_eq!(state.mark_ready(), Some(request("/b")));
+        assert_eq!(state.mark_ready(), None);
+        assert_eq!(state.queue(request("/c")), Some(request("/c")));
+    }
+
+    #[test]
+    fn test_context_menu_registry_strings() {
+        assert_eq!(
+            context_menu_keys(),
+            vec![
+                r"HKCU\Software\Classes\Directory\shell\DiskRookie".to_string(),
+                r"HKCU\Software\Classes\Drive\shell\DiskRookie".to_string(),
+            ]
+        );
+        let exe = Path::new(r"C:\Program Files\DiskRookie\diskrookie.exe");
+        assert_eq!(
+            context_menu_command(exe),
+            r#""C:\Program Files\DiskRookie\diskrookie.exe" --scan "%1""#
+        );
+
+        let args = register_reg_args(exe);
+        assert_eq!(args.len(), 6);
+        assert_eq!(
+            args[2],
+            [
+                "add",
+                r"HKCU\Software\Classes\Directory\shell\DiskRookie\command",
+                "/ve",
+                "/t",
+                "REG_SZ",
+                "/d",
+                r#""C:\Program Files\DiskRookie\diskrookie.exe" --scan "%1""#,
+                "/f",
+            ]
+            .map(String::from)
+        );
+        assert_eq!(args[1][2..4], ["/v".to_string(), "Icon".to_string()]);
+        assert_eq!(args[3][1], r"HKCU\Software\Classes\Drive\shell\DiskRookie");
+    }
+}
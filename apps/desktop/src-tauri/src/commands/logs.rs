@@ -0,0 +1,24 @@
+//! 日志命令：读取 `.disk-rookie/logs/app.log` 末尾若干行，供设置页「诊断日志」展示。
+
+use std::path::PathBuf;
+
+use ai_disk_common::{read_recent_logs, CommandError};
+use tauri::AppHandle;
+
+use super::storage::get_storage_root;
+
+/// 默认返回的行数
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// 日志目录（存储根目录下的 logs）
+pub(crate) fn get_log_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(get_storage_root(app)?.join("logs"))
+}
+
+/// 获取最近的日志行（JSON Lines，已脱敏）
+#[tauri::command]
+pub fn get_recent_logs(app: AppHandle, lines: Option<usize>) -> Result<Vec<String>, CommandError> {
+    let dir = get_log_dir(&app)?;
+    read_recent_logs(&dir, lines.unwrap_or(DEFAULT_LOG_LINES))
+        .map_err(|e| CommandError::io("读取日志失败", &e))
+}
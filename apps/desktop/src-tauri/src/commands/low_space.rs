@@ -0,0 +1,120 @@
+//! 低空间监控：启动后台任务，定期检查各固定卷的剩余空间，跌破 `low_space` 配置的阈值时
+//! 发出 `low-disk-space` 事件并发送系统通知。
+//!
+//! 开启 `low_space.auto_scan` 时还会以后台限速方式扫描该卷并做启发式分析，结果放入扫描缓存，
+//! 通过 `low-disk-space-prepared` 事件告知前端，用户打开应用即可看到建议列表。
+//! 阈值与回差判断见 [`ai_disk_scanner::LowSpaceMonitor`]。
+
+use std::time::Duration;
+
+use ai_disk_common::{format_bytes, localize, ByteStyle, CommandError, Lang, MessageId};
+use ai_disk_domain::LowSpaceAlert;
+use ai_disk_scanner::{
+    scan_path_with_progress, BackgroundScan, LowSpaceMonitor, SystemSpaceProvider, WalkOptions,
+};
+use tauri::{async_runtime, AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use super::busy::{BusyKind, BusyState};
+use super::config::ConfigState;
+use super::scan::ScanStore;
+
+/// 空间不足事件名
+pub const LOW_DISK_SPACE_EVENT: &str = "low-disk-space";
+/// 自动扫描与分析完成事件名，负载为 `(LowSpaceAlert, DiskAnalysis)`
+pub const LOW_DISK_SPACE_PREPARED_EVENT: &str = "low-disk-space-prepared";
+
+/// 启动后的首次检查延迟，避开启动时的磁盘繁忙期
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+
+/// 启动后台监控任务；每轮重新读取配置，关闭监控后任务仍在但不做检查
+pub fn spawn_low_space_monitor(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut monitor = LowSpaceMonitor::new();
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            let app_config = app.state::<ConfigState>().get();
+            let (config, lang) = (app_config.low_space, app_config.ui.language);
+            if config.enabled {
+                for alert in monitor.check(&SystemSpaceProvider, &config) {
+                    tracing::warn!(
+                        volume = %alert.volume,
+                        free_bytes = alert.free_bytes,
+                        free_percent = alert.free_percent,
+                        "low disk space"
+                    );
+                    notify_low_space(&app, &alert, lang);
+                    if config.auto_scan {
+                        spawn_prepare_suggestions(app.clone(), alert);
+                    }
+                }
+            }
+            let interval = Duration::from_secs(config.check_interval_minutes.max(1) * 60);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// 发出事件与以 `lang` 渲染的系统通知；通知失败只记录日志
+fn notify_low_space(app: &AppHandle, alert: &LowSpaceAlert, lang: Lang) {
+    let _ = app.emit(LOW_DISK_SPACE_EVENT, alert);
+    let free = format_bytes(alert.free_bytes, ByteStyle::Binary);
+    let percent = format!("{:.1}", alert.free_percent);
+    let body = localize(
+        MessageId::LowSpaceBody,
+        lang,
+        &[
+            ("volume", &alert.volume),
+            ("free", &free),
+            ("percent", &percent),
+        ],
+    );
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(localize(MessageId::LowSpaceTitle, lang, &[]))
+        .body(body)
+        .show()
+    {
+        tracing::warn!(volume = %alert.volume, error = %e, "low space notification failed");
+    }
+}
+
+/// 后台扫描该卷并做启发式分析，完成后发出 `low-disk-space-prepared`
+fn spawn_prepare_suggestions(app: AppHandle, mut alert: LowSpaceAlert) {
+    async_runtime::spawn_blocking(move || {
+        let busy = app.state::<BusyState>();
+        let _busy = busy.begin(BusyKind::Scan);
+        let scan_config = app.state::<ConfigState>().get().scan;
+        let background = Some(BackgroundScan {
+            entries_per_sec: scan_config.background_entries_per_sec,
+        });
+        let scanned = scan_path_with_progress(
+            &alert.volume,
+            None,
+            scan_config.shallow_dirs,
+            scan_config.use_mft,
+            scan_config.concurrency,
+            background,
+            &WalkOptions::default(),
+        );
+        let result = match scanned {
+            Ok((result, _used_mft)) => result,
+            Err(e) => {
+                tracing::warn!(volume = %alert.volume, error = %e, "low space auto scan failed");
+                ai_disk_common::record_error(CommandError::from(e).code);
+                return;
+            }
+        };
+        let result = app.state::<ScanStore>().insert(result);
+        let analysis = ai_disk_engine::analyze_scan(&result);
+        alert.scan_id = result.scan_id.clone();
+        tracing::info!(
+            volume = %alert.volume,
+            scan_id = ?alert.scan_id,
+            reclaimable_low_risk = analysis.reclaimable_low_risk,
+            "low space suggestions prepared"
+        );
+        let _ = app.emit(LOW_DISK_SPACE_PREPARED_EVENT, (alert, analysis));
+    });
+}
@@ -1,10 +1,35 @@
 pub mod analyze;
+pub mod app_data;
+pub mod busy;
+pub mod cloud_quota;
 pub mod cloud_upload;
+pub mod config;
+pub mod credentials;
 pub mod delete;
+pub mod diagnostics;
+pub mod disk_health;
+pub mod documents;
+pub(crate) mod errors;
 pub mod execute;
+pub mod hashing;
+pub mod integrations;
+pub mod io_sampling;
+pub mod launch;
+pub mod logs;
+pub mod low_space;
 pub mod oauth;
 pub mod open_in_file_manager;
 pub mod permission;
 pub mod plan;
+pub mod preview;
+pub mod saved_plans;
 pub mod scan;
+pub mod scan_notification;
+pub mod scan_payload;
+pub mod scheduled_executions;
+pub mod sessions;
 pub mod storage;
+pub mod telemetry;
+pub mod token_manager;
+pub mod vhd;
+pub mod windows_cleanup;
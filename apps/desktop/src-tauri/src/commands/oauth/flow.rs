@@ -0,0 +1,1089 @@
+//! 通用 OAuth 授权码流程：本地回调服务器、PKCE/state 生成、打开浏览器与换取 token。
+//! 各网盘的差异（授权 URL 参数、token 请求方式、是否支持 PKCE）由 `OAuthProvider` 实现提供。
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use ai_disk_common::{localize, CommandError, ErrorCode, Lang, MessageId};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use super::{OAuthState, OAuthTokens};
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+/// 授权成功后展示给浏览器的页面模板，`{{LANG}}`、`{{TITLE}}`、`{{MESSAGE}}`、`{{HINT}}` 在渲染时替换
+const CALLBACK_SUCCESS_HTML: &str = include_str!("callback_success.html");
+
+/// 授权失败/被拒绝时的页面模板，占位符同成功页面
+const CALLBACK_ERROR_HTML: &str = include_str!("callback_error.html");
+
+/// 回调等待超时的默认值
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 调用方可指定的超时范围；企业 SSO 等流程可能需要更久
+const MIN_CALLBACK_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_CALLBACK_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 回调服务器轮询间隔，同时决定取消的响应延迟
+const CALLBACK_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 等待授权期间每秒发出的倒计时事件
+pub(crate) const OAUTH_WAITING_EVENT: &str = "oauth-waiting";
+/// 等待超时时发出的事件，前端据此提供「重试」
+pub(crate) const OAUTH_TIMEOUT_EVENT: &str = "oauth-timeout";
+
+/// 等待回调的状态，作为 `oauth-waiting` / `oauth-timeout` 事件的载荷
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct OAuthWaiting {
+    pub provider: &'static str,
+    /// 本地回调服务器端口
+    pub port: u16,
+    /// 剩余秒数（向上取整）；超时事件中为 0
+    pub seconds_remaining: u64,
+}
+
+/// 阻塞等待线程发往异步侧的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum OAuthWaitEvent {
+    Waiting(OAuthWaiting),
+    TimedOut(OAuthWaiting),
+}
+
+impl OAuthWaitEvent {
+    fn emit(&self, app: &AppHandle) {
+        let (event, payload) = match self {
+            OAuthWaitEvent::Waiting(payload) => (OAUTH_WAITING_EVENT, payload),
+            OAuthWaitEvent::TimedOut(payload) => (OAUTH_TIMEOUT_EVENT, payload),
+        };
+        if let Err(e) = app.emit(event, payload) {
+            tracing::warn!(event, error = %e, "发送 OAuth 等待事件失败");
+        }
+    }
+}
+
+/// 调用方指定的超时（秒），限制在允许范围内；未指定时取默认值
+pub(crate) fn callback_timeout(timeout_secs: Option<u64>) -> Duration {
+    timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(CALLBACK_TIMEOUT)
+        .clamp(MIN_CALLBACK_TIMEOUT, MAX_CALLBACK_TIMEOUT)
+}
+
+/// 等待回调期间的上下文：倒计时事件经 `events` 发往异步侧
+struct CallbackWait<'a> {
+    provider: &'static str,
+    port: u16,
+    expected_state: &'a str,
+    lang: Lang,
+    timeout: Duration,
+    cancelled: &'a mpsc::Receiver<()>,
+    events: &'a tokio::sync::mpsc::UnboundedSender<OAuthWaitEvent>,
+}
+
+/// 流程结束（含提前返回或 future 被丢弃）时从 `OAuthState` 中移除登记
+struct PendingGuard<'a> {
+    state: &'a OAuthState,
+    id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.state.finish(self.id);
+    }
+}
+
+/// 单个 OAuth 服务商的差异部分
+pub(crate) trait OAuthProvider: Send + Sync {
+    /// 日志中的服务商标识
+    fn name(&self) -> &'static str;
+
+    /// 是否使用 PKCE（S256）
+    fn supports_pkce(&self) -> bool;
+
+    /// 授权页 URL；`code_challenge` 仅在 `supports_pkce` 为 true 时为 Some
+    fn auth_url(&self, redirect_uri: &str, state: &str, code_challenge: Option<&str>) -> String;
+
+    /// 用授权码换取 token 的请求；`code_verifier` 仅在 `supports_pkce` 为 true 时为 Some
+    fn token_request(
+        &self,
+        client: &reqwest::Client,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> reqwest::RequestBuilder;
+
+    /// 解析 token 响应体
+    fn parse_tokens(&self, body: &str) -> Result<OAuthTokens, serde_json::Error> {
+        serde_json::from_str(body)
+    }
+
+    /// 授权码换取的 token 的校验。后续刷新依赖 refresh token，缺失时（如 Dropbox 未请求离线访问、
+    /// Google 未重新征得同意）只能撤销本应用的访问权限后重新授权
+    fn validate_tokens(&self, tokens: &OAuthTokens) -> Result<(), CommandError> {
+        let missing = if tokens.access_token.is_empty() {
+            MessageId::TokenMissingAccess
+        } else if tokens.refresh_token.as_deref().is_none_or(str::is_empty) {
+            MessageId::TokenMissingRefresh
+        } else {
+            return Ok(());
+        };
+        Err(CommandError::localized(
+            ErrorCode::Unauthorized,
+            missing,
+            &[("provider", self.name())],
+        ))
+    }
+
+    /// 用 refresh token 换取新 token 的请求
+    fn refresh_request(
+        &self,
+        client: &reqwest::Client,
+        refresh_token: &str,
+    ) -> reqwest::RequestBuilder;
+
+    /// 刷新失败的响应是否表示 refresh token 已永久失效（重试无益，需要重新授权）；
+    /// 默认按 OAuth 标准的 `error=invalid_grant` 判断
+    fn is_refresh_revoked(&self, body: &str) -> bool {
+        oauth_error_field(body, "error").as_deref() == Some("invalid_grant")
+    }
+}
+
+/// 读取 JSON 响应体中的字符串字段，响应不是 JSON 或字段不存在时返回 None
+pub(crate) fn oauth_error_field(body: &str, field: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()?
+        .get(field)?
+        .as_str()
+        .map(str::to_string)
+}
+
+// 生成随机字符串
+fn generate_random_string(length: usize) -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~";
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+// 生成 PKCE code verifier
+fn generate_code_verifier() -> String {
+    generate_random_string(64)
+}
+
+// 生成 PKCE code challenge (S256)
+fn generate_code_challenge(verifier: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(verifier.as_bytes());
+    let hash = hasher.finalize();
+    URL_SAFE_NO_PAD.encode(hash)
+}
+
+// 启动本地服务器并返回端口
+fn start_callback_server() -> Result<(tiny_http::Server, u16), CommandError> {
+    // 尝试在随机端口上启动服务器
+    for _ in 0..10 {
+        let port = rand::thread_rng().gen_range(49152..65535);
+        let addr = format!("127.0.0.1:{}", port);
+        if let Ok(server) = tiny_http::Server::http(&addr) {
+            return Ok((server, port));
+        }
+    }
+    Err(CommandError::localized(
+        ErrorCode::Internal,
+        MessageId::OAuthCallbackServer,
+        &[],
+    ))
+}
+
+/// 回调请求的处理结果
+enum CallbackOutcome {
+    /// 不是授权回调（如 favicon、非本机 Host），继续等待
+    Ignored,
+    /// 授权成功，携带授权码
+    Code(String),
+    /// 服务商返回错误或 state 校验失败
+    Failed(CommandError),
+}
+
+fn html_response(html: String, status: u16) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    tiny_http::Response::from_string(html)
+        .with_status_code(status)
+        .with_header(
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..])
+                .unwrap(),
+        )
+}
+
+fn render_page(
+    template: &str,
+    lang: Lang,
+    title: MessageId,
+    message: &str,
+    hint: MessageId,
+) -> String {
+    template
+        .replace("{{LANG}}", if lang == Lang::En { "en" } else { "zh-CN" })
+        .replace("{{TITLE}}", &localize(title, lang, &[]))
+        .replace("{{MESSAGE}}", message)
+        .replace("{{HINT}}", &localize(hint, lang, &[]))
+}
+
+/// 渲染成功页面
+fn render_success_page(lang: Lang) -> String {
+    render_page(
+        CALLBACK_SUCCESS_HTML,
+        lang,
+        MessageId::OAuthPageSuccessTitle,
+        &localize(MessageId::OAuthPageSuccessMessage, lang, &[]),
+        MessageId::OAuthPageSuccessHint,
+    )
+}
+
+/// 渲染失败页面；`message` 可能来自服务商回调参数，需转义
+fn render_error_page(lang: Lang, title: MessageId, message: &str) -> String {
+    let escaped = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;");
+    render_page(
+        CALLBACK_ERROR_HTML,
+        lang,
+        title,
+        &escaped,
+        MessageId::OAuthPageRetryHint,
+    )
+}
+
+/// 只接受 Host 为 127.0.0.1 的请求，防止 DNS rebinding 等从其它来源访问回调端口
+fn is_loopback_host(request: &tiny_http::Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Host"))
+        .is_some_and(|h| {
+            let host = h.value.as_str();
+            host.rsplit_once(':').map_or(host, |(name, _)| name) == "127.0.0.1"
+        })
+}
+
+/// 处理单个请求并以 `lang` 向浏览器返回对应页面
+fn handle_callback_request(
+    request: tiny_http::Request,
+    expected_state: &str,
+    lang: Lang,
+) -> CallbackOutcome {
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default().to_string();
+    tracing::debug!(path = %path, "收到回调请求");
+
+    if !is_loopback_host(&request) {
+        tracing::warn!(path = %path, "拒绝非本机 Host 的回调请求");
+        let _ =
+            request.respond(tiny_http::Response::from_string("Forbidden").with_status_code(403));
+        return CallbackOutcome::Ignored;
+    }
+
+    // 解析查询参数
+    let params: HashMap<String, String> = url
+        .split('?')
+        .nth(1)
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| {
+            let mut parts = pair.split('=');
+            let key = parts.next()?;
+            let value = parts.next().unwrap_or("");
+            Some((
+                urlencoding::decode(key).ok()?.into_owned(),
+                urlencoding::decode(value).ok()?.into_owned(),
+            ))
+        })
+        .collect();
+
+    let (page, status, outcome) = if let Some(error) = params.get("error") {
+        let error_desc = params
+            .get("error_description")
+            .cloned()
+            .unwrap_or_else(|| localize(MessageId::OAuthPageUnknownError, lang, &[]));
+        tracing::warn!(error = %error, description = %error_desc, "OAuth 授权返回错误");
+        // 用户在授权页点击拒绝时，标准返回 error=access_denied
+        let (code, title) = if error == "access_denied" {
+            (ErrorCode::OAuthCancelled, MessageId::OAuthPageDeniedTitle)
+        } else {
+            (ErrorCode::OAuthFailed, MessageId::OAuthPageFailedTitle)
+        };
+        (
+            render_error_page(lang, title, &error_desc),
+            400,
+            CallbackOutcome::Failed(CommandError::localized(
+                code,
+                MessageId::OAuthProviderError,
+                &[("error", error), ("description", &error_desc)],
+            )),
+        )
+    } else if let (Some(code), Some(state)) = (params.get("code"), params.get("state")) {
+        if state == expected_state {
+            tracing::debug!("成功获取授权码，state 校验通过");
+            (
+                render_success_page(lang),
+                200,
+                CallbackOutcome::Code(code.clone()),
+            )
+        } else {
+            tracing::warn!("回调 state 不匹配");
+            (
+                render_error_page(
+                    lang,
+                    MessageId::OAuthPageFailedTitle,
+                    &localize(MessageId::OAuthPageStateMismatch, lang, &[]),
+                ),
+                400,
+                CallbackOutcome::Failed(CommandError::localized(
+                    ErrorCode::OAuthFailed,
+                    MessageId::OAuthStateMismatch,
+                    &[],
+                )),
+            )
+        }
+    } else {
+        let _ =
+            request.respond(tiny_http::Response::from_string("Not Found").with_status_code(404));
+        return CallbackOutcome::Ignored;
+    };
+
+    if let Err(e) = request.respond(html_response(page, status)) {
+        tracing::warn!(error = %e, "发送回调响应失败");
+    }
+    outcome
+}
+
+/// 等待 OAuth 回调并返回授权码；`cancelled` 收到消息或发送端被丢弃时立即返回 OAuthCancelled。
+/// `elapsed` 返回自开始等待以来的时长（测试中注入假时钟），剩余秒数每变化一次发出一个等待事件，
+/// 超时时发出超时事件
+fn wait_for_callback(
+    server: &tiny_http::Server,
+    wait: &CallbackWait<'_>,
+    elapsed: impl Fn() -> Duration,
+) -> Result<String, CommandError> {
+    tracing::debug!("等待 OAuth 回调");
+    let status = |seconds_remaining| OAuthWaiting {
+        provider: wait.provider,
+        port: wait.port,
+        seconds_remaining,
+    };
+    // 接收端已关闭时（流程被丢弃）不影响等待本身
+    let send = |event| {
+        let _ = wait.events.send(event);
+    };
+    let mut last_reported = None;
+
+    loop {
+        let elapsed = elapsed();
+        if elapsed >= wait.timeout {
+            tracing::warn!(timeout_secs = wait.timeout.as_secs(), "OAuth 授权超时");
+            send(OAuthWaitEvent::TimedOut(status(0)));
+            return Err(CommandError::localized(
+                ErrorCode::OAuthTimeout,
+                MessageId::OAuthTimeout,
+                &[],
+            ));
+        }
+        let remaining = (wait.timeout - elapsed).as_millis().div_ceil(1000) as u64;
+        if last_reported != Some(remaining) {
+            last_reported = Some(remaining);
+            send(OAuthWaitEvent::Waiting(status(remaining)));
+        }
+
+        if !matches!(wait.cancelled.try_recv(), Err(mpsc::TryRecvError::Empty)) {
+            tracing::info!("OAuth 授权已取消，关闭回调服务器");
+            return Err(CommandError::localized(
+                ErrorCode::OAuthCancelled,
+                MessageId::OAuthCancelled,
+                &[],
+            ));
+        }
+
+        match server.recv_timeout(CALLBACK_POLL_INTERVAL) {
+            Ok(Some(request)) => {
+                match handle_callback_request(request, wait.expected_state, wait.lang) {
+                    CallbackOutcome::Ignored => {}
+                    CallbackOutcome::Code(code) => return Ok(code),
+                    CallbackOutcome::Failed(e) => return Err(e),
+                }
+            }
+            Ok(None) => {
+                // 超时，继续循环
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "接收回调请求时出错");
+                // 继续循环，不中断
+            }
+        }
+    }
+}
+
+/// 完整授权流程：启动回调服务器 → 打开浏览器 → 等待回调 → 校验 state → 换取 token
+/// 同一时间只允许一个流程：新流程会取消 `oauth_state` 中进行中的旧流程。
+/// 浏览器中的回调页面以 `lang` 渲染；等待期间向前端发出 `oauth-waiting` 倒计时，
+/// 超时（`timeout_secs`，见 [`callback_timeout`]）时发出 `oauth-timeout`
+pub(crate) async fn run_oauth_flow<P: OAuthProvider>(
+    provider: &P,
+    oauth_state: &OAuthState,
+    app: &AppHandle,
+    lang: Lang,
+    timeout_secs: Option<u64>,
+) -> Result<OAuthTokens, CommandError> {
+    let app = app.clone();
+    run_oauth_flow_with(
+        provider,
+        oauth_state,
+        lang,
+        callback_timeout(timeout_secs),
+        |url| {
+            open::that(url).map_err(|e| {
+                CommandError::localized(
+                    ErrorCode::Internal,
+                    MessageId::OpenBrowserFailed,
+                    &[("error", &e.to_string())],
+                )
+            })
+        },
+        move |event| event.emit(&app),
+    )
+    .await
+}
+
+/// `run_oauth_flow` 的可注入版本：`launch` 负责让用户（或测试）访问授权 URL，
+/// `on_wait_event` 在异步侧接收等待线程发出的事件
+async fn run_oauth_flow_with<P: OAuthProvider>(
+    provider: &P,
+    oauth_state: &OAuthState,
+    lang: Lang,
+    timeout: Duration,
+    launch: impl FnOnce(&str) -> Result<(), CommandError>,
+    on_wait_event: impl Fn(OAuthWaitEvent) + Send + 'static,
+) -> Result<OAuthTokens, CommandError> {
+    let name = provider.name();
+    tracing::info!(provider = name, "开始 OAuth 授权流程");
+    let started = std::time::Instant::now();
+
+    let (id, cancelled) = oauth_state.begin(name);
+    let _guard = PendingGuard {
+        state: oauth_state,
+        id,
+    };
+
+    // 启动本地回调服务器
+    let (server, port) = start_callback_server()?;
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+    tracing::info!(provider = name, port, "本地回调服务器已启动");
+
+    // 生成 PKCE（如支持）和 state
+    let code_verifier = provider.supports_pkce().then(generate_code_verifier);
+    let code_challenge = code_verifier.as_deref().map(generate_code_challenge);
+    let state = generate_random_string(32);
+    tracing::debug!(
+        provider = name,
+        pkce = code_verifier.is_some(),
+        "授权参数已生成"
+    );
+
+    // 构建授权 URL
+    let auth_url = provider.auth_url(&redirect_uri, &state, code_challenge.as_deref());
+    tracing::debug!(provider = name, "授权 URL: {}", auth_url);
+
+    // 打开浏览器
+    tracing::info!(provider = name, "正在打开浏览器");
+    launch(&auth_url)?;
+
+    // 在阻塞线程池中等待回调（避免阻塞 async runtime）
+    tracing::info!(provider = name, redirect_uri = %redirect_uri, "等待用户授权");
+    // 等待线程经通道把倒计时事件交给异步侧；等待结束时发送端随之释放，转发任务退出
+    let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+    let forward = tokio::spawn(async move {
+        while let Some(event) = received.recv().await {
+            on_wait_event(event);
+        }
+    });
+    let expected_state = state.clone();
+    let waited = tokio::task::spawn_blocking(move || {
+        tracing::debug!(provider = name, "回调服务器正在监听");
+        let wait = CallbackWait {
+            provider: name,
+            port,
+            expected_state: &expected_state,
+            lang,
+            timeout,
+            cancelled: &cancelled,
+            events: &events,
+        };
+        let start = std::time::Instant::now();
+        let result = wait_for_callback(&server, &wait, || start.elapsed());
+        tracing::debug!(provider = name, ok = result.is_ok(), "回调服务器收到响应");
+        result
+    })
+    .await;
+    // 确保超时事件先于命令结果到达前端
+    let _ = forward.await;
+    let code = waited.map_err(|e| {
+        CommandError::localized(
+            ErrorCode::Internal,
+            MessageId::OAuthCallbackFailed,
+            &[("error", &e.to_string())],
+        )
+    })??;
+
+    tracing::debug!(provider = name, "收到授权码");
+
+    // 交换授权码获取 token
+    tracing::info!(provider = name, "开始交换授权码获取 token");
+    let client = reqwest::Client::new();
+    let token_response = provider
+        .token_request(&client, &code, &redirect_uri, code_verifier.as_deref())
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::warn!(provider = name, error = %e, "Token 请求发送失败");
+            request_error(MessageId::TokenRequestFailed, &e)
+        })?;
+
+    let status = token_response.status();
+    tracing::debug!(provider = name, status = %status, "收到 token 响应");
+
+    if !status.is_success() {
+        let error_text = token_response.text().await.unwrap_or_default();
+        tracing::warn!(provider = name, status = %status, body = %error_text, "Token 请求失败");
+        return Err(status_error(
+            MessageId::TokenRequestFailed,
+            status,
+            &error_text,
+        ));
+    }
+
+    let response_text = token_response.text().await.map_err(|e| {
+        tracing::warn!(provider = name, error = %e, "读取 token 响应失败");
+        request_error(MessageId::TokenReadFailed, &e)
+    })?;
+
+    let tokens = provider.parse_tokens(&response_text).map_err(|e| {
+        tracing::warn!(provider = name, error = %e, bytes = response_text.len(), "解析 token 响应失败");
+        parse_error(MessageId::TokenParseFailed, e)
+    })?;
+    if !tokens.extra.is_empty() {
+        let fields: Vec<&str> = tokens.extra.keys().map(String::as_str).collect();
+        tracing::debug!(provider = name, ?fields, "token 响应包含未识别的字段");
+    }
+    provider.validate_tokens(&tokens).inspect_err(|e| {
+        tracing::warn!(provider = name, error = %e, "token 响应不完整");
+    })?;
+
+    tracing::info!(
+        provider = name,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "OAuth 授权完成"
+    );
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::{Arc, Mutex};
+
+    /// 指向本地假授权服务器的服务商
+    struct MockProvider {
+        base_url: String,
+        pkce: bool,
+    }
+
+    impl OAuthProvider for MockProvider {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn supports_pkce(&self) -> bool {
+            self.pkce
+        }
+
+        fn auth_url(
+            &self,
+            redirect_uri: &str,
+            state: &str,
+            code_challenge: Option<&str>,
+        ) -> String {
+            let mut url = format!(
+                "{}/authorize?redirect_uri={}&state={}",
+                self.base_url,
+                urlencoding::encode(redirect_uri),
+                urlencoding::encode(state)
+            );
+            if let Some(challenge) = code_challenge {
+                url.push_str(&format!("&code_challenge={}", challenge));
+            }
+            url
+        }
+
+        fn token_request(
+            &self,
+            client: &reqwest::Client,
+            code: &str,
+            redirect_uri: &str,
+            code_verifier: Option<&str>,
+        ) -> reqwest::RequestBuilder {
+            let mut form = vec![
+                ("grant_type", "authorization_code".to_string()),
+                ("code", code.to_string()),
+                ("redirect_uri", redirect_uri.to_string()),
+            ];
+            if let Some(verifier) = code_verifier {
+                form.push(("code_verifier", verifier.to_string()));
+            }
+            client.post(format!("{}/token", self.base_url)).form(&form)
+        }
+
+        fn refresh_request(
+            &self,
+            client: &reqwest::Client,
+            refresh_token: &str,
+        ) -> reqwest::RequestBuilder {
+            client.post(format!("{}/token", self.base_url)).form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ])
+        }
+    }
+
+    /// 假授权服务器：`/authorize` 以 302 跳回 redirect_uri（可选追加 error），`/token` 返回固定 token
+    fn start_fake_auth_server(
+        callback_query: fn(&HashMap<String, String>) -> String,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let token_bodies = Arc::new(Mutex::new(Vec::new()));
+        let bodies = token_bodies.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let url = request.url().to_string();
+                let params: HashMap<String, String> = url
+                    .split('?')
+                    .nth(1)
+                    .unwrap_or("")
+                    .split('&')
+                    .filter_map(|pair| {
+                        let (k, v) = pair.split_once('=')?;
+                        Some((k.to_string(), urlencoding::decode(v).ok()?.into_owned()))
+                    })
+                    .collect();
+                if url.starts_with("/authorize") {
+                    let location =
+                        format!("{}?{}", params["redirect_uri"], callback_query(&params));
+                    let response = tiny_http::Response::empty(302).with_header(
+                        tiny_http::Header::from_bytes(&b"Location"[..], location.as_bytes())
+                            .unwrap(),
+                    );
+                    let _ = request.respond(response);
+                } else {
+                    let mut body = String::new();
+                    let _ = request.as_reader().read_to_string(&mut body);
+                    bodies.lock().unwrap().push(body);
+                    let response = tiny_http::Response::from_string(
+                        r#"{"access_token":"at-1","refresh_token":"rt-1","expires_in":3600,"token_type":"Bearer","scope":null}"#,
+                    );
+                    let _ = request.respond(response);
+                }
+            }
+        });
+        (base_url, token_bodies)
+    }
+
+    /// 极简 HTTP GET，返回原始响应
+    fn http_get(url: &str) -> String {
+        let rest = url.strip_prefix("http://").unwrap();
+        let (host, path) = rest.split_at(rest.find('/').unwrap());
+        let mut stream = TcpStream::connect(host).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, host
+        )
+        .unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    }
+
+    /// 模拟浏览器：访问授权页并跟随 302 回到本地回调
+    fn browser(url: &str) -> Result<(), CommandError> {
+        let url = url.to_string();
+        std::thread::spawn(move || {
+            let response = http_get(&url);
+            let location = response
+                .lines()
+                .find_map(|l| l.strip_prefix("Location: "))
+                .unwrap()
+                .trim()
+                .to_string();
+            http_get(&location);
+        });
+        Ok(())
+    }
+
+    fn run(provider: &MockProvider) -> Result<OAuthTokens, CommandError> {
+        tauri::async_runtime::block_on(run_oauth_flow_with(
+            provider,
+            &OAuthState::default(),
+            Lang::Zh,
+            Duration::from_secs(10),
+            browser,
+            |_| {},
+        ))
+    }
+
+    #[test]
+    fn test_flow_exchanges_code_with_pkce() {
+        let (base_url, bodies) =
+            start_fake_auth_server(|p| format!("code=auth-code&state={}", p["state"]));
+        let tokens = run(&MockProvider {
+            base_url,
+            pkce: true,
+        })
+        .unwrap();
+        assert_eq!(tokens.access_token, "at-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("rt-1"));
+
+        let body = bodies.lock().unwrap()[0].clone();
+        assert!(body.contains("code=auth-code"));
+        assert!(body.contains("code_verifier="));
+    }
+
+    #[test]
+    fn test_flow_without_pkce_sends_no_verifier() {
+        let (base_url, bodies) =
+            start_fake_auth_server(|p| format!("code=auth-code&state={}", p["state"]));
+        run(&MockProvider {
+            base_url,
+            pkce: false,
+        })
+        .unwrap();
+        assert!(!bodies.lock().unwrap()[0].contains("code_verifier"));
+    }
+
+    #[test]
+    fn test_flow_rejects_state_mismatch() {
+        let (base_url, bodies) = start_fake_auth_server(|_| "code=auth-code&state=forged".into());
+        let err = run(&MockProvider {
+            base_url,
+            pkce: true,
+        })
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::OAuthFailed);
+        assert!(bodies.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_flow_maps_access_denied_to_cancelled() {
+        let (base_url, _) =
+            start_fake_auth_server(|p| format!("error=access_denied&state={}", p["state"]));
+        let err = run(&MockProvider {
+            base_url,
+            pkce: true,
+        })
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::OAuthCancelled);
+    }
+
+    #[test]
+    fn test_cancel_resolves_pending_flow_and_releases_port() {
+        let (base_url, bodies) =
+            start_fake_auth_server(|p| format!("code=auth-code&state={}", p["state"]));
+        let provider = MockProvider {
+            base_url,
+            pkce: true,
+        };
+        let state = Arc::new(OAuthState::default());
+        let redirect_uri = Arc::new(Mutex::new(String::new()));
+
+        let started = std::time::Instant::now();
+        let err = tauri::async_runtime::block_on(run_oauth_flow_with(
+            &provider,
+            &state,
+            Lang::Zh,
+            Duration::from_secs(30),
+            |url| {
+                // 用户没有完成授权：记录回调地址，稍后从另一线程取消
+                let query = url.split_once('?').unwrap().1;
+                let encoded = query
+                    .split('&')
+                    .find_map(|p| p.strip_prefix("redirect_uri="))
+                    .unwrap();
+                *redirect_uri.lock().unwrap() = urlencoding::decode(encoded).unwrap().into_owned();
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(100));
+                    assert!(state.cancel());
+                });
+                Ok(())
+            },
+            |_| {},
+        ))
+        .unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::OAuthCancelled);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(bodies.lock().unwrap().is_empty());
+        assert!(state.pending_auth.lock().unwrap().is_none());
+        assert!(!state.cancel());
+
+        // 回调端口已释放，可以重新监听
+        let addr = redirect_uri
+            .lock()
+            .unwrap()
+            .trim_start_matches("http://")
+            .to_string();
+        assert!(tiny_http::Server::http(addr.as_str()).is_ok());
+    }
+
+    #[test]
+    fn test_new_flow_cancels_previous_one() {
+        let state = OAuthState::default();
+        let (first, first_cancelled) = state.begin("first");
+        let (second, second_cancelled) = state.begin("second");
+        assert!(first_cancelled.try_recv().is_ok());
+        assert!(matches!(
+            second_cancelled.try_recv(),
+            Err(mpsc::TryRecvError::Empty)
+        ));
+
+        // 旧流程结束时不能清掉新流程的登记
+        state.finish(first);
+        assert!(state.pending_auth.lock().unwrap().is_some());
+        state.finish(second);
+        assert!(state.pending_auth.lock().unwrap().is_none());
+    }
+
+    /// 在后台线程中等待回调，返回服务器地址和结果句柄
+    fn spawn_waiter(
+        expected_state: &'static str,
+    ) -> (
+        String,
+        mpsc::Sender<()>,
+        std::thread::JoinHandle<Result<String, CommandError>>,
+    ) {
+        spawn_waiter_in(expected_state, Lang::Zh)
+    }
+
+    fn spawn_waiter_in(
+        expected_state: &'static str,
+        lang: Lang,
+    ) -> (
+        String,
+        mpsc::Sender<()>,
+        std::thread::JoinHandle<Result<String, CommandError>>,
+    ) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let (cancel, cancelled) = mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            let (events, _received) = tokio::sync::mpsc::unbounded_channel();
+            let wait = CallbackWait {
+                provider: "mock",
+                port: 0,
+                expected_state,
+                lang,
+                timeout: Duration::from_secs(10),
+                cancelled: &cancelled,
+                events: &events,
+            };
+            let start = std::time::Instant::now();
+            wait_for_callback(&server, &wait, || start.elapsed())
+        });
+        (addr, cancel, handle)
+    }
+
+    /// 以指定 Host 头发送原始 GET 请求
+    fn raw_get(addr: &str, path: &str, host: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, host
+        )
+        .unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    }
+
+    fn status_line(response: &str) -> &str {
+        response.lines().next().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_callback_ignores_stray_requests_before_success() {
+        let (addr, _cancel, handle) = spawn_waiter("s1");
+
+        let favicon = raw_get(&addr, "/favicon.ico", &addr);
+        assert!(status_line(&favicon).contains("404"));
+        let partial = raw_get(&addr, "/?code=abc", &addr);
+        assert!(status_line(&partial).contains("404"));
+        let foreign = raw_get(&addr, "/?code=abc&state=s1", "evil.example:80");
+        assert!(status_line(&foreign).contains("403"));
+
+        let ok = raw_get(&addr, "/?code=abc&state=s1", &addr);
+        assert!(status_line(&ok).contains("200"));
+        assert!(ok.contains("授权成功"));
+        assert_eq!(handle.join().unwrap().unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_callback_forged_state_shows_failure_page() {
+        let (addr, _cancel, handle) = spawn_waiter("s1");
+
+        raw_get(&addr, "/favicon.ico", &addr);
+        let forged = raw_get(&addr, "/?code=abc&state=forged", &addr);
+        assert!(status_line(&forged).contains("400"));
+        assert!(forged.contains("state 校验失败"));
+        assert!(!forged.contains("授权成功"));
+        assert_eq!(
+            handle.join().unwrap().unwrap_err().code,
+            ErrorCode::OAuthFailed
+        );
+    }
+
+    #[test]
+    fn test_callback_denial_shows_escaped_error_page() {
+        let (addr, _cancel, handle) = spawn_waiter("s1");
+
+        let denied = raw_get(
+            &addr,
+            "/?error=access_denied&error_description=%3Cscript%3Ex%3C%2Fscript%3E&state=s1",
+            &addr,
+        );
+        assert!(status_line(&denied).contains("400"));
+        assert!(denied.contains("授权已拒绝"));
+        assert!(denied.contains("&lt;script&gt;"));
+        assert!(!denied.contains("<script>x"));
+        assert_eq!(
+            handle.join().unwrap().unwrap_err().code,
+            ErrorCode::OAuthCancelled
+        );
+    }
+
+    #[test]
+    fn test_callback_pages_follow_configured_language() {
+        let (addr, _cancel, handle) = spawn_waiter_in("s1", Lang::En);
+        let ok = raw_get(&addr, "/?code=abc&state=s1", &addr);
+        assert!(ok.contains(r#"<html lang="en">"#));
+        assert!(ok.contains("<h1>Authorization successful</h1>"));
+        assert!(!ok.contains("授权成功"));
+        assert!(!ok.contains("{{"));
+        handle.join().unwrap().unwrap();
+
+        let (addr, _cancel, handle) = spawn_waiter_in("s1", Lang::En);
+        let denied = raw_get(&addr, "/?error=access_denied&state=s1", &addr);
+        assert!(denied.contains("<h1>Authorization denied</h1>"));
+        assert!(denied.contains("Unknown error<br>Please close this window"));
+        let err = handle.join().unwrap().unwrap_err();
+        assert_eq!(err.message_id, Some(MessageId::OAuthProviderError));
+        assert_eq!(err.params["error"], "access_denied");
+    }
+
+    #[test]
+    fn test_waiting_countdown_and_timeout_events() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let (_cancel, cancelled) = mpsc::channel();
+        let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let wait = CallbackWait {
+            provider: "mock",
+            port: 4242,
+            expected_state: "s1",
+            lang: Lang::Zh,
+            timeout: Duration::from_secs(3),
+            cancelled: &cancelled,
+            events: &events,
+        };
+        // 假时钟：每轮轮询前进 500ms，不依赖真实等待
+        let ticks = std::cell::Cell::new(0u64);
+        let clock = || {
+            let elapsed = Duration::from_millis(500 * ticks.get());
+            ticks.set(ticks.get() + 1);
+            elapsed
+        };
+        let err = wait_for_callback(&server, &wait, clock).unwrap_err();
+        assert_eq!(err.code, ErrorCode::OAuthTimeout);
+        assert_eq!(ticks.get(), 7);
+
+        let waiting = |seconds_remaining| {
+            OAuthWaitEvent::Waiting(OAuthWaiting {
+                provider: "mock",
+                port: 4242,
+                seconds_remaining,
+            })
+        };
+        let mut seen = Vec::new();
+        while let Ok(event) = received.try_recv() {
+            seen.push(event);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                waiting(3),
+                waiting(2),
+                waiting(1),
+                OAuthWaitEvent::TimedOut(OAuthWaiting {
+                    provider: "mock",
+                    port: 4242,
+                    seconds_remaining: 0,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flow_forwards_wait_events_before_returning() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let err = tauri::async_runtime::block_on(run_oauth_flow_with(
+            &MockProvider {
+                base_url: "http://127.0.0.1:9".to_string(),
+                pkce: true,
+            },
+            &OAuthState::default(),
+            Lang::Zh,
+            Duration::from_millis(300),
+            |_| Ok(()),
+            move |event| sink.lock().unwrap().push(event),
+        ))
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::OAuthTimeout);
+        let seen = seen.lock().unwrap();
+        assert!(matches!(
+            seen.first(),
+            Some(OAuthWaitEvent::Waiting(OAuthWaiting {
+                seconds_remaining: 1,
+                ..
+            }))
+        ));
+        assert!(matches!(seen.last(), Some(OAuthWaitEvent::TimedOut(_))));
+    }
+
+    #[test]
+    fn test_callback_timeout_is_clamped() {
+        assert_eq!(callback_timeout(None), CALLBACK_TIMEOUT);
+        assert_eq!(callback_timeout(Some(900)), Duration::from_secs(900));
+        assert_eq!(callback_timeout(Some(1)), MIN_CALLBACK_TIMEOUT);
+        assert_eq!(callback_timeout(Some(u64::MAX)), MAX_CALLBACK_TIMEOUT);
+    }
+}
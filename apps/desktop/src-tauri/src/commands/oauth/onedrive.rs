@@ -0,0 +1,325 @@
+//! Microsoft OneDrive：Microsoft 身份平台（v2.0 端点，PKCE）授权，Graph API 获取用户信息与配额。
+
+use ai_disk_common::CommandError;
+use serde::Deserialize;
+use tauri::{AppHandle, State};
+
+use super::flow::{run_oauth_flow, OAuthProvider};
+use super::refresh::refresh_with_retry;
+use super::{de_seconds, default_token_type, OAuthState, OAuthTokens};
+use crate::commands::config::ConfigState;
+use crate::commands::credentials::{
+    refresh_stored, save_oauth_tokens, CredentialsStatus, OAuthConnection,
+};
+use crate::commands::errors::{parse_error, request_error, status_error};
+use crate::commands::token_manager::TokenManager;
+
+// OneDrive OAuth 配置 - 从环境变量读取（编译时嵌入，如果不存在则使用空字符串）
+const ONEDRIVE_CLIENT_ID: &str = match option_env!("ONEDRIVE_CLIENT_ID") {
+    Some(id) => id,
+    None => "",
+};
+// 桌面应用注册为公共客户端时没有 secret，仅在配置了时才发送
+const ONEDRIVE_CLIENT_SECRET: &str = match option_env!("ONEDRIVE_CLIENT_SECRET") {
+    Some(secret) => secret,
+    None => "",
+};
+const ONEDRIVE_AUTH_URL: &str = "https://login.microsoftonline.com/common/oauth2/v2.0/authorize";
+const ONEDRIVE_TOKEN_URL: &str = "https://login.microsoftonline.com/common/oauth2/v2.0/token";
+const ONEDRIVE_SCOPES: &str = "Files.ReadWrite offline_access User.Read";
+const GRAPH_API_URL: &str = "https://graph.microsoft.com/v1.0";
+
+/// Microsoft 身份平台的 token 响应。
+/// v1 端点及部分代理会把 `expires_in`/`ext_expires_in` 返回为字符串；
+/// `ext_expires_in` 是服务端故障时的延长有效期，仅在缺少 `expires_in` 时使用
+#[derive(Debug, Deserialize)]
+struct AadTokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    #[serde(default, deserialize_with = "de_seconds")]
+    expires_in: Option<u64>,
+    #[serde(default, deserialize_with = "de_seconds")]
+    ext_expires_in: Option<u64>,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+    scope: Option<String>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 解析 Microsoft 身份平台 token 响应
+fn parse_aad_tokens(body: &str) -> Result<OAuthTokens, serde_json::Error> {
+    let raw: AadTokenResponse = serde_json::from_str(body)?;
+    let expires_in = raw
+        .expires_in
+        .or(raw.ext_expires_in)
+        .ok_or_else(|| <serde_json::Error as serde::de::Error>::missing_field("expires_in"))?;
+    Ok(OAuthTokens {
+        access_token: raw.access_token,
+        refresh_token: raw.refresh_token,
+        expires_in,
+        token_type: raw.token_type,
+        scope: raw.scope,
+        extra: raw.extra,
+    })
+}
+
+/// OneDrive 使用 PKCE；token 端点需要在请求中重复 scope
+pub(super) struct OneDriveProvider {
+    pub(super) token_url: String,
+}
+
+impl Default for OneDriveProvider {
+    fn default() -> Self {
+        Self {
+            token_url: ONEDRIVE_TOKEN_URL.to_string(),
+        }
+    }
+}
+
+impl OAuthProvider for OneDriveProvider {
+    fn name(&self) -> &'static str {
+        "onedrive"
+    }
+
+    fn supports_pkce(&self) -> bool {
+        true
+    }
+
+    fn auth_url(&self, redirect_uri: &str, state: &str, code_challenge: Option<&str>) -> String {
+        format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&response_mode=query&scope={}&code_challenge={}&code_challenge_method=S256&state={}",
+            ONEDRIVE_AUTH_URL,
+            urlencoding::encode(ONEDRIVE_CLIENT_ID),
+            urlencoding::encode(redirect_uri),
+            urlencoding::encode(ONEDRIVE_SCOPES),
+            urlencoding::encode(code_challenge.unwrap_or_default()),
+            urlencoding::encode(state)
+        )
+    }
+
+    fn token_request(
+        &self,
+        client: &reqwest::Client,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> reqwest::RequestBuilder {
+        let mut form = vec![
+            ("client_id", ONEDRIVE_CLIENT_ID),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("scope", ONEDRIVE_SCOPES),
+            ("code_verifier", code_verifier.unwrap_or_default()),
+        ];
+        if !ONEDRIVE_CLIENT_SECRET.is_empty() {
+            form.push(("client_secret", ONEDRIVE_CLIENT_SECRET));
+        }
+        client.post(&self.token_url).form(&form)
+    }
+
+    fn parse_tokens(&self, body: &str) -> Result<OAuthTokens, serde_json::Error> {
+        parse_aad_tokens(body)
+    }
+
+    fn refresh_request(
+        &self,
+        client: &reqwest::Client,
+        refresh_token: &str,
+    ) -> reqwest::RequestBuilder {
+        let mut form = vec![
+            ("client_id", ONEDRIVE_CLIENT_ID),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("scope", ONEDRIVE_SCOPES),
+        ];
+        if !ONEDRIVE_CLIENT_SECRET.is_empty() {
+            form.push(("client_secret", ONEDRIVE_CLIENT_SECRET));
+        }
+        client.post(&self.token_url).form(&form)
+    }
+}
+
+/// 完成 OneDrive OAuth 授权（等待回调并交换 token），token 直接保存到凭据存储。
+/// `account_id` 为重新授权的已有账号，新连接时省略
+#[tauri::command]
+pub async fn complete_onedrive_oauth(
+    app: AppHandle,
+    oauth_state: State<'_, OAuthState>,
+    config_state: State<'_, ConfigState>,
+    tokens: State<'_, TokenManager>,
+    account_id: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<OAuthConnection, CommandError> {
+    let oauth_tokens = run_oauth_flow(
+        &OneDriveProvider::default(),
+        &oauth_state,
+        &app,
+        config_state.get().ui.language,
+        timeout_secs,
+    )
+    .await?;
+    save_oauth_tokens(tokens.store(), "onedrive", account_id, oauth_tokens)
+}
+
+/// 用 refresh token 换取新的 OneDrive token（供 `TokenManager` 使用）
+pub(crate) async fn refresh_onedrive(refresh_token: &str) -> Result<OAuthTokens, CommandError> {
+    refresh_with_retry(&OneDriveProvider::default(), refresh_token).await
+}
+
+/// 用已保存的 refresh token 刷新 OneDrive access token 并写回凭据存储
+#[tauri::command]
+pub async fn refresh_onedrive_token(
+    tokens: State<'_, TokenManager>,
+    account_id: String,
+) -> Result<CredentialsStatus, CommandError> {
+    refresh_stored(&tokens, "onedrive", &account_id).await
+}
+
+/// 撤销 OneDrive OAuth 授权
+/// 注意：Microsoft 身份平台没有撤销单个 token 的端点（revokeSignInSessions 会注销用户所有会话），
+/// 这里只在本地丢弃 token
+#[tauri::command]
+pub async fn revoke_onedrive_token(_account_id: String) -> Result<(), CommandError> {
+    tracing::info!(provider = "onedrive", "token 撤销（仅本地丢弃）");
+    Ok(())
+}
+
+async fn graph_get(
+    access_token: &str,
+    path: &str,
+    context: &str,
+) -> Result<serde_json::Value, CommandError> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}{}", GRAPH_API_URL, path))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| request_error(&format!("{}失败", context), &e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(status_error(
+            &format!("{}失败", context),
+            status,
+            &error_text,
+        ));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| parse_error(&format!("解析{}失败", context), e))
+}
+
+/// 获取 OneDrive 用户信息（Graph /me）
+#[tauri::command]
+pub async fn get_onedrive_user_info(
+    tokens: State<'_, TokenManager>,
+    account_id: String,
+) -> Result<serde_json::Value, CommandError> {
+    let access_token = tokens
+        .get_valid_access_token("onedrive", &account_id)
+        .await?;
+    graph_get(&access_token, "/me", "获取用户信息").await
+}
+
+/// 获取 OneDrive 存储配额信息（Graph /me/drive，配额位于 `quota` 字段）
+#[tauri::command]
+pub async fn get_onedrive_quota(
+    tokens: State<'_, TokenManager>,
+    account_id: String,
+) -> Result<serde_json::Value, CommandError> {
+    let access_token = tokens
+        .get_valid_access_token("onedrive", &account_id)
+        .await?;
+    graph_get(&access_token, "/me/drive", "获取存储配额").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    /// 模拟 token 端点：记录请求体并返回固定响应
+    fn start_token_endpoint(response: &'static str) -> (String, Arc<Mutex<Vec<String>>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", server.server_addr().to_ip().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                recorded.lock().unwrap().push(body);
+                let _ = request.respond(tiny_http::Response::from_string(response));
+            }
+        });
+        (url, bodies)
+    }
+
+    #[test]
+    fn test_parse_aad_tokens_with_ext_expires_in() {
+        let body = r#"{"token_type":"Bearer","scope":"Files.ReadWrite User.Read","expires_in":3599,"ext_expires_in":3599,"access_token":"eyJ0","refresh_token":"M.R3"}"#;
+        let tokens = parse_aad_tokens(body).unwrap();
+        assert_eq!(tokens.expires_in, 3599);
+        assert_eq!(tokens.refresh_token.as_deref(), Some("M.R3"));
+
+        // 字符串形式的秒数，且只有 ext_expires_in
+        let body = r#"{"token_type":"Bearer","ext_expires_in":"7200","access_token":"a"}"#;
+        assert_eq!(parse_aad_tokens(body).unwrap().expires_in, 7200);
+
+        assert!(parse_aad_tokens(r#"{"token_type":"Bearer","access_token":"a"}"#).is_err());
+    }
+
+    #[test]
+    fn test_code_exchange_against_mock_endpoint() {
+        let (token_url, bodies) = start_token_endpoint(
+            r#"{"token_type":"Bearer","scope":"Files.ReadWrite","expires_in":"3600","ext_expires_in":"3600","access_token":"at","refresh_token":"rt"}"#,
+        );
+        let provider = OneDriveProvider { token_url };
+        let tokens = tauri::async_runtime::block_on(async {
+            let client = reqwest::Client::new();
+            let text = provider
+                .token_request(
+                    &client,
+                    "code-1",
+                    "http://127.0.0.1:50000",
+                    Some("verifier-1"),
+                )
+                .send()
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            provider.parse_tokens(&text).unwrap()
+        });
+        assert_eq!(tokens.access_token, "at");
+        assert_eq!(tokens.expires_in, 3600);
+
+        let body = bodies.lock().unwrap()[0].clone();
+        assert!(body.contains("grant_type=authorization_code"));
+        assert!(body.contains("code_verifier=verifier-1"));
+        assert!(body.contains("offline_access"));
+    }
+
+    #[test]
+    fn test_refresh_against_mock_endpoint() {
+        let (token_url, bodies) = start_token_endpoint(
+            r#"{"token_type":"Bearer","expires_in":3599,"ext_expires_in":3599,"access_token":"at2","refresh_token":"rt2"}"#,
+        );
+        let tokens = tauri::async_runtime::block_on(refresh_with_retry(
+            &OneDriveProvider { token_url },
+            "rt1",
+        ))
+        .unwrap();
+        assert_eq!(tokens.access_token, "at2");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("rt2"));
+        assert!(bodies.lock().unwrap()[0].contains("refresh_token=rt1"));
+    }
+}
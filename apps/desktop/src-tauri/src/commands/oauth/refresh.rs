@@ -0,0 +1,245 @@
+//! 各服务商统一的 token 刷新策略：请求超时 30 秒，网络错误、服务端错误等临时失败最多尝试 3 次，
+//! 第 n 次失败后等待 n 秒再试。401/403 不重试；服务商表示 refresh token 已撤销或过期的响应
+//! （见 [`OAuthProvider::is_refresh_revoked`]）同样不重试，以 `ReauthRequired` 返回。
+
+use std::time::Duration;
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+
+use super::flow::OAuthProvider;
+use super::OAuthTokens;
+use crate::commands::errors::{parse_error, request_error, status_error};
+
+/// 刷新 token 的超时与重试参数
+pub(crate) struct RefreshPolicy {
+    /// 单次请求超时
+    pub timeout: Duration,
+    /// 最多尝试次数（含第一次）
+    pub max_attempts: u32,
+    /// 第 n 次失败后等待 `backoff * n`
+    pub backoff: Duration,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// 单次尝试的失败：是否值得重试
+enum RefreshFailure {
+    Transient(CommandError),
+    Permanent(CommandError),
+}
+
+/// 按默认策略刷新 token
+pub(crate) async fn refresh_with_retry<P: OAuthProvider>(
+    provider: &P,
+    refresh_token: &str,
+) -> Result<OAuthTokens, CommandError> {
+    refresh_with_policy(provider, refresh_token, &RefreshPolicy::default()).await
+}
+
+pub(crate) async fn refresh_with_policy<P: OAuthProvider>(
+    provider: &P,
+    refresh_token: &str,
+    policy: &RefreshPolicy,
+) -> Result<OAuthTokens, CommandError> {
+    let client = reqwest::Client::builder()
+        .timeout(policy.timeout)
+        .build()
+        .map_err(|e| request_error(MessageId::TokenRequestFailed, &e))?;
+    let name = provider.name();
+    let mut attempt = 1;
+    loop {
+        match refresh_once(provider, &client, refresh_token).await {
+            Ok(tokens) => return Ok(tokens),
+            Err(RefreshFailure::Permanent(e)) => {
+                tracing::warn!(provider = name, attempt, code = ?e.code, "刷新 token 失败，不再重试");
+                return Err(e);
+            }
+            Err(RefreshFailure::Transient(e)) if attempt >= policy.max_attempts => {
+                tracing::warn!(provider = name, attempt, error = %e, "刷新 token 失败，已达重试上限");
+                return Err(e);
+            }
+            Err(RefreshFailure::Transient(e)) => {
+                tracing::info!(provider = name, attempt, error = %e, "刷新 token 失败，稍后重试");
+                tokio::time::sleep(policy.backoff * attempt).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+async fn refresh_once<P: OAuthProvider>(
+    provider: &P,
+    client: &reqwest::Client,
+    refresh_token: &str,
+) -> Result<OAuthTokens, RefreshFailure> {
+    let response = provider
+        .refresh_request(client, refresh_token)
+        .send()
+        .await
+        .map_err(|e| RefreshFailure::Transient(request_error(MessageId::TokenRequestFailed, &e)))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| RefreshFailure::Transient(request_error(MessageId::TokenReadFailed, &e)))?;
+
+    if provider.is_refresh_revoked(&body) {
+        return Err(RefreshFailure::Permanent(reauth_required(provider, &body)));
+    }
+    if !status.is_success() {
+        let e = status_error(MessageId::TokenRequestFailed, status, &body);
+        return Err(if e.code == ErrorCode::Unauthorized {
+            RefreshFailure::Permanent(e)
+        } else {
+            RefreshFailure::Transient(e)
+        });
+    }
+    provider
+        .parse_tokens(&body)
+        .map_err(|e| RefreshFailure::Transient(parse_error(MessageId::TokenParseFailed, e)))
+}
+
+fn reauth_required<P: OAuthProvider>(provider: &P, body: &str) -> CommandError {
+    CommandError::localized(
+        ErrorCode::ReauthRequired,
+        MessageId::ReauthRequired,
+        &[("provider", provider.name()), ("error", body)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::oauth::onedrive::OneDriveProvider;
+    use crate::commands::oauth::{AliyunProvider, BaiduProvider, DropboxProvider, GoogleProvider};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const TOKENS: &str = r#"{"access_token":"at-2","refresh_token":"rt-2","expires_in":3600,"token_type":"Bearer","scope":null}"#;
+
+    /// 按脚本依次返回 (状态码, 响应体) 的假 token 端点，返回其 URL 与收到的请求数
+    fn scripted_endpoint(script: Vec<(u16, &'static str)>) -> (String, Arc<AtomicUsize>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", server.server_addr().to_ip().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let (status, body) = script.get(n).copied().unwrap_or((500, ""));
+                let _ = request
+                    .respond(tiny_http::Response::from_string(body).with_status_code(status));
+            }
+        });
+        (url, hits)
+    }
+
+    fn fast_policy() -> RefreshPolicy {
+        RefreshPolicy {
+            timeout: Duration::from_secs(5),
+            max_attempts: 3,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn refresh<P: OAuthProvider>(provider: &P) -> Result<OAuthTokens, CommandError> {
+        tauri::async_runtime::block_on(refresh_with_policy(provider, "rt-1", &fast_policy()))
+    }
+
+    /// 各服务商：临时失败后成功、持续失败、永久失效三种脚本
+    fn check_provider<P: OAuthProvider>(make: impl Fn(String) -> P, revoked: (u16, &'static str)) {
+        let name = make(String::new()).name();
+
+        let (url, hits) = scripted_endpoint(vec![(503, "busy"), (502, ""), (200, TOKENS)]);
+        let tokens = refresh(&make(url)).unwrap();
+        assert_eq!(tokens.access_token, "at-2", "{}", name);
+        assert_eq!(hits.load(Ordering::SeqCst), 3, "{}", name);
+
+        let (url, hits) = scripted_endpoint(vec![(503, ""), (503, ""), (503, ""), (200, TOKENS)]);
+        let err = refresh(&make(url)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Network, "{}", name);
+        assert_eq!(hits.load(Ordering::SeqCst), 3, "{}", name);
+
+        let (url, hits) = scripted_endpoint(vec![revoked, (200, TOKENS)]);
+        let err = refresh(&make(url)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ReauthRequired, "{}", name);
+        assert_eq!(err.message_id, Some(MessageId::ReauthRequired));
+        assert_eq!(hits.load(Ordering::SeqCst), 1, "{}", name);
+    }
+
+    #[test]
+    fn test_google_refresh_policy() {
+        check_provider(
+            |token_url| GoogleProvider { token_url },
+            (
+                400,
+                r#"{"error":"invalid_grant","error_description":"Token has been expired or revoked."}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_baidu_refresh_policy() {
+        check_provider(
+            |token_url| BaiduProvider { token_url },
+            (
+                400,
+                r#"{"error":"expired_token","error_description":"refresh token has been used"}"#,
+            ),
+        );
+        // 以 errno 表示身份验证失败的响应（HTTP 200）同样视为失效
+        let (url, hits) = scripted_endpoint(vec![(200, r#"{"errno":-6,"errmsg":"invalid"}"#)]);
+        let err = refresh(&BaiduProvider { token_url: url }).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ReauthRequired);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_aliyun_refresh_policy() {
+        check_provider(
+            |token_url| AliyunProvider { token_url },
+            (
+                400,
+                r#"{"code":"InvalidRefreshToken","message":"refresh token is invalid"}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_dropbox_refresh_policy() {
+        check_provider(
+            |token_url| DropboxProvider { token_url },
+            (
+                400,
+                r#"{"error":"invalid_grant","error_description":"refresh token is invalid or revoked"}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_onedrive_refresh_policy() {
+        check_provider(
+            |token_url| OneDriveProvider { token_url },
+            (
+                400,
+                r#"{"error":"invalid_grant","error_description":"AADSTS700082: The refresh token has expired"}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_unauthorized_is_not_retried() {
+        let (url, hits) = scripted_endpoint(vec![(401, "unauthorized"), (200, TOKENS)]);
+        let err = refresh(&DropboxProvider { token_url: url }).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,14 +1,131 @@
 //! 在系统文件管理器中打开路径：Windows 为资源管理器，macOS 为 Finder。
 
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+use serde::Serialize;
+
+/// 去掉 canonicalize 在 Windows 上产生的 `\\?\` 前缀，资源管理器不识别这种路径
+#[cfg(any(windows, test))]
+fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// 资源管理器的 `/select,"path"` 参数。explorer 自己解析命令行而不遵循 argv 规则，
+/// 必须用 `raw_arg` 原样传入并把路径整体放在引号内，空格与逗号才不会拆开参数；
+/// Windows 路径中不会出现双引号，无需转义
+#[cfg(any(windows, test))]
+fn explorer_select_arg(path: &str) -> String {
+    format!("/select,\"{}\"", strip_verbatim_prefix(path))
+}
+
+/// AppleScript 字符串字面量
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 在 Finder 中一次选中多个文件的脚本，作为 `osascript -e` 的参数传入（不经过 shell）
+#[cfg(any(target_os = "macos", test))]
+fn finder_reveal_script(paths: &[PathBuf]) -> String {
+    let items = paths
+        .iter()
+        .map(|p| format!("POSIX file {}", applescript_string(&p.to_string_lossy())))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "tell application \"Finder\"\nreveal {{{}}}\nactivate\nend tell",
+        items
+    )
+}
+
+#[cfg(windows)]
+fn explorer_raw(arg: String) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    Command::new("explorer")
+        .raw_arg(arg)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("无法打开资源管理器: {}", e))
+}
+
+/// 用 SHOpenFolderAndSelectItems 在同一个资源管理器窗口中选中 `folder` 下的多个文件
+#[cfg(windows)]
+fn select_in_explorer(folder: &Path, items: &[PathBuf]) -> Result<(), String> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::System::Com::{
+        CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED,
+    };
+    use windows_sys::Win32::UI::Shell::Common::ITEMIDLIST;
+    use windows_sys::Win32::UI::Shell::{ILCreateFromPathW, ILFree, SHOpenFolderAndSelectItems};
+
+    fn wide(path: &Path) -> Vec<u16> {
+        OsStr::new(&strip_verbatim_prefix(&path.to_string_lossy()))
+            .encode_wide()
+            .chain(Some(0))
+            .collect()
+    }
+
+    // SAFETY: 传入的字符串均以 NUL 结尾；创建的 PIDL 在返回前全部释放
+    unsafe {
+        let init = CoInitializeEx(std::ptr::null(), COINIT_APARTMENTTHREADED);
+        let folder_pidl = ILCreateFromPathW(wide(folder).as_ptr());
+        let item_pidls: Vec<*const ITEMIDLIST> = items
+            .iter()
+            .map(|p| ILCreateFromPathW(wide(p).as_ptr()) as *const ITEMIDLIST)
+            .filter(|p| !p.is_null())
+            .collect();
+        let hr = if folder_pidl.is_null() || item_pidls.is_empty() {
+            -1
+        } else {
+            SHOpenFolderAndSelectItems(folder_pidl, item_pidls.len() as u32, item_pidls.as_ptr(), 0)
+        };
+        for pidl in &item_pidls {
+            ILFree(*pidl);
+        }
+        if !folder_pidl.is_null() {
+            ILFree(folder_pidl);
+        }
+        if init >= 0 {
+            CoUninitialize();
+        }
+        if hr < 0 {
+            Err(format!("SHOpenFolderAndSelectItems 失败 (0x{:08X})", hr))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 最近的仍然存在的祖先目录；路径本身存在时返回 None
+fn nearest_existing_ancestor(path: &Path) -> Option<&Path> {
+    if path.exists() {
+        return None;
+    }
+    path.ancestors()
+        .skip(1)
+        .find(|p| !p.as_os_str().is_empty() && p.is_dir())
+}
+
+/// 打开路径；路径已不存在（例如计划中的文件已被清理，或位于浅扫描的子树中）时，
+/// 退而打开最近的仍然存在的祖先目录
 #[tauri::command]
 pub async fn open_in_file_manager(path: String, is_file: bool) -> Result<(), String> {
-    let path_buf = Path::new(&path);
-    if !path_buf.exists() {
-        return Err(format!("路径不存在: {}", path));
-    }
+    let requested = Path::new(&path);
+    let (path_buf, is_file) = match nearest_existing_ancestor(requested) {
+        Some(ancestor) => (ancestor, false),
+        None if requested.exists() => (requested, is_file),
+        None => return Err(format!("路径不存在: {}", path)),
+    };
 
     #[cfg(windows)]
     {
@@ -17,16 +134,12 @@ pub async fn open_in_file_manager(path: String, is_file: bool) -> Result<(), Str
             .map_err(|e| format!("无法解析路径: {}", e))?;
         let path_str = path_abs.to_string_lossy();
         if is_file {
-            // 打开资源管理器并选中该文件（格式: /select,"path"）
-            let arg = format!("/select,\"{}\"", path_str.replace('"', "\\\""));
-            Command::new("explorer")
-                .arg(arg)
-                .spawn()
-                .map_err(|e| format!("无法打开资源管理器: {}", e))?;
+            // 打开资源管理器并选中该文件
+            explorer_raw(explorer_select_arg(&path_str))?;
         } else {
-            // 打开该文件夹
+            // 打开该文件夹；普通参数即可，由标准库负责引号
             Command::new("explorer")
-                .arg(path_str.as_ref())
+                .arg(strip_verbatim_prefix(&path_str))
                 .spawn()
                 .map_err(|e| format!("无法打开资源管理器: {}", e))?;
         }
@@ -71,3 +184,207 @@ pub async fn open_in_file_manager(path: String, is_file: bool) -> Result<(), Str
 
     Ok(())
 }
+
+/// 批量显示中单个路径的失败原因
+#[derive(Debug, Serialize)]
+pub struct RevealFailure {
+    pub path: String,
+    pub error: CommandError,
+}
+
+/// `reveal_in_file_manager` 的结果：成功显示的路径与逐个报告的失败
+#[derive(Debug, Default, Serialize)]
+pub struct RevealReport {
+    pub revealed: Vec<String>,
+    pub failed: Vec<RevealFailure>,
+}
+
+/// 按父目录分组，同一目录下的文件在一个窗口中选中；不存在的路径单独记为失败
+fn group_by_parent(
+    paths: &[String],
+    report: &mut RevealReport,
+) -> BTreeMap<PathBuf, Vec<(String, PathBuf)>> {
+    let mut groups: BTreeMap<PathBuf, Vec<(String, PathBuf)>> = BTreeMap::new();
+    for path in paths {
+        match Path::new(path).canonicalize() {
+            Ok(abs) => {
+                let parent = abs.parent().unwrap_or(&abs).to_path_buf();
+                groups.entry(parent).or_default().push((path.clone(), abs));
+            }
+            Err(e) => report.failed.push(RevealFailure {
+                path: path.clone(),
+                error: if e.kind() == std::io::ErrorKind::NotFound {
+                    CommandError::localized(
+                        ErrorCode::PathNotFound,
+                        MessageId::PathNotFound,
+                        &[("path", path)],
+                    )
+                } else {
+                    CommandError::io_localized(MessageId::PathUnresolvable, &[("path", path)], &e)
+                },
+            }),
+        }
+    }
+    groups
+}
+
+/// 在文件管理器中选中同一目录下的一组文件
+fn reveal_group(folder: &Path, items: &[PathBuf]) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        if let Err(e) = select_in_explorer(folder, items) {
+            tracing::warn!(error = %e, "批量选中失败，退回到只选中第一个文件");
+            let first = items.first().map(|p| p.as_path()).unwrap_or(folder);
+            explorer_raw(explorer_select_arg(&first.to_string_lossy()))?;
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = folder;
+        Command::new("osascript")
+            .args(["-e", &finder_reveal_script(items)])
+            .spawn()
+            .map_err(|e| format!("无法打开 Finder: {}", e))?;
+    }
+
+    // Linux 文件管理器没有统一的多选接口，只打开所在目录
+    #[cfg(target_os = "linux")]
+    {
+        let _ = items;
+        Command::new("xdg-open")
+            .arg(folder)
+            .spawn()
+            .map_err(|e| format!("无法打开文件管理器: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// 在文件管理器中同时选中多个路径；位于同一目录的文件共用一个窗口。
+/// 不存在或无法打开的路径在结果中逐个报告，不影响其他路径
+#[tauri::command]
+pub async fn reveal_in_file_manager(paths: Vec<String>) -> Result<RevealReport, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut report = RevealReport::default();
+        for (folder, entries) in group_by_parent(&paths, &mut report) {
+            let items: Vec<PathBuf> = entries.iter().map(|(_, abs)| abs.clone()).collect();
+            match reveal_group(&folder, &items) {
+                Ok(()) => report
+                    .revealed
+                    .extend(entries.into_iter().map(|(path, _)| path)),
+                Err(message) => {
+                    report
+                        .failed
+                        .extend(entries.into_iter().map(|(path, _)| RevealFailure {
+                            path,
+                            error: CommandError::internal(message.clone()),
+                        }))
+                }
+            }
+        }
+        report
+    })
+    .await
+    .map_err(|e| {
+        CommandError::localized(
+            ErrorCode::Internal,
+            MessageId::OpenFileManagerFailed,
+            &[("error", &e.to_string())],
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explorer_select_arg_quotes_whole_path() {
+        let cases = [
+            (
+                r"C:\Users\me\My Files\a b.txt",
+                r#"/select,"C:\Users\me\My Files\a b.txt""#,
+            ),
+            (
+                r"C:\data\a,b, c\x,y.log",
+                r#"/select,"C:\data\a,b, c\x,y.log""#,
+            ),
+            (
+                r"D:\照片\2024 年,旅行\海边 (1).jpg",
+                r#"/select,"D:\照片\2024 年,旅行\海边 (1).jpg""#,
+            ),
+            (
+                r"\\?\C:\Program Files\app.exe",
+                r#"/select,"C:\Program Files\app.exe""#,
+            ),
+            (
+                r"\\?\UNC\server\共享 盘\a,b.txt",
+                r#"/select,"\\server\共享 盘\a,b.txt""#,
+            ),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(explorer_select_arg(path), expected);
+        }
+    }
+
+    #[test]
+    fn test_finder_script_escapes_paths() {
+        let script = finder_reveal_script(&[
+            PathBuf::from("/Users/me/My Files/a, b.txt"),
+            PathBuf::from("/Users/me/下载/引号\"文件\\名.zip"),
+        ]);
+        assert!(script.contains(r#"POSIX file "/Users/me/My Files/a, b.txt""#));
+        assert!(script.contains(r#"POSIX file "/Users/me/下载/引号\"文件\\名.zip""#));
+        assert!(script.starts_with("tell application \"Finder\""));
+    }
+
+    #[test]
+    fn test_missing_path_falls_back_to_nearest_existing_ancestor() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("a b");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(sub.join("f.txt"), b"x").unwrap();
+
+        assert_eq!(nearest_existing_ancestor(&sub.join("f.txt")), None);
+        assert_eq!(nearest_existing_ancestor(&sub), None);
+        let missing = sub.join("gone").join("deeper").join("x.bin");
+        assert_eq!(nearest_existing_ancestor(&missing), Some(sub.as_path()));
+        // 父路径上是文件而不是目录时继续向上
+        let under_file = sub.join("f.txt").join("x");
+        assert_eq!(nearest_existing_ancestor(&under_file), Some(sub.as_path()));
+    }
+
+    #[test]
+    fn test_groups_by_parent_and_reports_missing_individually() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("子目录, 带空格");
+        std::fs::create_dir_all(&sub).unwrap();
+        for name in ["a b.txt", "c,d.txt"] {
+            std::fs::write(sub.join(name), b"x").unwrap();
+        }
+        std::fs::write(dir.path().join("文件.txt"), b"x").unwrap();
+
+        let paths: Vec<String> = [
+            sub.join("a b.txt"),
+            dir.path().join("缺失.txt"),
+            sub.join("c,d.txt"),
+            dir.path().join("文件.txt"),
+        ]
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+
+        let mut report = RevealReport::default();
+        let groups = group_by_parent(&paths, &mut report);
+        assert_eq!(groups.len(), 2);
+        let sub_group = &groups[&sub.canonicalize().unwrap()];
+        assert_eq!(sub_group.len(), 2);
+        assert_eq!(sub_group[0].0, paths[0]);
+        assert_eq!(sub_group[1].0, paths[2]);
+
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, paths[1]);
+        assert_eq!(report.failed[0].error.code, ErrorCode::PathNotFound);
+    }
+}
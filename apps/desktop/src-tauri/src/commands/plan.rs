@@ -1,6 +1,149 @@
-use ai_disk_domain::CleanupPlan;
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_domain::{CleanupPlan, PlanSummary, PlannedAction, ScanResult};
+use tauri::{async_runtime, AppHandle, State};
 
+use super::scan::ScanStore;
+use super::sessions::record_plan;
+
+/// 生成清理计划。扫描结果优先按 `scan_id` 取后端缓存，避免经 IPC 回传整棵树；
+/// 未进入缓存的结果（如从文件加载）可直接传入 `scan_result`。传入 `session_id` 时把通过校验的计划写入该会话
+#[tauri::command]
+pub async fn get_cleanup_plan(
+    app: AppHandle,
+    scan_store: State<'_, ScanStore>,
+    scan_id: Option<String>,
+    scan_result: Option<ScanResult>,
+    session_id: Option<String>,
+) -> Result<CleanupPlan, CommandError> {
+    match (&scan_result, &scan_id) {
+        (Some(scan), _) => tracing::info!(
+            file_count = scan.file_count,
+            "cleanup plan requested with inline scan result"
+        ),
+        (None, Some(id)) => tracing::info!(
+            payload_bytes = id.len(),
+            "cleanup plan requested by scan_id"
+        ),
+        (None, None) => {}
+    }
+    let scan = scan_store.resolve(scan_id, scan_result)?;
+    let plan = plan_for(&scan).await?;
+    if let Some(session_id) = session_id {
+        record_plan(&app, &session_id, &plan, &scan)?;
+    }
+    Ok(plan)
+}
+
+/// 已弃用：旧版前端传入整份扫描结果的 JSON 字符串。解析后转交 [`get_cleanup_plan`] 的同一逻辑，
+/// 保留一个版本后移除
+#[tauri::command]
+pub async fn get_cleanup_plan_json(scan_result: String) -> Result<CleanupPlan, CommandError> {
+    tracing::warn!(
+        payload_bytes = scan_result.len(),
+        "get_cleanup_plan_json is deprecated, pass scan_id to get_cleanup_plan instead"
+    );
+    let scan = parse_scan_json(&scan_result)?;
+    plan_for(&scan).await
+}
+
+fn parse_scan_json(json: &str) -> Result<ScanResult, CommandError> {
+    serde_json::from_str(json).map_err(|e| {
+        CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("扫描结果 JSON 无效: {}", e),
+        )
+    })
+}
+
+/// 生成并校验计划：返回的计划只含通过校验的动作，并带有各动作目标在这次扫描中的状态
+async fn plan_for(scan: &ScanResult) -> Result<CleanupPlan, CommandError> {
+    let plan = ai_disk_engine::plan_cleanup(scan)
+        .await
+        .map_err(CommandError::internal)
+        .inspect_err(|e| ai_disk_common::record_error(e.code))?;
+    let (plan, rejected) = ai_disk_engine::validate_plan(&plan, scan);
+    for reason in rejected {
+        tracing::warn!(%reason, "计划中丢弃未通过校验的动作");
+    }
+    ai_disk_common::record_plan_generated(plan.actions.len() as u64, "builtin");
+    Ok(plan)
+}
+
+/// 计划预览：按风险、类别与目录汇总动作，并列出最大的若干个动作。
+/// 扫描结果的来源与 analyze_disk 相同
 #[tauri::command]
-pub async fn get_cleanup_plan(scan_result: String) -> Result<CleanupPlan, String> {
-    ai_disk_engine::plan_cleanup(&scan_result).await
+pub async fn summarize_plan(
+    scan_store: State<'_, ScanStore>,
+    actions: Vec<PlannedAction>,
+    scan_id: Option<String>,
+    scan_result: Option<ScanResult>,
+) -> Result<PlanSummary, CommandError> {
+    let scan = scan_store.resolve(scan_id, scan_result)?;
+    async_runtime::spawn_blocking(move || ai_disk_engine::summarize_plan(&actions, &scan))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_common::MessageId;
+    use ai_disk_domain::FileNode;
+    use std::sync::Arc;
+
+    fn sample_scan() -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            root: FileNode::new("/home/u", 0, true),
+            scan_time_ms: 0,
+            file_count: 1,
+            total_size: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_input_by_scan_id_or_value() {
+        let store = ScanStore::default();
+        let cached = store.insert(sample_scan());
+        let scan_id = cached.scan_id.clone().unwrap();
+
+        let by_id = store.resolve(Some(scan_id), None).unwrap();
+        assert!(Arc::ptr_eq(&by_id, &cached));
+        let by_value = store.resolve(None, Some(sample_scan())).unwrap();
+        assert_eq!(by_value.root.path, "/home/u");
+        let plan = async_runtime::block_on(plan_for(&by_value)).unwrap();
+        assert!(plan.actions.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_scan_id_is_rejected() {
+        let store = ScanStore::default();
+        let err = store.resolve(Some("scan_0".to_string()), None).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert_eq!(err.message_id, Some(MessageId::ScanNotFound));
+        assert_eq!(err.params["scan_id"], "scan_0");
+        let err = store.resolve(None, None).unwrap_err();
+        assert_eq!(err.message_id, Some(MessageId::ScanInputMissing));
+    }
+
+    #[test]
+    fn test_deprecated_json_input_still_parses() {
+        let json = serde_json::to_string(&sample_scan()).unwrap();
+        assert_eq!(parse_scan_json(&json).unwrap().root.path, "/home/u");
+        assert_eq!(
+            parse_scan_json("{").unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+    }
 }
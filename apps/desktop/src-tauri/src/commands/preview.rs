@@ -0,0 +1,73 @@
+//! 文件预览命令：审阅清理计划时查看待删除文件的内容（文本开头、图片缩略图或元数据）。
+//!
+//! 默认只允许预览最近扫描范围内的文件，避免前端借此读取任意路径；`allow_any_path` 为 true 时不做限制。
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_domain::FilePreview;
+use ai_disk_scanner::{preview_file, DisplayPath, PREVIEW_DEFAULT_MAX_BYTES};
+use tauri::{async_runtime, State};
+
+use super::scan::ScanStore;
+
+/// 路径是否位于缓存中某次扫描的范围内；无法解析的路径视为不在范围内
+fn within_recent_scans(scan_store: &ScanStore, path: &str) -> bool {
+    std::fs::canonicalize(path.trim()).is_ok_and(|canonical| {
+        !scan_store
+            .covering_scans(DisplayPath::new(&canonical.to_string_lossy()).as_str())
+            .is_empty()
+    })
+}
+
+/// 预览文件：最多读取开头 `max_bytes` 字节（默认 16 KiB，上限 1 MiB）
+#[tauri::command]
+pub async fn preview_file_command(
+    scan_store: State<'_, ScanStore>,
+    path: String,
+    max_bytes: Option<usize>,
+    allow_any_path: Option<bool>,
+) -> Result<FilePreview, CommandError> {
+    if !allow_any_path.unwrap_or(false) && !within_recent_scans(&scan_store, &path) {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("路径不在最近的扫描范围内: {}", path),
+        ));
+    }
+    let max_bytes = max_bytes.unwrap_or(PREVIEW_DEFAULT_MAX_BYTES);
+    async_runtime::spawn_blocking(move || preview_file(&path, max_bytes))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_limited_to_recent_scans() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanned = dir.path().join("scanned");
+        std::fs::create_dir(&scanned).unwrap();
+        std::fs::write(scanned.join("notes.txt"), b"old notes").unwrap();
+        std::fs::write(dir.path().join("outside.txt"), b"secret").unwrap();
+
+        let store = ScanStore::default();
+        assert!(!within_recent_scans(
+            &store,
+            &scanned.join("notes.txt").to_string_lossy()
+        ));
+        store.insert(ai_disk_scanner::scan_path(&scanned.to_string_lossy()).unwrap());
+        assert!(within_recent_scans(
+            &store,
+            &scanned.join("notes.txt").to_string_lossy()
+        ));
+        assert!(!within_recent_scans(
+            &store,
+            &dir.path().join("outside.txt").to_string_lossy()
+        ));
+        assert!(!within_recent_scans(
+            &store,
+            &scanned.join("missing.txt").to_string_lossy()
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+//! 保存、加载与分享清理计划：计划以带 schema 版本的 JSON 保存在 `.disk-rookie/plans/<名称>.json`，
+//! 导出时在 JSON 旁写出同名的 Markdown 摘要（每个动作的路径、大小、理由与风险）。
+//! 加载时先把旧版本升级到当前结构，再自动重新模拟执行，因为保存之后文件系统可能已经变化。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::{format_bytes, write_atomic, ByteStyle, CommandError, ErrorCode};
+use ai_disk_domain::{Action, PlanFile, PlannedAction, RiskLevel, PLAN_SCHEMA_VERSION};
+use ai_disk_executor::{simulate_plan, PlanSimulation};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::documents::validate_segment;
+use super::storage::{get_storage_root, resolve_storage_path};
+
+/// 计划文件所在的目录（位于存储根目录下）
+const PLANS_DIR: &str = "plans";
+
+/// 加载计划的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedPlan {
+    pub plan: PlanFile,
+    /// 从旧版本升级而来时为原 schema 版本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migrated_from: Option<u32>,
+    /// 按当前文件系统重新模拟执行的结果；`missing` 中为保存后已不存在的路径
+    pub simulation: PlanSimulation,
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn plan_path(root: &Path, name: &str, extension: &str) -> Result<PathBuf, CommandError> {
+    validate_segment("计划名称", name)?;
+    resolve_storage_path(root, &format!("{}/{}.{}", PLANS_DIR, name, extension))
+}
+
+/// v1 → v2：v1 是直接序列化的 CleanupPlan，动作没有元数据。
+/// 迁移后的预计释放量未知（记为 0）、理由为空，风险按中等处理，需要用户重新确认
+fn migrate_v1(mut value: Value) -> Result<Value, String> {
+    let obj = value.as_object_mut().ok_or("不是对象")?;
+    let actions = obj
+        .get_mut("actions")
+        .and_then(Value::as_array_mut)
+        .ok_or("缺少 actions")?;
+    for action in actions.iter_mut() {
+        let bare = action.take();
+        *action = serde_json::to_value(PlannedAction {
+            action: serde_json::from_value(bare).map_err(|e| e.to_string())?,
+            bytes: 0,
+            risk: RiskLevel::Medium,
+            reason: String::new(),
+            scanned: None,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    obj.insert("schema_version".into(), 2.into());
+    Ok(value)
+}
+
+/// 解析计划文件并升级到当前 schema 版本，返回计划与原版本。
+/// 没有 `schema_version` 的文件视为 v1；高于当前版本的文件由更新的应用保存，拒绝加载
+fn parse_plan(raw: &str) -> Result<(PlanFile, u32), CommandError> {
+    let invalid = |message: String| CommandError::new(ErrorCode::InvalidInput, message);
+    let mut value: Value = serde_json::from_str(raw)
+        .map_err(|e| invalid(format!("计划文件不是有效的 JSON: {}", e)))?;
+    let original = match value.get("schema_version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| invalid(format!("无效的计划 schema 版本: {}", v)))?,
+    };
+    if original > PLAN_SCHEMA_VERSION {
+        return Err(CommandError::new(
+            ErrorCode::Config,
+            format!(
+                "计划文件的 schema 版本为 {}，高于当前支持的 {}，请升级应用后再打开",
+                original, PLAN_SCHEMA_VERSION
+            ),
+        ));
+    }
+    if original < 2 {
+        value = migrate_v1(value).map_err(|e| {
+            CommandError::new(ErrorCode::Config, format!("迁移计划 v1 失败: {}", e))
+        })?;
+    }
+    let plan =
+        serde_json::from_value(value).map_err(|e| invalid(format!("计划文件结构无效: {}", e)))?;
+    Ok((plan, original))
+}
+
+fn write_plan(path: &Path, plan: &PlanFile) -> Result<(), CommandError> {
+    let text = serde_json::to_vec_pretty(plan)
+        .map_err(|e| CommandError::internal(format!("序列化计划失败: {}", e)))?;
+    write_atomic(path, &text).map_err(|e| CommandError::io("写入计划失败", &e))
+}
+
+fn save_plan_to(
+    root: &Path,
+    name: &str,
+    actions: Vec<PlannedAction>,
+) -> Result<PlanFile, CommandError> {
+    let path = plan_path(root, name, "json")?;
+    let plan = PlanFile {
+        schema_version: PLAN_SCHEMA_VERSION,
+        name: name.to_string(),
+        saved_at: now_secs(),
+        estimated_space: actions.iter().map(|a| a.bytes).sum(),
+        actions,
+    };
+    write_plan(&path, &plan)?;
+    Ok(plan)
+}
+
+/// 读取并升级计划，再按当前文件系统重新模拟执行
+fn load_plan_from(path: &Path, name: &str) -> Result<LoadedPlan, CommandError> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!("计划不存在: {}", name),
+            ))
+        }
+        Err(e) => return Err(CommandError::io("读取计划失败", &e)),
+    };
+    let (mut plan, original) = parse_plan(&raw)?;
+    if plan.name.is_empty() {
+        plan.name = name.to_string();
+    }
+    let simulation = simulate_plan(&plan.to_cleanup_plan());
+    Ok(LoadedPlan {
+        plan,
+        migrated_from: (original < PLAN_SCHEMA_VERSION).then_some(original),
+        simulation,
+    })
+}
+
+/// 已保存计划中的动作，供定时执行使用
+pub(crate) fn saved_plan_actions(
+    root: &Path,
+    name: &str,
+) -> Result<Vec<PlannedAction>, CommandError> {
+    Ok(load_plan_from(&plan_path(root, name, "json")?, name)?
+        .plan
+        .actions)
+}
+
+fn action_label(action: &Action) -> String {
+    match action {
+        Action::Delete { .. } => "删除".to_string(),
+        Action::Move { to, .. } => format!("移动到 `{}`", escape_cell(to)),
+        Action::Offload { provider, .. } => format!("转存到 {}", provider),
+        Action::EmptyTrash { .. } => "清空废纸篓".to_string(),
+        Action::CompactVhd { .. } => "压缩虚拟磁盘".to_string(),
+    }
+}
+
+fn risk_label(risk: RiskLevel) -> &'static str {
+    match risk {
+        RiskLevel::Low => "低",
+        RiskLevel::Medium => "中",
+        RiskLevel::High => "高",
+    }
+}
+
+/// Markdown 表格单元格中的 `|` 与换行会破坏表格结构
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// 计划的 Markdown 摘要：总览与逐条动作表格
+fn render_markdown(plan: &PlanFile) -> String {
+    let mut out = format!(
+        "# 清理计划：{}\n\n- 动作数：{}\n- 预计释放：{}\n\n| 动作 | 路径 | 大小 | 风险 | 理由 |\n| --- | --- | ---: | --- | --- |\n",
+        plan.name,
+        plan.actions.len(),
+        format_bytes(plan.estimated_space, ByteStyle::Binary)
+    );
+    for planned in &plan.actions {
+        let size = if planned.bytes == 0 {
+            "未知".to_string()
+        } else {
+            format_bytes(planned.bytes, ByteStyle::Binary)
+        };
+        out.push_str(&format!(
+            "| {} | `{}` | {} | {} | {} |\n",
+            action_label(&planned.action),
+            escape_cell(planned.action.source_path()),
+            size,
+            risk_label(planned.risk),
+            escape_cell(&planned.reason)
+        ));
+    }
+    out
+}
+
+/// 把计划（升级到当前版本的 JSON）与 Markdown 摘要写到 `target_dir`，返回 Markdown 文件路径
+fn export_plan_to(root: &Path, name: &str, target_dir: &Path) -> Result<PathBuf, CommandError> {
+    let loaded = load_plan_from(&plan_path(root, name, "json")?, name)?;
+    let json_path = target_dir.join(format!("{}.json", name));
+    let markdown_path = target_dir.join(format!("{}.md", name));
+    write_plan(&json_path, &loaded.plan)?;
+    write_atomic(&markdown_path, render_markdown(&loaded.plan).as_bytes())
+        .map_err(|e| CommandError::io("写入计划摘要失败", &e))?;
+    Ok(markdown_path)
+}
+
+/// 保存计划到 `plans/<name>.json`，同名计划被覆盖；预计释放量为各动作之和
+#[tauri::command]
+pub async fn save_plan(
+    app: AppHandle,
+    name: String,
+    actions: Vec<PlannedAction>,
+) -> Result<PlanFile, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    save_plan_to(&storage_root, &name, actions)
+}
+
+/// 加载已保存的计划，并自动重新模拟执行
+#[tauri::command]
+pub async fn load_plan(app: AppHandle, name: String) -> Result<LoadedPlan, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let path = plan_path(&storage_root, &name, "json")?;
+    tauri::async_runtime::spawn_blocking(move || load_plan_from(&path, &name))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+/// 导出计划：在 `target_dir`（默认为 `plans/` 本身）写出 JSON 与 Markdown 摘要，返回 Markdown 文件路径
+#[tauri::command]
+pub async fn export_plan(
+    app: AppHandle,
+    name: String,
+    target_dir: Option<String>,
+) -> Result<String, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let target_dir = match target_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => resolve_storage_path(&storage_root, PLANS_DIR)?,
+    };
+    export_plan_to(&storage_root, &name, &target_dir).map(|p| p.to_string_lossy().into_owned())
+}
+
+/// 导入他人分享的计划文件：升级到当前版本后以文件名（不含扩展名）保存到 `plans/`，并重新模拟执行
+#[tauri::command]
+pub async fn import_plan(app: AppHandle, source_path: String) -> Result<LoadedPlan, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    let source = PathBuf::from(&source_path);
+    let name = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let target = plan_path(&storage_root, &name, "json")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut loaded = load_plan_from(&source, &name)?;
+        loaded.plan.name = name;
+        write_plan(&target, &loaded.plan)?;
+        Ok(loaded)
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn file(dir: &Path, name: &str, len: usize) -> String {
+        let path = dir.join(name);
+        fs::write(&path, vec![0u8; len]).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn delete(path: &str, bytes: u64, reason: &str) -> PlannedAction {
+        PlannedAction {
+            action: Action::Delete {
+                path: path.to_string(),
+            },
+            bytes,
+            risk: RiskLevel::Low,
+            reason: reason.to_string(),
+            scanned: None,
+        }
+    }
+
+    #[test]
+    fn test_save_and_reload_rechecks_filesystem() {
+        let storage = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let old_iso = file(data.path(), "old.iso", 10);
+        let setup = file(data.path(), "setup.exe", 5);
+        let saved = save_plan_to(
+            storage.path(),
+            "downloads",
+            vec![
+                delete(&old_iso, 700 * MB, "旧镜像"),
+                delete(&setup, 2 * MB, "安装包"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(saved.schema_version, PLAN_SCHEMA_VERSION);
+        assert_eq!(saved.estimated_space, 702 * MB);
+
+        let path = plan_path(storage.path(), "downloads", "json").unwrap();
+        let loaded = load_plan_from(&path, "downloads").unwrap();
+        assert!(loaded.migrated_from.is_none());
+        assert_eq!(loaded.plan.actions.len(), 2);
+        assert_eq!(loaded.plan.actions[0].reason, "旧镜像");
+        assert_eq!(loaded.simulation.delete_count, 2);
+        assert!(loaded.simulation.missing.is_empty());
+
+        // 保存后用户自己删掉了安装包：重新加载时模拟结果指出该路径
+        fs::remove_file(&setup).unwrap();
+        let reloaded = load_plan_from(&path, "downloads").unwrap();
+        assert_eq!(reloaded.simulation.missing, vec![setup]);
+
+        assert!(save_plan_to(storage.path(), "../escape", vec![]).is_err());
+        let err = load_plan_from(&plan_path(storage.path(), "none", "json").unwrap(), "none")
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn test_v1_plan_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = file(dir.path(), "kept.log", 3);
+        let v1 = serde_json::json!({
+            "actions": [
+                { "Delete": { "path": kept } },
+                { "Move": { "from": "/nonexistent/a.iso", "to": "/archive" } },
+            ],
+            "estimated_space": 4096,
+        });
+        let path = dir.path().join("legacy.json");
+        fs::write(&path, v1.to_string()).unwrap();
+
+        let loaded = load_plan_from(&path, "legacy").unwrap();
+        assert_eq!(loaded.migrated_from, Some(1));
+        let plan = &loaded.plan;
+        assert_eq!(plan.schema_version, 2);
+        assert_eq!(plan.name, "legacy");
+        assert_eq!(plan.estimated_space, 4096);
+        assert_eq!(plan.actions.len(), 2);
+        assert!(matches!(&plan.actions[0].action, Action::Delete { path } if *path == kept));
+        assert_eq!(plan.actions[1].bytes, 0);
+        assert_eq!(plan.actions[1].risk, RiskLevel::Medium);
+        assert_eq!(loaded.simulation.move_count, 1);
+        assert_eq!(loaded.simulation.missing, vec!["/nonexistent/a.iso"]);
+
+        // 升级后的 JSON 可以按当前版本再次读取
+        let (reparsed, original) = parse_plan(&serde_json::to_string(plan).unwrap()).unwrap();
+        assert_eq!(original, PLAN_SCHEMA_VERSION);
+        assert_eq!(reparsed.actions.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_newer_or_malformed_plans() {
+        let newer = serde_json::json!({
+            "schema_version": PLAN_SCHEMA_VERSION + 1,
+            "estimated_space": 0,
+            "actions": [],
+        });
+        let err = parse_plan(&newer.to_string()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Config);
+
+        for raw in [
+            "not json",
+            r#"{"schema_version": "2", "actions": []}"#,
+            r#"{"actions": [{"Shred": {"path": "/x"}}], "estimated_space": 0}"#,
+            r#"{"schema_version": 2, "actions": [{"Delete": {"path": "/x"}}], "estimated_space": 0}"#,
+        ] {
+            assert!(parse_plan(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_export_writes_markdown_beside_json() {
+        let storage = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let mut trash = delete("/home/u/.local/share/Trash", 3 * MB / 2, "废纸篓 | 已确认");
+        trash.action = Action::EmptyTrash {
+            path: "/home/u/.local/share/Trash".to_string(),
+        };
+        save_plan_to(
+            storage.path(),
+            "weekly",
+            vec![trash, delete("/tmp/build.log", 0, "")],
+        )
+        .unwrap();
+
+        let markdown = export_plan_to(storage.path(), "weekly", out.path()).unwrap();
+        assert_eq!(markdown, out.path().join("weekly.md"));
+        let (exported, _) =
+            parse_plan(&fs::read_to_string(out.path().join("weekly.json")).unwrap()).unwrap();
+        assert_eq!(exported.actions.len(), 2);
+
+        let text = fs::read_to_string(markdown).unwrap();
+        assert!(text.starts_with("# 清理计划：weekly\n"));
+        assert!(text.contains("- 预计释放：1.5 MiB\n"));
+        assert!(text.contains(
+            "| 清空废纸篓 | `/home/u/.local/share/Trash` | 1.5 MiB | 低 | 废纸篓 \\| 已确认 |\n"
+        ));
+        assert!(text.contains("| 删除 | `/tmp/build.log` | 未知 | 低 |  |\n"));
+    }
+}
@@ -2,77 +2,926 @@
 //! 后端通过 scan_path_with_progress(..., use_mft: true) 走 MFT 全量扫描（与普通扫描相同的树结构），
 //! 无需只取前 N 个文件，由 ai_disk_scanner 内部根据路径与 use_mft 决定是否调用 scan_volume_mft。
 
-use ai_disk_domain::ScanResult;
-use ai_disk_scanner::scan_path_with_progress;
-use std::io::Write;
-use tauri::{async_runtime, Emitter, Window};
+use ai_disk_common::{
+    format_bytes, localize, ByteStyle, CommandError, DiskAnalyzerError, ErrorCode, Lang, MessageId,
+    ProgressPhase,
+};
+use ai_disk_domain::{
+    CleanupTarget, FileNode, KnownFolderMap, MftAvailability, QuickDirStats, ScanLocation,
+    ScanPreflight, ScanResult, ScanStaleness, ScanStreamTotals, TargetState,
+};
+use ai_disk_scanner::{
+    attribute_owners, discover_cleanup_targets, estimate_unknown_sizes, explain_mft_availability,
+    is_fat_filesystem, locate_in_tree, quick_dir_stats, scan_path_with_progress, scan_preflight,
+    scan_to_writer, system_known_folders, volume_filesystem, BackgroundScan, DisplayPath,
+    GitignoreOptions, PauseToken, SizeOracle, StreamOptions, SystemOwnerResolver, WalkOptions,
+    OWNER_DIR_MIN_BYTES,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{async_runtime, AppHandle, Emitter, Manager, State, Window};
 
-fn stderr_flush() {
-    let _ = std::io::stderr().flush();
+use super::busy::{BusyKind, BusyState};
+use super::config::ConfigState;
+use super::permission::check_admin_permission;
+use super::scan_notification::{notify_scan_complete, should_notify};
+use super::scan_payload::{self, ScanPayload};
+
+/// 快速统计的默认与最大时间预算（毫秒）
+const DEFAULT_QUICK_STATS_BUDGET_MS: u64 = 200;
+const MAX_QUICK_STATS_BUDGET_MS: u64 = 5_000;
+
+/// 内存中保留的最近扫描结果数量
+const MAX_CACHED_SCANS: usize = 4;
+
+/// 一次扫描的会话：扫描结果与扫描后已知发生变化的路径（路径 -> 扫描树中的原大小）
+pub struct ScanSession {
+    pub scan_id: String,
+    pub result: Arc<ScanResult>,
+    dirty: BTreeMap<String, u64>,
+    /// 递归大小查询，首次使用时由扫描树构建
+    sizes: Option<Arc<SizeOracle>>,
+}
+
+impl ScanSession {
+    fn new(scan_id: String, result: Arc<ScanResult>) -> Self {
+        Self {
+            scan_id,
+            result,
+            dirty: BTreeMap::new(),
+            sizes: None,
+        }
+    }
+
+    /// 本次扫描的递归大小查询；构建时已标记变化的路径立即失效
+    fn sizes(&mut self) -> Arc<SizeOracle> {
+        let dirty = &self.dirty;
+        let result = &self.result;
+        self.sizes
+            .get_or_insert_with(|| {
+                let sizes = SizeOracle::from_tree(&result.root);
+                for path in dirty.keys() {
+                    sizes.invalidate(Path::new(path));
+                }
+                Arc::new(sizes)
+            })
+            .clone()
+    }
+
+    fn covers(&self, path: &Path) -> bool {
+        path.starts_with(&self.result.root.path)
+    }
+
+    /// 标记路径已变化；已被标记（或其上级目录已被标记）时返回 false。
+    /// 标记目录时合并其下已标记的路径，避免重复计算字节数
+    fn mark_dirty(&mut self, path: &str) -> bool {
+        let target = Path::new(path);
+        if self.dirty.keys().any(|d| target.starts_with(d)) {
+            return false;
+        }
+        self.dirty.retain(|d, _| !Path::new(d).starts_with(target));
+        let bytes = node_size(&self.result.root, target).unwrap_or(0);
+        self.dirty.insert(path.to_string(), bytes);
+        if let Some(sizes) = &self.sizes {
+            sizes.invalidate(target);
+        }
+        true
+    }
+
+    /// 扫描后 `path`、其上级目录或其下是否已标记变化
+    fn touches_dirty(&self, path: &Path) -> bool {
+        self.dirty.keys().any(|d| {
+            let dirty = Path::new(d);
+            path.starts_with(dirty) || dirty.starts_with(path)
+        })
+    }
+
+    pub fn staleness(&self) -> ScanStaleness {
+        ScanStaleness {
+            scan_id: self.scan_id.clone(),
+            dirty_paths: self.dirty.keys().cloned().collect(),
+            changed_bytes: self.dirty.values().sum(),
+        }
+    }
+}
+
+/// 在扫描树中查找路径对应节点的大小；路径位于未展开的目录下时返回 None
+fn node_size(root: &FileNode, target: &Path) -> Option<u64> {
+    let mut node = root;
+    loop {
+        if Path::new(&node.path) == target {
+            return Some(node.size);
+        }
+        node = node
+            .children
+            .iter()
+            .find(|child| target.starts_with(&child.path))?;
+    }
+}
+
+/// 最近扫描会话缓存（按 scan_id），供分析、计划等命令按 id 复用，避免前端回传整棵树；
+/// 删除、转存等操作完成后在此记录变化的路径，前端据此标记过期区域
+#[derive(Default)]
+pub struct ScanStore {
+    scans: Mutex<VecDeque<ScanSession>>,
+}
+
+impl ScanStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<ScanSession>> {
+        self.scans.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 新的 scan_id；扫描开始时即分配，供扫描进行中暂停、继续
+    pub fn allocate_id() -> String {
+        format!(
+            "scan_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis())
+                .unwrap_or(0)
+        )
+    }
+
+    /// 为扫描结果分配 scan_id 并缓存，返回带 scan_id 的结果
+    pub fn insert(&self, result: ScanResult) -> Arc<ScanResult> {
+        self.insert_with_id(Self::allocate_id(), result)
+    }
+
+    /// 以扫描开始时分配的 scan_id 缓存扫描结果
+    pub fn insert_with_id(&self, scan_id: String, mut result: ScanResult) -> Arc<ScanResult> {
+        result.scan_id = Some(scan_id.clone());
+        let result = Arc::new(result);
+        let mut scans = self.lock();
+        scans.push_back(ScanSession::new(scan_id, result.clone()));
+        while scans.len() > MAX_CACHED_SCANS {
+            scans.pop_front();
+        }
+        result
+    }
+
+    pub fn get(&self, scan_id: &str) -> Option<Arc<ScanResult>> {
+        self.lock()
+            .iter()
+            .find(|s| s.scan_id == scan_id)
+            .map(|s| s.result.clone())
+    }
+
+    /// 分析、计划等命令的扫描结果来源：优先用前端直接传入的结果，否则按 scan_id 取缓存
+    pub fn resolve(
+        &self,
+        scan_id: Option<String>,
+        scan_result: Option<ScanResult>,
+    ) -> Result<Arc<ScanResult>, CommandError> {
+        match (scan_result, scan_id) {
+            (Some(scan), _) => Ok(Arc::new(scan)),
+            (None, Some(id)) => self.get(&id).ok_or_else(|| {
+                CommandError::localized(
+                    ErrorCode::InvalidInput,
+                    MessageId::ScanNotFound,
+                    &[("scan_id", &id)],
+                )
+            }),
+            (None, None) => Err(CommandError::localized(
+                ErrorCode::InvalidInput,
+                MessageId::ScanInputMissing,
+                &[],
+            )),
+        }
+    }
+
+    /// 根路径为 `root`（[`DisplayPath`] 形式）的最近一次扫描的文件数，作为再次扫描的条目数估计
+    pub fn latest_file_count(&self, root: &str) -> Option<u64> {
+        self.lock()
+            .iter()
+            .rev()
+            .find(|s| s.result.root.path == root)
+            .map(|s| s.result.file_count)
+    }
+
+    /// 覆盖路径 `path`（[`DisplayPath`] 形式）的缓存扫描，最近的在前
+    pub fn covering_scans(&self, path: &str) -> Vec<Arc<ScanResult>> {
+        self.lock()
+            .iter()
+            .rev()
+            .filter(|s| s.covers(Path::new(path)))
+            .map(|s| s.result.clone())
+            .collect()
+    }
+
+    /// 覆盖路径 `path`（[`DisplayPath`] 形式）的缓存扫描的递归大小查询，最近的在前
+    pub fn covering_sizes(&self, path: &str) -> Vec<Arc<SizeOracle>> {
+        self.lock()
+            .iter_mut()
+            .rev()
+            .filter(|s| s.covers(Path::new(path)))
+            .map(ScanSession::sizes)
+            .collect()
+    }
+
+    /// 覆盖路径 `path`（[`DisplayPath`] 形式）的最近一次扫描中该路径的状态，供执行前核对目标；
+    /// 扫描后该路径、其上级或其下已由本应用的操作改变时查询不到
+    pub fn target_state(&self, path: &str) -> Option<TargetState> {
+        let scans = self.lock();
+        let session = scans.iter().rev().find(|s| s.covers(Path::new(path)))?;
+        if session.touches_dirty(Path::new(path)) {
+            return None;
+        }
+        ai_disk_engine::target_state(&session.result, path)
+    }
+
+    /// 缓存扫描中 `path` 的递归大小；扫描后该路径或其下已有变化时查询不到
+    pub fn recursive_size(&self, path: &str) -> Option<u64> {
+        self.covering_sizes(path)
+            .iter()
+            .find_map(|sizes| sizes.recursive_size(Path::new(path)))
+    }
+
+    pub fn staleness(&self, scan_id: &str) -> Option<ScanStaleness> {
+        self.lock()
+            .iter()
+            .find(|s| s.scan_id == scan_id)
+            .map(ScanSession::staleness)
+    }
+
+    /// 在所有覆盖这些路径的扫描会话中标记变化，返回有新变化的会话的最新状态
+    pub fn mark_dirty(&self, paths: &[String]) -> Vec<ScanStaleness> {
+        let mut scans = self.lock();
+        scans
+            .iter_mut()
+            .filter_map(|session| {
+                let mut changed = false;
+                for path in paths {
+                    if session.covers(Path::new(path)) {
+                        changed |= session.mark_dirty(path);
+                    }
+                }
+                changed.then(|| session.staleness())
+            })
+            .collect()
+    }
+}
+
+/// 进行中的一次扫描的控制：暂停令牌与最近上报的文件数（暂停时随 `scan-progress` 一并发出）
+struct ScanControl {
+    pause: PauseToken,
+    last_count: Arc<AtomicU64>,
+}
+
+/// 进行中的扫描（按 scan_id），供 `pause_scan` / `resume_scan` 查找
+#[derive(Default)]
+pub struct ScanControls {
+    scans: Mutex<HashMap<String, ScanControl>>,
+}
+
+impl ScanControls {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ScanControl>> {
+        self.scans.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 登记一次扫描，返回的守卫 drop 时注销
+    fn register(
+        &self,
+        scan_id: &str,
+        pause: PauseToken,
+        last_count: Arc<AtomicU64>,
+    ) -> ScanControlGuard<'_> {
+        self.lock()
+            .insert(scan_id.to_string(), ScanControl { pause, last_count });
+        ScanControlGuard {
+            controls: self,
+            scan_id: scan_id.to_string(),
+        }
+    }
+
+    /// 暂停或继续；扫描不存在（已结束）时返回 None，状态未变化时返回 Some(false)。
+    /// 状态变化时同时返回最近上报的文件数
+    fn set_paused(&self, scan_id: &str, paused: bool) -> Option<(bool, u64)> {
+        let scans = self.lock();
+        let control = scans.get(scan_id)?;
+        let changed = if paused {
+            control.pause.pause()
+        } else {
+            control.pause.resume()
+        };
+        Some((changed, control.last_count.load(Ordering::Relaxed)))
+    }
+}
+
+/// 扫描登记，drop 时注销；扫描因出错提前返回时也会继续，避免扫描线程一直阻塞
+struct ScanControlGuard<'a> {
+    controls: &'a ScanControls,
+    scan_id: String,
+}
+
+impl Drop for ScanControlGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(control) = self.controls.lock().remove(&self.scan_id) {
+            control.pause.resume();
+        }
+    }
+}
+
+/// 用覆盖同一路径的缓存扫描（如此前以管理员权限完成的整卷 MFT 扫描）估算无权限目录的大小，
+/// 返回估算出的字节数
+fn estimate_from_cache(scan_store: &ScanStore, result: &mut ScanResult) -> u64 {
+    let sources = scan_store.covering_sizes(&result.root.path);
+    if sources.is_empty() {
+        return 0;
+    }
+    estimate_unknown_sizes(result, &sources)
+}
+
+/// 执行器操作完成后记录变化的路径，并为受影响的每个扫描发送 `scan-dirty` 事件
+pub(crate) fn notify_scan_dirty(app: &AppHandle, scan_store: &ScanStore, paths: &[String]) {
+    for staleness in scan_store.mark_dirty(paths) {
+        let _ = app.emit("scan-dirty", staleness);
+    }
+}
+
+/// `scan-progress` 事件的载荷：`(已处理条目数, 文本, 阶段)`。普通进度的文本为当前路径、阶段为 null；
+/// 阶段提示的文本按界面语言渲染，阶段本身以稳定标识发出，供前端自行本地化
+fn progress_payload(count: u64, text: &str, lang: Lang) -> (u64, String, Option<ProgressPhase>) {
+    match ProgressPhase::from_marker(text) {
+        Some(phase) => (count, localize(phase.message_id(), lang, &[]), Some(phase)),
+        None => (count, text.to_string(), None),
+    }
 }
 
 #[tauri::command]
 pub async fn scan_path_command(
     window: Window,
+    scan_store: State<'_, ScanStore>,
+    scan_controls: State<'_, ScanControls>,
+    config_state: State<'_, ConfigState>,
+    busy: State<'_, BusyState>,
     path: String,
     shallow_dirs: Option<bool>,
     use_mft: Option<bool>,
-) -> Result<ScanResult, String> {
+    resolve_owners: Option<bool>,
+    concurrency: Option<usize>,
+    background: Option<bool>,
+    compress: Option<bool>,
+    respect_gitignore: Option<bool>,
+    same_filesystem_only: Option<bool>,
+    include_streams: Option<bool>,
+    treemap_rollups: Option<bool>,
+) -> Result<ScanPayload, CommandError> {
     let path_trimmed = path.trim().to_string();
-    let use_shallow = shallow_dirs.unwrap_or(true);
-    // 明确使用传入值：None 视为默认 true，Some(false) 必须为 false
-    let use_mft = use_mft.unwrap_or(true);
-
-    let thread_count = std::thread::available_parallelism()
-        .map(|p| p.get())
-        .unwrap_or(1);
-    if use_mft {
-        let _ = writeln!(
-            std::io::stderr(),
-            "[DiskRookie] scan start (MFT requested), path: {}, threads: {}",
-            path_trimmed,
-            thread_count
-        );
-    } else {
-        let _ = writeln!(
-            std::io::stderr(),
-            "[DiskRookie] scan start (normal walk), path: {}",
-            path_trimmed
-        );
-    }
-    stderr_flush();
+    let config = config_state.get();
+    let (scan_config, lang) = (config.scan, config.ui.language);
+    let use_shallow = shallow_dirs.unwrap_or(scan_config.shallow_dirs);
+    // 明确使用传入值：None 时取配置中的默认值，Some(false) 必须为 false
+    let use_mft = use_mft.unwrap_or(scan_config.use_mft);
+    let resolve_owners = resolve_owners.unwrap_or(scan_config.resolve_owners);
+    // None 时由扫描器按磁盘类型决定线程数
+    let concurrency = concurrency.or(scan_config.concurrency);
+    // 交互式扫描默认不限速；定时等后台调用方显式传 true
+    let background = background.unwrap_or(false).then_some(BackgroundScan {
+        entries_per_sec: scan_config.background_entries_per_sec,
+    });
+    let pause = PauseToken::new();
+    let walk = WalkOptions {
+        gitignore: respect_gitignore
+            .unwrap_or(scan_config.respect_gitignore)
+            .then(|| GitignoreOptions {
+                ignore_file_name: scan_config.ignore_file_name.clone(),
+            }),
+        // None 时由扫描器决定：仅卷根扫描不进入其他文件系统
+        same_filesystem_only: same_filesystem_only.or(scan_config.same_filesystem_only),
+        pause: Some(pause.clone()),
+        include_streams: include_streams.unwrap_or(scan_config.include_streams),
+        treemap_rollups: treemap_rollups.unwrap_or(scan_config.treemap_rollups),
+    };
+
+    tracing::info!(
+        path = %path_trimmed,
+        use_mft,
+        concurrency = ?concurrency,
+        background = background.is_some(),
+        gitignore = walk.gitignore.is_some(),
+        same_filesystem_only = ?walk.same_filesystem_only,
+        include_streams = walk.include_streams,
+        treemap_rollups = walk.treemap_rollups,
+        "scan start"
+    );
+    let started = std::time::Instant::now();
+    let _busy = busy.begin(BusyKind::Scan);
+    // 扫描开始即分配 scan_id，前端据此暂停、继续
+    let scan_id = ScanStore::allocate_id();
+    let last_count = Arc::new(AtomicU64::new(0));
+    let _control = scan_controls.register(&scan_id, pause, last_count.clone());
+    let _ = window.emit("scan-started", (scan_id.clone(), path_trimmed.clone()));
 
     let path_clone = path_trimmed.clone();
     let window_progress = window.clone();
     let progress = std::sync::Arc::new(Box::new(move |count: u64, path_str: &str| {
-        let _ = window_progress.emit("scan-progress", (count, path_str.to_string()));
+        last_count.store(count, Ordering::Relaxed);
+        let _ = window_progress.emit("scan-progress", progress_payload(count, path_str, lang));
     }) as Box<dyn Fn(u64, &str) + Send + Sync>);
     let window_emit = window.clone();
-    let (result, used_mft) = async_runtime::spawn_blocking(move || {
-        scan_path_with_progress(&path_clone, Some(&progress), use_shallow, use_mft)
+    let (mut result, used_mft) = async_runtime::spawn_blocking(move || {
+        let (mut result, used_mft) = scan_path_with_progress(
+            &path_clone,
+            Some(&progress),
+            use_shallow,
+            use_mft,
+            concurrency,
+            background,
+            &walk,
+        )?;
+        if resolve_owners {
+            // FAT 系列卷没有安全描述符，逐个查询所有者只会全部失败
+            if volume_filesystem(Path::new(&path_clone)).is_some_and(|fs| is_fat_filesystem(&fs)) {
+                tracing::info!(path = %path_clone, "FAT volume has no owner information, skipping owner resolution");
+            } else {
+                progress(result.file_count, &ProgressPhase::ResolvingOwners.marker());
+                attribute_owners(
+                    &mut result,
+                    &mut SystemOwnerResolver::new(),
+                    OWNER_DIR_MIN_BYTES,
+                );
+            }
+        }
+        Ok::<_, DiskAnalyzerError>((result, used_mft))
     })
     .await
-    .map_err(|e| e.to_string())?
-    .map_err(|e| e.to_string())?;
-
-    if used_mft {
-        let _ = writeln!(
-            std::io::stderr(),
-            "[DiskRookie] scan done (MFT used), path: {}, file_count: {}, total_size: {}",
-            path_trimmed,
-            result.file_count,
-            result.total_size
-        );
+    .map_err(|e| CommandError::internal(e.to_string()))?
+    .map_err(CommandError::from)
+    .inspect_err(|e| ai_disk_common::record_error(e.code))?;
+
+    // 非管理员扫描时其他用户的目录无权限读取，尽力用缓存扫描补上大小
+    let estimated_bytes = estimate_from_cache(&scan_store, &mut result);
+
+    // 暂停时长不计入耗时，与 scan_time_ms 一致
+    let elapsed_ms = (started.elapsed().as_millis() as u64).saturating_sub(result.paused_ms);
+    ai_disk_common::record_scan_completed(elapsed_ms, result.file_count, used_mft);
+    // 请求了 MFT 却未使用时，scan_warning 即回退原因
+    let mft_fallback_reason = if use_mft && !used_mft {
+        result.scan_warning.clone()
     } else {
-        let _ = writeln!(
-            std::io::stderr(),
-            "[DiskRookie] scan done (normal walk), path: {}, file_count: {}, total_size: {}",
-            path_trimmed,
-            result.file_count,
-            result.total_size
+        None
+    };
+    let _ = window_emit.emit(
+        "scan-mft-status",
+        (path_trimmed.clone(), used_mft, mft_fallback_reason),
+    );
+    let result = scan_store.insert_with_id(scan_id, result);
+    if should_notify(elapsed_ms, &scan_config) {
+        let (app, result, path) = (
+            window.app_handle().clone(),
+            result.clone(),
+            path_trimmed.clone(),
         );
+        // 通知需要对整棵树做一次分析，不阻塞结果返回
+        async_runtime::spawn_blocking(move || {
+            notify_scan_complete(&app, &path, &result, elapsed_ms, &scan_config, lang)
+        });
+    }
+    let (file_count, total_size, ignored_bytes, result_paused_ms) = (
+        result.file_count,
+        result.total_size,
+        result.ignored_bytes,
+        result.paused_ms,
+    );
+    let compress = compress.unwrap_or(false);
+    let encoded = async_runtime::spawn_blocking(move || {
+        scan_payload::encode(ScanResult::clone(&result), compress)
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))??;
+    tracing::info!(
+        path = %path_trimmed,
+        used_mft,
+        file_count,
+        total_size,
+        size = %format_bytes(total_size, ByteStyle::Binary),
+        ignored_bytes = ?ignored_bytes,
+        estimated_bytes,
+        elapsed_ms,
+        paused_ms = result_paused_ms,
+        compress,
+        json_bytes = encoded.json_bytes,
+        payload_bytes = encoded.payload_bytes,
+        "scan done"
+    );
+    Ok(encoded.payload)
+}
+
+/// 暂停或继续进行中的扫描，状态变化时发出一条阶段为 `scan.paused` / `scan.resumed` 的 `scan-progress`
+fn set_scan_paused(
+    window: &Window,
+    scan_controls: &ScanControls,
+    scan_id: &str,
+    paused: bool,
+    lang: Lang,
+) -> Result<bool, CommandError> {
+    let (changed, count) = scan_controls.set_paused(scan_id, paused).ok_or_else(|| {
+        CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::ScanNotRunning,
+            &[("scan_id", scan_id)],
+        )
+    })?;
+    if changed {
+        let phase = if paused {
+            ProgressPhase::Paused
+        } else {
+            ProgressPhase::Resumed
+        };
+        tracing::info!(scan_id, paused, "scan pause state changed");
+        let _ = window.emit(
+            "scan-progress",
+            progress_payload(count, &phase.marker(), lang),
+        );
+    }
+    Ok(changed)
+}
+
+/// 暂停进行中的扫描：扫描线程在下一个检查点阻塞等待，暂停时长不计入扫描耗时；已暂停时返回 false
+#[tauri::command]
+pub fn pause_scan(
+    window: Window,
+    scan_controls: State<'_, ScanControls>,
+    config_state: State<'_, ConfigState>,
+    scan_id: String,
+) -> Result<bool, CommandError> {
+    let lang = config_state.get().ui.language;
+    set_scan_paused(&window, &scan_controls, &scan_id, true, lang)
+}
+
+/// 继续已暂停的扫描；未暂停时返回 false
+#[tauri::command]
+pub fn resume_scan(
+    window: Window,
+    scan_controls: State<'_, ScanControls>,
+    config_state: State<'_, ConfigState>,
+    scan_id: String,
+) -> Result<bool, CommandError> {
+    let lang = config_state.get().ui.language;
+    set_scan_paused(&window, &scan_controls, &scan_id, false, lang)
+}
+
+/// 扫描 `path` 并以 JSON Lines 流式写入 `target_file`，不在内存中保留整棵树；失败时删除写了一半的文件
+fn export_scan_stream_to(
+    path: &str,
+    target_file: &Path,
+    shallow_dirs: bool,
+) -> Result<ScanStreamTotals, CommandError> {
+    // 导出目标可能位于被扫描的目录中，写入期间不计入其他扫描
+    let _in_flight = ai_disk_common::register_in_flight(target_file);
+    let file = std::fs::File::create(target_file)?;
+    let options = StreamOptions {
+        tree: false,
+        shallow_dirs,
+    };
+    scan_to_writer(path, file, &options).map_err(|e| {
+        let _ = std::fs::remove_file(target_file);
+        CommandError::from(e)
+    })
+}
+
+/// 流式导出扫描结果（JSON Lines）到文件，供 jq 等外部工具增量处理
+#[tauri::command]
+pub async fn export_scan_stream(
+    config_state: State<'_, ConfigState>,
+    path: String,
+    target_file: String,
+) -> Result<ScanStreamTotals, CommandError> {
+    let shallow_dirs = config_state.get().scan.shallow_dirs;
+    let path = path.trim().to_string();
+    async_runtime::spawn_blocking(move || {
+        export_scan_stream_to(&path, Path::new(&target_file), shallow_dirs)
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+/// 超出时间预算的快速统计改用缓存扫描中的递归大小（扫描后没有变化时）
+fn complete_from_cache(scan_store: &ScanStore, stats: &mut QuickDirStats) {
+    if !stats.partial {
+        return;
+    }
+    if let Some(size) = scan_store.recursive_size(DisplayPath::new(&stats.path).as_str()) {
+        stats.recursive_size = size;
+        stats.partial = false;
+    }
+}
+
+/// 目录快速统计（不做完整扫描），供目录选择器悬停时展示；超出时间预算且缓存扫描中没有该目录时
+/// 返回 `partial: true`
+#[tauri::command]
+pub async fn quick_dir_stats_command(
+    scan_store: State<'_, ScanStore>,
+    path: String,
+    budget_ms: Option<u64>,
+) -> Result<QuickDirStats, CommandError> {
+    let budget = std::time::Duration::from_millis(
+        budget_ms
+            .unwrap_or(DEFAULT_QUICK_STATS_BUDGET_MS)
+            .min(MAX_QUICK_STATS_BUDGET_MS),
+    );
+    let mut stats = async_runtime::spawn_blocking(move || quick_dir_stats(&path, budget))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))??;
+    complete_from_cache(&scan_store, &mut stats);
+    Ok(stats)
+}
+
+/// 列出临时目录、浏览器与包管理器缓存等已知可回收位置及其占用，供仪表盘展示
+#[tauri::command]
+pub async fn discover_cleanup_targets_command() -> Result<Vec<CleanupTarget>, CommandError> {
+    async_runtime::spawn_blocking(discover_cleanup_targets)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+/// 系统解析出的桌面、文档、下载等已知目录的实际位置（目录被移动后同样准确），供侧边栏快捷方式使用
+#[tauri::command]
+pub async fn get_known_folders() -> Result<KnownFolderMap, CommandError> {
+    async_runtime::spawn_blocking(system_known_folders)
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+/// 勾选「使用 MFT」时的预检：该路径能否使用 MFT 扫描，不能时给出原因
+#[tauri::command]
+pub async fn explain_mft_availability_command(
+    path: String,
+) -> Result<MftAvailability, CommandError> {
+    async_runtime::spawn_blocking(move || explain_mft_availability(path.trim()))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))
+}
+
+/// 预检 `path`：预计条目数取自缓存中该路径最近一次的扫描，提权状态与 [`check_admin_permission`] 一致
+fn preflight_scan_for(scan_store: &ScanStore, path: &str, use_mft: bool) -> ScanPreflight {
+    let estimated_entries = std::fs::canonicalize(path).ok().and_then(|canonical| {
+        scan_store.latest_file_count(DisplayPath::new(&canonical.to_string_lossy()).as_str())
+    });
+    ScanPreflight {
+        is_elevated: check_admin_permission(),
+        ..scan_preflight(path, use_mft, estimated_entries)
+    }
+}
+
+/// 开始扫描前的预检：是否会使用 MFT 及原因、卷信息、预计条目数与推荐的扫描方式；
+/// `use_mft` 为 None 时取配置中的默认值
+#[tauri::command]
+pub async fn preflight_scan(
+    scan_store: State<'_, ScanStore>,
+    config_state: State<'_, ConfigState>,
+    path: String,
+    use_mft: Option<bool>,
+) -> Result<ScanPreflight, CommandError> {
+    let use_mft = use_mft.unwrap_or(config_state.get().scan.use_mft);
+    Ok(preflight_scan_for(&scan_store, path.trim(), use_mft))
+}
+
+/// 扫描后已被删除、移动或转存的路径及其原大小之和；scan_id 不存在或已过期时报错
+#[tauri::command]
+pub async fn get_scan_staleness(
+    scan_store: State<'_, ScanStore>,
+    scan_id: String,
+) -> Result<ScanStaleness, CommandError> {
+    scan_store.staleness(&scan_id).ok_or_else(|| {
+        CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::ScanNotFound,
+            &[("scan_id", &scan_id)],
+        )
+    })
+}
+
+/// 返回 `path` 在缓存扫描树中的祖先链，供计划审阅时在扫描树中展开到该项。
+/// 路径位于浅扫描或被裁剪的子树中时链止于最近的已扫描目录（`exact` 为 false）；
+/// 路径不在扫描范围内时返回 NotInScan
+#[tauri::command]
+pub async fn locate_in_scan(
+    scan_store: State<'_, ScanStore>,
+    scan_id: String,
+    path: String,
+) -> Result<ScanLocation, CommandError> {
+    let scan = scan_store.get(&scan_id).ok_or_else(|| {
+        CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::ScanNotFound,
+            &[("scan_id", &scan_id)],
+        )
+    })?;
+    locate_in_tree(&scan.root, path.trim()).ok_or_else(|| {
+        CommandError::localized(
+            ErrorCode::NotInScan,
+            MessageId::NotInScan,
+            &[("path", path.trim())],
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::SizeSource;
+    use std::fs;
+
+    fn find<'a>(node: &'a FileNode, name: &str) -> Option<&'a FileNode> {
+        if node.name == name {
+            return Some(node);
+        }
+        node.children.iter().find_map(|c| find(c, name))
+    }
+
+    /// 扫描 root/{a/big.bin(1000), a/small.bin(10), b.txt(5)} 并放入缓存
+    fn scanned_store() -> (tempfile::TempDir, ScanStore, Arc<ScanResult>) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("a")).unwrap();
+        fs::write(root.join("a").join("big.bin"), vec![0u8; 1000]).unwrap();
+        fs::write(root.join("a").join("small.bin"), vec![0u8; 10]).unwrap();
+        fs::write(root.join("b.txt"), b"hello").unwrap();
+        let result = ai_disk_scanner::scan_path(&root.to_string_lossy()).unwrap();
+        let store = ScanStore::default();
+        let result = store.insert(result);
+        (dir, store, result)
+    }
+
+    #[test]
+    fn test_delete_marks_scan_dirty() {
+        let (_dir, store, result) = scanned_store();
+        let scan_id = result.scan_id.clone().unwrap();
+        let big = find(&result.root, "big.bin").unwrap().path.clone();
+        let a = find(&result.root, "a").unwrap().path.clone();
+        let b = find(&result.root, "b.txt").unwrap().path.clone();
+        assert!(store.staleness(&scan_id).unwrap().dirty_paths.is_empty());
+        assert_eq!(store.recursive_size(&a), Some(1010));
+        let scanned = store.target_state(&a).unwrap();
+        assert_eq!((scanned.size, scanned.child_count), (1010, Some(2)));
+
+        super::super::delete::delete_path(&big, Default::default()).unwrap();
+        assert!(!Path::new(&big).exists());
+        let events = store.mark_dirty(std::slice::from_ref(&big));
+        assert_eq!(
+            events,
+            vec![ScanStaleness {
+                scan_id: scan_id.clone(),
+                dirty_paths: vec![big.clone()],
+                changed_bytes: 1000,
+            }]
+        );
+        assert_eq!(store.staleness(&scan_id).unwrap(), events[0]);
+        // 变化路径的各级祖先不再使用扫描时的大小
+        assert_eq!(store.recursive_size(&a), None);
+        assert_eq!(store.recursive_size(&result.root.path), None);
+        assert_eq!(store.recursive_size(&b), Some(5));
+        // 本应用自己改变过的路径不再作为执行前核对的依据
+        assert_eq!(store.target_state(&a), None);
+        assert_eq!(store.target_state(&b).unwrap().size, 5);
+
+        // 重复标记不再发送事件
+        assert!(store.mark_dirty(std::slice::from_ref(&big)).is_empty());
+    }
+
+    #[test]
+    fn test_partial_quick_stats_completed_from_cache() {
+        let (_dir, store, result) = scanned_store();
+        let a = find(&result.root, "a").unwrap().path.clone();
+        let mut stats = QuickDirStats {
+            path: a.clone(),
+            child_count: 2,
+            file_count: 2,
+            dir_count: 0,
+            direct_size: 1010,
+            recursive_size: 10,
+            scanned_entries: 1,
+            partial: true,
+            elapsed_ms: 200,
+        };
+        complete_from_cache(&store, &mut stats);
+        assert_eq!((stats.recursive_size, stats.partial), (1010, false));
+
+        // 扫描后已有变化的目录保持部分结果
+        store.mark_dirty(&[a]);
+        stats.recursive_size = 10;
+        stats.partial = true;
+        complete_from_cache(&store, &mut stats);
+        assert_eq!((stats.recursive_size, stats.partial), (10, true));
+    }
+
+    #[test]
+    fn test_dirty_directory_absorbs_children() {
+        let (_dir, store, result) = scanned_store();
+        let scan_id = result.scan_id.clone().unwrap();
+        let big = find(&result.root, "big.bin").unwrap().path.clone();
+        let a = find(&result.root, "a").unwrap().path.clone();
+
+        store.mark_dirty(&[big.clone()]);
+        let events = store.mark_dirty(&[a.clone()]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].dirty_paths, vec![a.clone()]);
+        assert_eq!(events[0].changed_bytes, 1010);
+
+        // 已标记目录下的路径与扫描范围外的路径都不会产生变化
+        assert!(store.mark_dirty(&[big]).is_empty());
+        assert!(store
+            .mark_dirty(&["/definitely/not/scanned".to_string()])
+            .is_empty());
+        assert_eq!(store.staleness(&scan_id).unwrap().changed_bytes, 1010);
+        assert!(store.staleness("scan_missing").is_none());
+    }
+
+    #[test]
+    fn test_preflight_estimates_from_previous_scan() {
+        let (dir, store, result) = scanned_store();
+        let root = dir.path().to_string_lossy().to_string();
+        let preflight = preflight_scan_for(&store, &root, true);
+        assert_eq!(preflight.estimated_entries, Some(result.file_count));
+        assert!(!preflight.will_use_mft);
+        assert!(preflight.mft_reason.is_some());
+        assert!(!preflight.is_volume_root);
+        assert_eq!(preflight.is_elevated, check_admin_permission());
+        assert_eq!(preflight.recommended_mode, ai_disk_domain::ScanMode::Walk);
+        let json = serde_json::to_value(&preflight).unwrap();
+        assert_eq!(json["recommended_mode"], "walk");
+        assert_eq!(json["estimated_entries"], result.file_count);
+
+        // 未单独扫描过的子目录没有估计
+        let sub = dir.path().join("a").to_string_lossy().to_string();
+        assert_eq!(
+            preflight_scan_for(&store, &sub, true).estimated_entries,
+            None
+        );
+    }
+
+    #[test]
+    fn test_denied_directory_estimated_from_cached_scan() {
+        let (_dir, store, cached) = scanned_store();
+        // 再次扫描时 a 无权限读取：占位节点大小为 0、来源未知
+        let mut result = ScanResult::clone(&cached);
+        result.scan_id = None;
+        let a = result
+            .root
+            .children
+            .iter_mut()
+            .find(|c| c.name == "a")
+            .unwrap();
+        a.name = "a [无权限]".to_string();
+        a.size = 0;
+        a.size_source = SizeSource::Unknown;
+        a.children.clear();
+        result.root.size = 5;
+        result.total_size = 5;
+        let mut uncached = result.clone();
+
+        assert_eq!(estimate_from_cache(&store, &mut result), 1010);
+        assert_eq!(result.total_size, 1015);
+        assert_eq!(result.root.size_source, SizeSource::Estimated);
+        let a = find(&result.root, "a [无权限]").unwrap();
+        assert_eq!((a.size, a.size_source), (1010, SizeSource::Estimated));
+
+        // 没有覆盖该路径的缓存扫描时保持未知
+        assert_eq!(estimate_from_cache(&ScanStore::default(), &mut uncached), 0);
+        let a = find(&uncached.root, "a [无权限]").unwrap();
+        assert_eq!(a.size_source, SizeSource::Unknown);
+    }
+
+    #[test]
+    fn test_scan_controls_pause_and_release() {
+        let controls = ScanControls::default();
+        let pause = PauseToken::new();
+        let count = Arc::new(AtomicU64::new(0));
+        assert_eq!(controls.set_paused("scan_1", true), None);
+        {
+            let _guard = controls.register("scan_1", pause.clone(), count.clone());
+            count.store(42, Ordering::Relaxed);
+            assert_eq!(controls.set_paused("scan_1", true), Some((true, 42)));
+            assert_eq!(controls.set_paused("scan_1", true), Some((false, 42)));
+            assert!(pause.is_paused());
+        }
+        // 注销时自动继续，避免扫描线程一直阻塞
+        assert!(!pause.is_paused());
+        assert_eq!(controls.set_paused("scan_1", false), None);
+    }
+
+    #[test]
+    fn test_export_scan_stream_writes_trailer() {
+        let (dir, _store, result) = scanned_store();
+        let out = tempfile::tempdir().unwrap();
+        let target = out.path().join("scan.jsonl");
+        let root = dir.path().to_string_lossy().to_string();
+        let totals = export_scan_stream_to(&root, &target, true).unwrap();
+
+        assert_eq!(totals.file_count, result.file_count);
+        assert_eq!(totals.total_size, result.total_size);
+        let content = fs::read_to_string(&target).unwrap();
+        let last: serde_json::Value =
+            serde_json::from_str(content.lines().last().unwrap()).unwrap();
+        assert_eq!(last["type"], "trailer");
+        assert_eq!(last["total_size"], result.total_size);
+
+        let missing = dir.path().join("missing").to_string_lossy().to_string();
+        let failed = out.path().join("failed.jsonl");
+        assert!(export_scan_stream_to(&missing, &failed, true).is_err());
+        assert!(!failed.exists());
     }
-    stderr_flush();
-    let _ = window_emit.emit("scan-mft-status", (path_trimmed.clone(), used_mft));
-    Ok(result)
 }
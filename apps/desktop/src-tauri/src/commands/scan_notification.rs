@@ -0,0 +1,255 @@
+//! 扫描完成后的系统通知：显示占用与预计可释放空间，并发出 `open-scan-results` 事件。
+//!
+//! 桌面平台的通知插件不提供可靠的点击回调，因此后端在发送通知的同时发出深链事件，
+//! 由前端记录待打开的 scan_id，在用户点击通知、窗口重新获得焦点时跳转到结果视图。
+
+use ai_disk_common::{format_bytes, localize, ByteStyle, Lang, MessageId, ScanConfig};
+use ai_disk_domain::ScanResult;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// 通知对应的深链事件名
+pub const OPEN_SCAN_RESULTS_EVENT: &str = "open-scan-results";
+
+/// 一条扫描完成通知
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScanNotification {
+    pub title: String,
+    pub body: String,
+    /// 深链事件的载荷：前端据此打开对应的扫描结果
+    pub scan_id: Option<String>,
+    pub path: String,
+}
+
+/// 通知开启且扫描耗时不短于阈值
+pub fn should_notify(elapsed_ms: u64, config: &ScanConfig) -> bool {
+    config.notify_on_complete && elapsed_ms >= config.notify_min_duration_secs.saturating_mul(1000)
+}
+
+/// 按配置决定是否通知并以 `lang` 生成文本；reclaimable 为启发式分析得出的低风险可释放字节数
+pub fn build_scan_notification(
+    path: &str,
+    result: &ScanResult,
+    reclaimable: u64,
+    elapsed_ms: u64,
+    config: &ScanConfig,
+    lang: Lang,
+) -> Option<ScanNotification> {
+    if !should_notify(elapsed_ms, config) {
+        return None;
+    }
+    let used = format_bytes(result.total_size, ByteStyle::Binary);
+    let reclaimable = format_bytes(reclaimable, ByteStyle::Binary);
+    Some(ScanNotification {
+        title: localize(MessageId::ScanCompleteTitle, lang, &[]),
+        body: localize(
+            MessageId::ScanCompleteBody,
+            lang,
+            &[
+                ("path", path),
+                ("used", &used),
+                ("reclaimable", &reclaimable),
+            ],
+        ),
+        scan_id: result.scan_id.clone(),
+        path: path.to_string(),
+    })
+}
+
+/// 通知的发送端；测试中以记录调用的实现替代系统通知
+pub trait ScanNotifier {
+    fn show(&self, notification: &ScanNotification) -> Result<(), String>;
+    fn emit_open_results(&self, notification: &ScanNotification) -> Result<(), String>;
+}
+
+impl ScanNotifier for AppHandle {
+    fn show(&self, notification: &ScanNotification) -> Result<(), String> {
+        self.notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.body)
+            .show()
+            .map_err(|e| e.to_string())
+    }
+
+    fn emit_open_results(&self, notification: &ScanNotification) -> Result<(), String> {
+        self.emit(OPEN_SCAN_RESULTS_EVENT, notification)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 扫描完成后按配置发送通知；会对整棵扫描树做一次启发式分析，应在阻塞线程中调用。
+/// 通知发送失败不影响深链事件，也不影响扫描结果。
+/// 返回是否发出了通知
+pub fn notify_scan_complete(
+    notifier: &impl ScanNotifier,
+    path: &str,
+    result: &ScanResult,
+    elapsed_ms: u64,
+    config: &ScanConfig,
+    lang: Lang,
+) -> bool {
+    // 先判断阈值，避免对不需要通知的扫描做一次完整分析
+    if !should_notify(elapsed_ms, config) {
+        return false;
+    }
+    let reclaimable = ai_disk_engine::analyze_scan(result).reclaimable_low_risk;
+    let Some(notification) =
+        build_scan_notification(path, result, reclaimable, elapsed_ms, config, lang)
+    else {
+        return false;
+    };
+    if let Err(e) = notifier.show(&notification) {
+        tracing::warn!(path, error = %e, "scan notification failed");
+    }
+    if let Err(e) = notifier.emit_open_results(&notification) {
+        tracing::warn!(path, error = %e, "emit open-scan-results failed");
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::FileNode;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        shown: RefCell<Vec<ScanNotification>>,
+        emitted: RefCell<Vec<ScanNotification>>,
+        fail_show: bool,
+    }
+
+    impl ScanNotifier for RecordingNotifier {
+        fn show(&self, notification: &ScanNotification) -> Result<(), String> {
+            if self.fail_show {
+                return Err("notifications disabled by system".to_string());
+            }
+            self.shown.borrow_mut().push(notification.clone());
+            Ok(())
+        }
+
+        fn emit_open_results(&self, notification: &ScanNotification) -> Result<(), String> {
+            self.emitted.borrow_mut().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    fn scan(total_size: u64) -> ScanResult {
+        ScanResult {
+            scan_id: Some("scan_1".to_string()),
+            root: FileNode {
+                path: "C:\\".to_string(),
+                name: "C:\\".to_string(),
+                size: total_size,
+                is_dir: true,
+                ..Default::default()
+            },
+            total_size,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_notification_text_and_threshold() {
+        let config = ScanConfig::default();
+        let result = scan(1_319_413_953_331);
+        let notification = build_scan_notification(
+            "C:\\",
+            &result,
+            85 * 1024 * 1024 * 1024,
+            12_000,
+            &config,
+            Lang::Zh,
+        )
+        .unwrap();
+        assert_eq!(notification.title, "扫描完成");
+        assert_eq!(
+            notification.body,
+            format!(
+                "C:\\ 扫描完成：已用 {}，预计可释放 {}",
+                format_bytes(1_319_413_953_331, ByteStyle::Binary),
+                format_bytes(85 * 1024 * 1024 * 1024, ByteStyle::Binary)
+            )
+        );
+        assert_eq!(notification.scan_id.as_deref(), Some("scan_1"));
+        let english =
+            build_scan_notification("C:\\", &result, 0, 12_000, &config, Lang::En).unwrap();
+        assert_eq!(english.title, "Scan complete");
+        assert!(english.body.starts_with("C:\\ scanned: "));
+
+        // 短于阈值的扫描不通知；阈值为 0 时总是通知
+        assert!(build_scan_notification("C:\\", &result, 0, 9_999, &config, Lang::Zh).is_none());
+        let always = ScanConfig {
+            notify_min_duration_secs: 0,
+            ..Default::default()
+        };
+        assert!(build_scan_notification("C:\\", &result, 0, 0, &always, Lang::Zh).is_some());
+    }
+
+    #[test]
+    fn test_disabled_sends_nothing() {
+        let config = ScanConfig {
+            notify_on_complete: false,
+            ..Default::default()
+        };
+        let notifier = RecordingNotifier::default();
+        assert!(!notify_scan_complete(
+            &notifier,
+            "C:\\",
+            &scan(100),
+            60_000,
+            &config,
+            Lang::Zh
+        ));
+        assert!(notifier.shown.borrow().is_empty());
+        assert!(notifier.emitted.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_emits_open_results_event() {
+        let config = ScanConfig::default();
+        let notifier = RecordingNotifier::default();
+        assert!(notify_scan_complete(
+            &notifier,
+            "C:\\",
+            &scan(100),
+            60_000,
+            &config,
+            Lang::Zh
+        ));
+        assert_eq!(notifier.shown.borrow().len(), 1);
+        let emitted = notifier.emitted.borrow();
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].scan_id.as_deref(), Some("scan_1"));
+
+        // 系统通知失败时仍发出深链事件
+        let failing = RecordingNotifier {
+            fail_show: true,
+            ..Default::default()
+        };
+        assert!(notify_scan_complete(
+            &failing,
+            "C:\\",
+            &scan(100),
+            60_000,
+            &config,
+            Lang::Zh
+        ));
+        assert_eq!(failing.emitted.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,205 @@
+//! scan_path_command 的返回载荷：卷根扫描的 ScanResult 可达数十 MB JSON，IPC 序列化开销明显，
+//! 请求 `compress: true` 时改为 zstd 压缩后 base64 的字符串。
+//!
+//! 前端解码（见 `services/scanPayload.ts` 的 `decodeScanPayload`）：
+//! `compressed` 为 true 时对 `data` 做 base64 解码 → zstd 解压 → UTF-8 解码 → `JSON.parse`，
+//! 否则载荷本身就是 ScanResult。
+
+use std::io::Write;
+
+use ai_disk_common::CommandError;
+use ai_disk_domain::ScanResult;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+/// zstd 压缩级别：3 为默认级别，对重复度高的树 JSON 已有很高压缩比且足够快
+const ZSTD_LEVEL: i32 = 3;
+
+/// 未压缩时与原先一样直接是 ScanResult，压缩时为 `{ compressed: true, data }`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ScanPayload {
+    Compressed { compressed: bool, data: String },
+    Plain(Box<ScanResult>),
+}
+
+/// 编码结果与大小统计（写入扫描完成日志）
+pub(crate) struct EncodedScan {
+    pub payload: ScanPayload,
+    /// ScanResult 序列化后的 JSON 字节数
+    pub json_bytes: u64,
+    /// 实际经 IPC 返回的字节数：未压缩时同 json_bytes，压缩时为 base64 字符串长度
+    pub payload_bytes: u64,
+}
+
+/// 统计写入字节数的 Writer 包装
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn encode_error(e: impl std::fmt::Display) -> CommandError {
+    CommandError::internal(format!("扫描结果编码失败: {}", e))
+}
+
+/// 按需压缩；JSON 直接流式写入压缩器，不额外保留一份完整 JSON
+pub(crate) fn encode(result: ScanResult, compress: bool) -> Result<EncodedScan, CommandError> {
+    if !compress {
+        let mut counter = CountingWriter {
+            inner: std::io::sink(),
+            count: 0,
+        };
+        serde_json::to_writer(&mut counter, &result).map_err(encode_error)?;
+        return Ok(EncodedScan {
+            payload: ScanPayload::Plain(Box::new(result)),
+            json_bytes: counter.count,
+            payload_bytes: counter.count,
+        });
+    }
+
+    let encoder = zstd::Encoder::new(Vec::new(), ZSTD_LEVEL).map_err(encode_error)?;
+    let mut counter = CountingWriter {
+        inner: encoder,
+        count: 0,
+    };
+    serde_json::to_writer(&mut counter, &result).map_err(encode_error)?;
+    let compressed = counter.inner.finish().map_err(encode_error)?;
+    let data = STANDARD.encode(compressed);
+    Ok(EncodedScan {
+        json_bytes: counter.count,
+        payload_bytes: data.len() as u64,
+        payload: ScanPayload::Compressed {
+            compressed: true,
+            data,
+        },
+    })
+}
+
+/// [`encode`] 的逆过程，与前端解码步骤一致
+#[cfg(test)]
+pub(crate) fn decode(payload: ScanPayload) -> Result<ScanResult, CommandError> {
+    match payload {
+        ScanPayload::Plain(result) => Ok(*result),
+        ScanPayload::Compressed { data, .. } => {
+            let compressed = STANDARD.decode(data).map_err(encode_error)?;
+            let json = zstd::decode_all(compressed.as_slice()).map_err(encode_error)?;
+            serde_json::from_slice(&json).map_err(encode_error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::FileNode;
+
+    /// 每层 `width` 个子目录、每个目录 `width` 个文件，路径与名称高度重复
+    fn synthetic_tree(prefix: &str, depth: usize, width: usize) -> FileNode {
+        let mut children = Vec::new();
+        for i in 0..width {
+            let path = format!("{}/file_{:04}.log", prefix, i);
+            children.push(FileNode {
+                id: i as u64,
+                name: format!("file_{:04}.log", i),
+                path,
+                size: 4096,
+                is_dir: false,
+                modified: Some(1_700_000_000),
+                ..Default::default()
+            });
+            if depth > 0 {
+                children.push(synthetic_tree(
+                    &format!("{}/dir_{:04}", prefix, i),
+                    depth - 1,
+                    width,
+                ));
+            }
+        }
+        FileNode::new(prefix, children.iter().map(|c| c.size).sum(), true)
+            .with_modified(Some(1_700_000_000))
+            .with_children(children)
+    }
+
+    fn synthetic_result() -> ScanResult {
+        let root = synthetic_tree("/data/projects", 2, 20);
+        ScanResult {
+            scan_id: Some("scan_1".to_string()),
+            total_size: root.size,
+            root,
+            scan_time_ms: 1234,
+            file_count: 8_420,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    /// FileNode 未实现 PartialEq，按 JSON 比较
+    fn as_json(result: &ScanResult) -> serde_json::Value {
+        serde_json::to_value(result).unwrap()
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let expected = as_json(&synthetic_result());
+        let encoded = encode(synthetic_result(), true).unwrap();
+        assert!(matches!(
+            encoded.payload,
+            ScanPayload::Compressed {
+                compressed: true,
+                ..
+            }
+        ));
+        assert_eq!(
+            encoded.json_bytes,
+            serde_json::to_vec(&synthetic_result()).unwrap().len() as u64
+        );
+        // 经 IPC 的 JSON 形式再解析一次，与前端拿到的一致
+        let wire = serde_json::to_string(&encoded.payload).unwrap();
+        let decoded = decode(serde_json::from_str(&wire).unwrap()).unwrap();
+        assert_eq!(as_json(&decoded), expected);
+    }
+
+    #[test]
+    fn test_compressed_payload_is_much_smaller() {
+        let encoded = encode(synthetic_result(), true).unwrap();
+        assert!(
+            encoded.json_bytes >= 5 * encoded.payload_bytes,
+            "json {} bytes, payload {} bytes",
+            encoded.json_bytes,
+            encoded.payload_bytes
+        );
+    }
+
+    #[test]
+    fn test_plain_payload_is_unchanged_scan_result() {
+        let expected = as_json(&synthetic_result());
+        let encoded = encode(synthetic_result(), false).unwrap();
+        assert_eq!(encoded.json_bytes, encoded.payload_bytes);
+        // 未压缩时序列化结果与直接返回 ScanResult 完全相同，旧前端无需改动
+        let wire = serde_json::to_value(&encoded.payload).unwrap();
+        assert_eq!(wire, expected);
+        let decoded = decode(serde_json::from_value(wire).unwrap()).unwrap();
+        assert_eq!(as_json(&decoded), expected);
+    }
+}
@@ -0,0 +1,220 @@
+//! 定时执行计划：用户批准计划后指定执行时间与条件（接通电源、键鼠空闲若干分钟、没有全屏应用），
+//! 后台任务到时间后检查条件，在降低了优先级的专用线程上执行，完成后发送系统通知。
+//! 任务及其执行结果保存在 `.disk-rookie/scheduled_executions.json`，目标为会话时结果同时追加到会话。
+//! 调度与条件检测见 [`ai_disk_executor::ExecutionScheduler`] 与 [`ai_disk_executor::SystemConditions`]。
+
+use std::path::Path;
+use std::time::Duration;
+
+use ai_disk_common::{format_bytes, localize, ByteStyle, CommandError, ErrorCode, MessageId};
+use ai_disk_domain::{
+    ExecutionConditions, ExecutionReport, PlannedAction, ScheduleTarget, ScheduledExecution,
+};
+use ai_disk_executor::{ExecutionScheduler, ScheduleRunner, SystemClock, SystemConditions};
+use tauri::{async_runtime, AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Notify;
+
+use super::config::ConfigState;
+use super::execute::{execute_actions, failed_report, validate_actions};
+use super::saved_plans::saved_plan_actions;
+use super::sessions::approved_actions;
+use super::storage::get_storage_root;
+
+/// 任务文件名（位于存储根目录下）
+const SCHEDULES_FILE: &str = "scheduled_executions.json";
+
+/// 检查到期任务的间隔；新建任务与「立即执行」会提前唤醒
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 定时执行完成事件名，负载为 `(schedule_id, ExecutionReport)`
+pub const SCHEDULED_EXECUTION_FINISHED_EVENT: &str = "scheduled-execution-finished";
+
+pub struct ScheduleState {
+    scheduler: ExecutionScheduler,
+    /// 唤醒后台任务立即检查
+    wake: Notify,
+}
+
+impl ScheduleState {
+    pub fn open(storage_root: &Path) -> Self {
+        Self {
+            scheduler: ExecutionScheduler::open(&storage_root.join(SCHEDULES_FILE)),
+            wake: Notify::new(),
+        }
+    }
+}
+
+/// 启动后台任务：每隔 [`CHECK_INTERVAL`] 或被唤醒时执行到期且条件满足的任务
+pub fn spawn_execution_scheduler(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let state = app.state::<ScheduleState>();
+        let mut runner = DesktopScheduleRunner { app: &app };
+        loop {
+            let _ = tokio::time::timeout(CHECK_INTERVAL, state.wake.notified()).await;
+            state
+                .scheduler
+                .tick(&SystemClock, &SystemConditions, &mut runner)
+                .await;
+        }
+    });
+}
+
+struct DesktopScheduleRunner<'a> {
+    app: &'a AppHandle,
+}
+
+impl ScheduleRunner for DesktopScheduleRunner<'_> {
+    async fn run(&mut self, schedule: &ScheduledExecution) -> ExecutionReport {
+        tracing::info!(
+            schedule_id = %schedule.schedule_id,
+            target = ?schedule.target,
+            "running scheduled execution"
+        );
+        let app = self.app.clone();
+        let target = schedule.target.clone();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        // 专用线程降低 CPU 与 IO 优先级后执行，不与前台应用争抢资源
+        std::thread::spawn(move || {
+            ai_disk_scanner::lower_current_thread_priority();
+            let _ = tx.send(async_runtime::block_on(run_target(&app, &target)));
+        });
+        let report = rx
+            .await
+            .unwrap_or_else(|_| failed_report(&CommandError::internal("定时执行的线程异常退出")));
+        let _ = self.app.emit(
+            SCHEDULED_EXECUTION_FINISHED_EVENT,
+            (&schedule.schedule_id, &report),
+        );
+        notify_finished(self.app, &report);
+        report
+    }
+}
+
+/// 按当前文件读取目标的动作：计划或会话在定时之后可能已被修改
+fn target_actions(
+    root: &Path,
+    target: &ScheduleTarget,
+) -> Result<Vec<PlannedAction>, CommandError> {
+    match target {
+        ScheduleTarget::Plan { name } => saved_plan_actions(root, name),
+        ScheduleTarget::Session { session_id } => approved_actions(root, session_id),
+    }
+}
+
+async fn run_target(app: &AppHandle, target: &ScheduleTarget) -> ExecutionReport {
+    let actions = match get_storage_root(app).and_then(|root| target_actions(&root, target)) {
+        Ok(actions) => actions,
+        Err(e) => return failed_report(&e),
+    };
+    let session_id = match target {
+        ScheduleTarget::Session { session_id } => Some(session_id.as_str()),
+        ScheduleTarget::Plan { .. } => None,
+    };
+    // 无人值守执行，目标已改变的动作一律跳过
+    let (report, result) = execute_actions(app, &actions, session_id, None, false).await;
+    if let Err(e) = result {
+        tracing::warn!(error = %e, "scheduled execution failed");
+    }
+    report
+}
+
+/// 以界面语言发送完成通知；通知失败只记录日志
+fn notify_finished(app: &AppHandle, report: &ExecutionReport) {
+    let lang = app.state::<ConfigState>().get().ui.language;
+    let executed = report.executed.to_string();
+    let body = if report.failed {
+        localize(
+            MessageId::ScheduledExecutionFailedBody,
+            lang,
+            &[("executed", &executed), ("error", &report.summary)],
+        )
+    } else {
+        let freed = format_bytes(report.freed, ByteStyle::Binary);
+        localize(
+            MessageId::ScheduledExecutionBody,
+            lang,
+            &[("executed", &executed), ("freed", &freed)],
+        )
+    };
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(localize(MessageId::ScheduledExecutionTitle, lang, &[]))
+        .body(body)
+        .show()
+    {
+        tracing::warn!(error = %e, "scheduled execution notification failed");
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn not_pending(schedule_id: &str) -> CommandError {
+    CommandError::new(
+        ErrorCode::InvalidInput,
+        format!("没有等待中的定时执行: {}", schedule_id),
+    )
+}
+
+/// 定时执行已保存的计划或会话中已批准的动作：到 `run_at`（Unix 秒）后满足 `conditions` 时在后台执行。
+/// 创建时先按当前文件检查目标，执行时重新读取，期间对计划或决定的修改会生效
+#[tauri::command]
+pub async fn schedule_plan_execution(
+    app: AppHandle,
+    state: State<'_, ScheduleState>,
+    target: ScheduleTarget,
+    run_at: u64,
+    conditions: Option<ExecutionConditions>,
+) -> Result<ScheduledExecution, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    validate_actions(&target_actions(&storage_root, &target)?)?;
+    let schedule_id = format!("schedule_{}_{:08x}", now_secs(), rand::random::<u32>());
+    let schedule = ScheduledExecution::new(
+        schedule_id,
+        target,
+        run_at,
+        conditions.unwrap_or_default(),
+        now_secs(),
+    );
+    state.scheduler.add(schedule.clone())?;
+    state.wake.notify_one();
+    Ok(schedule)
+}
+
+/// 所有定时执行（含已结束的及其执行结果），按计划时间排序
+#[tauri::command]
+pub fn list_scheduled_executions(state: State<'_, ScheduleState>) -> Vec<ScheduledExecution> {
+    state.scheduler.list()
+}
+
+/// 取消等待中的定时执行；已开始的执行不能取消
+#[tauri::command]
+pub fn cancel_scheduled_execution(
+    state: State<'_, ScheduleState>,
+    schedule_id: String,
+) -> Result<ScheduledExecution, CommandError> {
+    state
+        .scheduler
+        .cancel(&schedule_id)?
+        .ok_or_else(|| not_pending(&schedule_id))
+}
+
+/// 不再等待时间与条件，立即在后台执行
+#[tauri::command]
+pub fn run_scheduled_execution_now(
+    state: State<'_, ScheduleState>,
+    schedule_id: String,
+) -> Result<ScheduledExecution, CommandError> {
+    let schedule = state
+        .scheduler
+        .run_now(&schedule_id)?
+        .ok_or_else(|| not_pending(&schedule_id))?;
+    state.wake.notify_one();
+    Ok(schedule)
+}
@@ -0,0 +1,531 @@
+//! 分析会话：把一次扫描的分析结果、通过校验的清理计划（含用户对每个动作的决定）与执行结果
+//! 保存在 `.disk-rookie/sessions/<session_id>.json`。分析、计划与执行命令传入 `session_id` 时
+//! 把各自的结果写入会话，重启应用后可从会话接着处理，会话本身也是完整的审计记录。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ai_disk_common::{write_atomic, CommandError, ErrorCode};
+use ai_disk_domain::{
+    ActionDecision, CleanupPlan, DiskAnalysis, ExecutionReport, PlannedAction, RiskLevel,
+    ScanReference, ScanResult, Session, SessionPlan, SessionSummary,
+};
+use ai_disk_engine::validate_plan;
+use tauri::{AppHandle, State};
+
+use super::scan::ScanStore;
+use super::storage::{get_storage_root, resolve_storage_path};
+
+/// 会话文件所在的目录（位于存储根目录下）
+const SESSIONS_DIR: &str = "sessions";
+
+/// 会话的读-改-写串行进行，避免并发的分析与计划命令互相覆盖
+static SESSIONS_LOCK: Mutex<()> = Mutex::new(());
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 新会话 ID：秒级时间戳加随机后缀，同时用作会话文件名
+fn new_session_id() -> String {
+    format!("session_{}_{:08x}", now_secs(), rand::random::<u32>())
+}
+
+/// 会话 ID 只能含字母、数字、`_` 与 `-`，防止拼出会话目录之外的路径
+fn session_path(root: &Path, session_id: &str) -> Result<PathBuf, CommandError> {
+    let valid = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("无效的会话 ID: {}", session_id),
+        ));
+    }
+    resolve_storage_path(root, &format!("{}/{}.json", SESSIONS_DIR, session_id))
+}
+
+fn read_session(path: &Path, session_id: &str) -> Result<Session, CommandError> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CommandError::new(
+                ErrorCode::InvalidInput,
+                format!("会话不存在: {}", session_id),
+            ))
+        }
+        Err(e) => return Err(CommandError::io("读取会话失败", &e)),
+    };
+    serde_json::from_str(&raw).map_err(|e| {
+        CommandError::new(
+            ErrorCode::Config,
+            format!("会话文件 {} 无效: {}", session_id, e),
+        )
+    })
+}
+
+fn write_session(path: &Path, session: &Session) -> Result<(), CommandError> {
+    let text = serde_json::to_vec_pretty(session)
+        .map_err(|e| CommandError::internal(format!("序列化会话失败: {}", e)))?;
+    write_atomic(path, &text).map_err(|e| CommandError::io("写入会话失败", &e))
+}
+
+fn create_session_in(
+    root: &Path,
+    scan_id: String,
+    scan: &ScanResult,
+) -> Result<Session, CommandError> {
+    let session_id = new_session_id();
+    let path = session_path(root, &session_id)?;
+    let now = now_secs();
+    let session = Session {
+        session_id,
+        created_at: now,
+        updated_at: now,
+        scan: ScanReference::new(scan_id, scan),
+        analysis: None,
+        plan: None,
+        executions: Vec::new(),
+    };
+    let _guard = SESSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    write_session(&path, &session)?;
+    Ok(session)
+}
+
+fn load_session_in(root: &Path, session_id: &str) -> Result<Session, CommandError> {
+    read_session(&session_path(root, session_id)?, session_id)
+}
+
+/// 读取会话、由 `update` 修改后写回，并刷新更新时间；`update` 出错时会话保持不变
+fn update_session_in(
+    root: &Path,
+    session_id: &str,
+    update: impl FnOnce(&mut Session) -> Result<(), CommandError>,
+) -> Result<Session, CommandError> {
+    let path = session_path(root, session_id)?;
+    let _guard = SESSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut session = read_session(&path, session_id)?;
+    update(&mut session)?;
+    session.updated_at = now_secs();
+    write_session(&path, &session)?;
+    Ok(session)
+}
+
+/// 按更新时间倒序列出会话；无法读取的会话文件记录日志后跳过
+fn list_sessions_in(root: &Path) -> Result<Vec<SessionSummary>, CommandError> {
+    let dir = root.join(SESSIONS_DIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CommandError::io("读取会话目录失败", &e)),
+    };
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let session_id = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match read_session(&path, &session_id) {
+            Ok(session) => sessions.push(session.summary()),
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "跳过无法读取的会话"),
+        }
+    }
+    sessions.sort_by(|a, b| (b.updated_at, &b.session_id).cmp(&(a.updated_at, &a.session_id)));
+    Ok(sessions)
+}
+
+/// 写入分析结果，覆盖之前的分析
+pub(crate) fn record_analysis(
+    app: &AppHandle,
+    session_id: &str,
+    analysis: &DiskAnalysis,
+) -> Result<(), CommandError> {
+    let root = get_storage_root(app)?;
+    update_session_in(&root, session_id, |session| {
+        session.analysis = Some(analysis.clone());
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+/// 只保留通过校验的动作并按扫描结果重新计算预计释放量、记录各动作目标的扫描时状态，
+/// 所有动作的决定重置为待定
+fn validated_plan(plan: &CleanupPlan, scan: &ScanResult) -> SessionPlan {
+    let (plan, rejected) = validate_plan(plan, scan);
+    for reason in rejected {
+        tracing::warn!(%reason, "会话计划中丢弃未通过校验的动作");
+    }
+    SessionPlan {
+        decisions: vec![ActionDecision::Pending; plan.actions.len()],
+        plan,
+    }
+}
+
+/// 写入新生成的计划，覆盖之前的计划及决定
+pub(crate) fn record_plan(
+    app: &AppHandle,
+    session_id: &str,
+    plan: &CleanupPlan,
+    scan: &ScanResult,
+) -> Result<(), CommandError> {
+    let root = get_storage_root(app)?;
+    update_session_in(&root, session_id, |session| {
+        session.plan = Some(validated_plan(plan, scan));
+        Ok(())
+    })
+    .map(|_| ())
+}
+
+fn set_decisions(
+    session: &mut Session,
+    decisions: Vec<ActionDecision>,
+) -> Result<(), CommandError> {
+    let Some(plan) = session.plan.as_mut() else {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("会话 {} 还没有清理计划", session.session_id),
+        ));
+    };
+    if decisions.len() != plan.plan.actions.len() {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "决定数量 {} 与计划中的动作数量 {} 不一致",
+                decisions.len(),
+                plan.plan.actions.len()
+            ),
+        ));
+    }
+    plan.decisions = decisions;
+    Ok(())
+}
+
+/// 会话只保存动作本身与目标的扫描时状态，转为计划动作时预计释放量记为 0、风险按中等处理，
+/// 与迁移的 v1 计划相同
+fn session_planned_action(plan: &SessionPlan, index: usize) -> PlannedAction {
+    PlannedAction {
+        action: plan.plan.actions[index].clone(),
+        bytes: 0,
+        risk: RiskLevel::Medium,
+        reason: String::new(),
+        scanned: plan.plan.targets.get(index).copied().flatten(),
+    }
+}
+
+/// 会话计划中的全部动作（不论决定），下标与 `plan.actions` 一致，供部分执行按下标勾选
+pub(crate) fn session_plan_actions(
+    root: &Path,
+    session_id: &str,
+) -> Result<Vec<PlannedAction>, CommandError> {
+    let session = load_session_in(root, session_id)?;
+    let plan = session.plan.ok_or_else(|| {
+        CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("会话 {} 还没有计划", session_id),
+        )
+    })?;
+    Ok((0..plan.plan.actions.len())
+        .map(|index| session_planned_action(&plan, index))
+        .collect())
+}
+
+/// 会话计划中已批准的动作，供定时执行使用
+pub(crate) fn approved_actions(
+    root: &Path,
+    session_id: &str,
+) -> Result<Vec<PlannedAction>, CommandError> {
+    let session = load_session_in(root, session_id)?;
+    let actions: Vec<PlannedAction> = session
+        .plan
+        .iter()
+        .flat_map(|plan| {
+            (0..plan.plan.actions.len().min(plan.decisions.len()))
+                .filter(move |&index| plan.decisions[index] == ActionDecision::Approved)
+                .map(move |index| session_planned_action(plan, index))
+        })
+        .collect();
+    if actions.is_empty() {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("会话 {} 中没有已批准的动作", session_id),
+        ));
+    }
+    Ok(actions)
+}
+
+/// 追加一次执行的结果。文件已经改动，写入失败只记录日志，不影响执行命令的返回
+pub(crate) fn record_execution(app: &AppHandle, session_id: &str, report: ExecutionReport) {
+    let result = get_storage_root(app).and_then(|root| {
+        update_session_in(&root, session_id, |session| {
+            session.executions.push(report);
+            Ok(())
+        })
+    });
+    if let Err(e) = result {
+        tracing::warn!(session_id, error = %e, "写入会话执行结果失败");
+    }
+}
+
+/// 为缓存中的扫描结果创建会话
+#[tauri::command]
+pub async fn create_session(
+    app: AppHandle,
+    scan_store: State<'_, ScanStore>,
+    scan_id: String,
+) -> Result<Session, CommandError> {
+    let scan = scan_store.resolve(Some(scan_id.clone()), None)?;
+    let storage_root = get_storage_root(&app)?;
+    create_session_in(&storage_root, scan_id, &scan)
+}
+
+#[tauri::command]
+pub async fn get_session(app: AppHandle, session_id: String) -> Result<Session, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    load_session_in(&storage_root, &session_id)
+}
+
+/// 更新用户对计划中每个动作的决定，`decisions` 与计划中的动作一一对应
+#[tauri::command]
+pub async fn update_session_decisions(
+    app: AppHandle,
+    session_id: String,
+    decisions: Vec<ActionDecision>,
+) -> Result<Session, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    update_session_in(&storage_root, &session_id, |session| {
+        set_decisions(session, decisions)
+    })
+}
+
+/// 列出所有会话，最近更新的在前
+#[tauri::command]
+pub async fn list_sessions(app: AppHandle) -> Result<Vec<SessionSummary>, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    list_sessions_in(&storage_root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_common::{ExecutionMode, SnapshotConfig};
+    use ai_disk_domain::{Action, FileNode, PlannedAction, RiskLevel, SessionStage};
+    use ai_disk_executor::{run_execution, ActionExecutor, ExecutionStep};
+
+    fn node(path: &Path, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path.to_string_lossy(), size, !children.is_empty()).with_children(children)
+    }
+
+    fn fixture_scan(data: &Path, files: &[(&str, u64)]) -> ScanResult {
+        let children: Vec<FileNode> = files
+            .iter()
+            .map(|(name, size)| {
+                fs::write(data.join(name), vec![0u8; *size as usize]).unwrap();
+                node(&data.join(name), *size, vec![])
+            })
+            .collect();
+        let total_size = files.iter().map(|(_, size)| size).sum();
+        ScanResult {
+            scan_id: Some("scan_1".to_string()),
+            root: node(data, total_size, children),
+            scan_time_ms: 12,
+            file_count: files.len() as u64,
+            total_size,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    /// 直接删除文件的执行器
+    struct DeleteExecutor;
+
+    impl ActionExecutor for DeleteExecutor {
+        type Error = CommandError;
+
+        async fn execute(&mut self, step: &ExecutionStep<'_>) -> Result<u64, CommandError> {
+            let Action::Delete { path } = &step.planned.action else {
+                unreachable!("测试计划只含删除动作");
+            };
+            fs::remove_file(path).map_err(|e| CommandError::io("删除失败", &e))?;
+            Ok(step.planned.bytes)
+        }
+    }
+
+    #[test]
+    fn test_session_lifecycle_is_persisted() {
+        let storage = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let root = storage.path();
+        let scan = fixture_scan(data.path(), &[("old.iso", 300), ("notes.txt", 20)]);
+        let old_iso = data.path().join("old.iso").to_string_lossy().into_owned();
+        let notes = data.path().join("notes.txt").to_string_lossy().into_owned();
+
+        let created = create_session_in(root, "scan_1".to_string(), &scan).unwrap();
+        assert_eq!(created.stage(), SessionStage::Scanned);
+        let id = created.session_id.clone();
+
+        // 分析
+        let analysis = ai_disk_engine::analyze_scan(&scan);
+        update_session_in(root, &id, |session| {
+            session.analysis = Some(analysis.clone());
+            Ok(())
+        })
+        .unwrap();
+
+        // 计划：作用于正在写入的导出文件的动作被丢弃
+        let export = data.path().join("export.zip.tmp");
+        let _in_flight = ai_disk_common::register_in_flight(&export);
+        let plan = CleanupPlan {
+            actions: vec![
+                Action::Delete {
+                    path: old_iso.clone(),
+                },
+                Action::Delete {
+                    path: export.to_string_lossy().into_owned(),
+                },
+                Action::Delete {
+                    path: notes.clone(),
+                },
+            ],
+            estimated_space: 1 << 40,
+            ..Default::default()
+        };
+        let planned = update_session_in(root, &id, |session| {
+            session.plan = Some(validated_plan(&plan, &scan));
+            Ok(())
+        })
+        .unwrap();
+        let session_plan = planned.plan.as_ref().unwrap();
+        assert_eq!(session_plan.plan.actions.len(), 2);
+        assert_eq!(session_plan.plan.estimated_space, 320);
+        assert_eq!(session_plan.decisions, vec![ActionDecision::Pending; 2]);
+
+        // 决定：数量不一致时拒绝且不改动会话
+        let err = update_session_in(root, &id, |session| {
+            set_decisions(session, vec![ActionDecision::Approved])
+        })
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        // 还没有批准任何动作时不能定时执行
+        assert_eq!(
+            approved_actions(root, &id).unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+        let decided = update_session_in(root, &id, |session| {
+            set_decisions(
+                session,
+                vec![ActionDecision::Approved, ActionDecision::Rejected],
+            )
+        })
+        .unwrap();
+        assert_eq!(decided.stage(), SessionStage::Planned);
+        let scheduled = approved_actions(root, &id).unwrap();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].risk, RiskLevel::Medium);
+
+        // 执行已批准的动作
+        let session_plan = decided.plan.unwrap();
+        let approved: Vec<PlannedAction> = session_plan
+            .plan
+            .actions
+            .iter()
+            .zip(&session_plan.decisions)
+            .filter(|(_, decision)| **decision == ActionDecision::Approved)
+            .map(|(action, _)| PlannedAction {
+                action: action.clone(),
+                bytes: 300,
+                risk: RiskLevel::Low,
+                reason: String::new(),
+                scanned: None,
+            })
+            .collect();
+        let outcome = tauri::async_runtime::block_on(run_execution(
+            &root.join("executions"),
+            "exec_1",
+            &approved,
+            &[ExecutionMode::Permanent],
+            &SnapshotConfig::default(),
+            false,
+            &mut DeleteExecutor,
+        ))
+        .unwrap();
+        update_session_in(root, &id, |session| {
+            session.executions.push(ExecutionReport {
+                execution_id: "exec_1".to_string(),
+                finished_at: now_secs(),
+                resumed: false,
+                executed: outcome.executed,
+                skipped: outcome.skipped,
+                freed: outcome.freed,
+                summary: "删除 1 项".to_string(),
+                failed: false,
+                selection: None,
+                changed: outcome.changed,
+            });
+            Ok(())
+        })
+        .unwrap();
+        assert!(!Path::new(&old_iso).exists());
+        assert!(Path::new(&notes).exists());
+
+        // 重启后从磁盘读取：每个阶段都在
+        let session = load_session_in(root, &id).unwrap();
+        assert_eq!(session.scan.scan_id, "scan_1");
+        assert_eq!(session.scan.root_path, scan.root.path);
+        assert_eq!(session.scan.total_size, 320);
+        assert_eq!(session.analysis.as_ref().unwrap().total_size, 320);
+        let plan = session.plan.as_ref().unwrap();
+        assert!(matches!(&plan.plan.actions[1], Action::Delete { path } if *path == notes));
+        assert_eq!(plan.decisions[1], ActionDecision::Rejected);
+        assert_eq!(session.executions.len(), 1);
+        assert_eq!(session.executions[0].executed, 1);
+        assert_eq!(session.executions[0].freed, 300);
+        assert_eq!(session.stage(), SessionStage::Executed);
+
+        let listed = list_sessions_in(root).unwrap();
+        assert_eq!(listed, vec![session.summary()]);
+        assert_eq!(listed[0].freed_bytes, 300);
+    }
+
+    #[test]
+    fn test_rejects_unknown_or_unsafe_session_ids() {
+        let storage = tempfile::tempdir().unwrap();
+        let root = storage.path();
+        assert!(list_sessions_in(root).unwrap().is_empty());
+        for bad in ["", "../secrets", "a/b", r"a\b", "s.json"] {
+            assert_eq!(
+                load_session_in(root, bad).unwrap_err().code,
+                ErrorCode::InvalidInput
+            );
+        }
+        let err = load_session_in(root, "session_0_missing").unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+
+        // 没有计划时不能设置决定
+        let data = tempfile::tempdir().unwrap();
+        let scan = fixture_scan(data.path(), &[]);
+        let id = create_session_in(root, "scan_1".to_string(), &scan)
+            .unwrap()
+            .session_id;
+        let err =
+            update_session_in(root, &id, |session| set_decisions(session, vec![])).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+    }
+}
@@ -1,6 +1,7 @@
+use ai_disk_common::{write_atomic, CommandError, ErrorCode};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -10,33 +11,116 @@ pub struct StorageResult {
 }
 
 /// 获取存储根目录 (.disk-rookie)
-fn get_storage_root(app: &AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn get_storage_root(app: &AppHandle) -> Result<PathBuf, CommandError> {
     let home_dir = app
         .path()
         .home_dir()
-        .map_err(|e| format!("无法获取用户目录: {}", e))?;
+        .map_err(|e| CommandError::internal(format!("无法获取用户目录: {}", e)))?;
 
     let storage_root = home_dir.join(".disk-rookie");
 
     // 确保目录存在
     if !storage_root.exists() {
-        fs::create_dir_all(&storage_root).map_err(|e| format!("创建存储目录失败: {}", e))?;
+        fs::create_dir_all(&storage_root).map_err(|e| CommandError::io("创建存储目录失败", &e))?;
     }
 
     Ok(storage_root)
 }
 
-/// 读取文件
-#[tauri::command]
-pub async fn read_storage_file(app: AppHandle, filename: String) -> Result<String, String> {
-    let storage_root = get_storage_root(&app)?;
-    let file_path = storage_root.join(&filename);
+/// 把前端传入的相对文件名解析为存储根目录下的路径。
+///
+/// `/` 与 `\\` 都视为分隔符；拒绝空名、绝对路径、`..`、Windows 盘符与 UNC 路径，
+/// 并对已存在的最深祖先目录做 canonicalize，防止通过符号链接逃出存储根目录
+pub(crate) fn resolve_storage_path(root: &Path, filename: &str) -> Result<PathBuf, CommandError> {
+    let invalid = |reason: &str| {
+        CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("非法的存储文件名 {}: {}", filename, reason),
+        )
+        .with_details(serde_json::json!({ "filename": filename }))
+    };
 
-    if !file_path.exists() {
-        return Ok(String::new());
+    if filename.is_empty() || filename.contains('\0') {
+        return Err(invalid("文件名为空或包含空字符"));
     }
+    if filename.starts_with(['/', '\\']) || Path::new(filename).is_absolute() {
+        return Err(invalid("不允许绝对路径"));
+    }
+
+    let mut path = root.to_path_buf();
+    for segment in filename.split(['/', '\\']) {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(invalid("不允许访问上级目录")),
+            s if s.contains(':') => return Err(invalid("不允许盘符或备用数据流")),
+            s => path.push(s),
+        }
+    }
+    if path == root {
+        return Err(invalid("未指定文件"));
+    }
+
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|e| CommandError::io("解析存储目录失败", &e))?;
+    let mut ancestor = path.as_path();
+    // 用 symlink_metadata 判断存在性：悬空的符号链接也要参与 canonicalize（并因此失败）
+    while fs::symlink_metadata(ancestor).is_err() {
+        ancestor = match ancestor.parent() {
+            Some(parent) => parent,
+            None => break,
+        };
+    }
+    let canonical = ancestor
+        .canonicalize()
+        .map_err(|e| CommandError::io("解析存储路径失败", &e))?;
+    if !canonical.starts_with(&canonical_root) {
+        return Err(invalid("路径位于存储目录之外"));
+    }
+    Ok(path)
+}
 
-    fs::read_to_string(&file_path).map_err(|e| format!("读取文件失败 {}: {}", filename, e))
+fn read_file_in(root: &Path, filename: &str) -> Result<String, CommandError> {
+    let file_path = resolve_storage_path(root, filename)?;
+    match fs::read_to_string(&file_path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(CommandError::io(&format!("读取文件失败 {}", filename), &e)),
+    }
+}
+
+/// 原子写入（临时文件 + fsync + rename），写入失败时原文件保持不变
+fn write_file_in(root: &Path, filename: &str, content: &str) -> Result<(), CommandError> {
+    let file_path = resolve_storage_path(root, filename)?;
+    if file_path.is_dir() {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("目标是目录: {}", filename),
+        ));
+    }
+    write_atomic(&file_path, content.as_bytes())
+        .map_err(|e| CommandError::io(&format!("写入文件失败 {}", filename), &e))
+}
+
+fn delete_file_in(root: &Path, filename: &str) -> Result<(), CommandError> {
+    let file_path = resolve_storage_path(root, filename)?;
+    match fs::symlink_metadata(&file_path) {
+        Ok(metadata) if metadata.is_dir() => Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            format!("不能删除目录: {}", filename),
+        )),
+        Ok(_) => fs::remove_file(&file_path)
+            .map_err(|e| CommandError::io(&format!("删除文件失败 {}", filename), &e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(CommandError::io(&format!("删除文件失败 {}", filename), &e)),
+    }
+}
+
+/// 读取文件
+#[tauri::command]
+pub async fn read_storage_file(app: AppHandle, filename: String) -> Result<String, CommandError> {
+    let storage_root = get_storage_root(&app)?;
+    read_file_in(&storage_root, &filename)
 }
 
 /// 写入文件
@@ -45,18 +129,9 @@ pub async fn write_storage_file(
     app: AppHandle,
     filename: String,
     content: String,
-) -> Result<StorageResult, String> {
+) -> Result<StorageResult, CommandError> {
     let storage_root = get_storage_root(&app)?;
-    let file_path = storage_root.join(&filename);
-
-    // 确保父目录存在
-    if let Some(parent) = file_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
-        }
-    }
-
-    fs::write(&file_path, content).map_err(|e| format!("写入文件失败 {}: {}", filename, e))?;
+    write_file_in(&storage_root, &filename, &content)?;
 
     Ok(StorageResult {
         success: true,
@@ -64,18 +139,14 @@ pub async fn write_storage_file(
     })
 }
 
-/// 删除文件
+/// 删除文件（不删除目录）
 #[tauri::command]
 pub async fn delete_storage_file(
     app: AppHandle,
     filename: String,
-) -> Result<StorageResult, String> {
+) -> Result<StorageResult, CommandError> {
     let storage_root = get_storage_root(&app)?;
-    let file_path = storage_root.join(&filename);
-
-    if file_path.exists() {
-        fs::remove_file(&file_path).map_err(|e| format!("删除文件失败 {}: {}", filename, e))?;
-    }
+    delete_file_in(&storage_root, &filename)?;
 
     Ok(StorageResult {
         success: true,
@@ -88,10 +159,10 @@ pub async fn delete_storage_file(
 pub async fn list_storage_files(
     app: AppHandle,
     subdir: Option<String>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, CommandError> {
     let storage_root = get_storage_root(&app)?;
     let target_dir = if let Some(sub) = subdir {
-        storage_root.join(sub)
+        resolve_storage_path(&storage_root, &sub)?
     } else {
         storage_root
     };
@@ -100,7 +171,7 @@ pub async fn list_storage_files(
         return Ok(Vec::new());
     }
 
-    let entries = fs::read_dir(&target_dir).map_err(|e| format!("读取目录失败: {}", e))?;
+    let entries = fs::read_dir(&target_dir).map_err(|e| CommandError::io("读取目录失败", &e))?;
 
     let mut files = Vec::new();
     for entry in entries.flatten() {
@@ -115,10 +186,103 @@ pub async fn list_storage_files(
 
 /// 获取存储根目录路径
 #[tauri::command]
-pub async fn get_storage_path(app: AppHandle) -> Result<String, String> {
+pub async fn get_storage_path(app: AppHandle) -> Result<String, CommandError> {
     let storage_root = get_storage_root(&app)?;
     storage_root
         .to_str()
         .map(|s| s.to_string())
-        .ok_or_else(|| "无法转换路径".to_string())
+        .ok_or_else(|| CommandError::internal("无法转换路径"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_escape_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".disk-rookie");
+        fs::create_dir_all(&root).unwrap();
+        let payloads = [
+            "",
+            ".",
+            "../secret.txt",
+            "../../.ssh/authorized_keys",
+            "..\\..\\.ssh\\authorized_keys",
+            "config/../../outside.json",
+            "config\\..\\..\\outside.json",
+            "./../outside",
+            "/etc/passwd",
+            "\\etc\\passwd",
+            "\\\\server\\share\\file",
+            "//server/share/file",
+            "C:\\Windows\\win.ini",
+            "C:outside.txt",
+            "settings.json:stream",
+            "bad\0name",
+        ];
+        for payload in payloads {
+            let err = resolve_storage_path(&root, payload).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidInput, "{:?}", payload);
+            assert!(write_file_in(&root, payload, "x").is_err(), "{:?}", payload);
+            assert!(delete_file_in(&root, payload).is_err(), "{:?}", payload);
+        }
+        assert!(!dir.path().join("secret.txt").exists());
+        assert!(!dir.path().join("outside.json").exists());
+
+        assert_eq!(
+            resolve_storage_path(&root, "plans\\today.json").unwrap(),
+            root.join("plans").join("today.json")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_symlink_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(".disk-rookie");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        assert!(resolve_storage_path(&root, "link/file.txt").is_err());
+        assert!(write_file_in(&root, "link/file.txt", "x").is_err());
+        assert!(!outside.join("file.txt").exists());
+    }
+
+    #[test]
+    fn test_write_read_delete_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert_eq!(read_file_in(root, "missing.json").unwrap(), "");
+
+        write_file_in(root, "plans/a.json", "{}").unwrap();
+        assert_eq!(read_file_in(root, "plans/a.json").unwrap(), "{}");
+
+        let err = delete_file_in(root, "plans").unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert!(root.join("plans").is_dir());
+
+        delete_file_in(root, "plans/a.json").unwrap();
+        delete_file_in(root, "plans/a.json").unwrap();
+        assert!(!root.join("plans/a.json").exists());
+    }
+
+    #[test]
+    fn test_failed_write_keeps_old_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_file_in(root, "settings.json", "old").unwrap();
+
+        // 占住临时文件路径，让写入在 rename 之前失败
+        let tmp = root.join(format!(".settings.json.{}.tmp", std::process::id()));
+        fs::create_dir(&tmp).unwrap();
+        assert!(write_file_in(root, "settings.json", "new").is_err());
+        assert_eq!(read_file_in(root, "settings.json").unwrap(), "old");
+
+        fs::remove_dir(&tmp).unwrap();
+        write_file_in(root, "settings.json", "new").unwrap();
+        assert_eq!(read_file_in(root, "settings.json").unwrap(), "new");
+    }
 }
@@ -0,0 +1,60 @@
+//! 遥测命令：开关匿名遥测、查看待上报事件；后台任务定期批量上报队列中的事件。
+
+use std::time::Duration;
+
+use ai_disk_common::{telemetry, AppConfig, CommandError};
+use tauri::{AppHandle, State};
+
+use super::config::ConfigState;
+
+/// 上报间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// 开启或关闭遥测；关闭时清空本地队列并重置匿名 ID
+#[tauri::command]
+pub fn set_telemetry_enabled(
+    app: AppHandle,
+    state: State<'_, ConfigState>,
+    enabled: bool,
+) -> Result<AppConfig, CommandError> {
+    let mut config = state.get();
+    config.telemetry.enabled = enabled;
+    state.replace(&app, config)
+}
+
+/// 本地队列中尚未上报的事件，供用户查看将要发送的内容
+#[tauri::command]
+pub fn get_pending_telemetry() -> Vec<serde_json::Value> {
+    telemetry().map(|t| t.pending_events()).unwrap_or_default()
+}
+
+/// 启动后台上报任务；未开启或未配置上报地址时每轮直接跳过
+pub fn spawn_telemetry_flusher() {
+    tauri::async_runtime::spawn(async {
+        let client = reqwest::Client::new();
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let Some(t) = telemetry() else {
+                continue;
+            };
+            let Some(endpoint) = t.endpoint() else {
+                continue;
+            };
+            let batch = t.pending_batch();
+            if batch.is_empty() {
+                continue;
+            }
+            let sent = client
+                .post(&endpoint)
+                .json(&batch)
+                .timeout(Duration::from_secs(30))
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            match sent {
+                Ok(_) => t.ack(batch.len()),
+                Err(e) => tracing::debug!(error = %e, "遥测上报失败，保留在本地队列"),
+            }
+        }
+    });
+}
@@ -0,0 +1,314 @@
+//! 后端 token 管理：按账号保存带绝对过期时间的凭据，云存储调用前统一取有效 access token，
+//! 临近过期时透明刷新（同一账号的并发刷新串行化）；refresh token 失效时广播 `cloud-auth-expired`。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use ai_disk_common::{CommandError, ErrorCode};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::credentials::{refresh_tokens, CredentialStore, StoredCredentials};
+use super::oauth::OAuthTokens;
+
+/// 距过期不足该时长即视为需要刷新
+const REFRESH_MARGIN_MS: u64 = 60_000;
+
+/// 用 refresh token 换取新 token：`(provider, refresh_token)`
+type Refresher = Box<
+    dyn Fn(String, String) -> BoxFuture<'static, Result<OAuthTokens, CommandError>> + Send + Sync,
+>;
+
+/// refresh token 失效时的回调：`(provider, account_id)`
+type AuthExpiredHook = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+/// `cloud-auth-expired` 事件数据，前端据此提示重新授权
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthExpiredEvent {
+    pub provider: String,
+    pub account_id: String,
+}
+
+pub struct TokenManager {
+    store: CredentialStore,
+    refresher: Refresher,
+    on_auth_expired: AuthExpiredHook,
+    refresh_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn expires_soon(credentials: &StoredCredentials) -> bool {
+    credentials.expires_at <= now_millis().saturating_add(REFRESH_MARGIN_MS)
+}
+
+/// refresh token 已被撤销或过期：统一刷新策略返回 `ReauthRequired`，
+/// 其他来源的错误仍按各服务商的错误字段判断
+fn is_invalid_grant(e: &CommandError) -> bool {
+    e.code == ErrorCode::ReauthRequired
+        || e.message.contains("invalid_grant")
+        || e.message.contains("expired_token")
+}
+
+impl TokenManager {
+    pub fn new(store: CredentialStore, app: AppHandle) -> Self {
+        Self::with_refresher(
+            store,
+            Box::new(|provider, refresh_token| {
+                Box::pin(async move { refresh_tokens(&provider, refresh_token).await })
+            }),
+            Box::new(move |provider, account_id| {
+                let _ = app.emit(
+                    "cloud-auth-expired",
+                    AuthExpiredEvent {
+                        provider: provider.to_string(),
+                        account_id: account_id.to_string(),
+                    },
+                );
+            }),
+        )
+    }
+
+    fn with_refresher(
+        store: CredentialStore,
+        refresher: Refresher,
+        on_auth_expired: AuthExpiredHook,
+    ) -> Self {
+        Self {
+            store,
+            refresher,
+            on_auth_expired,
+            refresh_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn store(&self) -> &CredentialStore {
+        &self.store
+    }
+
+    /// 取有效的 access token，距过期不足 60 秒时先刷新
+    pub async fn get_valid_access_token(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<String, CommandError> {
+        Ok(self
+            .get_valid_credentials(provider, account_id)
+            .await?
+            .access_token)
+    }
+
+    /// 同 [`Self::get_valid_access_token`]，返回完整凭据（含过期时间）
+    pub async fn get_valid_credentials(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<StoredCredentials, CommandError> {
+        self.ensure_fresh(provider, account_id, false).await
+    }
+
+    /// 无论是否临近过期都刷新一次（等待期间已被其他调用方刷新则直接使用其结果）
+    pub async fn force_refresh(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<StoredCredentials, CommandError> {
+        self.ensure_fresh(provider, account_id, true).await
+    }
+
+    fn load_required(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<StoredCredentials, CommandError> {
+        self.store.load(provider, account_id)?.ok_or_else(|| {
+            CommandError::new(ErrorCode::Unauthorized, "未找到已保存的凭据，请重新授权")
+        })
+    }
+
+    fn lock_for(&self, provider: &str, account_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.refresh_locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(format!("{}:{}", provider, account_id))
+            .or_default()
+            .clone()
+    }
+
+    fn auth_expired(&self, provider: &str, account_id: &str) -> CommandError {
+        tracing::warn!(provider, "refresh token 已失效，需要重新授权");
+        (self.on_auth_expired)(provider, account_id);
+        CommandError::new(ErrorCode::Unauthorized, "登录已过期，请重新授权")
+    }
+
+    async fn ensure_fresh(
+        &self,
+        provider: &str,
+        account_id: &str,
+        force: bool,
+    ) -> Result<StoredCredentials, CommandError> {
+        let current = self.load_required(provider, account_id)?;
+        if !force && !expires_soon(&current) {
+            return Ok(current);
+        }
+
+        let lock = self.lock_for(provider, account_id);
+        let _guard = lock.lock().await;
+
+        // 等锁期间其他调用方可能已经刷新过
+        let latest = self.load_required(provider, account_id)?;
+        let refreshed_meanwhile = latest.expires_at != current.expires_at;
+        if !expires_soon(&latest) && (!force || refreshed_meanwhile) {
+            return Ok(latest);
+        }
+
+        let Some(refresh_token) = latest.refresh_token.clone() else {
+            return Err(self.auth_expired(provider, account_id));
+        };
+
+        tracing::info!(provider, "access token 即将过期，正在刷新");
+        let tokens = (self.refresher)(provider.to_string(), refresh_token)
+            .await
+            .map_err(|e| {
+                if is_invalid_grant(&e) {
+                    self.auth_expired(provider, account_id)
+                } else {
+                    e
+                }
+            })?;
+
+        let credentials = StoredCredentials::from_tokens(tokens, latest.refresh_token);
+        self.store.save(provider, account_id, &credentials)?;
+        Ok(credentials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn stored(access: &str, expires_at: u64) -> StoredCredentials {
+        StoredCredentials {
+            access_token: access.to_string(),
+            refresh_token: Some("rt-old".to_string()),
+            expires_at,
+            token_type: "Bearer".to_string(),
+            scope: None,
+        }
+    }
+
+    /// 计数的模拟刷新：延迟一段时间后返回新 token
+    fn counting_manager(calls: Arc<AtomicUsize>) -> TokenManager {
+        TokenManager::with_refresher(
+            CredentialStore::mock(),
+            Box::new(move |_, refresh_token| {
+                let calls = calls.clone();
+                Box::pin(async move {
+                    assert_eq!(refresh_token, "rt-old");
+                    let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    Ok(OAuthTokens {
+                        access_token: format!("at-new-{}", n),
+                        refresh_token: None,
+                        expires_in: 3600,
+                        token_type: "Bearer".to_string(),
+                        scope: None,
+                        extra: Default::default(),
+                    })
+                })
+            }),
+            Box::new(|_, _| {}),
+        )
+    }
+
+    #[test]
+    fn test_valid_token_is_returned_without_refresh() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let manager = counting_manager(calls.clone());
+        manager
+            .store()
+            .save(
+                "dropbox",
+                "acc",
+                &stored("at-live", now_millis() + 3_600_000),
+            )
+            .unwrap();
+
+        let token =
+            tauri::async_runtime::block_on(manager.get_valid_access_token("dropbox", "acc"))
+                .unwrap();
+        assert_eq!(token, "at-live");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_concurrent_callers_share_one_refresh() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let manager = counting_manager(calls.clone());
+        // 30 秒后过期，落在 60 秒刷新窗口内
+        manager
+            .store()
+            .save(
+                "google_drive",
+                "acc",
+                &stored("at-old", now_millis() + 30_000),
+            )
+            .unwrap();
+
+        let tokens = tauri::async_runtime::block_on(futures::future::join_all(
+            (0..8).map(|_| manager.get_valid_access_token("google_drive", "acc")),
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        for token in tokens {
+            assert_eq!(token.unwrap(), "at-new-1");
+        }
+
+        // 刷新结果已持久化，且保留了旧的 refresh token
+        let saved = manager
+            .store()
+            .load("google_drive", "acc")
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.refresh_token.as_deref(), Some("rt-old"));
+        assert!(!expires_soon(&saved));
+    }
+
+    #[test]
+    fn test_invalid_grant_emits_auth_expired() {
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let recorded = expired.clone();
+        let manager = TokenManager::with_refresher(
+            CredentialStore::mock(),
+            Box::new(|_, _| {
+                Box::pin(async {
+                    Err(CommandError::new(
+                        ErrorCode::Network,
+                        r#"刷新 token 失败: {"error":"invalid_grant"}"#,
+                    ))
+                })
+            }),
+            Box::new(move |provider, account_id| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:{}", provider, account_id));
+            }),
+        );
+        manager
+            .store()
+            .save("onedrive", "acc", &stored("at-old", 0))
+            .unwrap();
+
+        let err = tauri::async_runtime::block_on(manager.get_valid_access_token("onedrive", "acc"))
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert_eq!(*expired.lock().unwrap(), vec!["onedrive:acc".to_string()]);
+    }
+}
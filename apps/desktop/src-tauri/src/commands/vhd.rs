@@ -0,0 +1,40 @@
+//! 虚拟磁盘压缩命令：给出压缩 WSL/Docker 虚拟磁盘要执行的命令行，
+//! 供用户不想交给计划执行（或未以管理员身份运行）时自行复制执行。
+
+use std::path::Path;
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+use ai_disk_executor::{command_line, compact_commands, CompactMethod};
+
+/// 压缩 `path` 需要依次执行的命令行。Windows 上能找到对应的 WSL 发行版时为
+/// `wsl --manage <发行版> --set-sparse true`，否则为 Optimize-VHD
+#[tauri::command]
+pub async fn get_vhd_compact_commands(path: String) -> Result<Vec<String>, CommandError> {
+    if !path.to_lowercase().ends_with(".vhdx") {
+        return Err(CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::NotAVirtualDisk,
+            &[("path", &path)],
+        ));
+    }
+    let method = tauri::async_runtime::spawn_blocking({
+        let path = path.clone();
+        move || compact_method(Path::new(&path))
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?;
+    Ok(compact_commands(&path, &method)
+        .iter()
+        .map(|argv| command_line(argv))
+        .collect())
+}
+
+#[cfg(windows)]
+fn compact_method(path: &Path) -> CompactMethod {
+    ai_disk_executor::compact_method_for(path)
+}
+
+#[cfg(not(windows))]
+fn compact_method(_path: &Path) -> CompactMethod {
+    CompactMethod::OptimizeVhd
+}
@@ -0,0 +1,73 @@
+//! Windows 组件清理命令：DISM 估算组件存储可回收空间，cleanmgr / DISM 执行清理。
+//! 两者都需要管理员权限，未提权时直接返回 NeedsElevation，前端据此提示以管理员身份重启。
+
+use ai_disk_common::{CommandError, ErrorCode};
+use ai_disk_executor::{ComponentStoreAnalysis, WindowsCleanupCategory, WindowsCleanupReport};
+use tauri::AppHandle;
+
+use super::permission::check_admin_permission;
+
+fn ensure_windows_admin() -> Result<(), CommandError> {
+    if !cfg!(windows) {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            "Windows 组件清理仅支持 Windows",
+        ));
+    }
+    if !check_admin_permission() {
+        return Err(CommandError::new(
+            ErrorCode::NeedsElevation,
+            "Windows 组件清理需要以管理员身份运行",
+        ));
+    }
+    Ok(())
+}
+
+/// 估算组件存储（旧的更新文件等）可回收的空间；DISM 分析通常需要数十秒
+#[tauri::command]
+pub async fn get_windows_cleanup_estimate() -> Result<ComponentStoreAnalysis, CommandError> {
+    ensure_windows_admin()?;
+    #[cfg(windows)]
+    {
+        tauri::async_runtime::spawn_blocking(ai_disk_executor::analyze_component_store)
+            .await
+            .map_err(|e| CommandError::internal(e.to_string()))?
+            .map_err(CommandError::from)
+    }
+    #[cfg(not(windows))]
+    {
+        unreachable!("ensure_windows_admin rejects non-Windows platforms")
+    }
+}
+
+/// 清理所选类别，过程中发送 `windows-cleanup-progress` 事件
+#[tauri::command]
+pub async fn run_windows_cleanup(
+    app: AppHandle,
+    categories: Vec<WindowsCleanupCategory>,
+) -> Result<WindowsCleanupReport, CommandError> {
+    if categories.is_empty() {
+        return Err(CommandError::new(
+            ErrorCode::InvalidInput,
+            "请至少选择一个清理类别",
+        ));
+    }
+    ensure_windows_admin()?;
+    #[cfg(windows)]
+    {
+        use tauri::Emitter;
+        tauri::async_runtime::spawn_blocking(move || {
+            ai_disk_executor::run_windows_cleanup(&categories, &mut |progress| {
+                let _ = app.emit("windows-cleanup-progress", progress);
+            })
+        })
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .map_err(CommandError::from)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = app;
+        unreachable!("ensure_windows_admin rejects non-Windows platforms")
+    }
+}
@@ -1,27 +1,122 @@
 mod commands;
 
+use ai_disk_common::{ForwardedArgs, InstanceRole};
+use commands::busy::BusyState;
+use commands::cloud_upload::UploadState;
+use commands::config::ConfigState;
+use commands::credentials::CredentialStore;
+use commands::hashing::HashCacheState;
+use commands::io_sampling::IoSamplingState;
+use commands::launch::LaunchState;
 use commands::oauth::OAuthState;
+use commands::scan::{ScanControls, ScanStore};
+use commands::scheduled_executions::ScheduleState;
+use commands::token_manager::TokenManager;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // 初始化日志系统（过滤 tao/winit 事件循环的 WARN，避免刷屏）
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .filter_module("tao", log::LevelFilter::Error)
-        .filter_module("winit", log::LevelFilter::Error)
-        .init();
-
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_notification::init())
         .manage(OAuthState::default())
+        .manage(ScanStore::default())
+        .manage(ScanControls::default())
+        .manage(UploadState::default())
+        .manage(IoSamplingState::default())
+        .manage(LaunchState::default())
+        .manage(BusyState::default())
+        .manage(HashCacheState::default())
+        .on_window_event(|window, event| {
+            // 执行计划、上传或扫描进行中时先请前端确认
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                commands::busy::handle_close_requested(window, api);
+            }
+        })
+        .setup(|app| {
+            // 已有实例在运行时把启动参数（如要扫描的路径）转交给它后退出，
+            // 避免两个进程同时读取同一卷的 MFT、重复启动 OAuth 回调服务
+            let storage_root = commands::storage::get_storage_root(app.handle())?;
+            // 存储目录（已保存的扫描、日志等）不计入扫描结果，计划也不能作用于其中
+            ai_disk_common::set_storage_root(&storage_root);
+            match ai_disk_common::acquire_instance_lock(&storage_root)? {
+                InstanceRole::Primary(guard) => {
+                    let handle = app.handle().clone();
+                    guard.listen(move |forwarded| {
+                        commands::launch::handle_forwarded_launch(&handle, forwarded);
+                    })?;
+                    // `--scan <路径>`：前端就绪后发出 startup-scan-request
+                    commands::launch::queue_startup_scan(app.handle(), ForwardedArgs::from_env());
+                }
+                InstanceRole::Secondary => {
+                    let forwarded = ForwardedArgs::from_env();
+                    if let Err(e) = ai_disk_common::forward_to_primary(&storage_root, &forwarded) {
+                        eprintln!("转发启动参数失败: {}", e);
+                    }
+                    std::process::exit(0);
+                }
+            }
+            let config_state = ConfigState::load(app.handle())?;
+            // 日志级别取自配置；初始化失败时不影响启动
+            let log_dir = commands::logs::get_log_dir(app.handle())?;
+            if let Err(e) =
+                ai_disk_common::init_logging(&log_dir, &config_state.get().logging.level)
+            {
+                eprintln!("初始化日志失败: {}", e);
+            }
+            ai_disk_common::init_telemetry(
+                &storage_root.join("telemetry"),
+                &config_state.get().telemetry,
+            );
+            commands::telemetry::spawn_telemetry_flusher();
+            commands::cloud_upload::bandwidth::upload_limiter()
+                .set_limit(config_state.get().upload.max_bytes_per_sec);
+            ai_disk_engine::llm::llm_limiter().set_limits(
+                ai_disk_engine::llm::LlmLimits::from_config(&config_state.get().llm),
+            );
+            let credential_store = CredentialStore::open(&storage_root);
+            match commands::credentials::migrate_plaintext_tokens(&storage_root, &credential_store)
+            {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "已将明文 token 迁移到凭据存储"),
+                Err(e) => tracing::warn!(error = %e, "迁移明文 token 失败，下次启动重试"),
+            }
+            app.manage(TokenManager::new(credential_store, app.handle().clone()));
+            app.manage(config_state);
+            // 定期检查各卷剩余空间，跌破阈值时提醒
+            commands::low_space::spawn_low_space_monitor(app.handle().clone());
+            // 上次崩溃或被强制关闭时未完成的执行，由前端提示续做
+            commands::execute::log_interrupted_executions(app.handle());
+            // 定时执行：上次退出时正在执行的任务标记为中断，其执行日志可续做
+            app.manage(ScheduleState::open(&storage_root));
+            commands::scheduled_executions::spawn_execution_scheduler(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::scan::scan_path_command,
+            commands::scan::quick_dir_stats_command,
+            commands::scan::discover_cleanup_targets_command,
+            commands::scan::get_known_folders,
+            commands::scan::get_scan_staleness,
+            commands::scan::locate_in_scan,
+            commands::scan::explain_mft_availability_command,
+            commands::scan::preflight_scan,
+            commands::scan::export_scan_stream,
+            commands::scan::pause_scan,
+            commands::scan::resume_scan,
             commands::analyze::analyze_disk,
             commands::plan::get_cleanup_plan,
+            commands::plan::get_cleanup_plan_json,
+            commands::plan::summarize_plan,
+            commands::preview::preview_file_command,
             commands::execute::execute_plan,
+            commands::execute::execute_plan_selection,
+            commands::execute::list_interrupted_executions,
+            commands::execute::resume_execution,
+            commands::execute::rollback_execution,
+            commands::hashing::hash_files,
             commands::permission::check_admin_permission,
             commands::delete::delete_item,
             commands::storage::read_storage_file,
@@ -29,7 +124,35 @@ pub fn run() {
             commands::storage::delete_storage_file,
             commands::storage::list_storage_files,
             commands::storage::get_storage_path,
+            commands::documents::get_document,
+            commands::documents::put_document,
+            commands::documents::migrate_documents,
+            commands::saved_plans::save_plan,
+            commands::saved_plans::load_plan,
+            commands::saved_plans::export_plan,
+            commands::saved_plans::import_plan,
+            commands::sessions::create_session,
+            commands::sessions::get_session,
+            commands::sessions::update_session_decisions,
+            commands::sessions::list_sessions,
+            commands::scheduled_executions::schedule_plan_execution,
+            commands::scheduled_executions::list_scheduled_executions,
+            commands::scheduled_executions::cancel_scheduled_execution,
+            commands::scheduled_executions::run_scheduled_execution_now,
+            commands::app_data::export_app_data,
+            commands::app_data::import_app_data,
+            commands::config::get_app_config,
+            commands::config::set_app_config,
+            commands::logs::get_recent_logs,
+            commands::telemetry::set_telemetry_enabled,
+            commands::telemetry::get_pending_telemetry,
+            commands::diagnostics::get_diagnostics,
+            commands::diagnostics::reset_diagnostics,
+            commands::credentials::get_cloud_credentials,
+            commands::credentials::delete_cloud_credentials,
+            commands::credentials::refresh_cloud_credentials,
             // OAuth commands
+            commands::oauth::cancel_oauth_flow,
             commands::oauth::complete_google_oauth,
             commands::oauth::refresh_google_token,
             commands::oauth::revoke_google_token,
@@ -53,9 +176,37 @@ pub fn run() {
             commands::oauth::revoke_dropbox_token,
             commands::oauth::get_dropbox_user_info,
             commands::oauth::get_dropbox_quota,
+            // OneDrive OAuth commands
+            commands::oauth::onedrive::complete_onedrive_oauth,
+            commands::oauth::onedrive::refresh_onedrive_token,
+            commands::oauth::onedrive::revoke_onedrive_token,
+            commands::oauth::onedrive::get_onedrive_user_info,
+            commands::oauth::onedrive::get_onedrive_quota,
+            commands::cloud_quota::get_all_cloud_quotas,
+            commands::integrations::check_integrations,
             // Cloud upload commands
             commands::cloud_upload::upload_to_cloud,
+            commands::cloud_upload::cancel_uploads,
+            commands::cloud_upload::list_resumable_uploads,
+            commands::cloud_upload::resume_upload,
+            commands::cloud_upload::set_upload_bandwidth_limit,
+            commands::cloud_upload::verify_remote_file,
+            commands::cloud_upload::list_cloud_files,
+            commands::cloud_upload::delete_cloud_file,
+            commands::cloud_upload::find_backed_up_files,
             commands::open_in_file_manager::open_in_file_manager,
+            commands::open_in_file_manager::reveal_in_file_manager,
+            commands::windows_cleanup::get_windows_cleanup_estimate,
+            commands::windows_cleanup::run_windows_cleanup,
+            commands::vhd::get_vhd_compact_commands,
+            commands::io_sampling::start_io_sampling,
+            commands::io_sampling::stop_io_sampling,
+            commands::disk_health::get_disk_health,
+            commands::launch::frontend_ready,
+            commands::launch::register_context_menu,
+            commands::launch::unregister_context_menu,
+            commands::busy::get_busy_state,
+            commands::busy::force_close,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,889 @@
+//! 对 ScanResult 的确定性分析：按目录名/路径特征将空间归入缓存、临时文件、陈旧下载等类别，
+//! 生成结论与可回收空间估算。不依赖 LLM，相同输入总是得到相同输出。
+//! 可回收空间按实际占用（NTFS 压缩、重复数据删除后）估算，删除压缩文件只能释放其实际占用。
+//! 应用自身的占用（存储根目录、正在写入的导出文件）不计入任何类别，也不出现在结论中。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ai_disk_common::{format_bytes, ByteStyle, InternalPaths};
+use ai_disk_domain::{
+    CategoryTotal, DiskAnalysis, FileCategory, FileNode, Finding, KnownFolder, RedownloadableItem,
+    RedownloadableSummary, RiskLevel, ScanResult,
+};
+
+use crate::llm::{LlmError, LlmProvider};
+use crate::prompt::{build_analysis_prompt, ANALYSIS_SYSTEM_PROMPT};
+use crate::redownloadable::match_redownloadable;
+use crate::validator::score_node_risk;
+use crate::virtual_disks::find_virtual_disks;
+
+/// 下载目录中超过该时长未使用（访问或修改，见 [`FileNode::last_used`]）的文件视为陈旧（约 6 个月）
+const STALE_DOWNLOAD_SECS: u64 = 180 * 24 * 3600;
+/// 每条结论最多保留的支撑路径数
+const MAX_FINDING_PATHS: usize = 10;
+
+/// 整体视为缓存的目录名（小写比较）
+const CACHE_DIR_NAMES: &[&str] = &[
+    "cache",
+    "caches",
+    "cache2",
+    "code cache",
+    "gpucache",
+    "shadercache",
+    "grshadercache",
+    "cachestorage",
+    ".cache",
+];
+
+/// 整体视为临时文件的目录名
+const TEMP_DIR_NAMES: &[&str] = &["temp", "tmp"];
+
+/// 包管理器缓存与依赖目录
+const PACKAGE_CACHE_DIR_NAMES: &[&str] = &[
+    "node_modules",
+    ".npm",
+    ".yarn",
+    ".pnpm",
+    ".pnpm-store",
+    "__pycache__",
+    ".gradle",
+    "bower_components",
+];
+
+/// 路径中出现这些片段时，其下的缓存目录归为浏览器缓存（已统一为小写、正斜杠）
+const BROWSER_PATH_MARKERS: &[&str] = &[
+    "/google/chrome/",
+    "/microsoft/edge/",
+    "/mozilla/firefox/",
+    "/bravesoftware/",
+    "/chromium/",
+    "/opera software/",
+    "/vivaldi/",
+    "/com.apple.safari/",
+];
+
+/// 下载目录中视为安装包的扩展名
+pub(crate) const INSTALLER_EXTENSIONS: &[&str] = &[
+    "exe", "msi", "msix", "dmg", "pkg", "iso", "deb", "rpm", "appimage",
+];
+
+/// 类别对应的风险等级；Other 与按扩展名识别的文件类型不参与可回收空间估算
+fn category_risk(category: FileCategory) -> Option<RiskLevel> {
+    match category {
+        FileCategory::BrowserCache
+        | FileCategory::AppCache
+        | FileCategory::Temp
+        | FileCategory::Installer
+        | FileCategory::Trash => Some(RiskLevel::Low),
+        FileCategory::PackageCache
+        | FileCategory::StaleDownloads
+        | FileCategory::Redownloadable => Some(RiskLevel::Medium),
+        FileCategory::Video
+        | FileCategory::Audio
+        | FileCategory::Image
+        | FileCategory::Document
+        | FileCategory::Archive
+        | FileCategory::Other => None,
+    }
+}
+
+fn category_headline(category: FileCategory, bytes: u64) -> String {
+    let size = format_bytes(bytes, ByteStyle::Binary);
+    match category {
+        FileCategory::BrowserCache => format!("浏览器缓存共 {}", size),
+        FileCategory::AppCache => format!("应用缓存共 {}", size),
+        FileCategory::PackageCache => format!("包管理器缓存与依赖目录共 {}", size),
+        FileCategory::Temp => format!("临时文件共 {}", size),
+        FileCategory::StaleDownloads => format!("下载目录中 6 个月以上未使用的文件共 {}", size),
+        FileCategory::Installer => format!("下载目录中的安装包共 {}", size),
+        FileCategory::Trash => format!("废纸篓中的文件共 {}", size),
+        FileCategory::Redownloadable => {
+            format!("可重新下载的游戏、模型与容器镜像等共 {}", size)
+        }
+        FileCategory::Video => format!("视频共 {}", size),
+        FileCategory::Audio => format!("音频共 {}", size),
+        FileCategory::Image => format!("图片共 {}", size),
+        FileCategory::Document => format!("文档共 {}", size),
+        FileCategory::Archive => format!("压缩包与镜像共 {}", size),
+        FileCategory::Other => format!("其他文件共 {}", size),
+    }
+}
+
+/// 系统文件与文档、图片目录即使命中缓存等类别也不计入可回收空间
+fn demote_system_files(category: FileCategory, node: &FileNode) -> FileCategory {
+    match category_risk(category) {
+        Some(risk) if score_node_risk(risk, node) == RiskLevel::High => FileCategory::Other,
+        _ => category,
+    }
+}
+
+/// 节点实际占用的磁盘空间
+fn physical_size(node: &FileNode) -> u64 {
+    node.physical_size.unwrap_or(node.size)
+}
+
+/// 节点是否命中可重新下载内容的规则
+fn is_redownloadable(node: &FileNode) -> bool {
+    match_redownloadable(&node.path, node.is_dir).is_some()
+}
+
+/// 整体归类的目录中含有可重新下载的直接子节点时（如 `.cache/huggingface`），
+/// 这些子节点单独归类，其余部分仍按该目录的类别计
+fn splits_for_redownloadable(category: FileCategory, node: &FileNode) -> bool {
+    !matches!(category, FileCategory::Redownloadable | FileCategory::Trash)
+        && node.children.iter().any(is_redownloadable)
+}
+
+/// 扫描器标记的下载目录（按系统解析出的实际位置，不看目录名）
+pub(crate) fn is_downloads(node: &FileNode) -> bool {
+    node.known_folder == Some(KnownFolder::Downloads)
+}
+
+/// 遍历时的路径上下文
+#[derive(Clone, Copy, Default)]
+struct WalkContext {
+    in_downloads: bool,
+}
+
+#[derive(Default)]
+struct CategoryAcc {
+    bytes: u64,
+    /// 实际占用，用于可回收空间估算
+    physical_bytes: u64,
+    count: u64,
+    /// (大小, 路径)；Other 类别不记录路径，避免大树下占用过多内存
+    entries: Vec<(u64, String)>,
+}
+
+struct Analyzer {
+    now_secs: u64,
+    internal: InternalPaths,
+    /// 跳过的应用自身占用的字节数
+    internal_bytes: u64,
+    acc: BTreeMap<FileCategory, CategoryAcc>,
+    redownloadable: Vec<RedownloadableItem>,
+}
+
+impl Analyzer {
+    fn new(now_secs: u64, internal: InternalPaths) -> Self {
+        Self {
+            now_secs,
+            internal,
+            internal_bytes: 0,
+            acc: BTreeMap::new(),
+            redownloadable: Vec::new(),
+        }
+    }
+
+    /// `node` 之下应用自身占用的字节数（含自身）
+    fn internal_bytes_within(&self, node: &FileNode) -> u64 {
+        if self.internal.contains(&node.path) {
+            return node.size;
+        }
+        if !node.is_dir || !self.internal.any_within(&node.path) {
+            return 0;
+        }
+        node.children
+            .iter()
+            .map(|child| self.internal_bytes_within(child))
+            .sum()
+    }
+
+    fn record(&mut self, category: FileCategory, node: &FileNode, bytes: u64, physical: u64) {
+        let category = demote_system_files(category, node);
+        let acc = self.acc.entry(category).or_default();
+        acc.bytes = acc.bytes.saturating_add(bytes);
+        acc.physical_bytes = acc.physical_bytes.saturating_add(physical);
+        acc.count += 1;
+        if category != FileCategory::Other {
+            acc.entries.push((bytes, node.path.clone()));
+        }
+        if category == FileCategory::Redownloadable {
+            if let Some(rule) = match_redownloadable(&node.path, node.is_dir) {
+                self.redownloadable.push(RedownloadableItem {
+                    path: node.path.clone(),
+                    bytes,
+                    app: rule.app.to_string(),
+                    restore_hint: rule.restore_hint.to_string(),
+                });
+            }
+        }
+    }
+
+    fn classify_dir(node: &FileNode) -> Option<FileCategory> {
+        // 扫描器已按位置识别的类别（废纸篓）优先
+        if node.category.is_some() {
+            return node.category;
+        }
+        if is_redownloadable(node) {
+            return Some(FileCategory::Redownloadable);
+        }
+        let name = node.name.to_lowercase();
+        if PACKAGE_CACHE_DIR_NAMES.contains(&name.as_str()) {
+            return Some(FileCategory::PackageCache);
+        }
+        if TEMP_DIR_NAMES.contains(&name.as_str()) {
+            return Some(FileCategory::Temp);
+        }
+        if CACHE_DIR_NAMES.contains(&name.as_str()) {
+            let path = node.path.to_lowercase().replace('\\', "/");
+            let in_browser = BROWSER_PATH_MARKERS.iter().any(|m| path.contains(m));
+            return Some(if in_browser {
+                FileCategory::BrowserCache
+            } else {
+                FileCategory::AppCache
+            });
+        }
+        None
+    }
+
+    fn classify_file(&self, node: &FileNode, ctx: WalkContext) -> FileCategory {
+        if is_redownloadable(node) {
+            return FileCategory::Redownloadable;
+        }
+        if !ctx.in_downloads {
+            return FileCategory::Other;
+        }
+        let ext = node
+            .name
+            .rsplit_once('.')
+            .map(|(_, e)| e.to_lowercase())
+            .unwrap_or_default();
+        if INSTALLER_EXTENSIONS.contains(&ext.as_str()) {
+            return FileCategory::Installer;
+        }
+        if self.is_stale(node) {
+            FileCategory::StaleDownloads
+        } else {
+            FileCategory::Other
+        }
+    }
+
+    fn is_stale(&self, node: &FileNode) -> bool {
+        node.last_used()
+            .is_some_and(|t| self.now_secs.saturating_sub(t) > STALE_DOWNLOAD_SECS)
+    }
+
+    /// 下载目录中的子目录：其下最新的修改时间（[`FileNode::latest_child_modified`]）也已陈旧时整体视为陈旧。
+    /// 目录自身的修改时间只反映条目增删，不能说明其中的文件久未使用，因此其下的时间未知时不判断
+    fn classify_stale_folder(&self, node: &FileNode, ctx: WalkContext) -> Option<FileCategory> {
+        (ctx.in_downloads && node.latest_child_modified.is_some() && self.is_stale(node))
+            .then_some(FileCategory::StaleDownloads)
+    }
+
+    fn walk(&mut self, node: &FileNode, ctx: WalkContext) {
+        if !node.is_dir {
+            let category = self.classify_file(node, ctx);
+            self.record(category, node, node.size, physical_size(node));
+            return;
+        }
+        // 命中的目录整体归类，不再向下细分，避免重复计数
+        if let Some(category) =
+            Self::classify_dir(node).or_else(|| self.classify_stale_folder(node, ctx))
+        {
+            let internal = self.internal_bytes_within(node);
+            self.internal_bytes = self.internal_bytes.saturating_add(internal);
+            let mut rest = node.size.saturating_sub(internal);
+            // 应用自身占用按逻辑大小扣除，宁可少估
+            let mut rest_physical = physical_size(node).saturating_sub(internal);
+            if splits_for_redownloadable(category, node) {
+                for child in node.children.iter().filter(|c| is_redownloadable(c)) {
+                    rest = rest.saturating_sub(child.size);
+                    rest_physical = rest_physical.saturating_sub(physical_size(child));
+                    self.record(
+                        FileCategory::Redownloadable,
+                        child,
+                        child.size,
+                        physical_size(child),
+                    );
+                }
+            }
+            self.record(category, node, rest, rest_physical);
+            return;
+        }
+        let ctx = WalkContext {
+            in_downloads: ctx.in_downloads || is_downloads(node),
+        };
+        let check_internal = self.internal.any_within(&node.path);
+        let mut children_size = 0u64;
+        for child in &node.children {
+            children_size = children_size.saturating_add(child.size);
+            if check_internal && self.internal.contains(&child.path) {
+                self.internal_bytes = self.internal_bytes.saturating_add(child.size);
+                continue;
+            }
+            self.walk(child, ctx);
+        }
+        // 深度/子节点数截断后，目录大小可能多于可见子节点之和，差额计入 Other
+        let unseen = node.size.saturating_sub(children_size);
+        if unseen > 0 {
+            let acc = self.acc.entry(FileCategory::Other).or_default();
+            acc.bytes = acc.bytes.saturating_add(unseen);
+        }
+    }
+}
+
+/// 按与 [`analyze_scan_at`] 相同的规则判断扫描树中 `path` 的类别：
+/// 位于已归类目录之下时取该目录的类别。`path` 不在扫描树中时返回 None
+pub(crate) fn classify_path(root: &FileNode, path: &Path, now_secs: u64) -> Option<FileCategory> {
+    let analyzer = Analyzer::new(now_secs, InternalPaths::default());
+    let mut ctx = WalkContext::default();
+    let mut dir_category = None;
+    // 整体归类的目录需要把可重新下载的直接子节点单独归类时为 true
+    let mut split_children = false;
+    let mut node = root;
+    if !path.starts_with(&node.path) {
+        return None;
+    }
+    loop {
+        if split_children && is_redownloadable(node) {
+            dir_category = Some(FileCategory::Redownloadable);
+        }
+        split_children = false;
+        if node.is_dir && dir_category.is_none() {
+            dir_category =
+                Analyzer::classify_dir(node).or_else(|| analyzer.classify_stale_folder(node, ctx));
+            split_children =
+                dir_category.is_some_and(|category| splits_for_redownloadable(category, node));
+        }
+        if Path::new(&node.path) == path {
+            let category = match dir_category {
+                Some(category) => category,
+                None if node.is_dir => FileCategory::Other,
+                None => analyzer.classify_file(node, ctx),
+            };
+            return Some(demote_system_files(category, node));
+        }
+        ctx.in_downloads = ctx.in_downloads || is_downloads(node);
+        node = node
+            .children
+            .iter()
+            .find(|child| path.starts_with(&child.path))?;
+    }
+}
+
+/// 以当前时间分析扫描结果
+pub fn analyze_scan(scan: &ScanResult) -> DiskAnalysis {
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    analyze_scan_at(scan, now_secs)
+}
+
+/// 以指定时间（Unix 秒）分析扫描结果，用于判断下载文件是否陈旧
+pub fn analyze_scan_at(scan: &ScanResult, now_secs: u64) -> DiskAnalysis {
+    // 与扫描一致：分析的本就是存储目录时不做排除
+    let internal = Some(InternalPaths::current())
+        .filter(|internal| !internal.contains(&scan.root.path))
+        .unwrap_or_default();
+    let mut analyzer = Analyzer::new(now_secs, internal);
+    analyzer.walk(&scan.root, WalkContext::default());
+    let total_size = scan.total_size.saturating_sub(analyzer.internal_bytes);
+
+    let mut findings = Vec::new();
+    let mut category_totals = Vec::new();
+    let mut reclaimable_low_risk = 0u64;
+    let mut reclaimable_medium_risk = 0u64;
+    let mut redownloadable = RedownloadableSummary {
+        total_bytes: analyzer.redownloadable.iter().map(|item| item.bytes).sum(),
+        items: analyzer.redownloadable,
+    };
+    redownloadable
+        .items
+        .sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    for (category, mut acc) in analyzer.acc {
+        category_totals.push(CategoryTotal {
+            category,
+            bytes: acc.bytes,
+            entry_count: acc.count,
+        });
+        let Some(risk) = category_risk(category) else {
+            continue;
+        };
+        if acc.bytes == 0 {
+            continue;
+        }
+        if risk == RiskLevel::Low {
+            reclaimable_low_risk = reclaimable_low_risk.saturating_add(acc.physical_bytes);
+        }
+        reclaimable_medium_risk = reclaimable_medium_risk.saturating_add(acc.physical_bytes);
+        acc.entries
+            .sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        findings.push(Finding {
+            category,
+            headline: category_headline(category, acc.bytes),
+            bytes: acc.bytes,
+            risk,
+            paths: acc
+                .entries
+                .into_iter()
+                .take(MAX_FINDING_PATHS)
+                .map(|(_, p)| p)
+                .collect(),
+        });
+    }
+    findings.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+
+    DiskAnalysis {
+        root_path: scan.root.path.clone(),
+        total_size,
+        findings,
+        category_totals,
+        reclaimable_low_risk,
+        reclaimable_medium_risk,
+        redownloadable,
+        virtual_disks: find_virtual_disks(&scan.root),
+        projection: None,
+        narrative: None,
+    }
+}
+
+/// 通过 LLM 为分析结果生成自然语言总结，写入 `analysis.narrative`
+pub async fn narrate_analysis<P: LlmProvider>(
+    analysis: &mut DiskAnalysis,
+    provider: &P,
+) -> Result<(), LlmError> {
+    let prompt = build_analysis_prompt(analysis);
+    let narrative = provider.complete(ANALYSIS_SYSTEM_PROMPT, &prompt).await?;
+    analysis.narrative = Some(narrative.trim().to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::score_risk;
+    use ai_disk_domain::FileAttributes;
+
+    const NOW: u64 = 1_700_000_000;
+    const DAY: u64 = 24 * 3600;
+
+    fn file(path: &str, size: u64, modified: Option<u64>) -> FileNode {
+        FileNode::new(path, size, false).with_modified(modified)
+    }
+
+    fn dir(path: &str, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, children.iter().map(|c| c.size).sum(), true).with_children(children)
+    }
+
+    /// 扫描器标记为下载目录的目录
+    fn downloads(path: &str, children: Vec<FileNode>) -> FileNode {
+        let mut node = dir(path, children);
+        node.known_folder = Some(KnownFolder::Downloads);
+        node
+    }
+
+    fn scan_of(root: FileNode) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    fn sample_scan() -> ScanResult {
+        let chrome_cache = dir(
+            "/home/u/.config/google/chrome/Default/Cache",
+            vec![file(
+                "/home/u/.config/google/chrome/Default/Cache/data_1",
+                4000,
+                Some(NOW),
+            )],
+        );
+        let chrome = dir(
+            "/home/u/.config/google/chrome/Default",
+            vec![
+                chrome_cache,
+                file(
+                    "/home/u/.config/google/chrome/Default/Bookmarks",
+                    10,
+                    Some(NOW),
+                ),
+            ],
+        );
+        let downloads = downloads(
+            "/home/u/Downloads",
+            vec![
+                file("/home/u/Downloads/old.zip", 2000, Some(NOW - 400 * DAY)),
+                file("/home/u/Downloads/new.zip", 500, Some(NOW - DAY)),
+                file("/home/u/Downloads/setup.exe", 700, Some(NOW - DAY)),
+            ],
+        );
+        let project = dir(
+            "/home/u/project",
+            vec![
+                dir(
+                    "/home/u/project/node_modules",
+                    vec![file("/home/u/project/node_modules/a.js", 300, Some(NOW))],
+                ),
+                file("/home/u/project/main.rs", 50, Some(NOW)),
+            ],
+        );
+        let tmp = dir("/home/u/tmp", vec![file("/home/u/tmp/x", 100, Some(NOW))]);
+        scan_of(dir("/home/u", vec![chrome, downloads, project, tmp]))
+    }
+
+    fn total_of(analysis: &DiskAnalysis, category: FileCategory) -> u64 {
+        analysis
+            .category_totals
+            .iter()
+            .find(|t| t.category == category)
+            .map(|t| t.bytes)
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_categorizes_known_content() {
+        let analysis = analyze_scan_at(&sample_scan(), NOW);
+        assert_eq!(total_of(&analysis, FileCategory::BrowserCache), 4000);
+        assert_eq!(total_of(&analysis, FileCategory::StaleDownloads), 2000);
+        assert_eq!(total_of(&analysis, FileCategory::Installer), 700);
+        assert_eq!(total_of(&analysis, FileCategory::PackageCache), 300);
+        assert_eq!(total_of(&analysis, FileCategory::Temp), 100);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 10 + 500 + 50);
+        let sum: u64 = analysis.category_totals.iter().map(|t| t.bytes).sum();
+        assert_eq!(sum, analysis.total_size);
+    }
+
+    #[test]
+    fn test_recent_access_keeps_download_fresh() {
+        let mut opened = file("/home/u/Downloads/manual.pdf", 300, Some(NOW - 400 * DAY));
+        opened.accessed = Some(NOW - DAY);
+        let mut untouched = file("/home/u/Downloads/old.zip", 900, Some(NOW - 400 * DAY));
+        untouched.accessed = Some(NOW - 300 * DAY);
+        // 访问时间停在过去（关闭了访问时间更新）时以较晚的修改时间为准
+        let mut rewritten = file("/home/u/Downloads/notes.txt", 50, Some(NOW - DAY));
+        rewritten.accessed = Some(NOW - 400 * DAY);
+        let downloads = downloads("/home/u/Downloads", vec![opened, untouched, rewritten]);
+        let analysis = analyze_scan_at(&scan_of(dir("/home/u", vec![downloads])), NOW);
+        assert_eq!(total_of(&analysis, FileCategory::StaleDownloads), 900);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 300 + 50);
+    }
+
+    #[test]
+    fn test_download_folders_use_bubbled_modified_time() {
+        // 目录自身的修改时间都很旧，深层文件近期修改过的目录不算陈旧
+        let mut active = dir(
+            "/home/u/Downloads/project",
+            vec![
+                file(
+                    "/home/u/Downloads/project/old.bin",
+                    400,
+                    Some(NOW - 400 * DAY),
+                ),
+                file("/home/u/Downloads/project/main.rs", 20, Some(NOW - DAY)),
+            ],
+        );
+        active.modified = Some(NOW - 400 * DAY);
+        active.latest_child_modified = Some(NOW - DAY);
+        let mut idle = dir(
+            "/home/u/Downloads/photos",
+            vec![
+                file("/home/u/Downloads/photos/a.jpg", 600, Some(NOW - 300 * DAY)),
+                file("/home/u/Downloads/photos/b.jpg", 700, Some(NOW - 200 * DAY)),
+            ],
+        );
+        idle.modified = Some(NOW - 400 * DAY);
+        idle.latest_child_modified = Some(NOW - 200 * DAY);
+        let root = dir(
+            "/home/u",
+            vec![downloads("/home/u/Downloads", vec![active, idle])],
+        );
+        let analysis = analyze_scan_at(&scan_of(root.clone()), NOW);
+        assert_eq!(
+            total_of(&analysis, FileCategory::StaleDownloads),
+            400 + 1300
+        );
+        let stale = analysis
+            .findings
+            .iter()
+            .find(|f| f.category == FileCategory::StaleDownloads)
+            .unwrap();
+        assert_eq!(
+            stale.paths,
+            vec![
+                "/home/u/Downloads/photos".to_string(),
+                "/home/u/Downloads/project/old.bin".to_string()
+            ]
+        );
+        assert_eq!(
+            classify_path(&root, Path::new("/home/u/Downloads/photos/a.jpg"), NOW),
+            Some(FileCategory::StaleDownloads)
+        );
+        assert_eq!(
+            classify_path(&root, Path::new("/home/u/Downloads/project"), NOW),
+            Some(FileCategory::Other)
+        );
+    }
+
+    #[test]
+    fn test_system_files_are_not_reclaimable() {
+        let mut pagefile = file("/home/u/pagefile.sys", 900, Some(NOW));
+        pagefile.attributes = Some(FileAttributes {
+            system: true,
+            hidden: true,
+            ..Default::default()
+        });
+        let mut installer = file("/home/u/Downloads/setup.exe", 700, Some(NOW));
+        installer.attributes = Some(FileAttributes {
+            hidden: true,
+            ..Default::default()
+        });
+        let mut locked = file("/home/u/Downloads/driver.msi", 300, Some(NOW));
+        locked.attributes = Some(FileAttributes::from_windows_bits(0x4));
+        let downloads = downloads("/home/u/Downloads", vec![installer, locked]);
+        let analysis = analyze_scan_at(&scan_of(dir("/home/u", vec![downloads, pagefile])), NOW);
+        // 隐藏不影响归类，系统文件归入 Other
+        assert_eq!(total_of(&analysis, FileCategory::Installer), 700);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 900 + 300);
+        assert_eq!(analysis.reclaimable_low_risk, 700);
+        assert_eq!(
+            score_risk(RiskLevel::Low, Some(FileAttributes::from_windows_bits(0x4))),
+            RiskLevel::High
+        );
+        assert_eq!(
+            score_risk(
+                RiskLevel::Medium,
+                Some(FileAttributes::from_windows_bits(0x3))
+            ),
+            RiskLevel::Medium
+        );
+    }
+
+    #[test]
+    fn test_findings_sorted_with_backing_paths() {
+        let analysis = analyze_scan_at(&sample_scan(), NOW);
+        assert_eq!(analysis.findings[0].category, FileCategory::BrowserCache);
+        assert_eq!(
+            analysis.findings[0].paths,
+            vec!["/home/u/.config/google/chrome/Default/Cache".to_string()]
+        );
+        let stale = analysis
+            .findings
+            .iter()
+            .find(|f| f.category == FileCategory::StaleDownloads)
+            .unwrap();
+        assert_eq!(stale.paths, vec!["/home/u/Downloads/old.zip".to_string()]);
+        assert!(analysis
+            .findings
+            .iter()
+            .all(|f| f.category != FileCategory::Other));
+    }
+
+    #[test]
+    fn test_reclaimable_estimates() {
+        let analysis = analyze_scan_at(&sample_scan(), NOW);
+        assert_eq!(analysis.reclaimable_low_risk, 4000 + 700 + 100);
+        assert_eq!(
+            analysis.reclaimable_medium_risk,
+            4000 + 700 + 100 + 2000 + 300
+        );
+    }
+
+    #[test]
+    fn test_reclaimable_uses_physical_size() {
+        let mut cache = dir(
+            "/home/u/.cache",
+            vec![file("/home/u/.cache/blob", 1000, Some(NOW))],
+        );
+        cache.physical_size = Some(250);
+        let mut installer = file("/home/u/Downloads/setup.exe", 800, Some(NOW));
+        installer.physical_size = Some(600);
+        let downloads = downloads("/home/u/Downloads", vec![installer]);
+        let analysis = analyze_scan_at(&scan_of(dir("/home/u", vec![cache, downloads])), NOW);
+        // 类别合计与结论仍为逻辑大小，可回收空间按实际占用估算
+        assert_eq!(total_of(&analysis, FileCategory::AppCache), 1000);
+        assert_eq!(total_of(&analysis, FileCategory::Installer), 800);
+        assert_eq!(analysis.reclaimable_low_risk, 250 + 600);
+        assert_eq!(analysis.reclaimable_medium_risk, 250 + 600);
+    }
+
+    #[test]
+    fn test_tagged_trash_is_low_risk() {
+        let mut trash = dir(
+            "/home/u/.local/share/Trash",
+            vec![dir(
+                "/home/u/.local/share/Trash/files",
+                vec![file(
+                    "/home/u/.local/share/Trash/files/cache",
+                    900,
+                    Some(NOW),
+                )],
+            )],
+        );
+        trash.category = Some(FileCategory::Trash);
+        let analysis = analyze_scan_at(&scan_of(dir("/home/u", vec![trash])), NOW);
+        // 整个废纸篓归为一类，其中名为 cache 的条目不再单独归类
+        assert_eq!(total_of(&analysis, FileCategory::Trash), 900);
+        assert_eq!(total_of(&analysis, FileCategory::AppCache), 0);
+        assert_eq!(analysis.reclaimable_low_risk, 900);
+        assert_eq!(analysis.findings[0].headline, "废纸篓中的文件共 900 B");
+    }
+
+    #[test]
+    fn test_redownloadable_content_separated_from_caches() {
+        let steam = dir(
+            "/home/u/.local/share/Steam/steamapps/common",
+            vec![dir(
+                "/home/u/.local/share/Steam/steamapps/common/Portal 2",
+                vec![file(
+                    "/home/u/.local/share/Steam/steamapps/common/Portal 2/pak01.vpk",
+                    5000,
+                    Some(NOW),
+                )],
+            )],
+        );
+        let cache = dir(
+            "/home/u/.cache",
+            vec![
+                dir(
+                    "/home/u/.cache/huggingface",
+                    vec![file(
+                        "/home/u/.cache/huggingface/model.bin",
+                        3000,
+                        Some(NOW),
+                    )],
+                ),
+                dir(
+                    "/home/u/.cache/pip",
+                    vec![file("/home/u/.cache/pip/wheel.whl", 200, Some(NOW))],
+                ),
+            ],
+        );
+        // 用户自己的虚拟磁盘不算
+        let vms = dir(
+            "/home/u/VMs",
+            vec![file("/home/u/VMs/ext4.vhdx", 800, Some(NOW))],
+        );
+        let scan = scan_of(dir("/home/u", vec![steam, cache, vms]));
+        let analysis = analyze_scan_at(&scan, NOW);
+
+        assert_eq!(
+            total_of(&analysis, FileCategory::Redownloadable),
+            5000 + 3000
+        );
+        // .cache 中除 huggingface 外的部分仍是应用缓存
+        assert_eq!(total_of(&analysis, FileCategory::AppCache), 200);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 800);
+        assert_eq!(analysis.reclaimable_medium_risk, 5000 + 3000 + 200);
+        assert_eq!(analysis.reclaimable_low_risk, 200);
+
+        let summary = &analysis.redownloadable;
+        assert_eq!(summary.total_bytes, 8000);
+        let apps: Vec<_> = summary.items.iter().map(|i| i.app.as_str()).collect();
+        assert_eq!(apps, ["Steam", "Hugging Face"]);
+        assert_eq!(
+            summary.items[0].path,
+            "/home/u/.local/share/Steam/steamapps/common/Portal 2"
+        );
+
+        // 计划汇总按同样的规则归类
+        let classify = |p: &str| classify_path(&scan.root, Path::new(p), NOW);
+        assert_eq!(
+            classify("/home/u/.cache/huggingface/model.bin"),
+            Some(FileCategory::Redownloadable)
+        );
+        assert_eq!(
+            classify("/home/u/.cache/pip/wheel.whl"),
+            Some(FileCategory::AppCache)
+        );
+        assert_eq!(
+            classify("/home/u/.local/share/Steam/steamapps/common/Portal 2"),
+            Some(FileCategory::Redownloadable)
+        );
+        assert_eq!(classify("/home/u/VMs/ext4.vhdx"), Some(FileCategory::Other));
+    }
+
+    #[test]
+    fn test_truncated_dir_remainder_counted_as_other() {
+        let mut root = dir("/data", vec![file("/data/a.bin", 100, None)]);
+        root.size = 1000;
+        let analysis = analyze_scan_at(&scan_of(root), NOW);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 1000);
+    }
+
+    #[test]
+    fn test_app_storage_excluded_from_findings() {
+        let storage = dir(
+            "/home/u/.disk-rookie",
+            vec![
+                dir(
+                    "/home/u/.disk-rookie/cache",
+                    vec![file("/home/u/.disk-rookie/cache/scan.bin", 5000, Some(NOW))],
+                ),
+                file("/home/u/.disk-rookie/app.log", 200, Some(NOW)),
+            ],
+        );
+        let export = file("/home/u/tmp/export.zip.tmp", 900, Some(NOW));
+        let tmp = dir(
+            "/home/u/tmp",
+            vec![file("/home/u/tmp/x", 100, Some(NOW)), export],
+        );
+        let scan = scan_of(dir("/home/u", vec![storage, tmp]));
+        let _root = ai_disk_common::register_in_flight(Path::new("/home/u/.disk-rookie"));
+        let _export = ai_disk_common::register_in_flight(Path::new("/home/u/tmp/export.zip.tmp"));
+
+        let analysis = analyze_scan_at(&scan, NOW);
+        assert_eq!(total_of(&analysis, FileCategory::AppCache), 0);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 0);
+        assert_eq!(total_of(&analysis, FileCategory::Temp), 100);
+        assert_eq!(analysis.total_size, 100);
+        assert!(analysis
+            .findings
+            .iter()
+            .flat_map(|f| &f.paths)
+            .all(|p| !p.contains(".disk-rookie")));
+    }
+
+    #[test]
+    fn test_known_folder_tags_replace_name_heuristics() {
+        // 下载目录被移到了 /data/下载；家目录下名为 Downloads 的只是普通目录
+        let relocated = downloads(
+            "/data/下载",
+            vec![file("/data/下载/setup.exe", 700, Some(NOW))],
+        );
+        let plain = dir(
+            "/home/u/Downloads",
+            vec![file("/home/u/Downloads/driver.msi", 300, Some(NOW))],
+        );
+        // 文档目录被移到了名为 tmp 的目录，不能按临时文件清理
+        let mut documents = dir(
+            "/data/tmp",
+            vec![file("/data/tmp/thesis.docx", 900, Some(NOW))],
+        );
+        documents.known_folder = Some(KnownFolder::Documents);
+        let root = dir("/", vec![dir("/data", vec![relocated, documents]), plain]);
+        let analysis = analyze_scan_at(&scan_of(root.clone()), NOW);
+        assert_eq!(total_of(&analysis, FileCategory::Installer), 700);
+        assert_eq!(total_of(&analysis, FileCategory::Temp), 0);
+        assert_eq!(total_of(&analysis, FileCategory::Other), 300 + 900);
+        assert_eq!(
+            classify_path(&root, Path::new("/data/下载/setup.exe"), NOW),
+            Some(FileCategory::Installer)
+        );
+        assert_eq!(
+            classify_path(&root, Path::new("/data/tmp"), NOW),
+            Some(FileCategory::Other)
+        );
+    }
+}
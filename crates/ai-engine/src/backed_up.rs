@@ -0,0 +1,273 @@
+//! 已备份文件的清理建议：本地扫描中的文件如果在云端已有同名同大小的副本，可以放心删除本地文件。
+//!
+//! 匹配刻意保守：大小必须完全相同，文件名只忽略大小写；同一个（文件名, 大小）在本地或云端
+//! 出现不止一次时无法确定对应关系，一律不匹配。空文件不参与匹配。
+
+use std::collections::HashMap;
+
+use ai_disk_domain::{
+    Action, BackedUpMatch, CloudFileEntry, FileNode, PlannedAction, RiskLevel, ScanResult,
+};
+
+use crate::validator::score_node_risk;
+
+/// 匹配键：小写文件名与字节数
+type MatchKey = (String, u64);
+
+fn match_key(name: &str, size: u64) -> MatchKey {
+    (name.to_lowercase(), size)
+}
+
+fn collect_files<'a>(node: &'a FileNode, out: &mut HashMap<MatchKey, Vec<&'a FileNode>>) {
+    if !node.is_dir {
+        // 系统文件不建议删除，不作为候选
+        if node.size > 0 && score_node_risk(RiskLevel::Low, node) == RiskLevel::Low {
+            out.entry(match_key(&node.name, node.size))
+                .or_default()
+                .push(node);
+        }
+        return;
+    }
+    for child in &node.children {
+        collect_files(child, out);
+    }
+}
+
+/// 在扫描结果中查找已上传到云端的文件，按本地路径排序
+pub fn find_backed_up_files(
+    scan: &ScanResult,
+    remote_index: &[CloudFileEntry],
+) -> Vec<BackedUpMatch> {
+    let mut remote: HashMap<MatchKey, Vec<&CloudFileEntry>> = HashMap::new();
+    for entry in remote_index.iter().filter(|e| !e.is_dir && e.size > 0) {
+        remote
+            .entry(match_key(&entry.name, entry.size))
+            .or_default()
+            .push(entry);
+    }
+    let mut local = HashMap::new();
+    collect_files(&scan.root, &mut local);
+
+    let mut matches: Vec<BackedUpMatch> = local
+        .into_iter()
+        .filter_map(
+            |(key, files)| match (files.as_slice(), remote.get(&key)?.as_slice()) {
+                ([file], [entry]) => Some(BackedUpMatch {
+                    local_path: file.path.clone(),
+                    size: file.size,
+                    remote: (*entry).clone(),
+                    verified: false,
+                }),
+                _ => None,
+            },
+        )
+        .collect();
+    matches.sort_by(|a, b| a.local_path.cmp(&b.local_path));
+    matches
+}
+
+/// 服务商在界面与理由中的名称
+fn provider_display_name(provider: &str) -> &str {
+    match provider {
+        "google_drive" => "Google Drive",
+        "dropbox" => "Dropbox",
+        "baidu_netdisk" => "百度网盘",
+        "aliyun_drive" => "阿里云盘",
+        other => other,
+    }
+}
+
+/// Unix 秒转为 UTC 日期 `YYYY-MM-DD`（Howard Hinnant 的 civil_from_days）
+fn format_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 为已备份的文件生成低风险的删除建议；上传日期取云端文件的修改时间
+pub fn plan_backed_up_cleanup(matches: &[BackedUpMatch]) -> Vec<PlannedAction> {
+    matches
+        .iter()
+        .map(|m| {
+            let provider = provider_display_name(&m.remote.provider);
+            let mut reason = match m.remote.modified {
+                Some(secs) => format!("已于 {} 上传到 {}", format_date(secs), provider),
+                None => format!("已上传到 {}", provider),
+            };
+            if m.verified {
+                reason.push_str("，校验和一致");
+            }
+            PlannedAction {
+                action: Action::Delete {
+                    path: m.local_path.clone(),
+                },
+                bytes: m.size,
+                risk: RiskLevel::Low,
+                reason,
+                scanned: None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::FileAttributes;
+
+    fn node(path: &str, size: u64, is_dir: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, is_dir).with_children(children)
+    }
+
+    fn file(path: &str, size: u64) -> FileNode {
+        node(path, size, false, vec![])
+    }
+
+    fn remote(name: &str, size: u64) -> CloudFileEntry {
+        CloudFileEntry {
+            id: format!("id-{}", name),
+            name: name.to_string(),
+            size,
+            // 2024-03-05T12:00:00Z
+            modified: Some(1_709_640_000),
+            is_dir: false,
+            provider: "google_drive".to_string(),
+        }
+    }
+
+    fn scan_of(files: Vec<FileNode>) -> ScanResult {
+        let root = node("/home/u", files.iter().map(|f| f.size).sum(), true, files);
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    fn matched_paths(scan: &ScanResult, index: &[CloudFileEntry]) -> Vec<String> {
+        find_backed_up_files(scan, index)
+            .into_iter()
+            .map(|m| m.local_path)
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_unique_name_and_size_ignoring_case() {
+        let scan = scan_of(vec![
+            node(
+                "/home/u/Photos",
+                3000,
+                true,
+                vec![file("/home/u/Photos/IMG_0001.JPG", 3000)],
+            ),
+            file("/home/u/report.pdf", 500),
+        ]);
+        let index = [remote("img_0001.jpg", 3000), remote("other.pdf", 500)];
+        let matches = find_backed_up_files(&scan, &index);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].local_path, "/home/u/Photos/IMG_0001.JPG");
+        assert_eq!(matches[0].remote.id, "id-img_0001.jpg");
+        assert!(!matches[0].verified);
+
+        let actions = plan_backed_up_cleanup(&matches);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].risk, RiskLevel::Low);
+        assert_eq!(actions[0].bytes, 3000);
+        assert_eq!(actions[0].reason, "已于 2024-03-05 上传到 Google Drive");
+    }
+
+    #[test]
+    fn test_size_mismatch_never_matches() {
+        let scan = scan_of(vec![
+            file("/home/u/a.mov", 1000),
+            file("/home/u/b.mov", 1000),
+        ]);
+        let index = [remote("a.mov", 999), remote("b.mov", 1001)];
+        assert!(matched_paths(&scan, &index).is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_duplicates_never_match() {
+        // 本地两个同名同大小的文件：不知道哪一个是被上传的那份
+        let scan = scan_of(vec![
+            node(
+                "/home/u/a",
+                100,
+                true,
+                vec![file("/home/u/a/notes.txt", 100)],
+            ),
+            node(
+                "/home/u/b",
+                100,
+                true,
+                vec![file("/home/u/b/Notes.TXT", 100)],
+            ),
+            file("/home/u/song.mp3", 200),
+            file("/home/u/empty.log", 0),
+        ]);
+        // 云端两个同名同大小的 song.mp3 同样无法对应
+        let index = [
+            remote("notes.txt", 100),
+            remote("song.mp3", 200),
+            remote("song.mp3", 200),
+            remote("empty.log", 0),
+        ];
+        assert!(matched_paths(&scan, &index).is_empty());
+
+        // 去掉云端重复项后 song.mp3 可以匹配，本地重复的 notes.txt 仍不匹配
+        assert_eq!(
+            matched_paths(&scan, &index[..2]),
+            vec!["/home/u/song.mp3".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_folders_and_system_files_are_excluded() {
+        let mut system = file("/home/u/desktop.ini", 80);
+        system.attributes = Some(FileAttributes {
+            system: true,
+            ..Default::default()
+        });
+        let scan = scan_of(vec![system, file("/home/u/b.zip", 10)]);
+        let mut folder = remote("b.zip", 10);
+        folder.is_dir = true;
+        assert!(matched_paths(&scan, &[remote("desktop.ini", 80), folder]).is_empty());
+    }
+
+    #[test]
+    fn test_reason_without_date_and_verified() {
+        let mut m = BackedUpMatch {
+            local_path: "/home/u/x.iso".to_string(),
+            size: 7,
+            remote: remote("x.iso", 7),
+            verified: true,
+        };
+        m.remote.provider = "baidu_netdisk".to_string();
+        m.remote.modified = None;
+        let actions = plan_backed_up_cleanup(&[m]);
+        assert_eq!(actions[0].reason, "已上传到 百度网盘，校验和一致");
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+    }
+}
@@ -0,0 +1,383 @@
+//! 计划的预计释放量：不采用规划器（模型）给出的数字，而是按扫描结果重新计算。
+//! 直接累加每个动作的大小会重复计算——同时删除一个目录和其中的文件时，文件被算了两次。
+//!
+//! 每个动作的字节数取自扫描结果的 [`SizeOracle`]（扫描中没有的路径按 0 计），再按语义折算：
+//! - 删除、清空废纸篓：释放路径的递归大小，其下的其他动作不再计入；
+//! - 移动：同一卷内只是换了位置，释放 0；跨卷时源卷释放递归大小，其下的其他动作不再计入；
+//! - 转存：上传后本地文件移入回收站，释放文件大小；
+//! - 压缩虚拟磁盘：能缩小多少无法从扫描得知，按 0 计。
+//!
+//! 剩余空间预测（[`project_free_space`]）还考虑每个动作的执行方式：跳过的动作不释放空间；
+//! 移入回收站的删除仍占用同一卷上的空间，清空回收站后才释放，单独计为延后释放的部分。
+
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::ExecutionMode;
+use ai_disk_domain::{Action, CleanupPlan, FreeSpaceProjection, ScanResult};
+use ai_disk_scanner::{normalize_node_path, same_volume, SizeOracle};
+
+/// 按扫描结果重新计算计划的预计释放量。无法判断移动的两端是否在同一卷时按同一卷处理（释放 0）
+pub fn recompute_estimated_space(plan: &CleanupPlan, scan: &ScanResult) -> u64 {
+    let sizes = SizeOracle::from_tree(&scan.root);
+    estimated_space_with(&plan.actions, &sizes, |from, to| {
+        same_volume(from, to).unwrap_or(true)
+    })
+}
+
+/// 以 `modes[i]` 方式执行 `actions[i]` 后扫描所在卷的剩余空间预测。`modes` 应与 `actions`
+/// 一一对应（通常由执行策略解析得到），缺少的按跳过处理。转存与跨卷移动在校验通过后立即释放；
+/// 以回收站方式执行的删除计为延后释放
+pub fn project_free_space(
+    scan: &ScanResult,
+    actions: &[Action],
+    modes: &[ExecutionMode],
+) -> FreeSpaceProjection {
+    let sizes = SizeOracle::from_tree(&scan.root);
+    let (immediate, deferred) = projected_bytes_with(actions, modes, &sizes, |from, to| {
+        same_volume(from, to).unwrap_or(true)
+    });
+    FreeSpaceProjection::new(scan.volume_free_bytes, immediate, deferred)
+}
+
+/// 单个动作的效果
+struct Effect {
+    /// 规范化后的源路径
+    path: PathBuf,
+    bytes: u64,
+    /// 是否连同其下所有路径一并释放
+    subsumes: bool,
+    /// 是否要等清空回收站后才释放
+    deferred: bool,
+}
+
+fn effect(
+    action: &Action,
+    sizes: &SizeOracle,
+    same_volume: &impl Fn(&Path, &Path) -> bool,
+) -> Effect {
+    let path = PathBuf::from(normalize_node_path(action.source_path()));
+    let size = sizes.recursive_size(&path).unwrap_or(0);
+    let (bytes, subsumes) = match action {
+        Action::Delete { .. } | Action::EmptyTrash { .. } => (size, true),
+        Action::Move { from, to } if same_volume(Path::new(from), Path::new(to)) => (0, false),
+        Action::Move { .. } => (size, true),
+        Action::Offload { .. } => (size, false),
+        Action::CompactVhd { .. } => (0, false),
+    };
+    Effect {
+        path,
+        bytes,
+        subsumes,
+        deferred: false,
+    }
+}
+
+/// 以给定的递归大小与卷判断计算预计释放量
+pub(crate) fn estimated_space_with(
+    actions: &[Action],
+    sizes: &SizeOracle,
+    same_volume: impl Fn(&Path, &Path) -> bool,
+) -> u64 {
+    let effects = actions
+        .iter()
+        .map(|action| effect(action, sizes, &same_volume))
+        .collect();
+    let (immediate, deferred) = total_freed(effects);
+    immediate + deferred
+}
+
+/// 以给定的递归大小与卷判断计算 (立即释放, 延后释放) 的字节数
+pub(crate) fn projected_bytes_with(
+    actions: &[Action],
+    modes: &[ExecutionMode],
+    sizes: &SizeOracle,
+    same_volume: impl Fn(&Path, &Path) -> bool,
+) -> (u64, u64) {
+    let effects = actions
+        .iter()
+        .zip(modes)
+        .filter(|(_, mode)| **mode != ExecutionMode::Skip)
+        .map(|(action, mode)| Effect {
+            deferred: *mode == ExecutionMode::Trash && matches!(action, Action::Delete { .. }),
+            ..effect(action, sizes, &same_volume)
+        })
+        .collect();
+    total_freed(effects)
+}
+
+/// 去掉重复计算后的 (立即释放, 延后释放) 的字节数
+fn total_freed(mut effects: Vec<Effect>) -> (u64, u64) {
+    // 按路径分量排序，祖先紧排在其所有后代之前；同一路径上释放整棵子树的动作在前，
+    // 其次是立即释放的，再次是字节数大的
+    effects.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then(b.subsumes.cmp(&a.subsumes))
+            .then(a.deferred.cmp(&b.deferred))
+            .then(b.bytes.cmp(&a.bytes))
+    });
+    let (mut immediate, mut deferred) = (0u64, 0u64);
+    // 当前路径的各级祖先中释放整棵子树的动作路径
+    let mut ancestors: Vec<&Path> = Vec::new();
+    let mut last_counted: Option<&Path> = None;
+    for effect in &effects {
+        while ancestors
+            .last()
+            .is_some_and(|ancestor| !effect.path.starts_with(ancestor))
+        {
+            ancestors.pop();
+        }
+        if !ancestors.is_empty() || last_counted == Some(effect.path.as_path()) {
+            continue;
+        }
+        let total = if effect.deferred {
+            &mut deferred
+        } else {
+            &mut immediate
+        };
+        *total = total.saturating_add(effect.bytes);
+        last_counted = Some(&effect.path);
+        if effect.subsumes {
+            ancestors.push(&effect.path);
+        }
+    }
+    (immediate, deferred)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::FileNode;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        let size = if children.is_empty() {
+            size
+        } else {
+            children.iter().map(|c| c.size).sum()
+        };
+        FileNode::new(path, size, !children.is_empty()).with_children(children)
+    }
+
+    fn delete(path: &str) -> Action {
+        Action::Delete {
+            path: path.to_string(),
+        }
+    }
+
+    fn move_to(from: &str, to: &str) -> Action {
+        Action::Move {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    fn offload(path: &str) -> Action {
+        Action::Offload {
+            path: path.to_string(),
+            provider: "dropbox".to_string(),
+            account_id: "a".to_string(),
+            target_path: "/backup".to_string(),
+        }
+    }
+
+    /// 以 `/other` 开头的路径在另一个卷上，其余路径在同一卷上
+    fn fake_same_volume(from: &Path, to: &Path) -> bool {
+        from.starts_with("/other") == to.starts_with("/other")
+    }
+
+    fn tree() -> FileNode {
+        node(
+            "/r",
+            0,
+            vec![
+                node(
+                    "/r/cache",
+                    0,
+                    vec![
+                        node("/r/cache/a.bin", 300, vec![]),
+                        node("/r/cache/b.bin", 700, vec![]),
+                    ],
+                ),
+                node("/r/cache-old", 20, vec![]),
+                node("/r/video.mkv", 5_000, vec![]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_nested_actions_are_counted_once() {
+        let sizes = SizeOracle::from_tree(&tree());
+        let estimate = |actions: &[Action]| estimated_space_with(actions, &sizes, fake_same_volume);
+        // 目录与其中的文件只按目录计；/r/cache-old 不在 /r/cache 之下
+        let actions = [
+            delete("/r/cache/a.bin"),
+            delete("/r/cache"),
+            delete("/r/cache-old"),
+            delete("/r/cache"),
+        ];
+        assert_eq!(estimate(&actions), 1_020);
+        // 同一卷内移动不释放空间，其下的删除照常计入；跨卷移动释放源卷上的大小
+        assert_eq!(
+            estimate(&[
+                move_to("/r/cache", "/r/archive/cache"),
+                delete("/r/cache/a.bin")
+            ]),
+            300
+        );
+        assert_eq!(
+            estimate(&[
+                move_to("/r/cache", "/other/cache"),
+                offload("/r/cache/b.bin")
+            ]),
+            1_000
+        );
+        // 转存释放文件大小；压缩虚拟磁盘与扫描中没有的路径按 0 计
+        let compact = Action::CompactVhd {
+            path: "/r/video.mkv".to_string(),
+        };
+        assert_eq!(
+            estimate(&[offload("/r/video.mkv"), compact, delete("/r/missing")]),
+            5_000
+        );
+    }
+
+    #[test]
+    fn test_projection_splits_immediate_and_deferred() {
+        use ExecutionMode::{Permanent, Skip, Trash};
+        let sizes = SizeOracle::from_tree(&tree());
+        let project = |actions: &[Action], modes: &[ExecutionMode]| {
+            projected_bytes_with(actions, modes, &sizes, fake_same_volume)
+        };
+        // 移入回收站的删除延后释放；永久删除、转存与跨卷移动立即释放；跳过的不计
+        let actions = [
+            delete("/r/cache/a.bin"),
+            delete("/r/cache-old"),
+            offload("/r/video.mkv"),
+            delete("/r/cache/b.bin"),
+        ];
+        assert_eq!(
+            project(&actions, &[Trash, Permanent, Trash, Skip]),
+            (5_020, 300)
+        );
+        // 同一卷内移动不释放；跨卷移动立即释放，其下移入回收站的删除不再重复计入
+        let actions = [
+            move_to("/r/video.mkv", "/r/archive/video.mkv"),
+            move_to("/r/cache", "/other/cache"),
+            delete("/r/cache/a.bin"),
+            delete("/r/cache-old"),
+        ];
+        assert_eq!(
+            project(&actions, &[Permanent, Trash, Trash, Trash]),
+            (1_000, 20)
+        );
+        // 同一路径既移入回收站又永久删除时按立即释放计；缺少执行方式的动作按跳过处理
+        let actions = [
+            delete("/r/cache"),
+            delete("/r/cache"),
+            delete("/r/video.mkv"),
+        ];
+        assert_eq!(project(&actions, &[Trash, Permanent]), (1_000, 0));
+        assert_eq!(project(&actions, &[Trash, Trash, Trash]), (0, 6_000));
+
+        let projection = FreeSpaceProjection::new(Some(41_000), 5_020, 300);
+        assert_eq!(projection.projected_free_bytes, Some(46_020));
+        assert_eq!(projection.projected_free_after_trash_bytes, Some(46_320));
+        assert_eq!(
+            FreeSpaceProjection::new(None, 5_020, 300).projected_free_bytes,
+            None
+        );
+    }
+
+    /// xorshift64*，避免为测试引入随机数依赖
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    /// 随机树：返回树、所有路径与所有文件（路径, 大小）
+    fn random_tree(rng: &mut Rng) -> (FileNode, Vec<String>, Vec<(String, u64)>) {
+        fn build(
+            rng: &mut Rng,
+            path: &str,
+            depth: usize,
+            paths: &mut Vec<String>,
+            files: &mut Vec<(String, u64)>,
+        ) -> FileNode {
+            paths.push(path.to_string());
+            if depth == 0 || rng.below(3) == 0 {
+                let size = rng.next() % 10_000;
+                files.push((path.to_string(), size));
+                return node(path, size, vec![]);
+            }
+            let children = (0..1 + rng.below(4))
+                .map(|i| build(rng, &format!("{path}/n{i}"), depth - 1, paths, files))
+                .collect();
+            node(path, 0, children)
+        }
+        let (mut paths, mut files) = (Vec::new(), Vec::new());
+        let children = (0..1 + rng.below(4))
+            .map(|i| build(rng, &format!("/r/n{i}"), 3, &mut paths, &mut files))
+            .collect();
+        (node("/r", 0, children), paths, files)
+    }
+
+    /// 参照实现：逐个文件判断是否被释放——位于某个删除或跨卷移动的路径之下，或者被转存
+    fn reference(actions: &[Action], files: &[(String, u64)]) -> u64 {
+        files
+            .iter()
+            .filter(|(file, _)| {
+                actions.iter().any(|action| {
+                    let under = Path::new(file).starts_with(action.source_path());
+                    match action {
+                        Action::Delete { .. } | Action::EmptyTrash { .. } => under,
+                        Action::Move { from, to } => {
+                            under && !fake_same_volume(Path::new(from), Path::new(to))
+                        }
+                        Action::Offload { path, .. } => path == file,
+                        Action::CompactVhd { .. } => false,
+                    }
+                })
+            })
+            .map(|(_, size)| size)
+            .sum()
+    }
+
+    #[test]
+    fn test_random_nested_actions_match_reference() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..500 {
+            let (root, paths, files) = random_tree(&mut rng);
+            let sizes = SizeOracle::from_tree(&root);
+            let actions: Vec<Action> = (0..1 + rng.below(8))
+                .map(|_| {
+                    let path = &paths[rng.below(paths.len())];
+                    match rng.below(5) {
+                        0 => move_to(path, &format!("/r/moved{}", rng.below(100))),
+                        1 => move_to(path, &format!("/other{}", rng.below(100))),
+                        2 => {
+                            let (file, _) = &files[rng.below(files.len())];
+                            offload(file)
+                        }
+                        3 => Action::EmptyTrash { path: path.clone() },
+                        _ => delete(path),
+                    }
+                })
+                .collect();
+            assert_eq!(
+                estimated_space_with(&actions, &sizes, fake_same_volume),
+                reference(&actions, &files),
+                "{actions:?}"
+            );
+        }
+    }
+}
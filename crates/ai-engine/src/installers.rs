@@ -0,0 +1,512 @@
+//! 下载目录中的安装包检测：按「去掉版本号与架构后的产品名」把同一软件的多个版本归为一组，
+//! 每组只保留最新的一个，其余生成删除建议。
+//!
+//! 归组只针对安装包类型的文件（扩展名 + 大小阈值），普通文件即使名字只差年份也不会归组。
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use ai_disk_domain::{Action, FileAttributes, FileNode, PlannedAction, RiskLevel, ScanResult};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{is_downloads, INSTALLER_EXTENSIONS};
+use crate::validator::{score_risk, target_state};
+
+/// 小于该大小的文件不视为安装包（排除同名的小脚本、快捷方式等）
+pub const INSTALLER_MIN_BYTES: u64 = 1024 * 1024;
+
+/// 文件名中表示架构、平台的片段，归一化时去掉（已统一为小写）
+const ARCH_TOKENS: &[&str] = &[
+    "x64",
+    "x86",
+    "amd64",
+    "arm64",
+    "aarch64",
+    "i386",
+    "i686",
+    "win32",
+    "win64",
+    "win",
+    "windows",
+    "macos",
+    "mac",
+    "osx",
+    "darwin",
+    "linux",
+    "universal",
+    "bit",
+    "32bit",
+    "64bit",
+];
+
+/// 预发布标记，归一化时去掉
+const PRERELEASE_TOKENS: &[&str] = &["alpha", "beta", "rc", "preview", "nightly"];
+
+/// 常见软件的展示名，键为归一化后的产品名
+const KNOWN_PRODUCTS: &[(&str, &str)] = &[
+    ("node", "Node.js"),
+    ("python", "Python"),
+    ("git", "Git"),
+    ("vscodeusersetup", "Visual Studio Code"),
+    ("vscodesetup", "Visual Studio Code"),
+    ("chromesetup", "Google Chrome"),
+    ("googlechrome", "Google Chrome"),
+    ("firefox setup", "Firefox"),
+    ("docker desktop installer", "Docker Desktop"),
+    ("7z", "7-Zip"),
+    ("vlc", "VLC"),
+];
+
+/// 下载目录中的一个安装包
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallerFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// Unix 时间戳（秒）
+    pub modified: Option<u64>,
+    /// 从文件名中识别出的版本号
+    pub version: Option<String>,
+    pub attributes: Option<FileAttributes>,
+}
+
+/// 同一软件的多个安装包
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallerGroup {
+    /// 归一化后的产品名与扩展名，例如 `node.msi`
+    pub key: String,
+    /// 展示名，例如「Node.js」
+    pub product: String,
+    /// 从新到旧排列，第一个为建议保留的版本
+    pub files: Vec<InstallerFile>,
+}
+
+/// 归一化结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedInstaller {
+    pub key: String,
+    pub product: String,
+    pub version: Option<String>,
+}
+
+fn extension_of(file_name: &str) -> Option<(&str, String)> {
+    let (stem, ext) = file_name.rsplit_once('.')?;
+    let ext = ext.to_lowercase();
+    INSTALLER_EXTENSIONS
+        .contains(&ext.as_str())
+        .then_some((stem, ext))
+}
+
+/// 浏览器重复下载时追加的 ` (1)`、`(2)` 后缀
+fn strip_duplicate_suffix(stem: &str) -> &str {
+    let Some(rest) = stem.strip_suffix(')') else {
+        return stem;
+    };
+    match rest.rsplit_once('(') {
+        Some((base, n)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => {
+            base.trim_end()
+        }
+        _ => stem,
+    }
+}
+
+/// `v18.17.0`、`2.43.0`、`121` 这样的版本号片段
+fn as_version(token: &str) -> Option<&str> {
+    let digits = token
+        .strip_prefix('v')
+        .or_else(|| token.strip_prefix('V'))
+        .unwrap_or(token);
+    let valid = !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && !digits.starts_with('.')
+        && !digits.ends_with('.')
+        && digits.chars().next().is_some_and(|c| c.is_ascii_digit());
+    valid.then_some(digits)
+}
+
+fn is_prerelease(token: &str) -> bool {
+    PRERELEASE_TOKENS.iter().any(|p| {
+        token
+            .strip_prefix(p)
+            .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// `7z2301` 这样把版本号直接接在名字后面的片段：拆成名字与版本
+fn split_glued_version(token: &str) -> Option<(&str, &str)> {
+    let name_len = token.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (name, digits) = token.split_at(name_len);
+    let has_letter = name.chars().any(|c| c.is_ascii_alphabetic());
+    (has_letter && digits.len() >= 3).then_some((name, digits))
+}
+
+/// 把安装包文件名归一化为产品名。非安装包扩展名或去掉版本后没有剩余名字时返回 None
+pub fn normalize_installer_name(file_name: &str) -> Option<NormalizedInstaller> {
+    let (stem, ext) = extension_of(file_name)?;
+    let stem = strip_duplicate_suffix(stem);
+
+    let mut kept: Vec<&str> = Vec::new();
+    let mut versions: Vec<&str> = Vec::new();
+    let pieces = stem
+        .split(|c: char| c.is_whitespace() || "-_()[]+,~".contains(c))
+        .filter(|p| !p.is_empty());
+    for piece in pieces {
+        if let Some(version) = as_version(piece) {
+            versions.push(version);
+            continue;
+        }
+        for token in piece.split('.').filter(|t| !t.is_empty()) {
+            let lower = token.to_lowercase();
+            if let Some(version) = as_version(token) {
+                versions.push(version);
+            } else if ARCH_TOKENS.contains(&lower.as_str()) || is_prerelease(&lower) {
+                continue;
+            } else if let Some((name, version)) = split_glued_version(token) {
+                kept.push(name);
+                versions.push(version);
+            } else {
+                kept.push(token);
+            }
+        }
+    }
+    if kept.is_empty() {
+        return None;
+    }
+
+    let name = kept
+        .iter()
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let product = KNOWN_PRODUCTS
+        .iter()
+        .find(|(k, _)| *k == name)
+        .map(|(_, display)| display.to_string())
+        .unwrap_or_else(|| kept.join(" "));
+    // 优先取带点的完整版本号，`64-bit` 中的 64 之类只在没有其他候选时使用
+    let version = versions
+        .iter()
+        .find(|v| v.contains('.'))
+        .or_else(|| versions.first())
+        .map(|v| v.to_string());
+    Some(NormalizedInstaller {
+        key: format!("{}.{}", name, ext),
+        product,
+        version,
+    })
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// 收集扫描结果中位于下载目录（扫描器标记的已知目录）下、达到大小阈值的安装包
+pub fn find_installers(scan: &ScanResult) -> Vec<InstallerFile> {
+    fn walk(node: &FileNode, in_downloads: bool, out: &mut Vec<InstallerFile>) {
+        if node.is_dir {
+            let in_downloads = in_downloads || is_downloads(node);
+            for child in &node.children {
+                walk(child, in_downloads, out);
+            }
+            return;
+        }
+        if !in_downloads || node.size < INSTALLER_MIN_BYTES {
+            return;
+        }
+        if let Some(normalized) = normalize_installer_name(&node.name) {
+            out.push(InstallerFile {
+                path: node.path.clone(),
+                name: node.name.clone(),
+                size: node.size,
+                modified: node.modified,
+                version: normalized.version,
+                attributes: node.attributes,
+            });
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(&scan.root, false, &mut out);
+    out
+}
+
+/// 组内从新到旧排序：所有文件都有版本号时按版本号，否则按修改时间
+fn sort_newest_first(files: &mut [InstallerFile]) {
+    let all_versioned = files.iter().all(|f| f.version.is_some());
+    files.sort_by(|a, b| {
+        let by_version = if all_versioned {
+            let va = parse_version(a.version.as_deref().unwrap_or_default());
+            let vb = parse_version(b.version.as_deref().unwrap_or_default());
+            vb.cmp(&va)
+        } else {
+            Ordering::Equal
+        };
+        by_version
+            .then_with(|| b.modified.cmp(&a.modified))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// 按产品名归组，只返回包含多个安装包的组
+pub fn group_installers(files: Vec<InstallerFile>) -> Vec<InstallerGroup> {
+    let mut groups: BTreeMap<String, (String, Vec<InstallerFile>)> = BTreeMap::new();
+    for file in files {
+        let Some(normalized) = normalize_installer_name(&file.name) else {
+            continue;
+        };
+        groups
+            .entry(normalized.key)
+            .or_insert_with(|| (normalized.product, Vec::new()))
+            .1
+            .push(file);
+    }
+    groups
+        .into_iter()
+        .filter(|(_, (_, files))| files.len() > 1)
+        .map(|(key, (product, mut files))| {
+            sort_newest_first(&mut files);
+            InstallerGroup {
+                key,
+                product,
+                files,
+            }
+        })
+        .collect()
+}
+
+/// 为每组中除最新版本外的安装包生成删除建议，并记录安装包在这次扫描中的状态
+pub fn plan_installer_cleanup(scan: &ScanResult) -> Vec<PlannedAction> {
+    let mut actions = Vec::new();
+    for group in group_installers(find_installers(scan)) {
+        let Some((newest, older)) = group.files.split_first() else {
+            continue;
+        };
+        for file in older {
+            let reason = match (&file.version, &newest.version) {
+                (Some(old), Some(new)) if old != new => format!(
+                    "{} 安装包的旧版本 {}（保留最新版 {}）",
+                    group.product, old, new
+                ),
+                _ => format!(
+                    "{} 安装包的重复下载（保留最近下载的 {}）",
+                    group.product, newest.name
+                ),
+            };
+            actions.push(PlannedAction {
+                action: Action::Delete {
+                    path: file.path.clone(),
+                },
+                bytes: file.size,
+                risk: score_risk(RiskLevel::Low, file.attributes),
+                reason,
+                scanned: target_state(scan, &file.path),
+            });
+        }
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::KnownFolder;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn key(name: &str) -> Option<String> {
+        normalize_installer_name(name).map(|n| n.key)
+    }
+
+    fn version(name: &str) -> Option<String> {
+        normalize_installer_name(name).and_then(|n| n.version)
+    }
+
+    #[test]
+    fn test_normalization_groups_versions_and_arches() {
+        let same = [
+            ("node-v18.17.0-x64.msi", "node-v20.5.1-x64.msi"),
+            ("node-v20.5.1-x64.msi", "node-v20.5.1-arm64.msi"),
+            (
+                "VSCodeUserSetup-x64-1.84.2.exe",
+                "VSCodeUserSetup-x64-1.85.1.exe",
+            ),
+            ("Git-2.42.0-64-bit.exe", "Git-2.43.0-64-bit.exe"),
+            ("python-3.11.5-amd64.exe", "python-3.12.1-amd64.exe"),
+            ("ChromeSetup.exe", "ChromeSetup (1).exe"),
+            ("Firefox Setup 120.0.1.exe", "Firefox Setup 121.0.exe"),
+            ("7z2301-x64.exe", "7z2408-x64.exe"),
+            ("vlc-3.0.18-win64.exe", "vlc-3.0.20-win32.exe"),
+            (
+                "ubuntu-22.04.3-desktop-amd64.iso",
+                "ubuntu-23.10-desktop-amd64.iso",
+            ),
+            ("Slack-4.35.131-macOS.dmg", "Slack-4.36.140-macOS.dmg"),
+            ("jdk-21_windows-x64_bin.exe", "jdk-22_windows-x64_bin.exe"),
+            ("app-2.0.0-rc1.exe", "app-2.0.0.exe"),
+        ];
+        for (a, b) in same {
+            assert!(key(a).is_some(), "{} 应识别为安装包", a);
+            assert_eq!(key(a), key(b), "{} 与 {} 应归为一组", a, b);
+        }
+        assert_eq!(key("node-v18.17.0-x64.msi").unwrap(), "node.msi");
+        assert_eq!(
+            normalize_installer_name("node-v20.5.1-x64.msi")
+                .unwrap()
+                .product,
+            "Node.js"
+        );
+    }
+
+    #[test]
+    fn test_normalization_keeps_distinct_products_apart() {
+        let different = [
+            ("node-v20.5.1-x64.msi", "python-3.12.1-amd64.exe"),
+            ("Slack-4.36.140-macOS.dmg", "SlackSetup.exe"),
+            ("node-v20.5.1-x64.msi", "node-v20.5.1-x64.pkg"),
+            ("Docker Desktop Installer.exe", "Docker Desktop.dmg"),
+            (
+                "OpenJDK17U-jdk_x64_windows_hotspot_17.0.9_9.msi",
+                "OpenJDK21U-jdk_x64_windows_hotspot_21.0.1_12.msi",
+            ),
+        ];
+        for (a, b) in different {
+            assert_ne!(key(a), key(b), "{} 与 {} 不应归为一组", a, b);
+        }
+    }
+
+    #[test]
+    fn test_non_installers_are_never_grouped() {
+        for name in [
+            "photo-2021.zip",
+            "photo-2022.zip",
+            "report-v1.pdf",
+            "archive.tar.gz",
+            "notes",
+        ] {
+            assert_eq!(key(name), None, "{} 不是安装包", name);
+        }
+        // 只剩版本号、没有产品名时不归组
+        assert_eq!(key("1.2.3.exe"), None);
+        assert_eq!(key("x64.msi"), None);
+    }
+
+    #[test]
+    fn test_version_extraction() {
+        assert_eq!(version("node-v18.17.0-x64.msi").as_deref(), Some("18.17.0"));
+        assert_eq!(version("Git-2.43.0-64-bit.exe").as_deref(), Some("2.43.0"));
+        assert_eq!(version("7z2301-x64.exe").as_deref(), Some("2301"));
+        assert_eq!(version("jdk-21_windows-x64_bin.exe").as_deref(), Some("21"));
+        assert_eq!(version("ChromeSetup (1).exe"), None);
+        assert!(parse_version("20.5.1") > parse_version("18.17.0"));
+        assert!(parse_version("3.0.20") > parse_version("3.0.18"));
+    }
+
+    fn file(path: &str, size: u64, modified: Option<u64>) -> FileNode {
+        FileNode::new(path, size, false).with_modified(modified)
+    }
+
+    fn dir(path: &str, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, children.iter().map(|c| c.size).sum(), true).with_children(children)
+    }
+
+    /// 扫描器标记为下载目录的目录
+    fn downloads(path: &str, children: Vec<FileNode>) -> FileNode {
+        let mut node = dir(path, children);
+        node.known_folder = Some(KnownFolder::Downloads);
+        node
+    }
+
+    fn scan_of(root: FileNode) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_keeps_newest_of_each_group() {
+        let downloads = downloads(
+            "/home/u/Downloads",
+            vec![
+                // 较新的版本反而更早下载：按版本号而不是修改时间判断
+                file("/home/u/Downloads/node-v20.5.1-x64.msi", 30 * MB, Some(100)),
+                file(
+                    "/home/u/Downloads/node-v18.17.0-x64.msi",
+                    28 * MB,
+                    Some(200),
+                ),
+                file("/home/u/Downloads/node-v16.20.0-x64.msi", 27 * MB, Some(50)),
+                file("/home/u/Downloads/ChromeSetup.exe", 2 * MB, Some(10)),
+                file("/home/u/Downloads/ChromeSetup (1).exe", 2 * MB, Some(20)),
+                file("/home/u/Downloads/photo-2021.zip", 50 * MB, Some(10)),
+                file("/home/u/Downloads/photo-2022.zip", 50 * MB, Some(20)),
+                file("/home/u/Downloads/tool-1.0.exe", 100, Some(10)),
+                file("/home/u/Downloads/tool-2.0.exe", 100, Some(20)),
+                file(
+                    "/home/u/Downloads/python-3.12.1-amd64.exe",
+                    25 * MB,
+                    Some(10),
+                ),
+            ],
+        );
+        // Downloads 以外的安装包不参与
+        let other = dir(
+            "/home/u/installers",
+            vec![file(
+                "/home/u/installers/node-v14.0.0-x64.msi",
+                20 * MB,
+                Some(1),
+            )],
+        );
+        let scan = scan_of(dir("/home/u", vec![downloads, other]));
+
+        let groups = group_installers(find_installers(&scan));
+        assert_eq!(groups.len(), 2);
+        let node = groups.iter().find(|g| g.key == "node.msi").unwrap();
+        assert_eq!(node.files[0].version.as_deref(), Some("20.5.1"));
+
+        let actions = plan_installer_cleanup(&scan);
+        let deleted: Vec<&str> = actions
+            .iter()
+            .map(|a| match &a.action {
+                Action::Delete { path } => path.as_str(),
+                other => panic!("unexpected action {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            deleted,
+            vec![
+                "/home/u/Downloads/ChromeSetup.exe",
+                "/home/u/Downloads/node-v18.17.0-x64.msi",
+                "/home/u/Downloads/node-v16.20.0-x64.msi",
+            ]
+        );
+        assert_eq!(
+            actions[1].reason,
+            "Node.js 安装包的旧版本 18.17.0（保留最新版 20.5.1）"
+        );
+        assert!(actions[0].reason.contains("ChromeSetup (1).exe"));
+        assert_eq!(actions.iter().map(|a| a.bytes).sum::<u64>(), 57 * MB);
+        assert!(actions.iter().all(|a| a.risk == RiskLevel::Low));
+        assert!(actions
+            .iter()
+            .all(|a| a.scanned.is_some_and(|s| s.size == a.bytes)));
+    }
+}
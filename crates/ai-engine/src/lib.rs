@@ -1,8 +1,26 @@
+pub mod analysis;
+pub mod backed_up;
+pub mod estimated_space;
+pub mod installers;
 pub mod llm;
+pub mod plan_selection;
+pub mod plan_summary;
 pub mod planner;
 pub mod prompt;
+pub mod redownloadable;
+pub mod trash;
 pub mod validator;
+pub mod virtual_disks;
 
+pub use analysis::*;
+pub use backed_up::*;
+pub use estimated_space::*;
+pub use installers::*;
+pub use plan_selection::*;
+pub use plan_summary::*;
 pub use planner::*;
 pub use prompt::*;
+pub use redownloadable::*;
+pub use trash::*;
 pub use validator::*;
+pub use virtual_disks::*;
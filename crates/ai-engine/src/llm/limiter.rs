@@ -0,0 +1,251 @@
+//! LLM 请求限流：所有 LLM 调用共享一个信号量，同时进行的请求不超过上限，多出的排队等待。
+//!
+//! 排队的请求数也有上限，队列已满时新请求立即以 [`LlmError::Busy`] 失败，
+//! 避免失控的重试循环持续压向服务商、触发限流或产生费用。排队时间计入运行指标与遥测。
+//! 上限可随配置修改：之后的请求使用新的信号量，已在进行或排队的请求按旧上限完成。
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use ai_disk_common::{
+    metrics, record_llm_request, LlmConfig, LABEL_ALL, METRIC_LLM_QUEUE_MS, METRIC_LLM_REJECTED,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::LlmError;
+
+/// 限流参数，取自 `llm` 配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlmLimits {
+    /// 同时进行的请求数上限
+    pub max_concurrent: usize,
+    /// 排队等待的请求数上限
+    pub max_queued: usize,
+    /// 单个请求的超时，不含排队时间
+    pub timeout: Duration,
+}
+
+impl LlmLimits {
+    pub fn from_config(config: &LlmConfig) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent_requests.max(1),
+            max_queued: config.max_queued_requests,
+            timeout: Duration::from_secs(config.request_timeout_secs.max(1)),
+        }
+    }
+}
+
+impl Default for LlmLimits {
+    fn default() -> Self {
+        Self::from_config(&LlmConfig::default())
+    }
+}
+
+struct Slots {
+    limits: LlmLimits,
+    semaphore: Arc<Semaphore>,
+}
+
+pub struct LlmLimiter {
+    slots: Mutex<Slots>,
+    /// 正在排队的请求数
+    waiting: AtomicUsize,
+}
+
+/// 持有期间占用一个并发名额
+pub struct LlmPermit {
+    _permit: OwnedSemaphorePermit,
+    /// 取得名额前的排队时间
+    pub queued: Duration,
+}
+
+/// 离开队列（取得名额或请求被取消）时减少排队计数
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+static LLM_LIMITER: OnceLock<LlmLimiter> = OnceLock::new();
+
+/// 所有 LLM 请求共享的限流器；启动时与配置保存时按 `llm` 配置设置
+pub fn llm_limiter() -> &'static LlmLimiter {
+    LLM_LIMITER.get_or_init(|| LlmLimiter::new(LlmLimits::default()))
+}
+
+impl LlmLimiter {
+    pub fn new(limits: LlmLimits) -> Self {
+        Self {
+            slots: Mutex::new(Slots {
+                limits,
+                semaphore: Arc::new(Semaphore::new(limits.max_concurrent)),
+            }),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Slots> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn limits(&self) -> LlmLimits {
+        self.lock().limits
+    }
+
+    /// 修改上限，作用于之后的请求
+    pub fn set_limits(&self, limits: LlmLimits) {
+        let mut slots = self.lock();
+        if slots.limits.max_concurrent != limits.max_concurrent {
+            slots.semaphore = Arc::new(Semaphore::new(limits.max_concurrent));
+        }
+        slots.limits = limits;
+    }
+
+    /// 取得一个并发名额：有空闲名额时立即返回，否则排队；队列已满时立即返回 Busy
+    pub async fn acquire(&self) -> Result<LlmPermit, LlmError> {
+        let (semaphore, max_queued) = {
+            let slots = self.lock();
+            (slots.semaphore.clone(), slots.limits.max_queued)
+        };
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(LlmPermit {
+                _permit: permit,
+                queued: Duration::ZERO,
+            });
+        }
+        let queued = self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _waiting = Waiting(&self.waiting);
+        if queued >= max_queued {
+            metrics().increment(METRIC_LLM_REJECTED, LABEL_ALL, 1);
+            record_llm_request(0, "busy");
+            return Err(LlmError::Busy { max_queued });
+        }
+        let start = Instant::now();
+        // 信号量从不关闭
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .map_err(|_| LlmError::Busy { max_queued })?;
+        Ok(LlmPermit {
+            _permit: permit,
+            queued: start.elapsed(),
+        })
+    }
+
+    /// 在限流下执行一次请求：排队时间计入指标，排队时间与结果计入遥测
+    pub async fn run<T>(
+        &self,
+        request: impl Future<Output = Result<T, LlmError>>,
+    ) -> Result<T, LlmError> {
+        let permit = self.acquire().await?;
+        metrics().observe_duration(METRIC_LLM_QUEUE_MS, LABEL_ALL, permit.queued);
+        let result = request.await;
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        record_llm_request(permit.queued.as_millis() as u64, outcome);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::{pin, Pin};
+    use std::task::{Context, Poll};
+
+    use ai_disk_common::{CommandError, ErrorCode};
+    use futures::channel::oneshot;
+    use futures::poll;
+
+    fn limits(max_concurrent: usize, max_queued: usize) -> LlmLimits {
+        LlmLimits {
+            max_concurrent,
+            max_queued,
+            timeout: Duration::from_secs(120),
+        }
+    }
+
+    /// 让出一次执行权并立即要求再次轮询
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// 并发执行 `count` 个模拟请求，返回同时进行的最大请求数
+    fn peak_concurrency(limiter: &LlmLimiter, count: usize) -> usize {
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let (active, peak) = (&active, &peak);
+        let requests = (0..count).map(|i| {
+            limiter.run(async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                for _ in 0..3 {
+                    YieldNow(false).await;
+                }
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok::<_, LlmError>(i)
+            })
+        });
+        let results = futures::executor::block_on(futures::future::join_all(requests));
+        let done: Vec<usize> = results.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(done, (0..count).collect::<Vec<_>>());
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_requests_beyond_limit_wait_their_turn() {
+        let limiter = LlmLimiter::new(limits(2, 10));
+        assert_eq!(peak_concurrency(&limiter, 6), 2);
+        limiter.set_limits(limits(1, 10));
+        assert_eq!(peak_concurrency(&limiter, 4), 1);
+        // 不加限制时所有请求同时进行
+        limiter.set_limits(limits(10, 10));
+        assert_eq!(peak_concurrency(&limiter, 4), 4);
+    }
+
+    #[test]
+    fn test_full_queue_fails_fast_with_busy() {
+        let limiter = LlmLimiter::new(limits(1, 1));
+        futures::executor::block_on(async {
+            let (release, hold) = oneshot::channel::<()>();
+            let mut first = pin!(limiter.run(async {
+                hold.await.ok();
+                Ok::<_, LlmError>(1)
+            }));
+            assert!(poll!(first.as_mut()).is_pending());
+            let mut second = pin!(limiter.run(async { Ok::<_, LlmError>(2) }));
+            assert!(poll!(second.as_mut()).is_pending());
+
+            // 名额与队列都已占满，不等待直接失败
+            let third = limiter.run(async { Ok::<_, LlmError>(3) }).await;
+            let busy = third.unwrap_err();
+            assert!(matches!(busy, LlmError::Busy { max_queued: 1 }));
+            let error = CommandError::from(busy);
+            assert_eq!(error.code, ErrorCode::Busy);
+            assert_eq!(error.message, "分析繁忙，请稍后重试");
+
+            release.send(()).unwrap();
+            assert_eq!(first.await.unwrap(), 1);
+            assert_eq!(second.await.unwrap(), 2);
+            // 队列空出后恢复接受请求
+            assert_eq!(
+                limiter.run(async { Ok::<_, LlmError>(4) }).await.unwrap(),
+                4
+            );
+        });
+    }
+}
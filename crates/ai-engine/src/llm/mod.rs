@@ -1,2 +1,66 @@
+pub mod limiter;
 pub mod local;
 pub mod openai;
+
+use std::future::Future;
+
+use ai_disk_common::{CommandError, ErrorCode, MessageId};
+use thiserror::Error;
+
+pub use limiter::{llm_limiter, LlmLimiter, LlmLimits, LlmPermit};
+pub use openai::{OpenAiConfig, OpenAiProvider};
+
+/// LLM 调用错误
+#[derive(Error, Debug)]
+pub enum LlmError {
+    #[error("LLM provider not configured")]
+    NotConfigured,
+
+    #[error("LLM request failed: {0}")]
+    Request(String),
+
+    #[error("LLM returned HTTP {status}: {body}")]
+    Http { status: u16, body: String },
+
+    #[error("LLM response malformed: {0}")]
+    InvalidResponse(String),
+
+    /// 同时进行的请求已达上限且排队已满，见 [`limiter`]
+    #[error("LLM is busy: {max_queued} requests already queued")]
+    Busy { max_queued: usize },
+
+    #[error("LLM request timed out after {secs}s")]
+    Timeout { secs: u64 },
+}
+
+impl From<LlmError> for CommandError {
+    fn from(e: LlmError) -> Self {
+        match &e {
+            LlmError::Busy { .. } => {
+                return CommandError::localized(ErrorCode::Busy, MessageId::LlmBusy, &[]);
+            }
+            LlmError::Timeout { .. } => {
+                return CommandError::new(ErrorCode::NetworkTimeout, e.to_string());
+            }
+            _ => {}
+        }
+        let details = match &e {
+            LlmError::Http { status, .. } => Some(serde_json::json!({ "status": status })),
+            _ => None,
+        };
+        let err = CommandError::new(ErrorCode::LlmUnavailable, e.to_string());
+        match details {
+            Some(d) => err.with_details(d),
+            None => err,
+        }
+    }
+}
+
+/// LLM 提供方：给定系统提示词与用户提示词，返回模型回复文本
+pub trait LlmProvider: Send + Sync {
+    fn complete(
+        &self,
+        system: &str,
+        prompt: &str,
+    ) -> impl Future<Output = Result<String, LlmError>> + Send;
+}
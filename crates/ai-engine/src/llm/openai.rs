@@ -1 +1,153 @@
-//! OpenAI LLM 集成（预留）
+//! OpenAI 兼容协议（/chat/completions）的 LLM 集成
+
+use std::time::{Duration, Instant};
+
+use ai_disk_common::{metrics, METRIC_LLM_LATENCY_MS};
+use serde::{Deserialize, Serialize};
+
+use super::{llm_limiter, LlmError, LlmProvider};
+
+/// 连通性探测发送的唯一内容：固定文本，不携带任何扫描数据
+const PROBE_PROMPT: &str = "ping";
+
+/// OpenAI 兼容服务的连接配置，与前端 AI 设置中的 apiUrl/apiKey/model 对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    pub api_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl OpenAiConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.api_key.is_empty() && !self.api_url.is_empty()
+    }
+}
+
+pub struct OpenAiProvider {
+    config: OpenAiConfig,
+    client: reqwest::Client,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: OpenAiConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 以 1 个 token 的请求探测服务是否可达、API Key 是否有效
+    pub async fn probe(&self) -> Result<(), LlmError> {
+        self.chat(probe_body(&self.config.model)).await.map(|_| ())
+    }
+
+    /// 在全局限流下请求并记录耗时指标（不含排队时间）；未配置时不发请求，也不计入
+    async fn chat(&self, body: serde_json::Value) -> Result<serde_json::Value, LlmError> {
+        if !self.config.is_configured() {
+            return Err(LlmError::NotConfigured);
+        }
+        let limiter = llm_limiter();
+        let timeout = limiter.limits().timeout;
+        limiter
+            .run(async {
+                let start = Instant::now();
+                let result = self.send_chat(body, timeout).await;
+                let label = if result.is_ok() { "ok" } else { "error" };
+                metrics().observe_duration(METRIC_LLM_LATENCY_MS, label, start.elapsed());
+                result
+            })
+            .await
+    }
+
+    async fn send_chat(
+        &self,
+        body: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, LlmError> {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.api_url.trim_end_matches('/')
+        );
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.config.api_key)
+            .timeout(timeout)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| reqwest_error(&e, timeout, LlmError::Request))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(LlmError::Http {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| reqwest_error(&e, timeout, LlmError::InvalidResponse))
+    }
+}
+
+/// 超时（包括读取响应体时）归为 [`LlmError::Timeout`]，其余错误以 `other` 包装
+fn reqwest_error(e: &reqwest::Error, timeout: Duration, other: fn(String) -> LlmError) -> LlmError {
+    if e.is_timeout() {
+        LlmError::Timeout {
+            secs: timeout.as_secs(),
+        }
+    } else {
+        other(e.to_string())
+    }
+}
+
+/// 探测请求体：只有固定提示词，回复限制为 1 个 token
+fn probe_body(model: &str) -> serde_json::Value {
+    serde_json::json!({
+        "model": model,
+        "max_tokens": 1,
+        "messages": [{ "role": "user", "content": PROBE_PROMPT }],
+    })
+}
+
+impl LlmProvider for OpenAiProvider {
+    async fn complete(&self, system: &str, prompt: &str) -> Result<String, LlmError> {
+        let value = self
+            .chat(serde_json::json!({
+                "model": self.config.model,
+                "temperature": 0,
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": prompt },
+                ],
+            }))
+            .await?;
+        value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| LlmError::InvalidResponse("missing choices[0].message.content".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_sends_only_fixed_prompt() {
+        let body = probe_body("gpt-4o-mini");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "gpt-4o-mini",
+                "max_tokens": 1,
+                "messages": [{ "role": "user", "content": "ping" }],
+            })
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! 部分执行：用户只勾选计划中的一部分动作执行。勾选后的子集需要重新整理——
+//! 计划中原本互不相交的动作，在只勾选父目录与其下的子路径时会重复作用于同一文件，
+//! 字节数也会重复计算。纯计算，执行前由命令层调用，再按结果重新检查二次确认阈值。
+
+use std::path::Path;
+
+use ai_disk_domain::{Action, ActionRef, PlannedAction};
+
+/// 勾选子集整理后的结果，下标均为原计划中的下标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedSelection {
+    /// 勾选的动作（升序、去重）
+    pub selected: Vec<usize>,
+    /// 实际执行的动作，保持原计划顺序
+    pub kept: Vec<usize>,
+    /// 被祖先路径上的删除覆盖，或与另一个勾选的动作完全相同，不再单独执行的动作
+    pub covered: Vec<usize>,
+    /// 实际执行的动作预计释放的字节数之和
+    pub bytes: u64,
+}
+
+/// 把勾选解析为原计划中的下标（升序、去重）。路径须恰好匹配一个动作的源路径；
+/// 下标越界、路径不匹配或匹配多个动作、勾选为空时返回错误
+pub fn resolve_selection(
+    actions: &[PlannedAction],
+    selection: &[ActionRef],
+) -> Result<Vec<usize>, String> {
+    if selection.is_empty() {
+        return Err("没有勾选任何动作".to_string());
+    }
+    let mut indices = Vec::with_capacity(selection.len());
+    for item in selection {
+        let index = match item {
+            ActionRef::Index(index) if *index < actions.len() => *index,
+            ActionRef::Index(index) => {
+                return Err(format!(
+                    "动作下标 {} 超出范围（计划共 {} 个动作）",
+                    index,
+                    actions.len()
+                ))
+            }
+            ActionRef::Path(path) => {
+                let mut matches = actions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, planned)| planned.action.source_path() == path)
+                    .map(|(index, _)| index);
+                match (matches.next(), matches.next()) {
+                    (Some(index), None) => index,
+                    (None, _) => return Err(format!("计划中没有作用于 {} 的动作", path)),
+                    (Some(_), Some(_)) => {
+                        return Err(format!("计划中有多个作用于 {} 的动作，请按下标勾选", path))
+                    }
+                }
+            }
+        };
+        indices.push(index);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Ok(indices)
+}
+
+/// 会把其下路径一并删除的动作
+fn removes_tree(action: &Action) -> bool {
+    matches!(action, Action::Delete { .. } | Action::EmptyTrash { .. })
+}
+
+/// 重新整理勾选的动作（`selected` 为 [`resolve_selection`] 的结果）：
+/// 位于另一个勾选的删除（或清空废纸篓）路径之下的删除被覆盖；作用于同一路径的相同动作只执行一次。
+/// 以下情况无法同时执行，返回冲突说明：同一路径上的不同动作；
+/// 在勾选的删除路径之下移动、转存或压缩（父目录删除后子路径已不存在，先执行则与删除的意图相反）；
+/// 移动的来源之下还有其他动作；移动的目标位于勾选的删除路径之下
+pub fn normalize_selection(
+    actions: &[PlannedAction],
+    selected: &[usize],
+) -> Result<NormalizedSelection, String> {
+    let mut covered = Vec::new();
+    for &index in selected {
+        let action = &actions[index].action;
+        let path = Path::new(action.source_path());
+        for &other_index in selected {
+            if other_index == index {
+                continue;
+            }
+            let other = &actions[other_index].action;
+            let other_path = Path::new(other.source_path());
+            let conflict = || {
+                format!(
+                    "动作 {} 与动作 {} 冲突：{} 与 {}",
+                    other_index,
+                    index,
+                    other.source_path(),
+                    action.source_path()
+                )
+            };
+            if other_path == path {
+                if other != action {
+                    return Err(conflict());
+                }
+                // 相同的动作保留下标最小的一个
+                if other_index < index {
+                    covered.push(index);
+                    break;
+                }
+            } else if path.starts_with(other_path) {
+                match other {
+                    _ if removes_tree(other) && removes_tree(action) => {
+                        covered.push(index);
+                        break;
+                    }
+                    Action::Delete { .. } | Action::EmptyTrash { .. } | Action::Move { .. } => {
+                        return Err(conflict())
+                    }
+                    _ => {}
+                }
+            }
+            if let (Action::Move { to, .. }, Action::Delete { .. }) = (action, other) {
+                if Path::new(to).starts_with(other_path) {
+                    return Err(format!(
+                        "动作 {} 把 {} 移动到动作 {} 将删除的 {} 之下",
+                        index,
+                        action.source_path(),
+                        other_index,
+                        other_path.display()
+                    ));
+                }
+            }
+        }
+    }
+    covered.sort_unstable();
+    let kept: Vec<usize> = selected
+        .iter()
+        .copied()
+        .filter(|index| covered.binary_search(index).is_err())
+        .collect();
+    let bytes = kept
+        .iter()
+        .fold(0u64, |sum, &index| sum.saturating_add(actions[index].bytes));
+    Ok(NormalizedSelection {
+        selected: selected.to_vec(),
+        kept,
+        covered,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::RiskLevel;
+
+    fn planned(action: Action, bytes: u64) -> PlannedAction {
+        PlannedAction {
+            action,
+            bytes,
+            risk: RiskLevel::Low,
+            reason: String::new(),
+            scanned: None,
+        }
+    }
+
+    fn delete(path: &str, bytes: u64) -> PlannedAction {
+        planned(
+            Action::Delete {
+                path: path.to_string(),
+            },
+            bytes,
+        )
+    }
+
+    fn move_to(from: &str, to: &str) -> PlannedAction {
+        planned(
+            Action::Move {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+            0,
+        )
+    }
+
+    /// 计划中父目录与其下的文件各有一个删除动作，另有一个无关的删除
+    fn plan() -> Vec<PlannedAction> {
+        vec![
+            delete("/data/cache/a.bin", 300),
+            delete("/data/cache", 1_000),
+            delete("/data/logs", 50),
+            delete("/data/cache-old", 20),
+        ]
+    }
+
+    #[test]
+    fn test_child_alone_is_kept() {
+        let actions = plan();
+        let selected = resolve_selection(&actions, &[ActionRef::Index(0)]).unwrap();
+        let normalized = normalize_selection(&actions, &selected).unwrap();
+        assert_eq!(normalized.kept, vec![0]);
+        assert!(normalized.covered.is_empty());
+        assert_eq!(normalized.bytes, 300);
+    }
+
+    #[test]
+    fn test_parent_covers_child() {
+        let actions = plan();
+        let selection = [
+            ActionRef::Path("/data/cache".to_string()),
+            ActionRef::Index(0),
+            ActionRef::Index(3),
+            ActionRef::Index(0),
+        ];
+        let selected = resolve_selection(&actions, &selection).unwrap();
+        assert_eq!(selected, vec![0, 1, 3]);
+        let normalized = normalize_selection(&actions, &selected).unwrap();
+        // 子文件已含在父目录的删除中，不重复执行也不重复计算；/data/cache-old 不在 /data/cache 之下
+        assert_eq!(normalized.kept, vec![1, 3]);
+        assert_eq!(normalized.covered, vec![0]);
+        assert_eq!(normalized.bytes, 1_020);
+    }
+
+    #[test]
+    fn test_identical_duplicates_run_once() {
+        let actions = vec![delete("/data/x", 10), delete("/data/x", 10)];
+        let normalized = normalize_selection(&actions, &[0, 1]).unwrap();
+        assert_eq!(normalized.kept, vec![0]);
+        assert_eq!(normalized.covered, vec![1]);
+        assert_eq!(normalized.bytes, 10);
+    }
+
+    #[test]
+    fn test_conflicting_selection_is_rejected() {
+        let mut actions = plan();
+        actions.push(move_to("/data/cache/keep.db", "/backup/keep.db"));
+        actions.push(move_to("/data/report.pdf", "/data/logs/report.pdf"));
+        actions.push(move_to("/data/logs", "/backup/logs"));
+        // 删除的目录之下有移动
+        assert!(normalize_selection(&actions, &[1, 4]).is_err());
+        // 移动的目标在删除的目录之下
+        assert!(normalize_selection(&actions, &[2, 5]).is_err());
+        // 同一路径上的删除与移动
+        assert!(normalize_selection(&actions, &[2, 6]).is_err());
+        // 不勾选父目录时可以执行
+        assert_eq!(
+            normalize_selection(&actions, &[0, 4]).unwrap().kept,
+            vec![0, 4]
+        );
+    }
+
+    #[test]
+    fn test_invalid_selection_is_rejected() {
+        let actions = plan();
+        assert!(resolve_selection(&actions, &[]).is_err());
+        assert!(resolve_selection(&actions, &[ActionRef::Index(4)]).is_err());
+        assert!(resolve_selection(&actions, &[ActionRef::Path("/data".to_string())]).is_err());
+        let duplicated = vec![delete("/data/x", 1), move_to("/data/x", "/backup/x")];
+        assert!(resolve_selection(&duplicated, &[ActionRef::Path("/data/x".to_string())]).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! 计划预览汇总：几百条动作的平铺列表无法逐条审阅，这里按风险、类别与目录把动作汇总，
+//! 并列出最大的若干个动作。纯计算，界面直接展示结果而不必自己再做一遍汇总。
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ai_disk_domain::{
+    CategoryTotal, FileCategory, PlanGroup, PlanSummary, PlannedAction, RiskTotal, ScanResult,
+};
+
+use crate::analysis::classify_path;
+
+/// 汇总中列出的最大动作数
+pub const PLAN_SUMMARY_LARGEST_N: usize = 10;
+
+/// 按路径分量建立的前缀树，节点上记录路径恰好落在此处的动作下标
+#[derive(Default)]
+struct PathTrie {
+    children: BTreeMap<String, PathTrie>,
+    actions: Vec<usize>,
+}
+
+impl PathTrie {
+    fn insert(&mut self, path: &Path, index: usize) {
+        let mut node = self;
+        for component in path.components() {
+            let key = match component {
+                // 根目录与盘符前缀之外的分量不带分隔符，重新拼接时由 PathBuf::push 补上
+                Component::RootDir => std::path::MAIN_SEPARATOR.to_string(),
+                other => other.as_os_str().to_string_lossy().into_owned(),
+            };
+            node = node.children.entry(key).or_default();
+        }
+        node.actions.push(index);
+    }
+
+    /// 转为分组：只有一个子节点且自身没有动作的中间目录并入子节点，
+    /// 因此每个分组的路径都是其中动作路径的最近公共祖先
+    fn into_group(mut self, mut path: PathBuf, actions: &[PlannedAction]) -> PlanGroup {
+        while self.actions.is_empty() && self.children.len() == 1 {
+            let (name, child) = self.children.pop_first().unwrap_or_default();
+            path.push(name);
+            self = child;
+        }
+        let mut children: Vec<PlanGroup> = self
+            .children
+            .into_iter()
+            .map(|(name, child)| child.into_group(path.join(name), actions))
+            .collect();
+        children.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+        let own_bytes: u64 = self.actions.iter().map(|&i| actions[i].bytes).sum();
+        PlanGroup {
+            path: path.to_string_lossy().into_owned(),
+            bytes: children
+                .iter()
+                .fold(own_bytes, |sum, child| sum.saturating_add(child.bytes)),
+            action_count: self.actions.len()
+                + children.iter().map(|c| c.action_count).sum::<usize>(),
+            actions: self.actions,
+            children,
+        }
+    }
+}
+
+/// 以当前时间汇总计划
+pub fn summarize_plan(actions: &[PlannedAction], scan: &ScanResult) -> PlanSummary {
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    summarize_plan_at(actions, scan, now_secs)
+}
+
+/// 以指定时间（Unix 秒）汇总计划；类别判断与 [`crate::analyze_scan_at`] 一致
+pub fn summarize_plan_at(
+    actions: &[PlannedAction],
+    scan: &ScanResult,
+    now_secs: u64,
+) -> PlanSummary {
+    // 以声明顺序为键，使结果按 Low、Medium、High 排列
+    let mut by_risk: BTreeMap<u8, RiskTotal> = BTreeMap::new();
+    let mut by_category: BTreeMap<FileCategory, CategoryTotal> = BTreeMap::new();
+    let mut known = PathTrie::default();
+    let mut unknown = Vec::new();
+    let mut total_bytes = 0u64;
+
+    for (index, planned) in actions.iter().enumerate() {
+        total_bytes = total_bytes.saturating_add(planned.bytes);
+        let risk = by_risk
+            .entry(planned.risk as u8)
+            .or_insert_with(|| RiskTotal {
+                risk: planned.risk,
+                bytes: 0,
+                action_count: 0,
+            });
+        risk.bytes = risk.bytes.saturating_add(planned.bytes);
+        risk.action_count += 1;
+
+        let path = Path::new(planned.action.source_path());
+        let Some(category) = classify_path(&scan.root, path, now_secs) else {
+            unknown.push(index);
+            continue;
+        };
+        let total = by_category
+            .entry(category)
+            .or_insert_with(|| CategoryTotal {
+                category,
+                bytes: 0,
+                entry_count: 0,
+            });
+        total.bytes = total.bytes.saturating_add(planned.bytes);
+        total.entry_count += 1;
+        known.insert(path, index);
+    }
+
+    let mut by_category: Vec<CategoryTotal> = by_category.into_values().collect();
+    by_category.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+
+    let tree = (unknown.len() < actions.len()).then(|| known.into_group(PathBuf::new(), actions));
+    let unknown = (!unknown.is_empty()).then(|| PlanGroup {
+        path: String::new(),
+        bytes: unknown.iter().map(|&i| actions[i].bytes).sum(),
+        action_count: unknown.len(),
+        actions: unknown,
+        children: Vec::new(),
+    });
+
+    let mut largest: Vec<usize> = (0..actions.len()).collect();
+    largest.sort_by(|&a, &b| actions[b].bytes.cmp(&actions[a].bytes).then(a.cmp(&b)));
+    largest.truncate(PLAN_SUMMARY_LARGEST_N);
+
+    PlanSummary {
+        total_bytes,
+        action_count: actions.len(),
+        by_risk: by_risk.into_values().collect(),
+        by_category,
+        tree,
+        unknown,
+        largest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::{Action, FileNode, KnownFolder, RiskLevel};
+
+    const NOW: u64 = 1_700_000_000;
+    const DAY: u64 = 24 * 3600;
+
+    fn node(path: &str, size: u64, is_dir: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, is_dir)
+            .with_modified(Some(NOW - 400 * DAY))
+            .with_children(children)
+    }
+
+    fn file(path: &str, size: u64) -> FileNode {
+        node(path, size, false, vec![])
+    }
+
+    fn dir(path: &str, children: Vec<FileNode>) -> FileNode {
+        let size = children.iter().map(|c| c.size).sum();
+        node(path, size, true, children)
+    }
+
+    /// 扫描器标记为下载目录的目录
+    fn downloads(path: &str, children: Vec<FileNode>) -> FileNode {
+        let mut node = dir(path, children);
+        node.known_folder = Some(KnownFolder::Downloads);
+        node
+    }
+
+    fn delete(path: &str, bytes: u64, risk: RiskLevel) -> PlannedAction {
+        PlannedAction {
+            action: Action::Delete {
+                path: path.to_string(),
+            },
+            bytes,
+            risk,
+            reason: String::new(),
+            scanned: None,
+        }
+    }
+
+    fn scan() -> ScanResult {
+        let root = dir(
+            "/home/u",
+            vec![
+                downloads(
+                    "/home/u/Downloads",
+                    vec![
+                        file("/home/u/Downloads/a.iso", 700),
+                        file("/home/u/Downloads/b.msi", 200),
+                        file("/home/u/Downloads/old.zip", 50),
+                        dir(
+                            "/home/u/Downloads/sub",
+                            vec![dir(
+                                "/home/u/Downloads/sub/deep",
+                                vec![file("/home/u/Downloads/sub/deep/c.exe", 30)],
+                            )],
+                        ),
+                    ],
+                ),
+                dir(
+                    "/home/u/project",
+                    vec![dir(
+                        "/home/u/project/node_modules",
+                        vec![file("/home/u/project/node_modules/x.js", 400)],
+                    )],
+                ),
+                dir("/home/u/.cache", vec![file("/home/u/.cache/thumb.db", 90)]),
+            ],
+        );
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    fn plan() -> Vec<PlannedAction> {
+        vec![
+            delete("/home/u/Downloads/a.iso", 700, RiskLevel::Low),
+            delete("/home/u/Downloads/b.msi", 200, RiskLevel::Low),
+            delete("/home/u/Downloads/old.zip", 50, RiskLevel::Medium),
+            delete("/home/u/Downloads/sub/deep/c.exe", 30, RiskLevel::Low),
+            delete("/home/u/project/node_modules", 400, RiskLevel::Medium),
+            // .cache 下的文件按所在缓存目录归类
+            delete("/home/u/.cache/thumb.db", 90, RiskLevel::High),
+            // 扫描之后才出现、或不在扫描范围内的路径
+            delete("/home/u/Downloads/new.iso", 1000, RiskLevel::Low),
+            delete("/var/log/syslog.1", 5, RiskLevel::Medium),
+        ]
+    }
+
+    #[test]
+    fn test_totals_by_risk_and_category() {
+        let summary = summarize_plan_at(&plan(), &scan(), NOW);
+        assert_eq!(summary.total_bytes, 2475);
+        assert_eq!(summary.action_count, 8);
+
+        let risks: Vec<_> = summary
+            .by_risk
+            .iter()
+            .map(|r| (r.risk, r.bytes, r.action_count))
+            .collect();
+        assert_eq!(
+            risks,
+            [
+                (RiskLevel::Low, 1930, 4),
+                (RiskLevel::Medium, 455, 3),
+                (RiskLevel::High, 90, 1),
+            ]
+        );
+
+        let categories: Vec<_> = summary
+            .by_category
+            .iter()
+            .map(|c| (c.category, c.bytes, c.entry_count))
+            .collect();
+        assert_eq!(
+            categories,
+            [
+                (FileCategory::Installer, 930, 3),
+                (FileCategory::PackageCache, 400, 1),
+                (FileCategory::AppCache, 90, 1),
+                (FileCategory::StaleDownloads, 50, 1),
+            ]
+        );
+        // 各类别与未知分组合计等于总量
+        let categorized: u64 = summary.by_category.iter().map(|c| c.bytes).sum();
+        assert_eq!(categorized + summary.unknown.as_ref().unwrap().bytes, 2475);
+    }
+
+    #[test]
+    fn test_tree_rolls_up_under_common_ancestors() {
+        let summary = summarize_plan_at(&plan(), &scan(), NOW);
+        let tree = summary.tree.unwrap();
+        assert_eq!(tree.path, "/home/u");
+        assert_eq!((tree.bytes, tree.action_count), (1470, 6));
+        assert!(tree.actions.is_empty());
+
+        let children: Vec<_> = tree
+            .children
+            .iter()
+            .map(|g| (g.path.as_str(), g.bytes, g.action_count))
+            .collect();
+        assert_eq!(
+            children,
+            [
+                ("/home/u/Downloads", 980, 4),
+                ("/home/u/project/node_modules", 400, 1),
+                ("/home/u/.cache/thumb.db", 90, 1),
+            ]
+        );
+        // project 只有一个子分组，并入 node_modules
+        assert_eq!(tree.children[1].actions, [4]);
+
+        let downloads = &tree.children[0];
+        let leaves: Vec<_> = downloads
+            .children
+            .iter()
+            .map(|g| (g.path.as_str(), g.bytes, g.actions.clone()))
+            .collect();
+        assert_eq!(
+            leaves,
+            [
+                ("/home/u/Downloads/a.iso", 700, vec![0]),
+                ("/home/u/Downloads/b.msi", 200, vec![1]),
+                ("/home/u/Downloads/old.zip", 50, vec![2]),
+                // sub/deep 这一串单子目录合并到文件本身
+                ("/home/u/Downloads/sub/deep/c.exe", 30, vec![3]),
+            ]
+        );
+
+        let unknown = summary.unknown.unwrap();
+        assert_eq!(unknown.actions, [6, 7]);
+        assert_eq!((unknown.bytes, unknown.action_count), (1005, 2));
+    }
+
+    #[test]
+    fn test_largest_actions_and_edge_cases() {
+        let summary = summarize_plan_at(&plan(), &scan(), NOW);
+        assert_eq!(summary.largest, [6, 0, 4, 1, 5, 2, 3, 7]);
+
+        let many: Vec<_> = (0..15)
+            .map(|i| delete(&format!("/elsewhere/{}", i), i, RiskLevel::Low))
+            .collect();
+        let summary = summarize_plan_at(&many, &scan(), NOW);
+        assert_eq!(summary.largest.len(), PLAN_SUMMARY_LARGEST_N);
+        assert_eq!(summary.largest[0], 14);
+        assert!(summary.tree.is_none());
+        assert!(summary.by_category.is_empty());
+        assert_eq!(summary.unknown.unwrap().bytes, (0..15).sum::<u64>());
+
+        // 动作路径恰为扫描根目录，且有嵌套在其下的动作
+        let nested = [
+            delete("/home/u/Downloads", 980, RiskLevel::Low),
+            delete("/home/u/Downloads/a.iso", 700, RiskLevel::Low),
+        ];
+        let tree = summarize_plan_at(&nested, &scan(), NOW).tree.unwrap();
+        assert_eq!(tree.path, "/home/u/Downloads");
+        assert_eq!((tree.bytes, tree.actions.clone()), (1680, vec![0]));
+        assert_eq!(tree.children[0].actions, [1]);
+
+        let empty = summarize_plan_at(&[], &scan(), NOW);
+        assert_eq!(empty.total_bytes, 0);
+        assert!(empty.tree.is_none() && empty.unknown.is_none());
+        assert!(empty.by_risk.is_empty() && empty.largest.is_empty());
+    }
+}
@@ -1,9 +1,15 @@
-use ai_disk_domain::CleanupPlan;
+use std::time::Instant;
 
-/// AI 规划器（预留）
-pub async fn plan_cleanup(_scan_result: &str) -> Result<CleanupPlan, String> {
-    Ok(CleanupPlan {
-        actions: vec![],
-        estimated_space: 0,
-    })
+use ai_disk_common::{metrics, LABEL_ALL, METRIC_PLANNER_DURATION_MS};
+use ai_disk_domain::{CleanupPlan, ScanResult};
+
+use crate::estimated_space::recompute_estimated_space;
+
+/// AI 规划器（预留）。预计释放量按扫描结果重新计算，不采用规划器给出的数字
+pub async fn plan_cleanup(scan: &ScanResult) -> Result<CleanupPlan, String> {
+    let start = Instant::now();
+    let mut plan = CleanupPlan::default();
+    plan.estimated_space = recompute_estimated_space(&plan, scan);
+    metrics().observe_duration(METRIC_PLANNER_DURATION_MS, LABEL_ALL, start.elapsed());
+    Ok(plan)
 }
@@ -1,4 +1,51 @@
-/// AI 提示词模板（预留）
-pub fn build_analysis_prompt(_data: &str) -> String {
-    "".to_string()
+use ai_disk_common::{format_bytes, ByteStyle};
+use ai_disk_domain::DiskAnalysis;
+
+/// 生成分析总结时使用的系统提示词
+pub const ANALYSIS_SYSTEM_PROMPT: &str = "你是一名磁盘清理助手。根据给出的磁盘分析结论，用简洁的中文向用户总结磁盘空间的主要去向，并按风险从低到高给出清理建议。不要编造结论中没有的数据。";
+
+/// 将确定性分析结果整理为 LLM 提示词
+pub fn build_analysis_prompt(analysis: &DiskAnalysis) -> String {
+    let mut prompt = format!(
+        "扫描路径: {}\n总大小: {}\n低风险可回收: {}\n中风险及以下可回收: {}\n结论:\n",
+        analysis.root_path,
+        format_bytes(analysis.total_size, ByteStyle::Binary),
+        format_bytes(analysis.reclaimable_low_risk, ByteStyle::Binary),
+        format_bytes(analysis.reclaimable_medium_risk, ByteStyle::Binary)
+    );
+    for finding in &analysis.findings {
+        prompt.push_str(&format!(
+            "- [{:?}] {}（风险: {:?}）\n",
+            finding.category, finding.headline, finding.risk
+        ));
+        for path in finding.paths.iter().take(3) {
+            prompt.push_str(&format!("    {}\n", path));
+        }
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_uses_readable_sizes() {
+        let analysis = DiskAnalysis {
+            root_path: "/home/u".to_string(),
+            total_size: 3 * 1024 * 1024 * 1024,
+            findings: vec![],
+            category_totals: vec![],
+            reclaimable_low_risk: 1536,
+            reclaimable_medium_risk: 1023,
+            redownloadable: Default::default(),
+            virtual_disks: vec![],
+            projection: None,
+            narrative: None,
+        };
+        let prompt = build_analysis_prompt(&analysis);
+        assert!(prompt.contains("总大小: 3.0 GiB\n"));
+        assert!(prompt.contains("低风险可回收: 1.5 KiB\n"));
+        assert!(prompt.contains("中风险及以下可回收: 1,023 B\n"));
+    }
 }
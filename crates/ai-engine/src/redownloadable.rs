@@ -0,0 +1,217 @@
+//! 可重新下载的内容：Steam 游戏、本地大模型权重、Docker/WSL 虚拟磁盘等。
+//! 删除后可以从原来源恢复，但需要重新下载，因此既不同于缓存，也不同于个人数据，单独归为
+//! [`ai_disk_domain::FileCategory::Redownloadable`]，清理建议一律为中风险，理由中附带恢复方法。
+//!
+//! 规则按路径末尾的若干分量匹配（不区分大小写，`*` 匹配任意一个分量），只要求末尾一致，
+//! 因此不依赖盘符或用户名；分量必须完全相等，`steamapps-backup/common` 之类不会命中。
+
+use ai_disk_common::{format_bytes, ByteStyle};
+use ai_disk_domain::{Action, DiskAnalysis, PlannedAction, RiskLevel};
+
+/// 一条可重新下载内容的识别规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedownloadRule {
+    /// 路径末尾的分量（小写），`*` 匹配任意一个分量
+    pub suffix: &'static [&'static str],
+    /// 规则针对目录还是文件
+    pub is_dir: bool,
+    /// 所属应用
+    pub app: &'static str,
+    /// 删除后如何恢复
+    pub restore_hint: &'static str,
+}
+
+/// 识别规则表
+pub const REDOWNLOAD_RULES: &[RedownloadRule] = &[
+    RedownloadRule {
+        suffix: &["steamapps", "common", "*"],
+        is_dir: true,
+        app: "Steam",
+        restore_hint: "在 Steam 库中重新安装该游戏即可恢复，云存档不受影响",
+    },
+    RedownloadRule {
+        suffix: &[".ollama", "models"],
+        is_dir: true,
+        app: "Ollama",
+        restore_hint: "需要时用 ollama pull <模型名> 重新下载",
+    },
+    RedownloadRule {
+        suffix: &[".cache", "huggingface"],
+        is_dir: true,
+        app: "Hugging Face",
+        restore_hint: "下次加载模型或数据集时会自动从 Hugging Face Hub 重新下载",
+    },
+    RedownloadRule {
+        suffix: &["docker", "wsl", "data", "ext4.vhdx"],
+        is_dir: false,
+        app: "Docker Desktop",
+        restore_hint: "镜像可重新 docker pull；删除前先退出 Docker Desktop，本地容器与数据卷会一并清空",
+    },
+    RedownloadRule {
+        suffix: &["docker", "wsl", "disk", "docker_data.vhdx"],
+        is_dir: false,
+        app: "Docker Desktop",
+        restore_hint: "镜像可重新 docker pull；删除前先退出 Docker Desktop，本地容器与数据卷会一并清空",
+    },
+    RedownloadRule {
+        suffix: &["appdata", "local", "packages", "*", "localstate", "ext4.vhdx"],
+        is_dir: false,
+        app: "WSL 发行版",
+        restore_hint: "可用 wsl --install 重新安装发行版；发行版内的个人文件会一并丢失，删除前先 wsl --export 备份",
+    },
+];
+
+/// 路径末尾的分量是否依次与 `suffix` 一致（不区分大小写，`*` 匹配任意一个分量）
+pub(crate) fn path_ends_with(path: &str, suffix: &[&str]) -> bool {
+    let lower = path.to_lowercase();
+    let components: Vec<&str> = lower.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+    components.len() >= suffix.len()
+        && components[components.len() - suffix.len()..]
+            .iter()
+            .zip(suffix)
+            .all(|(component, pattern)| *pattern == "*" || component == pattern)
+}
+
+/// 按规则表匹配路径，返回第一条命中的规则
+pub fn match_redownloadable(path: &str, is_dir: bool) -> Option<&'static RedownloadRule> {
+    REDOWNLOAD_RULES
+        .iter()
+        .find(|rule| rule.is_dir == is_dir && path_ends_with(path, rule.suffix))
+}
+
+/// 为分析结果中的可重新下载内容生成删除建议，均为中风险，理由中附带恢复方法
+pub fn plan_redownloadable_cleanup(analysis: &DiskAnalysis) -> Vec<PlannedAction> {
+    analysis
+        .redownloadable
+        .items
+        .iter()
+        .filter(|item| item.bytes > 0)
+        .map(|item| PlannedAction {
+            action: Action::Delete {
+                path: item.path.clone(),
+            },
+            bytes: item.bytes,
+            risk: RiskLevel::Medium,
+            reason: format!(
+                "{} 的可重新下载内容（{}）：{}",
+                item.app,
+                format_bytes(item.bytes, ByteStyle::Binary),
+                item.restore_hint
+            ),
+            scanned: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_of(path: &str, is_dir: bool) -> Option<&'static str> {
+        match_redownloadable(path, is_dir).map(|rule| rule.app)
+    }
+
+    #[test]
+    fn test_rules_match_realistic_paths() {
+        assert_eq!(
+            app_of(r"D:\SteamLibrary\steamapps\common\Cyberpunk 2077", true),
+            Some("Steam")
+        );
+        assert_eq!(
+            app_of(
+                "/home/u/.local/share/Steam/steamapps/common/Counter-Strike Global Offensive",
+                true
+            ),
+            Some("Steam")
+        );
+        assert_eq!(app_of(r"C:\Users\u\.ollama\models", true), Some("Ollama"));
+        assert_eq!(
+            app_of("/home/u/.cache/huggingface", true),
+            Some("Hugging Face")
+        );
+        assert_eq!(
+            app_of(r"C:\Users\u\AppData\Local\Docker\wsl\data\ext4.vhdx", false),
+            Some("Docker Desktop")
+        );
+        assert_eq!(
+            app_of(
+                r"C:\Users\u\AppData\Local\Docker\wsl\disk\docker_data.vhdx",
+                false
+            ),
+            Some("Docker Desktop")
+        );
+        assert_eq!(
+            app_of(
+                r"C:\Users\u\AppData\Local\Packages\CanonicalGroupLimited.Ubuntu22.04LTS_79rhkp1fndgsc\LocalState\ext4.vhdx",
+                false
+            ),
+            Some("WSL 发行版")
+        );
+    }
+
+    #[test]
+    fn test_false_positive_guards() {
+        // steamapps/common 本身、其中的文件，以及名字相近的目录
+        assert_eq!(app_of(r"D:\SteamLibrary\steamapps\common", true), None);
+        assert_eq!(
+            app_of(r"D:\SteamLibrary\steamapps\common\readme.txt", false),
+            None
+        );
+        assert_eq!(app_of(r"D:\Backup\steamapps-old\common\Game", true), None);
+        // 不带点的 ollama 目录、同名文件、huggingface 不在 .cache 下
+        assert_eq!(app_of("/home/u/ollama/models", true), None);
+        assert_eq!(app_of("/home/u/.cache/huggingface", false), None);
+        assert_eq!(app_of("/home/u/projects/huggingface", true), None);
+        assert_eq!(app_of("/home/u/my.cache/huggingface", true), None);
+        // 用户自己的虚拟磁盘
+        assert_eq!(app_of(r"D:\VMs\ext4.vhdx", false), None);
+        assert_eq!(
+            app_of(r"C:\Users\u\Documents\LocalState\ext4.vhdx", false),
+            None
+        );
+        assert_eq!(
+            app_of(r"C:\Users\u\AppData\Local\Docker\wsl\data\ext4.vhdx", true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plan_is_medium_risk_with_hint() {
+        let analysis = DiskAnalysis {
+            root_path: "/home/u".to_string(),
+            total_size: 0,
+            findings: vec![],
+            category_totals: vec![],
+            reclaimable_low_risk: 0,
+            reclaimable_medium_risk: 0,
+            redownloadable: ai_disk_domain::RedownloadableSummary {
+                total_bytes: 2048,
+                items: vec![
+                    ai_disk_domain::RedownloadableItem {
+                        path: "/home/u/.ollama/models".to_string(),
+                        bytes: 2048,
+                        app: "Ollama".to_string(),
+                        restore_hint: "需要时用 ollama pull <模型名> 重新下载".to_string(),
+                    },
+                    ai_disk_domain::RedownloadableItem {
+                        path: "/home/u/.cache/huggingface".to_string(),
+                        bytes: 0,
+                        app: "Hugging Face".to_string(),
+                        restore_hint: String::new(),
+                    },
+                ],
+            },
+            virtual_disks: vec![],
+            projection: None,
+            narrative: None,
+        };
+        let actions = plan_redownloadable_cleanup(&analysis);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].risk, RiskLevel::Medium);
+        assert_eq!(actions[0].bytes, 2048);
+        assert_eq!(
+            actions[0].reason,
+            "Ollama 的可重新下载内容（2.0 KiB）：需要时用 ollama pull <模型名> 重新下载"
+        );
+    }
+}
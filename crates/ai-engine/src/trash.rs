@@ -0,0 +1,93 @@
+//! 废纸篓清理建议：扫描器标记为 [`FileCategory::Trash`] 的每个废纸篓生成一条「清空废纸篓」动作，
+//! 用户确认一次即可释放其全部空间。
+
+use ai_disk_common::{format_bytes, ByteStyle};
+use ai_disk_domain::{Action, FileCategory, FileNode, PlannedAction, RiskLevel, ScanResult};
+
+use crate::validator::{score_node_risk, target_state};
+
+fn collect_trash<'a>(node: &'a FileNode, out: &mut Vec<&'a FileNode>) {
+    if node.category == Some(FileCategory::Trash) {
+        out.push(node);
+        return;
+    }
+    for child in &node.children {
+        collect_trash(child, out);
+    }
+}
+
+/// 为扫描范围内每个非空的废纸篓生成清空建议，并记录废纸篓在这次扫描中的状态
+pub fn plan_trash_cleanup(scan: &ScanResult) -> Vec<PlannedAction> {
+    let mut trash = Vec::new();
+    collect_trash(&scan.root, &mut trash);
+    trash
+        .into_iter()
+        .filter(|node| node.size > 0)
+        .map(|node| PlannedAction {
+            action: Action::EmptyTrash {
+                path: node.path.clone(),
+            },
+            bytes: node.size,
+            risk: score_node_risk(RiskLevel::Low, node),
+            reason: format!(
+                "废纸篓中的文件共 {}，清空后无法恢复",
+                format_bytes(node.size, ByteStyle::Binary)
+            ),
+            scanned: target_state(scan, &node.path),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, true).with_children(children)
+    }
+
+    #[test]
+    fn test_plans_one_action_per_non_empty_trash() {
+        let mut trash = node("/home/u/.local/share/Trash", 4096, vec![]);
+        trash.category = Some(FileCategory::Trash);
+        let mut empty = node("/home/v/.Trash", 0, vec![]);
+        empty.category = Some(FileCategory::Trash);
+        let root = node(
+            "/home",
+            4096,
+            vec![
+                node("/home/u", 4096, vec![trash]),
+                node("/home/v", 0, vec![empty]),
+            ],
+        );
+        let scan = ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: Some(4096),
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        };
+
+        let actions = plan_trash_cleanup(&scan);
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(
+            &actions[0].action,
+            Action::EmptyTrash { path } if path == "/home/u/.local/share/Trash"
+        ));
+        assert_eq!(actions[0].bytes, 4096);
+        assert_eq!(actions[0].risk, RiskLevel::Low);
+        assert!(actions[0].reason.contains("4.0 KiB"));
+        assert_eq!(actions[0].scanned.map(|s| s.size), Some(4096));
+    }
+}
@@ -1,6 +1,347 @@
-use ai_disk_domain::Action;
+use std::path::{Path, PathBuf};
 
-/// 动作校验器（预留）
-pub fn validate_action(_action: &Action) -> Result<(), String> {
+use ai_disk_common::InternalPaths;
+use ai_disk_domain::{
+    Action, CleanupPlan, FileAttributes, FileCategory, FileNode, KnownFolder, PlannedAction,
+    RiskLevel, ScanResult, TargetState,
+};
+use ai_disk_scanner::{is_volume_trash_name, normalize_node_path};
+
+use crate::estimated_space::recompute_estimated_space;
+
+/// 动作校验器：拒绝作用于应用自身占用路径（存储根目录、正在写入的导出文件）的动作
+pub fn validate_action(action: &Action) -> Result<(), String> {
+    validate_action_against(action, &InternalPaths::current())
+}
+
+/// 以给定的应用占用路径校验动作；移动动作的目标也不能落在其中
+pub fn validate_action_against(action: &Action, internal: &InternalPaths) -> Result<(), String> {
+    let target = match action {
+        Action::Move { to, .. } => Some(to.as_str()),
+        _ => None,
+    };
+    for path in std::iter::once(action.source_path()).chain(target) {
+        if internal.contains(path) {
+            return Err(format!("不能操作应用自身使用的路径: {}", path));
+        }
+    }
     Ok(())
 }
+
+/// 包含 `path`（或就是 `path`）的废纸篓：扫描树中标记为 [`FileCategory::Trash`] 的节点，
+/// 扫描树中找不到时按路径中按卷存放的回收站目录名（`$Recycle.Bin` 等）判断。
+/// 与树中路径的比较按计算节点 id 的规则进行（见 [`normalize_node_path`]）
+fn trash_container(root: &FileNode, path: &str) -> Option<PathBuf> {
+    fn find(node: &FileNode, target: &Path) -> Option<PathBuf> {
+        if !target.starts_with(normalize_node_path(&node.path)) {
+            return None;
+        }
+        if node.category == Some(FileCategory::Trash) {
+            return Some(PathBuf::from(&node.path));
+        }
+        node.children.iter().find_map(|child| find(child, target))
+    }
+    find(root, Path::new(&normalize_node_path(path))).or_else(|| {
+        Path::new(path)
+            .ancestors()
+            .find(|dir| {
+                dir.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(is_volume_trash_name)
+            })
+            .map(Path::to_path_buf)
+    })
+}
+
+/// 作用于废纸篓内部的动作改写为清空整个废纸篓：废纸篓只能整体清空，不能逐个删除、移动其中的文件
+fn rewrite_trash_action(action: &Action, root: &FileNode) -> Action {
+    match trash_container(root, action.source_path()) {
+        Some(container) => Action::EmptyTrash {
+            path: container.to_string_lossy().into_owned(),
+        },
+        None => action.clone(),
+    }
+}
+
+/// 校验计划：丢弃未通过 [`validate_action`] 的动作，作用于废纸篓或回收站内部的动作改写为清空整个废纸篓
+/// （同一废纸篓只保留一个），并按扫描结果重新计算保留动作的预计释放量，覆盖规划器给出的数字。
+/// 同时按 [`target_state`] 记录保留动作的目标在这次扫描中的状态，执行前据此核对。
+/// 返回校验后的计划与被丢弃动作的原因
+pub fn validate_plan(plan: &CleanupPlan, scan: &ScanResult) -> (CleanupPlan, Vec<String>) {
+    let mut rejected = Vec::new();
+    let mut actions: Vec<Action> = Vec::with_capacity(plan.actions.len());
+    for action in &plan.actions {
+        if let Err(reason) = validate_action(action) {
+            rejected.push(reason);
+            continue;
+        }
+        let action = rewrite_trash_action(action, &scan.root);
+        if matches!(action, Action::EmptyTrash { .. }) && actions.contains(&action) {
+            continue;
+        }
+        actions.push(action);
+    }
+    let targets = actions
+        .iter()
+        .map(|action| target_state(scan, action.source_path()))
+        .collect();
+    let mut validated = CleanupPlan {
+        actions,
+        estimated_space: 0,
+        targets,
+    };
+    validated.estimated_space = recompute_estimated_space(&validated, scan);
+    (validated, rejected)
+}
+
+/// 扫描树中 `path` 对应节点的状态，执行前据此核对目标是否在扫描后改变。
+/// 节点不在扫描树中、是挂载点、大小不是遍历得到的实际大小或没有计入子树的全部内容
+/// （[`FileNode::partial_size`]）时为 None；子节点不全（[`FileNode::partial_children`]）时不记录子项数。
+/// 路径的比较与 [`trash_container`] 相同
+pub fn target_state(scan: &ScanResult, path: &str) -> Option<TargetState> {
+    fn find<'a>(node: &'a FileNode, target: &Path) -> Option<&'a FileNode> {
+        let path = PathBuf::from(normalize_node_path(&node.path));
+        if path == target {
+            return Some(node);
+        }
+        if !target.starts_with(&path) {
+            return None;
+        }
+        node.children.iter().find_map(|child| find(child, target))
+    }
+    let node = find(&scan.root, Path::new(&normalize_node_path(path)))?;
+    if node.is_mount_point || !node.size_source.is_exact() || node.partial_size {
+        return None;
+    }
+    Some(TargetState {
+        is_dir: node.is_dir,
+        size: node.size,
+        modified: if node.is_dir { None } else { node.modified },
+        child_count: (node.is_dir && !node.partial_children).then_some(node.children.len()),
+    })
+}
+
+/// 为没有记录扫描时状态的动作按扫描结果补上源路径的状态
+pub fn record_target_states(actions: &mut [PlannedAction], scan: &ScanResult) {
+    for planned in actions.iter_mut().filter(|p| p.scanned.is_none()) {
+        planned.scanned = target_state(scan, planned.action.source_path());
+    }
+}
+
+/// 结合文件属性调整风险等级：带系统属性的文件一律视为高风险
+pub fn score_risk(base: RiskLevel, attributes: Option<FileAttributes>) -> RiskLevel {
+    match attributes {
+        Some(attrs) if attrs.system => RiskLevel::High,
+        _ => base,
+    }
+}
+
+/// 结合节点的文件属性与已知目录标签调整风险等级：系统解析出的文档、图片目录（含被移动过的）
+/// 一律视为高风险，不论目录名或位置
+pub fn score_node_risk(base: RiskLevel, node: &FileNode) -> RiskLevel {
+    match node.known_folder {
+        Some(KnownFolder::Documents | KnownFolder::Pictures) => RiskLevel::High,
+        _ => score_risk(base, node.attributes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_common::set_storage_root;
+
+    #[test]
+    fn test_rejects_actions_inside_storage_root() {
+        let dir = std::env::temp_dir().join("validator-test-home");
+        let storage = dir.join(".disk-rookie");
+        set_storage_root(&storage);
+        let inside = storage.join("scans").to_string_lossy().to_string();
+        let outside = dir.join("Downloads").to_string_lossy().to_string();
+
+        assert!(validate_action(&Action::Delete {
+            path: inside.clone()
+        })
+        .is_err());
+        assert!(validate_action(&Action::Move {
+            from: outside.clone(),
+            to: inside,
+        })
+        .is_err());
+        assert!(validate_action(&Action::Delete { path: outside }).is_ok());
+    }
+
+    #[test]
+    fn test_documents_and_pictures_are_high_risk() {
+        let mut node = FileNode::new("/data/tmp", 0, true);
+        assert_eq!(score_node_risk(RiskLevel::Low, &node), RiskLevel::Low);
+        for (folder, risk) in [
+            (KnownFolder::Documents, RiskLevel::High),
+            (KnownFolder::Pictures, RiskLevel::High),
+            (KnownFolder::Downloads, RiskLevel::Low),
+        ] {
+            node.known_folder = Some(folder);
+            assert_eq!(score_node_risk(RiskLevel::Low, &node), risk, "{folder:?}");
+        }
+    }
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, !children.is_empty()).with_children(children)
+    }
+
+    fn scan_of(root: FileNode) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    fn delete(path: &str) -> Action {
+        Action::Delete {
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_actions_inside_recycle_bin_become_one_empty_trash() {
+        // 扫描器把回收站收拢为不含子项的单个节点
+        let mut recycle_bin = node("/mnt/d/$Recycle.Bin", 4_600, vec![]);
+        recycle_bin.is_dir = true;
+        recycle_bin.category = Some(FileCategory::Trash);
+        let root = node(
+            "/mnt/d",
+            0,
+            vec![recycle_bin, node("/mnt/d/video.mkv", 900, vec![])],
+        );
+        let scan = scan_of(root);
+        let plan = CleanupPlan {
+            actions: vec![
+                delete("/mnt/d/$Recycle.Bin/S-1-5-21-1000/$RAB12CD.iso"),
+                delete("/mnt/d/video.mkv"),
+                Action::Move {
+                    from: "/mnt/d/$Recycle.Bin/S-1-5-21-1000/$RXY34EF".to_string(),
+                    to: "/mnt/e/restored".to_string(),
+                },
+                delete("/mnt/d/$Recycle.Bin/S-1-5-21-1000/$IAB12CD.iso"),
+                // 不在扫描范围内的回收站按目录名识别
+                delete("/mnt/e/$RECYCLE.BIN/S-1-5-21-1000/$R0001.bin"),
+            ],
+            estimated_space: 1,
+            ..Default::default()
+        };
+
+        let (validated, rejected) = validate_plan(&plan, &scan);
+        assert!(rejected.is_empty());
+        let empty = |path: &str| Action::EmptyTrash {
+            path: path.to_string(),
+        };
+        assert_eq!(
+            validated.actions,
+            vec![
+                empty("/mnt/d/$Recycle.Bin"),
+                delete("/mnt/d/video.mkv"),
+                empty("/mnt/e/$RECYCLE.BIN"),
+            ]
+        );
+        assert_eq!(validated.estimated_space, 5_500);
+        // 按这次扫描记录目标状态；不在扫描范围内的目标没有状态
+        let sizes: Vec<Option<u64>> = validated
+            .targets
+            .iter()
+            .map(|t| t.map(|t| t.size))
+            .collect();
+        assert_eq!(sizes, vec![Some(4_600), Some(900), None]);
+    }
+
+    #[test]
+    fn test_target_state_records_what_the_scan_saw() {
+        let mut report = node("/home/u/report.pdf", 300, vec![]);
+        report.modified = Some(1_700_000_000);
+        let mut cache = node("/home/u/cache", 0, vec![]);
+        cache.is_dir = true;
+        cache.size = 5_000;
+        cache.partial_children = true;
+        let project = node(
+            "/home/u/project",
+            320,
+            vec![
+                node("/home/u/project/a.o", 300, vec![]),
+                node("/home/u/project/b.o", 20, vec![]),
+            ],
+        );
+        let mut share = node("/home/u/share", 0, vec![]);
+        share.is_dir = true;
+        share.is_mount_point = true;
+        // 超过子项上限被截断：大小只计入保留的子项
+        let mut logs = node(
+            "/home/u/logs",
+            40,
+            vec![node("/home/u/logs/0.log", 40, vec![])],
+        );
+        logs.partial_children = true;
+        logs.partial_size = true;
+        // 达到深度上限未展开
+        let mut deep = node("/home/u/deep", 0, vec![]);
+        deep.is_dir = true;
+        deep.partial_children = true;
+        deep.partial_size = true;
+        // 部分子项被忽略规则过滤
+        let mut web = node(
+            "/home/u/web",
+            8,
+            vec![node("/home/u/web/index.html", 8, vec![])],
+        );
+        web.partial_children = true;
+        web.partial_size = true;
+        let scan = scan_of(node(
+            "/home/u",
+            5_668,
+            vec![report, cache, project, share, logs, deep, web],
+        ));
+
+        assert_eq!(
+            target_state(&scan, "/home/u/report.pdf"),
+            Some(TargetState {
+                is_dir: false,
+                size: 300,
+                modified: Some(1_700_000_000),
+                child_count: None,
+            })
+        );
+        let project = target_state(&scan, "/home/u/project/").unwrap();
+        assert_eq!((project.size, project.child_count), (320, Some(2)));
+        // 只计大小的目录不记录子项数
+        let cache = target_state(&scan, "/home/u/cache").unwrap();
+        assert_eq!((cache.size, cache.child_count), (5_000, None));
+        assert_eq!(target_state(&scan, "/home/u/share"), None);
+        for path in ["/home/u/logs", "/home/u/deep", "/home/u/web"] {
+            assert_eq!(target_state(&scan, path), None, "{path}");
+        }
+        // 子树内容不全的目录之下，完整扫描的节点照常记录
+        assert!(target_state(&scan, "/home/u/web/index.html").is_some());
+        assert_eq!(target_state(&scan, "/home/u/missing.txt"), None);
+
+        let mut actions = vec![PlannedAction {
+            action: delete("/home/u/project"),
+            bytes: 320,
+            risk: RiskLevel::Low,
+            reason: String::new(),
+            scanned: None,
+        }];
+        record_target_states(&mut actions, &scan);
+        assert_eq!(actions[0].scanned, Some(project));
+    }
+}
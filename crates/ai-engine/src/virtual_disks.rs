@@ -0,0 +1,122 @@
+//! WSL 发行版与 Docker Desktop 的虚拟磁盘（.vhdx）。
+//!
+//! 这类文件随写入自动增长，但在 WSL/容器中删除文件后不会自动缩小，常常占用几十 GB；
+//! 释放空间需要压缩（`wsl --manage <发行版> --set-sparse true` 或 `Optimize-VHD`），
+//! 执行见 `ai_disk_executor` 的 `CompactVhd` 动作。这里只按路径识别并报告大小。
+//! 只识别应用商店安装的发行版与 Docker Desktop 默认数据目录，`wsl --import` 到其他位置的不识别。
+
+use ai_disk_domain::{FileNode, VirtualDisk, VirtualDiskKind};
+
+use crate::redownloadable::path_ends_with;
+
+/// 虚拟磁盘的路径规则（末尾分量，小写）
+const VIRTUAL_DISK_PATTERNS: &[(&[&str], VirtualDiskKind)] = &[
+    (
+        &[
+            "appdata",
+            "local",
+            "packages",
+            "*",
+            "localstate",
+            "ext4.vhdx",
+        ],
+        VirtualDiskKind::WslDistro,
+    ),
+    (
+        &["docker", "wsl", "data", "ext4.vhdx"],
+        VirtualDiskKind::DockerDesktop,
+    ),
+    (
+        &["docker", "wsl", "disk", "docker_data.vhdx"],
+        VirtualDiskKind::DockerDesktop,
+    ),
+    (
+        &["docker", "wsl", "main", "ext4.vhdx"],
+        VirtualDiskKind::DockerDesktop,
+    ),
+];
+
+const VIRTUAL_DISK_NOTE: &str =
+    "虚拟磁盘只增不减：在 WSL 或容器中删除文件不会缩小该文件，需要压缩虚拟磁盘才能把空间还给系统";
+
+/// 路径对应的虚拟磁盘类型；不是已知位置的虚拟磁盘时返回 None
+pub fn virtual_disk_kind(path: &str) -> Option<VirtualDiskKind> {
+    VIRTUAL_DISK_PATTERNS
+        .iter()
+        .find(|(suffix, _)| path_ends_with(path, suffix))
+        .map(|&(_, kind)| kind)
+}
+
+/// 扫描树中的虚拟磁盘，按大小降序排列
+pub fn find_virtual_disks(root: &FileNode) -> Vec<VirtualDisk> {
+    fn walk(node: &FileNode, out: &mut Vec<VirtualDisk>) {
+        if !node.is_dir {
+            if let Some(kind) = virtual_disk_kind(&node.path) {
+                out.push(VirtualDisk {
+                    path: node.path.clone(),
+                    bytes: node.size,
+                    kind,
+                    note: VIRTUAL_DISK_NOTE.to_string(),
+                });
+            }
+            return;
+        }
+        for child in &node.children {
+            walk(child, out);
+        }
+    }
+    let mut disks = Vec::new();
+    walk(root, &mut disks);
+    disks.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    disks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, is_dir: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, is_dir).with_children(children)
+    }
+
+    #[test]
+    fn test_detects_wsl_and_docker_disks() {
+        let ubuntu = r"C:\Users\u\AppData\Local\Packages\CanonicalGroupLimited.Ubuntu22.04LTS_79rhkp1fndgsc\LocalState\ext4.vhdx";
+        let docker = r"C:\Users\u\AppData\Local\Docker\wsl\disk\docker_data.vhdx";
+        let root = node(
+            r"C:\Users\u",
+            0,
+            true,
+            vec![
+                node(ubuntu, 40 << 30, false, vec![]),
+                node(docker, 60 << 30, false, vec![]),
+                node(r"C:\Users\u\VMs\ext4.vhdx", 10 << 30, false, vec![]),
+                node(r"C:\Users\u\LocalState\ext4.vhdx", 1 << 30, false, vec![]),
+            ],
+        );
+        let disks = find_virtual_disks(&root);
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[0].path, docker);
+        assert_eq!(disks[0].kind, VirtualDiskKind::DockerDesktop);
+        assert_eq!(disks[1].kind, VirtualDiskKind::WslDistro);
+        assert_eq!(disks[1].bytes, 40 << 30);
+        assert!(disks[1].note.contains("不会缩小"));
+    }
+
+    #[test]
+    fn test_kind_by_path() {
+        assert_eq!(
+            virtual_disk_kind(r"D:\Docker\wsl\data\ext4.vhdx"),
+            Some(VirtualDiskKind::DockerDesktop)
+        );
+        assert_eq!(
+            virtual_disk_kind(r"C:\Users\u\AppData\Local\Docker\wsl\main\ext4.vhdx"),
+            Some(VirtualDiskKind::DockerDesktop)
+        );
+        // 目录名相同但不是 vhdx
+        assert_eq!(
+            virtual_disk_kind(r"C:\Users\u\AppData\Local\Docker\wsl\data\ext4.vhdx.bak"),
+            None
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! 字节数的统一格式化与解析，供界面、命令行、提示词与日志共用，避免各处得出不同的写法
+//! （1 GB / 1 GiB / 1,024 MB）。
+//!
+//! 格式化规则：不足一个单位时按字节显示整数；换算后小于 10 保留一位小数（`1.5 GiB`），
+//! 不小于 10 取整（`15 GiB`、`1,023 MiB`），整数部分按千位加逗号。
+//! 四舍五入后达到进制时进位到下一单位（1023.96 KiB 显示为 `1.0 MiB`）。
+
+use serde::{Deserialize, Deserializer};
+
+use crate::DiskAnalyzerError;
+
+/// 单位进制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteStyle {
+    /// 1024 进制：KiB、MiB、GiB……与操作系统文件管理器的换算一致
+    #[default]
+    Binary,
+    /// 1000 进制：KB、MB、GB……与磁盘厂商标称容量一致
+    Decimal,
+}
+
+const BINARY_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const DECIMAL_UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+
+impl ByteStyle {
+    fn base(self) -> f64 {
+        match self {
+            ByteStyle::Binary => 1024.0,
+            ByteStyle::Decimal => 1000.0,
+        }
+    }
+
+    fn units(self) -> &'static [&'static str] {
+        match self {
+            ByteStyle::Binary => BINARY_UNITS,
+            ByteStyle::Decimal => DECIMAL_UNITS,
+        }
+    }
+}
+
+/// 人类可读的大小，如 `1,023 B`、`1.5 GiB`、`18 EB`
+pub fn format_bytes(bytes: u64, style: ByteStyle) -> String {
+    let base = style.base();
+    let units = style.units();
+    if (bytes as f64) < base {
+        return format!("{} B", group_thousands(bytes));
+    }
+    let mut value = bytes as f64 / base;
+    let mut unit = 1;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    loop {
+        let one_decimal = (value * 10.0).round() / 10.0;
+        if one_decimal < 10.0 {
+            return format!("{:.1} {}", one_decimal, units[unit]);
+        }
+        let whole = value.round();
+        if whole < base || unit == units.len() - 1 {
+            return format!("{} {}", group_thousands(whole as u64), units[unit]);
+        }
+        value /= base;
+        unit += 1;
+    }
+}
+
+/// 整数按千位加逗号，如 `1,048,576`
+pub fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    groups.join(",")
+}
+
+/// 单位后缀（小写）对应的字节数：`KiB` 等为 1024 进制，`KB` 等为 1000 进制，
+/// 单个字母 `K`/`M`/`G`… 按 1024 进制（与 `du -h` 一致）
+fn unit_multiplier(unit: &str) -> Option<u64> {
+    if unit.is_empty() || unit == "b" {
+        return Some(1);
+    }
+    let mut chars = unit.chars();
+    let power = match chars.next()? {
+        'k' => 1,
+        'm' => 2,
+        'g' => 3,
+        't' => 4,
+        'p' => 5,
+        'e' => 6,
+        _ => return None,
+    };
+    let base: u64 = match chars.as_str() {
+        "" | "ib" => 1024,
+        "b" => 1000,
+        _ => return None,
+    };
+    Some(base.pow(power))
+}
+
+/// 解析配置中的大小，如 `1.5GB`、`500 MiB`、`10G`、`1,023 B`；不带单位时为字节数。
+/// 单位大小写不敏感，数字中可以有千位逗号或下划线，结果四舍五入到整字节
+pub fn parse_size(text: &str) -> Result<u64, DiskAnalyzerError> {
+    let invalid = || DiskAnalyzerError::Config(format!("无效的大小: {:?}", text));
+    let trimmed = text.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '_')))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: String = number.chars().filter(|c| !matches!(c, ',' | '_')).collect();
+    if number.is_empty() || number.starts_with('.') || number.ends_with('.') {
+        return Err(invalid());
+    }
+    let multiplier = unit_multiplier(&unit.trim().to_ascii_lowercase()).ok_or_else(invalid)?;
+
+    // 整数直接按 u64 计算，避免大数经 f64 丢失精度
+    if let Ok(whole) = number.parse::<u64>() {
+        return whole.checked_mul(multiplier).ok_or_else(invalid);
+    }
+    let value: f64 = number.parse().map_err(|_| invalid())?;
+    let bytes = (value * multiplier as f64).round();
+    // u64::MAX 转为 f64 后恰为 2^64，不小于它即溢出
+    if !bytes.is_finite() || bytes >= u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes as u64)
+}
+
+/// serde 反序列化辅助：配置中的大小既可以写字节数，也可以写 `"10GiB"` 之类的字符串
+pub fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SizeValue {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match SizeValue::deserialize(deserializer)? {
+        SizeValue::Bytes(bytes) => Ok(bytes),
+        SizeValue::Text(text) => parse_size(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    const GIB: u64 = 1024 * MIB;
+
+    #[test]
+    fn test_format_binary_boundaries() {
+        let cases = [
+            (0, "0 B"),
+            (1, "1 B"),
+            (999, "999 B"),
+            (1000, "1,000 B"),
+            (1023, "1,023 B"),
+            (1024, "1.0 KiB"),
+            (1536, "1.5 KiB"),
+            (10 * KIB - 52, "9.9 KiB"),
+            // 9.96 KiB 四舍五入到一位小数为 10.0，改为取整
+            (10 * KIB - 40, "10 KiB"),
+            (10 * KIB, "10 KiB"),
+            (1000 * KIB, "1,000 KiB"),
+            (1023 * KIB, "1,023 KiB"),
+            // 1023.96 KiB 取整为 1024，进位到 MiB
+            (MIB - 40, "1.0 MiB"),
+            (MIB - 1, "1.0 MiB"),
+            (MIB, "1.0 MiB"),
+            (3 * GIB, "3.0 GiB"),
+            (1536 * GIB, "1.5 TiB"),
+            (1 << 60, "1.0 EiB"),
+            (u64::MAX, "16 EiB"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(
+                format_bytes(bytes, ByteStyle::Binary),
+                expected,
+                "{}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_decimal_boundaries() {
+        let cases = [
+            (0, "0 B"),
+            (999, "999 B"),
+            (1000, "1.0 KB"),
+            (1023, "1.0 KB"),
+            (1024, "1.0 KB"),
+            (1_500_000_000, "1.5 GB"),
+            (9_949_999, "9.9 MB"),
+            (9_960_000, "10 MB"),
+            (999_499, "999 KB"),
+            (999_500, "1.0 MB"),
+            (u64::MAX, "18 EB"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(
+                format_bytes(bytes, ByteStyle::Decimal),
+                expected,
+                "{}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1_048_576), "1,048,576");
+        assert_eq!(group_thousands(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn test_parse_units() {
+        let cases = [
+            ("0", 0),
+            ("1023", 1023),
+            ("1,023 B", 1023),
+            ("1_000_000", 1_000_000),
+            ("1k", KIB),
+            ("1KiB", KIB),
+            ("1 KB", 1000),
+            ("1.5GB", 1_500_000_000),
+            ("1.5 GiB", GIB + GIB / 2),
+            ("10G", 10 * GIB),
+            ("500 mib", 500 * MIB),
+            ("  2 TB ", 2_000_000_000_000),
+            ("0.5 B", 1),
+            ("15 EiB", 15 << 60),
+            ("18446744073709551615", u64::MAX),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_size(text).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for text in [
+            "",
+            "GB",
+            "-1GB",
+            "1.GB",
+            ".5GB",
+            "1.5 XB",
+            "1 GiBs",
+            "1 kbit",
+            "abc",
+            "1e3",
+            "1 千B",
+            "16 EiB",
+            "18446744073709551616",
+            "19 EB",
+        ] {
+            assert!(parse_size(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_round_trips_formatted_sizes() {
+        let samples = [
+            0,
+            1,
+            1023,
+            1024,
+            1536,
+            10 * KIB - 40,
+            MIB - 1,
+            123_456_789,
+            3 * GIB,
+            999_500,
+            1 << 50,
+            15 << 60,
+        ];
+        for style in [ByteStyle::Binary, ByteStyle::Decimal] {
+            for bytes in samples {
+                let text = format_bytes(bytes, style);
+                let parsed = parse_size(&text).unwrap();
+                if bytes < style.base() as u64 {
+                    assert_eq!(parsed, bytes, "{}", text);
+                } else {
+                    // 一位小数或取整带来的误差不超过 5%
+                    let error = (parsed as f64 - bytes as f64).abs() / bytes as f64;
+                    assert!(error <= 0.05, "{} -> {} -> {}", bytes, text, parsed);
+                }
+                assert_eq!(format_bytes(parsed, style), text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_number_or_text() {
+        #[derive(Deserialize)]
+        struct Limits {
+            #[serde(deserialize_with = "deserialize_size")]
+            max: u64,
+        }
+        let parse = |toml: &str| toml::from_str::<Limits>(toml).map(|l| l.max);
+        assert_eq!(parse("max = 4096").unwrap(), 4096);
+        assert_eq!(parse("max = \"10GiB\"").unwrap(), 10 * GIB);
+        assert!(parse("max = \"lots\"").is_err());
+    }
+}
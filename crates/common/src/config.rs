@@ -1,6 +1,742 @@
-/// 应用配置
-#[derive(Debug, Clone, Default)]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{write_atomic, DiskAnalyzerError, Lang};
+
+/// 配置文件名，位于存储根目录（.disk-rookie）下
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 应用配置，持久化为 TOML。文件中未识别的键会保留在 `extra` 中，重写时原样写回
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
-    pub scan_depth: Option<usize>,
+    pub llm: LlmConfig,
+    pub scan: ScanConfig,
+    pub executor: ExecutorConfig,
+    pub telemetry: TelemetryConfig,
+    pub logging: LoggingConfig,
+    pub low_space: LowSpaceConfig,
+    pub upload: CloudUploadConfig,
+    pub ui: UiConfig,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// LLM 服务配置（OpenAI 兼容协议）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub provider: String,
+    pub api_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// 同时进行的 LLM 请求数上限，超出的请求排队
+    pub max_concurrent_requests: usize,
+    /// 排队等待的请求数上限，队列已满时新请求立即以 Busy 失败
+    pub max_queued_requests: usize,
+    /// 单个请求的超时（秒），不含排队时间
+    pub request_timeout_secs: u64,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: "openai".to_string(),
+            api_url: "https://api.openai.com/v1".to_string(),
+            api_key: String::new(),
+            model: "gpt-4o-mini".to_string(),
+            temperature: 0.0,
+            max_tokens: 2048,
+            max_concurrent_requests: 2,
+            max_queued_requests: 8,
+            request_timeout_secs: 120,
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 扫描默认参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig {
+    pub shallow_dirs: bool,
+    pub use_mft: bool,
+    pub max_depth: usize,
+    /// 除内置列表外，额外只计大小不递归的目录名
+    pub custom_shallow_dirs: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    /// 是否解析文件所有者并按所有者汇总占用（多用户机器上使用；会额外查询安全信息，默认关闭）
+    pub resolve_owners: bool,
+    /// 扫描线程数；不设置时机械硬盘为 2，其余为 min(可用核数, 8)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+    /// 后台扫描（定时扫描等）每秒最多读取的目录项数；交互式扫描不限速
+    pub background_entries_per_sec: u64,
+    /// 扫描项目目录时是否按各级 .gitignore 跳过被忽略的文件（仅目录遍历生效，MFT 扫描不支持）
+    pub respect_gitignore: bool,
+    /// 除 .gitignore 外额外读取的同格式忽略文件名，如 `.scanignore`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_file_name: Option<String>,
+    /// 是否在其他卷、网络共享的挂载点处停下；不设置时仅在扫描卷根时开启
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_filesystem_only: Option<bool>,
+    /// MFT 扫描时是否把 NTFS 备用数据流的大小计入文件与目录大小（默认只单独统计）
+    pub include_streams: bool,
+    /// 是否为 Treemap 按类型、按时间着色汇总各目录的主要文件类型与修改时间范围（多占一些内存，默认关闭）
+    pub treemap_rollups: bool,
+    /// 扫描完成后是否发送系统通知（交互式与定时扫描均适用）
+    pub notify_on_complete: bool,
+    /// 耗时短于该秒数的扫描不发送通知，0 表示总是通知
+    pub notify_min_duration_secs: u64,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            shallow_dirs: true,
+            use_mft: true,
+            max_depth: 10,
+            custom_shallow_dirs: Vec::new(),
+            exclude_patterns: Vec::new(),
+            resolve_owners: false,
+            concurrency: None,
+            background_entries_per_sec: 20_000,
+            respect_gitignore: false,
+            ignore_file_name: None,
+            same_filesystem_only: None,
+            include_streams: false,
+            treemap_rollups: false,
+            notify_on_complete: true,
+            notify_min_duration_secs: 10,
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 删除方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeleteMode {
+    /// 移入回收站/废纸篓
+    #[default]
+    Trash,
+    /// 永久删除
+    Permanent,
+}
+
+/// 计划动作的执行方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionMode {
+    /// 永久删除
+    Permanent,
+    /// 移入回收站/废纸篓，可以恢复
+    Trash,
+    /// 不自动执行，留给用户手动处理
+    Skip,
+}
+
+/// 按风险等级决定计划动作的执行方式：低风险默认移入回收站，开启快速模式后永久删除；
+/// 中风险总是移入回收站或跳过；高风险从不自动执行。`low`/`medium` 可覆盖对应等级的默认方式，
+/// `medium` 不能为 Permanent，`high` 只能为 Skip
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionPolicy {
+    /// 快速模式：低风险动作直接永久删除，不经过回收站
+    pub fast_mode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low: Option<ExecutionMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium: Option<ExecutionMode>,
+    /// 仅为与其他等级对称而保留；设为 Skip 以外的值会被校验拒绝，执行时也会忽略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high: Option<ExecutionMode>,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// 执行器配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutorConfig {
+    /// 单独删除（非计划动作）的方式
+    pub delete_mode: DeleteMode,
     pub dry_run: bool,
+    /// 单次计划释放空间超过该值（字节）时需要二次确认；也可以写作 `"10GiB"` 之类的字符串
+    #[serde(deserialize_with = "crate::byte_size::deserialize_size")]
+    pub confirm_threshold_bytes: u64,
+    /// 永不删除的路径（前缀匹配）
+    pub protected_paths: Vec<String>,
+    /// 计划动作按风险等级的执行方式
+    pub policy: ExecutionPolicy,
+    /// 执行删除动作前为小文件做快照
+    pub snapshot: SnapshotConfig,
+    /// 永久删除被拒绝时，除只读属性外也清除系统属性后重试（仅 Windows）
+    pub force_attributes: bool,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            delete_mode: DeleteMode::Trash,
+            dry_run: false,
+            confirm_threshold_bytes: 10 * 1024 * 1024 * 1024,
+            protected_paths: Vec::new(),
+            policy: ExecutionPolicy::default(),
+            snapshot: SnapshotConfig::default(),
+            force_attributes: false,
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 执行前快照：删除文档、代码、配置等类型的小文件前先复制到执行日志目录，回滚时可以恢复内容，
+/// 永久删除时同样有效
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub enabled: bool,
+    /// 只为不超过该大小（字节）的文件做快照；也可以写作 `"1MiB"` 之类的字符串
+    #[serde(deserialize_with = "crate::byte_size::deserialize_size")]
+    pub max_file_bytes: u64,
+    /// 一次执行中所有快照的总大小上限（字节），超出后不再做快照；也可以写作字符串
+    #[serde(deserialize_with = "crate::byte_size::deserialize_size")]
+    pub budget_bytes: u64,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_bytes: 1024 * 1024,
+            budget_bytes: 200 * 1024 * 1024,
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 遥测配置，默认关闭
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// 批量上报地址；为空时只在本地排队
+    pub endpoint: String,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// 日志配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// trace / debug / info / warn / error
+    pub level: String,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 低空间监控：定期检查各固定卷的剩余空间，低于阈值时提醒用户
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LowSpaceConfig {
+    pub enabled: bool,
+    /// 检查间隔（分钟）
+    pub check_interval_minutes: u64,
+    /// 剩余空间占总容量的百分比低于该值时视为空间不足
+    pub threshold_percent: f64,
+    /// 剩余空间低于该字节数时视为空间不足；也可以写作 `"10GiB"` 之类的字符串，0 表示只按百分比判断
+    #[serde(deserialize_with = "crate::byte_size::deserialize_size")]
+    pub threshold_bytes: u64,
+    /// 回差：触发后剩余空间需回升到阈值之上再加总容量的该百分比，才会重新提醒
+    pub rearm_margin_percent: f64,
+    /// 触发时是否在后台扫描该卷并做启发式分析，打开应用即可看到建议
+    pub auto_scan: bool,
+    /// 要监控的卷（如 `C:\`、`/`）；为空时监控所有固定卷
+    pub volumes: Vec<String>,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+impl Default for LowSpaceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_minutes: 30,
+            threshold_percent: 10.0,
+            threshold_bytes: 5 * 1024 * 1024 * 1024,
+            rearm_margin_percent: 2.0,
+            auto_scan: false,
+            volumes: Vec::new(),
+            extra: toml::Table::new(),
+        }
+    }
+}
+
+/// 云存储上传配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudUploadConfig {
+    /// 所有上传共享的带宽上限（字节/秒），0 表示不限；也可以写作 `"2MiB"` 之类的字符串
+    #[serde(deserialize_with = "crate::byte_size::deserialize_size")]
+    pub max_bytes_per_sec: u64,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// 界面配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// 后端需要自行渲染文本时（系统通知、OAuth 回调页面）使用的语言：`zh` / `en`
+    pub language: Lang,
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// 字段级校验错误
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFieldError {
+    /// 点分字段路径，如 `scan.max_depth`
+    pub field: String,
+    pub message: String,
+}
+
+impl AppConfig {
+    /// 从 TOML 文件加载；文件不存在时返回默认配置
+    pub fn load(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(DiskAnalyzerError::Io(e)),
+        };
+        Self::from_toml_str(&content)
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self, DiskAnalyzerError> {
+        toml::from_str(content).map_err(|e| DiskAnalyzerError::Config(e.to_string()))
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, DiskAnalyzerError> {
+        toml::to_string_pretty(self).map_err(|e| DiskAnalyzerError::Config(e.to_string()))
+    }
+
+    /// 校验后原子写入 TOML 文件
+    pub fn save(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        if let Err(errors) = self.validate() {
+            let msg = errors
+                .iter()
+                .map(|e| format!("{}: {}", e.field, e.message))
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(DiskAnalyzerError::Config(msg));
+        }
+        let content = self.to_toml_string()?;
+        write_atomic(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// 字段级校验，返回全部不合法字段
+    pub fn validate(&self) -> Result<(), Vec<ConfigFieldError>> {
+        let mut errors = Vec::new();
+        let mut push = |field: &str, message: &str| {
+            errors.push(ConfigFieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        };
+        if !(1..=64).contains(&self.scan.max_depth) {
+            push("scan.max_depth", "必须在 1 到 64 之间");
+        }
+        if self
+            .scan
+            .concurrency
+            .is_some_and(|n| !(1..=64).contains(&n))
+        {
+            push("scan.concurrency", "必须在 1 到 64 之间");
+        }
+        if self.scan.background_entries_per_sec == 0 {
+            push("scan.background_entries_per_sec", "必须大于 0");
+        }
+        if self
+            .scan
+            .custom_shallow_dirs
+            .iter()
+            .any(|d| d.trim().is_empty())
+        {
+            push("scan.custom_shallow_dirs", "目录名不能为空");
+        }
+        if self
+            .scan
+            .ignore_file_name
+            .as_deref()
+            .is_some_and(|n| n.trim().is_empty() || n.contains(['/', '\\']))
+        {
+            push("scan.ignore_file_name", "必须是不含路径分隔符的文件名");
+        }
+        if !(0.0..=2.0).contains(&self.llm.temperature) {
+            push("llm.temperature", "必须在 0 到 2 之间");
+        }
+        if self.llm.max_tokens == 0 {
+            push("llm.max_tokens", "必须大于 0");
+        }
+        if self.llm.max_concurrent_requests == 0 {
+            push("llm.max_concurrent_requests", "必须大于 0");
+        }
+        if self.llm.request_timeout_secs == 0 {
+            push("llm.request_timeout_secs", "必须大于 0");
+        }
+        if !self.llm.api_url.is_empty()
+            && !self.llm.api_url.starts_with("http://")
+            && !self.llm.api_url.starts_with("https://")
+        {
+            push("llm.api_url", "必须以 http:// 或 https:// 开头");
+        }
+        if self
+            .executor
+            .protected_paths
+            .iter()
+            .any(|p| p.trim().is_empty())
+        {
+            push("executor.protected_paths", "路径不能为空");
+        }
+        if self
+            .executor
+            .policy
+            .high
+            .is_some_and(|mode| mode != ExecutionMode::Skip)
+        {
+            push(
+                "executor.policy.high",
+                "高风险动作从不自动执行，只能为 Skip",
+            );
+        }
+        if self.executor.policy.medium == Some(ExecutionMode::Permanent) {
+            push(
+                "executor.policy.medium",
+                "中风险动作只能移入回收站或跳过，不能为 Permanent",
+            );
+        }
+        if !self.telemetry.endpoint.is_empty() && !self.telemetry.endpoint.starts_with("https://") {
+            push("telemetry.endpoint", "必须以 https:// 开头");
+        }
+        if !["trace", "debug", "info", "warn", "error"]
+            .contains(&self.logging.level.to_ascii_lowercase().as_str())
+        {
+            push("logging.level", "必须是 trace/debug/info/warn/error 之一");
+        }
+        if self.low_space.check_interval_minutes == 0 {
+            push("low_space.check_interval_minutes", "必须大于 0");
+        }
+        if !(0.0..=100.0).contains(&self.low_space.threshold_percent) {
+            push("low_space.threshold_percent", "必须在 0 到 100 之间");
+        }
+        if !(0.0..=50.0).contains(&self.low_space.rearm_margin_percent) {
+            push("low_space.rearm_margin_percent", "必须在 0 到 50 之间");
+        }
+        if self.low_space.volumes.iter().any(|v| v.trim().is_empty()) {
+            push("low_space.volumes", "路径不能为空");
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// 用 `previous` 中本配置没有的未知键补全，避免前端回传时丢失文件中的未知键
+    pub fn merge_unknown_keys_from(&mut self, previous: &AppConfig) {
+        fn merge(into: &mut toml::Table, from: &toml::Table) {
+            for (k, v) in from {
+                into.entry(k.clone()).or_insert_with(|| v.clone());
+            }
+        }
+        merge(&mut self.extra, &previous.extra);
+        merge(&mut self.llm.extra, &previous.llm.extra);
+        merge(&mut self.scan.extra, &previous.scan.extra);
+        merge(&mut self.executor.extra, &previous.executor.extra);
+        merge(
+            &mut self.executor.policy.extra,
+            &previous.executor.policy.extra,
+        );
+        merge(
+            &mut self.executor.snapshot.extra,
+            &previous.executor.snapshot.extra,
+        );
+        merge(&mut self.telemetry.extra, &previous.telemetry.extra);
+        merge(&mut self.logging.extra, &previous.logging.extra);
+        merge(&mut self.low_space.extra, &previous.low_space.extra);
+        merge(&mut self.upload.extra, &previous.upload.extra);
+        merge(&mut self.ui.extra, &previous.ui.extra);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WITH_UNKNOWN_KEYS: &str = r#"
+future_flag = true
+
+[scan]
+max_depth = 12
+use_mft = false
+experimental_walker = "fts"
+
+[executor]
+delete_mode = "Permanent"
+protected_paths = ["D:\\Work"]
+
+[plugins]
+name = "x"
+"#;
+
+    #[test]
+    fn test_round_trip_preserves_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        std::fs::write(&path, WITH_UNKNOWN_KEYS).unwrap();
+
+        let config = AppConfig::load(&path).unwrap();
+        assert_eq!(config.scan.max_depth, 12);
+        assert!(!config.scan.use_mft);
+        assert!(config.scan.shallow_dirs);
+        assert_eq!(config.executor.delete_mode, DeleteMode::Permanent);
+        config.save(&path).unwrap();
+
+        let reloaded = AppConfig::load(&path).unwrap();
+        assert_eq!(reloaded, config);
+        assert_eq!(
+            reloaded.extra.get("future_flag"),
+            Some(&toml::Value::Boolean(true))
+        );
+        assert!(reloaded.extra.contains_key("plugins"));
+        assert_eq!(
+            reloaded.scan.extra.get("experimental_walker"),
+            Some(&toml::Value::String("fts".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_missing_file_yields_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::load(&dir.path().join("none.toml")).unwrap();
+        assert_eq!(config, AppConfig::default());
+        assert!(!config.telemetry.enabled);
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        let mut config = AppConfig::default();
+        config.scan.max_depth = 0;
+        config.llm.api_url = "ftp://example.com".to_string();
+        config.llm.max_concurrent_requests = 0;
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "scan.max_depth",
+                "llm.max_concurrent_requests",
+                "llm.api_url"
+            ]
+        );
+
+        config.scan.max_depth = 65;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        assert!(matches!(
+            config.save(&path),
+            Err(DiskAnalyzerError::Config(_))
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_scan_concurrency_round_trip_and_range() {
+        let mut config = AppConfig::from_toml_str("[scan]\nconcurrency = 4\n").unwrap();
+        assert_eq!(config.scan.concurrency, Some(4));
+        assert!(config.validate().is_ok());
+        assert!(!AppConfig::default()
+            .to_toml_string()
+            .unwrap()
+            .contains("concurrency"));
+
+        config.scan.concurrency = Some(0);
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "scan.concurrency");
+    }
+
+    #[test]
+    fn test_scan_ignore_file_name() {
+        let mut config = AppConfig::from_toml_str(
+            "[scan]\nrespect_gitignore = true\nignore_file_name = \".scanignore\"\n",
+        )
+        .unwrap();
+        assert!(config.scan.respect_gitignore);
+        assert!(config.validate().is_ok());
+        assert!(!AppConfig::default()
+            .to_toml_string()
+            .unwrap()
+            .contains("ignore_file_name"));
+
+        config.scan.ignore_file_name = Some("sub/.scanignore".to_string());
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "scan.ignore_file_name");
+    }
+
+    #[test]
+    fn test_scan_notification_settings() {
+        let defaults = ScanConfig::default();
+        assert!(defaults.notify_on_complete);
+        assert_eq!(defaults.notify_min_duration_secs, 10);
+
+        let config = AppConfig::from_toml_str(
+            "[scan]\nnotify_on_complete = false\nnotify_min_duration_secs = 0\n",
+        )
+        .unwrap();
+        assert!(!config.scan.notify_on_complete);
+        assert_eq!(config.scan.notify_min_duration_secs, 0);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_confirm_threshold_accepts_size_string() {
+        let config =
+            AppConfig::from_toml_str("[executor]\nconfirm_threshold_bytes = \"1.5 GiB\"\n")
+                .unwrap();
+        assert_eq!(
+            config.executor.confirm_threshold_bytes,
+            3 * 512 * 1024 * 1024
+        );
+        // 写回时仍为字节数
+        assert!(config
+            .to_toml_string()
+            .unwrap()
+            .contains("confirm_threshold_bytes = 1610612736"));
+        assert_eq!(
+            AppConfig::from_toml_str("[executor]\nconfirm_threshold_bytes = 4096\n")
+                .unwrap()
+                .executor
+                .confirm_threshold_bytes,
+            4096
+        );
+        let err = AppConfig::from_toml_str("[executor]\nconfirm_threshold_bytes = \"huge\"\n")
+            .unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::Config(_)));
+    }
+
+    #[test]
+    fn test_snapshot_settings() {
+        let snapshot = AppConfig::default().executor.snapshot;
+        assert!(snapshot.enabled);
+        assert_eq!(snapshot.max_file_bytes, 1024 * 1024);
+        assert_eq!(snapshot.budget_bytes, 200 * 1024 * 1024);
+        let config = AppConfig::from_toml_str(
+            "[executor.snapshot]\nenabled = false\nbudget_bytes = \"50MiB\"\n",
+        )
+        .unwrap();
+        assert!(!config.executor.snapshot.enabled);
+        assert_eq!(config.executor.snapshot.budget_bytes, 50 * 1024 * 1024);
+        assert_eq!(config.executor.snapshot.max_file_bytes, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_ui_language_defaults_to_chinese() {
+        assert_eq!(AppConfig::default().ui.language, Lang::Zh);
+        let config = AppConfig::from_toml_str("[ui]\nlanguage = \"en\"\n").unwrap();
+        assert_eq!(config.ui.language, Lang::En);
+        assert!(AppConfig::from_toml_str("[ui]\nlanguage = \"fr\"\n").is_err());
+    }
+
+    #[test]
+    fn test_execution_policy_round_trip_and_high_override() {
+        let mut config =
+            AppConfig::from_toml_str("[executor.policy]\nfast_mode = true\nmedium = \"Skip\"\n")
+                .unwrap();
+        let policy = &config.executor.policy;
+        assert!(policy.fast_mode);
+        assert_eq!(policy.low, None);
+        assert_eq!(policy.medium, Some(ExecutionMode::Skip));
+        assert!(config.validate().is_ok());
+        let text = config.to_toml_string().unwrap();
+        let table: toml::Table = text.parse().unwrap();
+        let written = table["executor"]["policy"].as_table().unwrap();
+        assert!(!written.contains_key("low"));
+        assert!(!written.contains_key("high"));
+        assert_eq!(AppConfig::from_toml_str(&text).unwrap(), config);
+
+        config.executor.policy.high = Some(ExecutionMode::Skip);
+        assert!(config.validate().is_ok());
+        config.executor.policy.high = Some(ExecutionMode::Permanent);
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "executor.policy.high");
+
+        config.executor.policy.high = None;
+        config.executor.policy.medium = Some(ExecutionMode::Trash);
+        assert!(config.validate().is_ok());
+        config.executor.policy.medium = Some(ExecutionMode::Permanent);
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors[0].field, "executor.policy.medium");
+    }
+
+    #[test]
+    fn test_low_space_settings() {
+        let config = AppConfig::from_toml_str(
+            "[low_space]\nthreshold_bytes = \"20GiB\"\nauto_scan = true\nvolumes = [\"D:\\\\\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.low_space.threshold_bytes, 20 * 1024 * 1024 * 1024);
+        assert!(config.low_space.auto_scan);
+        assert_eq!(config.low_space.volumes, vec!["D:\\".to_string()]);
+        assert_eq!(config.low_space.threshold_percent, 10.0);
+        assert_eq!(config.upload.max_bytes_per_sec, 0);
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config.low_space.check_interval_minutes = 0;
+        config.low_space.threshold_percent = 120.0;
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "low_space.check_interval_minutes",
+                "low_space.threshold_percent"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_type_is_config_error() {
+        let err = AppConfig::from_toml_str("[scan]\nmax_depth = \"deep\"\n").unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::Config(_)));
+    }
+
+    #[test]
+    fn test_merge_unknown_keys() {
+        let previous = AppConfig::from_toml_str(WITH_UNKNOWN_KEYS).unwrap();
+        let mut incoming = AppConfig::default();
+        incoming.merge_unknown_keys_from(&previous);
+        assert!(incoming.extra.contains_key("future_flag"));
+        assert!(incoming.scan.extra.contains_key("experimental_walker"));
+    }
 }
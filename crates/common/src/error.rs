@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::{localize, Lang, MessageId};
+
 #[derive(Error, Debug)]
 pub enum DiskAnalyzerError {
     #[error("IO error: {0}")]
@@ -13,4 +18,195 @@ pub enum DiskAnalyzerError {
 
     #[error("Configuration error: {0}")]
     Config(String),
+
+    /// 需要管理员权限（如读取 $MFT）
+    #[error("Elevation required: {0}")]
+    NeedsElevation(String),
+
+    /// 上传到云存储失败
+    #[error("Upload failed: {0}")]
+    Upload(String),
+
+    /// 云端文件与本地文件的大小或校验和不一致
+    #[error("Verification failed: {0}")]
+    VerificationFailed(String),
+}
+
+/// 命令错误码：前端据此决定重试、弹窗或请求提权，而不是匹配错误文本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    PermissionDenied,
+    PathNotFound,
+    /// 路径不在所引用的扫描结果中
+    NotInScan,
+    InvalidInput,
+    NeedsElevation,
+    OAuthCancelled,
+    OAuthTimeout,
+    OAuthFailed,
+    /// 云存储/服务端拒绝当前凭据，需要刷新 token 或重新授权
+    Unauthorized,
+    /// refresh token 已被撤销或过期，刷新不会成功，只能重新授权
+    ReauthRequired,
+    NetworkTimeout,
+    Network,
+    LlmUnavailable,
+    /// 同时进行的请求已达上限且排队已满，稍后重试
+    Busy,
+    PlanConflict,
+    Cancelled,
+    /// 上传后云端文件的大小或校验和与本地不一致
+    ChecksumMismatch,
+    /// 乐观并发写入时文档版本与预期不一致
+    VersionConflict,
+    /// 操作超出需要二次确认的阈值，用户确认后带确认标记重试
+    ConfirmationRequired,
+    Io,
+    Config,
+    Internal,
+}
+
+/// Tauri 命令统一返回的错误类型，序列化为 `{ code, message, message_id?, params?, details? }`。
+/// 带 `message_id` 的错误由前端按 ID 与 `params` 本地化，`message` 为默认语言的渲染结果
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+#[error("{message}")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    /// 面向用户的可读信息
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<MessageId>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            message_id: None,
+            params: BTreeMap::new(),
+            details: None,
+        }
+    }
+
+    /// 以消息 ID 构造，`message` 按默认语言渲染
+    pub fn localized(code: ErrorCode, id: MessageId, params: &[(&str, &str)]) -> Self {
+        Self {
+            code,
+            message: localize(id, Lang::default(), params),
+            message_id: Some(id),
+            params: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            details: None,
+        }
+    }
+
+    /// 以消息 ID 构造的 IO 错误，错误码按 ErrorKind 推断，错误原因作为 `error` 参数
+    pub fn io_localized(id: MessageId, params: &[(&str, &str)], e: &std::io::Error) -> Self {
+        let error = e.to_string();
+        let mut params = params.to_vec();
+        params.push(("error", &error));
+        Self::localized(io_error_code(e), id, &params)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    /// 带上下文的 IO 错误，错误码按 ErrorKind 推断
+    pub fn io(context: &str, e: &std::io::Error) -> Self {
+        Self::new(io_error_code(e), format!("{}: {}", context, e))
+    }
+}
+
+fn io_error_code(e: &std::io::Error) -> ErrorCode {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => ErrorCode::PathNotFound,
+        std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+        _ => ErrorCode::Io,
+    }
+}
+
+impl From<DiskAnalyzerError> for CommandError {
+    fn from(e: DiskAnalyzerError) -> Self {
+        let code = match &e {
+            DiskAnalyzerError::Io(io) => match io.kind() {
+                std::io::ErrorKind::NotFound => ErrorCode::PathNotFound,
+                std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                _ => ErrorCode::Io,
+            },
+            DiskAnalyzerError::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            // 扫描器对不存在或无法解析的路径统一返回 InvalidPath
+            DiskAnalyzerError::InvalidPath(_) => ErrorCode::PathNotFound,
+            DiskAnalyzerError::Config(_) => ErrorCode::Config,
+            DiskAnalyzerError::NeedsElevation(_) => ErrorCode::NeedsElevation,
+            DiskAnalyzerError::Upload(_) => ErrorCode::Network,
+            DiskAnalyzerError::VerificationFailed(_) => ErrorCode::ChecksumMismatch,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(e: std::io::Error) -> Self {
+        DiskAnalyzerError::Io(e).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_errors_map_to_codes() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(CommandError::from(missing).code, ErrorCode::PathNotFound);
+        let err: CommandError =
+            DiskAnalyzerError::NeedsElevation("NTFS volume access".into()).into();
+        assert_eq!(err.code, ErrorCode::NeedsElevation);
+        let err: CommandError = DiskAnalyzerError::PermissionDenied("C:\\x".into()).into();
+        assert_eq!(err.code, ErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let err = CommandError::new(ErrorCode::PathNotFound, "路径不存在: /x");
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "PathNotFound");
+        assert_eq!(json["message"], "路径不存在: /x");
+        assert!(json.get("details").is_none());
+        assert!(json.get("message_id").is_none());
+        assert!(json.get("params").is_none());
+    }
+
+    #[test]
+    fn test_localized_error_carries_id_and_params() {
+        let err = CommandError::localized(
+            ErrorCode::InvalidInput,
+            MessageId::ScanNotFound,
+            &[("scan_id", "s-1")],
+        );
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["message"], "扫描结果不存在或已过期: s-1");
+        assert_eq!(json["message_id"], "error.scan_not_found");
+        assert_eq!(json["params"]["scan_id"], "s-1");
+
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err =
+            CommandError::io_localized(MessageId::PathUnresolvable, &[("path", "/x")], &denied);
+        assert_eq!(err.code, ErrorCode::PermissionDenied);
+        assert_eq!(err.params["error"], "denied");
+        assert_eq!(err.message, "无法解析路径 /x: denied");
+    }
 }
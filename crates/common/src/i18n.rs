@@ -0,0 +1,585 @@
+//! 后端文案的多语言支持：面向用户的文本以稳定的消息 ID 表示，前端按 ID 和参数自行本地化；
+//! 必须在 Rust 中渲染文本的地方（系统通知、OAuth 回调页面、执行汇总）用 [`localize`] 从内置的中英文表中取文案。
+//!
+//! 模板中的 `{name}` 在渲染时替换为同名参数；缺少的参数原样保留，便于发现遗漏。
+
+use serde::{Deserialize, Serialize};
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+/// 面向用户的后端文案 ID，序列化为稳定的点分标识，前端据此查找翻译
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MessageId {
+    // 命令错误
+    #[serde(rename = "error.path_not_found")]
+    PathNotFound,
+    #[serde(rename = "error.path_unresolvable")]
+    PathUnresolvable,
+    #[serde(rename = "error.not_a_virtual_disk")]
+    NotAVirtualDisk,
+    #[serde(rename = "error.empty_file_list")]
+    EmptyFileList,
+    #[serde(rename = "error.scan_not_found")]
+    ScanNotFound,
+    #[serde(rename = "error.scan_not_running")]
+    ScanNotRunning,
+    #[serde(rename = "error.scan_input_missing")]
+    ScanInputMissing,
+    #[serde(rename = "error.not_in_scan")]
+    NotInScan,
+    #[serde(rename = "error.open_file_manager_failed")]
+    OpenFileManagerFailed,
+    #[serde(rename = "error.oauth_callback_server")]
+    OAuthCallbackServer,
+    #[serde(rename = "error.oauth_provider")]
+    OAuthProviderError,
+    #[serde(rename = "error.oauth_state_mismatch")]
+    OAuthStateMismatch,
+    #[serde(rename = "error.oauth_timeout")]
+    OAuthTimeout,
+    #[serde(rename = "error.oauth_cancelled")]
+    OAuthCancelled,
+    #[serde(rename = "error.open_browser_failed")]
+    OpenBrowserFailed,
+    #[serde(rename = "error.oauth_callback_failed")]
+    OAuthCallbackFailed,
+    #[serde(rename = "error.token_request_failed")]
+    TokenRequestFailed,
+    #[serde(rename = "error.token_read_failed")]
+    TokenReadFailed,
+    #[serde(rename = "error.token_parse_failed")]
+    TokenParseFailed,
+    #[serde(rename = "error.reauth_required")]
+    ReauthRequired,
+    #[serde(rename = "error.token_missing_access")]
+    TokenMissingAccess,
+    #[serde(rename = "error.token_missing_refresh")]
+    TokenMissingRefresh,
+    #[serde(rename = "error.user_info_failed")]
+    UserInfoFailed,
+    #[serde(rename = "error.check_timed_out")]
+    CheckTimedOut,
+    #[serde(rename = "error.llm_busy")]
+    LlmBusy,
+
+    // OAuth 回调页面
+    #[serde(rename = "oauth_page.success_title")]
+    OAuthPageSuccessTitle,
+    #[serde(rename = "oauth_page.success_message")]
+    OAuthPageSuccessMessage,
+    #[serde(rename = "oauth_page.success_hint")]
+    OAuthPageSuccessHint,
+    #[serde(rename = "oauth_page.denied_title")]
+    OAuthPageDeniedTitle,
+    #[serde(rename = "oauth_page.failed_title")]
+    OAuthPageFailedTitle,
+    #[serde(rename = "oauth_page.state_mismatch")]
+    OAuthPageStateMismatch,
+    #[serde(rename = "oauth_page.unknown_error")]
+    OAuthPageUnknownError,
+    #[serde(rename = "oauth_page.retry_hint")]
+    OAuthPageRetryHint,
+
+    // 系统通知
+    #[serde(rename = "notification.scan_complete_title")]
+    ScanCompleteTitle,
+    #[serde(rename = "notification.scan_complete_body")]
+    ScanCompleteBody,
+    #[serde(rename = "notification.low_space_title")]
+    LowSpaceTitle,
+    #[serde(rename = "notification.low_space_body")]
+    LowSpaceBody,
+    #[serde(rename = "notification.scheduled_execution_title")]
+    ScheduledExecutionTitle,
+    #[serde(rename = "notification.scheduled_execution_body")]
+    ScheduledExecutionBody,
+    #[serde(rename = "notification.scheduled_execution_failed_body")]
+    ScheduledExecutionFailedBody,
+
+    // 执行汇总，各部分只在计数大于 0 时出现
+    #[serde(rename = "execution.offloaded")]
+    ExecutionOffloaded,
+    #[serde(rename = "execution.deleted")]
+    ExecutionDeleted,
+    #[serde(rename = "execution.moved")]
+    ExecutionMoved,
+    #[serde(rename = "execution.trash_emptied")]
+    ExecutionTrashEmptied,
+    #[serde(rename = "execution.vhd_compacted")]
+    ExecutionVhdCompacted,
+    #[serde(rename = "execution.skipped")]
+    ExecutionSkipped,
+    #[serde(rename = "execution.targets_changed")]
+    ExecutionTargetsChanged,
+    #[serde(rename = "execution.nothing_done")]
+    ExecutionNothingDone,
+
+    // 扫描进度阶段，见 ProgressPhase
+    #[serde(rename = "phase.scan.resolving_owners")]
+    PhaseResolvingOwners,
+    #[serde(rename = "phase.scan.paused")]
+    PhasePaused,
+    #[serde(rename = "phase.scan.resumed")]
+    PhaseResumed,
+    #[serde(rename = "phase.scan.mft_fallback")]
+    PhaseMftFallback,
+    #[serde(rename = "phase.mft.opening_volume")]
+    PhaseMftOpeningVolume,
+    #[serde(rename = "phase.mft.volume_busy")]
+    PhaseMftVolumeBusy,
+    #[serde(rename = "phase.mft.building_tree")]
+    PhaseMftBuildingTree,
+}
+
+impl MessageId {
+    /// 全部消息 ID
+    pub const ALL: &'static [MessageId] = &[
+        MessageId::PathNotFound,
+        MessageId::PathUnresolvable,
+        MessageId::NotAVirtualDisk,
+        MessageId::EmptyFileList,
+        MessageId::ScanNotFound,
+        MessageId::ScanNotRunning,
+        MessageId::ScanInputMissing,
+        MessageId::NotInScan,
+        MessageId::OpenFileManagerFailed,
+        MessageId::OAuthCallbackServer,
+        MessageId::OAuthProviderError,
+        MessageId::OAuthStateMismatch,
+        MessageId::OAuthTimeout,
+        MessageId::OAuthCancelled,
+        MessageId::OpenBrowserFailed,
+        MessageId::OAuthCallbackFailed,
+        MessageId::TokenRequestFailed,
+        MessageId::TokenReadFailed,
+        MessageId::TokenParseFailed,
+        MessageId::ReauthRequired,
+        MessageId::TokenMissingAccess,
+        MessageId::TokenMissingRefresh,
+        MessageId::UserInfoFailed,
+        MessageId::CheckTimedOut,
+        MessageId::LlmBusy,
+        MessageId::OAuthPageSuccessTitle,
+        MessageId::OAuthPageSuccessMessage,
+        MessageId::OAuthPageSuccessHint,
+        MessageId::OAuthPageDeniedTitle,
+        MessageId::OAuthPageFailedTitle,
+        MessageId::OAuthPageStateMismatch,
+        MessageId::OAuthPageUnknownError,
+        MessageId::OAuthPageRetryHint,
+        MessageId::ScanCompleteTitle,
+        MessageId::ScanCompleteBody,
+        MessageId::LowSpaceTitle,
+        MessageId::LowSpaceBody,
+        MessageId::ScheduledExecutionTitle,
+        MessageId::ScheduledExecutionBody,
+        MessageId::ScheduledExecutionFailedBody,
+        MessageId::ExecutionOffloaded,
+        MessageId::ExecutionDeleted,
+        MessageId::ExecutionMoved,
+        MessageId::ExecutionTrashEmptied,
+        MessageId::ExecutionVhdCompacted,
+        MessageId::ExecutionSkipped,
+        MessageId::ExecutionTargetsChanged,
+        MessageId::ExecutionNothingDone,
+        MessageId::PhaseResolvingOwners,
+        MessageId::PhasePaused,
+        MessageId::PhaseResumed,
+        MessageId::PhaseMftFallback,
+        MessageId::PhaseMftOpeningVolume,
+        MessageId::PhaseMftVolumeBusy,
+        MessageId::PhaseMftBuildingTree,
+    ];
+
+    /// 稳定标识，与序列化结果一致
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageId::PathNotFound => "error.path_not_found",
+            MessageId::PathUnresolvable => "error.path_unresolvable",
+            MessageId::NotAVirtualDisk => "error.not_a_virtual_disk",
+            MessageId::EmptyFileList => "error.empty_file_list",
+            MessageId::ScanNotFound => "error.scan_not_found",
+            MessageId::ScanNotRunning => "error.scan_not_running",
+            MessageId::ScanInputMissing => "error.scan_input_missing",
+            MessageId::NotInScan => "error.not_in_scan",
+            MessageId::OpenFileManagerFailed => "error.open_file_manager_failed",
+            MessageId::OAuthCallbackServer => "error.oauth_callback_server",
+            MessageId::OAuthProviderError => "error.oauth_provider",
+            MessageId::OAuthStateMismatch => "error.oauth_state_mismatch",
+            MessageId::OAuthTimeout => "error.oauth_timeout",
+            MessageId::OAuthCancelled => "error.oauth_cancelled",
+            MessageId::OpenBrowserFailed => "error.open_browser_failed",
+            MessageId::OAuthCallbackFailed => "error.oauth_callback_failed",
+            MessageId::TokenRequestFailed => "error.token_request_failed",
+            MessageId::TokenReadFailed => "error.token_read_failed",
+            MessageId::TokenParseFailed => "error.token_parse_failed",
+            MessageId::ReauthRequired => "error.reauth_required",
+            MessageId::TokenMissingAccess => "error.token_missing_access",
+            MessageId::TokenMissingRefresh => "error.token_missing_refresh",
+            MessageId::UserInfoFailed => "error.user_info_failed",
+            MessageId::CheckTimedOut => "error.check_timed_out",
+            MessageId::LlmBusy => "error.llm_busy",
+            MessageId::OAuthPageSuccessTitle => "oauth_page.success_title",
+            MessageId::OAuthPageSuccessMessage => "oauth_page.success_message",
+            MessageId::OAuthPageSuccessHint => "oauth_page.success_hint",
+            MessageId::OAuthPageDeniedTitle => "oauth_page.denied_title",
+            MessageId::OAuthPageFailedTitle => "oauth_page.failed_title",
+            MessageId::OAuthPageStateMismatch => "oauth_page.state_mismatch",
+            MessageId::OAuthPageUnknownError => "oauth_page.unknown_error",
+            MessageId::OAuthPageRetryHint => "oauth_page.retry_hint",
+            MessageId::ScanCompleteTitle => "notification.scan_complete_title",
+            MessageId::ScanCompleteBody => "notification.scan_complete_body",
+            MessageId::LowSpaceTitle => "notification.low_space_title",
+            MessageId::LowSpaceBody => "notification.low_space_body",
+            MessageId::ScheduledExecutionTitle => "notification.scheduled_execution_title",
+            MessageId::ScheduledExecutionBody => "notification.scheduled_execution_body",
+            MessageId::ScheduledExecutionFailedBody => {
+                "notification.scheduled_execution_failed_body"
+            }
+            MessageId::ExecutionOffloaded => "execution.offloaded",
+            MessageId::ExecutionDeleted => "execution.deleted",
+            MessageId::ExecutionMoved => "execution.moved",
+            MessageId::ExecutionTrashEmptied => "execution.trash_emptied",
+            MessageId::ExecutionVhdCompacted => "execution.vhd_compacted",
+            MessageId::ExecutionSkipped => "execution.skipped",
+            MessageId::ExecutionTargetsChanged => "execution.targets_changed",
+            MessageId::ExecutionNothingDone => "execution.nothing_done",
+            MessageId::PhaseResolvingOwners => "phase.scan.resolving_owners",
+            MessageId::PhasePaused => "phase.scan.paused",
+            MessageId::PhaseResumed => "phase.scan.resumed",
+            MessageId::PhaseMftFallback => "phase.scan.mft_fallback",
+            MessageId::PhaseMftOpeningVolume => "phase.mft.opening_volume",
+            MessageId::PhaseMftVolumeBusy => "phase.mft.volume_busy",
+            MessageId::PhaseMftBuildingTree => "phase.mft.building_tree",
+        }
+    }
+
+    /// 指定语言的模板
+    pub fn template(self, lang: Lang) -> &'static str {
+        let (en, zh) = self.templates();
+        match lang {
+            Lang::En => en,
+            Lang::Zh => zh,
+        }
+    }
+
+    /// 中英文模板；新增 ID 时必须同时给出两种语言
+    fn templates(self) -> (&'static str, &'static str) {
+        match self {
+            MessageId::PathNotFound => ("Path not found: {path}", "路径不存在: {path}"),
+            MessageId::PathUnresolvable => (
+                "Cannot resolve path {path}: {error}",
+                "无法解析路径 {path}: {error}",
+            ),
+            MessageId::NotAVirtualDisk => (
+                "Not a virtual disk file: {path}",
+                "不是虚拟磁盘文件: {path}",
+            ),
+            MessageId::EmptyFileList => ("The file list must not be empty", "文件列表不能为空"),
+            MessageId::ScanNotFound => (
+                "Scan result not found or expired: {scan_id}",
+                "扫描结果不存在或已过期: {scan_id}",
+            ),
+            MessageId::ScanNotRunning => (
+                "Scan not found or already finished: {scan_id}",
+                "扫描不存在或已结束: {scan_id}",
+            ),
+            MessageId::ScanInputMissing => (
+                "Either scan_id or scan_result is required",
+                "需要提供 scan_id 或 scan_result",
+            ),
+            MessageId::NotInScan => (
+                "Path is not part of this scan: {path}",
+                "路径不在该次扫描中: {path}",
+            ),
+            MessageId::OpenFileManagerFailed => (
+                "Failed to open the file manager: {error}",
+                "打开文件管理器失败: {error}",
+            ),
+            MessageId::OAuthCallbackServer => (
+                "Cannot start the local callback server",
+                "无法启动本地回调服务器",
+            ),
+            MessageId::OAuthProviderError => (
+                "OAuth error: {error} - {description}",
+                "OAuth 错误: {error} - {description}",
+            ),
+            MessageId::OAuthStateMismatch => (
+                "State verification failed, the request may be a CSRF attack",
+                "State 验证失败，可能存在 CSRF 攻击",
+            ),
+            MessageId::OAuthTimeout => ("OAuth authorization timed out", "OAuth 授权超时"),
+            MessageId::OAuthCancelled => ("OAuth authorization was cancelled", "OAuth 授权已取消"),
+            MessageId::OpenBrowserFailed => (
+                "Cannot open the browser: {error}",
+                "无法打开浏览器: {error}",
+            ),
+            MessageId::OAuthCallbackFailed => (
+                "Waiting for the callback failed: {error}",
+                "等待回调失败: {error}",
+            ),
+            MessageId::TokenRequestFailed => {
+                ("Token request failed: {error}", "Token 请求失败: {error}")
+            }
+            MessageId::TokenReadFailed => (
+                "Failed to read the token response: {error}",
+                "读取 token 响应失败: {error}",
+            ),
+            MessageId::TokenParseFailed => (
+                "Failed to parse the token response: {error}",
+                "解析 token 响应失败: {error}",
+            ),
+            MessageId::ReauthRequired => (
+                "The {provider} sign-in has expired, please authorize again: {error}",
+                "{provider} 的登录已失效，请重新授权: {error}",
+            ),
+            MessageId::TokenMissingAccess => (
+                "{provider} did not return an access_token, please authorize again",
+                "{provider} 未返回 access_token，请重新授权",
+            ),
+            MessageId::TokenMissingRefresh => (
+                "{provider} did not return a refresh_token — revoke the app's access in your {provider} account settings and authorize again",
+                "{provider} 未返回 refresh_token —— 请在 {provider} 账户设置中移除本应用的访问权限后重新授权",
+            ),
+            MessageId::UserInfoFailed => (
+                "Failed to fetch account info: {error}",
+                "获取用户信息失败: {error}",
+            ),
+            MessageId::CheckTimedOut => (
+                "No response within {seconds} seconds",
+                "{seconds} 秒内未响应",
+            ),
+            MessageId::LlmBusy => (
+                "Analysis is busy, please try again later",
+                "分析繁忙，请稍后重试",
+            ),
+            MessageId::OAuthPageSuccessTitle => ("Authorization successful", "授权成功"),
+            MessageId::OAuthPageSuccessMessage => (
+                "Your AI disk cleaner is now connected",
+                "您的 AI 磁盘清理工具已激活",
+            ),
+            MessageId::OAuthPageSuccessHint => (
+                "You can close this window and return to the app",
+                "现在可以关闭此窗口返回应用",
+            ),
+            MessageId::OAuthPageDeniedTitle => ("Authorization denied", "授权已拒绝"),
+            MessageId::OAuthPageFailedTitle => ("Authorization failed", "授权失败"),
+            MessageId::OAuthPageStateMismatch => (
+                "State verification failed, the request may have been forged",
+                "state 校验失败，请求可能被伪造",
+            ),
+            MessageId::OAuthPageUnknownError => ("Unknown error", "未知错误"),
+            MessageId::OAuthPageRetryHint => (
+                "Please close this window and try again from the app",
+                "请关闭此窗口，返回应用后重试",
+            ),
+            MessageId::ScanCompleteTitle => ("Scan complete", "扫描完成"),
+            MessageId::ScanCompleteBody => (
+                "{path} scanned: {used} used, about {reclaimable} can be freed",
+                "{path} 扫描完成：已用 {used}，预计可释放 {reclaimable}",
+            ),
+            MessageId::LowSpaceTitle => ("Low disk space", "磁盘空间不足"),
+            MessageId::LowSpaceBody => (
+                "Only {free} ({percent}%) left on {volume}",
+                "{volume} 仅剩 {free}（{percent}%）",
+            ),
+            MessageId::ScheduledExecutionTitle => ("Scheduled cleanup finished", "定时清理已完成"),
+            MessageId::ScheduledExecutionBody => (
+                "{executed} actions done, {freed} freed",
+                "已执行 {executed} 个动作，释放 {freed}",
+            ),
+            MessageId::ScheduledExecutionFailedBody => (
+                "Stopped after {executed} actions: {error}",
+                "执行 {executed} 个动作后中断：{error}",
+            ),
+            MessageId::ExecutionOffloaded => (
+                "{count} files offloaded to the cloud",
+                "已转存 {count} 个文件到云端",
+            ),
+            MessageId::ExecutionDeleted => ("{count} items deleted", "删除 {count} 项"),
+            MessageId::ExecutionMoved => ("{count} items moved", "移动 {count} 项"),
+            MessageId::ExecutionTrashEmptied => (
+                "{count} trash folders emptied",
+                "清空 {count} 个废纸篓",
+            ),
+            MessageId::ExecutionVhdCompacted => (
+                "{count} virtual disks compacted",
+                "压缩 {count} 个虚拟磁盘",
+            ),
+            MessageId::ExecutionSkipped => (
+                "{count} actions skipped because they are not run automatically",
+                "跳过 {count} 个不自动执行的动作",
+            ),
+            MessageId::ExecutionTargetsChanged => (
+                "{count} actions not run because their targets changed after the scan",
+                "{count} 个动作的目标在扫描后已改变，未执行",
+            ),
+            MessageId::ExecutionNothingDone => ("No actions were executed", "没有执行任何动作"),
+            MessageId::PhaseResolvingOwners => {
+                ("Resolving file owners...", "正在解析文件所有者...")
+            }
+            MessageId::PhasePaused => ("Scan paused", "扫描已暂停"),
+            MessageId::PhaseResumed => ("Scan resumed", "扫描已继续"),
+            MessageId::PhaseMftFallback => (
+                "MFT unavailable, falling back to a directory walk",
+                "MFT 不可用，改用目录遍历",
+            ),
+            MessageId::PhaseMftOpeningVolume => ("Opening volume...", "正在打开卷..."),
+            MessageId::PhaseMftVolumeBusy => ("Volume busy, retrying...", "卷正忙，正在重试..."),
+            MessageId::PhaseMftBuildingTree => {
+                ("Building the directory tree...", "正在构建目录树...")
+            }
+        }
+    }
+}
+
+/// 按语言渲染消息，`params` 中的值替换模板里的同名 `{name}`
+pub fn localize(id: MessageId, lang: Lang, params: &[(&str, &str)]) -> String {
+    let mut text = id.template(lang).to_string();
+    for (name, value) in params {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+/// 扫描进度中的阶段提示。进度回调的文本参数平时是当前路径，阶段提示以 [`ProgressPhase::marker`]
+/// 的形式传递，桌面端识别后按界面语言渲染，并把稳定标识一并发给前端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProgressPhase {
+    #[serde(rename = "scan.resolving_owners")]
+    ResolvingOwners,
+    #[serde(rename = "scan.paused")]
+    Paused,
+    #[serde(rename = "scan.resumed")]
+    Resumed,
+    #[serde(rename = "scan.mft_fallback")]
+    MftFallback,
+    #[serde(rename = "mft.opening_volume")]
+    MftOpeningVolume,
+    #[serde(rename = "mft.volume_busy")]
+    MftVolumeBusy,
+    #[serde(rename = "mft.building_tree")]
+    MftBuildingTree,
+}
+
+impl ProgressPhase {
+    pub const ALL: &'static [ProgressPhase] = &[
+        ProgressPhase::ResolvingOwners,
+        ProgressPhase::Paused,
+        ProgressPhase::Resumed,
+        ProgressPhase::MftFallback,
+        ProgressPhase::MftOpeningVolume,
+        ProgressPhase::MftVolumeBusy,
+        ProgressPhase::MftBuildingTree,
+    ];
+
+    /// 对应的文案
+    pub fn message_id(self) -> MessageId {
+        match self {
+            ProgressPhase::ResolvingOwners => MessageId::PhaseResolvingOwners,
+            ProgressPhase::Paused => MessageId::PhasePaused,
+            ProgressPhase::Resumed => MessageId::PhaseResumed,
+            ProgressPhase::MftFallback => MessageId::PhaseMftFallback,
+            ProgressPhase::MftOpeningVolume => MessageId::PhaseMftOpeningVolume,
+            ProgressPhase::MftVolumeBusy => MessageId::PhaseMftVolumeBusy,
+            ProgressPhase::MftBuildingTree => MessageId::PhaseMftBuildingTree,
+        }
+    }
+
+    /// 稳定标识：去掉 `phase.` 前缀的消息 ID
+    pub fn as_str(self) -> &'static str {
+        let id = self.message_id().as_str();
+        id.strip_prefix("phase.").unwrap_or(id)
+    }
+
+    /// 在进度回调中传递的文本，如 `[scan.paused]`；路径不会以 `[` 开头，不会与之混淆
+    pub fn marker(self) -> String {
+        format!("[{}]", self.as_str())
+    }
+
+    /// 识别进度文本中的阶段提示
+    pub fn from_marker(text: &str) -> Option<ProgressPhase> {
+        let id = text.strip_prefix('[')?.strip_suffix(']')?;
+        Self::ALL.iter().copied().find(|phase| phase.as_str() == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模板中的参数名，按出现顺序
+    fn placeholders(template: &str) -> Vec<&str> {
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_message_has_both_languages_with_same_params() {
+        let mut ids = std::collections::HashSet::new();
+        for &id in MessageId::ALL {
+            assert!(ids.insert(id.as_str()), "重复的消息 ID: {}", id.as_str());
+            let (en, zh) = (id.template(Lang::En), id.template(Lang::Zh));
+            assert!(!en.trim().is_empty(), "{} 缺少英文", id.as_str());
+            assert!(!zh.trim().is_empty(), "{} 缺少中文", id.as_str());
+            assert!(
+                !en.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)),
+                "{} 的英文模板含中文",
+                id.as_str()
+            );
+            let mut en_params = placeholders(en);
+            let mut zh_params = placeholders(zh);
+            en_params.sort_unstable();
+            zh_params.sort_unstable();
+            assert_eq!(en_params, zh_params, "{} 两种语言的参数不一致", id.as_str());
+            assert_eq!(
+                serde_json::to_value(id).unwrap(),
+                serde_json::json!(id.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_localize_substitutes_params() {
+        let params = [("scan_id", "scan-7")];
+        assert_eq!(
+            localize(MessageId::ScanNotFound, Lang::Zh, &params),
+            "扫描结果不存在或已过期: scan-7"
+        );
+        assert_eq!(
+            localize(MessageId::ScanNotFound, Lang::En, &params),
+            "Scan result not found or expired: scan-7"
+        );
+        // 缺少的参数原样保留
+        assert_eq!(
+            localize(MessageId::PathNotFound, Lang::En, &[]),
+            "Path not found: {path}"
+        );
+        assert_eq!(serde_json::to_string(&Lang::En).unwrap(), "\"en\"");
+    }
+
+    #[test]
+    fn test_progress_phase_markers_round_trip() {
+        for &phase in ProgressPhase::ALL {
+            assert!(phase.message_id().as_str().starts_with("phase."));
+            assert_eq!(ProgressPhase::from_marker(&phase.marker()), Some(phase));
+            assert_eq!(
+                serde_json::to_value(phase).unwrap(),
+                serde_json::json!(phase.as_str())
+            );
+        }
+        assert_eq!(ProgressPhase::Paused.marker(), "[scan.paused]");
+        assert_eq!(ProgressPhase::from_marker("/home/u/[scan.paused]"), None);
+        assert_eq!(ProgressPhase::from_marker("[scan.unknown]"), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! 应用自身的占用：存储根目录（已保存的扫描、日志、执行记录、导入暂存区等）
+//! 以及正在写入的导出/备份文件。这些路径始终不计入扫描结果与分析结论，
+//! 校验器也拒绝作用于其下的动作，避免把应用正在使用的数据当作可清理空间。
+//!
+//! 存储根目录在启动时设置；导出等操作开始写入前登记目标文件，返回的守卫释放时自动注销。
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+struct Registry {
+    storage_root: Option<PathBuf>,
+    /// (登记 ID, 路径)
+    in_flight: Vec<(u64, PathBuf)>,
+    next_id: u64,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    storage_root: None,
+    in_flight: Vec::new(),
+    next_id: 0,
+});
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 设置存储根目录（重复设置时以最后一次为准）
+pub fn set_storage_root(path: &Path) {
+    registry().storage_root = Some(path.to_path_buf());
+}
+
+/// 登记一个正在写入的文件或目录，守卫释放时注销
+#[must_use = "守卫释放后登记立即失效"]
+pub fn register_in_flight(path: &Path) -> InFlightGuard {
+    let mut registry = registry();
+    registry.next_id += 1;
+    let id = registry.next_id;
+    registry.in_flight.push((id, path.to_path_buf()));
+    InFlightGuard { id }
+}
+
+/// [`register_in_flight`] 返回的守卫
+#[derive(Debug)]
+pub struct InFlightGuard {
+    id: u64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        registry().in_flight.retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// 某一时刻应用自身占用路径的快照，按平台规则比较（Windows 不区分大小写、两种分隔符等价）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InternalPaths {
+    /// 已规范化的路径，不带末尾分隔符
+    paths: Vec<String>,
+    windows: bool,
+}
+
+impl InternalPaths {
+    /// 当前登记的存储根目录与进行中的写入。扫描树中的路径经过 canonicalize，
+    /// 因此存在的路径同时记录其解析后的形式（家目录是符号链接时也能对上）
+    pub fn current() -> Self {
+        let registered: Vec<PathBuf> = {
+            let registry = registry();
+            registry
+                .storage_root
+                .iter()
+                .chain(registry.in_flight.iter().map(|(_, path)| path))
+                .cloned()
+                .collect()
+        };
+        let mut paths = Vec::with_capacity(registered.len());
+        for path in registered {
+            if let Ok(canonical) = std::fs::canonicalize(&path) {
+                paths.push(strip_verbatim_prefix(&canonical.to_string_lossy()));
+            }
+            paths.push(path.to_string_lossy().to_string());
+        }
+        Self::from_paths_for(paths, cfg!(windows))
+    }
+
+    /// 由给定路径构造，`windows` 指定比较规则
+    pub fn from_paths_for<P: AsRef<Path>>(
+        paths: impl IntoIterator<Item = P>,
+        windows: bool,
+    ) -> Self {
+        let paths = paths
+            .into_iter()
+            .map(|path| normalize(&path.as_ref().to_string_lossy(), windows))
+            .collect();
+        Self { paths, windows }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// `path` 是否为应用自身占用的路径或位于其下
+    pub fn contains(&self, path: &str) -> bool {
+        let path = normalize(path, self.windows);
+        self.paths
+            .iter()
+            .any(|internal| is_same_or_under(&path, internal, self.windows))
+    }
+
+    /// 目录 `dir` 之下（含自身）是否有应用自身占用的路径，用于决定是否需要向下查找
+    pub fn any_within(&self, dir: &str) -> bool {
+        let dir = normalize(dir, self.windows);
+        self.paths
+            .iter()
+            .any(|internal| is_same_or_under(internal, &dir, self.windows))
+    }
+}
+
+/// 以当前登记判断 `path` 是否为应用自身占用的路径
+pub fn is_internal_path(path: &str) -> bool {
+    InternalPaths::current().contains(path)
+}
+
+/// Windows 上 canonicalize 返回 `\\?\C:\...` 或 `\\?\UNC\server\...`，转回常规形式
+fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(path).to_string()
+    }
+}
+
+fn normalize(path: &str, windows: bool) -> String {
+    let path = if windows {
+        path.replace('/', "\\").to_lowercase()
+    } else {
+        path.to_string()
+    };
+    path.trim_end_matches(separator(windows)).to_string()
+}
+
+fn separator(windows: bool) -> char {
+    if windows {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// 两者均已规范化；要求在分隔符处断开，`.disk-rookie-old` 不算在 `.disk-rookie` 之下
+fn is_same_or_under(path: &str, ancestor: &str, windows: bool) -> bool {
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(separator(windows)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_respects_separator_boundary() {
+        let internal = InternalPaths::from_paths_for(["/home/u/.disk-rookie/"], false);
+        assert!(internal.contains("/home/u/.disk-rookie"));
+        assert!(internal.contains("/home/u/.disk-rookie/scans/a.json"));
+        assert!(!internal.contains("/home/u/.disk-rookie-old/a.json"));
+        assert!(!internal.contains("/home/u/.Disk-Rookie"));
+        assert!(internal.any_within("/home/u"));
+        assert!(internal.any_within("/"));
+        assert!(!internal.any_within("/home/u/Documents"));
+    }
+
+    #[test]
+    fn test_windows_comparison_ignores_case_and_separator() {
+        let internal = InternalPaths::from_paths_for([r"C:\Users\u\.disk-rookie"], true);
+        assert!(internal.contains(r"c:\users\U\.DISK-ROOKIE\logs"));
+        assert!(internal.contains("C:/Users/u/.disk-rookie"));
+        assert!(internal.any_within(r"C:\"));
+        assert!(!internal.contains(r"C:\Users\u\Documents"));
+    }
+
+    #[test]
+    fn test_verbatim_prefix_stripped() {
+        assert_eq!(strip_verbatim_prefix(r"\\?\C:\Users\u"), r"C:\Users\u");
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\x"),
+            r"\\server\share\x"
+        );
+        assert_eq!(strip_verbatim_prefix("/home/u"), "/home/u");
+    }
+
+    #[test]
+    fn test_in_flight_registration_ends_with_guard() {
+        let target = std::env::temp_dir().join("internal-paths-test-export.zip.tmp");
+        let target_str = target.to_string_lossy().to_string();
+        let guard = register_in_flight(&target);
+        assert!(is_internal_path(&target_str));
+        drop(guard);
+        assert!(!is_internal_path(&target_str));
+    }
+}
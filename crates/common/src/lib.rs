@@ -1,7 +1,21 @@
+pub mod atomic_write;
+pub mod byte_size;
 pub mod config;
 pub mod error;
+pub mod i18n;
+pub mod internal_paths;
+pub mod logging;
+pub mod metrics;
+pub mod single_instance;
 pub mod telemetry;
 
+pub use atomic_write::*;
+pub use byte_size::*;
 pub use config::*;
 pub use error::*;
+pub use i18n::*;
+pub use internal_paths::*;
+pub use logging::*;
+pub use metrics::*;
+pub use single_instance::*;
 pub use telemetry::*;
@@ -0,0 +1,414 @@
+//! 结构化文件日志：基于 tracing，将事件以 JSON Lines 写入 `.disk-rookie/logs/app.log`，
+//! 按天及大小轮转；token、api_key 等敏感字段在写入前脱敏。
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::DiskAnalyzerError;
+
+/// 当前日志文件名，轮转后的文件为 `app.log.<毫秒时间戳>`
+pub const LOG_FILE_NAME: &str = "app.log";
+/// 单个日志文件大小上限
+pub const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// 保留的已轮转日志文件数
+pub const MAX_ROTATED_LOG_FILES: usize = 7;
+
+/// 值需要整体脱敏的字段名（小写比较）
+const SENSITIVE_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "api_key",
+    "apikey",
+    "client_secret",
+    "authorization",
+    "token",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// `authorization` 值中保留的认证方案前缀（小写比较）
+const AUTH_SCHEMES: &[&str] = &["bearer ", "basic "];
+
+fn is_sensitive_key(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_KEYS.contains(&name.as_str())
+}
+
+/// 脱敏文本中以 `key=value`、`key: value` 或 JSON `"key":"value"` 形式出现的敏感值
+pub fn redact_secrets(text: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    while pos < text.len() {
+        let next = SENSITIVE_KEYS
+            .iter()
+            .filter_map(|k| lower[pos..].find(k).map(|i| (pos + i, k.len())))
+            .min_by_key(|&(i, len)| (i, std::cmp::Reverse(len)));
+        let Some((start, key_len)) = next else {
+            break;
+        };
+        let mut cursor = start + key_len;
+        let bytes = text.as_bytes();
+        // 跳过键名后的引号、空白与分隔符
+        while cursor < bytes.len() && (bytes[cursor] == b'"' || bytes[cursor] == b' ') {
+            cursor += 1;
+        }
+        if cursor >= bytes.len() || (bytes[cursor] != b':' && bytes[cursor] != b'=') {
+            out.push_str(&text[pos..start + key_len]);
+            pos = start + key_len;
+            continue;
+        }
+        cursor += 1;
+        let mut quoted = false;
+        while cursor < bytes.len() && (bytes[cursor] == b' ' || bytes[cursor] == b'"') {
+            quoted |= bytes[cursor] == b'"';
+            cursor += 1;
+        }
+        let is_authorization = &lower[start..start + key_len] == "authorization";
+        if is_authorization {
+            // 保留认证方案，脱敏其后的凭据
+            if let Some(scheme) = AUTH_SCHEMES
+                .iter()
+                .find(|s| lower[cursor..].starts_with(*s))
+            {
+                cursor += scheme.len();
+            }
+        }
+        let value_start = cursor;
+        // authorization 的值可含空格，脱敏到引号或行尾为止
+        let ends_value = |b: u8| match b {
+            b'\n' | b'\r' => true,
+            b'"' => !is_authorization || quoted,
+            b'&' | b',' | b'}' | b' ' => !is_authorization,
+            _ => false,
+        };
+        while cursor < bytes.len() && !ends_value(bytes[cursor]) {
+            cursor += 1;
+        }
+        out.push_str(&text[pos..value_start]);
+        if cursor > value_start {
+            out.push_str(REDACTED);
+        }
+        pos = cursor;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// 按天与大小轮转的日志文件写入器
+pub struct RotatingFileWriter {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+    day: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFileWriter {
+    pub fn new(dir: &Path, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let day = metadata
+            .modified()
+            .map(day_of)
+            .unwrap_or_else(|_| day_of(SystemTime::now()));
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file: Some(file),
+            size: metadata.len(),
+            day,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let today = day_of(SystemTime::now());
+        let line_len = line.len() as u64 + 1;
+        if today != self.day || (self.size > 0 && self.size + line_len > self.max_bytes) {
+            self.rotate(today)?;
+        }
+        if self.file.is_none() {
+            self.reopen()?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+            self.size += line_len;
+        }
+        Ok(())
+    }
+
+    fn reopen(&mut self) -> std::io::Result<()> {
+        let path = self.dir.join(LOG_FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self, today: u64) -> std::io::Result<()> {
+        self.file = None;
+        let current = self.dir.join(LOG_FILE_NAME);
+        if current.exists() {
+            let rotated = self.dir.join(format!("{}.{}", LOG_FILE_NAME, now_millis()));
+            std::fs::rename(&current, rotated)?;
+        }
+        self.prune();
+        self.day = today;
+        self.reopen()
+    }
+
+    /// 删除超出保留数量的最旧轮转文件
+    fn prune(&self) {
+        let prefix = format!("{}.", LOG_FILE_NAME);
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut rotated: Vec<(u128, PathBuf)> = entries
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                let ts = name.strip_prefix(&prefix)?.parse::<u128>().ok()?;
+                Some((ts, e.path()))
+            })
+            .collect();
+        rotated.sort();
+        while rotated.len() > self.max_files {
+            let (_, path) = rotated.remove(0);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        let value = if is_sensitive_key(field.name()) {
+            Value::String(REDACTED.to_string())
+        } else {
+            match value {
+                Value::String(s) => Value::String(redact_secrets(&s)),
+                other => other,
+            }
+        };
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+}
+
+/// 将事件序列化为一行 JSON（含 ts/level/target 与事件字段）写入轮转文件
+pub struct JsonFileLayer {
+    writer: Mutex<RotatingFileWriter>,
+}
+
+impl JsonFileLayer {
+    pub fn new(writer: RotatingFileWriter) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for JsonFileLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+
+        let mut line = Map::new();
+        line.insert("ts".to_string(), Value::from(now_millis() as u64));
+        line.insert("level".to_string(), Value::from(meta.level().to_string()));
+        line.insert("target".to_string(), Value::from(meta.target()));
+        line.extend(visitor.fields);
+
+        if let Ok(text) = serde_json::to_string(&Value::Object(line)) {
+            let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writer.write_line(&text);
+        }
+    }
+}
+
+/// 初始化全局日志：JSON 文件 + stderr，同时接管 `log` crate 的输出。level 为 trace/debug/info/warn/error
+pub fn init_logging(log_dir: &Path, level: &str) -> Result<(), DiskAnalyzerError> {
+    let level: LevelFilter = level.parse().unwrap_or(LevelFilter::INFO);
+    let writer = RotatingFileWriter::new(log_dir, MAX_LOG_BYTES, MAX_ROTATED_LOG_FILES)?;
+    // 过滤 tao/winit 事件循环的 WARN，避免刷屏
+    let filter = Targets::new()
+        .with_default(level)
+        .with_target("tao", LevelFilter::ERROR)
+        .with_target("winit", LevelFilter::ERROR);
+    tracing_subscriber::registry()
+        .with(JsonFileLayer::new(writer))
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(filter)
+        .try_init()
+        .map_err(|e| DiskAnalyzerError::Config(format!("初始化日志失败: {}", e)))
+}
+
+/// 读取当前日志文件的最后 `lines` 行（不足时补充最近一个轮转文件）
+pub fn read_recent_logs(log_dir: &Path, lines: usize) -> std::io::Result<Vec<String>> {
+    let read_lines = |path: &Path| -> std::io::Result<Vec<String>> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => Ok(s.lines().map(str::to_string).collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    };
+    let mut result = read_lines(&log_dir.join(LOG_FILE_NAME))?;
+    if result.len() < lines {
+        let prefix = format!("{}.", LOG_FILE_NAME);
+        let latest_rotated = std::fs::read_dir(log_dir)?
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+            .map(|e| e.path())
+            .max();
+        if let Some(path) = latest_rotated {
+            let mut older = read_lines(&path)?;
+            older.append(&mut result);
+            result = older;
+        }
+    }
+    let skip = result.len().saturating_sub(lines);
+    Ok(result.split_off(skip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secrets_in_text() {
+        let text = r#"Token 响应内容: {"access_token":"ya29.secret","expires_in":3599,"refresh_token": "1//rt-secret"}"#;
+        let redacted = redact_secrets(text);
+        assert!(!redacted.contains("ya29.secret"));
+        assert!(!redacted.contains("rt-secret"));
+        assert!(redacted.contains("\"expires_in\":3599"));
+
+        let query = "https://x/api?access_token=abc123&method=uinfo";
+        assert_eq!(
+            redact_secrets(query),
+            "https://x/api?access_token=[REDACTED]&method=uinfo"
+        );
+        assert_eq!(redact_secrets("no secrets here"), "no secrets here");
+    }
+
+    #[test]
+    fn test_redact_authorization_header_with_scheme() {
+        assert_eq!(
+            redact_secrets("authorization: Bearer abc123\ncontent-type: json"),
+            "authorization: Bearer [REDACTED]\ncontent-type: json"
+        );
+        assert_eq!(
+            redact_secrets("Authorization: Basic dXNlcjpwYXNz extra"),
+            "Authorization: Basic [REDACTED]"
+        );
+        assert_eq!(
+            redact_secrets(r#"{"authorization":"Bearer abc 123","url":"x"}"#),
+            r#"{"authorization":"Bearer [REDACTED]","url":"x"}"#
+        );
+    }
+
+    #[test]
+    fn test_token_values_never_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = RotatingFileWriter::new(dir.path(), MAX_LOG_BYTES, 3).unwrap();
+        let subscriber = tracing_subscriber::registry().with(JsonFileLayer::new(writer));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                provider = "google",
+                access_token = "secret-field",
+                "token refreshed"
+            );
+            tracing::info!(api_key = %"secret-display", "llm configured");
+            tracing::warn!(
+                "Token 响应内容: {}",
+                r#"{"access_token":"secret-message","token_type":"Bearer"}"#
+            );
+        });
+        let content = std::fs::read_to_string(dir.path().join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(!content.contains("secret-"));
+        assert!(content.contains("\"provider\":\"google\""));
+        let first: Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first["level"], "INFO");
+        assert_eq!(first["message"], "token refreshed");
+    }
+
+    #[test]
+    fn test_rotation_by_size_and_recent_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RotatingFileWriter::new(dir.path(), 64, 2).unwrap();
+        for i in 0..20 {
+            writer
+                .write_line(&format!("line-{:02}-xxxxxxxxxxxxxxxx", i))
+                .unwrap();
+            // 轮转文件名以毫秒区分，避免同一毫秒内重名
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let rotated = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name() != LOG_FILE_NAME)
+            .count();
+        assert!(rotated <= 2);
+        let recent = read_recent_logs(dir.path(), 3).unwrap();
+        assert_eq!(recent.last().unwrap(), "line-19-xxxxxxxxxxxxxxxx");
+        assert_eq!(recent.len(), 3);
+    }
+}
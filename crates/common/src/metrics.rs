@@ -0,0 +1,349 @@
+//! 进程内运行指标：计数器与直方图，供诊断页查看扫描、执行、规划、LLM 与上传的性能。
+//!
+//! 与 [`crate::telemetry`] 不同，指标只保存在内存中、从不上报，重启或重置后清零；
+//! 标签可以是运行时字符串（扫描方式、云存储提供商等），但调用方不应传入路径。
+//! 各模块通过全局句柄 [`metrics`] 记录，关闭后记录调用只读一个原子标志。
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// 扫描耗时（毫秒），标签为扫描方式 `mft` / `walk`
+pub const METRIC_SCAN_DURATION_MS: &str = "scan.duration_ms";
+/// MFT 枚举速度（记录/秒）
+pub const METRIC_MFT_RECORDS_PER_SEC: &str = "scan.mft_records_per_sec";
+/// 执行的动作数，标签为结果 `succeeded` / `failed` / `skipped` / `changed`（目标在扫描后已改变）
+pub const METRIC_EXECUTOR_ACTIONS: &str = "executor.actions";
+/// 生成计划的耗时（毫秒）
+pub const METRIC_PLANNER_DURATION_MS: &str = "planner.duration_ms";
+/// LLM 请求耗时（毫秒），标签为结果 `ok` / `error`
+pub const METRIC_LLM_LATENCY_MS: &str = "llm.latency_ms";
+/// LLM 请求在限流队列中的等待时间（毫秒）
+pub const METRIC_LLM_QUEUE_MS: &str = "llm.queue_ms";
+/// 因排队已满被拒绝的 LLM 请求数
+pub const METRIC_LLM_REJECTED: &str = "llm.rejected";
+/// 上传吞吐量（字节/秒），标签为云存储提供商
+pub const METRIC_UPLOAD_BYTES_PER_SEC: &str = "upload.bytes_per_sec";
+/// 上传的字节数，标签为云存储提供商
+pub const METRIC_UPLOAD_BYTES: &str = "upload.bytes";
+
+/// 没有更细分类时使用的标签
+pub const LABEL_ALL: &str = "all";
+
+/// 直方图桶的上界（含），按 1-2-5 递增，覆盖毫秒耗时到每秒字节数；超出最后一个上界的计入溢出桶
+const BUCKET_BOUNDS: [u64; 30] = bucket_bounds();
+
+const fn bucket_bounds() -> [u64; 30] {
+    let mut bounds = [0u64; 30];
+    let mut scale = 1u64;
+    let mut i = 0;
+    while i < 30 {
+        bounds[i] = scale;
+        bounds[i + 1] = 2 * scale;
+        bounds[i + 2] = 5 * scale;
+        scale *= 10;
+        i += 3;
+    }
+    bounds
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+    /// 与 BUCKET_BOUNDS 一一对应，最后一项为溢出桶
+    buckets: [u64; BUCKET_BOUNDS.len() + 1],
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+            buckets: [0; BUCKET_BOUNDS.len() + 1],
+        }
+    }
+
+    fn observe(&mut self, value: u64) {
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buckets[BUCKET_BOUNDS.partition_point(|&bound| bound < value)] += 1;
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(i, &count)| HistogramBucket {
+                le: BUCKET_BOUNDS.get(i).copied(),
+                count,
+            })
+            .collect();
+        HistogramSnapshot {
+            count: self.count,
+            sum: self.sum,
+            min: self.min,
+            max: self.max,
+            buckets,
+        }
+    }
+}
+
+/// 直方图中的一个非空桶
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    /// 桶的上界（含）；溢出桶为 None
+    pub le: Option<u64>,
+    pub count: u64,
+}
+
+/// 直方图快照；只列出非空桶，按上界升序
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub sum: u64,
+    pub min: u64,
+    pub max: u64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// 指标快照。指标名与标签均按字典序排列，同样的记录得到同样的 JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub enabled: bool,
+    /// 开始统计的时间（Unix 毫秒）：进程启动或上次重置
+    pub since_ms: u64,
+    /// 指标名 → 标签 → 计数
+    pub counters: BTreeMap<String, BTreeMap<String, u64>>,
+    /// 指标名 → 标签 → 直方图
+    pub histograms: BTreeMap<String, BTreeMap<String, HistogramSnapshot>>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    since_ms: u64,
+    counters: BTreeMap<&'static str, BTreeMap<String, u64>>,
+    histograms: BTreeMap<&'static str, BTreeMap<String, Histogram>>,
+}
+
+/// 指标注册表
+#[derive(Debug)]
+pub struct Metrics {
+    enabled: AtomicBool,
+    registry: Mutex<Registry>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// 开启状态的空注册表
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            registry: Mutex::new(Registry {
+                since_ms: now_ms(),
+                ..Registry::default()
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 关闭后记录调用直接返回，已有数据保留
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 计数器加 `by`
+    pub fn increment(&self, name: &'static str, label: &str, by: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut registry = self.registry();
+        let labels = registry.counters.entry(name).or_default();
+        match labels.get_mut(label) {
+            Some(count) => *count = count.saturating_add(by),
+            None => {
+                labels.insert(label.to_string(), by);
+            }
+        }
+    }
+
+    /// 向直方图记录一个值
+    pub fn observe(&self, name: &'static str, label: &str, value: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut registry = self.registry();
+        let labels = registry.histograms.entry(name).or_default();
+        match labels.get_mut(label) {
+            Some(histogram) => histogram.observe(value),
+            None => {
+                let mut histogram = Histogram::new();
+                histogram.observe(value);
+                labels.insert(label.to_string(), histogram);
+            }
+        }
+    }
+
+    /// 记录一段耗时（毫秒）
+    pub fn observe_duration(&self, name: &'static str, label: &str, elapsed: Duration) {
+        self.observe(name, label, elapsed.as_millis() as u64);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let registry = self.registry();
+        MetricsSnapshot {
+            enabled: self.is_enabled(),
+            since_ms: registry.since_ms,
+            counters: registry
+                .counters
+                .iter()
+                .map(|(name, labels)| (name.to_string(), labels.clone()))
+                .collect(),
+            histograms: registry
+                .histograms
+                .iter()
+                .map(|(name, labels)| {
+                    let labels = labels
+                        .iter()
+                        .map(|(label, h)| (label.clone(), h.snapshot()))
+                        .collect();
+                    (name.to_string(), labels)
+                })
+                .collect(),
+        }
+    }
+
+    /// 清空所有指标并从现在开始重新统计
+    pub fn reset(&self) {
+        *self.registry() = Registry {
+            since_ms: now_ms(),
+            ..Registry::default()
+        };
+    }
+}
+
+/// 每秒速率；耗时不足 1 毫秒时无法估算，返回 None
+pub fn rate_per_sec(amount: u64, elapsed: Duration) -> Option<u64> {
+    let ms = elapsed.as_millis();
+    (ms > 0).then(|| (u128::from(amount) * 1000 / ms).min(u128::from(u64::MAX)) as u64)
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// 全局指标注册表，首次访问时创建
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        assert_eq!(&BUCKET_BOUNDS[..4], &[1, 2, 5, 10]);
+        assert_eq!(BUCKET_BOUNDS[29], 5_000_000_000);
+        let mut h = Histogram::new();
+        for value in [0, 1, 2, 3, 5_000_000_000, 5_000_000_001] {
+            h.observe(value);
+        }
+        let snapshot = h.snapshot();
+        assert_eq!((snapshot.count, snapshot.min), (6, 0));
+        let buckets: Vec<(Option<u64>, u64)> =
+            snapshot.buckets.iter().map(|b| (b.le, b.count)).collect();
+        assert_eq!(
+            buckets,
+            vec![
+                (Some(1), 2),
+                (Some(2), 1),
+                (Some(5), 1),
+                (Some(5_000_000_000), 1),
+                (None, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_serializes_stably() {
+        let m = Metrics::new();
+        // 记录顺序不同，快照相同
+        m.observe(METRIC_SCAN_DURATION_MS, "walk", 120);
+        m.increment(METRIC_EXECUTOR_ACTIONS, "succeeded", 2);
+        m.increment(METRIC_EXECUTOR_ACTIONS, "failed", 1);
+        m.observe(METRIC_SCAN_DURATION_MS, "mft", 40);
+        m.observe(METRIC_SCAN_DURATION_MS, "walk", 80);
+        m.increment(METRIC_EXECUTOR_ACTIONS, "succeeded", 1);
+        let mut snapshot = m.snapshot();
+        snapshot.since_ms = 0;
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"enabled":true,"since_ms":0,"#,
+                r#""counters":{"executor.actions":{"failed":1,"succeeded":3}},"#,
+                r#""histograms":{"scan.duration_ms":{"#,
+                r#""mft":{"count":1,"sum":40,"min":40,"max":40,"buckets":[{"le":50,"count":1}]},"#,
+                r#""walk":{"count":2,"sum":200,"min":80,"max":120,"#,
+                r#""buckets":[{"le":100,"count":1},{"le":200,"count":1}]}}}}"#
+            )
+        );
+        let parsed: MetricsSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[test]
+    fn test_disabled_and_reset() {
+        let m = Metrics::new();
+        m.set_enabled(false);
+        m.increment(METRIC_UPLOAD_BYTES, "dropbox", 10);
+        m.observe(METRIC_LLM_LATENCY_MS, "ok", 10);
+        let snapshot = m.snapshot();
+        assert!(!snapshot.enabled);
+        assert!(snapshot.counters.is_empty() && snapshot.histograms.is_empty());
+
+        m.set_enabled(true);
+        m.increment(METRIC_UPLOAD_BYTES, "dropbox", 10);
+        assert_eq!(m.snapshot().counters[METRIC_UPLOAD_BYTES]["dropbox"], 10);
+        m.reset();
+        assert!(m.snapshot().counters.is_empty());
+    }
+
+    #[test]
+    fn test_rate_per_sec() {
+        assert_eq!(
+            rate_per_sec(5_000, Duration::from_millis(500)),
+            Some(10_000)
+        );
+        assert_eq!(rate_per_sec(5_000, Duration::from_micros(500)), None);
+    }
+}
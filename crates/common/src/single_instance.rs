@@ -0,0 +1,341 @@
+//! 单实例：同一用户同时只运行一个桌面进程，避免两个进程同时读取同一卷的 MFT、重复启动 OAuth 回调服务。
+//!
+//! 首个进程独占锁定 `instance.lock`，在本机回环地址上监听并把端口写入 `instance.port`；
+//! 后启动的进程拿不到锁时按端口把自己的命令行参数以一行 JSON 转发给首个进程，收到确认后退出。
+//! 锁随文件句柄关闭而释放，进程崩溃后不会残留。
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{write_atomic, DiskAnalyzerError};
+
+const LOCK_FILE: &str = "instance.lock";
+const PORT_FILE: &str = "instance.port";
+/// 首个进程刚拿到锁、尚未写出端口（或端口文件是上次崩溃残留的）时，转发的重试次数与间隔
+const FORWARD_RETRIES: u32 = 20;
+const FORWARD_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+/// 单次转发的读写超时
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+/// 首个进程处理完转发后回复的确认行
+const ACK: &str = "ok";
+/// 启动时要求扫描某个路径的参数
+pub const SCAN_FLAG: &str = "--scan";
+
+/// 后启动的进程转发给首个进程的启动参数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardedArgs {
+    /// 不含程序路径的命令行参数
+    pub args: Vec<String>,
+    /// 后启动进程的工作目录，用于解析相对路径
+    pub cwd: String,
+}
+
+impl ForwardedArgs {
+    /// 当前进程的命令行参数与工作目录
+    pub fn from_env() -> Self {
+        Self {
+            args: std::env::args().skip(1).collect(),
+            cwd: std::env::current_dir()
+                .map(|d| d.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 编码为一行 JSON（以换行结尾）
+    pub fn to_line(&self) -> Result<String, DiskAnalyzerError> {
+        let mut line =
+            serde_json::to_string(self).map_err(|e| DiskAnalyzerError::Config(e.to_string()))?;
+        line.push('\n');
+        Ok(line)
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, DiskAnalyzerError> {
+        serde_json::from_str(line.trim_end())
+            .map_err(|e| DiskAnalyzerError::Config(format!("无效的转发参数: {}", e)))
+    }
+
+    /// 要扫描的路径：`--scan <路径>` 或 `--scan=<路径>`，没有时取第一个不以 `-` 开头的参数；
+    /// 相对路径按 cwd 解析
+    pub fn scan_path(&self) -> Option<String> {
+        let mut positional = None;
+        let mut iter = self.args.iter();
+        let mut explicit: Option<&str> = None;
+        while let Some(arg) = iter.next() {
+            if arg == SCAN_FLAG {
+                explicit = iter.next().map(String::as_str);
+                break;
+            }
+            if let Some(value) = arg
+                .strip_prefix(SCAN_FLAG)
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                explicit = Some(value);
+                break;
+            }
+            if positional.is_none() && !arg.starts_with('-') {
+                positional = Some(arg.as_str());
+            }
+        }
+        let arg = clean_path_arg(explicit.or(positional)?);
+        if arg.is_empty() {
+            return None;
+        }
+        if looks_absolute(&arg) || self.cwd.is_empty() {
+            Some(arg)
+        } else {
+            Some(
+                Path::new(&self.cwd)
+                    .join(arg)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+}
+
+/// 去掉参数中残留的引号。资源管理器按 `--scan "%1"` 传入驱动器根 `D:\` 时，
+/// Windows 的参数解析把结尾的 `\"` 当作转义的引号，得到 `D:"`，这里还原为 `D:\`
+fn clean_path_arg(arg: &str) -> String {
+    let arg = arg.trim();
+    if let Some(inner) = arg.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+        return inner.to_string();
+    }
+    match arg.strip_suffix('"') {
+        Some(rest) => format!("{}\\", rest),
+        None => arg.to_string(),
+    }
+}
+
+/// 当前平台的绝对路径，或 Windows 盘符 / UNC 路径（参数可能来自 Windows 外壳）
+fn looks_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    Path::new(path).is_absolute()
+        || path.starts_with("\\\\")
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// 获取单实例锁的结果
+pub enum InstanceRole {
+    /// 当前进程是首个实例，持有锁
+    Primary(InstanceGuard),
+    /// 已有实例在运行，应调用 [`forward_to_primary`] 后退出
+    Secondary,
+}
+
+/// 首个实例持有的锁与监听端口；drop 时释放锁并删除端口文件
+pub struct InstanceGuard {
+    lock: File,
+    listener: TcpListener,
+    port_file: PathBuf,
+}
+
+impl InstanceGuard {
+    pub fn port(&self) -> Result<u16, DiskAnalyzerError> {
+        Ok(self.listener.local_addr()?.port())
+    }
+
+    /// 在后台线程中接收转发，每收到一次调用一次 on_args；锁随线程一直持有到进程退出
+    pub fn listen<F>(self, on_args: F) -> std::io::Result<std::thread::JoinHandle<()>>
+    where
+        F: Fn(ForwardedArgs) + Send + 'static,
+    {
+        std::thread::Builder::new()
+            .name("single-instance".to_string())
+            .spawn(move || {
+                for stream in self.listener.incoming() {
+                    let result = stream
+                        .map_err(DiskAnalyzerError::from)
+                        .and_then(receive_forwarded);
+                    match result {
+                        Ok(args) => on_args(args),
+                        Err(e) => tracing::warn!(error = %e, "接收转发的启动参数失败"),
+                    }
+                }
+            })
+    }
+}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.port_file);
+        let _ = self.lock.unlock();
+    }
+}
+
+/// 尝试成为首个实例：dir 为锁文件与端口文件所在目录
+pub fn acquire_instance_lock(dir: &Path) -> Result<InstanceRole, DiskAnalyzerError> {
+    std::fs::create_dir_all(dir)?;
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(InstanceRole::Secondary),
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port_file = dir.join(PORT_FILE);
+    write_atomic(
+        &port_file,
+        listener.local_addr()?.port().to_string().as_bytes(),
+    )?;
+    Ok(InstanceRole::Primary(InstanceGuard {
+        lock,
+        listener,
+        port_file,
+    }))
+}
+
+/// 把启动参数转发给首个实例，等待其确认
+pub fn forward_to_primary(dir: &Path, args: &ForwardedArgs) -> Result<(), DiskAnalyzerError> {
+    let line = args.to_line()?;
+    let port_file = dir.join(PORT_FILE);
+    let mut last_error = None;
+    for _ in 0..FORWARD_RETRIES {
+        match send_forwarded(&port_file, &line) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+        std::thread::sleep(FORWARD_RETRY_INTERVAL);
+    }
+    Err(last_error.unwrap_or_else(|| DiskAnalyzerError::Config("转发失败".to_string())))
+}
+
+fn send_forwarded(port_file: &Path, line: &str) -> Result<(), DiskAnalyzerError> {
+    let port: u16 = std::fs::read_to_string(port_file)?
+        .trim()
+        .parse()
+        .map_err(|_| DiskAnalyzerError::Config("端口文件内容无效".to_string()))?;
+    let mut stream =
+        TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, port).into(), FORWARD_TIMEOUT)?;
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    stream.write_all(line.as_bytes())?;
+    let mut ack = String::new();
+    BufReader::new(stream).read_line(&mut ack)?;
+    if ack.trim_end() == ACK {
+        Ok(())
+    } else {
+        Err(DiskAnalyzerError::Config("首个实例未确认转发".to_string()))
+    }
+}
+
+fn receive_forwarded(mut stream: TcpStream) -> Result<ForwardedArgs, DiskAnalyzerError> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let args = ForwardedArgs::from_line(&line)?;
+    stream.write_all(format!("{}\n", ACK).as_bytes())?;
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn args(list: &[&str], cwd: &str) -> ForwardedArgs {
+        ForwardedArgs {
+            args: list.iter().map(|a| a.to_string()).collect(),
+            cwd: cwd.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lock_acquire_and_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let InstanceRole::Primary(guard) = acquire_instance_lock(dir.path()).unwrap() else {
+            panic!("首次获取应成为首个实例");
+        };
+        let port = guard.port().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(PORT_FILE)).unwrap(),
+            port.to_string()
+        );
+        assert!(matches!(
+            acquire_instance_lock(dir.path()).unwrap(),
+            InstanceRole::Secondary
+        ));
+
+        drop(guard);
+        assert!(!dir.path().join(PORT_FILE).exists());
+        assert!(matches!(
+            acquire_instance_lock(dir.path()).unwrap(),
+            InstanceRole::Primary(_)
+        ));
+    }
+
+    #[test]
+    fn test_forwarded_args_line_round_trip() {
+        let forwarded = args(&["--minimized", "D:\\Data dir\\新建"], "C:\\Users\\u");
+        let line = forwarded.to_line().unwrap();
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(ForwardedArgs::from_line(&line).unwrap(), forwarded);
+        assert!(ForwardedArgs::from_line("not json\n").is_err());
+    }
+
+    #[test]
+    fn test_scan_path_resolves_relative_to_cwd() {
+        assert_eq!(args(&["--minimized"], "/home/u").scan_path(), None);
+        assert_eq!(
+            args(&["-v", "/data"], "/home/u").scan_path().as_deref(),
+            Some("/data")
+        );
+        let relative = args(&["photos"], "/home/u").scan_path().unwrap();
+        assert_eq!(Path::new(&relative), Path::new("/home/u").join("photos"));
+    }
+
+    #[test]
+    fn test_scan_flag_and_quoted_paths() {
+        let scan = |list: &[&str]| args(list, "C:\\Users\\u").scan_path();
+        // --scan 优先于位置参数
+        assert_eq!(
+            scan(&["other", "--scan", "D:\\My Files"]).as_deref(),
+            Some("D:\\My Files")
+        );
+        assert_eq!(
+            scan(&["--scan=E:\\Program Files (x86)\\Steam"]).as_deref(),
+            Some("E:\\Program Files (x86)\\Steam")
+        );
+        // 引号未被外壳去掉时原样剥离
+        assert_eq!(
+            scan(&["--scan", "\"D:\\My Files\""]).as_deref(),
+            Some("D:\\My Files")
+        );
+        // `--scan "D:\"` 经 Windows 参数解析后结尾的反斜杠变成了引号
+        assert_eq!(scan(&["--scan", "D:\""]).as_deref(), Some("D:\\"));
+        assert_eq!(
+            scan(&["--scan", "D:\\Data dir\""]).as_deref(),
+            Some("D:\\Data dir\\")
+        );
+        assert_eq!(
+            scan(&["--scan", "\\\\nas\\share"]).as_deref(),
+            Some("\\\\nas\\share")
+        );
+        assert_eq!(scan(&["--scan"]), None);
+        assert_eq!(scan(&["--scan", "  "]), None);
+    }
+
+    #[test]
+    fn test_forward_reaches_primary() {
+        let dir = tempfile::tempdir().unwrap();
+        let InstanceRole::Primary(guard) = acquire_instance_lock(dir.path()).unwrap() else {
+            panic!("首次获取应成为首个实例");
+        };
+        let (tx, rx) = mpsc::channel();
+        guard
+            .listen(move |forwarded| tx.send(forwarded).unwrap())
+            .unwrap();
+
+        let forwarded = args(&["/data"], "/home/u");
+        forward_to_primary(dir.path(), &forwarded).unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), forwarded);
+    }
+}
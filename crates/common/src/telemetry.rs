@@ -1,4 +1,402 @@
-/// 遥测与日志（预留）
-pub fn init_telemetry() {
-    // TODO: 初始化日志和遥测
+//! 匿名遥测（默认关闭，需用户显式开启）：事件先追加到本地队列文件，
+//! 由后台任务在开启状态下按批上报；离线时保留在队列中，超过上限丢弃最旧事件。
+//!
+//! 事件属性只接受布尔、数值、分桶标签和错误码，不接受运行时字符串，从类型上杜绝路径、文件名进入事件。
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{write_atomic, ErrorCode, TelemetryConfig};
+
+/// 队列文件名（JSON Lines）
+pub const TELEMETRY_QUEUE_FILE: &str = "queue.jsonl";
+/// 匿名机器 ID 文件名
+const MACHINE_ID_FILE: &str = "machine_id";
+/// 队列文件大小上限，超过后丢弃最旧事件
+pub const MAX_TELEMETRY_QUEUE_BYTES: u64 = 512 * 1024;
+/// 单次上报的最大事件数
+pub const TELEMETRY_BATCH_SIZE: usize = 50;
+
+/// 事件属性值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum TelemetryValue {
+    Bool(bool),
+    Number(u64),
+    /// 编译期常量标签（分桶名、provider 名等）
+    Label(&'static str),
+    Code(ErrorCode),
+}
+
+/// 遥测记录器：队列文件与机器 ID 均位于 `dir` 下
+pub struct Telemetry {
+    dir: PathBuf,
+    enabled: AtomicBool,
+    endpoint: Mutex<String>,
+    /// 串行化队列文件读写
+    queue_lock: Mutex<()>,
+}
+
+impl Telemetry {
+    pub fn new(dir: &Path, config: &TelemetryConfig) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            enabled: AtomicBool::new(config.enabled),
+            endpoint: Mutex::new(config.endpoint.clone()),
+            queue_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 上报地址；未开启或未配置时为 None
+    pub fn endpoint(&self) -> Option<String> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let endpoint = self.endpoint.lock().unwrap_or_else(|e| e.into_inner());
+        (!endpoint.is_empty()).then(|| endpoint.clone())
+    }
+
+    /// 同步配置变更。关闭时清空队列并删除机器 ID，下次开启会生成新的 ID
+    pub fn apply_config(&self, config: &TelemetryConfig) {
+        *self.endpoint.lock().unwrap_or_else(|e| e.into_inner()) = config.endpoint.clone();
+        let was_enabled = self.enabled.swap(config.enabled, Ordering::Relaxed);
+        if was_enabled && !config.enabled {
+            let _guard = self.queue_lock.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = std::fs::remove_file(self.dir.join(TELEMETRY_QUEUE_FILE));
+            let _ = std::fs::remove_file(self.dir.join(MACHINE_ID_FILE));
+        }
+    }
+
+    /// 记录事件；未开启时直接丢弃，不落盘
+    pub fn record_event(&self, name: &'static str, properties: &[(&'static str, TelemetryValue)]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let _guard = self.queue_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let Ok(machine_id) = self.machine_id() else {
+            return;
+        };
+        let props: Map<String, Value> = properties
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), serde_json::to_value(v).ok()?)))
+            .collect();
+        let event = serde_json::json!({
+            "name": name,
+            "ts": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            "machine_id": machine_id,
+            "properties": props,
+        });
+        let mut lines = self.read_queue();
+        lines.push(event.to_string());
+        let _ = self.write_queue(&lines);
+    }
+
+    /// 队列中尚未上报的事件
+    pub fn pending_events(&self) -> Vec<Value> {
+        let _guard = self.queue_lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.read_queue()
+            .iter()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect()
+    }
+
+    /// 最早的一批待上报事件
+    pub fn pending_batch(&self) -> Vec<Value> {
+        let mut events = self.pending_events();
+        events.truncate(TELEMETRY_BATCH_SIZE);
+        events
+    }
+
+    /// 确认最早的 `count` 条事件已上报，从队列移除。上报期间新追加的事件不受影响
+    pub fn ack(&self, count: usize) {
+        let _guard = self.queue_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let lines = self.read_queue();
+        let _ = self.write_queue(&lines[count.min(lines.len())..]);
+    }
+
+    /// 用 `send` 同步上报最早的一批事件，成功后从队列移除；未开启或无上报地址时不调用 `send`
+    pub fn flush_with<E>(
+        &self,
+        send: impl FnOnce(&str, &[Value]) -> Result<(), E>,
+    ) -> Result<usize, E> {
+        let Some(endpoint) = self.endpoint() else {
+            return Ok(0);
+        };
+        let batch = self.pending_batch();
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        send(&endpoint, &batch)?;
+        self.ack(batch.len());
+        Ok(batch.len())
+    }
+
+    fn read_queue(&self) -> Vec<String> {
+        std::fs::read_to_string(self.dir.join(TELEMETRY_QUEUE_FILE))
+            .map(|s| s.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    fn write_queue(&self, lines: &[String]) -> std::io::Result<()> {
+        // 超出上限时从最旧的事件开始丢弃
+        let mut total: u64 = 0;
+        let mut keep_from = lines.len();
+        for (i, line) in lines.iter().enumerate().rev() {
+            total += line.len() as u64 + 1;
+            if total > MAX_TELEMETRY_QUEUE_BYTES {
+                break;
+            }
+            keep_from = i;
+        }
+        let mut content = String::new();
+        for line in &lines[keep_from..] {
+            content.push_str(line);
+            content.push('\n');
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.dir.join(TELEMETRY_QUEUE_FILE), content.as_bytes())
+    }
+
+    fn machine_id(&self) -> std::io::Result<String> {
+        let path = self.dir.join(MACHINE_ID_FILE);
+        if let Ok(id) = std::fs::read_to_string(&path) {
+            let id = id.trim();
+            if !id.is_empty() {
+                return Ok(id.to_string());
+            }
+        }
+        let id = random_id();
+        std::fs::create_dir_all(&self.dir)?;
+        write_atomic(&path, id.as_bytes())?;
+        Ok(id)
+    }
+}
+
+/// 128 位随机十六进制 ID（与硬件信息无关）
+fn random_id() -> String {
+    let part = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(salt);
+        hasher.write_u128(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0),
+        );
+        hasher.write_u32(std::process::id());
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", part(1), part(2))
+}
+
+/// 文件数量分桶
+pub fn count_bucket(count: u64) -> &'static str {
+    match count {
+        0..=999 => "<1k",
+        1_000..=9_999 => "1k-10k",
+        10_000..=99_999 => "10k-100k",
+        100_000..=999_999 => "100k-1M",
+        _ => ">=1M",
+    }
+}
+
+/// 字节数分桶
+pub fn bytes_bucket(bytes: u64) -> &'static str {
+    const MB: u64 = 1024 * 1024;
+    const GB: u64 = 1024 * MB;
+    match bytes {
+        0 => "0",
+        b if b < 100 * MB => "<100MB",
+        b if b < GB => "100MB-1GB",
+        b if b < 10 * GB => "1GB-10GB",
+        b if b < 100 * GB => "10GB-100GB",
+        _ => ">=100GB",
+    }
+}
+
+static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+
+/// 初始化全局遥测记录器，`dir` 一般为 `.disk-rookie/telemetry`
+pub fn init_telemetry(dir: &Path, config: &TelemetryConfig) -> &'static Telemetry {
+    TELEMETRY.get_or_init(|| Telemetry::new(dir, config))
+}
+
+/// 全局遥测记录器；未初始化时为 None
+pub fn telemetry() -> Option<&'static Telemetry> {
+    TELEMETRY.get()
+}
+
+/// 记录事件到全局记录器；未初始化或未开启时忽略
+pub fn record_event(name: &'static str, properties: &[(&'static str, TelemetryValue)]) {
+    if let Some(t) = telemetry() {
+        t.record_event(name, properties);
+    }
+}
+
+/// 扫描完成
+pub fn record_scan_completed(duration_ms: u64, file_count: u64, used_mft: bool) {
+    record_event(
+        "scan_completed",
+        &[
+            ("duration_ms", TelemetryValue::Number(duration_ms)),
+            (
+                "file_count",
+                TelemetryValue::Label(count_bucket(file_count)),
+            ),
+            ("used_mft", TelemetryValue::Bool(used_mft)),
+        ],
+    );
+}
+
+/// 生成清理计划
+pub fn record_plan_generated(action_count: u64, provider: &'static str) {
+    record_event(
+        "plan_generated",
+        &[
+            ("action_count", TelemetryValue::Number(action_count)),
+            ("provider", TelemetryValue::Label(provider)),
+        ],
+    );
+}
+
+/// 执行清理计划
+pub fn record_plan_executed(bytes_freed: u64) {
+    record_event(
+        "plan_executed",
+        &[(
+            "bytes_freed",
+            TelemetryValue::Label(bytes_bucket(bytes_freed)),
+        )],
+    );
+}
+
+/// LLM 请求：排队等待时间与结果（`ok` / `error` / `busy`）
+pub fn record_llm_request(queue_ms: u64, outcome: &'static str) {
+    record_event(
+        "llm_request",
+        &[
+            ("queue_ms", TelemetryValue::Number(queue_ms)),
+            ("outcome", TelemetryValue::Label(outcome)),
+        ],
+    );
+}
+
+/// 命令错误（只记录错误码，不记录错误信息）
+pub fn record_error(code: ErrorCode) {
+    record_event("error", &[("code", TelemetryValue::Code(code))]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(enabled: bool) -> TelemetryConfig {
+        TelemetryConfig {
+            enabled,
+            endpoint: "https://telemetry.example.com/v1/events".to_string(),
+            ..TelemetryConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_disabled_records_and_sends_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let t = Telemetry::new(dir.path(), &config(false));
+        t.record_event(
+            "scan_completed",
+            &[("used_mft", TelemetryValue::Bool(true))],
+        );
+        assert!(t.pending_events().is_empty());
+        assert!(!dir.path().join(TELEMETRY_QUEUE_FILE).exists());
+
+        let mut called = false;
+        let sent = t
+            .flush_with(|_, _| {
+                called = true;
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        assert_eq!(sent, 0);
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_events_never_contain_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let t = Telemetry::new(dir.path(), &config(true));
+        t.record_event(
+            "scan_completed",
+            &[
+                ("duration_ms", TelemetryValue::Number(1200)),
+                ("file_count", TelemetryValue::Label(count_bucket(123_456))),
+                ("used_mft", TelemetryValue::Bool(false)),
+            ],
+        );
+        t.record_event(
+            "error",
+            &[("code", TelemetryValue::Code(ErrorCode::PathNotFound))],
+        );
+
+        let queue = std::fs::read_to_string(dir.path().join(TELEMETRY_QUEUE_FILE)).unwrap();
+        let tmp = dir.path().to_string_lossy().into_owned();
+        assert!(!queue.contains(&tmp));
+        assert!(!queue.contains('\\'));
+        assert!(!queue.contains(":/"));
+
+        let events = t.pending_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["properties"]["file_count"], "100k-1M");
+        assert_eq!(events[1]["properties"]["code"], "PathNotFound");
+        assert_eq!(events[0]["machine_id"], events[1]["machine_id"]);
+    }
+
+    #[test]
+    fn test_flush_keeps_events_on_failure_and_opt_out_resets_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let t = Telemetry::new(dir.path(), &config(true));
+        for _ in 0..3 {
+            t.record_event(
+                "plan_executed",
+                &[("bytes_freed", TelemetryValue::Label("0"))],
+            );
+        }
+        assert!(t.flush_with(|_, _| Err("offline")).is_err());
+        assert_eq!(t.pending_events().len(), 3);
+
+        let first_id = t.pending_events()[0]["machine_id"].clone();
+        let sent = t.flush_with(|endpoint, batch| {
+            assert!(endpoint.starts_with("https://"));
+            assert_eq!(batch.len(), 3);
+            Ok::<(), ()>(())
+        });
+        assert_eq!(sent, Ok(3));
+        assert!(t.pending_events().is_empty());
+
+        t.apply_config(&config(false));
+        t.apply_config(&config(true));
+        t.record_event("plan_executed", &[]);
+        assert_ne!(t.pending_events()[0]["machine_id"], first_id);
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(count_bucket(0), "<1k");
+        assert_eq!(count_bucket(1_000_000), ">=1M");
+        assert_eq!(bytes_bucket(0), "0");
+        assert_eq!(bytes_bucket(2 * 1024 * 1024 * 1024), "1GB-10GB");
+    }
 }
@@ -0,0 +1,622 @@
+//! 发现当前系统与用户下常见的可回收位置：临时目录、系统更新缓存、浏览器缓存、
+//! 包管理器缓存、Xcode DerivedData 等，并统计各自的占用。
+//!
+//! 所有路径都由环境变量（`TEMP`、`LOCALAPPDATA`、`HOME`、`XDG_CACHE_HOME`、`CARGO_HOME` 等）
+//! 推导，不写死绝对路径；对应变量不存在时跳过该位置。
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
+
+use ai_disk_domain::{CleanupTarget, FileCategory};
+
+use crate::scanner::dir_size_only;
+
+/// 要发现哪个平台的位置；测试可以在任意主机上构造其他平台的候选
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// 由环境变量解析出的已知目录
+#[derive(Debug, Clone, Default)]
+pub struct KnownFolders {
+    pub home: Option<PathBuf>,
+    pub temp: Option<PathBuf>,
+    /// Windows `%LOCALAPPDATA%`
+    pub local_app_data: Option<PathBuf>,
+    /// Windows `%SystemRoot%`
+    pub system_root: Option<PathBuf>,
+    /// `$XDG_CACHE_HOME`，未设置时为 `~/.cache`
+    pub xdg_cache: Option<PathBuf>,
+    /// `$XDG_DATA_HOME`，未设置时为 `~/.local/share`
+    pub xdg_data: Option<PathBuf>,
+    pub cargo_home: Option<PathBuf>,
+    pub gradle_home: Option<PathBuf>,
+    /// `npm_config_cache` 覆盖的 npm 缓存目录
+    pub npm_cache: Option<PathBuf>,
+    /// `PIP_CACHE_DIR` 覆盖的 pip 缓存目录
+    pub pip_cache: Option<PathBuf>,
+}
+
+impl KnownFolders {
+    /// 从当前进程的环境变量解析
+    pub fn from_env() -> Self {
+        Self::from_lookup(Platform::current(), |key| std::env::var_os(key))
+    }
+
+    /// 用给定的环境变量查询函数解析；空值视为未设置
+    pub fn from_lookup(platform: Platform, lookup: impl Fn(&str) -> Option<OsString>) -> Self {
+        let var = |key: &str| lookup(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let home = match platform {
+            Platform::Windows => var("USERPROFILE"),
+            Platform::MacOs | Platform::Linux => var("HOME"),
+        };
+        let temp = match platform {
+            Platform::Windows => var("TEMP").or_else(|| var("TMP")),
+            Platform::MacOs | Platform::Linux => var("TMPDIR").or_else(|| {
+                // 未设置 TMPDIR 时使用标准库的默认临时目录（Linux 上为 /tmp）
+                (platform == Platform::current()).then(std::env::temp_dir)
+            }),
+        };
+        let xdg_cache = match platform {
+            Platform::Linux => {
+                var("XDG_CACHE_HOME").or_else(|| home.as_ref().map(|h| h.join(".cache")))
+            }
+            _ => None,
+        };
+        let xdg_data = match platform {
+            Platform::Linux => var("XDG_DATA_HOME")
+                .or_else(|| home.as_ref().map(|h| h.join(".local").join("share"))),
+            _ => None,
+        };
+        Self {
+            temp,
+            local_app_data: var("LOCALAPPDATA"),
+            system_root: var("SystemRoot").or_else(|| var("WINDIR")),
+            xdg_cache,
+            xdg_data,
+            cargo_home: var("CARGO_HOME").or_else(|| home.as_ref().map(|h| h.join(".cargo"))),
+            gradle_home: var("GRADLE_USER_HOME")
+                .or_else(|| home.as_ref().map(|h| h.join(".gradle"))),
+            npm_cache: var("npm_config_cache"),
+            pip_cache: var("PIP_CACHE_DIR"),
+            home,
+        }
+    }
+}
+
+/// 待统计的位置
+#[derive(Debug, Clone)]
+struct Candidate {
+    path: PathBuf,
+    name: String,
+    category: FileCategory,
+    requires_admin: bool,
+}
+
+impl Candidate {
+    fn new(path: PathBuf, name: impl Into<String>, category: FileCategory) -> Self {
+        Self {
+            path,
+            name: name.into(),
+            category,
+            requires_admin: false,
+        }
+    }
+
+    fn admin(mut self) -> Self {
+        self.requires_admin = true;
+        self
+    }
+}
+
+/// 浏览器缓存所在的根目录
+enum BrowserRoot {
+    /// Chromium 系：`<root>/<profile>/Cache` 与 `Code Cache`
+    Chromium(PathBuf, &'static str),
+    /// Firefox：`<root>/<profile>/cache2`
+    Firefox(PathBuf),
+}
+
+fn browser_roots(platform: Platform, folders: &KnownFolders) -> Vec<BrowserRoot> {
+    let mut roots = Vec::new();
+    match platform {
+        Platform::Windows => {
+            if let Some(local) = &folders.local_app_data {
+                roots.push(BrowserRoot::Chromium(
+                    local.join("Google").join("Chrome").join("User Data"),
+                    "Chrome",
+                ));
+                roots.push(BrowserRoot::Chromium(
+                    local.join("Microsoft").join("Edge").join("User Data"),
+                    "Edge",
+                ));
+                roots.push(BrowserRoot::Firefox(
+                    local.join("Mozilla").join("Firefox").join("Profiles"),
+                ));
+            }
+        }
+        Platform::MacOs => {
+            if let Some(home) = &folders.home {
+                let caches = home.join("Library").join("Caches");
+                roots.push(BrowserRoot::Chromium(
+                    caches.join("Google").join("Chrome"),
+                    "Chrome",
+                ));
+                roots.push(BrowserRoot::Chromium(caches.join("Microsoft Edge"), "Edge"));
+                roots.push(BrowserRoot::Firefox(
+                    caches.join("Firefox").join("Profiles"),
+                ));
+            }
+        }
+        Platform::Linux => {
+            if let Some(cache) = &folders.xdg_cache {
+                roots.push(BrowserRoot::Chromium(cache.join("google-chrome"), "Chrome"));
+                roots.push(BrowserRoot::Chromium(cache.join("microsoft-edge"), "Edge"));
+                roots.push(BrowserRoot::Firefox(cache.join("mozilla").join("firefox")));
+            }
+        }
+    }
+    roots
+}
+
+/// 列出浏览器根目录下各配置文件的缓存目录；根目录不存在时返回根目录本身（标记为不存在）
+fn browser_candidates(root: &BrowserRoot) -> Vec<Candidate> {
+    let (dir, browser) = match root {
+        BrowserRoot::Chromium(dir, browser) => (dir, *browser),
+        BrowserRoot::Firefox(dir) => (dir, "Firefox"),
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![Candidate::new(
+            dir.clone(),
+            format!("{} 缓存", browser),
+            FileCategory::BrowserCache,
+        )];
+    };
+    let mut profiles: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    profiles.sort();
+
+    let cache_dirs: &[&str] = match root {
+        BrowserRoot::Chromium(..) => &["Cache", "Code Cache"],
+        BrowserRoot::Firefox(_) => &["cache2"],
+    };
+    let mut candidates = Vec::new();
+    for profile in profiles {
+        let profile_name = profile
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for cache in cache_dirs {
+            let path = profile.join(cache);
+            if path.is_dir() {
+                candidates.push(Candidate::new(
+                    path,
+                    format!("{} {} ({})", browser, cache, profile_name),
+                    FileCategory::BrowserCache,
+                ));
+            }
+        }
+    }
+    candidates
+}
+
+/// 当前平台的候选位置
+fn candidates(platform: Platform, folders: &KnownFolders) -> Vec<Candidate> {
+    use FileCategory::{AppCache, PackageCache, Temp};
+
+    let mut list = Vec::new();
+    let home = folders.home.as_deref();
+    if let Some(temp) = &folders.temp {
+        list.push(Candidate::new(temp.clone(), "用户临时目录", Temp));
+    }
+
+    match platform {
+        Platform::Windows => {
+            if let Some(local) = &folders.local_app_data {
+                list.push(Candidate::new(local.join("Temp"), "本地应用临时目录", Temp));
+                list.push(Candidate::new(
+                    folders
+                        .npm_cache
+                        .clone()
+                        .unwrap_or_else(|| local.join("npm-cache")),
+                    "npm 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    local.join("Yarn").join("Cache"),
+                    "Yarn 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    local.join("pnpm").join("store"),
+                    "pnpm 存储",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    folders
+                        .pip_cache
+                        .clone()
+                        .unwrap_or_else(|| local.join("pip").join("Cache")),
+                    "pip 缓存",
+                    PackageCache,
+                ));
+            }
+            if let Some(system) = &folders.system_root {
+                list.push(Candidate::new(system.join("Temp"), "系统临时目录", Temp).admin());
+                list.push(
+                    Candidate::new(
+                        system.join("SoftwareDistribution").join("Download"),
+                        "Windows 更新下载缓存",
+                        AppCache,
+                    )
+                    .admin(),
+                );
+            }
+        }
+        Platform::MacOs => {
+            if let Some(home) = home {
+                let library = home.join("Library");
+                list.push(Candidate::new(
+                    library.join("Caches"),
+                    "用户缓存 (~/Library/Caches)",
+                    AppCache,
+                ));
+                list.push(Candidate::new(
+                    library.join("Developer").join("Xcode").join("DerivedData"),
+                    "Xcode DerivedData",
+                    AppCache,
+                ));
+                list.push(Candidate::new(
+                    folders
+                        .npm_cache
+                        .clone()
+                        .unwrap_or_else(|| home.join(".npm")),
+                    "npm 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    library.join("Caches").join("Yarn"),
+                    "Yarn 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    library.join("pnpm").join("store"),
+                    "pnpm 存储",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    folders
+                        .pip_cache
+                        .clone()
+                        .unwrap_or_else(|| library.join("Caches").join("pip")),
+                    "pip 缓存",
+                    PackageCache,
+                ));
+            }
+        }
+        Platform::Linux => {
+            if let Some(cache) = &folders.xdg_cache {
+                list.push(Candidate::new(
+                    cache.clone(),
+                    "用户缓存 (~/.cache)",
+                    AppCache,
+                ));
+                list.push(Candidate::new(
+                    cache.join("yarn"),
+                    "Yarn 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    folders
+                        .pip_cache
+                        .clone()
+                        .unwrap_or_else(|| cache.join("pip")),
+                    "pip 缓存",
+                    PackageCache,
+                ));
+            }
+            if let Some(home) = home {
+                list.push(Candidate::new(
+                    folders
+                        .npm_cache
+                        .clone()
+                        .unwrap_or_else(|| home.join(".npm")),
+                    "npm 缓存",
+                    PackageCache,
+                ));
+                list.push(Candidate::new(
+                    home.join(".local").join("share").join("pnpm").join("store"),
+                    "pnpm 存储",
+                    PackageCache,
+                ));
+            }
+        }
+    }
+
+    if let Some(cargo) = &folders.cargo_home {
+        let registry = cargo.join("registry");
+        list.push(Candidate::new(
+            registry.join("cache"),
+            "Cargo 下载的 crate",
+            PackageCache,
+        ));
+        list.push(Candidate::new(
+            registry.join("src"),
+            "Cargo 解压的源码",
+            PackageCache,
+        ));
+    }
+    if let Some(gradle) = &folders.gradle_home {
+        list.push(Candidate::new(
+            gradle.join("caches"),
+            "Gradle 缓存",
+            PackageCache,
+        ));
+    }
+    for root in browser_roots(platform, folders) {
+        list.extend(browser_candidates(&root));
+    }
+
+    // 同一路径只保留第一次出现（如 %TEMP% 与 %LOCALAPPDATA%\Temp 通常相同）
+    let mut seen = std::collections::HashSet::new();
+    list.retain(|c| seen.insert(c.path.clone()));
+    list
+}
+
+/// 统计单个位置；根目录无权限读取时视为需要管理员权限
+fn measure(candidate: Candidate) -> CleanupTarget {
+    let exists = candidate.path.exists();
+    let denied = exists
+        && matches!(
+            std::fs::read_dir(&candidate.path),
+            Err(ref e) if e.kind() == std::io::ErrorKind::PermissionDenied
+        );
+    let bytes = if exists && !denied {
+        dir_size_only(&candidate.path, &AtomicU64::new(0), None).unwrap_or(0)
+    } else {
+        0
+    };
+    CleanupTarget {
+        path: candidate.path.display().to_string(),
+        name: candidate.name,
+        category: candidate.category,
+        bytes,
+        exists,
+        requires_admin: candidate.requires_admin || denied,
+    }
+}
+
+/// 从外层目标的大小中扣除同时被列出的子目录，避免重复计算
+fn subtract_nested(targets: &mut [CleanupTarget]) {
+    let paths: Vec<(PathBuf, u64)> = targets
+        .iter()
+        .map(|t| (PathBuf::from(&t.path), t.bytes))
+        .collect();
+    for target in targets.iter_mut() {
+        let outer = Path::new(&target.path);
+        let nested: u64 = paths
+            .iter()
+            .filter(|(p, _)| p != outer && p.starts_with(outer))
+            // 只扣除直接嵌套的一层，更深的嵌套已从中间层扣除
+            .filter(|(p, _)| {
+                !paths.iter().any(|(mid, _)| {
+                    mid != outer && mid != p && mid.starts_with(outer) && p.starts_with(mid)
+                })
+            })
+            .map(|(_, bytes)| *bytes)
+            .sum();
+        target.bytes = target.bytes.saturating_sub(nested);
+    }
+}
+
+fn discover(platform: Platform, folders: &KnownFolders) -> Vec<CleanupTarget> {
+    let mut targets: Vec<CleanupTarget> = candidates(platform, folders)
+        .into_iter()
+        .map(measure)
+        .collect();
+    subtract_nested(&mut targets);
+    targets
+}
+
+/// 枚举当前系统与用户下常见的可回收位置并统计占用；不存在的位置也会返回（`exists: false`）
+pub fn discover_cleanup_targets() -> Vec<CleanupTarget> {
+    discover(Platform::current(), &KnownFolders::from_env())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+    }
+
+    fn folders(platform: Platform, vars: &[(&str, &Path)]) -> KnownFolders {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.as_os_str().to_owned()))
+            .collect();
+        KnownFolders::from_lookup(platform, |key| vars.get(key).cloned())
+    }
+
+    fn find<'a>(targets: &'a [CleanupTarget], name: &str) -> &'a CleanupTarget {
+        targets
+            .iter()
+            .find(|t| t.name == name)
+            .unwrap_or_else(|| panic!("缺少目标 {}", name))
+    }
+
+    #[test]
+    fn test_windows_targets_from_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let local = root.join("AppData").join("Local");
+        let system = root.join("Windows");
+        write(&local.join("Temp").join("a.tmp"), 100);
+        write(&local.join("npm-cache").join("_cacache").join("x"), 40);
+        let chrome = local.join("Google").join("Chrome").join("User Data");
+        write(&chrome.join("Default").join("Cache").join("data_0"), 300);
+        write(&chrome.join("Profile 1").join("Code Cache").join("js"), 50);
+        write(&chrome.join("Default").join("History"), 999);
+        write(
+            &system
+                .join("SoftwareDistribution")
+                .join("Download")
+                .join("kb.cab"),
+            700,
+        );
+
+        let folders = folders(
+            Platform::Windows,
+            &[
+                ("USERPROFILE", root),
+                ("TEMP", &local.join("Temp")),
+                ("LOCALAPPDATA", &local),
+                ("SystemRoot", &system),
+            ],
+        );
+        let targets = discover(Platform::Windows, &folders);
+
+        // %TEMP% 与 %LOCALAPPDATA%\Temp 相同，只出现一次
+        assert_eq!(
+            targets
+                .iter()
+                .filter(|t| t.category == FileCategory::Temp && t.bytes == 100)
+                .count(),
+            1
+        );
+        assert_eq!(find(&targets, "npm 缓存").bytes, 40);
+        assert_eq!(find(&targets, "Chrome Cache (Default)").bytes, 300);
+        assert_eq!(find(&targets, "Chrome Code Cache (Profile 1)").bytes, 50);
+        let update = find(&targets, "Windows 更新下载缓存");
+        assert_eq!(update.bytes, 700);
+        assert!(update.requires_admin);
+
+        // 不存在的位置仍然返回，标记为不存在
+        let edge = find(&targets, "Edge 缓存");
+        assert!(!edge.exists);
+        assert_eq!(edge.bytes, 0);
+        let pip = find(&targets, "pip 缓存");
+        assert!(!pip.exists);
+        assert!(pip.path.starts_with(&local.display().to_string()));
+    }
+
+    #[test]
+    fn test_macos_nested_targets_are_not_double_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path();
+        let caches = home.join("Library").join("Caches");
+        write(&caches.join("com.example.app").join("blob"), 10);
+        write(&caches.join("pip").join("http").join("x"), 20);
+        write(
+            &caches
+                .join("Google")
+                .join("Chrome")
+                .join("Default")
+                .join("Cache")
+                .join("f"),
+            30,
+        );
+        write(
+            &home
+                .join("Library")
+                .join("Developer")
+                .join("Xcode")
+                .join("DerivedData")
+                .join("App-abc")
+                .join("o"),
+            400,
+        );
+        write(
+            &home
+                .join(".cargo")
+                .join("registry")
+                .join("cache")
+                .join("index")
+                .join("serde.crate"),
+            60,
+        );
+
+        let folders = folders(
+            Platform::MacOs,
+            &[("HOME", home), ("TMPDIR", &home.join("tmp"))],
+        );
+        let targets = discover(Platform::MacOs, &folders);
+
+        assert_eq!(find(&targets, "用户缓存 (~/Library/Caches)").bytes, 10);
+        assert_eq!(find(&targets, "pip 缓存").bytes, 20);
+        assert_eq!(find(&targets, "Chrome Cache (Default)").bytes, 30);
+        assert_eq!(find(&targets, "Xcode DerivedData").bytes, 400);
+        assert_eq!(find(&targets, "Cargo 下载的 crate").bytes, 60);
+        assert!(!find(&targets, "用户临时目录").exists);
+        let total: u64 = targets.iter().map(|t| t.bytes).sum();
+        assert_eq!(total, 520);
+    }
+
+    #[test]
+    fn test_linux_respects_overrides_and_skips_unset_vars() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        let xdg = dir.path().join("xdg-cache");
+        let cargo = dir.path().join("cargo-home");
+        write(
+            &xdg.join("mozilla")
+                .join("firefox")
+                .join("abc.default")
+                .join("cache2")
+                .join("entries")
+                .join("1"),
+            80,
+        );
+        write(
+            &cargo
+                .join("registry")
+                .join("src")
+                .join("crate")
+                .join("lib.rs"),
+            15,
+        );
+
+        let folders = folders(
+            Platform::Linux,
+            &[
+                ("HOME", &home),
+                ("XDG_CACHE_HOME", &xdg),
+                ("CARGO_HOME", &cargo),
+                ("TMPDIR", &dir.path().join("t")),
+            ],
+        );
+        let targets = discover(Platform::Linux, &folders);
+
+        assert_eq!(find(&targets, "Firefox cache2 (abc.default)").bytes, 80);
+        assert_eq!(find(&targets, "用户缓存 (~/.cache)").bytes, 0);
+        assert_eq!(find(&targets, "Cargo 解压的源码").bytes, 15);
+        assert!(targets.iter().all(|t| !t.path.contains("AppData")));
+
+        // 没有任何环境变量时不返回依赖这些变量的位置
+        let empty = KnownFolders::from_lookup(Platform::Windows, |_| None);
+        assert!(discover(Platform::Windows, &empty).is_empty());
+    }
+}
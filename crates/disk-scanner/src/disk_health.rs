@@ -0,0 +1,769 @@
+//! 磁盘健康摘要（SMART）。
+//!
+//! - Windows：通过 `DeviceIoControl` 查询卷所在设备的型号、序列号、寻道惩罚（判断 SSD/HDD），
+//!   以及 `IOCTL_STORAGE_PREDICT_FAILURE`（其中的厂商数据即 ATA SMART 属性页）
+//! - Linux：由 `/proc/self/mountinfo` 找到块设备，读取 `/sys/block` 下的信息；
+//!   安装了 smartctl 时再解析 `smartctl -a -j` 的 JSON 输出
+//!
+//! 任何一步失败都只在 `warnings` 中记录，返回能拿到的部分结果。
+
+use ai_disk_domain::{DiskHealth, MediaType, SmartAttribute};
+use serde_json::Value;
+
+/// ATA SMART 属性页中的属性条目数与每条长度
+const ATA_SMART_ENTRIES: usize = 30;
+const ATA_SMART_ENTRY_LEN: usize = 12;
+
+const ATTR_REALLOCATED_SECTORS: u8 = 5;
+const ATTR_POWER_ON_HOURS: u8 = 9;
+const ATTR_AIRFLOW_TEMPERATURE: u8 = 190;
+const ATTR_TEMPERATURE: u8 = 194;
+
+/// 常见 SMART 属性名（与 smartctl 的命名一致）
+fn ata_attribute_name(id: u8) -> &'static str {
+    match id {
+        1 => "Raw_Read_Error_Rate",
+        3 => "Spin_Up_Time",
+        4 => "Start_Stop_Count",
+        5 => "Reallocated_Sector_Ct",
+        7 => "Seek_Error_Rate",
+        9 => "Power_On_Hours",
+        10 => "Spin_Retry_Count",
+        12 => "Power_Cycle_Count",
+        177 => "Wear_Leveling_Count",
+        187 => "Reported_Uncorrect",
+        190 => "Airflow_Temperature_Cel",
+        194 => "Temperature_Celsius",
+        196 => "Reallocated_Event_Count",
+        197 => "Current_Pending_Sector",
+        198 => "Offline_Uncorrectable",
+        199 => "UDMA_CRC_Error_Count",
+        231 => "SSD_Life_Left",
+        241 => "Total_LBAs_Written",
+        242 => "Total_LBAs_Read",
+        _ => "Unknown_Attribute",
+    }
+}
+
+/// 解析 ATA SMART READ DATA 属性页（512 字节），`thresholds` 为 READ THRESHOLDS 页。
+/// 两页都从偏移 2 开始，每条 12 字节，id 为 0 的条目为空
+pub fn parse_ata_smart_data(data: &[u8], thresholds: Option<&[u8]>) -> Vec<SmartAttribute> {
+    let threshold_of = |id: u8| -> u8 {
+        let Some(page) = thresholds else {
+            return 0;
+        };
+        (0..ATA_SMART_ENTRIES)
+            .map(|i| 2 + i * ATA_SMART_ENTRY_LEN)
+            .filter_map(|o| page.get(o..o + 2))
+            .find(|entry| entry[0] == id)
+            .map(|entry| entry[1])
+            .unwrap_or(0)
+    };
+    (0..ATA_SMART_ENTRIES)
+        .map(|i| 2 + i * ATA_SMART_ENTRY_LEN)
+        .filter_map(|o| data.get(o..o + ATA_SMART_ENTRY_LEN))
+        .filter(|entry| entry[0] != 0)
+        .map(|entry| {
+            let mut raw = [0u8; 8];
+            raw[..6].copy_from_slice(&entry[5..11]);
+            SmartAttribute {
+                id: entry[0],
+                name: ata_attribute_name(entry[0]).to_string(),
+                current: entry[3],
+                worst: entry[4],
+                threshold: threshold_of(entry[0]),
+                raw: u64::from_le_bytes(raw),
+            }
+        })
+        .collect()
+}
+
+/// 当前值降到阈值以下的属性说明厂商认为设备即将失效
+fn attributes_indicate_failure(attributes: &[SmartAttribute]) -> bool {
+    attributes
+        .iter()
+        .any(|a| a.threshold > 0 && a.current <= a.threshold)
+}
+
+/// 从属性表中填充温度、通电时间、重映射扇区数与失效预测
+fn apply_attributes(health: &mut DiskHealth, attributes: Vec<SmartAttribute>) {
+    let raw_of = |id: u8| attributes.iter().find(|a| a.id == id).map(|a| a.raw);
+    // 温度只取原始值的最低字节，其余字节是部分厂商记录的最低/最高温度
+    let temperature = raw_of(ATTR_TEMPERATURE)
+        .or_else(|| raw_of(ATTR_AIRFLOW_TEMPERATURE))
+        .map(|raw| (raw & 0xFF) as u32);
+    health.temperature_celsius = health.temperature_celsius.or(temperature);
+    // 部分厂商在高位记录分钟数，只取低 32 位
+    health.power_on_hours = health
+        .power_on_hours
+        .or(raw_of(ATTR_POWER_ON_HOURS).map(|raw| raw & 0xFFFF_FFFF));
+    health.reallocated_sectors = health
+        .reallocated_sectors
+        .or(raw_of(ATTR_REALLOCATED_SECTORS));
+    health.predicted_failure |= attributes_indicate_failure(&attributes);
+    health.raw_attributes = attributes;
+}
+
+/// 只保留序列号末 4 位，其余替换为 `*`
+pub fn redact_serial(serial: &str) -> Option<String> {
+    let serial = serial.trim();
+    if serial.is_empty() {
+        return None;
+    }
+    let chars: Vec<char> = serial.chars().collect();
+    let keep = chars.len().min(4);
+    let hidden = chars.len() - keep;
+    Some(
+        std::iter::repeat_n('*', hidden)
+            .chain(chars[hidden..].iter().copied())
+            .collect(),
+    )
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// 解析 `smartctl -a -j` 的 JSON 输出（ATA 与 NVMe），结果写入 `health` 中尚未取得的字段
+pub fn apply_smartctl_json(health: &mut DiskHealth, json: &str) -> Result<(), String> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| format!("无法解析 smartctl 输出: {}", e))?;
+    // smartctl 打不开设备时仍输出 JSON，错误信息在 messages 中
+    if let Some(messages) = value["smartctl"]["messages"].as_array() {
+        for message in messages {
+            if message["severity"].as_str() == Some("error") {
+                if let Some(text) = message["string"].as_str() {
+                    health.warnings.push(format!("smartctl: {}", text));
+                }
+            }
+        }
+    }
+
+    if health.model.is_none() {
+        health.model = value["model_name"].as_str().and_then(non_empty);
+    }
+    if health.serial_redacted.is_none() {
+        health.serial_redacted = value["serial_number"].as_str().and_then(redact_serial);
+    }
+    if health.media_type == MediaType::Unknown {
+        let is_nvme = value["device"]["protocol"]
+            .as_str()
+            .is_some_and(|p| p.eq_ignore_ascii_case("nvme"));
+        health.media_type = match value["rotation_rate"].as_u64() {
+            _ if is_nvme => MediaType::Ssd,
+            Some(0) => MediaType::Ssd,
+            Some(_) => MediaType::Hdd,
+            None => MediaType::Unknown,
+        };
+    }
+    if let Some(current) = value["temperature"]["current"].as_u64() {
+        health.temperature_celsius = Some(current as u32);
+    }
+    if let Some(hours) = value["power_on_time"]["hours"].as_u64() {
+        health.power_on_hours = Some(hours);
+    }
+    if value["smart_status"]["passed"].as_bool() == Some(false) {
+        health.predicted_failure = true;
+    }
+
+    let nvme = &value["nvme_smart_health_information_log"];
+    if nvme.is_object() {
+        if health.temperature_celsius.is_none() {
+            health.temperature_celsius = nvme["temperature"].as_u64().map(|t| t as u32);
+        }
+        if health.power_on_hours.is_none() {
+            health.power_on_hours = nvme["power_on_hours"].as_u64();
+        }
+        // critical_warning 的任一位被置位（备用空间不足、只读模式等）都视为即将失效
+        if nvme["critical_warning"].as_u64().is_some_and(|w| w != 0) {
+            health.predicted_failure = true;
+        }
+    }
+
+    if let Some(table) = value["ata_smart_attributes"]["table"].as_array() {
+        let attributes: Vec<SmartAttribute> = table
+            .iter()
+            .filter_map(|row| {
+                let id = u8::try_from(row["id"].as_u64()?).ok()?;
+                Some(SmartAttribute {
+                    id,
+                    name: row["name"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| ata_attribute_name(id).to_string()),
+                    current: row["value"].as_u64().unwrap_or(0) as u8,
+                    worst: row["worst"].as_u64().unwrap_or(0) as u8,
+                    threshold: row["thresh"].as_u64().unwrap_or(0) as u8,
+                    raw: row["raw"]["value"].as_u64().unwrap_or(0),
+                })
+            })
+            .collect();
+        apply_attributes(health, attributes);
+    }
+    Ok(())
+}
+
+/// 解析 `IOCTL_STORAGE_QUERY_PROPERTY(StorageDeviceProperty)` 返回的 STORAGE_DEVICE_DESCRIPTOR，
+/// 得到 (型号, 序列号, 总线类型)
+pub fn parse_storage_device_descriptor(buf: &[u8]) -> (Option<String>, Option<String>, u32) {
+    let u32_at = |offset: usize| -> u32 {
+        buf.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .unwrap_or(0)
+    };
+    // 偏移为 0 表示该字段不存在；字符串以 NUL 结尾，常以空格补齐
+    let string_at = |offset: u32| -> Option<String> {
+        let start = offset as usize;
+        if start == 0 || start >= buf.len() {
+            return None;
+        }
+        let end = buf[start..]
+            .iter()
+            .position(|&b| b == 0)
+            .map_or(buf.len(), |p| start + p);
+        non_empty(&String::from_utf8_lossy(&buf[start..end]))
+    };
+    let vendor = string_at(u32_at(12));
+    let product = string_at(u32_at(16));
+    let model = match (vendor, product) {
+        (Some(v), Some(p)) if !p.starts_with(&v) => Some(format!("{} {}", v, p)),
+        (v, p) => p.or(v),
+    };
+    let serial = string_at(u32_at(24));
+    (model, serial, u32_at(28))
+}
+
+/// 在 `/proc/self/mountinfo` 中找到包含 `path` 的最长挂载点，返回其挂载源（如 `/dev/sda2`）
+#[cfg(any(target_os = "linux", test))]
+fn mount_source_for(mountinfo: &str, path: &std::path::Path) -> Option<String> {
+    // mountinfo 中的空格等字符以八进制转义，例如 `\040`
+    fn unescape(field: &str) -> String {
+        let mut out = String::new();
+        let mut rest = field;
+        while let Some(i) = rest.find('\\') {
+            out.push_str(&rest[..i]);
+            match rest
+                .get(i + 1..i + 4)
+                .and_then(|o| u8::from_str_radix(o, 8).ok())
+            {
+                Some(byte) => {
+                    out.push(byte as char);
+                    rest = &rest[i + 4..];
+                }
+                None => {
+                    out.push('\\');
+                    rest = &rest[i + 1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let mount_point = unescape(left.split(' ').nth(4)?);
+            let source = right.split(' ').nth(1)?;
+            Some((mount_point, source.to_string()))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, source)| source)
+}
+
+/// 查询 `volume`（Windows 上为盘符，其他平台为任意路径）所在磁盘的健康信息
+pub fn get_disk_health(volume: &str) -> DiskHealth {
+    let mut health = DiskHealth::empty(volume);
+    platform::fill(&mut health, volume);
+    health
+}
+
+/// 仅查询 `volume` 所在磁盘是否为机械硬盘，不读取 SMART（不调用 smartctl，开销很小）
+pub fn detect_media_type(volume: &str) -> MediaType {
+    platform::media_type(volume)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use super::*;
+
+    /// 分区（如 sda2、nvme0n1p2）所属的整块磁盘名
+    fn whole_disk(name: &str) -> String {
+        let class = Path::new("/sys/class/block").join(name);
+        if class.join("partition").exists() {
+            if let Some(parent) = std::fs::canonicalize(&class)
+                .ok()
+                .and_then(|p| p.parent().map(Path::to_path_buf))
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            {
+                return parent;
+            }
+        }
+        name.to_string()
+    }
+
+    fn read_sys(disk: &Path, file: &str) -> Option<String> {
+        std::fs::read_to_string(disk.join(file))
+            .ok()
+            .and_then(|s| non_empty(&s))
+    }
+
+    /// 路径所在的整块磁盘名（如 sda、nvme0n1）；不在块设备上时返回 None
+    fn disk_for(volume: &str) -> Option<String> {
+        let path = std::fs::canonicalize(volume).unwrap_or_else(|_| PathBuf::from(volume));
+        let source = std::fs::read_to_string("/proc/self/mountinfo")
+            .ok()
+            .and_then(|info| mount_source_for(&info, &path))
+            .filter(|s| s.starts_with("/dev/"))?;
+        let source = std::fs::canonicalize(&source)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or(source);
+        Some(whole_disk(source.trim_start_matches("/dev/")))
+    }
+
+    fn rotational(sys: &Path) -> MediaType {
+        match read_sys(sys, "queue/rotational").as_deref() {
+            Some("0") => MediaType::Ssd,
+            Some("1") => MediaType::Hdd,
+            _ => MediaType::Unknown,
+        }
+    }
+
+    pub(super) fn media_type(volume: &str) -> MediaType {
+        disk_for(volume)
+            .map(|disk| rotational(&Path::new("/sys/block").join(disk)))
+            .unwrap_or(MediaType::Unknown)
+    }
+
+    pub(super) fn fill(health: &mut DiskHealth, volume: &str) {
+        let Some(disk) = disk_for(volume) else {
+            health
+                .warnings
+                .push("无法确定该路径所在的块设备".to_string());
+            return;
+        };
+        let device = format!("/dev/{}", disk);
+        health.device = Some(device.clone());
+
+        let sys = Path::new("/sys/block").join(&disk);
+        health.media_type = rotational(&sys);
+        health.model = read_sys(&sys, "device/model");
+        health.serial_redacted = read_sys(&sys, "device/serial").and_then(|s| redact_serial(&s));
+
+        match Command::new("smartctl")
+            .args(["-a", "-j", &device])
+            .output()
+        {
+            Ok(output) if !output.stdout.is_empty() => {
+                if let Err(e) =
+                    apply_smartctl_json(health, &String::from_utf8_lossy(&output.stdout))
+                {
+                    health.warnings.push(e);
+                }
+            }
+            Ok(_) => health
+                .warnings
+                .push("smartctl 没有输出，可能需要 root 权限".to_string()),
+            Err(_) => health
+                .warnings
+                .push("未安装 smartctl，无法读取 SMART 属性".to_string()),
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+mod platform {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows_sys::Win32::System::Ioctl::{
+        PropertyStandardQuery, StorageDeviceProperty, StorageDeviceSeekPenaltyProperty,
+        DEVICE_SEEK_PENALTY_DESCRIPTOR, IOCTL_STORAGE_PREDICT_FAILURE,
+        IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_PREDICT_FAILURE, STORAGE_PROPERTY_QUERY,
+    };
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    use super::*;
+
+    /// STORAGE_BUS_TYPE 中的 NVMe
+    const BUS_TYPE_NVME: u32 = 17;
+
+    /// `C:`、`C:\`、`C` 统一为 `\\.\C:`
+    fn volume_device(volume: &str) -> Option<String> {
+        let letter = volume.trim().chars().next()?;
+        letter
+            .is_ascii_alphabetic()
+            .then(|| format!(r"\\.\{}:", letter.to_ascii_uppercase()))
+    }
+
+    fn ioctl<T>(
+        handle: HANDLE,
+        code: u32,
+        input: Option<&STORAGE_PROPERTY_QUERY>,
+        out: &mut T,
+    ) -> bool {
+        let mut returned = 0u32;
+        let (in_ptr, in_len) = match input {
+            Some(query) => (
+                query as *const STORAGE_PROPERTY_QUERY as *const c_void,
+                std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+            ),
+            None => (std::ptr::null(), 0),
+        };
+        // SAFETY: 输入输出缓冲区的指针与长度一致，同步调用不使用 OVERLAPPED
+        unsafe {
+            DeviceIoControl(
+                handle,
+                code,
+                in_ptr,
+                in_len,
+                out as *mut T as *mut c_void,
+                std::mem::size_of::<T>() as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            ) != 0
+        }
+    }
+
+    fn query(property: i32) -> STORAGE_PROPERTY_QUERY {
+        STORAGE_PROPERTY_QUERY {
+            PropertyId: property,
+            QueryType: PropertyStandardQuery,
+            AdditionalParameters: [0],
+        }
+    }
+
+    /// 以访问权限 0 打开卷设备：查询设备属性不需要管理员权限。调用方负责关闭句柄
+    fn open_device(device: &str) -> HANDLE {
+        let wide: Vec<u16> = std::ffi::OsStr::new(device)
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        // SAFETY: 路径以 NUL 结尾
+        unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                0,
+                0,
+            )
+        }
+    }
+
+    /// 按寻道开销判断机械硬盘；设备不支持该查询时返回 Unknown
+    fn seek_penalty(handle: HANDLE) -> MediaType {
+        // SAFETY: 全零是该 C 结构体的合法值
+        let mut seek: DEVICE_SEEK_PENALTY_DESCRIPTOR = unsafe { std::mem::zeroed() };
+        if !ioctl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            Some(&query(StorageDeviceSeekPenaltyProperty)),
+            &mut seek,
+        ) {
+            return MediaType::Unknown;
+        }
+        if seek.IncursSeekPenalty != 0 {
+            MediaType::Hdd
+        } else {
+            MediaType::Ssd
+        }
+    }
+
+    pub(super) fn media_type(volume: &str) -> MediaType {
+        let Some(device) = volume_device(volume) else {
+            return MediaType::Unknown;
+        };
+        let handle = open_device(&device);
+        if handle == INVALID_HANDLE_VALUE {
+            return MediaType::Unknown;
+        }
+        let media_type = seek_penalty(handle);
+        // SAFETY: handle 由 CreateFileW 打开且尚未关闭
+        unsafe {
+            CloseHandle(handle);
+        }
+        media_type
+    }
+
+    pub(super) fn fill(health: &mut DiskHealth, volume: &str) {
+        let Some(device) = volume_device(volume) else {
+            health.warnings.push(format!("无效的卷: {}", volume));
+            return;
+        };
+        health.device = Some(device.clone());
+        // 句柄在函数末尾关闭
+        let handle = open_device(&device);
+        if handle == INVALID_HANDLE_VALUE {
+            health.warnings.push(format!(
+                "无法打开 {}: {}",
+                device,
+                std::io::Error::last_os_error()
+            ));
+            return;
+        }
+
+        let mut descriptor = [0u8; 1024];
+        if ioctl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            Some(&query(StorageDeviceProperty)),
+            &mut descriptor,
+        ) {
+            let (model, serial, bus_type) = parse_storage_device_descriptor(&descriptor);
+            health.model = model;
+            health.serial_redacted = serial.and_then(|s| redact_serial(&s));
+            if bus_type == BUS_TYPE_NVME {
+                health.media_type = MediaType::Ssd;
+            }
+        } else {
+            health.warnings.push("无法读取设备型号与序列号".to_string());
+        }
+
+        if health.media_type == MediaType::Unknown {
+            health.media_type = seek_penalty(handle);
+        }
+
+        // SAFETY: 全零是该 C 结构体的合法值
+        let mut predict: STORAGE_PREDICT_FAILURE = unsafe { std::mem::zeroed() };
+        if ioctl(handle, IOCTL_STORAGE_PREDICT_FAILURE, None, &mut predict) {
+            health.predicted_failure = predict.PredictFailure != 0;
+            // ATA 设备的厂商数据即 SMART 属性页；NVMe 等设备为空
+            let attributes = parse_ata_smart_data(&predict.VendorSpecific, None);
+            if !attributes.is_empty() {
+                apply_attributes(health, attributes);
+            }
+        } else {
+            health
+                .warnings
+                .push("设备不支持失效预测或需要管理员权限".to_string());
+        }
+
+        // SAFETY: handle 由 CreateFileW 打开且尚未关闭
+        unsafe {
+            CloseHandle(handle);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", all(windows, feature = "windows-native"))))]
+mod platform {
+    use super::*;
+
+    pub(super) fn fill(health: &mut DiskHealth, _volume: &str) {
+        health
+            .warnings
+            .push("当前平台暂不支持读取磁盘健康信息".to_string());
+    }
+
+    pub(super) fn media_type(_volume: &str) -> MediaType {
+        MediaType::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn hex(chunks: &[&str]) -> Vec<u8> {
+        let text: String = chunks.concat();
+        let mut bytes: Vec<u8> = (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect();
+        bytes.resize(bytes.len().max(512), 0);
+        bytes
+    }
+
+    /// Samsung 860 EVO 的 SMART READ DATA / READ THRESHOLDS 页（末尾的零已省略）
+    const SSD_DATA: &[&str] = &[
+        "10000533006464000000000000000932005f5f725500000000000c32006363b3",
+        "040000000000b1130060602f000000000000c22200423422001400300000c73e",
+        "00646400000000000000f1320063634e5db6d908",
+    ];
+    const SSD_THRESHOLDS: &[&str] = &[
+        "1000050a000000000000000000000900000000000000000000000c0000000000",
+        "000000000000b10a00000000000000000000c20000000000000000000000c700",
+        "00000000000000000000f1",
+    ];
+    /// 重映射扇区已超过阈值的机械硬盘
+    const FAILING_HDD_DATA: &[&str] = &[
+        "1000012f00c8c8000000000000000533000505c80b00000000000932003d3d3b",
+        "700000000000c22200716125000000000000c53200c8c80c",
+    ];
+    const FAILING_HDD_THRESHOLDS: &[&str] = &[
+        "1000013300000000000000000000052400000000000000000000090000000000",
+        "000000000000c20000000000000000000000c5",
+    ];
+    /// SATA SSD 的 STORAGE_DEVICE_DESCRIPTOR
+    const SATA_DESCRIPTOR: &[&str] = &[
+        "010000005c0000000000000100000000240000003e000000470000000b000000",
+        "0000000053616d73756e6720535344203836302045564f203530304742005256",
+        "5430344236510053335a324e42304b31323334353641202020202000",
+    ];
+
+    #[test]
+    fn test_parse_ata_smart_pages() {
+        let data = hex(SSD_DATA);
+        let thresholds = hex(SSD_THRESHOLDS);
+        let attributes = parse_ata_smart_data(&data, Some(&thresholds));
+        assert_eq!(attributes.len(), 7);
+        let temp = attributes.iter().find(|a| a.id == 194).unwrap();
+        assert_eq!((temp.current, temp.worst), (66, 52));
+        assert_eq!(temp.raw, 0x0030_0014_0022);
+        let realloc = attributes.iter().find(|a| a.id == 5).unwrap();
+        assert_eq!(realloc.name, "Reallocated_Sector_Ct");
+        assert_eq!(realloc.threshold, 10);
+        assert_eq!(
+            attributes.iter().find(|a| a.id == 241).unwrap().raw,
+            38_012_345_678
+        );
+
+        let mut health = DiskHealth::empty("C:");
+        apply_attributes(&mut health, attributes);
+        // 温度只取最低字节（34），高位是最低/最高温度
+        assert_eq!(health.temperature_celsius, Some(34));
+        assert_eq!(health.power_on_hours, Some(21874));
+        assert_eq!(health.reallocated_sectors, Some(0));
+        assert!(!health.predicted_failure);
+    }
+
+    #[test]
+    fn test_failing_attribute_predicts_failure() {
+        let attributes =
+            parse_ata_smart_data(&hex(FAILING_HDD_DATA), Some(&hex(FAILING_HDD_THRESHOLDS)));
+        let mut health = DiskHealth::empty("D:");
+        apply_attributes(&mut health, attributes);
+        assert_eq!(health.reallocated_sectors, Some(3016));
+        assert_eq!(health.temperature_celsius, Some(37));
+        assert!(health.predicted_failure);
+
+        // Windows 的失效预测数据不含阈值，不据此判断
+        let without_thresholds = parse_ata_smart_data(&hex(FAILING_HDD_DATA), None);
+        assert!(!attributes_indicate_failure(&without_thresholds));
+        // 数据不完整时不越界
+        assert_eq!(parse_ata_smart_data(&[0x10, 0x00, 5], None), vec![]);
+    }
+
+    #[test]
+    fn test_parse_storage_device_descriptor() {
+        let (model, serial, bus) = parse_storage_device_descriptor(&hex(SATA_DESCRIPTOR));
+        assert_eq!(model.as_deref(), Some("Samsung SSD 860 EVO 500GB"));
+        assert_eq!(serial.as_deref(), Some("S3Z2NB0K123456A"));
+        assert_eq!(bus, 11);
+        assert_eq!(
+            redact_serial(&serial.unwrap()).as_deref(),
+            Some("***********456A")
+        );
+        assert_eq!(redact_serial("  ").as_deref(), None);
+        assert_eq!(redact_serial("AB1").as_deref(), Some("AB1"));
+        assert_eq!(parse_storage_device_descriptor(&[0u8; 8]), (None, None, 0));
+    }
+
+    const SMARTCTL_ATA: &str = r#"{
+      "json_format_version": [1, 0],
+      "smartctl": {"version": [7, 3], "exit_status": 0},
+      "device": {"name": "/dev/sda", "type": "sat", "protocol": "ATA"},
+      "model_name": "WDC WD40EFRX-68N32N0",
+      "serial_number": "WD-WCC7K1234567",
+      "rotation_rate": 5400,
+      "smart_status": {"passed": true},
+      "ata_smart_attributes": {"revision": 16, "table": [
+        {"id": 1, "name": "Raw_Read_Error_Rate", "value": 200, "worst": 200, "thresh": 51, "raw": {"value": 0, "string": "0"}},
+        {"id": 5, "name": "Reallocated_Sector_Ct", "value": 200, "worst": 200, "thresh": 140, "raw": {"value": 8, "string": "8"}},
+        {"id": 9, "name": "Power_On_Hours", "value": 45, "worst": 45, "thresh": 0, "raw": {"value": 40213, "string": "40213"}},
+        {"id": 194, "name": "Temperature_Celsius", "value": 116, "worst": 103, "thresh": 0, "raw": {"value": 36, "string": "36"}}
+      ]},
+      "power_on_time": {"hours": 40213},
+      "temperature": {"current": 36}
+    }"#;
+
+    const SMARTCTL_NVME: &str = r#"{
+      "smartctl": {"version": [7, 3], "exit_status": 0},
+      "device": {"name": "/dev/nvme0", "type": "nvme", "protocol": "NVMe"},
+      "model_name": "Samsung SSD 980 PRO 1TB",
+      "serial_number": "S5GXNF0R812345",
+      "smart_status": {"passed": true, "nvme": {"value": 4}},
+      "nvme_smart_health_information_log": {
+        "critical_warning": 4, "temperature": 41, "available_spare": 100,
+        "percentage_used": 3, "power_on_hours": 5120, "media_errors": 0
+      },
+      "temperature": {"current": 41},
+      "power_on_time": {"hours": 5120}
+    }"#;
+
+    const SMARTCTL_NO_PERMISSION: &str = r#"{
+      "smartctl": {"version": [7, 3], "exit_status": 2, "messages": [
+        {"string": "Smartctl open device: /dev/sda failed: Permission denied", "severity": "error"}
+      ]},
+      "device": {"name": "/dev/sda", "type": "sat"}
+    }"#;
+
+    #[test]
+    fn test_parse_smartctl_ata_json() {
+        let mut health = DiskHealth::empty("/");
+        apply_smartctl_json(&mut health, SMARTCTL_ATA).unwrap();
+        assert_eq!(health.model.as_deref(), Some("WDC WD40EFRX-68N32N0"));
+        assert_eq!(health.serial_redacted.as_deref(), Some("***********4567"));
+        assert_eq!(health.media_type, MediaType::Hdd);
+        assert_eq!(health.temperature_celsius, Some(36));
+        assert_eq!(health.power_on_hours, Some(40213));
+        assert_eq!(health.reallocated_sectors, Some(8));
+        assert_eq!(health.raw_attributes.len(), 4);
+        assert!(!health.predicted_failure);
+        assert!(health.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_smartctl_nvme_json_and_partial_results() {
+        let mut health = DiskHealth::empty("/home");
+        apply_smartctl_json(&mut health, SMARTCTL_NVME).unwrap();
+        assert_eq!(health.media_type, MediaType::Ssd);
+        assert_eq!(health.temperature_celsius, Some(41));
+        assert_eq!(health.power_on_hours, Some(5120));
+        assert_eq!(health.reallocated_sectors, None);
+        // critical_warning 第 2 位：可靠性下降
+        assert!(health.predicted_failure);
+
+        // sysfs 已取得的型号保留，smartctl 的错误记为警告
+        let mut health = DiskHealth::empty("/");
+        health.model = Some("ST4000DM004".to_string());
+        health.media_type = MediaType::Hdd;
+        apply_smartctl_json(&mut health, SMARTCTL_NO_PERMISSION).unwrap();
+        assert_eq!(health.model.as_deref(), Some("ST4000DM004"));
+        assert_eq!(health.media_type, MediaType::Hdd);
+        assert_eq!(health.temperature_celsius, None);
+        assert!(health.warnings[0].contains("Permission denied"));
+
+        assert!(apply_smartctl_json(&mut health, "not json").is_err());
+    }
+
+    #[test]
+    fn test_mount_source_for_longest_prefix() {
+        let mountinfo = "\
+22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+25 22 8:1 / /mnt/data rw,relatime shared:2 - ext4 /dev/sda1 rw
+26 22 8:17 / /mnt/my\\040disk rw,relatime shared:3 - xfs /dev/sdb1 rw
+27 22 0:25 / /proc rw - proc proc rw
+";
+        let source = |p: &str| mount_source_for(mountinfo, Path::new(p));
+        assert_eq!(source("/home/u").as_deref(), Some("/dev/nvme0n1p2"));
+        assert_eq!(source("/mnt/data/photos").as_deref(), Some("/dev/sda1"));
+        assert_eq!(source("/mnt/my disk/x").as_deref(), Some("/dev/sdb1"));
+        assert_eq!(source("/mnt/database").as_deref(), Some("/dev/nvme0n1p2"));
+        assert_eq!(source("/proc/1").as_deref(), Some("proc"));
+    }
+}
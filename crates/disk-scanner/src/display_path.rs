@@ -0,0 +1,156 @@
+//! 扫描结果中路径的统一表示。
+//!
+//! MFT 扫描得到 `C:\Users\me`，目录遍历（canonicalize 之后）得到 `\\?\C:\Users\me`；
+//! 两种模式的 `FileNode.path` 与 `TopFileEntry.path` 都统一为 [`DisplayPath`]，
+//! 需要做文件操作时再用 [`DisplayPath::to_os_path`] 转回系统可用的路径。
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// 超过该长度的 Windows 路径在转回系统路径时加上 `\\?\` 前缀，绕过 MAX_PATH 限制
+const WINDOWS_LONG_PATH_THRESHOLD: usize = 248;
+
+/// 规范化后的展示路径。Windows 上：去掉 `\\?\` 前缀、盘符大写、分隔符统一为 `\`、
+/// 除卷根（`C:\`）外不带末尾分隔符；其他平台只去掉末尾的 `/`（根目录 `/` 除外）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DisplayPath(String);
+
+impl DisplayPath {
+    pub fn new(path: &str) -> Self {
+        Self(normalize_for(path, cfg!(windows)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// 最后一级名称；卷根与 `/` 返回自身（如 `C:\`），供根节点命名
+    pub fn name(&self) -> &str {
+        let sep = if cfg!(windows) { '\\' } else { '/' };
+        match self.0.trim_end_matches(sep).rsplit(sep).next() {
+            Some(name) if !name.is_empty() && !is_volume_root(&self.0) => name,
+            _ => &self.0,
+        }
+    }
+
+    /// 转回可用于文件操作的系统路径；Windows 上过长的路径加上 `\\?\` 前缀
+    pub fn to_os_path(&self) -> PathBuf {
+        PathBuf::from(to_os_string_for(&self.0, cfg!(windows)))
+    }
+}
+
+impl fmt::Display for DisplayPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn is_volume_root(path: &str) -> bool {
+    let b = path.as_bytes();
+    path == "/" || (b.len() == 3 && b[0].is_ascii_alphabetic() && &b[1..] == b":\\")
+}
+
+pub(crate) fn normalize_for(path: &str, windows: bool) -> String {
+    if !windows {
+        let trimmed = path.trim_end_matches('/');
+        return if trimmed.is_empty() && path.starts_with('/') {
+            "/".to_string()
+        } else {
+            trimmed.to_string()
+        };
+    }
+    let path = path.replace('/', "\\");
+    let mut path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path
+    };
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        let drive = path[..1].to_ascii_uppercase();
+        path.replace_range(..1, &drive);
+        let trimmed_len = path.trim_end_matches('\\').len();
+        path.truncate(trimmed_len);
+        if path.len() == 2 {
+            path.push('\\');
+        }
+        path
+    } else {
+        path.trim_end_matches('\\').to_string()
+    }
+}
+
+fn to_os_string_for(display: &str, windows: bool) -> String {
+    if !windows || display.len() < WINDOWS_LONG_PATH_THRESHOLD {
+        return display.to_string();
+    }
+    match display.strip_prefix(r"\\") {
+        Some(unc) => format!(r"\\?\UNC\{}", unc),
+        None => format!(r"\\?\{}", display),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_and_mft_paths_are_byte_identical() {
+        // (目录遍历 canonicalize 后的路径, MFT 路径)
+        let pairs = [
+            (r"\\?\C:\Users\me", r"C:\Users\me"),
+            (r"\\?\c:\Users\me\", r"C:\Users\me"),
+            (r"\\?\C:\", r"C:\"),
+            (r"\\?\D:", r"D:\"),
+            (r"C:/Users/me/Downloads/", r"C:\Users\me\Downloads"),
+            (r"\\?\UNC\nas\share\a.iso", r"\\nas\share\a.iso"),
+        ];
+        for (walk, mft) in pairs {
+            assert_eq!(normalize_for(walk, true), normalize_for(mft, true));
+            assert_eq!(
+                normalize_for(walk, true).as_bytes(),
+                normalize_for(mft, true).as_bytes()
+            );
+        }
+        assert_eq!(normalize_for(r"\\?\c:\Users\me\", true), r"C:\Users\me");
+        assert_eq!(normalize_for(r"\\?\C:\", true), r"C:\");
+        // 目录名大小写保留，只有盘符统一大写
+        assert_eq!(normalize_for(r"c:\Users\ME", true), r"C:\Users\ME");
+    }
+
+    #[test]
+    fn test_unix_normalization() {
+        assert_eq!(normalize_for("/home/me/", false), "/home/me");
+        assert_eq!(normalize_for("/", false), "/");
+        assert_eq!(normalize_for(r"/tmp/a\b", false), r"/tmp/a\b");
+    }
+
+    #[test]
+    fn test_os_path_round_trip() {
+        assert_eq!(to_os_string_for(r"C:\Users\me", true), r"C:\Users\me");
+        let long = format!(r"C:\{}", "a".repeat(300));
+        let os = to_os_string_for(&long, true);
+        assert_eq!(os, format!(r"\\?\{}", long));
+        assert_eq!(normalize_for(&os, true), long);
+        let unc = format!(r"\\nas\share\{}", "b".repeat(300));
+        assert!(to_os_string_for(&unc, true).starts_with(r"\\?\UNC\nas\share\"));
+        assert_eq!(normalize_for(&to_os_string_for(&unc, true), true), unc);
+        assert_eq!(to_os_string_for(&"/x".repeat(200), false), "/x".repeat(200));
+    }
+
+    #[test]
+    fn test_name() {
+        let path = DisplayPath::new(&std::env::temp_dir().join("abc").to_string_lossy());
+        assert_eq!(path.name(), "abc");
+        #[cfg(windows)]
+        assert_eq!(DisplayPath::new(r"\\?\C:\").name(), r"C:\");
+        #[cfg(not(windows))]
+        assert_eq!(DisplayPath::new("/").name(), "/");
+    }
+}
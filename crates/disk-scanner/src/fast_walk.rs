@@ -0,0 +1,997 @@
+//! 目录遍历的快速后端：Linux 上用 getdents64 + statx，macOS 上用 getattrlistbulk，
+//! Windows 上用 FindFirstFileExW（FIND_FIRST_EX_LARGE_FETCH），一次系统调用取回一批目录项
+//! （macOS / Windows 连同类型、大小、时间与属性），省去 `read_dir` + `metadata` 逐项的额外查询。
+//!
+//! 生成的树与 [`crate::scanner`] 的通用遍历逐节点一致：排序、截断、shallow 目录、占位节点、计数与进度回调
+//! 都按同样的规则处理；符号链接（Windows 上为重解析点）与无法 stat 的条目直接交给通用实现的 [`build_child`]。
+//! 系统不支持对应调用时 [`list_dir`] 回退到 `read_dir`；其余错误由调用方整体回退到通用遍历。
+
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{FileAttributes, FileNode, SizeSource};
+
+use crate::ignore_rules::IgnoreRules;
+use crate::parallel::map_collect;
+use crate::scanner::{
+    accessed_secs, build_child, created_secs, file_attributes, is_collapsed_dir_name,
+    is_corruption_io_error, is_skippable_dir_error, latest_modified_of, mount_point_node,
+    placeholder_node, walk_error, ProgressCb, WalkContext,
+};
+use crate::throttle::on_dir_listed;
+
+const MAX_DEPTH: usize = 10;
+const MAX_CHILDREN_PER_DIR: usize = 500;
+
+/// 目录项类型（不跟随符号链接）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    Dir,
+    File,
+    /// 符号链接或取不到属性的条目，交给通用实现处理
+    Other,
+}
+
+/// 一个目录项及其属性
+#[derive(Debug, Clone)]
+struct EntryStat {
+    name: OsString,
+    kind: EntryKind,
+    size: u64,
+    modified: Option<u64>,
+    created: Option<u64>,
+    accessed: Option<u64>,
+    attributes: Option<FileAttributes>,
+}
+
+impl EntryStat {
+    fn from_metadata(name: OsString, metadata: &std::fs::Metadata) -> Self {
+        let attributes = file_attributes(&name.to_string_lossy(), metadata);
+        let file_type = metadata.file_type();
+        let kind = if file_type.is_symlink() {
+            EntryKind::Other
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+        Self {
+            name,
+            kind,
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            created: created_secs(metadata),
+            accessed: accessed_secs(metadata),
+            attributes,
+        }
+    }
+
+    fn other(name: OsString) -> Self {
+        Self {
+            name,
+            kind: EntryKind::Other,
+            size: 0,
+            modified: None,
+            created: None,
+            accessed: None,
+            attributes: None,
+        }
+    }
+}
+
+/// 列出目录项（不含 `.` 与 `..`）；系统调用不可用时回退到 `read_dir`
+fn list_dir(dir: &Path) -> io::Result<Vec<EntryStat>> {
+    match platform::list_dir(dir) {
+        Err(e)
+            if e.raw_os_error()
+                .is_some_and(|code| platform::UNSUPPORTED_ERRORS.contains(&code)) =>
+        {
+            list_dir_std(dir)
+        }
+        other => other,
+    }
+}
+
+fn list_dir_std(dir: &Path) -> io::Result<Vec<EntryStat>> {
+    Ok(std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| match e.metadata() {
+            Ok(m) => EntryStat::from_metadata(e.file_name(), &m),
+            Err(_) => EntryStat::other(e.file_name()),
+        })
+        .collect())
+}
+
+/// 以 O_DIRECTORY 打开的目录句柄，Drop 时关闭
+#[cfg(unix)]
+struct DirFd(libc::c_int);
+
+#[cfg(unix)]
+impl DirFd {
+    #[allow(unsafe_code)]
+    fn open(dir: &Path) -> io::Result<Self> {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: c_path 以 NUL 结尾，在调用期间有效
+        let fd = unsafe {
+            libc::open(
+                c_path.as_ptr(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(fd))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for DirFd {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        // SAFETY: fd 由 open 返回且只在此处关闭
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use crate::scanner::{name_attributes, nonzero_unix_secs};
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    pub(super) const UNSUPPORTED_ERRORS: &[i32] = &[libc::ENOSYS, libc::ENOTSUP, libc::EINVAL];
+    const BUF_SIZE: usize = 32 * 1024;
+    /// linux_dirent64：d_ino(8) d_off(8) d_reclen(2) d_type(1) d_name[]
+    const RECLEN_OFFSET: usize = 16;
+    const NAME_OFFSET: usize = 19;
+
+    pub(super) fn list_dir(dir: &Path) -> io::Result<Vec<EntryStat>> {
+        let fd = DirFd::open(dir)?;
+        let mut buf = vec![0u8; BUF_SIZE];
+        let mut out = Vec::new();
+        loop {
+            let n = getdents64(&fd, &mut buf);
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if n == 0 {
+                break;
+            }
+            let mut pos = 0usize;
+            while pos < n as usize {
+                let reclen =
+                    u16::from_ne_bytes([buf[pos + RECLEN_OFFSET], buf[pos + RECLEN_OFFSET + 1]])
+                        as usize;
+                let Some(name) = buf
+                    .get(pos + NAME_OFFSET..pos + reclen)
+                    .and_then(|b| CStr::from_bytes_until_nul(b).ok())
+                else {
+                    break;
+                };
+                pos += reclen;
+                let bytes = name.to_bytes();
+                if bytes == b"." || bytes == b".." {
+                    continue;
+                }
+                out.push(stat_entry(&fd, name));
+            }
+        }
+        Ok(out)
+    }
+
+    /// 把目录项读入 `buf`，返回读到的字节数，0 表示读完，负数表示出错
+    #[allow(unsafe_code)]
+    fn getdents64(fd: &DirFd, buf: &mut [u8]) -> libc::c_long {
+        // SAFETY: buf 在调用期间有效，长度与传入一致
+        unsafe {
+            libc::syscall(
+                libc::SYS_getdents64,
+                fd.0,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        }
+    }
+
+    /// 不跟随符号链接的 statx；失败时为 None
+    #[allow(unsafe_code)]
+    fn statx(fd: &DirFd, name: &CStr) -> Option<libc::statx> {
+        // SAFETY: statx 结构体全零是合法值
+        let mut stx: libc::statx = unsafe { std::mem::zeroed() };
+        // SAFETY: fd 为打开的目录，name 以 NUL 结尾，stx 可写
+        let rc = unsafe {
+            libc::statx(
+                fd.0,
+                name.as_ptr(),
+                libc::AT_SYMLINK_NOFOLLOW,
+                libc::STATX_BASIC_STATS | libc::STATX_BTIME,
+                &mut stx,
+            )
+        };
+        (rc == 0).then_some(stx)
+    }
+
+    /// 不跟随符号链接的 fstatat；失败时为 None
+    #[allow(unsafe_code)]
+    fn fstatat(fd: &DirFd, name: &CStr) -> Option<libc::stat> {
+        // SAFETY: stat 结构体全零是合法值
+        let mut st: libc::stat = unsafe { std::mem::zeroed() };
+        // SAFETY: fd 为打开的目录，name 以 NUL 结尾，st 可写
+        let rc = unsafe { libc::fstatat(fd.0, name.as_ptr(), &mut st, libc::AT_SYMLINK_NOFOLLOW) };
+        (rc == 0).then_some(st)
+    }
+
+    fn stat_entry(fd: &DirFd, name: &CStr) -> EntryStat {
+        let os_name = OsStr::from_bytes(name.to_bytes()).to_os_string();
+        statx_entry(fd, name, &os_name).unwrap_or_else(|| fstatat_entry(fd, name, os_name))
+    }
+
+    fn entry_kind(mode: u32) -> EntryKind {
+        match mode & libc::S_IFMT {
+            libc::S_IFDIR => EntryKind::Dir,
+            libc::S_IFLNK => EntryKind::Other,
+            _ => EntryKind::File,
+        }
+    }
+
+    /// statx 能取到创建时间（文件系统支持时）；失败（如 4.11 之前的内核）时返回 None，由 fstatat 重试
+    fn statx_entry(fd: &DirFd, name: &CStr, os_name: &OsStr) -> Option<EntryStat> {
+        let stx = statx(fd, name)?;
+        let created = if stx.stx_mask & libc::STATX_BTIME != 0 {
+            nonzero_unix_secs(stx.stx_btime.tv_sec)
+        } else {
+            None
+        };
+        Some(EntryStat {
+            attributes: name_attributes(&os_name.to_string_lossy()),
+            name: os_name.to_os_string(),
+            kind: entry_kind(u32::from(stx.stx_mode)),
+            size: stx.stx_size,
+            modified: (stx.stx_mtime.tv_sec >= 0).then_some(stx.stx_mtime.tv_sec as u64),
+            created,
+            accessed: nonzero_unix_secs(stx.stx_atime.tv_sec),
+        })
+    }
+
+    fn fstatat_entry(fd: &DirFd, name: &CStr, os_name: OsString) -> EntryStat {
+        let Some(st) = fstatat(fd, name) else {
+            return EntryStat::other(os_name);
+        };
+        EntryStat {
+            attributes: name_attributes(&os_name.to_string_lossy()),
+            name: os_name,
+            kind: entry_kind(st.st_mode),
+            size: st.st_size as u64,
+            modified: (st.st_mtime >= 0).then_some(st.st_mtime as u64),
+            created: None,
+            accessed: nonzero_unix_secs(st.st_atime),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+    use crate::scanner::{name_attributes, nonzero_unix_secs};
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    pub(super) const UNSUPPORTED_ERRORS: &[i32] = &[libc::ENOSYS, libc::ENOTSUP, libc::EINVAL];
+    const BUF_SIZE: usize = 64 * 1024;
+    const ATTR_BIT_MAP_COUNT: u16 = 5;
+    const ATTR_CMN_NAME: u32 = 0x0000_0001;
+    const ATTR_CMN_OBJTYPE: u32 = 0x0000_0008;
+    const ATTR_CMN_CRTIME: u32 = 0x0000_0200;
+    const ATTR_CMN_MODTIME: u32 = 0x0000_0400;
+    const ATTR_CMN_ACCTIME: u32 = 0x0000_1000;
+    const ATTR_CMN_RETURNED_ATTRS: u32 = 0x8000_0000;
+    const ATTR_FILE_DATALENGTH: u32 = 0x0000_0200;
+    const VREG: u32 = 1;
+    const VDIR: u32 = 2;
+    const VLNK: u32 = 5;
+
+    /// <sys/attr.h> 的 struct attrlist
+    #[repr(C)]
+    struct AttrList {
+        bitmapcount: u16,
+        reserved: u16,
+        commonattr: u32,
+        volattr: u32,
+        dirattr: u32,
+        fileattr: u32,
+        forkattr: u32,
+    }
+
+    extern "C" {
+        fn getattrlistbulk(
+            dirfd: libc::c_int,
+            attr_list: *mut libc::c_void,
+            attr_buf: *mut libc::c_void,
+            attr_buf_size: libc::size_t,
+            options: u64,
+        ) -> libc::c_int;
+    }
+
+    pub(super) fn list_dir(dir: &Path) -> io::Result<Vec<EntryStat>> {
+        let fd = DirFd::open(dir)?;
+        let mut attrs = AttrList {
+            bitmapcount: ATTR_BIT_MAP_COUNT,
+            reserved: 0,
+            commonattr: ATTR_CMN_RETURNED_ATTRS
+                | ATTR_CMN_NAME
+                | ATTR_CMN_OBJTYPE
+                | ATTR_CMN_CRTIME
+                | ATTR_CMN_MODTIME
+                | ATTR_CMN_ACCTIME,
+            volattr: 0,
+            dirattr: 0,
+            fileattr: ATTR_FILE_DATALENGTH,
+            forkattr: 0,
+        };
+        let mut buf = vec![0u8; BUF_SIZE];
+        let mut out = Vec::new();
+        loop {
+            let count = read_bulk(&fd, &mut attrs, &mut buf);
+            if count < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if count == 0 {
+                break;
+            }
+            let mut pos = 0usize;
+            for _ in 0..count {
+                let len = read_u32(&buf, pos) as usize;
+                let Some(entry) = buf.get(pos..pos + len).and_then(parse_entry) else {
+                    break;
+                };
+                out.push(entry);
+                pos += len;
+            }
+        }
+        Ok(out)
+    }
+
+    /// 把一批目录项的属性读入 `buf`，返回条目数，0 表示读完，负数表示出错
+    #[allow(unsafe_code)]
+    fn read_bulk(fd: &DirFd, attrs: &mut AttrList, buf: &mut [u8]) -> libc::c_int {
+        // SAFETY: attrs 与 buf 在调用期间有效，缓冲区长度与传入一致
+        unsafe {
+            getattrlistbulk(
+                fd.0,
+                attrs as *mut AttrList as *mut libc::c_void,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        }
+    }
+
+    /// 条目布局：u32 长度、attribute_set_t（5 个 u32），随后按位序排列实际返回的属性
+    fn parse_entry(entry: &[u8]) -> Option<EntryStat> {
+        let returned_common = read_u32(entry, 4);
+        let returned_file = read_u32(entry, 16);
+        let mut pos = 24;
+
+        if returned_common & ATTR_CMN_NAME == 0 {
+            return None;
+        }
+        // attrreference_t：相对自身的偏移 + 含结尾 NUL 的长度
+        let start = pos.checked_add_signed(read_i32(entry, pos) as isize)?;
+        let len = read_u32(entry, pos + 4) as usize;
+        let name =
+            OsStr::from_bytes(entry.get(start..start + len.saturating_sub(1))?).to_os_string();
+        pos += 8;
+
+        if returned_common & ATTR_CMN_OBJTYPE == 0 || returned_common & ATTR_CMN_MODTIME == 0 {
+            return Some(EntryStat::other(name));
+        }
+        let obj_type = read_u32(entry, pos);
+        pos += 4;
+        // timespec 各占 16 字节，未返回的时间不占位置
+        let mut timespec = |attr: u32| {
+            (returned_common & attr != 0).then(|| {
+                let secs = read_i64(entry, pos);
+                pos += 16;
+                secs
+            })
+        };
+        let created = timespec(ATTR_CMN_CRTIME).and_then(nonzero_unix_secs);
+        let secs = timespec(ATTR_CMN_MODTIME).unwrap_or(-1);
+        let accessed = timespec(ATTR_CMN_ACCTIME).and_then(nonzero_unix_secs);
+        let size = if returned_file & ATTR_FILE_DATALENGTH != 0 {
+            read_i64(entry, pos).max(0) as u64
+        } else {
+            0
+        };
+        let kind = match obj_type {
+            VDIR => EntryKind::Dir,
+            VLNK => EntryKind::Other,
+            VREG => EntryKind::File,
+            _ => EntryKind::File,
+        };
+        Some(EntryStat {
+            attributes: name_attributes(&name.to_string_lossy()),
+            name,
+            kind,
+            size: if kind == EntryKind::File { size } else { 0 },
+            modified: (secs >= 0).then_some(secs as u64),
+            created,
+            accessed,
+        })
+    }
+
+    fn read_u32(b: &[u8], at: usize) -> u32 {
+        b.get(at..at + 4)
+            .map(|s| u32::from_ne_bytes([s[0], s[1], s[2], s[3]]))
+            .unwrap_or(0)
+    }
+
+    fn read_i32(b: &[u8], at: usize) -> i32 {
+        read_u32(b, at) as i32
+    }
+
+    fn read_i64(b: &[u8], at: usize) -> i64 {
+        let lo = read_u32(b, at) as u64;
+        let hi = read_u32(b, at + 4) as u64;
+        if cfg!(target_endian = "little") {
+            (lo | (hi << 32)) as i64
+        } else {
+            ((lo << 32) | hi) as i64
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use windows_sys::Win32::Foundation::{
+        GetLastError, ERROR_FILE_NOT_FOUND, ERROR_INVALID_PARAMETER, ERROR_NOT_SUPPORTED,
+        ERROR_NO_MORE_FILES, FILETIME, HANDLE, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindExInfoBasic, FindExSearchNameMatch, FindFirstFileExW, FindNextFileW,
+        FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_REPARSE_POINT, FIND_FIRST_EX_LARGE_FETCH,
+        WIN32_FIND_DATAW,
+    };
+
+    /// 不支持 FIND_FIRST_EX_LARGE_FETCH / FindExInfoBasic 的旧系统返回的错误
+    pub(super) const UNSUPPORTED_ERRORS: &[i32] =
+        &[ERROR_INVALID_PARAMETER as i32, ERROR_NOT_SUPPORTED as i32];
+    /// 1601-01-01 到 1970-01-01 之间的 100ns 间隔数
+    const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+    /// FindFirstFileExW 返回的搜索句柄，Drop 时关闭
+    struct FindHandle(HANDLE);
+
+    impl Drop for FindHandle {
+        #[allow(unsafe_code)]
+        fn drop(&mut self) {
+            // SAFETY: 句柄由 FindFirstFileExW 返回且只在此处关闭
+            unsafe {
+                FindClose(self.0);
+            }
+        }
+    }
+
+    pub(super) fn list_dir(dir: &Path) -> io::Result<Vec<EntryStat>> {
+        let pattern: Vec<u16> = dir
+            .join("*")
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        let (handle, mut data) = match find_first(&pattern) {
+            Ok(found) => found,
+            // 卷根这类没有 `.` / `..` 的空目录
+            Err(code) if code == ERROR_FILE_NOT_FOUND => return Ok(Vec::new()),
+            Err(code) => return Err(io::Error::from_raw_os_error(code as i32)),
+        };
+        let mut out = Vec::new();
+        loop {
+            if let Some(entry) = parse_find_data(&data) {
+                out.push(entry);
+            }
+            match find_next(&handle, &mut data) {
+                Ok(()) => {}
+                Err(code) if code == ERROR_NO_MORE_FILES => break,
+                Err(code) => return Err(io::Error::from_raw_os_error(code as i32)),
+            }
+        }
+        Ok(out)
+    }
+
+    /// 开始查找 `pattern`（以 NUL 结尾），返回搜索句柄与第一个目录项；失败时返回错误码
+    #[allow(unsafe_code)]
+    fn find_first(pattern: &[u16]) -> Result<(FindHandle, WIN32_FIND_DATAW), u32> {
+        // SAFETY: 全零是该 C 结构体的合法值
+        let mut data: WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
+        // SAFETY: pattern 以 NUL 结尾；data 可写；不使用搜索过滤参数
+        let handle = unsafe {
+            FindFirstFileExW(
+                pattern.as_ptr(),
+                FindExInfoBasic,
+                &mut data as *mut WIN32_FIND_DATAW as *mut _,
+                FindExSearchNameMatch,
+                std::ptr::null(),
+                FIND_FIRST_EX_LARGE_FETCH,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            // SAFETY: 紧跟在失败的调用之后读取错误码
+            return Err(unsafe { GetLastError() });
+        }
+        Ok((FindHandle(handle), data))
+    }
+
+    /// 读取下一个目录项到 `data`；失败时返回错误码（读完为 ERROR_NO_MORE_FILES）
+    #[allow(unsafe_code)]
+    fn find_next(handle: &FindHandle, data: &mut WIN32_FIND_DATAW) -> Result<(), u32> {
+        // SAFETY: 句柄有效；data 可写
+        if unsafe { FindNextFileW(handle.0, data) } == 0 {
+            // SAFETY: 紧跟在失败的调用之后读取错误码
+            return Err(unsafe { GetLastError() });
+        }
+        Ok(())
+    }
+
+    /// FILETIME 转为 Unix 秒；早于 1970 年时为 None
+    fn unix_secs(time: &FILETIME) -> Option<u64> {
+        let ticks = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+        ticks
+            .checked_sub(FILETIME_UNIX_EPOCH)
+            .map(|t| t / 10_000_000)
+    }
+
+    /// 目录项的大小、属性与时间都直接取自查找数据，不再逐项查询元数据
+    fn parse_find_data(data: &WIN32_FIND_DATAW) -> Option<EntryStat> {
+        let len = data
+            .cFileName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(data.cFileName.len());
+        let wide = &data.cFileName[..len];
+        if wide == [b'.' as u16] || wide == [b'.' as u16, b'.' as u16] {
+            return None;
+        }
+        let name = OsString::from_wide(wide);
+        let bits = data.dwFileAttributes;
+        // 重解析点（符号链接、目录联接等）按目标判断，交给通用实现
+        if bits & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            return Some(EntryStat::other(name));
+        }
+        let kind = if bits & FILE_ATTRIBUTE_DIRECTORY != 0 {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+        Some(EntryStat {
+            name,
+            kind,
+            size: (u64::from(data.nFileSizeHigh) << 32) | u64::from(data.nFileSizeLow),
+            modified: unix_secs(&data.ftLastWriteTime),
+            created: unix_secs(&data.ftCreationTime).filter(|&t| t > 0),
+            accessed: unix_secs(&data.ftLastAccessTime).filter(|&t| t > 0),
+            attributes: FileAttributes::from_windows_bits(bits).non_empty(),
+        })
+    }
+}
+
+/// 与通用遍历的 `build_tree` 结果一致的快速实现；遇到无法处理的错误时返回 Err，由调用方回退
+pub(crate) fn build_tree(
+    path: &Path,
+    name: &str,
+    depth: usize,
+    ctx: &WalkContext,
+    ignore: Option<&IgnoreRules>,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    let metadata = std::fs::metadata(path).map_err(|e| walk_error(path, e))?;
+    let stat = EntryStat::from_metadata(OsString::from(name), &metadata);
+    build_node(path, name, &stat, depth, ctx, ignore)
+}
+
+fn build_node(
+    path: &Path,
+    name: &str,
+    stat: &EntryStat,
+    depth: usize,
+    ctx: &WalkContext,
+    ignore: Option<&IgnoreRules>,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    let is_dir = stat.kind == EntryKind::Dir;
+    let mut size = if is_dir { 0u64 } else { stat.size };
+    let mut file_count = if is_dir { 0u64 } else { 1u64 };
+    let mut children = Vec::new();
+    // 超过深度上限的目录不展开也不统计大小
+    let mut partial = is_dir && depth >= MAX_DEPTH;
+    let mut partial_size = partial;
+
+    if is_dir && depth < MAX_DEPTH {
+        let entries = match list_dir(path) {
+            Ok(entries) => entries,
+            // 与通用遍历一致：目录损坏时保留该目录节点，不展开子项
+            Err(e) if is_corruption_io_error(&e) => {
+                return Ok((
+                    FileNode {
+                        path: path.display().to_string(),
+                        name: name.to_string(),
+                        size: 0,
+                        is_dir: true,
+                        modified: stat.modified,
+                        created: stat.created,
+                        accessed: stat.accessed,
+                        attributes: stat.attributes,
+                        size_source: SizeSource::Unknown,
+                        ..Default::default()
+                    },
+                    0u64,
+                ));
+            }
+            Err(e) => return Err(walk_error(path, e)),
+        };
+        let listed = entries.len();
+        on_dir_listed(listed);
+        // 与通用遍历相同的顺序：目录（符号链接按目标判断）在前，其余按名称
+        let mut entries: Vec<(EntryStat, bool)> = entries
+            .into_iter()
+            .map(|e| {
+                let entry_is_dir = match e.kind {
+                    EntryKind::Dir => true,
+                    EntryKind::File => false,
+                    EntryKind::Other => path.join(&e.name).is_dir(),
+                };
+                (e, entry_is_dir)
+            })
+            .collect();
+        // 与通用遍历一致：先去掉被忽略的子项，再排序截断
+        let rules = ignore.map(|r| r.enter(path));
+        if let Some(rules) = &rules {
+            entries = rules.retain(entries, |(e, entry_is_dir)| {
+                (path.join(&e.name), *entry_is_dir)
+            });
+        }
+        entries.sort_by(|(a, a_is_dir), (b, b_is_dir)| {
+            b_is_dir.cmp(a_is_dir).then_with(|| a.name.cmp(&b.name))
+        });
+        entries.truncate(MAX_CHILDREN_PER_DIR);
+        partial = entries.len() < listed;
+        partial_size = partial;
+
+        let results = map_collect(&entries, |(entry, _)| {
+            build_entry(path, entry, depth + 1, ctx, rules.as_ref())
+        });
+
+        for r in results {
+            let (node, cnt) = r?;
+            size += node.size;
+            file_count += cnt;
+            partial_size |= node.partial_size;
+            children.push(node);
+        }
+
+        ctx.counter.fetch_add(file_count, Ordering::Relaxed);
+        if let Some(cb) = ctx.progress {
+            cb(
+                ctx.counter.load(Ordering::Relaxed),
+                path.display().to_string().as_str(),
+            );
+        }
+    }
+
+    let latest_child_modified = latest_modified_of(&children);
+    Ok((
+        FileNode {
+            path: path.display().to_string(),
+            name: name.to_string(),
+            size,
+            is_dir,
+            modified: stat.modified,
+            created: stat.created,
+            accessed: stat.accessed,
+            attributes: stat.attributes,
+            latest_child_modified,
+            partial_children: partial,
+            partial_size,
+            children,
+            ..Default::default()
+        },
+        file_count,
+    ))
+}
+
+/// 构建目录下的单个子项（`depth` 为子项自身的深度）
+fn build_entry(
+    parent: &Path,
+    entry: &EntryStat,
+    depth: usize,
+    ctx: &WalkContext,
+    ignore: Option<&IgnoreRules>,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    let child_path = parent.join(&entry.name);
+    let child_name = entry.name.to_string_lossy().to_string();
+    let result = match entry.kind {
+        EntryKind::Dir if ctx.crosses_filesystem(&child_path) => {
+            return Ok(mount_point_node(&child_path, &child_name))
+        }
+        EntryKind::Other => return build_child(&child_path, &child_name, depth, ctx, ignore),
+        EntryKind::Dir if is_collapsed_dir_name(&child_name, ctx.shallow_dirs) => {
+            dir_size_and_latest(&child_path, ctx.counter, ctx.progress).map(|(size, latest)| {
+                (
+                    FileNode {
+                        path: child_path.display().to_string(),
+                        name: child_name.clone(),
+                        size,
+                        is_dir: true,
+                        modified: entry.modified,
+                        created: entry.created,
+                        accessed: entry.accessed,
+                        attributes: entry.attributes,
+                        latest_child_modified: latest,
+                        partial_children: true,
+                        ..Default::default()
+                    },
+                    1u64,
+                )
+            })
+        }
+        _ => build_node(&child_path, &child_name, entry, depth, ctx, ignore),
+    };
+    match result {
+        Err(DiskAnalyzerError::PermissionDenied(_)) => Ok(placeholder_node(
+            &child_path,
+            format!("{} [无权限]", child_name),
+            entry.kind == EntryKind::Dir,
+        )),
+        Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok(placeholder_node(
+            &child_path,
+            format!("{} [损坏]", child_name),
+            entry.kind == EntryKind::Dir,
+        )),
+        other => other,
+    }
+}
+
+/// 仅统计目录总大小与子树中最近的修改时间（用于 shallow 目录），与通用实现的 `dir_size_and_latest` 一致
+fn dir_size_and_latest(
+    path: &Path,
+    counter: &AtomicU64,
+    progress: Option<&ProgressCb>,
+) -> Result<(u64, Option<u64>), DiskAnalyzerError> {
+    let entries = match list_dir(path) {
+        Ok(e) => e,
+        Err(e) if is_skippable_dir_error(&e) => return Ok((0, None)),
+        Err(e) => return Err(DiskAnalyzerError::Io(e)),
+    };
+    on_dir_listed(entries.len());
+    let mut total: u64 = 0;
+    let mut latest: Option<u64> = None;
+    for entry in &entries {
+        let child = path.join(&entry.name);
+        latest = latest.max(entry.modified);
+        let (size, child_latest) = match entry.kind {
+            EntryKind::Dir => dir_size_and_latest(&child, counter, progress).unwrap_or_default(),
+            EntryKind::File => (entry.size, None),
+            EntryKind::Other if child.is_dir() => {
+                crate::scanner::dir_size_and_latest(&child, counter, progress).unwrap_or_default()
+            }
+            EntryKind::Other => (
+                std::fs::symlink_metadata(&child)
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+                None,
+            ),
+        };
+        total = total.saturating_add(size);
+        latest = latest.max(child_latest);
+    }
+    counter.fetch_add(1, Ordering::Relaxed);
+    if let Some(cb) = progress {
+        cb(
+            counter.load(Ordering::Relaxed),
+            path.display().to_string().as_str(),
+        );
+    }
+    Ok((total, latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Instant;
+
+    fn walk_ctx(counter: &AtomicU64, shallow_dirs: bool) -> WalkContext<'_> {
+        WalkContext {
+            counter,
+            progress: None,
+            shallow_dirs,
+            fs_boundary: None,
+        }
+    }
+
+    /// 两种遍历的结果（树 + 文件数）
+    fn walk_both(root: &Path, shallow_dirs: bool) -> (serde_json::Value, serde_json::Value) {
+        let name = root.file_name().unwrap().to_string_lossy().to_string();
+        let counter = AtomicU64::new(0);
+        let (generic, generic_count) =
+            crate::scanner::build_tree(root, &name, 0, &walk_ctx(&counter, shallow_dirs), None)
+                .unwrap();
+        let generic_counter = counter.load(Ordering::Relaxed);
+        let counter = AtomicU64::new(0);
+        let (fast, fast_count) =
+            build_tree(root, &name, 0, &walk_ctx(&counter, shallow_dirs), None).unwrap();
+        let fast_counter = counter.load(Ordering::Relaxed);
+        let mut generic = serde_json::json!([generic, generic_count, generic_counter]);
+        let mut fast = serde_json::json!([fast, fast_count, fast_counter]);
+        strip_dir_access_times(&mut generic);
+        strip_dir_access_times(&mut fast);
+        (generic, fast)
+    }
+
+    /// 第一次遍历读取目录时（relatime 下）会更新目录的访问时间，两次遍历之间不可比
+    fn strip_dir_access_times(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(node) => {
+                if node.get("is_dir") == Some(&serde_json::Value::Bool(true)) {
+                    node.remove("accessed");
+                }
+                node.values_mut().for_each(strip_dir_access_times);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip_dir_access_times),
+            _ => {}
+        }
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/nested/deeper")).unwrap();
+        fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+        fs::write(root.join("src/nested/deeper/data.bin"), vec![7u8; 4096]).unwrap();
+        fs::write(root.join(".env"), b"KEY=1").unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg/lib")).unwrap();
+        fs::write(root.join("node_modules/pkg/lib/index.js"), vec![1u8; 300]).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+        fs::create_dir(root.join("many")).unwrap();
+        for i in 0..600 {
+            fs::write(root.join("many").join(format!("f{:03}", i)), vec![0u8; i]).unwrap();
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+            symlink(root.join("src"), root.join("link_to_dir")).unwrap();
+            symlink(root.join(".env"), root.join("link_to_file")).unwrap();
+            symlink(root.join("missing"), root.join("dangling")).unwrap();
+            symlink(root.join("node_modules"), root.join("src/vendor")).unwrap();
+        }
+        // Windows 的属性取自查找数据，需与 metadata 读到的一致
+        #[cfg(windows)]
+        #[allow(unsafe_code)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            use windows_sys::Win32::Storage::FileSystem::{
+                SetFileAttributesW, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM,
+            };
+            for (path, attrs) in [
+                (root.join(".env"), FILE_ATTRIBUTE_HIDDEN),
+                (
+                    root.join("src"),
+                    FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM,
+                ),
+            ] {
+                let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+                // SAFETY: 路径以 NUL 结尾
+                assert_ne!(unsafe { SetFileAttributesW(wide.as_ptr(), attrs) }, 0);
+            }
+        }
+        dir
+    }
+
+    #[test]
+    fn test_fast_walk_matches_generic_walk() {
+        let dir = fixture();
+        for shallow_dirs in [true, false] {
+            let (generic, fast) = walk_both(dir.path(), shallow_dirs);
+            assert_eq!(generic, fast, "shallow_dirs = {}", shallow_dirs);
+        }
+    }
+
+    #[test]
+    fn test_list_dir_matches_read_dir() {
+        let dir = fixture();
+        let mut fast = list_dir(dir.path()).unwrap();
+        let mut read_dir = list_dir_std(dir.path()).unwrap();
+        fast.sort_by(|a, b| a.name.cmp(&b.name));
+        read_dir.sort_by(|a, b| a.name.cmp(&b.name));
+        let key = |e: &EntryStat| {
+            (
+                e.name.clone(),
+                e.kind,
+                e.size,
+                (e.modified, e.created, e.accessed),
+                e.attributes,
+            )
+        };
+        assert_eq!(
+            fast.iter().map(key).collect::<Vec<_>>(),
+            read_dir.iter().map(key).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_missing_dir_maps_to_permission_denied() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("gone");
+        let counter = AtomicU64::new(0);
+        let err = build_tree(&missing, "gone", 0, &walk_ctx(&counter, true), None).unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::PermissionDenied(_)));
+    }
+
+    /// 10 万个文件（200 个目录 × 500 个文件）上两种遍历的耗时对比；
+    /// 运行：cargo test -p ai-disk-scanner --release fast_walk -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_fast_walk_100k_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for d in 0..200 {
+            let sub = dir.path().join(format!("dir_{:03}", d));
+            fs::create_dir(&sub).unwrap();
+            for f in 0..500 {
+                fs::write(sub.join(format!("file_{:03}.dat", f)), vec![0u8; f % 64]).unwrap();
+            }
+        }
+        let name = dir
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        // 先遍历一次预热目录项缓存，两次计时都在热缓存上进行
+        build_tree(
+            dir.path(),
+            &name,
+            0,
+            &walk_ctx(&AtomicU64::new(0), true),
+            None,
+        )
+        .unwrap();
+
+        let counter = AtomicU64::new(0);
+        let start = Instant::now();
+        let (generic, _) =
+            crate::scanner::build_tree(dir.path(), &name, 0, &walk_ctx(&counter, true), None)
+                .unwrap();
+        let generic_ms = start.elapsed().as_millis();
+
+        let counter = AtomicU64::new(0);
+        let start = Instant::now();
+        let (fast, fast_count) =
+            build_tree(dir.path(), &name, 0, &walk_ctx(&counter, true), None).unwrap();
+        let fast_ms = start.elapsed().as_millis();
+
+        assert_eq!(fast_count, 100_000);
+        assert_eq!(
+            serde_json::to_value(&generic).unwrap(),
+            serde_json::to_value(&fast).unwrap()
+        );
+        eprintln!(
+            "[fast_walk] 100k files: read_dir {} ms, fast backend {} ms",
+            generic_ms, fast_ms
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! 按 .gitignore（及用户指定的同格式忽略文件）跳过项目目录中的构建产物、依赖等。
+//!
+//! 每进入一个目录，若其中有 `.gitignore` 或自定义忽略文件，就在父目录的规则链上叠加一层；
+//! 判断时从最内层开始，第一个给出结论（忽略 / `!` 重新包含）的层生效，与 git 的优先级一致。
+//! 扫描根位于 git 仓库内时，仓库根到扫描根之间各级目录的 .gitignore 也会生效。
+//! 被忽略的文件和目录不进入扫描树，其大小累计到 `ScanResult::ignored_bytes`。
+//! 只作用于目录遍历；MFT 扫描不读取忽略规则。
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+use crate::parallel::map_collect;
+use crate::scanner::dir_size_only;
+
+/// git 的忽略文件名
+pub const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// 按忽略文件跳过子项的扫描参数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitignoreOptions {
+    /// 除 .gitignore 外，每个目录中还要读取的同格式忽略文件名（如 `.scanignore`），
+    /// 同一目录中优先级高于 .gitignore
+    pub ignore_file_name: Option<String>,
+}
+
+/// 某个目录的忽略规则及其父目录的规则链
+struct Layer {
+    matcher: Gitignore,
+    parent: Option<Arc<Layer>>,
+}
+
+/// 当前目录生效的忽略规则；进入子目录时用 [`IgnoreRules::enter`] 得到子目录的规则
+#[derive(Clone)]
+pub(crate) struct IgnoreRules {
+    file_names: Arc<Vec<String>>,
+    layers: Option<Arc<Layer>>,
+    ignored_bytes: Arc<AtomicU64>,
+}
+
+impl IgnoreRules {
+    /// 扫描根的上层规则：根位于 git 仓库内时加载仓库根到根的父目录之间的忽略文件
+    pub(crate) fn new(root: &Path, options: &GitignoreOptions) -> Self {
+        let mut file_names = vec![GITIGNORE_FILE_NAME.to_string()];
+        file_names.extend(
+            options
+                .ignore_file_name
+                .iter()
+                .filter(|n| !n.is_empty() && n.as_str() != GITIGNORE_FILE_NAME)
+                .cloned(),
+        );
+        let mut rules = Self {
+            file_names: Arc::new(file_names),
+            layers: None,
+            ignored_bytes: Arc::new(AtomicU64::new(0)),
+        };
+        let ancestors: Vec<&Path> = root.ancestors().skip(1).collect();
+        if let Some(repo_root) = ancestors.iter().position(|dir| dir.join(".git").exists()) {
+            for dir in ancestors[..=repo_root].iter().rev() {
+                rules = rules.enter(dir);
+            }
+        }
+        rules
+    }
+
+    /// 进入目录 `dir`：目录中有忽略文件时叠加一层规则
+    pub(crate) fn enter(&self, dir: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        for file_name in self.file_names.iter() {
+            let file = dir.join(file_name);
+            if !file.is_file() {
+                continue;
+            }
+            found = true;
+            if let Some(e) = builder.add(&file) {
+                tracing::warn!(file = %file.display(), error = %e, "ignore file partially parsed");
+            }
+        }
+        if !found {
+            return self.clone();
+        }
+        let matcher = match builder.build() {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::warn!(dir = %dir.display(), error = %e, "failed to build ignore rules");
+                return self.clone();
+            }
+        };
+        Self {
+            file_names: Arc::clone(&self.file_names),
+            layers: Some(Arc::new(Layer {
+                matcher,
+                parent: self.layers.clone(),
+            })),
+            ignored_bytes: Arc::clone(&self.ignored_bytes),
+        }
+    }
+
+    /// `path` 是否被忽略：从最内层规则开始，第一个匹配的规则决定结果
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut layer = self.layers.as_deref();
+        while let Some(l) = layer {
+            match l.matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => layer = l.parent.as_deref(),
+            }
+        }
+        false
+    }
+
+    /// 去掉被忽略的子项，其大小（目录为递归大小）计入 ignored_bytes。
+    /// `entry_path` 返回子项路径及其是否为目录
+    pub(crate) fn retain<T>(
+        &self,
+        entries: Vec<T>,
+        entry_path: impl Fn(&T) -> (PathBuf, bool),
+    ) -> Vec<T> {
+        if self.layers.is_none() {
+            return entries;
+        }
+        let mut ignored = Vec::new();
+        let mut kept = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let (path, is_dir) = entry_path(&entry);
+            if self.is_ignored(&path, is_dir) {
+                ignored.push((path, is_dir));
+            } else {
+                kept.push(entry);
+            }
+        }
+        let sizes = map_collect(&ignored, |(path, is_dir)| ignored_size(path, *is_dir));
+        self.ignored_bytes
+            .fetch_add(sizes.iter().sum::<u64>(), Ordering::Relaxed);
+        kept
+    }
+
+    /// 到目前为止被忽略的字节数
+    pub(crate) fn ignored_bytes(&self) -> u64 {
+        self.ignored_bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// 被忽略子项的大小；不计入扫描进度与文件数
+fn ignored_size(path: &Path, is_dir: bool) -> u64 {
+    if is_dir {
+        dir_size_only(path, &AtomicU64::new(0), None).unwrap_or(0)
+    } else {
+        std::fs::symlink_metadata(path)
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_nested_rules_override_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("app/logs")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        fs::write(root.join("app/.gitignore"), "!keep.log\n").unwrap();
+
+        let rules = IgnoreRules::new(root, &GitignoreOptions::default()).enter(root);
+        assert!(rules.is_ignored(&root.join("a.log"), false));
+        assert!(rules.is_ignored(&root.join("build"), true));
+        // 仅匹配目录的规则不影响同名文件
+        assert!(!rules.is_ignored(&root.join("build"), false));
+
+        let app = rules.enter(&root.join("app"));
+        assert!(app.is_ignored(&root.join("app/b.log"), false));
+        assert!(!app.is_ignored(&root.join("app/keep.log"), false));
+        assert!(!app.is_ignored(&root.join("app/main.rs"), false));
+    }
+
+    #[test]
+    fn test_custom_ignore_file_and_repo_ancestors() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("sub/inner")).unwrap();
+        fs::write(repo.join(".gitignore"), "*.tmp\n").unwrap();
+        fs::write(repo.join("sub/.scanignore"), "inner/\n").unwrap();
+
+        let scan_root = repo.join("sub");
+        // 扫描子目录时仓库根的 .gitignore 仍然生效
+        let plain = IgnoreRules::new(&scan_root, &GitignoreOptions::default()).enter(&scan_root);
+        assert!(plain.is_ignored(&scan_root.join("x.tmp"), false));
+        assert!(!plain.is_ignored(&scan_root.join("inner"), true));
+
+        let options = GitignoreOptions {
+            ignore_file_name: Some(".scanignore".to_string()),
+        };
+        let custom = IgnoreRules::new(&scan_root, &options).enter(&scan_root);
+        assert!(custom.is_ignored(&scan_root.join("inner"), true));
+    }
+
+    #[test]
+    fn test_retain_counts_ignored_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("dist/js")).unwrap();
+        fs::write(root.join("dist/js/app.js"), vec![0u8; 300]).unwrap();
+        fs::write(root.join("debug.log"), vec![0u8; 50]).unwrap();
+        fs::write(root.join("main.rs"), b"fn main() {}").unwrap();
+        fs::write(root.join(".gitignore"), "dist/\n*.log\n").unwrap();
+
+        let rules = IgnoreRules::new(root, &GitignoreOptions::default()).enter(root);
+        let mut names: Vec<String> = rules
+            .retain(
+                fs::read_dir(root).unwrap().filter_map(|e| e.ok()).collect(),
+                |e| (e.path(), e.path().is_dir()),
+            )
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![".gitignore", "main.rs"]);
+        assert_eq!(rules.ignored_bytes(), 350);
+    }
+}
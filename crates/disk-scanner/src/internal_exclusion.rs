@@ -0,0 +1,171 @@
+//! 从扫描结果中剔除应用自身的占用（存储根目录、正在写入的导出文件，见
+//! [`ai_disk_common::InternalPaths`]）。目录遍历与 MFT 两种扫描得到的树都在返回前经过这里，
+//! 被剔除节点的大小与条目数从各级祖先和结果汇总中扣除，大文件列表中的对应条目一并移除。
+//!
+//! 扫描根目录本身就是应用占用的路径时（用户有意查看存储目录），不做剔除。
+
+use ai_disk_common::InternalPaths;
+use ai_disk_domain::{FileNode, ScanResult};
+
+/// 剔除 `result` 中位于 `internal` 下的节点，返回剔除的字节数
+pub fn exclude_internal_paths(result: &mut ScanResult, internal: &InternalPaths) -> u64 {
+    if internal.is_empty() || internal.contains(&result.root.path) {
+        return 0;
+    }
+    let (bytes, physical, entries) = prune(&mut result.root, internal);
+    result.total_size = result.total_size.saturating_sub(bytes);
+    if let Some(usage) = &mut result.physical_usage {
+        usage.logical_bytes = usage.logical_bytes.saturating_sub(bytes);
+        usage.physical_bytes = usage.physical_bytes.saturating_sub(physical);
+    }
+    result.file_count = result.file_count.saturating_sub(entries);
+    if let Some(top_files) = &mut result.top_files {
+        top_files.retain(|entry| !internal.contains(&entry.path));
+    }
+    if bytes > 0 {
+        tracing::info!(bytes, entries, "excluded app-owned paths from scan result");
+    }
+    bytes
+}
+
+/// 递归剔除，返回 (字节数, 实际占用字节数, 条目数)；只进入其下可能有应用占用路径的目录
+fn prune(node: &mut FileNode, internal: &InternalPaths) -> (u64, u64, u64) {
+    let (mut bytes, mut physical, mut entries) = (0u64, 0u64, 0u64);
+    let listed = node.children.len();
+    node.children.retain_mut(|child| {
+        if internal.contains(&child.path) {
+            bytes = bytes.saturating_add(child.size);
+            physical = physical.saturating_add(child.physical_size.unwrap_or(child.size));
+            entries += count_entries(child);
+            return false;
+        }
+        if child.is_dir && internal.any_within(&child.path) {
+            let (b, p, e) = prune(child, internal);
+            bytes = bytes.saturating_add(b);
+            physical = physical.saturating_add(p);
+            entries += e;
+        }
+        true
+    });
+    node.partial_children |= node.children.len() < listed;
+    node.partial_size |= bytes > 0;
+    node.size = node.size.saturating_sub(bytes);
+    node.physical_size = node.physical_size.map(|p| p.saturating_sub(physical));
+    (bytes, physical, entries)
+}
+
+fn count_entries(node: &FileNode) -> u64 {
+    1 + node.children.iter().map(count_entries).sum::<u64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{scan_path_with_progress, WalkOptions};
+    use ai_disk_common::set_storage_root;
+    use ai_disk_domain::{SizeSource, TopFileEntry};
+    use std::fs;
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            size,
+            is_dir: !children.is_empty(),
+            size_source: SizeSource::default(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    fn scan_of(root: FileNode, file_count: u64) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    fn find<'a>(node: &'a FileNode, name: &str) -> Option<&'a FileNode> {
+        if node.name == name {
+            return Some(node);
+        }
+        node.children.iter().find_map(|c| find(c, name))
+    }
+
+    #[test]
+    fn test_pruned_sizes_bubble_up() {
+        let root = node(
+            "/home/u",
+            110,
+            vec![
+                node(
+                    "/home/u/.disk-rookie",
+                    100,
+                    vec![node("/home/u/.disk-rookie/scans.json", 100, vec![])],
+                ),
+                node("/home/u/notes.txt", 10, vec![]),
+            ],
+        );
+        let mut result = scan_of(root, 4);
+        result.top_files = Some(vec![TopFileEntry {
+            path: "/home/u/.disk-rookie/scans.json".to_string(),
+            size: 100,
+            modified: None,
+            created: None,
+            accessed: None,
+            attributes: None,
+            owner: None,
+        }]);
+        let internal = InternalPaths::from_paths_for(["/home/u/.disk-rookie"], false);
+        assert_eq!(exclude_internal_paths(&mut result, &internal), 100);
+        assert_eq!(result.root.size, 10);
+        assert_eq!(result.total_size, 10);
+        assert_eq!(result.file_count, 2);
+        assert_eq!(result.root.children.len(), 1);
+        assert!(result.top_files.unwrap().is_empty());
+
+        // 扫描根目录本身在存储目录下时保持原样
+        let mut own = scan_of(node("/home/u/.disk-rookie", 100, vec![]), 1);
+        assert_eq!(exclude_internal_paths(&mut own, &internal), 0);
+    }
+
+    #[test]
+    fn test_scan_excludes_storage_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join(".disk-rookie");
+        fs::create_dir_all(storage.join("scans")).unwrap();
+        fs::write(storage.join("scans").join("saved.json"), vec![0u8; 4096]).unwrap();
+        fs::write(storage.join("app.log"), vec![0u8; 1024]).unwrap();
+        fs::write(dir.path().join("user.txt"), vec![0u8; 100]).unwrap();
+        set_storage_root(&storage);
+
+        let (result, _) = scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            false,
+            false,
+            Some(1),
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+        assert!(find(&result.root, ".disk-rookie").is_none());
+        assert!(find(&result.root, "user.txt").is_some());
+        assert_eq!(result.total_size, 100);
+        assert_eq!(result.root.size, 100);
+    }
+}
@@ -0,0 +1,254 @@
+//! 已知目录的实际位置：桌面、文档、下载、图片等目录可以被用户移动（如移到 D 盘或 OneDrive 中），
+//! 按默认路径或目录名判断会认错。这里向系统查询实际位置——Windows 为 `SHGetKnownFolderPath`，
+//! Linux 为 XDG 用户目录配置（`$XDG_CONFIG_HOME/user-dirs.dirs`），macOS 的标准目录固定在家目录下
+//! （即 `NSSearchPathForDirectoriesInDomains` 的结果）——并在扫描树中标记对应节点
+//! （[`FileNode::known_folder`]），供风险评估与界面侧边栏使用。
+
+use std::path::{Path, PathBuf};
+
+use ai_disk_domain::{FileNode, KnownFolder, KnownFolderMap};
+
+#[cfg(not(all(windows, feature = "windows-native")))]
+use crate::cleanup_targets::KnownFolders;
+use crate::display_path::DisplayPath;
+use crate::trash::find_node_mut;
+
+/// 已知目录 → 实际位置
+pub trait KnownFolderResolver {
+    fn locate(&self, folder: KnownFolder) -> Option<PathBuf>;
+}
+
+/// 向当前系统查询的解析器
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemKnownFolderResolver;
+
+/// 目录在家目录下的默认名称；没有默认位置的目录（OneDrive）返回 None
+#[cfg(not(all(windows, feature = "windows-native")))]
+fn default_dir_name(folder: KnownFolder) -> Option<&'static str> {
+    match folder {
+        KnownFolder::Desktop => Some("Desktop"),
+        KnownFolder::Documents => Some("Documents"),
+        KnownFolder::Downloads => Some("Downloads"),
+        KnownFolder::Pictures => Some("Pictures"),
+        KnownFolder::Music => Some("Music"),
+        KnownFolder::Videos if cfg!(target_os = "macos") => Some("Movies"),
+        KnownFolder::Videos => Some("Videos"),
+        KnownFolder::OneDrive => None,
+    }
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+impl KnownFolderResolver for SystemKnownFolderResolver {
+    fn locate(&self, folder: KnownFolder) -> Option<PathBuf> {
+        use std::ffi::OsString;
+        use std::os::windows::ffi::OsStringExt;
+        use windows_sys::Win32::System::Com::CoTaskMemFree;
+        use windows_sys::Win32::UI::Shell::{
+            FOLDERID_Desktop, FOLDERID_Documents, FOLDERID_Downloads, FOLDERID_Music,
+            FOLDERID_Pictures, FOLDERID_SkyDrive, FOLDERID_Videos, SHGetKnownFolderPath,
+        };
+
+        let id = match folder {
+            KnownFolder::Desktop => &FOLDERID_Desktop,
+            KnownFolder::Documents => &FOLDERID_Documents,
+            KnownFolder::Downloads => &FOLDERID_Downloads,
+            KnownFolder::Pictures => &FOLDERID_Pictures,
+            KnownFolder::Music => &FOLDERID_Music,
+            KnownFolder::Videos => &FOLDERID_Videos,
+            KnownFolder::OneDrive => &FOLDERID_SkyDrive,
+        };
+        let mut raw = std::ptr::null_mut();
+        // SAFETY: id 指向静态 GUID，令牌为 0 表示当前用户；无论成败都需用 CoTaskMemFree 释放输出
+        let hr = unsafe { SHGetKnownFolderPath(id, 0, 0, &mut raw) };
+        let path = (hr >= 0 && !raw.is_null()).then(|| {
+            // SAFETY: 成功时 raw 是以 NUL 结尾的宽字符串
+            let len = unsafe { (0..).take_while(|&i| *raw.add(i) != 0).count() };
+            let wide = unsafe { std::slice::from_raw_parts(raw, len) };
+            PathBuf::from(OsString::from_wide(wide))
+        });
+        // SAFETY: raw 由 SHGetKnownFolderPath 分配（可能为空指针），此后不再使用
+        unsafe { CoTaskMemFree(raw as _) };
+        path.or_else(|| match folder {
+            // 未登录 OneDrive 客户端时没有注册该目录，退回客户端设置的环境变量
+            KnownFolder::OneDrive => std::env::var_os("OneDrive")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(all(windows, not(feature = "windows-native")))]
+impl KnownFolderResolver for SystemKnownFolderResolver {
+    fn locate(&self, folder: KnownFolder) -> Option<PathBuf> {
+        let home = KnownFolders::from_env().home?;
+        match folder {
+            KnownFolder::OneDrive => std::env::var_os("OneDrive")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+            _ => Some(home.join(default_dir_name(folder)?)),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl KnownFolderResolver for SystemKnownFolderResolver {
+    fn locate(&self, folder: KnownFolder) -> Option<PathBuf> {
+        Some(
+            KnownFolders::from_env()
+                .home?
+                .join(default_dir_name(folder)?),
+        )
+    }
+}
+
+#[cfg(all(not(windows), not(target_os = "macos")))]
+impl KnownFolderResolver for SystemKnownFolderResolver {
+    fn locate(&self, folder: KnownFolder) -> Option<PathBuf> {
+        let home = KnownFolders::from_env().home?;
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        let configured = std::fs::read_to_string(config_home.join("user-dirs.dirs"))
+            .ok()
+            .and_then(|content| parse_user_dirs(&content, &home, folder));
+        configured.or_else(|| Some(home.join(default_dir_name(folder)?)))
+    }
+}
+
+/// `user-dirs.dirs` 中对应的变量名
+#[cfg(any(all(not(windows), not(target_os = "macos")), test))]
+fn xdg_key(folder: KnownFolder) -> Option<&'static str> {
+    match folder {
+        KnownFolder::Desktop => Some("XDG_DESKTOP_DIR"),
+        KnownFolder::Documents => Some("XDG_DOCUMENTS_DIR"),
+        KnownFolder::Downloads => Some("XDG_DOWNLOAD_DIR"),
+        KnownFolder::Pictures => Some("XDG_PICTURES_DIR"),
+        KnownFolder::Music => Some("XDG_MUSIC_DIR"),
+        KnownFolder::Videos => Some("XDG_VIDEOS_DIR"),
+        KnownFolder::OneDrive => None,
+    }
+}
+
+/// 解析 `user-dirs.dirs`（`XDG_DOWNLOAD_DIR="$HOME/下载"`）。
+/// 值只能是 `$HOME/` 开头的相对路径或绝对路径；值为 `$HOME` 本身表示禁用该目录
+#[cfg(any(all(not(windows), not(target_os = "macos")), test))]
+fn parse_user_dirs(content: &str, home: &Path, folder: KnownFolder) -> Option<PathBuf> {
+    let key = xdg_key(folder)?;
+    let value = content.lines().find_map(|line| {
+        let (name, value) = line.trim().split_once('=')?;
+        (name.trim() == key).then(|| value.trim().trim_matches('"'))
+    })?;
+    let path = match value.strip_prefix("$HOME") {
+        Some("" | "/") => return None,
+        Some(rest) => home.join(rest.strip_prefix('/')?),
+        None if value.starts_with('/') => PathBuf::from(value),
+        None => return None,
+    };
+    Some(path)
+}
+
+/// 用解析器查询所有已知目录；不存在的目录不收录
+pub fn resolve_known_folders(resolver: &impl KnownFolderResolver) -> KnownFolderMap {
+    KnownFolder::ALL
+        .into_iter()
+        .filter_map(|folder| {
+            // 树中的路径为 canonicalize 后的 DisplayPath，目录经符号链接或联接点移动时也能对上
+            let canonical = std::fs::canonicalize(resolver.locate(folder)?).ok()?;
+            canonical.is_dir().then(|| {
+                let path = DisplayPath::new(&canonical.to_string_lossy()).into_string();
+                (folder, path)
+            })
+        })
+        .collect()
+}
+
+/// 当前系统与用户的已知目录
+pub fn system_known_folders() -> KnownFolderMap {
+    resolve_known_folders(&SystemKnownFolderResolver)
+}
+
+/// 在已整理的扫描树中标记已知目录节点；位于未展开的目录下的不标记
+pub fn tag_known_folders(root: &mut FileNode, folders: &KnownFolderMap) {
+    for (folder, path) in folders {
+        if let Some(node) = find_node_mut(root, Path::new(path)) {
+            node.known_folder = Some(*folder);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{scan_path_with_progress, WalkOptions};
+    use std::collections::HashMap;
+    use std::fs;
+
+    struct FakeResolver(HashMap<KnownFolder, PathBuf>);
+
+    impl KnownFolderResolver for FakeResolver {
+        fn locate(&self, folder: KnownFolder) -> Option<PathBuf> {
+            self.0.get(&folder).cloned()
+        }
+    }
+
+    fn find<'a>(node: &'a FileNode, name: &str) -> Option<&'a FileNode> {
+        if node.name == name {
+            return Some(node);
+        }
+        node.children.iter().find_map(|c| find(c, name))
+    }
+
+    #[test]
+    fn test_tags_relocated_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        // 文档移到了 data/我的文档，下载目录名为 Downloads 但位置不是默认的；
+        // 家目录下同名的 Documents 只是普通目录
+        for sub in ["home/Documents", "data/我的文档/notes", "data/Downloads"] {
+            fs::create_dir_all(dir.path().join(sub)).unwrap();
+        }
+        let resolver = FakeResolver(HashMap::from([
+            (KnownFolder::Documents, dir.path().join("data/我的文档")),
+            (KnownFolder::Downloads, dir.path().join("data/Downloads")),
+            (KnownFolder::Pictures, dir.path().join("missing")),
+        ]));
+        let folders = resolve_known_folders(&resolver);
+        assert_eq!(folders.len(), 2);
+
+        let (mut result, _) = scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            false,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+        tag_known_folders(&mut result.root, &folders);
+        let tag = |name: &str| find(&result.root, name).unwrap().known_folder;
+        assert_eq!(tag("我的文档"), Some(KnownFolder::Documents));
+        assert_eq!(tag("Downloads"), Some(KnownFolder::Downloads));
+        assert_eq!(tag("Documents"), None);
+        assert_eq!(tag("notes"), None);
+    }
+
+    #[test]
+    fn test_parse_user_dirs() {
+        let home = Path::new("/home/u");
+        let content =
+            "# comment\nXDG_DESKTOP_DIR=\"$HOME/桌面\"\nXDG_DOWNLOAD_DIR=\"/mnt/data/dl\"\n\
+                       XDG_MUSIC_DIR=\"$HOME\"\nXDG_PICTURES_DIR=\"$HOME/\"\n";
+        let parse = |folder| parse_user_dirs(content, home, folder);
+        assert_eq!(parse(KnownFolder::Desktop), Some(home.join("桌面")));
+        assert_eq!(
+            parse(KnownFolder::Downloads),
+            Some(PathBuf::from("/mnt/data/dl"))
+        );
+        assert_eq!(parse(KnownFolder::Music), None);
+        assert_eq!(parse(KnownFolder::Pictures), None);
+        assert_eq!(parse(KnownFolder::Documents), None);
+        assert_eq!(parse(KnownFolder::OneDrive), None);
+    }
+}
@@ -1,15 +1,90 @@
+pub mod cleanup_targets;
+pub mod disk_health;
+pub mod display_path;
 pub mod filters;
+pub mod ignore_rules;
+pub mod internal_exclusion;
+pub mod known_folders;
+pub mod locate;
+pub mod low_space;
+pub mod mft_availability;
+pub mod mount_points;
 pub mod node;
+pub mod owners;
+mod parallel;
+pub mod pause;
+mod physical_size;
+pub mod preflight;
+pub mod preview;
+pub mod process_io;
+pub mod quick_stats;
+pub mod recent_activity;
+pub mod record_arena;
+mod rollups;
 pub mod scanner;
+pub mod size_fallback;
+pub mod size_oracle;
+pub mod stream_export;
+pub mod throttle;
+pub mod trash;
 
-#[cfg(windows)]
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos",
+    all(windows, feature = "windows-native")
+))]
+mod fast_walk;
+
+#[cfg(any(test, all(windows, feature = "windows-native")))]
+mod mft_chunks;
+#[cfg(all(windows, feature = "windows-native"))]
 pub mod mft_scan;
 
 pub use ai_disk_domain::ScanResult;
+pub use cleanup_targets::{discover_cleanup_targets, KnownFolders, Platform};
+pub use disk_health::{
+    apply_smartctl_json, detect_media_type, get_disk_health, parse_ata_smart_data,
+    parse_storage_device_descriptor, redact_serial,
+};
+pub use display_path::DisplayPath;
 pub use filters::*;
+pub use ignore_rules::{GitignoreOptions, GITIGNORE_FILE_NAME};
+pub use internal_exclusion::exclude_internal_paths;
+pub use known_folders::{
+    resolve_known_folders, system_known_folders, tag_known_folders, KnownFolderResolver,
+    SystemKnownFolderResolver,
+};
+pub use locate::locate_in_tree;
+pub use low_space::{
+    fixed_volumes, volume_space, LowSpaceMonitor, SpaceProvider, SystemSpaceProvider, VolumeSpace,
+};
+pub use mft_availability::{explain_mft_availability, is_fat_filesystem, volume_filesystem};
+pub use mount_points::{is_volume_root, same_volume};
 pub use node::*;
-pub use scanner::{scan_path, scan_path_with_progress, scan_will_use_mft};
+pub use owners::{attribute_owners, OwnerResolver, SystemOwnerResolver, OWNER_DIR_MIN_BYTES};
+pub use pause::PauseToken;
+pub use preflight::{recommend_scan_mode, scan_preflight, MFT_RECOMMENDED_MIN_ENTRIES};
+pub use preview::{preview_file, PREVIEW_DEFAULT_MAX_BYTES, PREVIEW_MAX_BYTES};
+pub use process_io::{
+    read_process_io, IoSampler, ProcessIoCounters, IO_SAMPLE_MIN_INTERVAL_MS, IO_SAMPLE_TOP_N,
+};
+pub use quick_stats::{quick_dir_stats, QUICK_STATS_MAX_ENTRIES};
+pub use recent_activity::{
+    recent_activity_from_arena, recent_activity_from_tree, RECENT_ACTIVITY_LIMIT,
+};
+pub use record_arena::{RecordArena, RecordArenaBuilder, RecordMeta};
+pub use scanner::{
+    default_scan_concurrency, scan_path, scan_path_with_progress, scan_will_use_mft, WalkOptions,
+    DEFAULT_SCAN_THREADS_MAX, HDD_SCAN_THREADS,
+};
+pub use size_fallback::{estimate_unknown_sizes, CachedScanSizes, DirSizeSource};
+pub use size_oracle::SizeOracle;
+pub use stream_export::{scan_to_writer, StreamOptions};
+pub use throttle::{
+    lower_current_thread_priority, BackgroundScan, TokenBucket, DEFAULT_BACKGROUND_ENTRIES_PER_SEC,
+};
+pub use trash::{is_volume_trash_name, trash_dirs, user_trash_dirs};
 
 pub use ai_disk_domain::TopFileEntry;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "windows-native"))]
 pub use mft_scan::{scan_volume_mft_top_files, TOP_FILES_DEFAULT_N};
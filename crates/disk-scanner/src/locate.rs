@@ -0,0 +1,162 @@
+//! 在扫描树中定位路径：返回从根到目标的节点链，供计划审阅时在树中展开到某个动作的路径。
+//!
+//! 路径按计算节点 id 的规则比较（见 [`crate::normalize_node_path`]），Windows 上不区分大小写，
+//! `\\?\` 前缀与分隔符写法的差异也被忽略。浅扫描或深度截断的目录没有子节点，
+//! 目标位于其下时链止于该目录并标记为非精确命中。
+
+use ai_disk_domain::{FileNode, NodeLocator, ScanLocation};
+
+use crate::node::normalize_node_path_for;
+
+/// 定位 `path`；不在扫描范围内，或所在目录已完整列出却没有该项时返回 None
+pub fn locate_in_tree(root: &FileNode, path: &str) -> Option<ScanLocation> {
+    locate_in_tree_for(root, path, cfg!(windows))
+}
+
+/// `key` 是否为 `target` 本身或其祖先（两者都已规范化）
+fn is_within(key: &str, target: &str, windows: bool) -> bool {
+    let sep = if windows { '\\' } else { '/' };
+    target == key
+        || (target.starts_with(key) && (key.ends_with(sep) || target[key.len()..].starts_with(sep)))
+}
+
+fn locator(node: &FileNode) -> NodeLocator {
+    NodeLocator {
+        id: node.id,
+        name: node.name.clone(),
+        path: node.path.clone(),
+    }
+}
+
+fn locate_in_tree_for(root: &FileNode, path: &str, windows: bool) -> Option<ScanLocation> {
+    let target = normalize_node_path_for(path, windows);
+    let mut node = root;
+    if !is_within(
+        &normalize_node_path_for(&node.path, windows),
+        &target,
+        windows,
+    ) {
+        return None;
+    }
+    let mut chain = vec![locator(node)];
+    loop {
+        if normalize_node_path_for(&node.path, windows) == target {
+            return Some(ScanLocation { chain, exact: true });
+        }
+        if node.is_dir && node.children.is_empty() {
+            return Some(ScanLocation {
+                chain,
+                exact: false,
+            });
+        }
+        node = node.children.iter().find(|child| {
+            is_within(
+                &normalize_node_path_for(&child.path, windows),
+                &target,
+                windows,
+            )
+        })?;
+        chain.push(locator(node));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, is_dir: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            id: path.len() as u64,
+            ..FileNode::new(path, 0, is_dir).with_children(children)
+        }
+    }
+
+    fn names(location: &ScanLocation) -> Vec<&str> {
+        location.chain.iter().map(|n| n.name.as_str()).collect()
+    }
+
+    fn unix_tree() -> FileNode {
+        node(
+            "/home/u",
+            true,
+            vec![
+                node(
+                    "/home/u/Downloads",
+                    true,
+                    vec![node("/home/u/Downloads/a.zip", false, vec![])],
+                ),
+                // 浅扫描：目录没有子节点
+                node("/home/u/project", true, vec![]),
+                node(
+                    "/home/u/projects",
+                    true,
+                    vec![node("/home/u/projects/x", false, vec![])],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_exact_match_returns_full_chain() {
+        let location = locate_in_tree_for(&unix_tree(), "/home/u/Downloads/a.zip", false).unwrap();
+        assert!(location.exact);
+        assert_eq!(names(&location), ["u", "Downloads", "a.zip"]);
+        assert_eq!(location.chain[2].path, "/home/u/Downloads/a.zip");
+
+        let root = locate_in_tree_for(&unix_tree(), "/home/u/", false).unwrap();
+        assert!(root.exact);
+        assert_eq!(root.chain.len(), 1);
+    }
+
+    #[test]
+    fn test_path_under_shallow_dir_stops_at_nearest_node() {
+        let location =
+            locate_in_tree_for(&unix_tree(), "/home/u/project/target/debug/app", false).unwrap();
+        assert!(!location.exact);
+        assert_eq!(names(&location), ["u", "project"]);
+        // 前缀相同的兄弟目录不会被误认为祖先
+        let sibling = locate_in_tree_for(&unix_tree(), "/home/u/projects/x", false).unwrap();
+        assert!(sibling.exact);
+        assert_eq!(names(&sibling), ["u", "projects", "x"]);
+    }
+
+    #[test]
+    fn test_not_in_scan() {
+        let tree = unix_tree();
+        assert!(locate_in_tree_for(&tree, "/home/other/file", false).is_none());
+        assert!(locate_in_tree_for(&tree, "/home/uu", false).is_none());
+        // 目录已完整列出，其中没有该项
+        assert!(locate_in_tree_for(&tree, "/home/u/Downloads/b.zip", false).is_none());
+        // Unix 路径区分大小写
+        assert!(locate_in_tree_for(&tree, "/home/u/downloads/a.zip", false).is_none());
+    }
+
+    #[test]
+    fn test_windows_lookup_ignores_case_and_verbatim_prefix() {
+        let tree = node(
+            r"C:\",
+            true,
+            vec![node(
+                r"C:\Users",
+                true,
+                vec![
+                    node(r"C:\Users\Bob", true, vec![]),
+                    node(
+                        r"C:\Users\Public",
+                        true,
+                        vec![node(r"C:\Users\Public\Setup.EXE", false, vec![])],
+                    ),
+                ],
+            )],
+        );
+        let exact = locate_in_tree_for(&tree, r"\\?\c:\users\public\setup.exe", true).unwrap();
+        assert!(exact.exact);
+        assert_eq!(names(&exact), ["C:", "Users", "Public", "Setup.EXE"]);
+
+        let shallow = locate_in_tree_for(&tree, r"c:/users/bob/AppData/Local", true).unwrap();
+        assert!(!shallow.exact);
+        assert_eq!(names(&shallow), ["C:", "Users", "Bob"]);
+
+        assert!(locate_in_tree_for(&tree, r"D:\Users\Bob", true).is_none());
+    }
+}
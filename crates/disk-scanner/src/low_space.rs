@@ -0,0 +1,364 @@
+//! 低空间监控：按配置判断各卷剩余空间是否不足，并用回差避免空间持续不足时每轮都提醒。
+//!
+//! 卷剩余空间的查询通过 [`SpaceProvider`] 注入，测试中以固定数值替代系统调用。
+//! 一个卷触发提醒后进入「已提醒」状态，剩余空间回升到阈值之上再加
+//! `rearm_margin_percent` 个百分点的总容量后才重新布防。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::LowSpaceConfig;
+use ai_disk_domain::LowSpaceAlert;
+
+/// 一个卷的容量与剩余空间（字节）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VolumeSpace {
+    pub total: u64,
+    pub free: u64,
+}
+
+impl VolumeSpace {
+    /// 剩余空间占总容量的百分比；总容量为 0 时视为 100
+    pub fn free_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.free as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// 卷剩余空间的查询来源
+pub trait SpaceProvider {
+    /// 要检查的卷；配置中指定了卷时只检查这些卷
+    fn volumes(&self) -> Vec<PathBuf>;
+    /// 卷的容量与剩余空间，查询失败时返回 None（本轮跳过该卷）
+    fn space(&self, volume: &Path) -> Option<VolumeSpace>;
+}
+
+/// 通过操作系统查询：Windows 为所有固定磁盘，Unix 为根目录与家目录所在的文件系统
+pub struct SystemSpaceProvider;
+
+impl SpaceProvider for SystemSpaceProvider {
+    fn volumes(&self) -> Vec<PathBuf> {
+        fixed_volumes()
+    }
+
+    fn space(&self, volume: &Path) -> Option<VolumeSpace> {
+        volume_space(volume)
+    }
+}
+
+/// 各卷的提醒状态
+#[derive(Debug, Default)]
+pub struct LowSpaceMonitor {
+    /// 已提醒、尚未回升到回差之上的卷
+    alerted: BTreeSet<PathBuf>,
+}
+
+impl LowSpaceMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查一轮，返回本轮新进入空间不足状态的卷
+    pub fn check(
+        &mut self,
+        provider: &impl SpaceProvider,
+        config: &LowSpaceConfig,
+    ) -> Vec<LowSpaceAlert> {
+        let volumes = if config.volumes.is_empty() {
+            provider.volumes()
+        } else {
+            config.volumes.iter().map(PathBuf::from).collect()
+        };
+        let mut alerts = Vec::new();
+        for volume in volumes {
+            let Some(space) = provider.space(&volume) else {
+                continue;
+            };
+            if self.alerted.contains(&volume) {
+                if is_recovered(space, config) {
+                    self.alerted.remove(&volume);
+                }
+                continue;
+            }
+            if is_low(space, config) {
+                alerts.push(LowSpaceAlert {
+                    volume: volume.to_string_lossy().into_owned(),
+                    total_bytes: space.total,
+                    free_bytes: space.free,
+                    free_percent: space.free_percent(),
+                    scan_id: None,
+                });
+                self.alerted.insert(volume);
+            }
+        }
+        alerts
+    }
+}
+
+/// 剩余空间低于百分比阈值或字节阈值
+pub fn is_low(space: VolumeSpace, config: &LowSpaceConfig) -> bool {
+    space.free_percent() < config.threshold_percent || space.free < config.threshold_bytes
+}
+
+/// 剩余空间已回升到两个阈值之上，且各超出总容量的 `rearm_margin_percent`
+fn is_recovered(space: VolumeSpace, config: &LowSpaceConfig) -> bool {
+    let margin_bytes = (space.total as f64 * config.rearm_margin_percent / 100.0) as u64;
+    space.free_percent() >= config.threshold_percent + config.rearm_margin_percent
+        && space.free >= config.threshold_bytes.saturating_add(margin_bytes)
+}
+
+/// 路径所在卷的容量与剩余空间
+pub fn volume_space(path: &Path) -> Option<VolumeSpace> {
+    platform::volume_space(path)
+}
+
+/// 默认监控的固定卷
+pub fn fixed_volumes() -> Vec<PathBuf> {
+    platform::fixed_volumes()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+
+    use super::VolumeSpace;
+
+    // statvfs 字段的类型随平台不同（macOS 上块数为 u32），统一转为 u64
+    #[allow(clippy::unnecessary_cast)]
+    pub(super) fn volume_space(path: &Path) -> Option<VolumeSpace> {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        let stat = statvfs(&c_path)?;
+        let block = stat.f_frsize as u64;
+        Some(VolumeSpace {
+            total: (stat.f_blocks as u64).saturating_mul(block),
+            // 普通用户可用的空间，不含为 root 保留的块
+            free: (stat.f_bavail as u64).saturating_mul(block),
+        })
+    }
+
+    /// 失败时为 None
+    #[allow(unsafe_code)]
+    fn statvfs(path: &std::ffi::CStr) -> Option<libc::statvfs> {
+        // SAFETY: statvfs 结构体全零是合法值
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: path 以 NUL 结尾，在调用期间有效；stat 可写，由 statvfs 填充
+        let rc = unsafe { libc::statvfs(path.as_ptr(), &mut stat) };
+        (rc == 0).then_some(stat)
+    }
+
+    /// 根目录，以及与之不在同一文件系统的家目录
+    pub(super) fn fixed_volumes() -> Vec<PathBuf> {
+        let root = PathBuf::from("/");
+        let mut volumes = vec![root.clone()];
+        let root_dev = std::fs::metadata(&root).ok().map(|m| m.dev());
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            let home_dev = std::fs::metadata(&home).ok().map(|m| m.dev());
+            if home_dev.is_some() && home_dev != root_dev {
+                volumes.push(home);
+            }
+        }
+        volumes
+    }
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::VolumeSpace;
+
+    /// GetDriveTypeW 的 DRIVE_FIXED
+    const DRIVE_FIXED: u32 = 3;
+
+    pub(super) fn volume_space(path: &Path) -> Option<VolumeSpace> {
+        crate::mft_scan::get_volume_space_bytes(&path.to_string_lossy())
+            .map(|(total, free)| VolumeSpace { total, free })
+    }
+
+    /// 所有盘符中类型为固定磁盘的卷根（`C:\` 等）
+    #[allow(unsafe_code)]
+    pub(super) fn fixed_volumes() -> Vec<PathBuf> {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetLogicalDrives};
+        // SAFETY: 无参数
+        let mask = unsafe { GetLogicalDrives() };
+        (0..26u8)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| PathBuf::from(format!("{}:\\", (b'A' + i) as char)))
+            .filter(|root| {
+                let wide: Vec<u16> = root.as_os_str().encode_wide().chain(Some(0)).collect();
+                // SAFETY: wide 以 NUL 结尾，在调用期间有效
+                unsafe { GetDriveTypeW(wide.as_ptr()) == DRIVE_FIXED }
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(unix, all(windows, feature = "windows-native"))))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::VolumeSpace;
+
+    pub(super) fn volume_space(_path: &Path) -> Option<VolumeSpace> {
+        None
+    }
+
+    pub(super) fn fixed_volumes() -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    /// 各卷剩余空间可在测试中途修改的模拟来源
+    struct FakeSpace {
+        volumes: RefCell<BTreeMap<PathBuf, VolumeSpace>>,
+    }
+
+    impl FakeSpace {
+        fn new(entries: &[(&str, u64, u64)]) -> Self {
+            Self {
+                volumes: RefCell::new(
+                    entries
+                        .iter()
+                        .map(|&(v, total, free)| (PathBuf::from(v), VolumeSpace { total, free }))
+                        .collect(),
+                ),
+            }
+        }
+
+        fn set_free(&self, volume: &str, free: u64) {
+            self.volumes
+                .borrow_mut()
+                .get_mut(Path::new(volume))
+                .unwrap()
+                .free = free;
+        }
+    }
+
+    impl SpaceProvider for FakeSpace {
+        fn volumes(&self) -> Vec<PathBuf> {
+            self.volumes.borrow().keys().cloned().collect()
+        }
+
+        fn space(&self, volume: &Path) -> Option<VolumeSpace> {
+            self.volumes.borrow().get(volume).copied()
+        }
+    }
+
+    fn config() -> LowSpaceConfig {
+        LowSpaceConfig {
+            threshold_percent: 10.0,
+            threshold_bytes: 5 * GIB,
+            rearm_margin_percent: 2.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_percent_or_bytes_threshold() {
+        let config = config();
+        // 1000 GiB 卷剩 9%：低于百分比阈值
+        assert!(is_low(
+            VolumeSpace {
+                total: 1000 * GIB,
+                free: 90 * GIB
+            },
+            &config
+        ));
+        // 40 GiB 卷剩 4 GiB（10%）：低于字节阈值
+        assert!(is_low(
+            VolumeSpace {
+                total: 40 * GIB,
+                free: 4 * GIB
+            },
+            &config
+        ));
+        assert!(!is_low(
+            VolumeSpace {
+                total: 1000 * GIB,
+                free: 100 * GIB
+            },
+            &config
+        ));
+        let percent_only = LowSpaceConfig {
+            threshold_bytes: 0,
+            ..config
+        };
+        assert!(!is_low(
+            VolumeSpace {
+                total: 40 * GIB,
+                free: 4 * GIB
+            },
+            &percent_only
+        ));
+    }
+
+    #[test]
+    fn test_hysteresis_fires_once_until_recovered() {
+        let config = config();
+        let space = FakeSpace::new(&[
+            ("C:\\", 1000 * GIB, 200 * GIB),
+            ("D:\\", 1000 * GIB, 500 * GIB),
+        ]);
+        let mut monitor = LowSpaceMonitor::new();
+        assert!(monitor.check(&space, &config).is_empty());
+
+        space.set_free("C:\\", 80 * GIB);
+        let alerts = monitor.check(&space, &config);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].volume, "C:\\");
+        assert_eq!(alerts[0].free_bytes, 80 * GIB);
+        assert!((alerts[0].free_percent - 8.0).abs() < 1e-9);
+
+        // 持续不足、或回升到阈值附近但未超过回差时不再提醒
+        assert!(monitor.check(&space, &config).is_empty());
+        space.set_free("C:\\", 110 * GIB);
+        assert!(monitor.check(&space, &config).is_empty());
+        space.set_free("C:\\", 90 * GIB);
+        assert!(monitor.check(&space, &config).is_empty());
+
+        // 回升到 12% 以上后重新布防，再次跌破时提醒
+        space.set_free("C:\\", 130 * GIB);
+        assert!(monitor.check(&space, &config).is_empty());
+        space.set_free("C:\\", 90 * GIB);
+        assert_eq!(monitor.check(&space, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_configured_volumes_and_failed_queries() {
+        let space = FakeSpace::new(&[
+            ("C:\\", 1000 * GIB, 10 * GIB),
+            ("D:\\", 1000 * GIB, 10 * GIB),
+        ]);
+        let config = LowSpaceConfig {
+            volumes: vec!["D:\\".to_string(), "E:\\".to_string()],
+            ..config()
+        };
+        let mut monitor = LowSpaceMonitor::new();
+        let alerts = monitor.check(&space, &config);
+        // 只检查配置中的卷；E:\ 查询失败，本轮跳过
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].volume, "D:\\");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_root_volume_space() {
+        let space = volume_space(Path::new("/")).unwrap();
+        assert!(space.total > 0);
+        assert!(space.free <= space.total);
+        assert_eq!(fixed_volumes()[0], PathBuf::from("/"));
+    }
+}
@@ -0,0 +1,318 @@
+//! MFT 扫描的前置条件检查：Windows、磁盘卷根、NTFS、管理员权限。
+//! 扫描前由 UI 调用以解释复选框为何无效，扫描时用于决定是否直接改用目录遍历。
+
+use std::path::Path;
+
+use ai_disk_domain::{MftAvailability, MftBlocker};
+
+use crate::scanner::normalize_path;
+
+/// 扫描时对 MFT 的决策，见 [`MftPreconditions::route`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MftRoute {
+    /// 不是卷根，本就使用目录遍历，无需说明
+    Walk,
+    /// 满足全部前置条件，尝试 MFT 扫描
+    Attempt,
+    /// 卷根但不能使用 MFT（如 exFAT/FAT32 卷、未提权），直接改用目录遍历并说明原因
+    Fallback(String),
+}
+
+/// 路径所在卷的 MFT 前置条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MftPreconditions {
+    /// 运行在 Windows 上且启用了 `windows-native`
+    pub windows: bool,
+    pub volume_root: bool,
+    /// 卷的文件系统名（如 `NTFS`、`exFAT`）；查询失败时为 None，不据此拒绝
+    pub filesystem: Option<String>,
+    pub elevated: bool,
+}
+
+impl MftPreconditions {
+    /// 探测已规范化的路径
+    pub fn probe(path: &Path) -> Self {
+        #[cfg(all(windows, feature = "windows-native"))]
+        {
+            let volume_root = crate::mft_scan::is_windows_volume_root(path);
+            Self {
+                windows: true,
+                volume_root,
+                filesystem: if volume_root {
+                    filesystem_name(path)
+                } else {
+                    None
+                },
+                elevated: is_elevated::is_elevated(),
+            }
+        }
+        #[cfg(not(all(windows, feature = "windows-native")))]
+        {
+            let _ = path;
+            Self {
+                windows: false,
+                volume_root: false,
+                filesystem: None,
+                elevated: false,
+            }
+        }
+    }
+
+    /// 不能使用 MFT 的原因代码；按用户最容易处理的顺序只给出第一个
+    pub fn blocker(&self) -> Option<MftBlocker> {
+        if !self.windows {
+            if cfg!(windows) {
+                return Some(MftBlocker::NativeBackendDisabled);
+            }
+            return Some(MftBlocker::UnsupportedPlatform);
+        }
+        if !self.volume_root {
+            return Some(MftBlocker::NotVolumeRoot);
+        }
+        if let Some(fs) = &self.filesystem {
+            if !fs.eq_ignore_ascii_case("NTFS") {
+                return Some(MftBlocker::NotNtfs);
+            }
+        }
+        if !self.elevated {
+            return Some(MftBlocker::NotElevated);
+        }
+        None
+    }
+
+    /// 原因代码对应的面向用户的说明
+    pub fn describe(&self, blocker: MftBlocker) -> String {
+        match blocker {
+            MftBlocker::NotRequested => "未勾选「使用 MFT」".to_string(),
+            MftBlocker::PathNotFound => "路径不存在".to_string(),
+            MftBlocker::UnsupportedPlatform => "MFT 扫描仅支持 Windows".to_string(),
+            MftBlocker::NativeBackendDisabled => {
+                "当前构建未启用 Windows 原生扫描（windows-native）".to_string()
+            }
+            MftBlocker::NotVolumeRoot => "MFT 扫描仅适用于磁盘根目录（如 C:\\）".to_string(),
+            MftBlocker::NotNtfs => format!(
+                "该卷的文件系统为 {}，不是 NTFS",
+                self.filesystem.as_deref().unwrap_or("未知")
+            ),
+            MftBlocker::NotElevated => "未以管理员身份运行".to_string(),
+        }
+    }
+
+    /// 请求使用 MFT 时的决策：非 NTFS 卷在打开 `\\.\X:` 之前就回退，不会产生 NTFS 读取错误
+    pub fn route(&self) -> MftRoute {
+        if !self.volume_root {
+            return MftRoute::Walk;
+        }
+        match self.unavailable_reason() {
+            Some(reason) => MftRoute::Fallback(reason),
+            None => MftRoute::Attempt,
+        }
+    }
+
+    /// 不能使用 MFT 的原因；按用户最容易处理的顺序只给出第一个
+    pub fn unavailable_reason(&self) -> Option<String> {
+        self.blocker().map(|b| self.describe(b))
+    }
+
+    pub fn availability(&self) -> MftAvailability {
+        let reason = self.unavailable_reason();
+        MftAvailability {
+            available: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+/// 是否为 FAT 系列文件系统（FAT、FAT32、exFAT）：没有 MFT，也没有备用数据流与安全描述符
+pub fn is_fat_filesystem(name: &str) -> bool {
+    ["FAT", "FAT12", "FAT16", "FAT32", "exFAT"]
+        .iter()
+        .any(|fat| name.eq_ignore_ascii_case(fat))
+}
+
+/// 路径所在卷的文件系统名；非 Windows 平台或查询失败时为 None
+pub fn volume_filesystem(path: &Path) -> Option<String> {
+    #[cfg(all(windows, feature = "windows-native"))]
+    {
+        volume_root_of(path).and_then(|root| filesystem_name(&root))
+    }
+    #[cfg(not(all(windows, feature = "windows-native")))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// 通过 GetVolumePathNameW 获取路径所在卷的根（如 `E:\`，挂载到文件夹的卷为该文件夹）
+#[cfg(all(windows, feature = "windows-native"))]
+fn volume_root_of(path: &Path) -> Option<std::path::PathBuf> {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use windows_sys::Win32::Storage::FileSystem::GetVolumePathNameW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut root = [0u16; 1024];
+    // SAFETY: 路径以 NUL 结尾，输出缓冲区长度与传入一致
+    let ok = unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) };
+    if ok == 0 {
+        return None;
+    }
+    let len = root.iter().position(|&c| c == 0).unwrap_or(root.len());
+    Some(std::ffi::OsString::from_wide(&root[..len]).into())
+}
+
+/// 通过 GetVolumeInformationW 获取卷根的文件系统名
+#[cfg(all(windows, feature = "windows-native"))]
+pub(crate) fn filesystem_name(volume_root: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let mut root = volume_root.to_string_lossy().into_owned();
+    if !root.ends_with('\\') {
+        root.push('\\');
+    }
+    let wide: Vec<u16> = std::ffi::OsStr::new(&root)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut name = [0u16; 32];
+    // SAFETY: 路径以 NUL 结尾；不需要的输出参数传空指针，文件系统名缓冲区长度与传入一致
+    let ok = unsafe {
+        GetVolumeInformationW(
+            wide.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            name.as_mut_ptr(),
+            name.len() as u32,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    Some(String::from_utf16_lossy(&name[..len]))
+}
+
+/// 解释 `path` 能否使用 MFT 扫描，供 UI 在勾选「使用 MFT」时提前提示
+pub fn explain_mft_availability(path: &str) -> MftAvailability {
+    let path_buf = normalize_path(path);
+    match std::fs::canonicalize(&path_buf) {
+        Ok(canonical) => MftPreconditions::probe(&canonical).availability(),
+        Err(_) => MftAvailability {
+            available: false,
+            reason: Some(format!("路径不存在: {}", path)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntfs_root(elevated: bool) -> MftPreconditions {
+        MftPreconditions {
+            windows: true,
+            volume_root: true,
+            filesystem: Some("NTFS".to_string()),
+            elevated,
+        }
+    }
+
+    #[test]
+    fn test_available_when_all_preconditions_hold() {
+        assert_eq!(
+            ntfs_root(true).availability(),
+            MftAvailability {
+                available: true,
+                reason: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_elevated_reason() {
+        let availability = ntfs_root(false).availability();
+        assert!(!availability.available);
+        assert_eq!(availability.reason.as_deref(), Some("未以管理员身份运行"));
+    }
+
+    #[test]
+    fn test_not_volume_root_reason_takes_precedence() {
+        let sub_dir = MftPreconditions {
+            volume_root: false,
+            filesystem: None,
+            ..ntfs_root(false)
+        };
+        assert!(sub_dir.unavailable_reason().unwrap().contains("磁盘根目录"));
+
+        let exfat = MftPreconditions {
+            filesystem: Some("exFAT".to_string()),
+            ..ntfs_root(true)
+        };
+        assert!(exfat.unavailable_reason().unwrap().contains("exFAT"));
+    }
+
+    /// 以模拟的文件系统查询结果构造卷根的前置条件
+    fn volume_with_filesystem(filesystem: Option<&str>) -> MftPreconditions {
+        MftPreconditions {
+            filesystem: filesystem.map(str::to_string),
+            ..ntfs_root(true)
+        }
+    }
+
+    #[test]
+    fn test_non_ntfs_volume_roots_skip_mft() {
+        assert_eq!(
+            volume_with_filesystem(Some("NTFS")).route(),
+            MftRoute::Attempt
+        );
+        // 查询失败时不据此拒绝，仍尝试 MFT
+        assert_eq!(volume_with_filesystem(None).route(), MftRoute::Attempt);
+
+        for fs in ["exFAT", "FAT32", "fat"] {
+            let preconditions = volume_with_filesystem(Some(fs));
+            assert_eq!(preconditions.blocker(), Some(MftBlocker::NotNtfs));
+            assert!(!preconditions.availability().available);
+            assert_eq!(
+                preconditions.route(),
+                MftRoute::Fallback(format!("该卷的文件系统为 {}，不是 NTFS", fs))
+            );
+        }
+        // 非 NTFS 优先于未提权：换成管理员运行也无济于事
+        let unelevated_exfat = MftPreconditions {
+            elevated: false,
+            ..volume_with_filesystem(Some("exFAT"))
+        };
+        assert_eq!(unelevated_exfat.blocker(), Some(MftBlocker::NotNtfs));
+
+        // 子目录不需要说明回退原因
+        let sub_dir = MftPreconditions {
+            volume_root: false,
+            ..volume_with_filesystem(Some("exFAT"))
+        };
+        assert_eq!(sub_dir.route(), MftRoute::Walk);
+    }
+
+    #[test]
+    fn test_fat_filesystem_names() {
+        assert!(is_fat_filesystem("exFAT"));
+        assert!(is_fat_filesystem("FAT32"));
+        assert!(!is_fat_filesystem("NTFS"));
+        assert!(!is_fat_filesystem("ReFS"));
+    }
+
+    #[test]
+    fn test_explain_on_real_paths() {
+        let missing = explain_mft_availability("/nonexistent_xyz_12345_folder");
+        assert!(!missing.available);
+        assert!(missing.reason.unwrap().contains("路径不存在"));
+
+        // 临时目录不会是卷根；非 Windows 平台则直接不支持
+        let dir = tempfile::tempdir().unwrap();
+        let availability = explain_mft_availability(&dir.path().to_string_lossy());
+        assert!(!availability.available);
+        assert!(availability.reason.is_some());
+    }
+}
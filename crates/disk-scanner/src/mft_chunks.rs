@@ -0,0 +1,464 @@
+//! 分块读取 $MFT：按 record 0 中 $MFT 自身的数据运行（data runs）逐块读取，读取中途出错
+//! （坏扇区、卷被卸载）时保留已读部分，由 [`assess_partial`] 按已收字节 / $MFT 总大小决定
+//! 降级为部分结果（写入 `scan_warning`）还是视为失败（调用方回退到目录遍历）。
+//!
+//! 本模块只处理字节与 NTFS 记录格式，不依赖 ntfs-reader，便于在任意平台上测试；
+//! 组装 `Mft` 与枚举见 `mft_scan::load_mft`。
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use ai_disk_common::{format_bytes, ByteStyle, DiskAnalyzerError};
+
+/// 读取中途出错时，已读部分至少要占 $MFT 的比例；低于此比例视为失败
+pub const MFT_PARTIAL_MIN_COVERAGE: f64 = 0.10;
+
+/// 每块读取的字节数
+const CHUNK_BYTES: u64 = 4 << 20;
+
+/// 属性类型：属性列表结束标记、$DATA、$BITMAP
+const ATTR_END: u32 = 0xFFFF_FFFF;
+pub(crate) const ATTR_DATA: u32 = 0x80;
+pub(crate) const ATTR_BITMAP: u32 = 0xB0;
+
+/// 一段数据运行：起始簇号（稀疏运行为 None）与簇数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DataRun {
+    pub lcn: Option<u64>,
+    pub clusters: u64,
+}
+
+/// 记录中未命名属性的内容：常驻时为记录内的字节，非常驻时为数据运行与实际大小
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum AttrContent<'a> {
+    Resident(&'a [u8]),
+    NonResident { runs: Vec<DataRun>, size: u64 },
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn le_unsigned(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+fn le_signed(bytes: &[u8]) -> i64 {
+    let shift = 64 - 8 * bytes.len() as u32;
+    ((le_unsigned(bytes) << shift) as i64) >> shift
+}
+
+/// 在（已做 fixup 的）文件记录中查找指定类型的未命名属性；记录格式不合法或没有该属性时返回 None
+pub(crate) fn find_attribute(record: &[u8], attr_type: u32) -> Option<AttrContent<'_>> {
+    let mut offset = usize::from(u16_at(record, 0x14)?);
+    loop {
+        let ty = u32_at(record, offset)?;
+        if ty == ATTR_END {
+            return None;
+        }
+        let len = u32_at(record, offset + 4)? as usize;
+        if len == 0 {
+            return None;
+        }
+        let attr = record.get(offset..offset + len)?;
+        if ty == attr_type && *attr.get(9)? == 0 {
+            return if attr[8] == 0 {
+                let value_len = u32_at(attr, 0x10)? as usize;
+                let value_offset = usize::from(u16_at(attr, 0x14)?);
+                Some(AttrContent::Resident(
+                    attr.get(value_offset..value_offset + value_len)?,
+                ))
+            } else {
+                let runs_offset = usize::from(u16_at(attr, 0x20)?);
+                Some(AttrContent::NonResident {
+                    runs: parse_data_runs(attr.get(runs_offset..)?)?,
+                    size: u64_at(attr, 0x30)?,
+                })
+            };
+        }
+        offset += len;
+    }
+}
+
+/// 解析非常驻属性的数据运行列表（mapping pairs）；格式不合法时返回 None
+pub(crate) fn parse_data_runs(bytes: &[u8]) -> Option<Vec<DataRun>> {
+    let mut runs = Vec::new();
+    let mut lcn: i64 = 0;
+    let mut pos = 0;
+    loop {
+        let header = *bytes.get(pos)?;
+        if header == 0 {
+            return Some(runs);
+        }
+        let len_size = usize::from(header & 0x0F);
+        let offset_size = usize::from(header >> 4);
+        if len_size == 0 || len_size > 8 || offset_size > 8 {
+            return None;
+        }
+        let clusters = le_unsigned(bytes.get(pos + 1..pos + 1 + len_size)?);
+        let offset_bytes = bytes.get(pos + 1 + len_size..pos + 1 + len_size + offset_size)?;
+        // 偏移为相对上一段起始簇的有符号数；没有偏移的是稀疏运行
+        let run_lcn = if offset_size == 0 {
+            None
+        } else {
+            lcn = lcn.checked_add(le_signed(offset_bytes))?;
+            Some(u64::try_from(lcn).ok()?)
+        };
+        runs.push(DataRun {
+            lcn: run_lcn,
+            clusters,
+        });
+        pos += 1 + len_size + offset_size;
+    }
+}
+
+/// 数据运行是否覆盖属性的全部 `size` 字节；record 0 只含部分运行（其余在属性列表指向的扩展记录中）时为 false
+pub(crate) fn runs_cover(runs: &[DataRun], cluster_size: u64, size: u64) -> bool {
+    let clusters: u64 = runs.iter().map(|r| r.clusters).sum();
+    clusters.saturating_mul(cluster_size) >= size
+}
+
+/// 按数据运行逐块读出属性内容（稀疏运行补零），共 `size` 字节。
+/// 每次产出一块；读取出错时产出该错误后结束
+pub(crate) struct RunChunks<R> {
+    reader: R,
+    runs: std::vec::IntoIter<DataRun>,
+    cluster_size: u64,
+    remaining: u64,
+    /// 当前运行中下一个字节的卷内偏移（稀疏运行为 None）与剩余字节数
+    current: (Option<u64>, u64),
+    failed: bool,
+}
+
+impl<R: Read + Seek> RunChunks<R> {
+    pub(crate) fn new(reader: R, runs: Vec<DataRun>, cluster_size: u64, size: u64) -> Self {
+        Self {
+            reader,
+            runs: runs.into_iter(),
+            cluster_size,
+            remaining: size,
+            current: (None, 0),
+            failed: false,
+        }
+    }
+
+    fn read_chunk(&mut self, offset: Option<u64>, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0u8; len];
+        if let Some(offset) = offset {
+            self.reader.seek(SeekFrom::Start(offset))?;
+            self.reader.read_exact(&mut buf)?;
+        }
+        Ok(buf)
+    }
+}
+
+impl<R: Read + Seek> Iterator for RunChunks<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.remaining == 0 {
+            return None;
+        }
+        while self.current.1 == 0 {
+            let Some(run) = self.runs.next() else {
+                self.failed = true;
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "data runs end before the attribute size",
+                )));
+            };
+            self.current = (
+                run.lcn.map(|lcn| lcn * self.cluster_size),
+                run.clusters * self.cluster_size,
+            );
+        }
+        let (offset, left) = self.current;
+        let len = CHUNK_BYTES.min(left).min(self.remaining);
+        match self.read_chunk(offset, len as usize) {
+            Ok(chunk) => {
+                self.current = (offset.map(|o| o + len), left - len);
+                self.remaining -= len;
+                Some(Ok(chunk))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// 消费端收到的数据与中途的错误（完整读取时为 None）
+pub(crate) struct PartialRead {
+    pub data: Vec<u8>,
+    pub error: Option<io::Error>,
+}
+
+/// 依次接收数据块直到结束或出错，保留出错前已收到的部分
+pub(crate) fn collect_chunks(chunks: impl IntoIterator<Item = io::Result<Vec<u8>>>) -> PartialRead {
+    let mut data = Vec::new();
+    for chunk in chunks {
+        match chunk {
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(e) => {
+                return PartialRead {
+                    data,
+                    error: Some(e),
+                }
+            }
+        }
+    }
+    PartialRead { data, error: None }
+}
+
+/// 判断已读的 $MFT 能否使用：数据截到整条记录；完整读取时无警告；中途出错但已读比例不低于
+/// `min_coverage` 时返回部分数据与 `scan_warning` 文本（含错误与覆盖率）；否则返回错误
+pub(crate) fn assess_partial(
+    read: PartialRead,
+    total: u64,
+    record_size: usize,
+    min_coverage: f64,
+) -> Result<(Vec<u8>, Option<String>), DiskAnalyzerError> {
+    let PartialRead { mut data, error } = read;
+    data.truncate(total as usize);
+    data.truncate(data.len() - data.len() % record_size.max(1));
+    let Some(error) = error else {
+        return Ok((data, None));
+    };
+    let received = data.len() as u64;
+    let coverage = if total == 0 {
+        0.0
+    } else {
+        received as f64 / total as f64
+    };
+    let detail = format!(
+        "{}，已读取约 {:.1}% 的 $MFT（{} / {}）",
+        error,
+        coverage * 100.0,
+        format_bytes(received, ByteStyle::Binary),
+        format_bytes(total, ByteStyle::Binary)
+    );
+    if coverage < min_coverage {
+        return Err(DiskAnalyzerError::Io(io::Error::new(
+            error.kind(),
+            format!("MFT read I/O error: {}", detail),
+        )));
+    }
+    Ok((
+        data,
+        Some(format!(
+            "读取 MFT 中途出错（{}），结果只包含已读取的记录，部分文件未列出",
+            detail
+        )),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const RECORD: usize = 1024;
+
+    /// 模拟的数据块流：先产出 `ok` 块（每块两条记录），再产出一个读取错误
+    fn chunk_stream(ok: usize) -> Vec<io::Result<Vec<u8>>> {
+        let mut chunks: Vec<io::Result<Vec<u8>>> =
+            (0..ok).map(|i| Ok(vec![i as u8; 2 * RECORD])).collect();
+        chunks.push(Err(io::Error::other("device not ready")));
+        chunks
+    }
+
+    #[test]
+    fn test_parses_data_runs() {
+        // 0x30 簇 @ 0x10000；0x10 簇，相对偏移 -0x100；0x08 簇稀疏
+        let bytes = [
+            0x31, 0x30, 0x00, 0x00, 0x01, 0x21, 0x10, 0x00, 0xFF, 0x01, 0x08, 0x00,
+        ];
+        assert_eq!(
+            parse_data_runs(&bytes).unwrap(),
+            vec![
+                DataRun {
+                    lcn: Some(0x10000),
+                    clusters: 0x30
+                },
+                DataRun {
+                    lcn: Some(0xFF00),
+                    clusters: 0x10
+                },
+                DataRun {
+                    lcn: None,
+                    clusters: 0x08
+                },
+            ]
+        );
+        assert_eq!(parse_data_runs(&[0x31, 0x30]), None);
+    }
+
+    #[test]
+    fn test_finds_unnamed_attributes_in_record() {
+        let mut record = vec![0u8; RECORD];
+        record[0..4].copy_from_slice(b"FILE");
+        record[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        // 常驻 $BITMAP，8 字节值
+        let bitmap = 0x38;
+        record[bitmap..bitmap + 4].copy_from_slice(&ATTR_BITMAP.to_le_bytes());
+        record[bitmap + 4..bitmap + 8].copy_from_slice(&0x20u32.to_le_bytes());
+        record[bitmap + 0x10..bitmap + 0x14].copy_from_slice(&8u32.to_le_bytes());
+        record[bitmap + 0x14..bitmap + 0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        record[bitmap + 0x18..bitmap + 0x20].copy_from_slice(&[0xFF; 8]);
+        // 非常驻 $DATA，实际大小 0x40000，一段运行
+        let data = bitmap + 0x20;
+        record[data..data + 4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        record[data + 4..data + 8].copy_from_slice(&0x48u32.to_le_bytes());
+        record[data + 8] = 1;
+        record[data + 0x20..data + 0x22].copy_from_slice(&0x40u16.to_le_bytes());
+        record[data + 0x30..data + 0x38].copy_from_slice(&0x40000u64.to_le_bytes());
+        record[data + 0x40..data + 0x44].copy_from_slice(&[0x11, 0x40, 0x20, 0x00]);
+        record[data + 0x48..data + 0x4C].copy_from_slice(&ATTR_END.to_le_bytes());
+
+        assert_eq!(
+            find_attribute(&record, ATTR_BITMAP),
+            Some(AttrContent::Resident(&[0xFF; 8]))
+        );
+        let Some(AttrContent::NonResident { runs, size }) = find_attribute(&record, ATTR_DATA)
+        else {
+            panic!("$DATA not found");
+        };
+        assert_eq!(size, 0x40000);
+        assert_eq!(
+            runs,
+            vec![DataRun {
+                lcn: Some(0x20),
+                clusters: 0x40
+            }]
+        );
+        assert!(runs_cover(&runs, 4096, size));
+        assert!(!runs_cover(&runs, 4096, size + 1));
+        assert_eq!(find_attribute(&record, 0x30), None);
+    }
+
+    /// 读到 `limit` 字节之后的位置即报错的卷
+    struct FailingVolume {
+        inner: Cursor<Vec<u8>>,
+        limit: u64,
+    }
+
+    impl Read for FailingVolume {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.inner.position() + buf.len() as u64 > self.limit {
+                return Err(io::Error::other("bad sector"));
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for FailingVolume {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_run_chunks_follow_runs_and_stop_at_error() {
+        let cluster = 4096u64;
+        let volume: Vec<u8> = (0..16 * cluster).map(|i| (i / cluster) as u8).collect();
+        let runs = vec![
+            DataRun {
+                lcn: Some(3),
+                clusters: 2,
+            },
+            DataRun {
+                lcn: None,
+                clusters: 1,
+            },
+            DataRun {
+                lcn: Some(10),
+                clusters: 2,
+            },
+        ];
+        let whole = collect_chunks(RunChunks::new(
+            Cursor::new(volume.clone()),
+            runs.clone(),
+            cluster,
+            4 * cluster + 100,
+        ));
+        assert!(whole.error.is_none());
+        let clusters: Vec<u8> = whole.data.chunks(cluster as usize).map(|c| c[0]).collect();
+        assert_eq!(clusters, vec![3, 4, 0, 10, 11]);
+        assert_eq!(whole.data.len() as u64, 4 * cluster + 100);
+
+        let failing = FailingVolume {
+            inner: Cursor::new(volume),
+            limit: 10 * cluster,
+        };
+        let partial = collect_chunks(RunChunks::new(failing, runs, cluster, 5 * cluster));
+        assert_eq!(partial.error.unwrap().to_string(), "bad sector");
+        assert_eq!(partial.data.len() as u64, 3 * cluster);
+    }
+
+    #[test]
+    fn test_partial_read_keeps_records_and_warns() {
+        let total = (20 * RECORD) as u64;
+        let read = collect_chunks(chunk_stream(5));
+        let (data, warning) =
+            assess_partial(read, total, RECORD, MFT_PARTIAL_MIN_COVERAGE).unwrap();
+        assert_eq!(data.len(), 10 * RECORD);
+        assert_eq!(data[9 * RECORD], 4);
+        assert_eq!(
+            warning.unwrap(),
+            "读取 MFT 中途出错（device not ready，已读取约 50.0% 的 $MFT（10 KiB / 20 KiB）），\
+             结果只包含已读取的记录，部分文件未列出"
+        );
+    }
+
+    #[test]
+    fn test_partial_read_drops_incomplete_record() {
+        let read = PartialRead {
+            data: vec![1; 3 * RECORD + 100],
+            error: Some(io::Error::other("device not ready")),
+        };
+        let (data, warning) = assess_partial(read, (4 * RECORD) as u64, RECORD, 0.1).unwrap();
+        assert_eq!(data.len(), 3 * RECORD);
+        assert!(warning.unwrap().contains("75.0%"));
+    }
+
+    #[test]
+    fn test_low_coverage_is_an_error() {
+        let total = (30 * RECORD) as u64;
+        let err = assess_partial(
+            collect_chunks(chunk_stream(1)),
+            total,
+            RECORD,
+            MFT_PARTIAL_MIN_COVERAGE,
+        )
+        .unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("device not ready"), "{}", msg);
+        assert!(msg.contains("6.7%"), "{}", msg);
+    }
+
+    #[test]
+    fn test_complete_read_has_no_warning() {
+        let mut chunks = chunk_stream(3);
+        chunks.pop();
+        let (data, warning) = assess_partial(
+            collect_chunks(chunks),
+            (6 * RECORD) as u64,
+            RECORD,
+            MFT_PARTIAL_MIN_COVERAGE,
+        )
+        .unwrap();
+        assert_eq!(data.len(), 6 * RECORD);
+        assert!(warning.is_none());
+    }
+}
@@ -2,31 +2,64 @@
 //! volume `\\.\X:`, read $MFT into memory, and enumerate files with path cache.
 //! Requires admin (elevated) privileges.
 //!
-//! **当前限制**：ntfs-reader 的 `Mft::new(volume)` 会一次性将整个 $MFT 读入内存，因此
-//! “volume opened” 与 “MFT loaded” 之间会有较长等待；真正的边读边处理需自实现分块读 $MFT
-//! 或改用支持流式读取的库。
+//! **分块读取与降级**：完整扫描按 record 0 中 $MFT 的数据运行分块读取（见 `mft_chunks` 模块），
+//! 读取中途出错（坏扇区、卷被卸载）时用已读的记录建树，并在 `scan_warning` 中注明错误与覆盖率；
+//! 已读部分不足 [`MFT_PARTIAL_MIN_COVERAGE`] 时才返回错误（调用方回退到目录遍历）。
+//! 整个 $MFT 仍需读入内存后才开始枚举，因此 “volume opened” 与 “MFT loaded” 之间会有较长等待。
 //!
-//! **阶段耗时**：设置环境变量 `MFT_TIMING=1` 后扫描会打印三阶段耗时（获取 MFT / 枚举 / 建树）
+//! **阶段耗时**：设置环境变量 `MFT_TIMING=1` 后扫描会以 tracing 事件（target `mft_timing`）记录三阶段耗时（获取 MFT / 枚举 / 建树）
 //! 及可并行化建议。参见 tests/scan_timing.rs 中的运行示例。
 //!
+//! **内存**：枚举结果存入 [`RecordArena`]（名称 + 父记录下标，不保存完整路径），
+//! 建树时同步剪枝，只为返回给前端的节点拼出路径。
+//!
+//! **命名数据流与稀疏文件**：记录的 `size` 只取未命名 $DATA；命名 $DATA（备用数据流，如
+//! Zone.Identifier）的大小单独累加到 `FileNode::streams_size`，仅在 `include_streams` 时计入大小。
+//! 稀疏文件由 $STANDARD_INFORMATION 的 FILE_ATTRIBUTE_SPARSE_FILE 标出（`FileAttributes::sparse`）。
+//!
+//! **压缩与重复数据删除**：未命名 $DATA 带压缩或稀疏标志时（NTFS 压缩；重复数据删除后的文件为稀疏的
+//! 重解析点），非常驻属性头中的 compressed_size 即实际分配的字节数，与逻辑大小之差累加为
+//! `FileNode::physical_size` 与 `ScanResult::physical_usage`。
+//!
 //! **仅要前 N 大文件**：使用 `scan_volume_mft_top_files(path, n, progress)`，只做枚举 + 最小堆，
 //! 不建树，默认 N=100 时显著省时省内存。
 
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::BinaryHeap;
+use std::num::NonZeroU64;
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
-use ai_disk_common::DiskAnalyzerError;
-use ai_disk_domain::{FileNode, ScanResult, TopFileEntry};
+use ai_disk_common::{
+    format_bytes, metrics, rate_per_sec, ByteStyle, DiskAnalyzerError, ProgressPhase, LABEL_ALL,
+    METRIC_MFT_RECORDS_PER_SEC,
+};
+use ai_disk_domain::{
+    FileAttributes, FileNode, PhysicalUsage, ScanResult, SizeSource, TopFileEntry,
+};
+use ntfs_reader::api::NtfsAttributeType;
+use ntfs_reader::attribute::NtfsAttribute;
 use ntfs_reader::errors::NtfsReaderError;
+use ntfs_reader::file::NtfsFile;
 use ntfs_reader::file_info::{FileInfo, HashMapCache};
 use ntfs_reader::mft::Mft;
 use ntfs_reader::volume::Volume;
-use rayon::prelude::*;
 
-use crate::scanner::{normalize_path, ProgressCb, ProgressCbArc, SHALLOW_DIR_NAMES};
+use crate::display_path::DisplayPath;
+use crate::mft_availability::filesystem_name;
+pub use crate::mft_chunks::MFT_PARTIAL_MIN_COVERAGE;
+use crate::mft_chunks::{
+    assess_partial, collect_chunks, find_attribute, runs_cover, AttrContent, RunChunks,
+    ATTR_BITMAP, ATTR_DATA,
+};
+use crate::node::finalize_tree;
+use crate::parallel::map_collect;
+use crate::recent_activity::{now_secs, recent_activity_from_arena};
+use crate::record_arena::{RecordArena, RecordArenaBuilder, RecordMeta, ROOT};
+use crate::scanner::{
+    is_collapsed_dir_name, nonzero_unix_secs, normalize_path, ProgressCb, ProgressCbArc,
+};
 
 /// 通过 Windows API GetDiskFreeSpaceExW 获取卷总容量与剩余空间（字节）。
 /// 仅 Windows 有效；path 为卷上任意路径（如 "C:\" 或 "C:\Users"）。
@@ -79,7 +112,9 @@ fn drive_letter_from_volume_root(volume_root: &Path) -> Option<String> {
 fn to_disk_analyzer_error(e: NtfsReaderError) -> DiskAnalyzerError {
     let msg = match &e {
         NtfsReaderError::ElevationError => {
-            "NTFS volume access requires elevated (admin) privileges".to_string()
+            return DiskAnalyzerError::NeedsElevation(
+                "NTFS volume access requires elevated (admin) privileges".to_string(),
+            );
         }
         NtfsReaderError::IOError(io) => format!("MFT read I/O error: {}", io),
         _ => format!("MFT error: {}", e),
@@ -87,6 +122,59 @@ fn to_disk_analyzer_error(e: NtfsReaderError) -> DiskAnalyzerError {
     DiskAnalyzerError::Io(std::io::Error::new(std::io::ErrorKind::Other, msg))
 }
 
+/// 打开卷时视为暂时性、值得重试的 Windows 错误码：
+/// ERROR_SHARING_VIOLATION（备份/杀毒软件占用）、ERROR_NOT_READY（刚从睡眠唤醒）、ERROR_BUSY
+const TRANSIENT_OPEN_ERRORS: &[i32] = &[32, 21, 170];
+/// 首次打开失败后的重试等待时间，依次使用，最多重试 3 次
+const OPEN_RETRY_BACKOFF_MS: &[u64] = &[200, 500, 1000];
+
+fn is_transient_open_error(e: &NtfsReaderError) -> bool {
+    match e {
+        NtfsReaderError::IOError(io) => io
+            .raw_os_error()
+            .is_some_and(|code| TRANSIENT_OPEN_ERRORS.contains(&code)),
+        _ => false,
+    }
+}
+
+/// 打开卷（`Volume::new` 等），遇到暂时性错误时按退避时间重试，每次重试前上报进度；
+/// 拒绝访问等其他错误立即返回。`sleep` 可注入以便测试
+fn open_with_retry<T>(
+    mut open: impl FnMut() -> Result<T, NtfsReaderError>,
+    mut sleep: impl FnMut(std::time::Duration),
+    progress: Option<&ProgressCb>,
+) -> Result<T, NtfsReaderError> {
+    let mut backoff = OPEN_RETRY_BACKOFF_MS.iter();
+    loop {
+        let err = match open() {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        let delay_ms = match backoff.next() {
+            Some(&ms) if is_transient_open_error(&err) => ms,
+            _ => return Err(err),
+        };
+        let attempt = OPEN_RETRY_BACKOFF_MS.len() - backoff.len();
+        tracing::warn!(error = %err, attempt, delay_ms, "opening volume failed, retrying");
+        if let Some(cb) = progress {
+            // 具体原因与重试次数已写入日志，进度中只给出可本地化的阶段
+            cb(0, &ProgressPhase::MftVolumeBusy.marker());
+        }
+        sleep(std::time::Duration::from_millis(delay_ms));
+    }
+}
+
+/// 非 NTFS 卷（exFAT、FAT32 等）没有 $MFT：在打开卷之前拒绝，避免读取时报出令人困惑的 NTFS 错误。
+/// 查询失败时不据此拒绝
+fn ensure_ntfs(volume_root: &Path) -> Result<(), DiskAnalyzerError> {
+    match filesystem_name(volume_root) {
+        Some(fs) if !fs.eq_ignore_ascii_case("NTFS") => Err(DiskAnalyzerError::InvalidPath(
+            format!("not an NTFS volume ({})", fs),
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Normalize path from ntfs-reader (e.g. `\\.\F:\dir\file` 或 `C:\dir\file`) to `F:\dir\file`，
 /// 保证盘符后必有反斜杠以便正确做父路径切分（如 `C:\Windows` 的 parent 为 `C:\`）。
 fn normalize_ntfs_path(path_str: &str, drive: &str) -> String {
@@ -134,6 +222,8 @@ const MAX_DEPTH_RETURN: usize = 6;
 const MAX_CHILDREN_PER_DIR_RETURN: usize = 250;
 /// 进度回调间隔（增大以略减 IPC 次数）
 const PROGRESS_EVERY: u64 = 10_000;
+/// 枚举记录时每隔多少条检查一次是否已暂停
+const PAUSE_CHECK_EVERY: u64 = 1_000;
 /// build_tree 阶段每构建多少节点上报一次进度
 const BUILD_TREE_PROGRESS_EVERY: u64 = 10_000;
 /// 供前端摘要与 AI 分析的前 N 大文件数量
@@ -204,18 +294,26 @@ pub fn scan_volume_mft_top_files(
             "not a volume root".to_string(),
         ));
     }
+    ensure_ntfs(&path_buf)?;
 
     let drive = drive_letter_from_volume_root(&path_buf).ok_or_else(|| {
         DiskAnalyzerError::InvalidPath("cannot get drive letter from volume root".to_string())
     })?;
 
     let volume_path = format!(r"\\.\{}:", drive);
-    let volume = Volume::new(volume_path.as_str()).map_err(to_disk_analyzer_error)?;
+    let volume = open_with_retry(
+        || Volume::new(volume_path.as_str()),
+        std::thread::sleep,
+        progress,
+    )
+    .map_err(to_disk_analyzer_error)?;
     let mft = Mft::new(volume).map_err(to_disk_analyzer_error)?;
 
     let vol_trim_for_filter = format!("{}:", drive);
     let cap = n.saturating_add(1).min(1_000_000);
-    let mut heap: BinaryHeap<Reverse<(u64, String, Option<u64>)>> = BinaryHeap::with_capacity(cap);
+    // (大小, 路径, (修改, 创建, 访问时间), 属性位)
+    type Candidate = (u64, String, (Option<u64>, Option<u64>, Option<u64>), u32);
+    let mut heap: BinaryHeap<Reverse<Candidate>> = BinaryHeap::with_capacity(cap);
     let mut cache = HashMapCache::default();
     let counter = AtomicU64::new(0);
 
@@ -229,14 +327,15 @@ pub fn scan_volume_mft_top_files(
         if !path_under_volume_ascii(&full_path, &vol_trim_for_filter) {
             return;
         }
-        let modified = info.modified.and_then(|t| {
-            let s = t.unix_timestamp();
-            if s > 0 {
-                Some(s as u64)
-            } else {
-                None
-            }
-        });
+        let modified = info
+            .modified
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
+        let created = info
+            .created
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
+        let accessed = info
+            .accessed
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
         let c = counter.fetch_add(1, Ordering::Relaxed);
         if c > 0 && c % PROGRESS_EVERY == 0 {
             if let Some(ref cb) = progress {
@@ -244,7 +343,12 @@ pub fn scan_volume_mft_top_files(
             }
         }
         let size = info.size;
-        heap.push(Reverse((size, full_path, modified)));
+        heap.push(Reverse((
+            size,
+            full_path,
+            (modified, created, accessed),
+            RecordAttributes::read(file).bits,
+        )));
         while heap.len() > n {
             heap.pop();
         }
@@ -256,78 +360,144 @@ pub fn scan_volume_mft_top_files(
 
     let mut list: Vec<_> = heap
         .into_iter()
-        .map(|Reverse((size, path, modified))| TopFileEntry {
-            path,
-            size,
-            modified,
-        })
+        .map(
+            |Reverse((size, path, (modified, created, accessed), bits))| TopFileEntry {
+                path: DisplayPath::new(&path).into_string(),
+                size,
+                modified,
+                created: created.and_then(NonZeroU64::new),
+                accessed: accessed.and_then(NonZeroU64::new),
+                attributes: FileAttributes::from_windows_bits(bits).non_empty(),
+                owner: None,
+            },
+        )
         .collect();
     list.sort_by(|a, b| b.size.cmp(&a.size));
     Ok(list)
 }
 
-/// Single MFT-derived record for tree building.
-struct MftRecord {
-    full_path: String,
-    size: u64,
-    is_dir: bool,
-    modified: Option<u64>,
+/// 从 MFT 记录的属性中读取的信息
+#[derive(Debug, Default)]
+struct RecordAttributes {
+    /// $STANDARD_INFORMATION 的 FILE_ATTRIBUTE_* 位（隐藏、系统、只读、稀疏等）
+    bits: u32,
+    /// 命名 $DATA 属性（备用数据流）的大小合计
+    streams_size: u64,
+    /// 未命名 $DATA 因压缩或稀疏少占用的字节数
+    saved_bytes: u64,
 }
 
-/// 从直接大小与子索引一次性汇总递归大小（避免枚举时每文件 O(深度) 的祖先更新）
-fn compute_recursive_sizes(
-    records: &[MftRecord],
-    child_index: &HashMap<String, Vec<usize>>,
-    direct_sizes: &HashMap<String, u64>,
-    volume_root_trim: &str,
-    volume_root_key: &str,
-) -> HashMap<String, u64> {
-    let mut paths: Vec<String> = records
-        .iter()
-        .map(|r| r.full_path.trim_end_matches('\\').to_string())
-        .collect();
-    if !paths
-        .iter()
-        .any(|p| p.eq_ignore_ascii_case(volume_root_trim))
+impl RecordAttributes {
+    fn read(file: &NtfsFile) -> Self {
+        let mut out = Self::default();
+        file.attributes(|att| {
+            let type_id = att.header.type_id;
+            if type_id == NtfsAttributeType::StandardInformation as u32 {
+                out.bits = att.as_standard_info().file_attributes;
+            } else if type_id == NtfsAttributeType::Data as u32 && att.header.name_length > 0 {
+                out.streams_size = out.streams_size.saturating_add(data_size(&att));
+            } else if type_id == NtfsAttributeType::Data as u32 {
+                out.saved_bytes = saved_bytes(&att);
+            }
+        });
+        out
+    }
+}
+
+/// 属性头 flags 中的压缩与稀疏标志
+const ATTRIBUTE_FLAG_COMPRESSED: u16 = 0x0001;
+const ATTRIBUTE_FLAG_SPARSE: u16 = 0x8000;
+
+/// 压缩或稀疏的非常驻 $DATA 实际分配的字节数（compressed_size）比逻辑大小少的部分；
+/// 常驻属性与普通属性为 0。只有首个区段（lowest_vcn 为 0）的属性头带有这两个大小
+fn saved_bytes(att: &NtfsAttribute) -> u64 {
+    if att.header.is_non_resident == 0
+        || att.header.flags & (ATTRIBUTE_FLAG_COMPRESSED | ATTRIBUTE_FLAG_SPARSE) == 0
     {
-        paths.push(volume_root_trim.to_string());
-    }
-    paths.sort();
-    paths.dedup();
-    paths.sort_by_cached_key(|p| std::cmp::Reverse(p.matches('\\').count()));
-    let mut recursive_sizes: HashMap<String, u64> = HashMap::new();
-    for path in paths {
-        let direct = direct_sizes.get(&path).copied().unwrap_or(0);
-        let child_sum: u64 = {
-            let key = if path.eq_ignore_ascii_case(volume_root_trim) {
-                volume_root_key
-            } else {
-                &path
-            };
-            child_index
-                .get(key)
-                .map(|indices| {
-                    indices
-                        .iter()
-                        .map(|&i| {
-                            let c = records[i].full_path.trim_end_matches('\\').to_string();
-                            recursive_sizes.get(&c).copied().unwrap_or(0)
-                        })
-                        .sum()
-                })
-                .unwrap_or(0)
-        };
-        recursive_sizes.insert(path, direct.saturating_add(child_sum));
+        return 0;
+    }
+    let header = att.get_nonresident();
+    if header.lowest_vcn != 0 {
+        return 0;
     }
-    recursive_sizes
+    header.data_size.saturating_sub(header.compressed_size)
+}
+
+/// $DATA 属性的逻辑大小：常驻属性为值长度，非常驻属性取头部的 data_size
+/// （稀疏流同样是逻辑大小，未分配的区段不占簇）
+fn data_size(att: &NtfsAttribute) -> u64 {
+    if att.header.is_non_resident == 0 {
+        att.get_resident().value_length as u64
+    } else {
+        att.get_nonresident().data_size
+    }
+}
+
+/// 按 record 0 中的数据运行分块读入 $MFT 与其位图，组装 `Mft`。
+/// 读取 $MFT 中途出错时保留已读的整条记录，第二个返回值为写入 `scan_warning` 的说明；
+/// 已读比例过低时返回错误。record 0 中的数据运行不完整（碎片极多的 $MFT 使用属性列表）时改用
+/// `Mft::new` 整体读取
+fn load_mft(volume: Volume) -> Result<(Mft, Option<String>), DiskAnalyzerError> {
+    let mut reader = ntfs_reader::aligned_reader::open_volume(volume.path.as_path())
+        .map_err(|e| to_disk_analyzer_error(e.into()))?;
+    let record_size = volume.file_record_size as usize;
+    let cluster_size = volume.cluster_size as u64;
+    let record0 = Mft::get_record_fs(&mut reader, record_size, volume.mft_position)
+        .map_err(to_disk_analyzer_error)?;
+    let (runs, total) = match find_attribute(&record0, ATTR_DATA) {
+        Some(AttrContent::NonResident { runs, size }) if runs_cover(&runs, cluster_size, size) => {
+            (runs, size)
+        }
+        _ => {
+            tracing::warn!("$MFT data runs not complete in record 0, reading it in one piece");
+            return Ok((Mft::new(volume).map_err(to_disk_analyzer_error)?, None));
+        }
+    };
+    // 位图很小，读取失败时整体失败
+    let bitmap = match find_attribute(&record0, ATTR_BITMAP) {
+        Some(AttrContent::Resident(bytes)) => bytes.to_vec(),
+        Some(AttrContent::NonResident { runs, size }) => {
+            let read = collect_chunks(RunChunks::new(&mut reader, runs, cluster_size, size));
+            if let Some(e) = read.error {
+                return Err(to_disk_analyzer_error(NtfsReaderError::IOError(e)));
+            }
+            read.data
+        }
+        None => {
+            tracing::warn!("$MFT bitmap not found in record 0, reading it in one piece");
+            return Ok((Mft::new(volume).map_err(to_disk_analyzer_error)?, None));
+        }
+    };
+
+    let read = collect_chunks(RunChunks::new(&mut reader, runs, cluster_size, total));
+    let (mut data, warning) = assess_partial(read, total, record_size, MFT_PARTIAL_MIN_COVERAGE)?;
+    // 与 Mft::new 相同，逐条做 fixup；fixup 失败（记录损坏）的记录抹掉签名，枚举时跳过
+    for (number, record) in data.chunks_exact_mut(record_size).enumerate() {
+        if NtfsFile::is_valid(record) && Mft::fixup_record(number as u64, record).is_err() {
+            record[..4].fill(0);
+        }
+    }
+    let max_record = (data.len() / record_size) as u64;
+    Ok((
+        Mft {
+            volume,
+            data,
+            bitmap,
+            max_record,
+        },
+        warning,
+    ))
 }
 
 /// Scan volume root via MFT using ntfs-reader (Everything-style). Opens `\\.\X:`,
 /// reads $MFT into memory, iterates files with path cache, then builds tree.
+/// 读取 $MFT 中途出错时用已读的记录建树，并在 `scan_warning` 中注明错误与覆盖率（见 [`load_mft`]）。
+/// `include_streams` 为 true 时命名数据流的大小计入各节点大小与 `total_size`。
 pub fn scan_volume_mft(
     path: &str,
     progress: Option<ProgressCbArc>,
     shallow_dirs: bool,
+    include_streams: bool,
 ) -> Result<ScanResult, DiskAnalyzerError> {
     let start = Instant::now();
     let path_buf = normalize_path(path);
@@ -344,6 +514,7 @@ pub fn scan_volume_mft(
             "not a volume root".to_string(),
         ));
     }
+    ensure_ntfs(&path_buf)?;
 
     let volume_root_str = path_buf
         .to_string_lossy()
@@ -359,31 +530,38 @@ pub fn scan_volume_mft(
         DiskAnalyzerError::InvalidPath("cannot get drive letter from volume root".to_string())
     })?;
 
-    eprintln!(
-        "[scan:mft] starting MFT full scan for volume {} (drive {})",
-        path_buf.display(),
-        drive
+    tracing::info!(
+        volume = %path_buf.display(),
+        drive = %drive,
+        "starting MFT full scan"
     );
     if let Some(ref cb) = progress {
-        cb(0, "[scan:mft] opening volume...");
+        cb(0, &ProgressPhase::MftOpeningVolume.marker());
     }
     let volume_path = format!(r"\\.\{}:", drive);
-    let volume_root_trim = format!("{}:", drive);
     let volume_root_key = format!(r"{}:\", drive);
-    // 使用上游 ntfs-reader API：Mft::new 一次性加载 $MFT，再 iterate_files 枚举。
-    let volume = Volume::new(volume_path.as_str()).map_err(to_disk_analyzer_error)?;
-    eprintln!("[scan:mft] volume opened: {} bytes", volume.volume_size);
-    let mft = Mft::new(volume).map_err(to_disk_analyzer_error)?;
-    eprintln!(
-        "[scan:mft] MFT loaded into memory, max_records={}",
-        mft.max_record
+    // 分块读入 $MFT（见 load_mft），再用上游 ntfs-reader 的 iterate_files 枚举。
+    let volume = open_with_retry(
+        || Volume::new(volume_path.as_str()),
+        std::thread::sleep,
+        progress.as_deref(),
+    )
+    .map_err(to_disk_analyzer_error)?;
+    tracing::info!(
+        volume_size = volume.volume_size,
+        volume = %format_bytes(volume.volume_size, ByteStyle::Binary),
+        "volume opened"
     );
+    let (mft, scan_warning) = load_mft(volume)?;
+    if let Some(warning) = &scan_warning {
+        tracing::warn!(warning = %warning, "MFT read incomplete, building tree from partial records");
+    }
+    tracing::info!(max_records = mft.max_record, "MFT loaded into memory");
     let vol_trim_for_filter = format!("{}:", drive);
-    let mut records: Vec<MftRecord> = Vec::with_capacity(2_000_000);
-    let mut child_index: HashMap<String, Vec<usize>> = HashMap::new();
-    let mut direct_sizes: HashMap<String, u64> = HashMap::new();
+    let mut builder = RecordArenaBuilder::new(&volume_root_key, '\\');
     let mut cache = HashMapCache::default();
     let counter = AtomicU64::new(0);
+    let t_iterate = Instant::now();
     mft.iterate_files(|file| {
         let info = FileInfo::with_cache(&mft, file, &mut cache);
         let path_str = info.path.to_string_lossy();
@@ -391,52 +569,54 @@ pub fn scan_volume_mft(
         if !path_under_volume_ascii(&full_path, &vol_trim_for_filter) {
             return;
         }
-        let modified = info.modified.and_then(|t| {
-            let s = t.unix_timestamp();
-            if s > 0 {
-                Some(s as u64)
-            } else {
-                None
-            }
-        });
+        let modified = info
+            .modified
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
+        let created = info
+            .created
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
+        let accessed = info
+            .accessed
+            .and_then(|t| nonzero_unix_secs(t.unix_timestamp()));
         let c = counter.fetch_add(1, Ordering::Relaxed);
+        if c % PAUSE_CHECK_EVERY == 0 {
+            crate::pause::checkpoint();
+        }
         if c > 0 && c % PROGRESS_EVERY == 0 {
             if let Some(ref cb) = progress {
                 cb(c, &full_path);
             }
         }
-        records.push(MftRecord {
-            full_path: full_path.clone(),
-            size: info.size,
-            is_dir: info.is_directory,
-            modified,
-        });
-        let idx = records.len() - 1;
-        let path_trim = full_path.trim_end_matches('\\');
-        if !path_trim.eq_ignore_ascii_case(&volume_root_trim) {
-            if let Some(i) = full_path.rfind('\\') {
-                let parent = full_path[..i].to_string();
-                child_index.entry(parent).or_default().push(idx);
-            }
-        }
-        let s = info.size;
-        direct_sizes
-            .entry(path_trim.to_string())
-            .and_modify(|v| *v = v.saturating_add(s))
-            .or_insert(s);
+        let record = RecordAttributes::read(file);
+        let size = if include_streams {
+            info.size.saturating_add(record.streams_size)
+        } else {
+            info.size
+        };
+        builder.insert(
+            &full_path,
+            RecordMeta {
+                size,
+                is_dir: info.is_directory,
+                modified,
+                created: created.and_then(NonZeroU64::new),
+                accessed: accessed.and_then(NonZeroU64::new),
+                attributes: FileAttributes::from_windows_bits(record.bits).non_empty(),
+                streams_size: record.streams_size,
+                saved_bytes: record.saved_bytes,
+            },
+        );
     });
     let n_records = counter.load(Ordering::Relaxed);
+    if let Some(rate) = rate_per_sec(n_records, t_iterate.elapsed()) {
+        metrics().observe(METRIC_MFT_RECORDS_PER_SEC, LABEL_ALL, rate);
+    }
     if let Some(ref cb) = progress {
         cb(n_records, &volume_root_str);
     }
     let _volume = mft.volume.clone();
-    let recursive_sizes = compute_recursive_sizes(
-        &records,
-        &child_index,
-        &direct_sizes,
-        &volume_root_trim,
-        &volume_root_key,
-    );
+    let arena = builder.finish();
+    let recursive_sizes = arena.recursive_sizes();
     let t_after_mft_read = Instant::now();
     let t_after_iterate = t_after_mft_read;
 
@@ -448,57 +628,41 @@ pub fn scan_volume_mft(
         .unwrap_or_else(|| path.to_string());
     let root_path_str = path_buf.display().to_string();
 
-    let (root, file_count, total_size) = build_tree_from_mft_records(
-        &records,
-        &child_index,
+    let (mut root, file_count, total_size) = build_tree_from_arena(
+        &arena,
         &recursive_sizes,
-        &volume_root_trim,
-        &volume_root_key,
         &root_name,
         &root_path_str,
         shallow_dirs,
         progress.as_ref(),
         n_records,
-    )?;
+    );
     let t_after_build_tree = Instant::now();
     let scan_time_ms = start.elapsed().as_millis() as u64;
-    eprintln!(
-        "[scan:mft] build_tree done: file_count={}, total_size={}, elapsed_ms={}",
-        file_count, total_size, scan_time_ms
+    tracing::info!(
+        file_count,
+        total_size,
+        size = %format_bytes(total_size, ByteStyle::Binary),
+        elapsed_ms = scan_time_ms,
+        "MFT build_tree done"
     );
 
     if std::env::var("MFT_TIMING").is_ok() {
-        let get_mft_ms = t_after_mft_read.duration_since(start).as_millis();
-        let iterate_ms = t_after_iterate.duration_since(t_after_mft_read).as_millis();
+        let get_mft_ms = t_after_mft_read.duration_since(start).as_millis() as u64;
+        let iterate_ms = t_after_iterate.duration_since(t_after_mft_read).as_millis() as u64;
         let build_tree_ms = t_after_build_tree
             .duration_since(t_after_iterate)
-            .as_millis();
-        let total_ms = scan_time_ms as u128;
-        eprintln!("[MFT_TIMING] ---------- MFT scan phase timing (ms) ----------");
-        eprintln!(
-            "[MFT_TIMING] 1. get MFT content (Volume + Mft::new): {:>8} ms  ({:>5.1}%)",
+            .as_millis() as u64;
+        // 阶段 1 为磁盘 I/O、阶段 2 为 ntfs-reader 单线程枚举，仅阶段 3 已并行
+        tracing::info!(
+            target: "mft_timing",
             get_mft_ms,
-            100.0 * get_mft_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] 2. iterate_files + collect records:    {:>8} ms  ({:>5.1}%)",
             iterate_ms,
-            100.0 * iterate_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] 3. build_tree (parallel):              {:>8} ms  ({:>5.1}%)",
             build_tree_ms,
-            100.0 * build_tree_ms as f64 / total_ms as f64
-        );
-        eprintln!(
-            "[MFT_TIMING] total:                                {:>8} ms  records={}",
-            total_ms,
-            records.len()
+            total_ms = scan_time_ms,
+            records = arena.len(),
+            "MFT scan phase timing"
         );
-        eprintln!("[MFT_TIMING] ---------- parallelization notes ----------");
-        eprintln!("[MFT_TIMING] - phase 1: disk I/O, not parallelizable.");
-        eprintln!("[MFT_TIMING] - phase 2: ntfs-reader is single-threaded.");
-        eprintln!("[MFT_TIMING] - phase 3: already parallel (chunked map/index + par_iter).");
     }
 
     let (volume_total_bytes, volume_free_bytes) =
@@ -507,288 +671,427 @@ pub fn scan_volume_mft(
             None => (None, None),
         };
 
-    let root_pruned = prune_tree_for_display(root, 0);
-    let top_files = Some(build_top_files_from_records(&records, TOP_FILES_FOR_RESULT));
+    finalize_tree(&mut root);
+    let top_files = Some(build_top_files_from_arena(&arena, TOP_FILES_FOR_RESULT));
+    let recent_activity = recent_activity_from_arena(&arena, now_secs());
+    let physical_usage = root.physical_size.map(|physical_bytes| PhysicalUsage {
+        logical_bytes: total_size,
+        physical_bytes,
+    });
 
     Ok(ScanResult {
-        root: root_pruned,
+        scan_id: None,
+        root,
         scan_time_ms,
         file_count,
         total_size,
-        scan_warning: None,
+        scan_warning,
         volume_total_bytes,
         volume_free_bytes,
         top_files,
+        owner_usage: None,
+        throttled: false,
+        ignored_bytes: None,
+        trash_bytes: None,
+        paused_ms: 0,
+        physical_usage,
+        recent_activity,
     })
 }
 
-/// 从 records + index( indices ) 取根节点信息，再构建子树；建树过程中用 display_count 上报进度，避免前端数字回跳。
-fn build_tree_from_mft_records(
-    records: &[MftRecord],
-    child_index: &HashMap<String, Vec<usize>>,
-    recursive_sizes: &HashMap<String, u64>,
-    volume_root_trim: &str,
-    volume_root_key: &str,
+/// 建树时共享的只读上下文
+struct TreeBuild<'a> {
+    arena: &'a RecordArena,
+    recursive_sizes: &'a [u64],
+    recursive_streams: &'a [u64],
+    recursive_saved: &'a [u64],
+    recursive_latest: &'a [u64],
+    shallow_dirs: bool,
+    nodes_built: AtomicU64,
+    last_reported: AtomicU64,
+    progress: Option<&'a ProgressCbArc>,
+    display_count: u64,
+}
+
+/// 子项统计结果：大小、节点数（含自身与所有后代）、返回给前端的节点（被剪掉时为 None）、
+/// 大小是否不完整（见 [`FileNode::partial_size`]）
+type BuiltChild = (u64, u64, Option<FileNode>, bool);
+
+/// 子项统计结果：大小、节点数、保留的子节点、子节点是否不全、大小是否不完整
+type BuiltChildren = (u64, u64, Vec<FileNode>, bool, bool);
+
+/// 从记录集合建树，返回已按前端 Treemap 剪枝（6 层、每层最多 250 个子节点）的根节点、节点数与总大小。
+/// 剪枝在建树过程中完成：被剪掉的部分只统计大小与数量，不生成节点与路径字符串；
+/// 建树过程中用 display_count 上报进度，避免前端数字回跳。
+fn build_tree_from_arena(
+    arena: &RecordArena,
+    recursive_sizes: &[u64],
     root_name: &str,
     root_path_str: &str,
     shallow_dirs: bool,
     progress: Option<&ProgressCbArc>,
     display_count: u64,
-) -> Result<(FileNode, u64, u64), DiskAnalyzerError> {
-    let root_record = records.iter().find(|r| {
-        r.full_path
-            .trim_end_matches('\\')
-            .eq_ignore_ascii_case(volume_root_trim)
-    });
-    let (root_size, root_modified) = root_record
-        .map(|r| (r.size, r.modified))
-        .unwrap_or((0u64, None));
-
-    let direct_indices: Vec<usize> = child_index
-        .get(volume_root_key)
-        .or_else(|| child_index.get(volume_root_trim))
-        .or_else(|| {
-            child_index
-                .keys()
-                .find(|k| {
-                    k.eq_ignore_ascii_case(volume_root_key)
-                        || k.eq_ignore_ascii_case(volume_root_trim)
-                })
-                .and_then(|k| child_index.get(k))
-        })
-        .cloned()
-        .unwrap_or_default();
-
-    let nodes_built = AtomicU64::new(0);
-    let last_reported = AtomicU64::new(0);
-
-    let child_nodes: Vec<FileNode> = direct_indices
-        .par_iter()
-        .map(|&idx| {
-            let rec = &records[idx];
-            let name = rec
-                .full_path
-                .rsplit('\\')
-                .next()
-                .unwrap_or(rec.full_path.as_str());
-            let is_shallow = shallow_dirs
-                && rec.is_dir
-                && SHALLOW_DIR_NAMES
-                    .iter()
-                    .any(|&s| s.eq_ignore_ascii_case(name));
-            let path = rec.full_path.as_str();
-            if is_shallow {
-                let size = recursive_sizes
-                    .get(path.trim_end_matches('\\'))
-                    .copied()
-                    .unwrap_or(rec.size);
-                FileNode {
-                    path: path.to_string(),
-                    name: name.to_string(),
-                    size,
-                    is_dir: true,
-                    modified: rec.modified,
-                    children: vec![],
-                }
-            } else {
-                let (node, _cnt) = build_subtree_from_indices(
-                    records,
-                    child_index,
-                    recursive_sizes,
-                    path,
-                    name,
-                    1,
-                    shallow_dirs,
-                    &nodes_built,
-                    &last_reported,
-                    progress,
-                    display_count,
-                );
-                node
-            }
-        })
-        .collect();
-
-    let mut total_size = root_size;
-    let mut file_count = 1u64;
-    for c in &child_nodes {
-        total_size += c.size;
-        file_count += count_nodes(c);
-    }
-
+) -> (FileNode, u64, u64) {
+    let recursive_streams = arena.recursive_streams_sizes();
+    let recursive_saved = arena.recursive_saved_bytes();
+    let recursive_latest = arena.recursive_latest_modified();
+    let ctx = TreeBuild {
+        arena,
+        recursive_sizes,
+        recursive_streams: &recursive_streams,
+        recursive_saved: &recursive_saved,
+        recursive_latest: &recursive_latest,
+        shallow_dirs,
+        nodes_built: AtomicU64::new(0),
+        last_reported: AtomicU64::new(0),
+        progress,
+        display_count,
+    };
+    let root_meta = arena.meta(ROOT);
+    let (children_size, children_count, children, partial_children, partial_size) =
+        build_children(&ctx, ROOT, 0, true);
+    let total_size = root_meta.size.saturating_add(children_size);
+    let file_count = children_count + 1;
     let root = FileNode {
         path: root_path_str.to_string(),
         name: root_name.to_string(),
         size: total_size,
         is_dir: true,
-        modified: root_modified,
-        children: child_nodes,
+        modified: root_meta.modified,
+        created: root_meta.created.map(NonZeroU64::get),
+        accessed: root_meta.accessed.map(NonZeroU64::get),
+        streams_size: non_zero(recursive_streams[ROOT as usize]),
+        physical_size: physical_size(total_size, recursive_saved[ROOT as usize]),
+        latest_child_modified: non_zero(recursive_latest[ROOT as usize]),
+        partial_children,
+        partial_size,
+        children,
+        ..Default::default()
     };
-    Ok((root, file_count, total_size))
+    (root, file_count, total_size)
 }
 
-fn count_nodes(n: &FileNode) -> u64 {
-    if n.children.is_empty() {
-        return 1;
+/// 统计 `idx` 的子项（最多 MAX_CHILDREN_PER_DIR 个），返回 (大小, 节点数, 保留的子节点, 子节点是否不全, 大小是否不完整)。
+/// `depth` 为 `idx` 自身的深度（根为 0）；`keep` 为 false 时只统计不生成节点
+fn build_children(ctx: &TreeBuild, idx: u32, depth: usize, keep: bool) -> BuiltChildren {
+    let all_children = ctx.arena.children(idx);
+    let children = &all_children[..all_children.len().min(MAX_CHILDREN_PER_DIR)];
+    let keep_children = keep && depth < MAX_DEPTH_RETURN;
+    // 只为按递归大小排前 MAX_CHILDREN_PER_DIR_RETURN 的子项生成节点
+    let size_cutoff = if keep_children && children.len() > MAX_CHILDREN_PER_DIR_RETURN {
+        let mut sizes: Vec<u64> = children
+            .iter()
+            .map(|&c| ctx.recursive_sizes[c as usize])
+            .collect();
+        let (_, cutoff, _) =
+            sizes.select_nth_unstable_by(MAX_CHILDREN_PER_DIR_RETURN - 1, |a, b| b.cmp(a));
+        Some(*cutoff)
+    } else {
+        None
+    };
+    let keep_child = |c: u32| {
+        keep_children
+            && match size_cutoff {
+                Some(cut) => ctx.recursive_sizes[c as usize] >= cut,
+                None => true,
+            }
+    };
+
+    let built: Vec<BuiltChild> = if depth == 0 {
+        map_collect(children, |&c| build_child(ctx, c, depth + 1, keep_child(c)))
+    } else {
+        children
+            .iter()
+            .map(|&c| build_child(ctx, c, depth + 1, keep_child(c)))
+            .collect()
+    };
+
+    let mut size = 0u64;
+    let mut count = 0u64;
+    let mut nodes = Vec::new();
+    let mut partial_size = children.len() < all_children.len();
+    for (child_size, child_count, node, child_partial) in built {
+        size = size.saturating_add(child_size);
+        count += child_count;
+        partial_size |= child_partial;
+        nodes.extend(node);
+    }
+    // 同为截断边界大小的子项可能多于名额
+    if nodes.len() > MAX_CHILDREN_PER_DIR_RETURN {
+        nodes.sort_by_key(|n| Reverse(n.size));
+        nodes.truncate(MAX_CHILDREN_PER_DIR_RETURN);
     }
-    1 + n.children.iter().map(count_nodes).sum::<u64>()
+
+    let cur = ctx.nodes_built.fetch_add(1, Ordering::Relaxed) + 1;
+    if let Some(cb) = ctx.progress {
+        let last = ctx.last_reported.load(Ordering::Relaxed);
+        if cur.saturating_sub(last) >= BUILD_TREE_PROGRESS_EVERY
+            && ctx
+                .last_reported
+                .compare_exchange(last, cur, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            cb(ctx.display_count, &ProgressPhase::MftBuildingTree.marker());
+        }
+    }
+    let partial_children = nodes.len() < all_children.len();
+    (size, count, nodes, partial_children, partial_size)
 }
 
-/// 剪枝树以匹配前端 Treemap（深度 6、每层最多 250 子节点，按 size 取 top），减小 payload 与解析时间
-fn prune_tree_for_display(root: FileNode, depth: usize) -> FileNode {
-    if depth >= MAX_DEPTH_RETURN {
-        return FileNode {
-            path: root.path,
-            name: root.name,
-            size: root.size,
-            is_dir: root.is_dir,
-            modified: root.modified,
-            children: vec![],
+/// 单个子项：目录在 MAX_DEPTH 内继续展开，shallow 目录与超出深度的目录直接取递归大小
+fn build_child(ctx: &TreeBuild, idx: u32, depth: usize, keep: bool) -> BuiltChild {
+    let meta = ctx.arena.meta(idx);
+    let name = ctx.arena.name(idx);
+    let is_shallow = meta.is_dir && is_collapsed_dir_name(name, ctx.shallow_dirs);
+    let (size, descendants, children, partial_children, partial_size) =
+        if meta.is_dir && !is_shallow && depth < MAX_DEPTH {
+            build_children(ctx, idx, depth, keep)
+        } else if meta.is_dir {
+            let listed = ctx.arena.children(idx).is_empty();
+            (
+                ctx.recursive_sizes[idx as usize],
+                0,
+                Vec::new(),
+                !listed,
+                false,
+            )
+        } else {
+            (meta.size, 0, Vec::new(), false, false)
         };
-    }
-    let mut children = root.children;
-    if children.len() > MAX_CHILDREN_PER_DIR_RETURN {
-        children.sort_by(|a, b| b.size.cmp(&a.size));
-        children.truncate(MAX_CHILDREN_PER_DIR_RETURN);
-    }
-    let children: Vec<FileNode> = children
-        .into_iter()
-        .map(|c| prune_tree_for_display(c, depth + 1))
-        .collect();
-    FileNode {
-        path: root.path,
-        name: root.name,
-        size: root.size,
-        is_dir: root.is_dir,
-        modified: root.modified,
+    let node = keep.then(|| FileNode {
+        path: ctx.arena.path(idx),
+        name: name.to_string(),
+        size,
+        is_dir: meta.is_dir,
+        modified: meta.modified,
+        created: meta.created.map(NonZeroU64::get),
+        accessed: meta.accessed.map(NonZeroU64::get),
+        attributes: meta.attributes,
+        streams_size: non_zero(ctx.recursive_streams[idx as usize]),
+        physical_size: physical_size(size, ctx.recursive_saved[idx as usize]),
+        latest_child_modified: non_zero(ctx.recursive_latest[idx as usize]),
+        partial_children,
+        partial_size,
         children,
-    }
+        ..Default::default()
+    });
+    (size, descendants + 1, node, partial_size)
 }
 
-/// 从 records 中取前 N 大文件（仅文件，不含目录），供前端摘要与 AI 分析
-fn build_top_files_from_records(records: &[MftRecord], n: usize) -> Vec<TopFileEntry> {
-    let mut files: Vec<(&MftRecord, u64)> = records
-        .iter()
-        .filter(|r| !r.is_dir)
-        .map(|r| (r, r.size))
-        .collect();
-    files.sort_by(|a, b| b.1.cmp(&a.1));
-    files
+fn non_zero(bytes: u64) -> Option<u64> {
+    (bytes > 0).then_some(bytes)
+}
+
+/// 有节省时的实际占用
+fn physical_size(size: u64, saved: u64) -> Option<u64> {
+    (saved > 0).then(|| size.saturating_sub(saved))
+}
+
+/// 前 N 大文件（仅文件，不含目录），供前端摘要与 AI 分析
+fn build_top_files_from_arena(arena: &RecordArena, n: usize) -> Vec<TopFileEntry> {
+    arena
+        .largest_files(n)
         .into_iter()
-        .take(n)
-        .map(|(r, _)| TopFileEntry {
-            path: r.full_path.clone(),
-            size: r.size,
-            modified: r.modified,
+        .map(|idx| {
+            let meta = arena.meta(idx);
+            TopFileEntry {
+                path: DisplayPath::new(&arena.path(idx)).into_string(),
+                size: meta.size,
+                modified: meta.modified,
+                created: meta.created.map(NonZeroU64::get),
+                accessed: meta.accessed.map(NonZeroU64::get),
+                attributes: meta.attributes,
+                owner: None,
+            }
         })
         .collect()
 }
 
-/// 使用 indices 版 index 建子树，并周期性上报进度（用 display_count 保持前端数字不变），避免前端长时间无响应。
-fn build_subtree_from_indices(
-    records: &[MftRecord],
-    index: &HashMap<String, Vec<usize>>,
-    recursive_sizes: &HashMap<String, u64>,
-    path_prefix: &str,
-    name: &str,
-    depth: usize,
-    shallow_dirs: bool,
-    nodes_built: &AtomicU64,
-    last_reported: &AtomicU64,
-    progress: Option<&ProgressCbArc>,
-    display_count: u64,
-) -> (FileNode, u64) {
-    let children_indices = index.get(path_prefix).map(|v| v.as_slice()).unwrap_or(&[]);
-    let mut size = 0u64;
-    let mut file_count = 0u64;
-    let modified: Option<u64> = None;
-
-    let mut children: Vec<FileNode> =
-        Vec::with_capacity(children_indices.len().min(MAX_CHILDREN_PER_DIR));
-    for &idx in children_indices {
-        let rec = &records[idx];
-        if rec.full_path.eq_ignore_ascii_case(path_prefix) {
-            continue;
-        }
-        let child_name = rec
-            .full_path
-            .rsplit('\\')
-            .next()
-            .unwrap_or(rec.full_path.as_str());
-        let child_path = rec.full_path.as_str();
-        let is_shallow = shallow_dirs
-            && rec.is_dir
-            && SHALLOW_DIR_NAMES
-                .iter()
-                .any(|&s| s.eq_ignore_ascii_case(child_name));
-        if is_shallow {
-            let child_size = recursive_sizes
-                .get(child_path.trim_end_matches('\\'))
-                .copied()
-                .unwrap_or(rec.size);
-            size += child_size;
-            file_count += 1;
-            children.push(FileNode {
-                path: child_path.to_string(),
-                name: child_name.to_string(),
-                size: child_size,
-                is_dir: true,
-                modified: rec.modified,
-                children: vec![],
-            });
-        } else if depth < MAX_DEPTH {
-            let (child_node, cnt) = build_subtree_from_indices(
-                records,
-                index,
-                recursive_sizes,
-                child_path,
-                child_name,
-                depth + 1,
-                shallow_dirs,
-                nodes_built,
-                last_reported,
-                progress,
-                display_count,
-            );
-            size += child_node.size;
-            file_count += cnt;
-            children.push(child_node);
-        } else {
-            size += rec.size;
-            file_count += 1;
-            children.push(FileNode {
-                path: child_path.to_string(),
-                name: child_name.to_string(),
-                size: rec.size,
-                is_dir: rec.is_dir,
-                modified: rec.modified,
-                children: vec![],
-            });
-        }
-        if children.len() >= MAX_CHILDREN_PER_DIR {
-            break;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn os_error(code: i32) -> NtfsReaderError {
+        NtfsReaderError::IOError(std::io::Error::from_raw_os_error(code))
     }
 
-    let cur = nodes_built.fetch_add(1, Ordering::Relaxed) + 1;
-    if let Some(ref cb) = progress {
-        let last = last_reported.load(Ordering::Relaxed);
-        if cur.saturating_sub(last) >= BUILD_TREE_PROGRESS_EVERY
-            && last_reported
-                .compare_exchange(last, cur, Ordering::Relaxed, Ordering::Relaxed)
-                .is_ok()
-        {
-            cb(display_count, "[scan:mft] building tree...");
+    /// 按脚本依次返回结果的打开函数，记录调用次数、等待时间与进度消息
+    fn run_script(
+        script: Vec<Result<u32, NtfsReaderError>>,
+    ) -> (Result<u32, NtfsReaderError>, usize, Vec<u64>, Vec<String>) {
+        let script = RefCell::new(script.into_iter());
+        let calls = RefCell::new(0);
+        let sleeps = RefCell::new(Vec::new());
+        let messages = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        let progress: ProgressCb = Box::new(move |_: u64, msg: &str| {
+            sink.lock().unwrap().push(msg.to_string());
+        });
+        let result = open_with_retry(
+            || {
+                *calls.borrow_mut() += 1;
+                script.borrow_mut().next().expect("script exhausted")
+            },
+            |d: Duration| sleeps.borrow_mut().push(d.as_millis() as u64),
+            Some(&progress),
+        );
+        let messages = messages.lock().unwrap().clone();
+        (result, calls.into_inner(), sleeps.into_inner(), messages)
+    }
+
+    #[test]
+    fn test_retries_transient_errors_then_succeeds() {
+        let (result, calls, sleeps, messages) =
+            run_script(vec![Err(os_error(32)), Err(os_error(21)), Ok(7)]);
+        assert!(matches!(result, Ok(7)));
+        assert_eq!(calls, 3);
+        assert_eq!(sleeps, vec![200, 500]);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("retrying in 200 ms (1/3)"));
+        assert!(messages[1].contains("(2/3)"));
+    }
+
+    #[test]
+    fn test_gives_up_after_bounded_retries() {
+        let (result, calls, sleeps, _) = run_script(vec![
+            Err(os_error(170)),
+            Err(os_error(170)),
+            Err(os_error(170)),
+            Err(os_error(170)),
+        ]);
+        assert!(is_transient_open_error(&result.unwrap_err()));
+        assert_eq!(calls, 4);
+        assert_eq!(sleeps, vec![200, 500, 1000]);
+    }
+
+    #[test]
+    fn test_access_denied_is_not_retried() {
+        let (result, calls, sleeps, messages) = run_script(vec![Err(os_error(5))]);
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert!(sleeps.is_empty());
+        assert!(messages.is_empty());
+
+        let (result, calls, _, _) = run_script(vec![Err(NtfsReaderError::ElevationError)]);
+        assert!(matches!(result, Err(NtfsReaderError::ElevationError)));
+        assert_eq!(calls, 1);
+    }
+
+    /// 在临时目录所在的 NTFS 卷上创建带备用数据流的文件与稀疏文件，读取其 MFT 记录；
+    /// 临时目录不在 NTFS 卷上或未以管理员身份运行时跳过
+    #[test]
+    fn test_detects_streams_and_sparse_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // 取长文件名形式，与 MFT 中的路径一致
+        let dir_str = std::fs::canonicalize(dir.path())
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let drive = dir_str.trim_start_matches(r"\\?\").chars().next().unwrap();
+        let volume_root = format!(r"{}:\", drive);
+        let preconditions =
+            crate::mft_availability::MftPreconditions::probe(Path::new(&volume_root));
+        if let Some(blocker) = preconditions.blocker() {
+            eprintln!("跳过：{} 无法读取 MFT（{:?}）", volume_root, blocker);
+            return;
         }
+
+        let run = |cmd: &str| {
+            let status = std::process::Command::new("cmd")
+                .args(["/c", cmd])
+                .current_dir(dir.path())
+                .status()
+                .unwrap();
+            assert!(status.success(), "{}", cmd);
+        };
+        std::fs::write(dir.path().join("with_ads.txt"), b"hello").unwrap();
+        run("echo x > with_ads.txt:stream");
+        run("echo yy > with_ads.txt:Zone.Identifier");
+        std::fs::write(dir.path().join("plain.txt"), b"hello").unwrap();
+        std::fs::write(dir.path().join("sparse.bin"), b"").unwrap();
+        run("fsutil sparse setflag sparse.bin");
+        let stream_len = |name: &str| std::fs::metadata(dir.path().join(name)).unwrap().len();
+        let expected_streams =
+            stream_len("with_ads.txt:stream") + stream_len("with_ads.txt:Zone.Identifier");
+
+        let volume = Volume::new(format!(r"\\.\{}:", drive).as_str()).unwrap();
+        let mft = Mft::new(volume).unwrap();
+        let mut cache = HashMapCache::default();
+        let mut found = std::collections::HashMap::new();
+        let prefix = format!(r"{}:{}", drive, &dir_str.trim_start_matches(r"\\?\")[2..]);
+        mft.iterate_files(|file| {
+            let info = FileInfo::with_cache(&mft, file, &mut cache);
+            let path = normalize_ntfs_path(&info.path.to_string_lossy(), &drive.to_string());
+            if let Some(name) = path
+                .get(prefix.len()..)
+                .filter(|_| path[..prefix.len()].eq_ignore_ascii_case(&prefix))
+            {
+                found.insert(
+                    name.trim_start_matches('\\').to_string(),
+                    (info.size, RecordAttributes::read(file)),
+                );
+            }
+        });
+
+        let (size, record) = &found["with_ads.txt"];
+        // 未命名流的大小不变，命名流单独统计
+        assert_eq!(*size, 5);
+        assert_eq!(record.streams_size, expected_streams);
+        let (_, plain) = &found["plain.txt"];
+        assert_eq!(plain.streams_size, 0);
+        assert!(!FileAttributes::from_windows_bits(plain.bits).sparse);
+        let (_, sparse) = &found["sparse.bin"];
+        assert!(FileAttributes::from_windows_bits(sparse.bits).sparse);
     }
 
-    let node = FileNode {
-        path: path_prefix.to_string(),
-        name: name.to_string(),
-        size,
-        is_dir: true,
-        modified,
-        children,
-    };
-    (node, file_count + 1)
+    #[test]
+    fn test_pruned_children_keep_complete_sizes() {
+        let mut builder = RecordArenaBuilder::new(r"C:\", '\\');
+        let dir = RecordMeta {
+            is_dir: true,
+            ..RecordMeta::default()
+        };
+        builder.insert(r"C:\logs", dir).unwrap();
+        for i in 0..260u64 {
+            let file = RecordMeta {
+                size: i + 1,
+                ..RecordMeta::default()
+            };
+            builder
+                .insert(&format!(r"C:\logs\{:03}.log", i), file)
+                .unwrap();
+        }
+        builder.insert(r"C:\app\node_modules", dir).unwrap();
+        let file = RecordMeta {
+            size: 10,
+            ..RecordMeta::default()
+        };
+        builder
+            .insert(r"C:\app\node_modules\index.js", file)
+            .unwrap();
+        let arena = builder.finish();
+        let sizes = arena.recursive_sizes();
+        let (root, _, _) = build_tree_from_arena(&arena, &sizes, "C:", r"C:\", true, None, 0);
+
+        let find = |node: &FileNode, name: &str| {
+            node.children
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+        // 只返回最大的 250 个子节点，大小仍计入全部子项
+        let logs = find(&root, "logs");
+        assert_eq!(logs.children.len(), MAX_CHILDREN_PER_DIR_RETURN);
+        assert_eq!(logs.size, (1..=260).sum::<u64>());
+        assert!(logs.partial_children && !logs.partial_size);
+        // 只计大小的目录取递归大小，不列出子项
+        let modules = find(&find(&root, "app"), "node_modules");
+        assert_eq!((modules.size, modules.children.len()), (10, 0));
+        assert!(modules.partial_children && !modules.partial_size);
+        assert!(!root.partial_children && !root.partial_size);
+    }
 }
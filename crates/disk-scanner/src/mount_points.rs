@@ -0,0 +1,183 @@
+//! 只统计与扫描根同一文件系统的内容：遍历到其他卷、网络共享等的挂载点时停下，
+//! 挂载点作为 `is_mount_point` 的叶子节点保留在树中，大小为 0，避免把其他卷的占用重复计入。
+//!
+//! Unix 比较目录与扫描根的 `st_dev`；Windows 只检查带重解析点属性的目录
+//! （卷挂载点与目录联接的重解析标记同为 IO_REPARSE_TAG_MOUNT_POINT），其所在卷的序列号与根不同时视为挂载点。
+//! 未启用 `windows-native` 的 Windows 构建无法取得卷序列号，不做判断。
+
+use std::path::Path;
+
+/// 设备标识：Unix 为 `st_dev`，Windows 为卷序列号
+pub(crate) type DeviceId = u64;
+
+/// 本次扫描的文件系统边界
+#[derive(Clone, Copy)]
+pub(crate) struct FsBoundary {
+    root_device: DeviceId,
+    /// 目录所在设备；返回 None 表示无需或无法判断，按同一文件系统处理
+    device_of: fn(&Path) -> Option<DeviceId>,
+}
+
+impl FsBoundary {
+    /// 以扫描根所在设备为边界；取不到设备标识时返回 None（不限制）
+    pub(crate) fn for_root(root: &Path) -> Option<Self> {
+        platform::root_device(root).map(|root_device| Self {
+            root_device,
+            device_of: platform::mount_device,
+        })
+    }
+
+    /// 用给定的设备查询函数构造，供测试模拟挂载点
+    #[cfg(test)]
+    pub(crate) fn with_devices(
+        root_device: DeviceId,
+        device_of: fn(&Path) -> Option<DeviceId>,
+    ) -> Self {
+        Self {
+            root_device,
+            device_of,
+        }
+    }
+
+    /// 目录 `dir` 是否为其他文件系统的挂载点
+    pub(crate) fn is_mount_point(&self, dir: &Path) -> bool {
+        (self.device_of)(dir).is_some_and(|device| device != self.root_device)
+    }
+}
+
+/// 路径是否为卷根（Unix 上为挂载点根目录），未指定时据此决定是否只统计同一文件系统
+pub fn is_volume_root(path: &Path) -> bool {
+    match path.parent() {
+        None => true,
+        #[cfg(unix)]
+        Some(parent) => {
+            let device = platform::root_device(path);
+            device.is_some() && platform::root_device(parent) != device
+        }
+        #[cfg(not(unix))]
+        Some(_) => false,
+    }
+}
+
+/// 两个路径是否位于同一卷；路径不存在时按最近的已存在祖先判断（如尚未创建的移动目标）。
+/// 取不到设备标识时返回 None
+pub fn same_volume(a: &Path, b: &Path) -> Option<bool> {
+    let device = |path: &Path| path.ancestors().find_map(platform::root_device);
+    Some(device(a)? == device(b)?)
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    use super::DeviceId;
+
+    pub(super) fn root_device(path: &Path) -> Option<DeviceId> {
+        std::fs::metadata(path).ok().map(|m| m.dev())
+    }
+
+    pub(super) fn mount_device(dir: &Path) -> Option<DeviceId> {
+        root_device(dir)
+    }
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+    use std::path::Path;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{
+        CreateFileW, GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+        FILE_ATTRIBUTE_REPARSE_POINT, FILE_FLAG_BACKUP_SEMANTICS, FILE_SHARE_DELETE,
+        FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+
+    use super::DeviceId;
+
+    /// 路径（跟随重解析点）所在卷的序列号
+    pub(super) fn root_device(path: &Path) -> Option<DeviceId> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        // SAFETY: 路径以 NUL 结尾；访问权限为 0 只查询属性，打开目录需要 FILE_FLAG_BACKUP_SEMANTICS
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS,
+                0,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        // SAFETY: 全零是该 C 结构体的合法值
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: handle 由 CreateFileW 打开且尚未关闭，info 为有效的输出缓冲区
+        let ok = unsafe { GetFileInformationByHandle(handle, &mut info) } != 0;
+        // SAFETY: handle 由 CreateFileW 打开且尚未关闭
+        unsafe {
+            CloseHandle(handle);
+        }
+        ok.then_some(DeviceId::from(info.dwVolumeSerialNumber))
+    }
+
+    /// 普通目录不会跨卷，只对重解析点查询目标所在卷
+    pub(super) fn mount_device(dir: &Path) -> Option<DeviceId> {
+        let attributes = std::fs::symlink_metadata(dir).ok()?.file_attributes();
+        if attributes & FILE_ATTRIBUTE_REPARSE_POINT == 0 {
+            return None;
+        }
+        root_device(dir)
+    }
+}
+
+#[cfg(not(any(unix, all(windows, feature = "windows-native"))))]
+mod platform {
+    use std::path::Path;
+
+    use super::DeviceId;
+
+    pub(super) fn root_device(_path: &Path) -> Option<DeviceId> {
+        None
+    }
+
+    pub(super) fn mount_device(_dir: &Path) -> Option<DeviceId> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mocked_boundary() {
+        let boundary =
+            FsBoundary::with_devices(1, |p| if p.ends_with("mnt") { Some(2) } else { Some(1) });
+        assert!(boundary.is_mount_point(Path::new("/data/mnt")));
+        assert!(!boundary.is_mount_point(Path::new("/data/src")));
+
+        let unknown = FsBoundary::with_devices(1, |_| None);
+        assert!(!unknown.is_mount_point(Path::new("/data/mnt")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_real_boundary_on_same_filesystem() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let boundary = FsBoundary::for_root(dir.path()).unwrap();
+        assert!(!boundary.is_mount_point(&dir.path().join("sub")));
+        assert!(is_volume_root(Path::new("/")));
+        assert!(!is_volume_root(&dir.path().join("sub")));
+        assert_eq!(
+            same_volume(&dir.path().join("sub"), &dir.path().join("missing/target")),
+            Some(true)
+        );
+    }
+}
@@ -1,2 +1,152 @@
 // Re-export from domain
-pub use ai_disk_domain::FileNode;
+pub use ai_disk_domain::{FileNode, SizeSource};
+
+use crate::display_path::{normalize_for, DisplayPath};
+use crate::parallel::for_each_mut;
+
+/// 计算节点 id 所用的路径：在 [`DisplayPath`] 的基础上，Windows 上再忽略大小写
+pub fn normalize_node_path(path: &str) -> String {
+    normalize_node_path_for(path, cfg!(windows))
+}
+
+pub(crate) fn normalize_node_path_for(path: &str, windows: bool) -> String {
+    let display = normalize_for(path, windows);
+    if windows {
+        display.to_lowercase()
+    } else {
+        display
+    }
+}
+
+/// 节点的稳定 id：规范化路径的 FNV-1a 哈希，截断到 53 位以便在 JavaScript 中精确表示
+pub fn node_id(path: &str) -> u64 {
+    node_id_for(path, cfg!(windows))
+}
+
+fn node_id_for(path: &str, windows: bool) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = normalize_node_path_for(path, windows)
+        .bytes()
+        .fold(FNV_OFFSET, |h, b| (h ^ b as u64).wrapping_mul(FNV_PRIME));
+    hash & ((1 << 53) - 1)
+}
+
+/// 返回结果前整理树：路径统一为 [`DisplayPath`]（根节点名称也由其得出，卷根为 `C:\`），
+/// 子节点按大小降序、同大小按名称排序，并为每个节点填充 id
+pub(crate) fn finalize_tree(root: &mut FileNode) {
+    let display = DisplayPath::new(&root.path);
+    root.name = display.name().to_string();
+    finalize_node(root);
+}
+
+fn finalize_node(node: &mut FileNode) {
+    node.path = DisplayPath::new(&node.path).into_string();
+    node.id = node_id(&node.path);
+    node.children
+        .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    for_each_mut(&mut node.children, finalize_node);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_windows_paths_from_both_modes_share_id() {
+        // 目录遍历得到 canonicalize 后的 `\\?\` 路径，MFT 得到普通盘符路径
+        let pairs = [
+            (r"\\?\C:\Users\K\Downloads", r"C:\Users\K\Downloads"),
+            (r"\\?\C:\", r"C:\"),
+            (r"C:/Users/k/downloads/", r"C:\Users\K\Downloads"),
+            (r"\\?\UNC\nas\share\a.iso", r"\\nas\share\a.iso"),
+        ];
+        for (walk, mft) in pairs {
+            assert_eq!(
+                node_id_for(walk, true),
+                node_id_for(mft, true),
+                "{} vs {}",
+                walk,
+                mft
+            );
+        }
+        assert_ne!(
+            node_id_for(r"C:\Users\a", true),
+            node_id_for(r"C:\Users\b", true)
+        );
+        assert!(node_id_for(r"C:\Users", true) < (1 << 53));
+    }
+
+    #[test]
+    fn test_unix_paths_are_case_sensitive() {
+        assert_eq!(normalize_node_path_for("/home/u/", false), "/home/u");
+        assert_eq!(normalize_node_path_for("/", false), "/");
+        assert_ne!(node_id_for("/home/A", false), node_id_for("/home/a", false));
+    }
+
+    #[test]
+    fn test_finalize_tree_is_deterministic() {
+        let leaf = |name: &str, size: u64| FileNode::new(format!("/r/{}", name), size, false);
+        let mut a = FileNode {
+            path: "/r".to_string(),
+            name: "r".to_string(),
+            size: 17,
+            is_dir: true,
+            children: vec![leaf("b", 5), leaf("big", 10), leaf("a", 2), leaf("c", 5)],
+            ..Default::default()
+        };
+        let mut b = a.clone();
+        b.children.reverse();
+        finalize_tree(&mut a);
+        finalize_tree(&mut b);
+        let order = |n: &FileNode| -> Vec<(String, u64)> {
+            n.children.iter().map(|c| (c.name.clone(), c.id)).collect()
+        };
+        assert_eq!(order(&a), order(&b));
+        let names: Vec<_> = a.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["big", "b", "c", "a"]);
+        assert_eq!(a.id, node_id("/r"));
+        assert_eq!(a.children[0].id, node_id("/r/big"));
+    }
+
+    #[test]
+    fn test_walk_ids_match_mft_style_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("Sub")).unwrap();
+        fs::write(dir.path().join("Sub").join("a.bin"), vec![0u8; 64]).unwrap();
+        fs::write(dir.path().join("b.txt"), b"hello").unwrap();
+        let result = crate::scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            true,
+            false,
+            None,
+            None,
+            &crate::WalkOptions::default(),
+        )
+        .unwrap()
+        .0;
+
+        // MFT 记录的路径形如 `C:\dir\file`：不带 `\\?\` 前缀
+        fn mft_style(path: &str) -> String {
+            path.strip_prefix(r"\\?\").unwrap_or(path).to_string()
+        }
+        fn check(node: &FileNode) {
+            assert_ne!(node.id, 0);
+            assert!(!node.path.starts_with(r"\\?\"));
+            assert_eq!(DisplayPath::new(&node.path).as_str(), node.path);
+            assert_eq!(node.id, node_id(&mft_style(&node.path)), "{}", node.path);
+            node.children.iter().for_each(check);
+        }
+        check(&result.root);
+        // 子节点按大小降序
+        let names: Vec<_> = result
+            .root
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["Sub", "b.txt"]);
+    }
+}
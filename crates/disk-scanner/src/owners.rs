@@ -0,0 +1,366 @@
+//! 多用户机器上的所有者归属：解析文件/目录的所有者，并按所有者汇总占用。
+//! 查询安全信息较慢，只对超过阈值的节点与 top_files 解析，扫描时需显式开启。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use ai_disk_domain::{FileNode, OwnerUsage, ScanResult};
+
+use crate::display_path::DisplayPath;
+
+/// 只为不小于该大小的节点单独解析所有者，更小的节点计入其父节点的所有者
+pub const OWNER_DIR_MIN_BYTES: u64 = 256 * 1024 * 1024;
+
+/// 根节点无法解析所有者时使用的名称
+const UNKNOWN_OWNER: &str = "未知";
+
+/// 路径 → 所有者名称
+pub trait OwnerResolver {
+    fn owner_of(&mut self, path: &Path) -> Option<String>;
+}
+
+/// 使用系统 API 的解析器：Windows 上为所有者 SID → `DOMAIN\name`，其他平台为 uid → passwd 中的用户名。
+/// 相同 SID/uid 只查询一次
+#[derive(Default)]
+pub struct SystemOwnerResolver {
+    #[cfg(all(windows, feature = "windows-native"))]
+    names: HashMap<Vec<u8>, Option<String>>,
+    #[cfg(not(windows))]
+    users: Option<HashMap<u32, String>>,
+}
+
+impl SystemOwnerResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(not(windows))]
+impl OwnerResolver for SystemOwnerResolver {
+    fn owner_of(&mut self, path: &Path) -> Option<String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let uid = std::fs::symlink_metadata(path).ok()?.uid();
+        let users = self.users.get_or_insert_with(|| {
+            std::fs::read_to_string("/etc/passwd")
+                .map(|content| parse_passwd(&content))
+                .unwrap_or_default()
+        });
+        // passwd 中没有的 uid（如容器内或 LDAP 用户）直接显示数字
+        Some(users.get(&uid).cloned().unwrap_or_else(|| uid.to_string()))
+    }
+}
+
+/// 解析 `/etc/passwd`（`name:x:uid:gid:...`）为 uid → 用户名；同一 uid 取第一条
+#[cfg(any(not(windows), test))]
+fn parse_passwd(content: &str) -> HashMap<u32, String> {
+    let mut users = HashMap::new();
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(uid)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(uid) = uid.parse::<u32>() {
+            users.entry(uid).or_insert_with(|| name.to_string());
+        }
+    }
+    users
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+impl OwnerResolver for SystemOwnerResolver {
+    fn owner_of(&mut self, path: &Path) -> Option<String> {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+        use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+        use windows_sys::Win32::Security::{
+            GetLengthSid, IsValidSid, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+        };
+
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut owner: PSID = std::ptr::null_mut();
+        let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+        // SAFETY: 路径以 NUL 结尾；只请求所有者，其余输出参数传空指针；
+        // 成功时 owner 指向 descriptor 内部，descriptor 需用 LocalFree 释放
+        let status = unsafe {
+            GetNamedSecurityInfoW(
+                wide.as_ptr(),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION,
+                &mut owner,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut descriptor,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        // SAFETY: owner 在 descriptor 释放前有效；先校验 SID 再读取其长度与内容
+        let sid = unsafe {
+            if owner.is_null() || IsValidSid(owner) == 0 {
+                None
+            } else {
+                let len = GetLengthSid(owner) as usize;
+                Some(std::slice::from_raw_parts(owner as *const u8, len).to_vec())
+            }
+        };
+        let name = sid.and_then(|sid| {
+            self.names
+                .entry(sid)
+                .or_insert_with_key(|sid| lookup_account_name(sid.as_ptr() as PSID))
+                .clone()
+        });
+        // SAFETY: descriptor 由 GetNamedSecurityInfoW 分配，此后不再使用 owner
+        unsafe {
+            LocalFree(descriptor as _);
+        }
+        name
+    }
+}
+
+/// 通过 LookupAccountSidW 把 SID 转为 `DOMAIN\name`
+#[cfg(all(windows, feature = "windows-native"))]
+fn lookup_account_name(sid: windows_sys::Win32::Security::PSID) -> Option<String> {
+    use windows_sys::Win32::Security::{LookupAccountSidW, SID_NAME_USE};
+
+    let mut name = [0u16; 256];
+    let mut domain = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain_len = domain.len() as u32;
+    let mut sid_use: SID_NAME_USE = 0;
+    // SAFETY: sid 为有效 SID；缓冲区长度与传入的字符数一致，本机查询传空系统名
+    let ok = unsafe {
+        LookupAccountSidW(
+            std::ptr::null(),
+            sid,
+            name.as_mut_ptr(),
+            &mut name_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    Some(if domain.is_empty() {
+        name
+    } else {
+        format!("{}\\{}", domain, name)
+    })
+}
+
+/// 未启用 `windows-native` 的 Windows 构建无法读取安全信息，所有者一律未知
+#[cfg(all(windows, not(feature = "windows-native")))]
+impl OwnerResolver for SystemOwnerResolver {
+    fn owner_of(&mut self, _path: &Path) -> Option<String> {
+        None
+    }
+}
+
+/// 为 top_files 填充所有者，并按所有者汇总整棵树的占用写入 `owner_usage`。
+/// 只解析根节点与大小不小于 `min_dir_bytes` 的节点；其余部分计入最近的已解析祖先，
+/// 因此各所有者的字节数之和等于根节点大小
+pub fn attribute_owners(
+    result: &mut ScanResult,
+    resolver: &mut dyn OwnerResolver,
+    min_dir_bytes: u64,
+) {
+    if let Some(top_files) = result.top_files.as_mut() {
+        for entry in top_files {
+            entry.owner = resolver.owner_of(&DisplayPath::new(&entry.path).to_os_path());
+        }
+    }
+
+    let mut totals: HashMap<String, u64> = HashMap::new();
+    let root_owner = resolve(resolver, &result.root).unwrap_or_else(|| UNKNOWN_OWNER.to_string());
+    accumulate(
+        &result.root,
+        root_owner,
+        resolver,
+        min_dir_bytes,
+        &mut totals,
+    );
+
+    let mut usage: Vec<OwnerUsage> = totals
+        .into_iter()
+        .filter(|(_, bytes)| *bytes > 0)
+        .map(|(owner, bytes)| OwnerUsage { owner, bytes })
+        .collect();
+    usage.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.owner.cmp(&b.owner)));
+    result.owner_usage = Some(usage);
+}
+
+fn resolve(resolver: &mut dyn OwnerResolver, node: &FileNode) -> Option<String> {
+    resolver.owner_of(&DisplayPath::new(&node.path).to_os_path())
+}
+
+fn accumulate(
+    node: &FileNode,
+    owner: String,
+    resolver: &mut dyn OwnerResolver,
+    min_dir_bytes: u64,
+    totals: &mut HashMap<String, u64>,
+) {
+    let mut remainder = node.size;
+    for child in node.children.iter().filter(|c| c.size >= min_dir_bytes) {
+        remainder = remainder.saturating_sub(child.size);
+        // 无法解析（如无权读取安全信息）时沿用父节点的所有者
+        let child_owner = resolve(resolver, child).unwrap_or_else(|| owner.clone());
+        accumulate(child, child_owner, resolver, min_dir_bytes, totals);
+    }
+    *totals.entry(owner).or_insert(0) += remainder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::TopFileEntry;
+
+    /// 按路径最后一级名称返回所有者，记录查询过的路径
+    struct MockResolver {
+        owners: HashMap<&'static str, &'static str>,
+        queried: Vec<String>,
+    }
+
+    impl OwnerResolver for MockResolver {
+        fn owner_of(&mut self, path: &Path) -> Option<String> {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            self.queried.push(name.clone());
+            self.owners.get(name.as_str()).map(|o| o.to_string())
+        }
+    }
+
+    fn node(name: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(format!("/home/{}", name), size, !children.is_empty()).with_children(children)
+    }
+
+    fn scan_of(root: FileNode) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: Some(vec![TopFileEntry {
+                path: "/home/bob/video.mkv".to_string(),
+                size: 400,
+                modified: None,
+                created: None,
+                accessed: None,
+                attributes: None,
+                owner: None,
+            }]),
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_aggregates_bytes_per_owner() {
+        let root = node(
+            "root",
+            1030,
+            vec![
+                node(
+                    "alice",
+                    500,
+                    vec![node("shared", 200, vec![]), node("small", 50, vec![])],
+                ),
+                node("bob", 400, vec![]),
+                node("tiny", 30, vec![]),
+                node("locked", 100, vec![]),
+            ],
+        );
+        let mut result = scan_of(root);
+        let mut resolver = MockResolver {
+            owners: HashMap::from([
+                ("root", "root"),
+                ("alice", "alice"),
+                ("shared", "bob"),
+                ("bob", "bob"),
+                ("video.mkv", "bob"),
+            ]),
+            queried: Vec::new(),
+        };
+        attribute_owners(&mut result, &mut resolver, 100);
+
+        let usage = result.owner_usage.unwrap();
+        assert_eq!(
+            usage,
+            vec![
+                OwnerUsage {
+                    owner: "bob".to_string(),
+                    bytes: 600,
+                },
+                OwnerUsage {
+                    owner: "alice".to_string(),
+                    bytes: 300,
+                },
+                OwnerUsage {
+                    owner: "root".to_string(),
+                    bytes: 130,
+                },
+            ]
+        );
+        assert_eq!(usage.iter().map(|u| u.bytes).sum::<u64>(), 1030);
+        // 低于阈值的节点不单独解析
+        assert!(!resolver.queried.iter().any(|q| q == "tiny" || q == "small"));
+        assert_eq!(result.top_files.unwrap()[0].owner.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn test_unresolved_root_is_unknown() {
+        let mut result = scan_of(node("root", 10, vec![]));
+        let mut resolver = MockResolver {
+            owners: HashMap::new(),
+            queried: Vec::new(),
+        };
+        attribute_owners(&mut result, &mut resolver, OWNER_DIR_MIN_BYTES);
+        assert_eq!(
+            result.owner_usage.unwrap(),
+            vec![OwnerUsage {
+                owner: UNKNOWN_OWNER.to_string(),
+                bytes: 10,
+            }]
+        );
+        assert_eq!(result.top_files.unwrap()[0].owner, None);
+    }
+
+    #[test]
+    fn test_parse_passwd() {
+        let users = parse_passwd(
+            "# comment\nroot:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/sh\nbroken\nalias:x:1000:1000::/:/bin/sh\n",
+        );
+        assert_eq!(users.get(&0).map(String::as_str), Some("root"));
+        assert_eq!(users.get(&1000).map(String::as_str), Some("alice"));
+        assert_eq!(users.len(), 2);
+    }
+
+    #[test]
+    fn test_system_resolver_resolves_own_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.bin");
+        std::fs::write(&file, b"x").unwrap();
+        let mut resolver = SystemOwnerResolver::new();
+        let owner = resolver.owner_of(&file);
+        assert!(owner.is_some_and(|o| !o.is_empty()));
+        assert_eq!(resolver.owner_of(dir.path()), resolver.owner_of(&file));
+        assert_eq!(resolver.owner_of(&dir.path().join("missing")), None);
+    }
+}
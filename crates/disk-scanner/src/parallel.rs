@@ -0,0 +1,113 @@
+//! 遍历用的数据并行：启用 `parallel` feature（默认）时基于 rayon，
+//! 关闭时在调用线程上按顺序执行，调用方写法不变。
+
+use std::sync::Arc;
+
+use ai_disk_common::DiskAnalyzerError;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::pause::PauseToken;
+use crate::throttle::TokenBucket;
+
+/// 对每一项求值并按原顺序收集结果
+pub(crate) fn map_collect<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// 对每一项原地处理
+pub(crate) fn for_each_mut<T, F>(items: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(&mut T) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter_mut().for_each(f);
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter_mut().for_each(f);
+    }
+}
+
+/// 一次扫描专用的线程池；未启用 `parallel` 时直接在调用线程上执行，
+/// 后台扫描只限速、不调整调用线程的优先级
+pub(crate) struct ScanPool {
+    #[cfg(feature = "parallel")]
+    pool: rayon::ThreadPool,
+    #[cfg(not(feature = "parallel"))]
+    bucket: Option<Arc<TokenBucket>>,
+    #[cfg(not(feature = "parallel"))]
+    pause: Option<PauseToken>,
+}
+
+impl ScanPool {
+    /// `threads` 个工作线程；`bucket` 为 Some 时为后台扫描，`pause` 为 Some 时扫描可暂停
+    pub(crate) fn new(
+        threads: usize,
+        bucket: Option<Arc<TokenBucket>>,
+        pause: Option<PauseToken>,
+    ) -> Result<Self, DiskAnalyzerError> {
+        #[cfg(feature = "parallel")]
+        {
+            let mut builder = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|i| format!("disk-scan-{}", i));
+            if bucket.is_some() || pause.is_some() {
+                builder = builder.start_handler(move |_| {
+                    if let Some(bucket) = &bucket {
+                        crate::throttle::enter_background(bucket.clone());
+                    }
+                    if let Some(pause) = &pause {
+                        crate::pause::enter(pause.clone());
+                    }
+                });
+            }
+            let pool = builder
+                .build()
+                .map_err(|e| DiskAnalyzerError::Io(std::io::Error::other(e)))?;
+            Ok(Self { pool })
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            let _ = threads;
+            Ok(Self { bucket, pause })
+        }
+    }
+
+    /// 在线程池中执行 `f` 并等待其返回
+    pub(crate) fn install<R, F>(&self, f: F) -> R
+    where
+        R: Send,
+        F: FnOnce() -> R + Send,
+    {
+        #[cfg(feature = "parallel")]
+        {
+            self.pool.install(f)
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            let throttled = || match &self.bucket {
+                Some(bucket) => crate::throttle::with_throttle(bucket.clone(), f),
+                None => f(),
+            };
+            match &self.pause {
+                Some(pause) => crate::pause::with_pause(pause.clone(), throttled),
+                None => throttled(),
+            }
+        }
+    }
+}
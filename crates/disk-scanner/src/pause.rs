@@ -0,0 +1,184 @@
+//! 扫描暂停与继续：[`PauseToken`] 由调用方持有，暂停后扫描线程在检查点上阻塞等待，而不是中止扫描。
+//!
+//! 与限速器相同，暂停令牌通过线程池的启动回调挂到本次扫描的每个工作线程上（线程局部变量），
+//! 检查点与限速共用：遍历代码每读完一个目录调用 [`crate::throttle`] 的 `on_dir_listed`，
+//! MFT 扫描每枚举一批记录调用一次 [`checkpoint`]。
+//! MFT 扫描的 `Mft::new` 会一次性读入整个 $MFT（见 [`crate::mft_scan`]），读入期间无法暂停，
+//! 暂停从枚举阶段开始生效。
+//!
+//! 暂停的时长不计入 `ScanResult::scan_time_ms`，单独记在 `paused_ms` 中。
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct PauseState {
+    /// 本次暂停的开始时刻；未暂停时为 None
+    paused_since: Option<Instant>,
+    /// 已结束的各次暂停的总时长
+    paused_total: Duration,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<PauseState>,
+    resumed: Condvar,
+}
+
+/// 一次扫描的暂停开关，克隆后共享同一状态
+#[derive(Clone, Default)]
+pub struct PauseToken {
+    inner: Arc<Inner>,
+}
+
+impl PauseToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PauseState> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 暂停；已暂停时返回 false
+    pub fn pause(&self) -> bool {
+        let mut state = self.lock();
+        if state.paused_since.is_some() {
+            return false;
+        }
+        state.paused_since = Some(Instant::now());
+        true
+    }
+
+    /// 继续并唤醒所有等待中的扫描线程；未暂停时返回 false
+    pub fn resume(&self) -> bool {
+        let mut state = self.lock();
+        let Some(since) = state.paused_since.take() else {
+            return false;
+        };
+        state.paused_total += since.elapsed();
+        self.inner.resumed.notify_all();
+        true
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused_since.is_some()
+    }
+
+    /// 累计暂停时长，包含尚未结束的本次暂停
+    pub fn paused_duration(&self) -> Duration {
+        let state = self.lock();
+        state.paused_total + state.paused_since.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+
+    /// 暂停期间阻塞当前线程，继续后返回
+    pub fn wait_while_paused(&self) {
+        let mut state = self.lock();
+        while state.paused_since.is_some() {
+            state = self
+                .inner
+                .resumed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl fmt::Debug for PauseToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PauseToken")
+            .field("paused", &self.is_paused())
+            .finish()
+    }
+}
+
+/// 同一个令牌（克隆自同一来源）才相等
+impl PartialEq for PauseToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for PauseToken {}
+
+thread_local! {
+    static THREAD_PAUSE: RefCell<Option<PauseToken>> = const { RefCell::new(None) };
+}
+
+/// 在扫描线程池的启动回调中调用：挂上暂停令牌
+#[cfg(feature = "parallel")]
+pub(crate) fn enter(token: PauseToken) {
+    THREAD_PAUSE.with(|p| *p.borrow_mut() = Some(token));
+}
+
+/// 未启用 `parallel` 时扫描在调用线程上进行：只在 `f` 执行期间挂上暂停令牌
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn with_pause<R>(token: PauseToken, f: impl FnOnce() -> R) -> R {
+    let previous = THREAD_PAUSE.with(|p| p.borrow_mut().replace(token));
+    let result = f();
+    THREAD_PAUSE.with(|p| *p.borrow_mut() = previous);
+    result
+}
+
+/// 检查点：当前线程挂有暂停令牌且已暂停时阻塞到继续为止，否则立即返回
+pub(crate) fn checkpoint() {
+    THREAD_PAUSE.with(|p| {
+        if let Some(token) = p.borrow().as_ref() {
+            token.wait_while_paused();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_resume_accounting() {
+        let token = PauseToken::new();
+        assert!(!token.is_paused());
+        assert!(!token.resume());
+        assert_eq!(token.paused_duration(), Duration::ZERO);
+
+        assert!(token.pause());
+        assert!(!token.pause());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(token.paused_duration() >= Duration::from_millis(30));
+        assert!(token.resume());
+        let paused = token.paused_duration();
+        std::thread::sleep(Duration::from_millis(20));
+        // 继续后不再累计
+        assert_eq!(token.paused_duration(), paused);
+        assert_eq!(token.clone(), token);
+        assert_ne!(PauseToken::new(), token);
+    }
+
+    #[test]
+    fn test_waiting_thread_released_on_resume() {
+        let token = PauseToken::new();
+        token.pause();
+        let waiter = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                token.wait_while_paused();
+                Instant::now()
+            })
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+        let resumed_at = Instant::now();
+        token.resume();
+        assert!(waiter.join().unwrap() >= resumed_at);
+    }
+
+    #[test]
+    fn test_checkpoint_without_token_is_noop() {
+        let start = Instant::now();
+        for _ in 0..1_000 {
+            checkpoint();
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
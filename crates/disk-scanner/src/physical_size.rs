@@ -0,0 +1,179 @@
+//! 目录遍历结果的实际占用：对带压缩或稀疏属性的文件（NTFS 压缩；重复数据删除后的文件为稀疏的重解析点）
+//! 查询 GetCompressedFileSizeW，再逐级汇总到目录。MFT 扫描直接读取 $DATA 属性头，见 `mft_scan`。
+
+use std::path::Path;
+
+use ai_disk_domain::{FileNode, PhysicalUsage};
+
+/// 填充各节点的 `physical_size`，返回整棵树的逻辑大小与实际占用；没有任何节省时返回 None。
+/// `query` 返回文件实际分配的字节数，只对带压缩或稀疏属性的文件调用；须在 [`crate::node::finalize_tree`]
+/// 改写路径之前调用
+pub(crate) fn fill_physical_sizes(
+    root: &mut FileNode,
+    query: &dyn Fn(&Path) -> Option<u64>,
+) -> Option<PhysicalUsage> {
+    let physical_bytes = fill(root, query);
+    (physical_bytes < root.size).then_some(PhysicalUsage {
+        logical_bytes: root.size,
+        physical_bytes,
+    })
+}
+
+/// 返回节点的实际占用
+fn fill(node: &mut FileNode, query: &dyn Fn(&Path) -> Option<u64>) -> u64 {
+    let physical = if node.is_dir {
+        let mut seen = 0u64;
+        let mut physical = 0u64;
+        for child in &mut node.children {
+            seen = seen.saturating_add(child.size);
+            physical = physical.saturating_add(fill(child, query));
+        }
+        // 截断、只计大小的部分无从查询，按逻辑大小计
+        physical.saturating_add(node.size.saturating_sub(seen))
+    } else if node
+        .attributes
+        .is_some_and(|attributes| attributes.compressed || attributes.sparse)
+    {
+        query(Path::new(&node.path)).map_or(node.size, |bytes| bytes.min(node.size))
+    } else {
+        node.size
+    };
+    node.physical_size = (physical < node.size).then_some(physical);
+    physical
+}
+
+/// 通过 GetCompressedFileSizeW 获取文件实际分配的字节数；非 Windows 平台或未启用 `windows-native` 时为 None
+pub(crate) fn compressed_file_size(path: &Path) -> Option<u64> {
+    #[cfg(all(windows, feature = "windows-native"))]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Foundation::{GetLastError, NO_ERROR};
+        use windows_sys::Win32::Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut high = 0u32;
+        // SAFETY: 路径以 NUL 结尾，high 可写
+        let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+        // 低 32 位恰为 INVALID_FILE_SIZE 时需要结合错误码判断
+        // SAFETY: 紧跟在调用之后读取错误码
+        if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+            return None;
+        }
+        Some((u64::from(high) << 32) | u64::from(low))
+    }
+    #[cfg(not(all(windows, feature = "windows-native")))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::FileAttributes;
+
+    fn node(path: &str, size: u64, compressed: bool, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            size,
+            is_dir: !children.is_empty(),
+            attributes: FileAttributes {
+                compressed,
+                ..Default::default()
+            }
+            .non_empty(),
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compressed_files_roll_up() {
+        let logs = node(
+            "/v/logs",
+            1000,
+            false,
+            vec![
+                node("/v/logs/a.log", 600, true, vec![]),
+                node("/v/logs/b.log", 300, false, vec![]),
+            ],
+        );
+        let mut root = node(
+            "/v",
+            1500,
+            false,
+            vec![logs, node("/v/c.bin", 500, true, vec![])],
+        );
+        // a.log 压缩到 100 字节；c.bin 查询失败时按逻辑大小计
+        let query = |path: &Path| (path == Path::new("/v/logs/a.log")).then_some(100);
+        let usage = fill_physical_sizes(&mut root, &query).unwrap();
+
+        // logs 中未列出的 100 字节按逻辑大小计
+        assert_eq!(root.children[0].physical_size, Some(500));
+        assert_eq!(root.children[0].children[0].physical_size, Some(100));
+        assert_eq!(root.children[0].children[1].physical_size, None);
+        assert_eq!(root.children[1].physical_size, None);
+        assert_eq!(
+            usage,
+            PhysicalUsage {
+                logical_bytes: 1500,
+                physical_bytes: 1000,
+            }
+        );
+        assert_eq!(usage.saved_bytes(), 500);
+    }
+
+    #[test]
+    fn test_uncompressed_tree_has_no_usage() {
+        let mut root = node("/v", 10, false, vec![node("/v/a", 10, false, vec![])]);
+        let query = |_: &Path| -> Option<u64> { panic!("未压缩的文件不应查询") };
+        assert_eq!(fill_physical_sizes(&mut root, &query), None);
+        assert_eq!(root.physical_size, None);
+    }
+
+    /// 用 compact.exe 压缩临时目录后写入可压缩的数据，扫描结果中实际占用应小于逻辑大小；
+    /// 临时目录所在卷不支持压缩（非 NTFS）时跳过
+    #[cfg(all(windows, feature = "windows-native"))]
+    #[test]
+    fn test_scan_reports_ntfs_compression() {
+        use crate::scanner::{scan_path_with_progress, WalkOptions};
+
+        let dir = tempfile::tempdir().unwrap();
+        let status = std::process::Command::new("compact")
+            .args(["/c", "/q"])
+            .arg(dir.path())
+            .stdout(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        if !status.success() {
+            eprintln!("跳过：{} 不支持 NTFS 压缩", dir.path().display());
+            return;
+        }
+        // 新建的文件继承目录的压缩属性
+        std::fs::write(dir.path().join("zeros.bin"), vec![0u8; 4 * 1024 * 1024]).unwrap();
+
+        let (result, _) = scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+        let file = result
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "zeros.bin")
+            .unwrap();
+        assert!(file.attributes.is_some_and(|a| a.compressed));
+        let usage = result.physical_usage.unwrap();
+        assert_eq!(usage.logical_bytes, result.total_size);
+        assert!(usage.physical_bytes < usage.logical_bytes, "{:?}", usage);
+        assert!(file.physical_size.unwrap() < file.size);
+    }
+}
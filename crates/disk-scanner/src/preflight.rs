@@ -0,0 +1,125 @@
+//! 扫描前预检：本次扫描是否会使用 MFT、不会时的原因，以及按预计条目数推荐的扫描方式。
+//! 与 [`crate::scan_will_use_mft`] 同口径，供 UI 在开始扫描前说明。
+
+use ai_disk_domain::{MftBlocker, ScanMode, ScanPreflight};
+
+use crate::mft_availability::MftPreconditions;
+use crate::mount_points::is_volume_root;
+use crate::scanner::normalize_path;
+
+/// 推荐使用 MFT 的最小预计条目数：条目较少时目录遍历已足够快，
+/// 而 MFT 无论目录多小都要读取整个卷的记录
+pub const MFT_RECOMMENDED_MIN_ENTRIES: u64 = 500_000;
+
+/// 推荐的扫描方式：MFT 可用且没有扫描记录或预计条目数不少于 [`MFT_RECOMMENDED_MIN_ENTRIES`] 时用 MFT
+pub fn recommend_scan_mode(mft_available: bool, estimated_entries: Option<u64>) -> ScanMode {
+    match estimated_entries {
+        _ if !mft_available => ScanMode::Walk,
+        Some(entries) if entries < MFT_RECOMMENDED_MIN_ENTRIES => ScanMode::Walk,
+        _ => ScanMode::Mft,
+    }
+}
+
+/// 预检 `path`；`estimated_entries` 由调用方从此前的扫描结果中取得
+pub fn scan_preflight(path: &str, use_mft: bool, estimated_entries: Option<u64>) -> ScanPreflight {
+    let path_buf = normalize_path(path);
+    let Ok(canonical) = std::fs::canonicalize(&path_buf) else {
+        return ScanPreflight {
+            will_use_mft: false,
+            mft_blocker: Some(MftBlocker::PathNotFound),
+            mft_reason: Some(format!("路径不存在: {}", path)),
+            is_volume_root: false,
+            filesystem: None,
+            is_elevated: false,
+            estimated_entries,
+            recommended_mode: ScanMode::Walk,
+        };
+    };
+    let preconditions = MftPreconditions::probe(&canonical);
+    let unavailable = preconditions.blocker();
+    // 卷本身不支持时优先说明卷的原因，否则才是未勾选
+    let blocker = unavailable.or((!use_mft).then_some(MftBlocker::NotRequested));
+    ScanPreflight {
+        will_use_mft: blocker.is_none(),
+        mft_blocker: blocker,
+        mft_reason: blocker.map(|b| preconditions.describe(b)),
+        is_volume_root: preconditions.volume_root || is_volume_root(&canonical),
+        is_elevated: preconditions.elevated,
+        filesystem: preconditions.filesystem,
+        estimated_entries,
+        recommended_mode: recommend_scan_mode(unavailable.is_none(), estimated_entries),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommendation_thresholds() {
+        assert_eq!(recommend_scan_mode(false, None), ScanMode::Walk);
+        assert_eq!(recommend_scan_mode(false, Some(5_000_000)), ScanMode::Walk);
+        assert_eq!(recommend_scan_mode(true, None), ScanMode::Mft);
+        assert_eq!(recommend_scan_mode(true, Some(1_000)), ScanMode::Walk);
+        assert_eq!(
+            recommend_scan_mode(true, Some(MFT_RECOMMENDED_MIN_ENTRIES - 1)),
+            ScanMode::Walk
+        );
+        assert_eq!(
+            recommend_scan_mode(true, Some(MFT_RECOMMENDED_MIN_ENTRIES)),
+            ScanMode::Mft
+        );
+    }
+
+    #[test]
+    fn test_blocker_codes_follow_reason_order() {
+        let ntfs_root = MftPreconditions {
+            windows: true,
+            volume_root: true,
+            filesystem: Some("NTFS".to_string()),
+            elevated: true,
+        };
+        assert_eq!(ntfs_root.blocker(), None);
+        let not_elevated = MftPreconditions {
+            elevated: false,
+            ..ntfs_root
+        };
+        assert_eq!(not_elevated.blocker(), Some(MftBlocker::NotElevated));
+        let exfat = MftPreconditions {
+            filesystem: Some("exFAT".to_string()),
+            ..not_elevated
+        };
+        assert_eq!(exfat.blocker(), Some(MftBlocker::NotNtfs));
+        assert!(exfat.describe(MftBlocker::NotNtfs).contains("exFAT"));
+        let sub_dir = MftPreconditions {
+            volume_root: false,
+            ..exfat
+        };
+        assert_eq!(sub_dir.blocker(), Some(MftBlocker::NotVolumeRoot));
+    }
+
+    #[test]
+    fn test_preflight_on_real_paths() {
+        let missing = scan_preflight("/nonexistent_xyz_12345_folder", true, None);
+        assert!(!missing.will_use_mft);
+        assert_eq!(missing.mft_blocker, Some(MftBlocker::PathNotFound));
+        assert_eq!(missing.recommended_mode, ScanMode::Walk);
+
+        // 临时目录不是卷根，无论是否勾选都不会用 MFT，原因为卷本身而非未勾选
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy();
+        for use_mft in [true, false] {
+            let preflight = scan_preflight(&path, use_mft, Some(2_000_000));
+            assert!(!preflight.will_use_mft);
+            assert!(!preflight.is_volume_root);
+            assert_ne!(preflight.mft_blocker, Some(MftBlocker::NotRequested));
+            assert!(preflight.mft_reason.is_some());
+            assert_eq!(preflight.estimated_entries, Some(2_000_000));
+            assert_eq!(preflight.recommended_mode, ScanMode::Walk);
+            assert_eq!(
+                preflight.will_use_mft,
+                crate::scan_will_use_mft(&path, use_mft)
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! 文件预览：审阅清理计划时查看待删除文件的开头内容。
+//!
+//! 只读取文件开头至多 `max_bytes`（不超过 [`PREVIEW_MAX_BYTES`]）字节，按内容判断类型而不只看扩展名：
+//! 带 BOM 的 UTF-8/UTF-16 与不含 NUL、控制字符很少的内容按文本返回；能识别出格式的图片生成缩略图；
+//! 其余（包括数 GB 的二进制文件）只返回元数据。
+//! 图片需要整体解码，只对不超过 [`IMAGE_PREVIEW_MAX_FILE_BYTES`] 的文件生成缩略图，解码内存也有上限。
+
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{FilePreview, PreviewContent, TextEncoding};
+use base64::Engine;
+use image::{ImageFormat, ImageReader, Limits};
+
+use crate::display_path::DisplayPath;
+use crate::scanner::resolve_scan_root;
+
+/// 未指定时读取的字节数
+pub const PREVIEW_DEFAULT_MAX_BYTES: usize = 16 * 1024;
+/// 文本预览最多读取的字节数
+pub const PREVIEW_MAX_BYTES: usize = 1024 * 1024;
+/// 大于该大小的图片不生成缩略图，只返回元数据
+pub const IMAGE_PREVIEW_MAX_FILE_BYTES: u64 = 32 * 1024 * 1024;
+/// 缩略图长边的最大像素数
+pub const THUMBNAIL_MAX_SIDE: u32 = 256;
+
+/// 解码图片时允许分配的最大内存
+const IMAGE_DECODE_MAX_ALLOC: u64 = 256 * 1024 * 1024;
+/// 非 UTF-8 内容中控制字符占比超过该值时视为二进制
+const MAX_CONTROL_RATIO: f64 = 0.05;
+
+/// 预览文件 `path`：最多读取开头 `max_bytes` 字节。路径不存在或为目录时返回 InvalidPath
+pub fn preview_file(path: &str, max_bytes: usize) -> Result<FilePreview, DiskAnalyzerError> {
+    let path_buf = resolve_scan_root(path)?;
+    let metadata = std::fs::metadata(&path_buf)?;
+    if metadata.is_dir() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "不能预览目录: {}",
+            path
+        )));
+    }
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+
+    let limit = max_bytes.clamp(1, PREVIEW_MAX_BYTES);
+    let mut head = Vec::with_capacity(limit.min(size as usize));
+    std::fs::File::open(&path_buf)?
+        .take(limit as u64)
+        .read_to_end(&mut head)?;
+    let truncated = size > head.len() as u64;
+
+    let (mime, content) = if let Some(format) = sniff_image(&head) {
+        let content = if size <= IMAGE_PREVIEW_MAX_FILE_BYTES {
+            image_preview(&path_buf, format).unwrap_or(PreviewContent::Metadata)
+        } else {
+            PreviewContent::Metadata
+        };
+        (Some(format.to_mime_type().to_string()), content)
+    } else if let Some((text, encoding)) = decode_text(&head, truncated) {
+        (
+            Some("text/plain".to_string()),
+            PreviewContent::Text {
+                text,
+                encoding,
+                truncated,
+            },
+        )
+    } else {
+        (mime_from_extension(&path_buf), PreviewContent::Metadata)
+    };
+
+    Ok(FilePreview {
+        path: DisplayPath::new(&path_buf.to_string_lossy()).into_string(),
+        size,
+        modified,
+        mime,
+        content,
+    })
+}
+
+/// 按文件头识别的图片格式（仅限已启用解码器的格式）
+fn sniff_image(head: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(head).ok().filter(|format| {
+        matches!(
+            format,
+            ImageFormat::Png
+                | ImageFormat::Jpeg
+                | ImageFormat::Gif
+                | ImageFormat::Bmp
+                | ImageFormat::WebP
+        )
+    })
+}
+
+/// 解码图片并生成 PNG 缩略图；图片损坏或超出内存限制时返回 None
+fn image_preview(path: &Path, format: ImageFormat) -> Option<PreviewContent> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut reader = ImageReader::with_format(BufReader::new(file), format);
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(IMAGE_DECODE_MAX_ALLOC);
+    reader.limits(limits);
+    let image = reader.decode().ok()?;
+    let thumbnail = image.thumbnail(THUMBNAIL_MAX_SIDE, THUMBNAIL_MAX_SIDE);
+    let mut png = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut png, ImageFormat::Png).ok()?;
+    Some(PreviewContent::Image {
+        width: image.width(),
+        height: image.height(),
+        thumbnail_base64: base64::engine::general_purpose::STANDARD.encode(png.into_inner()),
+    })
+}
+
+/// 把文件开头解码为文本；看起来是二进制时返回 None。
+/// `truncated` 为 true 时末尾被截断的多字节字符直接丢弃，不替换为 U+FFFD
+fn decode_text(head: &[u8], truncated: bool) -> Option<(String, TextEncoding)> {
+    if let Some(rest) = head.strip_prefix(&[0xFF, 0xFE]) {
+        return Some((
+            decode_utf16(rest, u16::from_le_bytes),
+            TextEncoding::Utf16Le,
+        ));
+    }
+    if let Some(rest) = head.strip_prefix(&[0xFE, 0xFF]) {
+        return Some((
+            decode_utf16(rest, u16::from_be_bytes),
+            TextEncoding::Utf16Be,
+        ));
+    }
+    let head = head.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(head);
+    if head.contains(&0) {
+        return None;
+    }
+    let head = match std::str::from_utf8(head) {
+        Ok(_) => head,
+        // 只是末尾的字符被截断
+        Err(e) if truncated && e.error_len().is_none() => &head[..e.valid_up_to()],
+        Err(_) => {
+            let controls = head
+                .iter()
+                .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+                .count();
+            if controls as f64 > head.len() as f64 * MAX_CONTROL_RATIO {
+                return None;
+            }
+            head
+        }
+    };
+    Some((
+        String::from_utf8_lossy(head).into_owned(),
+        TextEncoding::Utf8,
+    ))
+}
+
+/// 按给定字节序解码 UTF-16，末尾不成对的字节丢弃
+fn decode_utf16(bytes: &[u8], to_u16: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| to_u16([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// 内容无法识别时按扩展名猜测 MIME 类型
+fn mime_from_extension(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "7z" => "application/x-7z-compressed",
+        "rar" => "application/vnd.rar",
+        "pdf" => "application/pdf",
+        "exe" | "dll" | "msi" => "application/vnd.microsoft.portable-executable",
+        "iso" | "img" | "vhd" | "vhdx" | "vmdk" => "application/x-raw-disk-image",
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_utf16_text_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes_final_v2.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "旧笔记\nhello".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        fs::write(&path, &bytes).unwrap();
+
+        let preview = preview_file(&path.to_string_lossy(), PREVIEW_DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!(preview.size, bytes.len() as u64);
+        assert_eq!(preview.mime.as_deref(), Some("text/plain"));
+        assert_eq!(
+            preview.content,
+            PreviewContent::Text {
+                text: "旧笔记\nhello".to_string(),
+                encoding: TextEncoding::Utf16Le,
+                truncated: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_utf8_text_truncated_on_char_boundary() {
+        let dir = tempfile::tempdir().unwrap();
+        // 没有扩展名，只按内容判断
+        let path = dir.path().join("README");
+        fs::write(&path, "ab你好".as_bytes()).unwrap();
+        // 前 4 个字节截在「你」的中间
+        let preview = preview_file(&path.to_string_lossy(), 4).unwrap();
+        assert_eq!(
+            preview.content,
+            PreviewContent::Text {
+                text: "ab".to_string(),
+                encoding: TextEncoding::Utf8,
+                truncated: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_png_thumbnail() {
+        let dir = tempfile::tempdir().unwrap();
+        // 扩展名与内容不符，仍按内容识别为 PNG
+        let path = dir.path().join("photo.dat");
+        image::RgbImage::from_pixel(600, 300, image::Rgb([200, 40, 40]))
+            .save_with_format(&path, ImageFormat::Png)
+            .unwrap();
+
+        let preview = preview_file(&path.to_string_lossy(), PREVIEW_DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!(preview.mime.as_deref(), Some("image/png"));
+        let PreviewContent::Image {
+            width,
+            height,
+            thumbnail_base64,
+        } = preview.content
+        else {
+            panic!("expected image preview, got {:?}", preview.content);
+        };
+        assert_eq!((width, height), (600, 300));
+        let png = base64::engine::general_purpose::STANDARD
+            .decode(thumbnail_base64)
+            .unwrap();
+        let thumbnail = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+    }
+
+    #[test]
+    fn test_large_sparse_binary_is_metadata_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.vhdx");
+        let file = fs::File::create(&path).unwrap();
+        // 10 GB 稀疏文件：只读取开头，不会占用 10 GB 内存
+        file.set_len(10 * 1024 * 1024 * 1024).unwrap();
+        drop(file);
+
+        let preview = preview_file(&path.to_string_lossy(), PREVIEW_MAX_BYTES * 4).unwrap();
+        assert_eq!(preview.size, 10 * 1024 * 1024 * 1024);
+        assert_eq!(preview.content, PreviewContent::Metadata);
+        assert_eq!(
+            preview.mime.as_deref(),
+            Some("application/x-raw-disk-image")
+        );
+        assert!(preview.modified.is_some());
+    }
+
+    #[test]
+    fn test_directory_and_missing_path_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            preview_file(&dir.path().to_string_lossy(), 16),
+            Err(DiskAnalyzerError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            preview_file(&dir.path().join("gone").to_string_lossy(), 16),
+            Err(DiskAnalyzerError::InvalidPath(_))
+        ));
+    }
+}
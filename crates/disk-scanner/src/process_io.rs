@@ -0,0 +1,405 @@
+//! 进程级磁盘读写采样：回答「现在是谁在写我的磁盘」。
+//!
+//! 每次采样读取所有进程的累计读写计数（Linux：`/proc/<pid>/io`；Windows：`GetProcessIoCounters`），
+//! 与上一次采样相减得到区间内的读写量。单次采样只做一次进程枚举和每个进程一次计数读取，
+//! 在最小间隔 [`IO_SAMPLE_MIN_INTERVAL_MS`] 下 CPU 开销远低于 1%。
+//!
+//! Linux 上使用 `rchar` / `wchar`（经由 read/write 系统调用的字节数，包括写入页缓存的数据），
+//! 只能读取当前用户的进程；其他进程静默跳过。
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{DiskIoSample, ProcessIoDelta, ProcessIoTotal};
+
+/// 采样间隔下限，避免过于频繁的枚举占用 CPU
+pub const IO_SAMPLE_MIN_INTERVAL_MS: u64 = 500;
+/// 每次采样返回的区间写入量最多的进程数
+pub const IO_SAMPLE_TOP_N: usize = 10;
+/// 累计统计最多返回的进程名数量
+const IO_SAMPLE_MAX_TOTALS: usize = 50;
+
+/// 某一时刻单个进程的累计读写计数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessIoCounters {
+    pub pid: u32,
+    pub name: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// 读取当前所有可访问进程的累计读写计数
+#[cfg(target_os = "linux")]
+pub fn read_process_io() -> Result<Vec<ProcessIoCounters>, DiskAnalyzerError> {
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir("/proc")?.filter_map(|e| e.ok()) {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // 进程可能在枚举后退出，或属于其他用户而无权读取，均跳过
+        let Ok(io) = std::fs::read_to_string(entry.path().join("io")) else {
+            continue;
+        };
+        let Some((read_bytes, write_bytes)) = parse_proc_io(&io) else {
+            continue;
+        };
+        let name = std::fs::read_to_string(entry.path().join("comm"))
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_else(|_| pid.to_string());
+        out.push(ProcessIoCounters {
+            pid,
+            name,
+            read_bytes,
+            write_bytes,
+        });
+    }
+    Ok(out)
+}
+
+/// 解析 `/proc/<pid>/io` 中的 `rchar` 与 `wchar`
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_io(text: &str) -> Option<(u64, u64)> {
+    let mut read = None;
+    let mut write = None;
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "rchar" => read = value.trim().parse().ok(),
+            "wchar" => write = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    Some((read?, write?))
+}
+
+#[cfg(all(windows, feature = "windows-native"))]
+pub fn read_process_io() -> Result<Vec<ProcessIoCounters>, DiskAnalyzerError> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::ProcessStatus::K32EnumProcesses;
+    use windows_sys::Win32::System::Threading::{
+        GetProcessIoCounters, OpenProcess, QueryFullProcessImageNameW, IO_COUNTERS,
+        PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    let mut pids = vec![0u32; 1024];
+    loop {
+        let mut needed = 0u32;
+        let capacity = (pids.len() * std::mem::size_of::<u32>()) as u32;
+        // SAFETY: 缓冲区大小与传入的字节数一致
+        let ok = unsafe { K32EnumProcesses(pids.as_mut_ptr(), capacity, &mut needed) };
+        if ok == 0 {
+            return Err(DiskAnalyzerError::Io(std::io::Error::last_os_error()));
+        }
+        if needed < capacity {
+            pids.truncate(needed as usize / std::mem::size_of::<u32>());
+            break;
+        }
+        pids.resize(pids.len() * 2, 0);
+    }
+
+    let mut out = Vec::with_capacity(pids.len());
+    for pid in pids.into_iter().filter(|&pid| pid != 0) {
+        // SAFETY: 句柄在本次循环结束前关闭；缓冲区长度与传入的长度一致
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            // 系统进程或已退出的进程无法打开，跳过
+            if handle == 0 {
+                continue;
+            }
+            let mut counters: IO_COUNTERS = std::mem::zeroed();
+            let ok = GetProcessIoCounters(handle, &mut counters);
+            let mut buf = [0u16; 1024];
+            let mut len = buf.len() as u32;
+            let name = if QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_WIN32,
+                buf.as_mut_ptr(),
+                &mut len,
+            ) != 0
+            {
+                let path = String::from_utf16_lossy(&buf[..len as usize]);
+                path.rsplit('\\').next().unwrap_or(&path).to_string()
+            } else {
+                pid.to_string()
+            };
+            CloseHandle(handle);
+            if ok == 0 {
+                continue;
+            }
+            out.push(ProcessIoCounters {
+                pid,
+                name,
+                read_bytes: counters.ReadTransferCount,
+                write_bytes: counters.WriteTransferCount,
+            });
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(not(any(target_os = "linux", all(windows, feature = "windows-native"))))]
+pub fn read_process_io() -> Result<Vec<ProcessIoCounters>, DiskAnalyzerError> {
+    Err(DiskAnalyzerError::Config(
+        "当前平台不支持进程读写采样".to_string(),
+    ))
+}
+
+/// 上一次采样时某个进程的计数
+struct Previous {
+    name: String,
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+/// 周期采样器：保存上一次的计数与按进程名的累计值
+pub struct IoSampler {
+    previous: HashMap<u32, Previous>,
+    totals: HashMap<String, (u64, u64)>,
+    /// 是否已建立基线；第一次采样前启动的进程的历史读写不计入
+    initialized: bool,
+    last_sample: Option<Instant>,
+}
+
+impl Default for IoSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoSampler {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+            totals: HashMap::new(),
+            initialized: false,
+            last_sample: None,
+        }
+    }
+
+    /// 读取当前计数并计算与上一次采样之间的差值；第一次调用只建立基线，`top` 为空
+    pub fn sample(&mut self) -> Result<DiskIoSample, DiskAnalyzerError> {
+        let counters = read_process_io()?;
+        let now = Instant::now();
+        let interval_ms = self
+            .last_sample
+            .map(|last| now.duration_since(last).as_millis() as u64)
+            .unwrap_or(0);
+        self.last_sample = Some(now);
+        Ok(self.apply(counters, interval_ms))
+    }
+
+    fn apply(&mut self, counters: Vec<ProcessIoCounters>, interval_ms: u64) -> DiskIoSample {
+        let baseline = !self.initialized;
+        self.initialized = true;
+        let mut next = HashMap::with_capacity(counters.len());
+        let mut deltas = Vec::new();
+        for c in counters {
+            // 同一个 pid 换了进程名，或计数变小，说明 pid 已被新进程复用：整段计数都算作本区间
+            let (read, write) = match self.previous.get(&c.pid) {
+                Some(p)
+                    if p.name == c.name
+                        && c.write_bytes >= p.write_bytes
+                        && c.read_bytes >= p.read_bytes =>
+                {
+                    (c.read_bytes - p.read_bytes, c.write_bytes - p.write_bytes)
+                }
+                _ if baseline => (0, 0),
+                _ => (c.read_bytes, c.write_bytes),
+            };
+            if read > 0 || write > 0 {
+                let total = self.totals.entry(c.name.clone()).or_default();
+                total.0 = total.0.saturating_add(read);
+                total.1 = total.1.saturating_add(write);
+            }
+            if write > 0 {
+                deltas.push(ProcessIoDelta {
+                    pid: c.pid,
+                    name: c.name.clone(),
+                    read_bytes: read,
+                    write_bytes: write,
+                });
+            }
+            next.insert(
+                c.pid,
+                Previous {
+                    name: c.name,
+                    read_bytes: c.read_bytes,
+                    write_bytes: c.write_bytes,
+                },
+            );
+        }
+        // 已退出的进程不在 next 中，自然被丢弃；它们在退出前的累计值仍保留在 totals 里
+        self.previous = next;
+
+        deltas.sort_by(|a, b| b.write_bytes.cmp(&a.write_bytes).then(a.pid.cmp(&b.pid)));
+        deltas.truncate(IO_SAMPLE_TOP_N);
+        let mut totals: Vec<ProcessIoTotal> = self
+            .totals
+            .iter()
+            .map(|(name, (read, write))| ProcessIoTotal {
+                name: name.clone(),
+                read_bytes: *read,
+                write_bytes: *write,
+            })
+            .collect();
+        totals.sort_by(|a, b| {
+            b.write_bytes
+                .cmp(&a.write_bytes)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        totals.truncate(IO_SAMPLE_MAX_TOTALS);
+        DiskIoSample {
+            interval_ms,
+            top: deltas,
+            totals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(pid: u32, name: &str, read: u64, write: u64) -> ProcessIoCounters {
+        ProcessIoCounters {
+            pid,
+            name: name.to_string(),
+            read_bytes: read,
+            write_bytes: write,
+        }
+    }
+
+    #[test]
+    fn test_deltas_exits_and_pid_reuse() {
+        let mut sampler = IoSampler::new();
+        let first = sampler.apply(
+            vec![
+                counters(1, "chrome", 100, 1000),
+                counters(2, "cargo", 0, 500),
+            ],
+            0,
+        );
+        assert!(first.top.is_empty());
+        assert!(first.totals.is_empty());
+
+        // pid 2 退出，pid 3 新启动，pid 1 写入 300
+        let second = sampler.apply(
+            vec![
+                counters(1, "chrome", 150, 1300),
+                counters(3, "rustc", 10, 700),
+            ],
+            1000,
+        );
+        assert_eq!(second.top.len(), 2);
+        assert_eq!((second.top[0].pid, second.top[0].write_bytes), (3, 700));
+        assert_eq!((second.top[1].pid, second.top[1].write_bytes), (1, 300));
+        assert_eq!(second.top[1].read_bytes, 50);
+
+        // pid 3 被复用为另一个进程
+        let third = sampler.apply(
+            vec![counters(1, "chrome", 150, 1300), counters(3, "git", 0, 40)],
+            1000,
+        );
+        assert_eq!(third.top.len(), 1);
+        assert_eq!(
+            (third.top[0].name.as_str(), third.top[0].write_bytes),
+            ("git", 40)
+        );
+        let names: Vec<(&str, u64)> = third
+            .totals
+            .iter()
+            .map(|t| (t.name.as_str(), t.write_bytes))
+            .collect();
+        assert_eq!(names, vec![("rustc", 700), ("chrome", 300), ("git", 40)]);
+    }
+
+    #[test]
+    fn test_top_is_limited_and_sorted() {
+        let mut sampler = IoSampler::new();
+        sampler.apply((1..=20).map(|pid| counters(pid, "p", 0, 0)).collect(), 0);
+        let sample = sampler.apply(
+            (1..=20)
+                .map(|pid| counters(pid, "p", 0, u64::from(pid) * 10))
+                .collect(),
+            1000,
+        );
+        assert_eq!(sample.top.len(), IO_SAMPLE_TOP_N);
+        assert_eq!(sample.top[0].pid, 20);
+        assert_eq!(sample.top[9].pid, 11);
+        assert_eq!(sample.totals.len(), 1);
+        assert_eq!(
+            sample.totals[0].write_bytes,
+            (1..=20).map(|p| p * 10).sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_parse_proc_io() {
+        let text = "rchar: 4212\nwchar: 1048576\nsyscr: 12\nsyscw: 16\nread_bytes: 0\nwrite_bytes: 4096\ncancelled_write_bytes: 0\n";
+        assert_eq!(parse_proc_io(text), Some((4212, 1_048_576)));
+        assert_eq!(parse_proc_io("read_bytes: 0\n"), None);
+    }
+
+    /// 子进程（shell 内建 printf，不再派生进程）写入已知字节数，采样结果应归属到该子进程
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_attributes_child_process_writes() {
+        use std::process::{Command, Stdio};
+        use std::time::Duration;
+
+        const CHUNKS: u64 = 64;
+        const CHUNK: u64 = 65536;
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.bin");
+
+        let mut sampler = IoSampler::new();
+        sampler.sample().unwrap();
+        let script = format!(
+            "i=0; while [ $i -lt {} ]; do printf '%{}s' ''; i=$((i+1)); done > '{}'; sleep 5",
+            CHUNKS,
+            CHUNK,
+            out.display()
+        );
+        let mut child = Command::new("sh")
+            .args(["-c", &script])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let expected = CHUNKS * CHUNK;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while std::fs::metadata(&out).map(|m| m.len()).unwrap_or(0) < expected {
+            assert!(Instant::now() < deadline, "子进程未按时写完");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let sample = sampler.sample().unwrap();
+        let entry = sample
+            .top
+            .iter()
+            .find(|d| d.pid == child.id())
+            .expect("子进程应出现在写入排行中");
+        assert!(entry.write_bytes >= expected, "{}", entry.write_bytes);
+        assert!(
+            entry.write_bytes <= expected + expected / 10,
+            "{}",
+            entry.write_bytes
+        );
+
+        // 子进程退出后再次采样不应出错，累计值保留
+        child.kill().unwrap();
+        child.wait().unwrap();
+        let after = sampler.sample().unwrap();
+        assert!(after.top.iter().all(|d| d.pid != child.id()));
+        assert!(after
+            .totals
+            .iter()
+            .any(|t| t.name == "sh" && t.write_bytes >= expected));
+    }
+}
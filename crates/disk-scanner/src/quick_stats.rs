@@ -0,0 +1,245 @@
+//! 目录快速统计：不构建文件树，在时间预算内按优先级遍历子目录，估算递归大小。
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::QuickDirStats;
+
+use crate::scanner::{is_shallow_dir_name, is_skippable_dir_error, normalize_path};
+
+/// 单次快速统计最多访问的条目数
+pub const QUICK_STATS_MAX_ENTRIES: u64 = 50_000;
+
+/// 每访问这么多条目检查一次是否超时，避免每个条目都读时钟
+const BUDGET_CHECK_INTERVAL: u64 = 256;
+
+/// 包管理器/缓存目录通常很大，优先统计
+const SHALLOW_DIR_BOOST: u64 = 1 << 40;
+
+/// 待遍历的子目录；按「看起来有多大」排序，同等优先级时先浅后深、先发现先处理
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Pending {
+    priority: u64,
+    depth: Reverse<usize>,
+    seq: Reverse<u64>,
+    path: PathBuf,
+}
+
+/// 目录「看起来有多大」：Unix 上目录项自身的大小随条目数增长，可作为廉价的规模估计；
+/// 其他平台为 0，退化为广度优先
+fn dir_priority(name: &str, metadata: Option<&std::fs::Metadata>) -> u64 {
+    let own = metadata.map(|m| m.len()).unwrap_or(0);
+    if is_shallow_dir_name(name) {
+        own.saturating_add(SHALLOW_DIR_BOOST)
+    } else {
+        own
+    }
+}
+
+struct Budget {
+    start: Instant,
+    limit: Duration,
+    max_entries: u64,
+}
+
+impl Budget {
+    fn exhausted(&self, scanned: u64) -> bool {
+        scanned >= self.max_entries
+            || (scanned.is_multiple_of(BUDGET_CHECK_INTERVAL) && self.start.elapsed() >= self.limit)
+    }
+}
+
+/// 快速统计目录：直接子项数量与大小，以及在 `budget` 时间内（最多
+/// [`QUICK_STATS_MAX_ENTRIES`] 个条目）估算的递归大小。
+/// 无权限、已消失或损坏的子目录按 build_tree 的规则跳过；不跟随符号链接
+pub fn quick_dir_stats(path: &str, budget: Duration) -> Result<QuickDirStats, DiskAnalyzerError> {
+    quick_dir_stats_with_limit(path, budget, QUICK_STATS_MAX_ENTRIES)
+}
+
+fn quick_dir_stats_with_limit(
+    path: &str,
+    budget: Duration,
+    max_entries: u64,
+) -> Result<QuickDirStats, DiskAnalyzerError> {
+    let budget = Budget {
+        start: Instant::now(),
+        limit: budget,
+        max_entries,
+    };
+    let root = normalize_path(path);
+    if !root.exists() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "路径不存在: {}",
+            path
+        )));
+    }
+    let root = std::fs::canonicalize(&root)
+        .map_err(|e| DiskAnalyzerError::InvalidPath(format!("无法解析路径: {}", e)))?;
+    if !root.is_dir() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "不是目录: {}",
+            path
+        )));
+    }
+
+    let entries = match std::fs::read_dir(&root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            return Err(DiskAnalyzerError::PermissionDenied(
+                root.display().to_string(),
+            ));
+        }
+        Err(e) => return Err(DiskAnalyzerError::Io(e)),
+    };
+
+    let mut stats = QuickDirStats {
+        path: root.display().to_string(),
+        child_count: 0,
+        file_count: 0,
+        dir_count: 0,
+        direct_size: 0,
+        recursive_size: 0,
+        scanned_entries: 0,
+        partial: false,
+        elapsed_ms: 0,
+    };
+    let mut queue = BinaryHeap::new();
+    let mut seq = 0u64;
+
+    // 直接子项总是完整统计，预算只限制向下的递归
+    for entry in entries.filter_map(|e| e.ok()) {
+        stats.child_count += 1;
+        stats.scanned_entries += 1;
+        // DirEntry::metadata 不跟随符号链接
+        let metadata = entry.metadata().ok();
+        if metadata.as_ref().is_some_and(|m| m.is_dir()) {
+            stats.dir_count += 1;
+            let name = entry.file_name().to_string_lossy().to_string();
+            seq += 1;
+            queue.push(Pending {
+                priority: dir_priority(&name, metadata.as_ref()),
+                depth: Reverse(1),
+                seq: Reverse(seq),
+                path: entry.path(),
+            });
+        } else {
+            stats.file_count += 1;
+            stats.direct_size = stats
+                .direct_size
+                .saturating_add(metadata.map(|m| m.len()).unwrap_or(0));
+        }
+    }
+    stats.recursive_size = stats.direct_size;
+
+    'walk: while let Some(dir) = queue.pop() {
+        if budget.start.elapsed() >= budget.limit || stats.scanned_entries >= budget.max_entries {
+            stats.partial = true;
+            break;
+        }
+        let entries = match std::fs::read_dir(&dir.path) {
+            Ok(entries) => entries,
+            Err(e) if is_skippable_dir_error(&e) => continue,
+            Err(e) => return Err(DiskAnalyzerError::Io(e)),
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            stats.scanned_entries += 1;
+            let metadata = entry.metadata().ok();
+            if metadata.as_ref().is_some_and(|m| m.is_dir()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                seq += 1;
+                queue.push(Pending {
+                    priority: dir_priority(&name, metadata.as_ref()),
+                    depth: Reverse(dir.depth.0 + 1),
+                    seq: Reverse(seq),
+                    path: entry.path(),
+                });
+            } else {
+                stats.recursive_size = stats
+                    .recursive_size
+                    .saturating_add(metadata.map(|m| m.len()).unwrap_or(0));
+            }
+            if budget.exhausted(stats.scanned_entries) {
+                stats.partial = true;
+                break 'walk;
+            }
+        }
+    }
+
+    stats.elapsed_ms = budget.start.elapsed().as_millis() as u64;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_small_tree_is_exact() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), vec![0u8; 10]).unwrap();
+        fs::write(root.join("b.bin"), vec![0u8; 20]).unwrap();
+        fs::create_dir_all(root.join("sub").join("deep")).unwrap();
+        fs::create_dir_all(root.join("node_modules").join("pkg")).unwrap();
+        fs::write(root.join("sub").join("c.txt"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("sub").join("deep").join("d.txt"), vec![0u8; 1000]).unwrap();
+        fs::write(
+            root.join("node_modules").join("pkg").join("index.js"),
+            vec![0u8; 5],
+        )
+        .unwrap();
+
+        let stats = quick_dir_stats(root.to_str().unwrap(), Duration::from_secs(10)).unwrap();
+        assert!(!stats.partial);
+        assert_eq!(stats.child_count, 4);
+        assert_eq!(stats.file_count, 2);
+        assert_eq!(stats.dir_count, 2);
+        assert_eq!(stats.direct_size, 30);
+        assert_eq!(stats.recursive_size, 1135);
+        assert_eq!(stats.scanned_entries, 9);
+    }
+
+    #[test]
+    fn test_budget_is_honored_on_large_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for d in 0..64 {
+            let sub = root.join(format!("dir{}", d)).join("inner");
+            fs::create_dir_all(&sub).unwrap();
+            for f in 0..300 {
+                fs::write(sub.join(format!("f{}", f)), b"x").unwrap();
+            }
+        }
+
+        let budget = Duration::from_millis(2);
+        let started = Instant::now();
+        let stats = quick_dir_stats(root.to_str().unwrap(), budget).unwrap();
+        assert!(stats.partial);
+        assert_eq!(stats.child_count, 64);
+        assert!(stats.recursive_size < 64 * 300);
+        // 允许 2 倍预算外加计时与调度的余量
+        assert!(started.elapsed() < budget * 2 + Duration::from_millis(50));
+
+        let stats =
+            quick_dir_stats_with_limit(root.to_str().unwrap(), Duration::from_secs(10), 500)
+                .unwrap();
+        assert!(stats.partial);
+        assert!(stats.scanned_entries <= 500);
+    }
+
+    #[test]
+    fn test_rejects_missing_path_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        fs::write(&file, b"x").unwrap();
+        for path in [dir.path().join("missing"), file] {
+            let err =
+                quick_dir_stats(path.to_str().unwrap(), Duration::from_millis(200)).unwrap_err();
+            assert!(matches!(err, DiskAnalyzerError::InvalidPath(_)));
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! 磁盘活动热度：按扫描根下的顶层目录汇总最近 24 小时、7 天内修改的文件，
+//! 用于回答「这周是什么在往磁盘里写数据」。
+//!
+//! 只使用扫描时已读取的修改时间，不再额外读取元数据：目录遍历由结果树汇总
+//! （[`recent_activity_from_tree`]，只计大小的目录与超出展开深度的部分没有节点，不计入），
+//! MFT 扫描由记录集合汇总（[`recent_activity_from_arena`]，包含卷上每一个文件）。
+//! 没有修改时间的文件不计入；修改时间晚于当前时间的文件按刚刚修改计。
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ai_disk_domain::{FileNode, RecentActivityEntry};
+
+use crate::display_path::DisplayPath;
+use crate::record_arena::{RecordArena, ROOT};
+
+/// 结果中最多保留的目录数
+pub const RECENT_ACTIVITY_LIMIT: usize = 50;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+/// 当前 Unix 时间（秒）
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 一个顶层目录的累计值
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    bytes_24h: u64,
+    bytes_7d: u64,
+    file_count_24h: u64,
+    /// 7 天内修改的文件数，为 0 的目录不出现在结果中
+    file_count_7d: u64,
+}
+
+impl Tally {
+    /// 计入一个 7 天内修改的文件
+    fn add(&mut self, size: u64, modified: u64, now: u64) {
+        let age = now.saturating_sub(modified);
+        if age <= DAY_SECS {
+            self.bytes_24h = self.bytes_24h.saturating_add(size);
+            self.file_count_24h += 1;
+        }
+        if age <= WEEK_SECS {
+            self.bytes_7d = self.bytes_7d.saturating_add(size);
+            self.file_count_7d += 1;
+        }
+    }
+}
+
+/// 文件的修改时间是否在最近 7 天内（含边界）
+fn is_recent(modified: u64, now: u64) -> bool {
+    now.saturating_sub(modified) <= WEEK_SECS
+}
+
+/// 按 24 小时字节数、7 天字节数降序（再按路径）排序，保留前 [`RECENT_ACTIVITY_LIMIT`] 项
+fn finish(tallies: impl IntoIterator<Item = (String, Tally)>) -> Vec<RecentActivityEntry> {
+    let mut entries: Vec<RecentActivityEntry> = tallies
+        .into_iter()
+        .map(|(path, tally)| RecentActivityEntry {
+            path,
+            bytes_24h: tally.bytes_24h,
+            bytes_7d: tally.bytes_7d,
+            file_count_24h: tally.file_count_24h,
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.bytes_24h
+            .cmp(&a.bytes_24h)
+            .then(b.bytes_7d.cmp(&a.bytes_7d))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    entries.truncate(RECENT_ACTIVITY_LIMIT);
+    entries
+}
+
+/// 文件节点在 7 天内修改时计入；目录节点忽略
+fn add_node(tally: &mut Tally, node: &FileNode, now: u64) {
+    match node.modified {
+        Some(modified) if !node.is_dir && is_recent(modified, now) => {
+            tally.add(node.size, modified, now);
+        }
+        _ => {}
+    }
+}
+
+/// 由扫描树汇总，`now` 为当前 Unix 时间（秒）
+pub fn recent_activity_from_tree(root: &FileNode, now: u64) -> Vec<RecentActivityEntry> {
+    let mut tallies = Vec::new();
+    let mut root_tally = Tally::default();
+    for top in &root.children {
+        if !top.is_dir {
+            add_node(&mut root_tally, top, now);
+            continue;
+        }
+        let mut tally = Tally::default();
+        let mut stack = vec![top];
+        while let Some(node) = stack.pop() {
+            add_node(&mut tally, node, now);
+            stack.extend(&node.children);
+        }
+        if tally.file_count_7d > 0 {
+            tallies.push((top.path.clone(), tally));
+        }
+    }
+    if root_tally.file_count_7d > 0 {
+        tallies.push((root.path.clone(), root_tally));
+    }
+    finish(tallies)
+}
+
+/// 由 MFT 扫描的记录集合汇总，`now` 为当前 Unix 时间（秒）
+pub fn recent_activity_from_arena(arena: &RecordArena, now: u64) -> Vec<RecentActivityEntry> {
+    if arena.is_empty() {
+        return Vec::new();
+    }
+    // 每条记录所属的顶层目录；父记录下标小于子记录，顺序一遍即可。根下的文件归入根
+    let mut top = vec![ROOT; arena.len()];
+    let mut tallies: HashMap<u32, Tally> = HashMap::new();
+    for idx in 1..arena.len() as u32 {
+        let parent = arena.parent(idx).unwrap_or(ROOT);
+        let meta = arena.meta(idx);
+        top[idx as usize] = match parent {
+            ROOT if meta.is_dir => idx,
+            ROOT => ROOT,
+            _ => top[parent as usize],
+        };
+        if meta.is_dir {
+            continue;
+        }
+        if let Some(modified) = meta.modified.filter(|&m| is_recent(m, now)) {
+            tallies
+                .entry(top[idx as usize])
+                .or_default()
+                .add(meta.size, modified, now);
+        }
+    }
+    finish(
+        tallies
+            .into_iter()
+            .map(|(idx, tally)| (DisplayPath::new(&arena.path(idx)).into_string(), tally)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_arena::{RecordArenaBuilder, RecordMeta};
+
+    const NOW: u64 = 1_800_000_000;
+
+    fn node(path: &str, size: u64, modified: Option<u64>, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, !children.is_empty())
+            .with_modified(modified)
+            .with_children(children)
+    }
+
+    /// 刚好 24 小时、24 小时零 1 秒、刚好 7 天、7 天零 1 秒、没有修改时间的文件各一个
+    fn boundary_files(dir: &str) -> Vec<(String, u64, Option<u64>)> {
+        vec![
+            (format!("{dir}/day.log"), 1, Some(NOW - DAY_SECS)),
+            (format!("{dir}/day_plus.log"), 10, Some(NOW - DAY_SECS - 1)),
+            (format!("{dir}/week.log"), 100, Some(NOW - WEEK_SECS)),
+            (format!("{dir}/old.log"), 1_000, Some(NOW - WEEK_SECS - 1)),
+            (format!("{dir}/unknown.log"), 10_000, None),
+            (format!("{dir}/future.log"), 100_000, Some(NOW + 60)),
+        ]
+    }
+
+    fn expected_boundary(path: &str) -> RecentActivityEntry {
+        RecentActivityEntry {
+            path: path.to_string(),
+            bytes_24h: 100_001,
+            bytes_7d: 100_111,
+            file_count_24h: 2,
+        }
+    }
+
+    #[test]
+    fn test_tree_boundary_buckets() {
+        let files = |dir: &str| {
+            boundary_files(dir)
+                .into_iter()
+                .map(|(path, size, modified)| node(&path, size, modified, vec![]))
+                .collect::<Vec<_>>()
+        };
+        let root = node(
+            "/r",
+            0,
+            None,
+            vec![
+                node(
+                    "/r/a",
+                    0,
+                    None,
+                    vec![node("/r/a/deep", 0, None, files("/r/a/deep"))],
+                ),
+                node(
+                    "/r/quiet",
+                    0,
+                    None,
+                    vec![node("/r/quiet/x", 5, Some(0), vec![])],
+                ),
+                node("/r/top.bin", 7, Some(NOW), vec![]),
+            ],
+        );
+        let activity = recent_activity_from_tree(&root, NOW);
+        assert_eq!(
+            activity,
+            vec![
+                expected_boundary("/r/a"),
+                RecentActivityEntry {
+                    path: "/r".to_string(),
+                    bytes_24h: 7,
+                    bytes_7d: 7,
+                    file_count_24h: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arena_boundary_buckets_match_tree() {
+        let mut builder = RecordArenaBuilder::new("/r", '/');
+        for (path, size, modified) in boundary_files("/r/a/deep") {
+            let meta = RecordMeta {
+                size,
+                modified,
+                ..RecordMeta::default()
+            };
+            builder.insert(&path, meta).unwrap();
+        }
+        let dir = RecordMeta {
+            is_dir: true,
+            ..RecordMeta::default()
+        };
+        builder.insert("/r/quiet", dir).unwrap();
+        let old = RecordMeta {
+            size: 5,
+            modified: Some(0),
+            ..RecordMeta::default()
+        };
+        builder.insert("/r/quiet/x", old).unwrap();
+        let activity = recent_activity_from_arena(&builder.finish(), NOW);
+        assert_eq!(activity, vec![expected_boundary("/r/a")]);
+    }
+
+    #[test]
+    fn test_keeps_top_directories_by_recent_bytes() {
+        let dirs = (0..60u64)
+            .map(|i| {
+                let dir = format!("/r/d{i:02}");
+                let file = node(&format!("{dir}/f"), i + 1, Some(NOW - 10), vec![]);
+                node(&dir, 0, None, vec![file])
+            })
+            .collect();
+        let activity = recent_activity_from_tree(&node("/r", 0, None, dirs), NOW);
+        assert_eq!(activity.len(), RECENT_ACTIVITY_LIMIT);
+        assert_eq!(activity[0].path, "/r/d59");
+        assert_eq!(activity[0].bytes_24h, 60);
+        assert_eq!(activity[RECENT_ACTIVITY_LIMIT - 1].path, "/r/d10");
+        assert!(activity
+            .windows(2)
+            .all(|pair| pair[0].bytes_24h > pair[1].bytes_24h));
+    }
+
+    #[test]
+    fn test_walk_scan_fills_recent_activity() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("busy")).unwrap();
+        std::fs::create_dir_all(dir.path().join("idle")).unwrap();
+        std::fs::write(dir.path().join("busy/new.bin"), vec![0u8; 300]).unwrap();
+        let old = dir.path().join("idle/old.bin");
+        std::fs::write(&old, vec![0u8; 500]).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - std::time::Duration::from_secs(30 * DAY_SECS))
+            .unwrap();
+
+        let (result, _) = crate::scanner::scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            false,
+            false,
+            None,
+            None,
+            &crate::scanner::WalkOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(result.recent_activity.len(), 1);
+        let busy = &result.recent_activity[0];
+        assert!(busy.path.ends_with("busy"), "{}", busy.path);
+        assert_eq!(
+            (busy.bytes_24h, busy.bytes_7d, busy.file_count_24h),
+            (300, 300, 1)
+        );
+    }
+}
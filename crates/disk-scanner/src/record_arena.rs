@@ -0,0 +1,454 @@
+//! 扫描记录的紧凑存储：每条记录只保存名称与父记录下标，名称统一放在一个字符串缓冲区中，
+//! 完整路径在需要时（生成返回给前端的节点、前 N 大文件）由 [`RecordArena::path`] 现拼。
+//!
+//! 大卷上有数百万条记录，若每条都持有完整绝对路径（再加上按路径索引的子节点表与大小表），
+//! 路径字符串会成为内存占用的主体；这里每条记录约 80 字节加上名称本身。
+
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+
+use ai_disk_domain::FileAttributes;
+
+/// 根记录的下标
+pub const ROOT: u32 = 0;
+
+/// 单条记录的元数据
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordMeta {
+    pub size: u64,
+    pub is_dir: bool,
+    pub modified: Option<u64>,
+    /// 创建时间与最近访问时间（Unix 秒）。纪元零点本就视为没有，
+    /// 用 NonZeroU64 省去 Option 的标记，每条记录少占 16 字节
+    pub created: Option<NonZeroU64>,
+    pub accessed: Option<NonZeroU64>,
+    pub attributes: Option<FileAttributes>,
+    /// 命名数据流的大小合计（不含在 `size` 中），没有时为 0
+    pub streams_size: u64,
+    /// NTFS 压缩、重复数据删除或稀疏使实际占用比 `size` 少的字节数，没有时为 0
+    pub saved_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    parent: u32,
+    name_len: u32,
+    name_start: usize,
+    meta: RecordMeta,
+}
+
+/// 只读的记录集合，由 [`RecordArenaBuilder::finish`] 得到。父记录的下标总是小于子记录
+#[derive(Debug, Clone)]
+pub struct RecordArena {
+    root_path: String,
+    separator: char,
+    names: String,
+    entries: Vec<Entry>,
+    /// 子记录表（按父记录分组，组内保持插入顺序）：`child_start[i]..child_start[i + 1]`
+    child_start: Vec<u32>,
+    child_list: Vec<u32>,
+}
+
+impl RecordArena {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 记录名称；根记录返回根路径本身
+    pub fn name(&self, idx: u32) -> &str {
+        let entry = &self.entries[idx as usize];
+        &self.names[entry.name_start..entry.name_start + entry.name_len as usize]
+    }
+
+    pub fn parent(&self, idx: u32) -> Option<u32> {
+        (idx != ROOT).then(|| self.entries[idx as usize].parent)
+    }
+
+    pub fn meta(&self, idx: u32) -> &RecordMeta {
+        &self.entries[idx as usize].meta
+    }
+
+    pub fn children(&self, idx: u32) -> &[u32] {
+        let i = idx as usize;
+        &self.child_list[self.child_start[i] as usize..self.child_start[i + 1] as usize]
+    }
+
+    /// 拼出完整路径（根路径 + 各级名称）
+    pub fn path(&self, idx: u32) -> String {
+        if idx == ROOT {
+            return self.root_path.clone();
+        }
+        let mut chain = Vec::new();
+        let mut cur = idx;
+        while cur != ROOT {
+            chain.push(cur);
+            cur = self.entries[cur as usize].parent;
+        }
+        let base = self.root_path.trim_end_matches(self.separator);
+        let len = base.len() + chain.iter().map(|&i| self.name(i).len() + 1).sum::<usize>();
+        let mut path = String::with_capacity(len);
+        path.push_str(base);
+        for &i in chain.iter().rev() {
+            path.push(self.separator);
+            path.push_str(self.name(i));
+        }
+        path
+    }
+
+    /// 每条记录的递归大小（自身大小 + 所有后代），下标与记录一致
+    pub fn recursive_sizes(&self) -> Vec<u64> {
+        self.recursive_sum(|meta| meta.size)
+    }
+
+    /// 每条记录的递归命名数据流大小，下标与记录一致
+    pub fn recursive_streams_sizes(&self) -> Vec<u64> {
+        self.recursive_sum(|meta| meta.streams_size)
+    }
+
+    /// 每条记录的递归节省字节数（见 [`RecordMeta::saved_bytes`]），下标与记录一致
+    pub fn recursive_saved_bytes(&self) -> Vec<u64> {
+        self.recursive_sum(|meta| meta.saved_bytes)
+    }
+
+    /// 每条记录的后代中最近的修改时间（不含自身，见 `FileNode::latest_child_modified`），
+    /// 没有后代或后代都没有修改时间时为 0，下标与记录一致
+    pub fn recursive_latest_modified(&self) -> Vec<u64> {
+        let mut latest = vec![0u64; self.entries.len()];
+        for idx in (1..self.entries.len()).rev() {
+            let parent = self.entries[idx].parent as usize;
+            let own = self.entries[idx].meta.modified.unwrap_or(0);
+            latest[parent] = latest[parent].max(own).max(latest[idx]);
+        }
+        latest
+    }
+
+    fn recursive_sum(&self, value: impl Fn(&RecordMeta) -> u64) -> Vec<u64> {
+        let mut sums: Vec<u64> = self.entries.iter().map(|e| value(&e.meta)).collect();
+        // 父记录下标小于子记录，倒序一遍即可把大小累加到所有祖先
+        for idx in (1..self.entries.len()).rev() {
+            let parent = self.entries[idx].parent as usize;
+            sums[parent] = sums[parent].saturating_add(sums[idx]);
+        }
+        sums
+    }
+
+    /// 按大小取前 N 个文件（不含目录）的下标，大小降序
+    pub fn largest_files(&self, n: usize) -> Vec<u32> {
+        let by_size_desc = |a: &u32, b: &u32| self.meta(*b).size.cmp(&self.meta(*a).size);
+        let mut files: Vec<u32> = (0..self.entries.len() as u32)
+            .filter(|&i| !self.meta(i).is_dir)
+            .collect();
+        if files.len() > n && n > 0 {
+            files.select_nth_unstable_by(n - 1, by_size_desc);
+        }
+        files.truncate(n);
+        files.sort_by(by_size_desc);
+        files
+    }
+}
+
+/// 逐条插入完整路径来构建 [`RecordArena`]。插入顺序任意：父目录尚未出现时先建占位记录，
+/// 目录记录到达后再补上元数据
+pub struct RecordArenaBuilder {
+    arena: RecordArena,
+    /// (父记录, 名称哈希) -> 目录记录；哈希冲突时退回 `colliding`
+    dirs: HashMap<(u32, u64), u32>,
+    colliding: HashMap<(u32, Box<str>), u32>,
+    /// 同一目录下的记录通常连续出现，缓存上一次解析的父目录
+    last_dir: Option<(String, u32)>,
+}
+
+impl RecordArenaBuilder {
+    /// `root_path` 为扫描根（如 `C:\`），`separator` 为路径分隔符
+    pub fn new(root_path: &str, separator: char) -> Self {
+        let mut arena = RecordArena {
+            root_path: root_path.to_string(),
+            separator,
+            names: String::new(),
+            entries: Vec::new(),
+            child_start: Vec::new(),
+            child_list: Vec::new(),
+        };
+        arena.names.push_str(root_path);
+        arena.entries.push(Entry {
+            parent: ROOT,
+            name_len: root_path.len() as u32,
+            name_start: 0,
+            meta: RecordMeta {
+                is_dir: true,
+                ..RecordMeta::default()
+            },
+        });
+        Self {
+            arena,
+            dirs: HashMap::new(),
+            colliding: HashMap::new(),
+            last_dir: None,
+        }
+    }
+
+    /// 插入一条记录，返回其下标；不在根路径之下的路径返回 None
+    pub fn insert(&mut self, path: &str, meta: RecordMeta) -> Option<u32> {
+        let sep = self.arena.separator;
+        let path = path.trim_end_matches(sep);
+        if path.eq_ignore_ascii_case(self.root_trim()) {
+            self.arena.entries[ROOT as usize].meta = meta;
+            return Some(ROOT);
+        }
+        let (parent_path, name) = path.rsplit_once(sep)?;
+        if name.is_empty() {
+            return None;
+        }
+        let parent = self.dir_index(parent_path)?;
+        if meta.is_dir {
+            let idx = self.child_dir(parent, name);
+            self.arena.entries[idx as usize].meta = meta;
+            Some(idx)
+        } else {
+            Some(self.push(parent, name, meta))
+        }
+    }
+
+    /// 生成子记录表并丢弃构建期的目录索引
+    pub fn finish(self) -> RecordArena {
+        let mut arena = self.arena;
+        let n = arena.entries.len();
+        let mut child_start = vec![0u32; n + 1];
+        for entry in arena.entries.iter().skip(1) {
+            child_start[entry.parent as usize + 1] += 1;
+        }
+        for i in 0..n {
+            child_start[i + 1] += child_start[i];
+        }
+        let mut fill = child_start.clone();
+        let mut child_list = vec![0u32; n.saturating_sub(1)];
+        for (idx, entry) in arena.entries.iter().enumerate().skip(1) {
+            let slot = &mut fill[entry.parent as usize];
+            child_list[*slot as usize] = idx as u32;
+            *slot += 1;
+        }
+        arena.names.shrink_to_fit();
+        arena.entries.shrink_to_fit();
+        arena.child_start = child_start;
+        arena.child_list = child_list;
+        arena
+    }
+
+    fn root_trim(&self) -> &str {
+        self.arena.root_path.trim_end_matches(self.arena.separator)
+    }
+
+    /// 去掉根路径前缀；根路径按 ASCII 忽略大小写匹配（盘符大小写可能不一致）
+    fn strip_root<'p>(&self, path: &'p str) -> Option<&'p str> {
+        let root = self.root_trim();
+        let head = path.get(..root.len())?;
+        head.eq_ignore_ascii_case(root).then(|| &path[root.len()..])
+    }
+
+    fn dir_index(&mut self, dir: &str) -> Option<u32> {
+        if dir.eq_ignore_ascii_case(self.root_trim()) {
+            return Some(ROOT);
+        }
+        if let Some((cached, idx)) = &self.last_dir {
+            if cached == dir {
+                return Some(*idx);
+            }
+        }
+        let sep = self.arena.separator;
+        let rest = self.strip_root(dir)?.strip_prefix(sep)?;
+        let mut idx = ROOT;
+        for component in rest.split(sep) {
+            idx = self.child_dir(idx, component);
+        }
+        self.last_dir = Some((dir.to_string(), idx));
+        Some(idx)
+    }
+
+    /// 查找或创建 parent 下名为 name 的目录记录
+    fn child_dir(&mut self, parent: u32, name: &str) -> u32 {
+        let key = (parent, name_hash(name));
+        match self.dirs.get(&key) {
+            Some(&idx) if self.arena.name(idx) == name => idx,
+            Some(_) => {
+                let collision_key = (parent, Box::from(name));
+                if let Some(&idx) = self.colliding.get(&collision_key) {
+                    return idx;
+                }
+                let idx = self.push(parent, name, dir_placeholder());
+                self.colliding.insert(collision_key, idx);
+                idx
+            }
+            None => {
+                let idx = self.push(parent, name, dir_placeholder());
+                self.dirs.insert(key, idx);
+                idx
+            }
+        }
+    }
+
+    fn push(&mut self, parent: u32, name: &str, meta: RecordMeta) -> u32 {
+        let idx = self.arena.entries.len() as u32;
+        let name_start = self.arena.names.len();
+        self.arena.names.push_str(name);
+        self.arena.entries.push(Entry {
+            parent,
+            name_len: name.len() as u32,
+            name_start,
+            meta,
+        });
+        idx
+    }
+}
+
+fn dir_placeholder() -> RecordMeta {
+    RecordMeta {
+        is_dir: true,
+        ..RecordMeta::default()
+    }
+}
+
+/// FNV-1a
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64) -> RecordMeta {
+        RecordMeta {
+            size,
+            ..RecordMeta::default()
+        }
+    }
+
+    fn dir(modified: u64) -> RecordMeta {
+        RecordMeta {
+            is_dir: true,
+            modified: Some(modified),
+            ..RecordMeta::default()
+        }
+    }
+
+    #[test]
+    fn test_paths_are_rebuilt_from_names() {
+        let mut builder = RecordArenaBuilder::new(r"C:\", '\\');
+        // 文件先于其父目录出现
+        let a = builder.insert(r"C:\Users\me\a.txt", file(10)).unwrap();
+        let users = builder.insert(r"C:\Users", dir(7)).unwrap();
+        let b = builder.insert(r"C:\Users\me\b.txt", file(5)).unwrap();
+        let page = builder.insert(r"C:\pagefile.sys", file(100)).unwrap();
+        assert_eq!(builder.insert(r"D:\x", file(1)), None);
+        let arena = builder.finish();
+
+        assert_eq!(arena.path(a), r"C:\Users\me\a.txt");
+        assert_eq!(arena.path(b), r"C:\Users\me\b.txt");
+        assert_eq!(arena.path(page), r"C:\pagefile.sys");
+        assert_eq!(arena.path(ROOT), r"C:\");
+        assert_eq!(arena.path(users), r"C:\Users");
+        assert_eq!(arena.name(a), "a.txt");
+        // 占位目录在目录记录到达后补上元数据
+        assert_eq!(arena.meta(users).modified, Some(7));
+        assert_eq!(arena.children(ROOT), [users, page]);
+        let me = arena.parent(a).unwrap();
+        assert_eq!(arena.children(me), [a, b]);
+        assert_eq!(arena.parent(me), Some(users));
+        assert_eq!(arena.parent(ROOT), None);
+    }
+
+    #[test]
+    fn test_recursive_sizes_and_largest_files() {
+        let mut builder = RecordArenaBuilder::new("/r", '/');
+        builder.insert("/r/x/y/big.bin", file(50));
+        builder.insert(
+            "/r/x/small.bin",
+            RecordMeta {
+                streams_size: 7,
+                ..file(3)
+            },
+        );
+        builder.insert("/r/z.bin", file(20));
+        builder.insert("/r", RecordMeta { size: 1, ..dir(0) });
+        let arena = builder.finish();
+        let sizes = arena.recursive_sizes();
+        assert_eq!(sizes[ROOT as usize], 74);
+        let x = arena.children(ROOT)[0];
+        assert_eq!(arena.name(x), "x");
+        assert_eq!(sizes[x as usize], 53);
+        // 数据流大小单独累加，不影响递归大小
+        let streams = arena.recursive_streams_sizes();
+        assert_eq!(streams[ROOT as usize], 7);
+        assert_eq!(streams[x as usize], 7);
+        assert_eq!(streams[arena.children(ROOT)[1] as usize], 0);
+
+        let top: Vec<String> = arena
+            .largest_files(2)
+            .into_iter()
+            .map(|i| arena.path(i))
+            .collect();
+        assert_eq!(top, ["/r/x/y/big.bin", "/r/z.bin"]);
+        assert_eq!(arena.largest_files(10).len(), 3);
+        assert!(arena.largest_files(0).is_empty());
+    }
+
+    #[test]
+    fn test_latest_modified_bubbles_past_older_ancestors() {
+        let mut builder = RecordArenaBuilder::new("/r", '/');
+        builder.insert("/r/proj", dir(100));
+        builder.insert("/r/proj/src", dir(110));
+        builder.insert(
+            "/r/proj/src/deep/main.rs",
+            RecordMeta {
+                modified: Some(900),
+                ..file(1)
+            },
+        );
+        builder.insert(
+            "/r/old.txt",
+            RecordMeta {
+                modified: Some(50),
+                ..file(1)
+            },
+        );
+        let arena = builder.finish();
+        let latest = arena.recursive_latest_modified();
+        let proj = arena.children(ROOT)[0];
+        let src = arena.children(proj)[0];
+        let deep = arena.children(src)[0];
+        assert_eq!(arena.name(proj), "proj");
+        for idx in [ROOT, proj, src, deep] {
+            assert_eq!(latest[idx as usize], 900, "{}", arena.path(idx));
+        }
+        // 文件没有后代
+        assert_eq!(latest[arena.children(deep)[0] as usize], 0);
+    }
+
+    #[test]
+    fn test_root_matches_case_insensitively() {
+        let mut builder = RecordArenaBuilder::new(r"C:\", '\\');
+        assert_eq!(builder.insert(r"c:\", dir(3)), Some(ROOT));
+        let f = builder.insert(r"c:\Users\a.txt", file(1)).unwrap();
+        let g = builder.insert(r"C:\Users\b.txt", file(2)).unwrap();
+        let arena = builder.finish();
+        assert_eq!(arena.meta(ROOT).modified, Some(3));
+        assert_eq!(arena.parent(f), arena.parent(g));
+        assert_eq!(arena.path(f), r"C:\Users\a.txt");
+        assert_eq!(arena.recursive_sizes()[ROOT as usize], 3);
+    }
+
+    #[test]
+    fn test_unix_root() {
+        let mut builder = RecordArenaBuilder::new("/", '/');
+        let f = builder.insert("/etc/hosts", file(1)).unwrap();
+        let arena = builder.finish();
+        assert_eq!(arena.path(f), "/etc/hosts");
+        assert_eq!(arena.path(ROOT), "/");
+    }
+}
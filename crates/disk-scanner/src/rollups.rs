@@ -0,0 +1,197 @@
+//! 供 Treemap 着色的目录汇总：文件按扩展名识别类型，目录记录子树中占字节数最多的类型
+//! （`dominant_category`）与文件的最新、最旧修改时间。
+//!
+//! MFT 扫描与目录遍历都在建树并排除应用自身占用之后对结果树调用 [`fill_rollups`]，
+//! 同一棵树得到的汇总完全相同；剪枝或只计大小（shallow）的部分没有节点，不参与汇总。
+
+use ai_disk_domain::{FileCategory, FileNode};
+
+/// 参与汇总的类型，同时是字节数相同时的优先顺序
+const TYPE_CATEGORIES: [FileCategory; 6] = [
+    FileCategory::Video,
+    FileCategory::Audio,
+    FileCategory::Image,
+    FileCategory::Document,
+    FileCategory::Archive,
+    FileCategory::Other,
+];
+
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "wmv", "flv", "webm", "m4v", "mpg", "mpeg", "ts", "m2ts", "3gp",
+];
+
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "wav", "aac", "ogg", "m4a", "wma", "opus", "aiff", "ape",
+];
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "heif", "tif", "tiff", "svg", "raw", "cr2",
+    "nef", "arw", "dng", "psd",
+];
+
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "rtf", "txt", "md",
+    "csv", "epub", "pages", "numbers", "key",
+];
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "iso", "img", "dmg", "cab",
+];
+
+/// 按扩展名识别文件类型，未知扩展名为 Other
+pub(crate) fn file_type_category(name: &str) -> FileCategory {
+    let Some((_, ext)) = name.rsplit_once('.') else {
+        return FileCategory::Other;
+    };
+    let ext = ext.to_ascii_lowercase();
+    let tables = [
+        (VIDEO_EXTENSIONS, FileCategory::Video),
+        (AUDIO_EXTENSIONS, FileCategory::Audio),
+        (IMAGE_EXTENSIONS, FileCategory::Image),
+        (DOCUMENT_EXTENSIONS, FileCategory::Document),
+        (ARCHIVE_EXTENSIONS, FileCategory::Archive),
+    ];
+    tables
+        .iter()
+        .find(|(extensions, _)| extensions.contains(&ext.as_str()))
+        .map_or(FileCategory::Other, |(_, category)| *category)
+}
+
+/// 子树的汇总：各类型的字节数（下标同 [`TYPE_CATEGORIES`]）与文件修改时间的范围
+#[derive(Default)]
+struct Rollup {
+    bytes: [u64; TYPE_CATEGORIES.len()],
+    newest: Option<u64>,
+    oldest: Option<u64>,
+}
+
+impl Rollup {
+    fn merge(&mut self, other: &Rollup) {
+        for (total, bytes) in self.bytes.iter_mut().zip(other.bytes) {
+            *total = total.saturating_add(bytes);
+        }
+        self.newest = self.newest.max(other.newest);
+        self.oldest = match (self.oldest, other.oldest) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// 字节数最多的类型；字节数相同时取 [`TYPE_CATEGORIES`] 中靠前的，全为 0 时为 None
+    fn dominant(&self) -> Option<FileCategory> {
+        let (index, bytes) = self
+            .bytes
+            .iter()
+            .enumerate()
+            .max_by(|(ia, a), (ib, b)| a.cmp(b).then(ib.cmp(ia)))?;
+        (*bytes > 0).then_some(TYPE_CATEGORIES[index])
+    }
+}
+
+/// 为整棵树填充 `dominant_category`、`newest_modified` 与 `oldest_modified`
+pub(crate) fn fill_rollups(root: &mut FileNode) {
+    fill(root);
+}
+
+fn fill(node: &mut FileNode) -> Rollup {
+    let mut rollup = Rollup::default();
+    if node.is_dir {
+        for child in &mut node.children {
+            rollup.merge(&fill(child));
+        }
+        node.dominant_category = rollup.dominant();
+        node.newest_modified = rollup.newest;
+        node.oldest_modified = rollup.oldest;
+    } else {
+        let category = file_type_category(&node.name);
+        let index = TYPE_CATEGORIES
+            .iter()
+            .position(|c| *c == category)
+            .unwrap_or(TYPE_CATEGORIES.len() - 1);
+        rollup.bytes[index] = node.size;
+        rollup.newest = node.modified;
+        rollup.oldest = node.modified;
+        node.dominant_category = Some(category);
+    }
+    rollup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, size: u64, modified: u64) -> FileNode {
+        FileNode::new(format!("/v/{}", name), size, false).with_modified(Some(modified))
+    }
+
+    fn dir(name: &str, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(
+            format!("/v/{}", name),
+            children.iter().map(|c| c.size).sum(),
+            true,
+        )
+        .with_children(children)
+    }
+
+    #[test]
+    fn test_video_heavy_folder_rolls_up() {
+        // 60% 视频、40% 文档
+        let mut root = dir(
+            "v",
+            vec![
+                dir(
+                    "media",
+                    vec![
+                        file("trip.MP4", 400, 1_700_000_000),
+                        file("clip.mkv", 200, 1_600_000_000),
+                        file("report.pdf", 250, 1_650_000_000),
+                        file("notes.docx", 150, 1_710_000_000),
+                    ],
+                ),
+                dir("empty", vec![]),
+                file("a.zip", 500, 1_500_000_000),
+            ],
+        );
+        fill_rollups(&mut root);
+
+        let media = &root.children[0];
+        assert_eq!(media.dominant_category, Some(FileCategory::Video));
+        assert_eq!(media.newest_modified, Some(1_710_000_000));
+        assert_eq!(media.oldest_modified, Some(1_600_000_000));
+        assert_eq!(
+            media.children[2].dominant_category,
+            Some(FileCategory::Document)
+        );
+        assert_eq!(media.children[2].newest_modified, None);
+
+        let empty = &root.children[1];
+        assert_eq!(empty.dominant_category, None);
+        assert_eq!(empty.newest_modified, None);
+
+        // 根目录：视频 600、文档 400、压缩包 500
+        assert_eq!(root.dominant_category, Some(FileCategory::Video));
+        assert_eq!(root.newest_modified, Some(1_710_000_000));
+        assert_eq!(root.oldest_modified, Some(1_500_000_000));
+    }
+
+    #[test]
+    fn test_ties_and_unknown_extensions() {
+        let mut root = dir(
+            "v",
+            vec![
+                file("song.flac", 100, 10),
+                file("cover.png", 100, 20),
+                file("Makefile", 0, 30),
+            ],
+        );
+        fill_rollups(&mut root);
+        // 字节数相同取靠前的类型
+        assert_eq!(root.dominant_category, Some(FileCategory::Audio));
+        assert_eq!(
+            root.children[2].dominant_category,
+            Some(FileCategory::Other)
+        );
+        assert_eq!(file_type_category("archive.tar.gz"), FileCategory::Archive);
+        assert_eq!(file_type_category(".bashrc"), FileCategory::Other);
+    }
+}
@@ -1,17 +1,38 @@
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Instant, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use ai_disk_common::DiskAnalyzerError;
-use ai_disk_domain::{FileNode, ScanResult};
-use rayon::prelude::*;
+use ai_disk_common::{
+    metrics, DiskAnalyzerError, InternalPaths, ProgressPhase, METRIC_SCAN_DURATION_MS,
+};
+use ai_disk_domain::{FileAttributes, FileNode, MediaType, ScanResult, SizeSource};
 
-const MAX_DEPTH: usize = 10;
-const MAX_CHILDREN_PER_DIR: usize = 500;
+use crate::disk_health::detect_media_type;
+use crate::ignore_rules::{GitignoreOptions, IgnoreRules};
+use crate::internal_exclusion::exclude_internal_paths;
+use crate::known_folders::{system_known_folders, tag_known_folders};
+use crate::mft_availability::{explain_mft_availability, MftPreconditions, MftRoute};
+use crate::mount_points::{is_volume_root, FsBoundary};
+use crate::node::finalize_tree;
+use crate::parallel::{map_collect, ScanPool};
+use crate::pause::PauseToken;
+use crate::physical_size::{compressed_file_size, fill_physical_sizes};
+use crate::recent_activity::{now_secs, recent_activity_from_tree};
+use crate::rollups::fill_rollups;
+use crate::throttle::{on_dir_listed, BackgroundScan, TokenBucket};
+use crate::trash::{is_volume_trash_name, tag_trash, user_trash_dirs};
+
+pub(crate) const MAX_DEPTH: usize = 10;
+pub(crate) const MAX_CHILDREN_PER_DIR: usize = 500;
+
+/// 未指定并发度时扫描线程数的上限，避免占满所有核心拖慢界面进程
+pub const DEFAULT_SCAN_THREADS_MAX: usize = 8;
+/// 机械硬盘的默认扫描线程数：深度并行 IO 只会加剧寻道
+pub const HDD_SCAN_THREADS: usize = 2;
 
 /// Windows: 文件或目录损坏且无法读取，遇到时跳过该路径继续扫描
 #[cfg(windows)]
-fn is_corruption_io_error(e: &std::io::Error) -> bool {
+pub(crate) fn is_corruption_io_error(e: &std::io::Error) -> bool {
     match e.raw_os_error() {
         Some(1392) => true, // ERROR_FILE_CORRUPT
         Some(1393) => true, // ERROR_DISK_CORRUPT
@@ -20,7 +41,7 @@ fn is_corruption_io_error(e: &std::io::Error) -> bool {
 }
 
 #[cfg(not(windows))]
-fn is_corruption_io_error(_e: &std::io::Error) -> bool {
+pub(crate) fn is_corruption_io_error(_e: &std::io::Error) -> bool {
     false
 }
 
@@ -41,42 +62,109 @@ pub(crate) const SHALLOW_DIR_NAMES: &[&str] = &[
     "jspm_packages",
 ];
 
+/// 是否为只统计大小、不展开子项的目录名（大小写不敏感）
+pub(crate) fn is_shallow_dir_name(name: &str) -> bool {
+    SHALLOW_DIR_NAMES
+        .iter()
+        .any(|&s| s.eq_ignore_ascii_case(name))
+}
+
+/// 建树时只统计总大小、不展开子项的目录：shallow 目录（`shallow_dirs` 为 true 时）与按卷存放的回收站
+pub(crate) fn is_collapsed_dir_name(name: &str, shallow_dirs: bool) -> bool {
+    (shallow_dirs && is_shallow_dir_name(name)) || is_volume_trash_name(name)
+}
+
+/// 读取目录时可以静默跳过的错误：无权限、路径已不存在（失效的符号链接、扫描中被删除）、磁盘损坏
+pub(crate) fn is_skippable_dir_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::NotFound
+    ) || is_corruption_io_error(e)
+}
+
 pub(crate) type ProgressCb = Box<dyn Fn(u64, &str) + Send + Sync>;
 
 /// 可共享的进度回调，用于 MFT 加载时在后台线程中上报进度。
 pub(crate) type ProgressCbArc = std::sync::Arc<ProgressCb>;
 
+/// 一次目录遍历中各层共用的参数
+pub(crate) struct WalkContext<'a> {
+    /// 已统计的文件数，用于进度
+    pub counter: &'a AtomicU64,
+    pub progress: Option<&'a ProgressCb>,
+    pub shallow_dirs: bool,
+    /// 只统计与扫描根同一文件系统时的边界；None 时跨越挂载点继续遍历
+    pub fs_boundary: Option<FsBoundary>,
+}
+
+impl WalkContext<'_> {
+    /// 目录 `dir` 是否为需要停下的其他文件系统挂载点
+    pub(crate) fn crosses_filesystem(&self, dir: &Path) -> bool {
+        self.fs_boundary.is_some_and(|b| b.is_mount_point(dir))
+    }
+}
+
+/// 扫描的可选行为；MFT 扫描只读取单个卷，只使用暂停令牌、`include_streams` 与 `treemap_rollups`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// 按各级 .gitignore（及指定的忽略文件）跳过子项，跳过的大小记入 `ignored_bytes`（见 [`crate::ignore_rules`]）
+    pub gitignore: Option<GitignoreOptions>,
+    /// 是否在其他卷、网络共享的挂载点处停下（见 [`crate::mount_points`]）；
+    /// None 时仅在扫描卷根时开启
+    pub same_filesystem_only: Option<bool>,
+    /// 调用方持有的暂停令牌，暂停后扫描线程阻塞等待（见 [`crate::pause`]）；MFT 扫描同样生效
+    pub pause: Option<PauseToken>,
+    /// 是否把命名数据流（NTFS 备用数据流）的大小计入节点大小与 `total_size`；
+    /// 仅 MFT 扫描统计数据流，无论是否计入都记在 `FileNode::streams_size` 中
+    pub include_streams: bool,
+    /// 是否为 Treemap 着色填充各节点的 `dominant_category` 与目录的修改时间范围（见 [`crate::rollups`]）；
+    /// 每个节点多占一些内存，默认关闭
+    pub treemap_rollups: bool,
+}
+
 /// 仅统计目录总大小，不构建子树（用于 shallow 目录）
-fn dir_size_only(
+pub(crate) fn dir_size_only(
     path: &Path,
     counter: &AtomicU64,
     progress: Option<&ProgressCb>,
 ) -> Result<u64, DiskAnalyzerError> {
+    dir_size_and_latest(path, counter, progress).map(|(size, _)| size)
+}
+
+/// 与 [`dir_size_only`] 相同，另外返回子树中最近的修改时间（见 `FileNode::latest_child_modified`）
+pub(crate) fn dir_size_and_latest(
+    path: &Path,
+    counter: &AtomicU64,
+    progress: Option<&ProgressCb>,
+) -> Result<(u64, Option<u64>), DiskAnalyzerError> {
     let mut total: u64 = 0;
+    let mut latest: Option<u64> = None;
     let entries = match std::fs::read_dir(path) {
         Ok(e) => e,
-        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
-            return Ok(0);
-        }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            // 路径不存在（符号链接失效、文件被删除等），跳过
-            return Ok(0);
-        }
-        Err(e) if is_corruption_io_error(&e) => {
-            return Ok(0); // 损坏，跳过该目录
-        }
+        Err(e) if is_skippable_dir_error(&e) => return Ok((0, None)),
         Err(e) => return Err(DiskAnalyzerError::Io(e)),
     };
+    let mut listed = 0usize;
     for entry in entries.filter_map(|e| e.ok()) {
+        listed += 1;
         let path = entry.path();
+        let metadata = entry.metadata().ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        latest = latest.max(modified);
         if path.is_dir() {
-            if let Ok(size) = dir_size_only(&path, counter, progress) {
+            if let Ok((size, dir_latest)) = dir_size_and_latest(&path, counter, progress) {
                 total = total.saturating_add(size);
+                latest = latest.max(dir_latest);
             }
         } else {
-            total = total.saturating_add(entry.metadata().map(|m| m.len()).unwrap_or(0));
+            total = total.saturating_add(metadata.map(|m| m.len()).unwrap_or(0));
         }
     }
+    on_dir_listed(listed);
     counter.fetch_add(1, Ordering::Relaxed);
     if let Some(ref cb) = progress {
         cb(
@@ -84,30 +172,29 @@ fn dir_size_only(
             path.display().to_string().as_str(),
         );
     }
-    Ok(total)
+    Ok((total, latest))
 }
 
-fn build_tree(
+/// 子项中最近的修改时间：各子项自身与其子树中修改时间的最大值
+pub(crate) fn latest_modified_of(children: &[FileNode]) -> Option<u64> {
+    children
+        .iter()
+        .map(|child| child.modified.max(child.latest_child_modified))
+        .max()
+        .flatten()
+}
+
+/// 通用目录遍历（`read_dir` + 逐项 metadata），各平台均可用，也是快速后端失败时的回退。
+/// `ignore` 为父目录生效的忽略规则，为 None 时不读取 .gitignore
+pub(crate) fn build_tree(
     path: &Path,
     name: &str,
     depth: usize,
-    counter: &AtomicU64,
-    progress: Option<&ProgressCb>,
-    shallow_dirs: bool,
+    ctx: &WalkContext,
+    ignore: Option<&IgnoreRules>,
 ) -> Result<(FileNode, u64), DiskAnalyzerError> {
     let metadata = match std::fs::metadata(path) {
         Ok(m) => m,
-        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
-            return Err(DiskAnalyzerError::PermissionDenied(
-                path.display().to_string(),
-            ));
-        }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            return Err(DiskAnalyzerError::PermissionDenied(format!(
-                "{} [路径不存在]",
-                path.display()
-            )));
-        }
         Err(e) if is_corruption_io_error(&e) => {
             return Ok((
                 FileNode {
@@ -115,34 +202,26 @@ fn build_tree(
                     name: format!("{} [损坏]", name),
                     size: 0,
                     is_dir: false,
-                    modified: None,
-                    children: vec![],
+                    size_source: SizeSource::Unknown,
+                    ..Default::default()
                 },
                 0u64,
             ));
         }
-        Err(e) => return Err(DiskAnalyzerError::Io(e)),
+        Err(e) => return Err(walk_error(path, e)),
     };
 
     let is_dir = metadata.is_dir();
     let mut size = if is_dir { 0u64 } else { metadata.len() };
     let mut file_count = if is_dir { 0u64 } else { 1u64 };
     let mut children = Vec::new();
+    // 超过深度上限的目录不展开也不统计大小
+    let mut partial = is_dir && depth >= MAX_DEPTH;
+    let mut partial_size = partial;
 
     if is_dir && depth < MAX_DEPTH {
         let entries = match std::fs::read_dir(path) {
             Ok(iter) => iter,
-            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
-                return Err(DiskAnalyzerError::PermissionDenied(
-                    path.display().to_string(),
-                ));
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Err(DiskAnalyzerError::PermissionDenied(format!(
-                    "{} [路径不存在]",
-                    path.display()
-                )));
-            }
             Err(e) if is_corruption_io_error(&e) => {
                 return Ok((
                     FileNode {
@@ -155,132 +234,44 @@ fn build_tree(
                             .ok()
                             .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
                             .map(|d| d.as_secs()),
-                        children: vec![],
+                        created: created_secs(&metadata),
+                        accessed: accessed_secs(&metadata),
+                        attributes: file_attributes(name, &metadata),
+                        size_source: SizeSource::Unknown,
+                        ..Default::default()
                     },
                     0u64,
                 ));
             }
-            Err(e) => return Err(DiskAnalyzerError::Io(e)),
+            Err(e) => return Err(walk_error(path, e)),
         };
-        let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-
-        entries.sort_by(|a, b| {
-            let a_is_dir = a.path().is_dir();
-            let b_is_dir = b.path().is_dir();
-            match (a_is_dir, b_is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a
-                    .file_name()
-                    .cmp(&b.file_name())
-                    .then_with(|| a.path().as_os_str().cmp(b.path().as_os_str())),
-            }
-        });
-
-        let entries: Vec<_> = entries.into_iter().take(MAX_CHILDREN_PER_DIR).collect();
+        let rules = ignore.map(|r| r.enter(path));
+        let (entries, dropped) = list_children(entries, rules.as_ref());
+        partial = dropped;
+        partial_size = dropped;
 
         // 并行处理子项；shallow_dirs 开启时，常见包管理器/缓存目录只计大小不递归
-        let results: Vec<_> = entries
-            .par_iter()
-            .map(|entry| {
-                let child_path = entry.path();
-                let child_name = entry.file_name().to_string_lossy().to_string();
-                let is_shallow_dir = child_path.is_dir()
-                    && shallow_dirs
-                    && SHALLOW_DIR_NAMES
-                        .iter()
-                        .any(|&s| s.eq_ignore_ascii_case(&child_name));
-                let entry_modified = entry
-                    .metadata()
-                    .ok()
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs());
-                if is_shallow_dir {
-                    match dir_size_only(&child_path, counter, progress) {
-                        Ok(size) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: child_name.clone(),
-                                size,
-                                is_dir: true,
-                                modified: entry_modified,
-                                children: vec![],
-                            },
-                            1u64,
-                        )),
-                        Err(DiskAnalyzerError::PermissionDenied(_)) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [无权限]", child_name),
-                                size: 0,
-                                is_dir: true,
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [损坏]", child_name),
-                                size: 0,
-                                is_dir: true,
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(e) => Err(e),
-                    }
-                } else {
-                    match build_tree(
-                        &child_path,
-                        &child_name,
-                        depth + 1,
-                        counter,
-                        progress,
-                        shallow_dirs,
-                    ) {
-                        Ok((node, cnt)) => Ok((node, cnt)),
-                        Err(DiskAnalyzerError::PermissionDenied(_)) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [无权限]", child_name),
-                                size: 0,
-                                is_dir: child_path.is_dir(),
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok((
-                            FileNode {
-                                path: child_path.display().to_string(),
-                                name: format!("{} [损坏]", child_name),
-                                size: 0,
-                                is_dir: child_path.is_dir(),
-                                modified: None,
-                                children: vec![],
-                            },
-                            0u64,
-                        )),
-                        Err(e) => Err(e),
-                    }
-                }
-            })
-            .collect();
+        let results = map_collect(&entries, |entry| {
+            build_child(
+                &entry.path(),
+                &entry.file_name().to_string_lossy(),
+                depth + 1,
+                ctx,
+                rules.as_ref(),
+            )
+        });
 
         for r in results {
             let (node, cnt) = r?;
             size += node.size;
             file_count += cnt;
+            partial_size |= node.partial_size;
             children.push(node);
         }
 
-        counter.fetch_add(file_count, Ordering::Relaxed);
-        if let Some(ref cb) = progress {
-            let total_so_far = counter.load(Ordering::Relaxed);
+        ctx.counter.fetch_add(file_count, Ordering::Relaxed);
+        if let Some(ref cb) = ctx.progress {
+            let total_so_far = ctx.counter.load(Ordering::Relaxed);
             cb(total_so_far, path.display().to_string().as_str());
         }
     }
@@ -290,6 +281,7 @@ fn build_tree(
         .ok()
         .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
         .map(|d| d.as_secs());
+    let latest_child_modified = latest_modified_of(&children);
     Ok((
         FileNode {
             path: path.display().to_string(),
@@ -297,12 +289,212 @@ fn build_tree(
             size,
             is_dir,
             modified,
+            created: created_secs(&metadata),
+            accessed: accessed_secs(&metadata),
+            attributes: file_attributes(name, &metadata),
+            latest_child_modified,
+            partial_children: partial,
+            partial_size,
             children,
+            ..Default::default()
         },
         file_count,
     ))
 }
 
+/// 读取目录项并按「目录在前、再按名称」排序，最多保留 [`MAX_CHILDREN_PER_DIR`] 项；
+/// 给出 `ignore` 时先去掉被忽略的子项，它们不占用保留名额。
+/// 同时返回是否有子项被忽略或截断
+pub(crate) fn list_children(
+    entries: std::fs::ReadDir,
+    ignore: Option<&IgnoreRules>,
+) -> (Vec<std::fs::DirEntry>, bool) {
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+    let listed = entries.len();
+    on_dir_listed(listed);
+    if let Some(rules) = ignore {
+        entries = rules.retain(entries, |e| {
+            let path = e.path();
+            let is_dir = path.is_dir();
+            (path, is_dir)
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        let a_is_dir = a.path().is_dir();
+        let b_is_dir = b.path().is_dir();
+        match (a_is_dir, b_is_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a
+                .file_name()
+                .cmp(&b.file_name())
+                .then_with(|| a.path().as_os_str().cmp(b.path().as_os_str())),
+        }
+    });
+    entries.truncate(MAX_CHILDREN_PER_DIR);
+    let dropped = entries.len() < listed;
+    (entries, dropped)
+}
+
+/// 构建目录下的单个子项（`depth` 为子项自身的深度）；无权限或损坏的子项生成带标记的占位节点
+pub(crate) fn build_child(
+    child_path: &Path,
+    child_name: &str,
+    depth: usize,
+    ctx: &WalkContext,
+    ignore: Option<&IgnoreRules>,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    let is_dir = child_path.is_dir();
+    if is_dir && ctx.crosses_filesystem(child_path) {
+        return Ok(mount_point_node(child_path, child_name));
+    }
+    let is_shallow_dir = is_dir && is_collapsed_dir_name(child_name, ctx.shallow_dirs);
+    // 不跟随符号链接，与 DirEntry::metadata 一致
+    let entry_metadata = std::fs::symlink_metadata(child_path).ok();
+    let entry_modified = entry_metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    if is_shallow_dir {
+        match dir_size_and_latest(child_path, ctx.counter, ctx.progress) {
+            Ok((size, latest_child_modified)) => Ok((
+                FileNode {
+                    path: child_path.display().to_string(),
+                    name: child_name.to_string(),
+                    size,
+                    is_dir: true,
+                    modified: entry_modified,
+                    created: entry_metadata.as_ref().and_then(created_secs),
+                    accessed: entry_metadata.as_ref().and_then(accessed_secs),
+                    attributes: entry_metadata
+                        .as_ref()
+                        .and_then(|m| file_attributes(child_name, m)),
+                    latest_child_modified,
+                    partial_children: true,
+                    ..Default::default()
+                },
+                1u64,
+            )),
+            Err(DiskAnalyzerError::PermissionDenied(_)) => Ok(placeholder_node(
+                child_path,
+                format!("{} [无权限]", child_name),
+                true,
+            )),
+            Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok(placeholder_node(
+                child_path,
+                format!("{} [损坏]", child_name),
+                true,
+            )),
+            Err(e) => Err(e),
+        }
+    } else {
+        match build_tree(child_path, child_name, depth, ctx, ignore) {
+            Ok((node, cnt)) => Ok((node, cnt)),
+            Err(DiskAnalyzerError::PermissionDenied(_)) => Ok(placeholder_node(
+                child_path,
+                format!("{} [无权限]", child_name),
+                child_path.is_dir(),
+            )),
+            Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => Ok(placeholder_node(
+                child_path,
+                format!("{} [损坏]", child_name),
+                child_path.is_dir(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 其他文件系统的挂载点：不展开、大小为 0、不计入文件数
+pub(crate) fn mount_point_node(path: &Path, name: &str) -> (FileNode, u64) {
+    (
+        FileNode {
+            path: path.display().to_string(),
+            name: name.to_string(),
+            size: 0,
+            is_dir: true,
+            is_mount_point: true,
+            ..Default::default()
+        },
+        0u64,
+    )
+}
+
+/// 无权限或损坏等无法展开的子项：大小为 0（来源为 Unknown）、不计入文件数，名称带标记
+pub(crate) fn placeholder_node(path: &Path, name: String, is_dir: bool) -> (FileNode, u64) {
+    (
+        FileNode {
+            path: path.display().to_string(),
+            name,
+            size: 0,
+            is_dir,
+            size_source: SizeSource::Unknown,
+            ..Default::default()
+        },
+        0u64,
+    )
+}
+
+/// 读取元数据或列目录失败时的错误：无权限与路径不存在都按无权限返回，由上层生成占位节点
+pub(crate) fn walk_error(path: &Path, e: std::io::Error) -> DiskAnalyzerError {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => {
+            DiskAnalyzerError::PermissionDenied(path.display().to_string())
+        }
+        std::io::ErrorKind::NotFound => {
+            DiskAnalyzerError::PermissionDenied(format!("{} [路径不存在]", path.display()))
+        }
+        _ => DiskAnalyzerError::Io(e),
+    }
+}
+
+/// 创建/访问时间（Unix 秒）；纪元零点及更早的时间表示未记录或被清零，视为没有
+pub(crate) fn nonzero_unix_secs(secs: i64) -> Option<u64> {
+    u64::try_from(secs).ok().filter(|&s| s > 0)
+}
+
+fn system_time_secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    let secs = time.ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(secs).filter(|&s| s > 0)
+}
+
+/// 创建时间（Unix 秒）；文件系统不记录时为 None
+pub(crate) fn created_secs(metadata: &std::fs::Metadata) -> Option<u64> {
+    system_time_secs(metadata.created())
+}
+
+/// 最近访问时间（Unix 秒）；文件系统不记录时为 None
+pub(crate) fn accessed_secs(metadata: &std::fs::Metadata) -> Option<u64> {
+    system_time_secs(metadata.accessed())
+}
+
+/// 文件的隐藏/系统/只读属性：Windows 上取自文件属性位，其他平台仅以「.」开头视为隐藏
+pub(crate) fn file_attributes(name: &str, metadata: &std::fs::Metadata) -> Option<FileAttributes> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        let _ = name;
+        FileAttributes::from_windows_bits(metadata.file_attributes()).non_empty()
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        name_attributes(name)
+    }
+}
+
+/// 非 Windows 平台的文件属性：仅以「.」开头视为隐藏
+#[cfg(not(windows))]
+pub(crate) fn name_attributes(name: &str) -> Option<FileAttributes> {
+    FileAttributes {
+        hidden: name.starts_with('.'),
+        ..Default::default()
+    }
+    .non_empty()
+}
+
 /// 规范化路径（支持正斜杠、去除首尾空白）
 pub(crate) fn normalize_path(path: &str) -> std::path::PathBuf {
     let s = path.trim();
@@ -311,9 +503,22 @@ pub(crate) fn normalize_path(path: &str) -> std::path::PathBuf {
     std::path::PathBuf::from(s)
 }
 
-/// 通过操作系统 API 获取该路径所在卷的总容量与剩余空间（仅 Windows 有效）。
+/// 规范化并解析扫描根路径；路径不存在或无法解析时返回 InvalidPath
+pub(crate) fn resolve_scan_root(path: &str) -> Result<std::path::PathBuf, DiskAnalyzerError> {
+    let path_buf = normalize_path(path);
+    if !path_buf.exists() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "路径不存在: {}",
+            path
+        )));
+    }
+    std::fs::canonicalize(&path_buf)
+        .map_err(|e| DiskAnalyzerError::InvalidPath(format!("无法解析路径: {}", e)))
+}
+
+/// 通过操作系统 API 获取该路径所在卷的总容量与剩余空间（仅启用 `windows-native` 的 Windows 构建有效）。
 fn get_volume_space_for_result_path(path: &std::path::Path) -> (Option<u64>, Option<u64>) {
-    #[cfg(windows)]
+    #[cfg(all(windows, feature = "windows-native"))]
     {
         let s = path.to_string_lossy();
         match crate::mft_scan::get_volume_space_bytes(&s) {
@@ -321,119 +526,243 @@ fn get_volume_space_for_result_path(path: &std::path::Path) -> (Option<u64>, Opt
             None => (None, None),
         }
     }
-    #[cfg(not(windows))]
+    #[cfg(not(all(windows, feature = "windows-native")))]
     {
         let _ = path;
         (None, None)
     }
 }
 
+/// 未指定并发度时的默认扫描线程数：机械硬盘为 [`HDD_SCAN_THREADS`]，其余为 min(可用核数, [`DEFAULT_SCAN_THREADS_MAX`])
+pub fn default_scan_concurrency(path: &str) -> usize {
+    match detect_media_type(path) {
+        MediaType::Hdd => HDD_SCAN_THREADS,
+        MediaType::Ssd | MediaType::Unknown => std::thread::available_parallelism()
+            .map(|p| p.get())
+            .unwrap_or(1)
+            .min(DEFAULT_SCAN_THREADS_MAX),
+    }
+}
+
 /// 判断本次扫描是否会使用 MFT（在真正开始扫描前可调用，用于提前打日志）。
-/// 条件：use_mft 为 true、路径存在、为 Windows 卷根（如 C:\）。
+/// 条件：use_mft 为 true，且 [`explain_mft_availability`] 认为可用（Windows NTFS 卷根、已提权）；
+/// 不会使用时的原因见 [`crate::scan_preflight`]。
 pub fn scan_will_use_mft(path: &str, use_mft: bool) -> bool {
-    if !use_mft {
-        return false;
-    }
-    let path_buf = normalize_path(path);
-    if !path_buf.exists() {
-        return false;
-    }
-    let canonical = match std::fs::canonicalize(&path_buf) {
-        Ok(p) => p,
-        Err(_) => return false,
-    };
-    #[cfg(windows)]
-    return crate::mft_scan::is_windows_volume_root(&canonical);
-    #[cfg(not(windows))]
+    use_mft && explain_mft_availability(path).available
+}
+
+/// 遍历目录树：Linux/macOS/Windows（需 `windows-native`）先用批量读取目录项的快速后端，出错时记录日志并改用通用遍历
+fn walk_tree(
+    path: &Path,
+    name: &str,
+    progress: Option<&ProgressCb>,
+    shallow_dirs: bool,
+    fs_boundary: Option<FsBoundary>,
+    ignore: Option<&IgnoreRules>,
+) -> Result<(FileNode, u64), DiskAnalyzerError> {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "macos",
+        all(windows, feature = "windows-native")
+    ))]
     {
-        let _ = canonical;
-        false
+        let counter = AtomicU64::new(0);
+        let ctx = WalkContext {
+            counter: &counter,
+            progress,
+            shallow_dirs,
+            fs_boundary,
+        };
+        match crate::fast_walk::build_tree(path, name, 0, &ctx, ignore) {
+            Ok(result) => return Ok(result),
+            Err(e) => {
+                tracing::warn!(error = %e, "fast directory walk failed, falling back to read_dir");
+            }
+        }
     }
+    let counter = AtomicU64::new(0);
+    let ctx = WalkContext {
+        counter: &counter,
+        progress,
+        shallow_dirs,
+        fs_boundary,
+    };
+    build_tree(path, name, 0, &ctx, ignore)
+}
+
+/// 卷根无法使用 MFT 时写入 `scan_warning` 的说明
+fn mft_fallback_warning(reason: &str) -> String {
+    format!("MFT 不可用（{}），已改用目录遍历", reason)
 }
 
 /// 执行磁盘扫描（支持进度回调；shallow_dirs 为 true 时对 node_modules/.git 等只计大小不递归）。
 /// 当 use_mft 为 true 且路径为 Windows 磁盘卷根（如 C:\）时，优先使用 MFT 加速扫描。
-/// 返回 `(ScanResult, used_mft)`，其中 `used_mft` 表示本次是否成功使用了 MFT。
+/// 返回 `(ScanResult, used_mft)`，其中 `used_mft` 表示本次是否成功使用了 MFT；
+/// 卷根无法使用 MFT 时发送一条回退说明的进度消息，并在 `scan_warning` 中注明原因。
+/// 遍历与 MFT 建树都在本次扫描专用的线程池中进行，线程数为 `concurrency`，
+/// 未指定时取 [`default_scan_concurrency`]。
+/// `background` 为 Some 时为后台扫描：扫描线程降为低优先级，目录读取按其速率限速（见 [`crate::throttle`]）。
+/// `walk` 为目录遍历的可选行为（忽略规则、是否跨越挂载点，见 [`WalkOptions`]），使用 MFT 时不生效；
+/// 其中的暂停令牌对两种扫描都生效，暂停时长不计入 `scan_time_ms`，记在 `paused_ms` 中。
 pub fn scan_path_with_progress(
     path: &str,
     progress: Option<&ProgressCbArc>,
     shallow_dirs: bool,
     use_mft: bool,
+    concurrency: Option<usize>,
+    background: Option<BackgroundScan>,
+    walk: &WalkOptions,
 ) -> Result<(ScanResult, bool), DiskAnalyzerError> {
     let start = Instant::now();
-    let path_buf = normalize_path(path);
-
-    if !path_buf.exists() {
-        return Err(DiskAnalyzerError::InvalidPath(format!(
-            "路径不存在: {}",
-            path
-        )));
-    }
+    let path_buf = resolve_scan_root(path)?;
 
-    let path_buf = std::fs::canonicalize(&path_buf)
-        .map_err(|e| DiskAnalyzerError::InvalidPath(format!("无法解析路径: {}", e)))?;
+    let threads = concurrency
+        .unwrap_or_else(|| default_scan_concurrency(path))
+        .max(1);
+    // 后台扫描允许积攒约 0.1 秒的读取量，之后按速率匀速读取
+    let bucket = background
+        .map(|b| std::sync::Arc::new(TokenBucket::new(b.entries_per_sec, b.entries_per_sec / 10)));
+    let pool = ScanPool::new(threads, bucket.clone(), walk.pause.clone())?;
+    tracing::info!(
+        threads,
+        background = bucket.is_some(),
+        "scan thread pool ready"
+    );
 
-    #[allow(unused_mut, unused_assignments)]
+    // 只在路径为卷根（MFT 适用）时才解释回退原因；普通子目录本就走目录遍历
     let mut mft_fallback_reason: Option<String> = None;
-    let _ = use_mft; // used only on windows
-    #[cfg(windows)]
-    if use_mft && crate::mft_scan::is_windows_volume_root(&path_buf) {
-        eprintln!(
-            "[scan] path is volume root, attempting MFT full scan: {}",
-            path_buf.display()
-        );
-        match crate::mft_scan::scan_volume_mft(path, progress.cloned(), shallow_dirs) {
-            Ok(result) => return Ok((result, true)),
-            Err(e) => {
-                let msg: String = e.to_string();
-                eprintln!(
-                    "[scan] MFT scan unavailable, falling back to normal walk. reason: {} (on Windows, reading $MFT often needs admin)",
-                    msg
-                );
-                mft_fallback_reason = Some(msg);
+    if use_mft {
+        match MftPreconditions::probe(&path_buf).route() {
+            MftRoute::Walk => {}
+            MftRoute::Fallback(reason) => mft_fallback_reason = Some(reason),
+            #[cfg(all(windows, feature = "windows-native"))]
+            MftRoute::Attempt => {
+                tracing::info!(path = %path_buf.display(), "path is volume root, attempting MFT full scan");
+                match pool.install(|| {
+                    crate::mft_scan::scan_volume_mft(
+                        path,
+                        progress.cloned(),
+                        shallow_dirs,
+                        walk.include_streams,
+                    )
+                }) {
+                    Ok(mut result) => {
+                        exclude_internal_paths(&mut result, &InternalPaths::current());
+                        result.trash_bytes = tag_trash(&mut result.root, &user_trash_dirs());
+                        tag_known_folders(&mut result.root, &system_known_folders());
+                        if walk.treemap_rollups {
+                            fill_rollups(&mut result.root);
+                        }
+                        exclude_paused_time(&mut result, walk.pause.as_ref());
+                        record_scan_metrics(&result, true);
+                        return Ok((result, true));
+                    }
+                    Err(e) => mft_fallback_reason = Some(format!("MFT 读取失败: {}", e)),
+                }
             }
+            // 未启用 windows-native 时前置条件不会全部满足
+            #[cfg(not(all(windows, feature = "windows-native")))]
+            MftRoute::Attempt => {}
+        }
+    }
+    if let Some(reason) = &mft_fallback_reason {
+        tracing::warn!(reason = %reason, "MFT scan unavailable, falling back to normal walk");
+        if let Some(cb) = progress {
+            // 回退原因另见 scan_warning
+            cb(0, &ProgressPhase::MftFallback.marker());
         }
     }
 
-    eprintln!("[scan] using normal directory walk: {}", path_buf.display());
+    tracing::info!(path = %path_buf.display(), "using normal directory walk");
     let name = path_buf
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or(path)
         .to_string();
 
-    let counter = AtomicU64::new(0);
-    let (root, file_count) = build_tree(
-        &path_buf,
-        &name,
-        0,
-        &counter,
-        progress.map(std::sync::Arc::as_ref),
-        shallow_dirs,
-    )?;
+    let ignore = walk
+        .gitignore
+        .as_ref()
+        .map(|options| IgnoreRules::new(&path_buf, options));
+    let fs_boundary = walk
+        .same_filesystem_only
+        .unwrap_or_else(|| is_volume_root(&path_buf))
+        .then(|| FsBoundary::for_root(&path_buf))
+        .flatten();
+    let (mut root, file_count) = pool.install(|| {
+        walk_tree(
+            &path_buf,
+            &name,
+            progress.map(std::sync::Arc::as_ref),
+            shallow_dirs,
+            fs_boundary,
+            ignore.as_ref(),
+        )
+    })?;
     let scan_time_ms = start.elapsed().as_millis() as u64;
     let total_size = root.size;
+    let physical_usage = fill_physical_sizes(&mut root, &compressed_file_size);
+    finalize_tree(&mut root);
+    let trash_bytes = tag_trash(&mut root, &user_trash_dirs());
+    tag_known_folders(&mut root, &system_known_folders());
 
     let (volume_total_bytes, volume_free_bytes) = get_volume_space_for_result_path(&path_buf);
 
-    Ok((
-        ScanResult {
-            root,
-            scan_time_ms,
-            file_count,
-            total_size,
-            scan_warning: mft_fallback_reason,
-            volume_total_bytes,
-            volume_free_bytes,
-            top_files: None,
-        },
-        false,
-    ))
+    let mut result = ScanResult {
+        scan_id: None,
+        root,
+        scan_time_ms,
+        file_count,
+        total_size,
+        scan_warning: mft_fallback_reason.as_deref().map(mft_fallback_warning),
+        volume_total_bytes,
+        volume_free_bytes,
+        top_files: None,
+        owner_usage: None,
+        throttled: bucket.is_some_and(|b| b.has_throttled()),
+        ignored_bytes: ignore.map(|rules| rules.ignored_bytes()),
+        trash_bytes,
+        paused_ms: 0,
+        physical_usage,
+        recent_activity: Vec::new(),
+    };
+    exclude_internal_paths(&mut result, &InternalPaths::current());
+    result.recent_activity = recent_activity_from_tree(&result.root, now_secs());
+    if walk.treemap_rollups {
+        fill_rollups(&mut result.root);
+    }
+    exclude_paused_time(&mut result, walk.pause.as_ref());
+    record_scan_metrics(&result, false);
+    Ok((result, false))
+}
+
+/// 记录扫描耗时指标（已扣除暂停时长），按扫描方式区分
+fn record_scan_metrics(result: &ScanResult, used_mft: bool) {
+    let mode = if used_mft { "mft" } else { "walk" };
+    metrics().observe(METRIC_SCAN_DURATION_MS, mode, result.scan_time_ms);
 }
 
-/// 执行磁盘扫描（无进度；默认开启 shallow_dirs；默认开启 MFT 加速卷根）
+/// 把暂停时长从 `scan_time_ms` 中扣除并单独记录
+fn exclude_paused_time(result: &mut ScanResult, pause: Option<&PauseToken>) {
+    if let Some(pause) = pause {
+        let paused_ms = pause.paused_duration().as_millis() as u64;
+        result.scan_time_ms = result.scan_time_ms.saturating_sub(paused_ms);
+        result.paused_ms = paused_ms;
+    }
+}
+
+/// 执行磁盘扫描（无进度；默认开启 shallow_dirs；默认开启 MFT 加速卷根；默认并发度）
 pub fn scan_path(path: &str) -> Result<ScanResult, DiskAnalyzerError> {
-    scan_path_with_progress(path, None::<&ProgressCbArc>, true, true).map(|(r, _)| r)
+    scan_path_with_progress(
+        path,
+        None::<&ProgressCbArc>,
+        true,
+        true,
+        None,
+        None,
+        &WalkOptions::default(),
+    )
+    .map(|(r, _)| r)
 }
 
 #[cfg(test)]
@@ -483,6 +812,30 @@ mod tests {
         assert!(matches!(err, DiskAnalyzerError::InvalidPath(_)));
     }
 
+    #[test]
+    fn test_missing_path_maps_to_path_not_found_code() {
+        let err = scan_path("/nonexistent_xyz_12345_folder").unwrap_err();
+        let cmd_err = ai_disk_common::CommandError::from(err);
+        assert_eq!(cmd_err.code, ai_disk_common::ErrorCode::PathNotFound);
+    }
+
+    #[test]
+    fn test_fat_volume_root_falls_back_with_warning() {
+        let exfat = MftPreconditions {
+            windows: true,
+            volume_root: true,
+            filesystem: Some("exFAT".to_string()),
+            elevated: true,
+        };
+        let MftRoute::Fallback(reason) = exfat.route() else {
+            panic!("exFAT 卷不应尝试 MFT");
+        };
+        assert_eq!(
+            mft_fallback_warning(&reason),
+            "MFT 不可用（该卷的文件系统为 exFAT，不是 NTFS），已改用目录遍历"
+        );
+    }
+
     #[test]
     fn test_scan_temp_dir() {
         let (_guard, path) = create_test_dir();
@@ -493,6 +846,40 @@ mod tests {
         assert!(!result.root.children.is_empty());
     }
 
+    #[test]
+    fn test_scan_reads_access_and_creation_times() {
+        let (_guard, path) = create_test_dir();
+        let file = Path::new(&path).join("subdir/a.txt");
+        let accessed = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let modified = UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_times(
+                fs::FileTimes::new()
+                    .set_accessed(accessed)
+                    .set_modified(modified),
+            )
+            .unwrap();
+        let created = created_secs(&fs::metadata(&file).unwrap());
+
+        let result = scan_path(&path).unwrap();
+        let subdir = result
+            .root
+            .children
+            .iter()
+            .find(|c| c.name == "subdir")
+            .unwrap();
+        let node = subdir.children.iter().find(|c| c.name == "a.txt").unwrap();
+        assert_eq!(node.modified, Some(1_600_000_000));
+        assert_eq!(node.accessed, Some(1_700_000_000));
+        assert_eq!(node.created, created);
+        assert_eq!(node.last_used(), Some(1_700_000_000));
+        assert_eq!(nonzero_unix_secs(0), None);
+        assert_eq!(nonzero_unix_secs(-5), None);
+    }
+
     #[test]
     #[cfg(windows)]
     fn test_scan_academic_path() {
@@ -502,4 +889,564 @@ mod tests {
             assert!(result.root.name == "Academic" || !result.root.path.is_empty());
         }
     }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_scan_reports_windows_attributes() {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::{
+            SetFileAttributesW, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL,
+            FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM,
+        };
+        let set = |path: &Path, attrs: u32| {
+            let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+            // SAFETY: 路径以 NUL 结尾
+            assert_ne!(unsafe { SetFileAttributesW(wide.as_ptr(), attrs) }, 0);
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let flagged = dir.path().join("flagged.bin");
+        fs::write(&flagged, b"x").unwrap();
+        fs::write(dir.path().join("plain.bin"), b"y").unwrap();
+        set(
+            &flagged,
+            FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_READONLY,
+        );
+        let result = scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        );
+        // 先恢复属性，保证临时目录能被删除
+        set(&flagged, FILE_ATTRIBUTE_NORMAL);
+
+        let root = result.unwrap().0.root;
+        let find = |name: &str| root.children.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(
+            find("flagged.bin").attributes,
+            Some(FileAttributes {
+                hidden: true,
+                system: true,
+                readonly: true,
+                sparse: false,
+            })
+        );
+        assert_eq!(find("plain.bin").attributes, None);
+    }
+
+    /// 顺序构建在调用线程上扫描，没有线程池可检查
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_scan_respects_concurrency() {
+        use std::collections::HashSet;
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::tempdir().unwrap();
+        for d in 0..64 {
+            let sub = dir.path().join(format!("d{:02}", d));
+            fs::create_dir(&sub).unwrap();
+            for f in 0..8 {
+                fs::write(sub.join(format!("f{}", f)), b"x").unwrap();
+            }
+        }
+        let path = dir.path().to_string_lossy().to_string();
+        for threads in [1, 2, 3] {
+            let seen = Arc::new(Mutex::new(HashSet::new()));
+            let seen_cb = seen.clone();
+            let progress: ProgressCbArc = Arc::new(Box::new(move |_: u64, _: &str| {
+                seen_cb.lock().unwrap().insert(std::thread::current().id());
+            }) as ProgressCb);
+            let result = scan_path_with_progress(
+                &path,
+                Some(&progress),
+                true,
+                false,
+                Some(threads),
+                None,
+                &WalkOptions::default(),
+            )
+            .unwrap()
+            .0;
+            assert_eq!(result.file_count, 64 * 8);
+            let seen = seen.lock().unwrap();
+            assert!(
+                !seen.is_empty() && seen.len() <= threads,
+                "{} threads observed with concurrency {}",
+                seen.len(),
+                threads
+            );
+            assert!(!seen.contains(&std::thread::current().id()));
+        }
+    }
+
+    #[test]
+    fn test_background_scan_is_throttled() {
+        let dir = tempfile::tempdir().unwrap();
+        for d in 0..20 {
+            let sub = dir.path().join(format!("d{:02}", d));
+            fs::create_dir(&sub).unwrap();
+            for f in 0..50 {
+                fs::write(sub.join(format!("f{}", f)), b"x").unwrap();
+            }
+        }
+        let path = dir.path().to_string_lossy().to_string();
+
+        let start = Instant::now();
+        let normal = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            Some(2),
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap()
+        .0;
+        let normal_elapsed = start.elapsed();
+        assert!(!normal.throttled);
+
+        // 约 1020 个目录项、每秒 2000 个、可积攒 200 个：至少需要约 0.4 秒
+        let background = BackgroundScan {
+            entries_per_sec: 2_000,
+        };
+        let start = Instant::now();
+        let throttled = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            Some(2),
+            Some(background),
+            &WalkOptions::default(),
+        )
+        .unwrap()
+        .0;
+        let throttled_elapsed = start.elapsed();
+        assert!(throttled.throttled);
+        assert_eq!(throttled.file_count, normal.file_count);
+        assert!(
+            throttled_elapsed >= std::time::Duration::from_millis(350),
+            "throttled scan took {:?}",
+            throttled_elapsed
+        );
+        assert!(throttled_elapsed > normal_elapsed);
+        assert!(throttled.scan_time_ms >= 350);
+    }
+
+    #[test]
+    fn test_default_concurrency_is_bounded() {
+        let threads = default_scan_concurrency(&std::env::temp_dir().to_string_lossy());
+        assert!((1..=DEFAULT_SCAN_THREADS_MAX).contains(&threads));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_scan_marks_dotfiles_hidden() {
+        let (_guard, path) = create_test_dir();
+        fs::write(Path::new(&path).join(".env"), b"x").unwrap();
+        let root = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap()
+        .0
+        .root;
+        let find = |name: &str| root.children.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(
+            find(".env").attributes,
+            Some(FileAttributes {
+                hidden: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(find("b.txt").attributes, None);
+        assert_eq!(find("subdir").attributes, None);
+    }
+
+    /// 类似项目仓库的目录：根与子目录各有 .gitignore，子目录用 `!` 重新包含
+    fn gitignore_fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("build/obj")).unwrap();
+        fs::create_dir_all(root.join("web/dist")).unwrap();
+        fs::create_dir_all(root.join("web/logs")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n*.log\n").unwrap();
+        fs::write(root.join("web/.gitignore"), "dist/\n!important.log\n").unwrap();
+        fs::write(root.join("src/main.rs"), vec![b'x'; 100]).unwrap();
+        fs::write(root.join("build/obj/main.o"), vec![0u8; 4000]).unwrap();
+        fs::write(root.join("debug.log"), vec![0u8; 200]).unwrap();
+        fs::write(root.join("web/dist/app.js"), vec![0u8; 1000]).unwrap();
+        fs::write(root.join("web/logs/trace.log"), vec![0u8; 300]).unwrap();
+        fs::write(root.join("web/logs/important.log"), vec![0u8; 50]).unwrap();
+        fs::write(root.join("web/index.html"), vec![0u8; 20]).unwrap();
+        dir
+    }
+
+    fn collect_paths(node: &FileNode, root: &Path, out: &mut Vec<String>) {
+        for child in &node.children {
+            let rel = Path::new(&child.path).strip_prefix(root).unwrap();
+            out.push(rel.to_string_lossy().replace('\\', "/"));
+            collect_paths(child, root, out);
+        }
+    }
+
+    #[test]
+    fn test_scan_respects_nested_gitignore() {
+        let dir = gitignore_fixture();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let path = root.to_string_lossy().to_string();
+
+        let plain = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap()
+        .0;
+        assert_eq!(plain.ignored_bytes, None);
+
+        let options = WalkOptions {
+            gitignore: Some(GitignoreOptions::default()),
+            ..Default::default()
+        };
+        let result = scan_path_with_progress(&path, None, true, false, None, None, &options)
+            .unwrap()
+            .0;
+        let mut paths = Vec::new();
+        collect_paths(&result.root, &root, &mut paths);
+        for kept in [
+            "src/main.rs",
+            "web/index.html",
+            "web/logs",
+            "web/logs/important.log",
+        ] {
+            assert!(
+                paths.iter().any(|p| p == kept),
+                "{} missing: {:?}",
+                kept,
+                paths
+            );
+        }
+        for ignored in ["build", "debug.log", "web/dist", "web/logs/trace.log"] {
+            assert!(
+                !paths
+                    .iter()
+                    .any(|p| p == ignored || p.starts_with(&format!("{}/", ignored))),
+                "{} should be ignored: {:?}",
+                ignored,
+                paths
+            );
+        }
+        assert_eq!(result.ignored_bytes, Some(4000 + 200 + 1000 + 300));
+        assert_eq!(
+            result.total_size + result.ignored_bytes.unwrap(),
+            plain.total_size
+        );
+        assert!(result.file_count < plain.file_count);
+    }
+
+    type BuildTree = fn(
+        &Path,
+        &str,
+        usize,
+        &WalkContext,
+        Option<&IgnoreRules>,
+    ) -> Result<(FileNode, u64), DiskAnalyzerError>;
+
+    /// 用通用遍历（及 Linux/macOS 上的快速遍历）构建，断言两者一致，返回 (树, 文件数, 忽略的字节数)
+    fn walk_both(
+        root: &Path,
+        fs_boundary: Option<FsBoundary>,
+        gitignore: Option<&GitignoreOptions>,
+    ) -> (FileNode, u64, u64) {
+        let name = root.file_name().unwrap().to_string_lossy().to_string();
+        let walk = |build: BuildTree| {
+            let rules = gitignore.map(|options| IgnoreRules::new(root, options));
+            let counter = AtomicU64::new(0);
+            let ctx = WalkContext {
+                counter: &counter,
+                progress: None,
+                shallow_dirs: true,
+                fs_boundary,
+            };
+            let (node, count) = build(root, &name, 0, &ctx, rules.as_ref()).unwrap();
+            (node, count, rules.map_or(0, |r| r.ignored_bytes()))
+        };
+        let generic = walk(build_tree);
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        {
+            let fast = walk(crate::fast_walk::build_tree);
+            assert_eq!(
+                serde_json::to_value(&fast.0).unwrap(),
+                serde_json::to_value(&generic.0).unwrap()
+            );
+            assert_eq!((fast.1, fast.2), (generic.1, generic.2));
+        }
+        generic
+    }
+
+    #[test]
+    fn test_gitignore_applies_to_both_walkers() {
+        let dir = gitignore_fixture();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let (tree, count, ignored) = walk_both(&root, None, Some(&GitignoreOptions::default()));
+        // 两个 .gitignore、main.rs、index.html、important.log
+        assert_eq!(count, 5);
+        assert_eq!(ignored, 5500);
+        // 被忽略的子项不计入子节点与大小，其所在目录及各级祖先标记为不完整
+        let find = |node: &FileNode, name: &str| {
+            node.children
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+        let (src, web) = (find(&tree, "src"), find(&tree, "web"));
+        let logs = find(&web, "logs");
+        assert!(!src.partial_children && !src.partial_size);
+        for node in [&tree, &web, &logs] {
+            assert!(node.partial_children && node.partial_size, "{}", node.name);
+        }
+    }
+
+    #[test]
+    fn test_truncated_and_depth_capped_dirs_are_partial() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let crowded = root.join("crowded");
+        fs::create_dir(&crowded).unwrap();
+        for i in 0..=MAX_CHILDREN_PER_DIR {
+            fs::write(crowded.join(format!("{:04}.txt", i)), b"x").unwrap();
+        }
+        let mut deep = root.join("deep");
+        for level in 0..MAX_DEPTH {
+            deep = deep.join(format!("d{}", level));
+        }
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(deep.join("leaf.bin"), vec![0u8; 64]).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), vec![0u8; 10]).unwrap();
+
+        let (tree, _, _) = walk_both(&root, None, None);
+        let find = |node: &FileNode, name: &str| {
+            node.children
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .clone()
+        };
+        // 超过子项上限的目录只统计了保留的子项
+        let crowded = find(&tree, "crowded");
+        assert_eq!(crowded.children.len(), MAX_CHILDREN_PER_DIR);
+        assert!(crowded.partial_children && crowded.partial_size);
+        // 达到深度上限的目录（根的深度为 0）不展开，大小记为 0
+        let mut capped = find(&tree, "deep");
+        for level in 0..MAX_DEPTH - 1 {
+            capped = find(&capped, &format!("d{}", level));
+        }
+        assert_eq!((capped.size, capped.children.len()), (0, 0));
+        assert!(capped.partial_children && capped.partial_size);
+        assert!(find(&tree, "deep").partial_size);
+        // 只计大小的目录大小完整，只是没有列出子项
+        let modules = find(&tree, "node_modules");
+        assert_eq!(modules.size, 10);
+        assert!(modules.partial_children && !modules.partial_size);
+        assert!(tree.partial_size && !tree.partial_children);
+    }
+
+    #[test]
+    fn test_latest_child_modified_bubbles_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let deep = root.join("project").join("src").join("deep");
+        let package = root.join("project").join("node_modules").join("pkg");
+        fs::create_dir_all(&deep).unwrap();
+        fs::create_dir_all(&package).unwrap();
+        fs::write(deep.join("main.rs"), b"fn main() {}").unwrap();
+        fs::write(package.join("index.js"), b"1").unwrap();
+        let set_modified = |path: &Path, secs: u64| {
+            File::open(path)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        set_modified(&deep.join("main.rs"), 1_700_000_000);
+        set_modified(&package.join("index.js"), 1_650_000_000);
+        // 祖先目录自身的修改时间都比深层文件旧（子项写完后再设置）
+        for ancestor in [
+            package.as_path(),
+            package.parent().unwrap(),
+            deep.as_path(),
+            deep.parent().unwrap(),
+            deep.parent().unwrap().parent().unwrap(),
+        ] {
+            set_modified(ancestor, 1_500_000_000);
+        }
+
+        let (tree, _, _) = walk_both(&root, None, None);
+        let child = |node: &FileNode, name: &str| {
+            node.children
+                .iter()
+                .find(|c| c.name == name)
+                .cloned()
+                .unwrap()
+        };
+        let project = child(&tree, "project");
+        assert_eq!(project.modified, Some(1_500_000_000));
+        assert_eq!(project.latest_child_modified, Some(1_700_000_000));
+        let src = child(&project, "src");
+        assert_eq!(src.latest_child_modified, Some(1_700_000_000));
+        assert_eq!(
+            child(&src, "deep").latest_child_modified,
+            Some(1_700_000_000)
+        );
+        // 只计大小的目录同样计入其下内容
+        let node_modules = child(&project, "node_modules");
+        assert!(node_modules.children.is_empty());
+        assert_eq!(node_modules.latest_child_modified, Some(1_650_000_000));
+    }
+
+    #[test]
+    fn test_mount_points_are_not_descended() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::create_dir_all(root.join("mnt/inner")).unwrap();
+        fs::write(root.join("data/a.bin"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("mnt/inner/big.bin"), vec![0u8; 5000]).unwrap();
+
+        let (crossing, crossing_count, _) = walk_both(&root, None, None);
+        assert_eq!((crossing.size, crossing_count), (5100, 2));
+
+        // 模拟 mnt 上挂载了另一个设备
+        let boundary =
+            FsBoundary::with_devices(1, |p| Some(if p.ends_with("mnt") { 2 } else { 1 }));
+        let (tree, count, _) = walk_both(&root, Some(boundary), None);
+        assert_eq!((tree.size, count), (100, 1));
+        let find = |name: &str| tree.children.iter().find(|c| c.name == name).unwrap();
+        let mnt = find("mnt");
+        assert!(mnt.is_mount_point && mnt.is_dir);
+        assert_eq!(mnt.size, 0);
+        assert!(mnt.children.is_empty());
+        assert!(!find("data").is_mount_point);
+
+        // 真实文件系统上的临时目录没有挂载点，开启后结果不变
+        let path = root.to_string_lossy().to_string();
+        let options = WalkOptions {
+            same_filesystem_only: Some(true),
+            ..Default::default()
+        };
+        let scanned = scan_path_with_progress(&path, None, true, false, None, None, &options)
+            .unwrap()
+            .0;
+        assert_eq!((scanned.total_size, scanned.file_count), (5100, 2));
+    }
+
+    #[test]
+    fn test_paused_scan_emits_no_progress_until_resumed() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        // 200 个目录、每个 20 个文件
+        let dir = tempfile::tempdir().unwrap();
+        for d in 0..200 {
+            let sub = dir.path().join(format!("d{:03}", d));
+            fs::create_dir(&sub).unwrap();
+            for f in 0..20 {
+                fs::write(sub.join(format!("f{:02}.bin", f)), [0u8; 16]).unwrap();
+            }
+        }
+        let path = dir.path().to_string_lossy().to_string();
+
+        let events = Arc::new(AtomicU64::new(0));
+        let progress: ProgressCbArc = {
+            let events = events.clone();
+            Arc::new(Box::new(move |_, _: &str| {
+                events.fetch_add(1, Ordering::SeqCst);
+            }))
+        };
+        let pause = PauseToken::new();
+        pause.pause();
+        let options = WalkOptions {
+            pause: Some(pause.clone()),
+            ..Default::default()
+        };
+        let scan = std::thread::spawn(move || {
+            scan_path_with_progress(&path, Some(&progress), true, false, Some(4), None, &options)
+        });
+
+        // 暂停期间扫描线程阻塞在检查点上，不会上报进度
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!scan.is_finished());
+        assert_eq!(events.load(Ordering::SeqCst), 0);
+
+        pause.resume();
+        let (result, used_mft) = scan.join().unwrap().unwrap();
+        assert!(!used_mft);
+        assert_eq!(result.file_count, 200 * 20);
+        assert_eq!(result.total_size, 200 * 20 * 16);
+        assert!(events.load(Ordering::SeqCst) > 0);
+        assert!(result.paused_ms >= 300);
+        assert!(result.scan_time_ms < result.paused_ms);
+    }
+
+    #[test]
+    fn test_treemap_rollups_are_opt_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let media = dir.path().join("media");
+        fs::create_dir(&media).unwrap();
+        fs::write(media.join("trip.mp4"), vec![0u8; 600]).unwrap();
+        fs::write(media.join("report.pdf"), vec![0u8; 400]).unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+        let find_media =
+            |root: &FileNode| root.children.iter().find(|c| c.name == "media").cloned();
+
+        let plain = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap()
+        .0;
+        let plain_media = find_media(&plain.root).unwrap();
+        assert_eq!(plain_media.dominant_category, None);
+        assert_eq!(plain_media.newest_modified, None);
+
+        let options = WalkOptions {
+            treemap_rollups: true,
+            ..Default::default()
+        };
+        let result = scan_path_with_progress(&path, None, true, false, None, None, &options)
+            .unwrap()
+            .0;
+        let media = find_media(&result.root).unwrap();
+        assert_eq!(
+            media.dominant_category,
+            Some(ai_disk_domain::FileCategory::Video)
+        );
+        assert!(media.newest_modified.is_some());
+        assert!(media.oldest_modified <= media.newest_modified);
+        assert_eq!(
+            result.root.dominant_category,
+            Some(ai_disk_domain::FileCategory::Video)
+        );
+    }
 }
@@ -0,0 +1,242 @@
+//! 无权限目录的大小估算。非管理员扫描 `C:\Users` 时，其他用户的配置文件夹无法列出，
+//! 只得到大小为 0、来源为 [`SizeSource::Unknown`] 的占位节点，按用户的占用明细因此失去意义。
+//! 扫描结束后可以尽力补上：向 [`DirSizeSource`] 查询这些目录的大小，得到的值记为
+//! [`SizeSource::Estimated`]，并累加到各级祖先（祖先的来源同样记为 Estimated）。
+//!
+//! 目前的来源是覆盖同一路径的缓存扫描，如此前以管理员权限完成的整卷 MFT 扫描。
+//! Windows 没有直接返回目录大小的 API，NTFS 配额按所有者而非目录统计，暂不作为来源。
+
+use std::path::Path;
+
+use ai_disk_domain::{FileNode, ScanResult, SizeSource};
+
+use crate::display_path::DisplayPath;
+use crate::node::normalize_node_path;
+
+/// 目录路径 → 估算大小
+pub trait DirSizeSource {
+    fn dir_size(&self, path: &Path) -> Option<u64>;
+}
+
+/// 依次查询各来源，取第一个结果
+impl<S: DirSizeSource> DirSizeSource for Vec<S> {
+    fn dir_size(&self, path: &Path) -> Option<u64> {
+        self.iter().find_map(|source| source.dir_size(path))
+    }
+}
+
+impl<S: DirSizeSource + ?Sized> DirSizeSource for std::sync::Arc<S> {
+    fn dir_size(&self, path: &Path) -> Option<u64> {
+        (**self).dir_size(path)
+    }
+}
+
+/// 以一次缓存扫描的树为来源；该扫描中同样大小未知的节点不作为结果
+pub struct CachedScanSizes<'a> {
+    root: &'a FileNode,
+}
+
+impl<'a> CachedScanSizes<'a> {
+    pub fn new(root: &'a FileNode) -> Self {
+        Self { root }
+    }
+}
+
+impl DirSizeSource for CachedScanSizes<'_> {
+    fn dir_size(&self, path: &Path) -> Option<u64> {
+        // 两次扫描的路径形式可能不同（`\\?\` 前缀、大小写），统一为计算节点 id 的形式再比较
+        let target = normalize_node_path(&path.to_string_lossy());
+        let target = Path::new(&target);
+        let mut node = self.root;
+        loop {
+            if Path::new(&normalize_node_path(&node.path)) == target {
+                return (node.size_source != SizeSource::Unknown).then_some(node.size);
+            }
+            node = node
+                .children
+                .iter()
+                .find(|child| target.starts_with(normalize_node_path(&child.path)))?;
+        }
+    }
+}
+
+/// 为扫描结果中大小未知的目录补上估算值，并累加到各级祖先与 `total_size`；
+/// 返回估算出的总字节数。补上后受影响的子节点列表重新按大小降序排列
+pub fn estimate_unknown_sizes(result: &mut ScanResult, source: &dyn DirSizeSource) -> u64 {
+    let added = estimate_node(&mut result.root, source);
+    result.total_size = result.total_size.saturating_add(added);
+    added
+}
+
+fn estimate_node(node: &mut FileNode, source: &dyn DirSizeSource) -> u64 {
+    if node.size_source == SizeSource::Unknown {
+        if !node.is_dir {
+            return 0;
+        }
+        let Some(size) = source.dir_size(&DisplayPath::new(&node.path).to_os_path()) else {
+            return 0;
+        };
+        node.size = size;
+        node.size_source = SizeSource::Estimated;
+        return size;
+    }
+    let added: u64 = node
+        .children
+        .iter_mut()
+        .map(|child| estimate_node(child, source))
+        .sum();
+    if added > 0 {
+        node.size = node.size.saturating_add(added);
+        node.size_source = SizeSource::Estimated;
+        node.children
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// 按路径返回预设大小，记录查询过的路径
+    struct MockSource {
+        sizes: HashMap<&'static str, u64>,
+        queried: RefCell<Vec<String>>,
+    }
+
+    impl DirSizeSource for MockSource {
+        fn dir_size(&self, path: &Path) -> Option<u64> {
+            let path = path.to_string_lossy().into_owned();
+            let size = self.sizes.get(path.as_str()).copied();
+            self.queried.borrow_mut().push(path);
+            size
+        }
+    }
+
+    fn node(path: &str, size: u64, children: Vec<FileNode>) -> FileNode {
+        FileNode::new(path, size, true).with_children(children)
+    }
+
+    fn denied(path: &str) -> FileNode {
+        let mut node = node(path, 0, vec![]);
+        node.name = format!("{} [无权限]", node.name);
+        node.size_source = SizeSource::Unknown;
+        node
+    }
+
+    fn scan_of(root: FileNode) -> ScanResult {
+        ScanResult {
+            scan_id: None,
+            total_size: root.size,
+            root,
+            scan_time_ms: 0,
+            file_count: 0,
+            scan_warning: None,
+            volume_total_bytes: None,
+            volume_free_bytes: None,
+            top_files: None,
+            owner_usage: None,
+            throttled: false,
+            ignored_bytes: None,
+            trash_bytes: None,
+            paused_ms: 0,
+            physical_usage: None,
+            recent_activity: Vec::new(),
+        }
+    }
+
+    /// /Users（300）下：自己的配置文件夹可读（300），bob 与 carol 无权限，Public 下的 shared 无权限
+    fn users_scan() -> ScanResult {
+        scan_of(node(
+            "/Users",
+            300,
+            vec![
+                node("/Users/alice", 300, vec![]),
+                node("/Users/Public", 0, vec![denied("/Users/Public/shared")]),
+                denied("/Users/bob"),
+                denied("/Users/carol"),
+            ],
+        ))
+    }
+
+    #[test]
+    fn test_estimates_propagate_to_ancestors() {
+        let mut result = users_scan();
+        let source = MockSource {
+            sizes: HashMap::from([("/Users/bob", 5000), ("/Users/Public/shared", 40)]),
+            queried: RefCell::new(Vec::new()),
+        };
+        assert_eq!(estimate_unknown_sizes(&mut result, &source), 5040);
+        assert_eq!(source.queried.borrow().len(), 3);
+
+        let root = &result.root;
+        assert_eq!(root.size, 5340);
+        assert_eq!(result.total_size, 5340);
+        assert_eq!(root.size_source, SizeSource::Estimated);
+        // 估算后按大小重新排序
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["bob [无权限]", "alice", "Public", "carol [无权限]"]);
+        assert_eq!(root.children[0].size_source, SizeSource::Estimated);
+        assert_eq!(root.children[1].size_source, SizeSource::Exact);
+        let public = &root.children[2];
+        assert_eq!(
+            (public.size, public.size_source),
+            (40, SizeSource::Estimated)
+        );
+        assert_eq!(public.children[0].size_source, SizeSource::Estimated);
+        // 没有估算值时保持未知
+        let carol = &root.children[3];
+        assert_eq!((carol.size, carol.size_source), (0, SizeSource::Unknown));
+    }
+
+    #[test]
+    fn test_cached_scan_source() {
+        // 缓存的整卷扫描：bob 已知，carol 在那次扫描中同样无权限
+        let cached = node(
+            "/",
+            9000,
+            vec![node(
+                "/Users",
+                9000,
+                vec![
+                    node(
+                        "/Users/bob",
+                        5000,
+                        vec![node("/Users/bob/Videos", 4000, vec![])],
+                    ),
+                    denied("/Users/carol"),
+                ],
+            )],
+        );
+        let source = CachedScanSizes::new(&cached);
+        assert_eq!(source.dir_size(Path::new("/Users/bob")), Some(5000));
+        assert_eq!(source.dir_size(Path::new("/Users/bob/Videos")), Some(4000));
+        assert_eq!(source.dir_size(Path::new("/Users/carol")), None);
+        assert_eq!(source.dir_size(Path::new("/Users/dave")), None);
+        assert_eq!(source.dir_size(Path::new("/Users/bob/Music")), None);
+
+        let empty = node("/Users", 0, vec![]);
+        let sources = vec![CachedScanSizes::new(&empty), CachedScanSizes::new(&cached)];
+        let mut result = users_scan();
+        assert_eq!(estimate_unknown_sizes(&mut result, &sources), 5000);
+        assert_eq!(result.root.size, 5300);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_cached_scan_matches_verbatim_and_case() {
+        let cached = node(
+            r"C:\",
+            100,
+            vec![node(
+                r"C:\Users",
+                100,
+                vec![node(r"C:\Users\Bob", 100, vec![])],
+            )],
+        );
+        let source = CachedScanSizes::new(&cached);
+        assert_eq!(source.dir_size(Path::new(r"\\?\C:\users\bob")), Some(100));
+    }
+}
@@ -0,0 +1,268 @@
+//! 「这个路径递归有多大」的统一查询。快速统计、计划的字节估算、扫描过期标记等功能都需要它，
+//! 此前各自重新遍历磁盘或在缓存的扫描树中逐级查找。
+//!
+//! [`SizeOracle`] 由一次扫描的结果构建：目录遍历与 MFT 扫描的树（[`SizeOracle::from_tree`]），
+//! 或 MFT 记录集合本身（[`SizeOracle::from_arena`]，与建树时使用同一份递归大小）。
+//! 查询按规范化路径在哈希表中进行；扫描中没有的路径可由 [`SizeOracle::live_recursive_size`]
+//! 遍历文件系统得到，结果会被记住。执行器删除、移动路径后调用 [`SizeOracle::invalidate`]：
+//! 该路径、其下所有路径与各级祖先的大小不再可信，之后的查询改为实时遍历。
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use ai_disk_domain::{FileNode, SizeSource};
+
+use crate::node::normalize_node_path;
+use crate::record_arena::RecordArena;
+use crate::size_fallback::DirSizeSource;
+
+#[derive(Debug, Default)]
+struct Sizes {
+    /// 扫描得到的递归大小，键为规范化路径；失效路径的祖先已移除
+    scanned: HashMap<String, u64>,
+    /// 已失效的路径，其下的扫描结果不再使用
+    invalidated: HashSet<String>,
+    /// 实时遍历得到的递归大小
+    live: HashMap<String, u64>,
+}
+
+impl Sizes {
+    fn lookup(&self, key: &str) -> Option<u64> {
+        if let Some(size) = self.live.get(key) {
+            return Some(*size);
+        }
+        if Path::new(key)
+            .ancestors()
+            .any(|ancestor| self.invalidated.contains(&*ancestor.to_string_lossy()))
+        {
+            return None;
+        }
+        self.scanned.get(key).copied()
+    }
+}
+
+/// 基于一次扫描结果的递归大小查询，可在线程间共享
+#[derive(Debug, Default)]
+pub struct SizeOracle {
+    sizes: RwLock<Sizes>,
+}
+
+impl SizeOracle {
+    fn with_scanned(scanned: HashMap<String, u64>) -> Self {
+        Self {
+            sizes: RwLock::new(Sizes {
+                scanned,
+                ..Sizes::default()
+            }),
+        }
+    }
+
+    /// 由扫描树构建；大小未知（无权限）的节点不收录。剪枝或只计大小的目录本身有递归大小，
+    /// 其下未展开的路径查询不到
+    pub fn from_tree(root: &FileNode) -> Self {
+        let mut scanned = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if node.size_source != SizeSource::Unknown {
+                scanned.insert(normalize_node_path(&node.path), node.size);
+            }
+            stack.extend(&node.children);
+        }
+        Self::with_scanned(scanned)
+    }
+
+    /// 由 MFT 扫描的记录集合构建，包含每一条记录
+    pub fn from_arena(arena: &RecordArena) -> Self {
+        let scanned = arena
+            .recursive_sizes()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, size)| (normalize_node_path(&arena.path(idx as u32)), size))
+            .collect();
+        Self::with_scanned(scanned)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Sizes> {
+        self.sizes.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Sizes> {
+        self.sizes.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 扫描（或此前的实时遍历）得到的递归大小；路径不在扫描中或已失效时返回 None
+    pub fn recursive_size(&self, path: &Path) -> Option<u64> {
+        self.read()
+            .lookup(&normalize_node_path(&path.to_string_lossy()))
+    }
+
+    /// 同 [`Self::recursive_size`]，查询不到时遍历文件系统并记住结果；路径不存在时返回 None
+    pub fn live_recursive_size(&self, path: &Path) -> Option<u64> {
+        let key = normalize_node_path(&path.to_string_lossy());
+        if let Some(size) = self.read().lookup(&key) {
+            return Some(size);
+        }
+        let size = walk_size(path)?;
+        self.write().live.insert(key, size);
+        Some(size)
+    }
+
+    /// 路径已被删除、移动或改写：它本身、其下所有路径与各级祖先的大小都不再可信
+    pub fn invalidate(&self, path: &Path) {
+        let key = normalize_node_path(&path.to_string_lossy());
+        let target = Path::new(&key);
+        let mut sizes = self.write();
+        for ancestor in target.ancestors().skip(1) {
+            sizes.scanned.remove(&*ancestor.to_string_lossy());
+        }
+        sizes
+            .live
+            .retain(|live, _| !Path::new(live).starts_with(target) && !target.starts_with(live));
+        sizes.invalidated.insert(key);
+    }
+}
+
+impl DirSizeSource for SizeOracle {
+    fn dir_size(&self, path: &Path) -> Option<u64> {
+        self.recursive_size(path)
+    }
+}
+
+/// 遍历文件系统累加文件大小，不跟随符号链接；无法读取的目录按 0 计，路径不存在时返回 None
+fn walk_size(path: &Path) -> Option<u64> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return Some(metadata.len());
+    }
+    let mut total = 0u64;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total = total.saturating_add(metadata.len());
+            }
+        }
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_arena::{RecordArenaBuilder, RecordMeta};
+    use crate::scanner::{scan_path_with_progress, WalkOptions};
+    use std::path::PathBuf;
+
+    /// 三层目录、大小各异的文件
+    fn fixture() -> (tempfile::TempDir, Vec<PathBuf>) {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("a/one.bin", 1_000),
+            ("a/two.bin", 2_500),
+            ("a/deep/three.bin", 40_000),
+            ("a/deep/er/four.bin", 7),
+            ("b/five.bin", 123_456),
+            ("six.bin", 64),
+        ];
+        let mut paths = vec![dir.path().to_path_buf()];
+        for (name, len) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, vec![0u8; len]).unwrap();
+            paths.push(path);
+        }
+        fs::create_dir(dir.path().join("empty")).unwrap();
+        for sub in ["a", "a/deep", "a/deep/er", "b", "empty"] {
+            paths.push(dir.path().join(sub));
+        }
+        (dir, paths)
+    }
+
+    fn scan(root: &Path) -> FileNode {
+        let (result, _) = scan_path_with_progress(
+            &root.to_string_lossy(),
+            None,
+            false,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+        result.root
+    }
+
+    #[test]
+    fn test_oracle_matches_direct_walks() {
+        let (dir, paths) = fixture();
+        let tree = SizeOracle::from_tree(&scan(dir.path()));
+
+        let mut builder =
+            RecordArenaBuilder::new(&dir.path().to_string_lossy(), std::path::MAIN_SEPARATOR);
+        for path in &paths[1..] {
+            let metadata = fs::metadata(path).unwrap();
+            let meta = RecordMeta {
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                is_dir: metadata.is_dir(),
+                ..RecordMeta::default()
+            };
+            builder.insert(&path.to_string_lossy(), meta).unwrap();
+        }
+        let arena = SizeOracle::from_arena(&builder.finish());
+
+        for path in &paths {
+            let walked = walk_size(path);
+            assert!(walked.is_some(), "{}", path.display());
+            assert_eq!(tree.recursive_size(path), walked, "{}", path.display());
+            assert_eq!(arena.recursive_size(path), walked, "{}", path.display());
+        }
+        assert_eq!(tree.recursive_size(dir.path()), Some(167_027));
+        let missing = dir.path().join("missing");
+        assert_eq!(tree.recursive_size(&missing), None);
+        assert_eq!(tree.live_recursive_size(&missing), None);
+    }
+
+    #[test]
+    fn test_invalidation_after_delete() {
+        let (dir, _) = fixture();
+        let oracle = SizeOracle::from_tree(&scan(dir.path()));
+        let a = dir.path().join("a");
+        let deep = a.join("deep");
+        let three = deep.join("three.bin");
+        assert_eq!(oracle.recursive_size(&deep), Some(40_007));
+
+        fs::remove_file(&three).unwrap();
+        oracle.invalidate(&three);
+        // 删除的路径及各级祖先失效，兄弟目录不受影响
+        assert_eq!(oracle.recursive_size(&three), None);
+        assert_eq!(oracle.recursive_size(&deep), None);
+        assert_eq!(oracle.recursive_size(&a), None);
+        assert_eq!(oracle.recursive_size(dir.path()), None);
+        assert_eq!(oracle.recursive_size(&dir.path().join("b")), Some(123_456));
+        assert_eq!(oracle.recursive_size(&deep.join("er")), Some(7));
+
+        // 实时遍历得到新值并记住
+        assert_eq!(oracle.live_recursive_size(&three), None);
+        assert_eq!(oracle.live_recursive_size(&a), Some(3_507));
+        assert_eq!(oracle.recursive_size(&a), Some(3_507));
+        assert_eq!(oracle.live_recursive_size(dir.path()), Some(127_027));
+
+        // 再次删除其下的路径时记住的值同样失效
+        fs::remove_dir_all(&deep).unwrap();
+        oracle.invalidate(&deep);
+        assert_eq!(oracle.recursive_size(&a), None);
+        assert_eq!(oracle.recursive_size(dir.path()), None);
+        assert_eq!(oracle.recursive_size(&deep.join("er")), None);
+        assert_eq!(oracle.live_recursive_size(&a), Some(3_500));
+    }
+}
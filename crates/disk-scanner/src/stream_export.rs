@@ -0,0 +1,376 @@
+//! 流式导出：把扫描结果按 JSON Lines 逐行写出（首行 header、每个文件/目录一行 entry、末行 trailer），
+//! 供 jq 等工具或外部集成增量处理，避免序列化一整个 ScanResult。
+//!
+//! `tree: false` 时边遍历边输出，内存中只保留当前路径上各层目录的子项列表；
+//! 遍历规则（深度上限、每目录子项上限、shallow 目录只计大小）与普通扫描一致，trailer 合计与其相同。
+//! `tree: true` 时先按普通扫描建完整棵树（卷根可走 MFT），再逐项输出。
+
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::time::{Instant, UNIX_EPOCH};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::{
+    FileNode, ScanStreamEntry, ScanStreamHeader, ScanStreamRecord, ScanStreamTotals,
+    SCAN_STREAM_VERSION,
+};
+
+use crate::display_path::DisplayPath;
+use crate::mount_points::{is_volume_root, FsBoundary};
+use crate::scanner::{
+    dir_size_only, is_collapsed_dir_name, is_corruption_io_error, list_children, resolve_scan_root,
+    scan_path_with_progress, walk_error, ProgressCbArc, WalkOptions, MAX_DEPTH,
+};
+
+/// 占位节点名称的后缀标记（见 [`crate::scanner::placeholder_node`]）→ entry 的 error
+const PLACEHOLDER_MARKS: &[(&str, &str)] = &[(" [无权限]", "无权限"), (" [损坏]", "损坏")];
+
+/// 流式导出参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// 先建完整棵树再输出（可使用 MFT 与并行遍历，但内存占用与普通扫描相同）
+    pub tree: bool,
+    /// node_modules、.git 等目录只计大小不展开
+    pub shallow_dirs: bool,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            tree: false,
+            shallow_dirs: true,
+        }
+    }
+}
+
+/// 扫描 `path` 并把 JSON Lines 写入 `writer`，返回 trailer 中的合计
+pub fn scan_to_writer<W: Write>(
+    path: &str,
+    writer: W,
+    options: &StreamOptions,
+) -> Result<ScanStreamTotals, DiskAnalyzerError> {
+    let start = Instant::now();
+    let root = resolve_scan_root(path)?;
+    let mut out = RecordWriter(BufWriter::new(writer));
+    out.write(&ScanStreamRecord::Header(ScanStreamHeader {
+        version: SCAN_STREAM_VERSION,
+        path: DisplayPath::new(&root.display().to_string()).into_string(),
+        tree: options.tree,
+        shallow_dirs: options.shallow_dirs,
+    }))?;
+
+    let (file_count, total_size) = if options.tree {
+        let (result, _) = scan_path_with_progress(
+            path,
+            None::<&ProgressCbArc>,
+            options.shallow_dirs,
+            true,
+            None,
+            None,
+            &WalkOptions::default(),
+        )?;
+        write_tree(&mut out, &result.root, 0)?;
+        (result.file_count, result.total_size)
+    } else {
+        let mut walker = StreamWalker {
+            out: &mut out,
+            shallow_dirs: options.shallow_dirs,
+            // 与普通扫描的默认行为一致：扫描卷根时不进入其他文件系统的挂载点
+            fs_boundary: is_volume_root(&root)
+                .then(|| FsBoundary::for_root(&root))
+                .flatten(),
+            counter: AtomicU64::new(0),
+        };
+        let (size, count) = walker.visit(&root, 0)?;
+        (count, size)
+    };
+
+    let totals = ScanStreamTotals {
+        file_count,
+        total_size,
+        scan_time_ms: start.elapsed().as_millis() as u64,
+    };
+    out.write(&ScanStreamRecord::Trailer(totals))?;
+    out.0.flush().map_err(DiskAnalyzerError::Io)?;
+    Ok(totals)
+}
+
+/// 每条记录一行
+struct RecordWriter<W: Write>(BufWriter<W>);
+
+impl<W: Write> RecordWriter<W> {
+    fn write(&mut self, record: &ScanStreamRecord) -> Result<(), DiskAnalyzerError> {
+        serde_json::to_writer(&mut self.0, record)
+            .map_err(|e| DiskAnalyzerError::Io(std::io::Error::other(e)))?;
+        self.0.write_all(b"\n").map_err(DiskAnalyzerError::Io)
+    }
+
+    fn entry(
+        &mut self,
+        path: &Path,
+        size: u64,
+        is_dir: bool,
+        modified: Option<u64>,
+        depth: usize,
+        error: Option<&str>,
+    ) -> Result<(), DiskAnalyzerError> {
+        self.write(&ScanStreamRecord::Entry(ScanStreamEntry {
+            path: DisplayPath::new(&path.display().to_string()).into_string(),
+            size,
+            is_dir,
+            modified,
+            depth,
+            error: error.map(str::to_string),
+            is_mount_point: false,
+        }))
+    }
+
+    /// 其他文件系统的挂载点：不展开，大小为 0
+    fn mount_point(&mut self, path: &Path, depth: usize) -> Result<(), DiskAnalyzerError> {
+        self.write(&ScanStreamRecord::Entry(ScanStreamEntry {
+            path: DisplayPath::new(&path.display().to_string()).into_string(),
+            size: 0,
+            is_dir: true,
+            modified: None,
+            depth,
+            error: None,
+            is_mount_point: true,
+        }))
+    }
+}
+
+/// 按后序输出已建好的树（目录在其子项之后，与边遍历边输出的顺序一致）
+fn write_tree<W: Write>(
+    out: &mut RecordWriter<W>,
+    node: &FileNode,
+    depth: usize,
+) -> Result<(), DiskAnalyzerError> {
+    for child in &node.children {
+        write_tree(out, child, depth + 1)?;
+    }
+    let error = PLACEHOLDER_MARKS
+        .iter()
+        .find(|(mark, _)| node.name.ends_with(mark))
+        .map(|&(_, reason)| reason);
+    out.write(&ScanStreamRecord::Entry(ScanStreamEntry {
+        path: node.path.clone(),
+        size: node.size,
+        is_dir: node.is_dir,
+        modified: node.modified,
+        depth,
+        error: error.map(str::to_string),
+        is_mount_point: node.is_mount_point,
+    }))
+}
+
+/// 顺序遍历并即时输出，规则与 [`crate::scanner::build_tree`] / [`crate::scanner::build_child`] 相同
+struct StreamWalker<'a, W: Write> {
+    out: &'a mut RecordWriter<W>,
+    shallow_dirs: bool,
+    fs_boundary: Option<FsBoundary>,
+    /// dir_size_only 的进度计数，导出时不上报
+    counter: AtomicU64,
+}
+
+impl<W: Write> StreamWalker<'_, W> {
+    /// 输出 `path` 及其子项，返回 `(大小, 文件数)`
+    fn visit(&mut self, path: &Path, depth: usize) -> Result<(u64, u64), DiskAnalyzerError> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(m) => m,
+            Err(e) if is_corruption_io_error(&e) => {
+                self.out.entry(path, 0, false, None, depth, Some("损坏"))?;
+                return Ok((0, 0));
+            }
+            Err(e) => return Err(walk_error(path, e)),
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        if !metadata.is_dir() {
+            self.out
+                .entry(path, metadata.len(), false, modified, depth, None)?;
+            return Ok((metadata.len(), 1));
+        }
+
+        let mut size = 0u64;
+        let mut file_count = 0u64;
+        if depth < MAX_DEPTH {
+            let entries = match std::fs::read_dir(path) {
+                Ok(iter) => iter,
+                Err(e) if is_corruption_io_error(&e) => {
+                    self.out.entry(path, 0, true, modified, depth, None)?;
+                    return Ok((0, 0));
+                }
+                Err(e) => return Err(walk_error(path, e)),
+            };
+            for entry in list_children(entries, None).0 {
+                let child_name = entry.file_name().to_string_lossy().to_string();
+                let (child_size, child_count) =
+                    self.visit_child(&entry.path(), &child_name, depth + 1)?;
+                size += child_size;
+                file_count += child_count;
+            }
+        }
+        self.out.entry(path, size, true, modified, depth, None)?;
+        Ok((size, file_count))
+    }
+
+    /// 目录下的单个子项；shallow 目录只计大小，无权限或损坏的子项输出带 error 的占位记录
+    fn visit_child(
+        &mut self,
+        path: &Path,
+        name: &str,
+        depth: usize,
+    ) -> Result<(u64, u64), DiskAnalyzerError> {
+        let is_dir = path.is_dir();
+        if is_dir && self.fs_boundary.is_some_and(|b| b.is_mount_point(path)) {
+            self.out.mount_point(path, depth)?;
+            return Ok((0, 0));
+        }
+        let result = if is_dir && is_collapsed_dir_name(name, self.shallow_dirs) {
+            dir_size_only(path, &self.counter, None).and_then(|size| {
+                let modified = std::fs::symlink_metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs());
+                self.out.entry(path, size, true, modified, depth, None)?;
+                Ok((size, 1))
+            })
+        } else {
+            self.visit(path, depth)
+        };
+        let reason = match result {
+            Err(DiskAnalyzerError::PermissionDenied(_)) => "无权限",
+            Err(DiskAnalyzerError::Io(ref e)) if is_corruption_io_error(e) => "损坏",
+            other => return other,
+        };
+        self.out.entry(path, 0, is_dir, None, depth, Some(reason))?;
+        Ok((0, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// root/{a/{x.bin(300), y.bin(20)}, node_modules/{m.js(7)}, b.txt(5)}
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("a").join("x.bin"), vec![0u8; 300]).unwrap();
+        fs::write(root.join("a").join("y.bin"), vec![0u8; 20]).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("node_modules").join("m.js"), vec![0u8; 7]).unwrap();
+        fs::write(root.join("b.txt"), b"hello").unwrap();
+        dir
+    }
+
+    fn export(path: &Path, options: &StreamOptions) -> (ScanStreamTotals, Vec<ScanStreamRecord>) {
+        let mut buf = Vec::new();
+        let totals = scan_to_writer(&path.to_string_lossy(), &mut buf, options).unwrap();
+        let records = String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (totals, records)
+    }
+
+    fn entry_records(records: &[ScanStreamRecord]) -> Vec<&ScanStreamEntry> {
+        records
+            .iter()
+            .filter_map(|r| match r {
+                ScanStreamRecord::Entry(e) => Some(e),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_totals_match_scan() {
+        let dir = fixture();
+        for shallow_dirs in [true, false] {
+            let (scan, _) = scan_path_with_progress(
+                &dir.path().to_string_lossy(),
+                None::<&ProgressCbArc>,
+                shallow_dirs,
+                false,
+                None,
+                None,
+                &WalkOptions::default(),
+            )
+            .unwrap();
+            for tree in [false, true] {
+                let options = StreamOptions { tree, shallow_dirs };
+                let (totals, records) = export(dir.path(), &options);
+
+                let ScanStreamRecord::Header(header) = &records[0] else {
+                    panic!("first record is not a header: {:?}", records[0]);
+                };
+                assert_eq!(header.version, SCAN_STREAM_VERSION);
+                assert_eq!(header.path, scan.root.path);
+                assert_eq!((header.tree, header.shallow_dirs), (tree, shallow_dirs));
+
+                let Some(ScanStreamRecord::Trailer(trailer)) = records.last() else {
+                    panic!("last record is not a trailer");
+                };
+                assert_eq!(*trailer, totals);
+                assert_eq!(trailer.file_count, scan.file_count, "{:?}", options);
+                assert_eq!(trailer.total_size, scan.total_size, "{:?}", options);
+                assert_eq!(trailer.total_size, 332);
+
+                // 根目录最后输出，大小即合计；文件大小之和与合计一致
+                let entries = entry_records(&records);
+                let root = entries.last().unwrap();
+                assert_eq!((root.depth, root.size), (0, 332));
+                assert_eq!(root.path, scan.root.path);
+                let names: Vec<&str> = entries
+                    .iter()
+                    .map(|e| e.path.rsplit(['/', '\\']).next().unwrap())
+                    .collect();
+                assert_eq!(names.contains(&"m.js"), !shallow_dirs, "{:?}", options);
+                let file_bytes: u64 = entries.iter().filter(|e| !e.is_dir).map(|e| e.size).sum();
+                let shallow_bytes = if shallow_dirs { 7 } else { 0 };
+                assert_eq!(file_bytes + shallow_bytes, 332);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_dirs_follow_their_children() {
+        let dir = fixture();
+        let (_, records) = export(dir.path(), &StreamOptions::default());
+        let entries = entry_records(&records);
+        let pos = |name: &str| {
+            entries
+                .iter()
+                .position(|e| e.path.rsplit(['/', '\\']).next() == Some(name))
+                .unwrap()
+        };
+        assert!(pos("x.bin") < pos("a"));
+        assert!(pos("y.bin") < pos("a"));
+        let a = &entries[pos("a")];
+        assert_eq!((a.size, a.depth, a.is_dir), (320, 1, true));
+        assert_eq!(entries[pos("x.bin")].depth, 2);
+        assert!(entries
+            .iter()
+            .all(|e| e.error.is_none() && e.modified.is_some()));
+    }
+
+    #[test]
+    fn test_stream_missing_path() {
+        let err = scan_to_writer(
+            "/nonexistent_xyz_12345_folder",
+            Vec::new(),
+            &StreamOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::InvalidPath(_)));
+    }
+}
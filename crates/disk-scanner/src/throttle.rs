@@ -0,0 +1,225 @@
+//! 后台扫描：降低扫描线程的 CPU / IO 优先级，并用令牌桶限制目录项的读取速度。
+//!
+//! 限速器通过线程池的启动回调挂到本次扫描的每个工作线程上（线程局部变量），
+//! 遍历代码每读完一个目录调用 [`on_dir_listed`]，无需在递归参数中逐层传递；
+//! 交互式扫描的线程池不挂限速器，调用即为空操作。未启用 `parallel` 时扫描在调用线程上进行，
+//! 限速器只在扫描期间挂到调用线程上，也不调整其优先级。
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 后台扫描默认每秒读取的目录项数
+pub const DEFAULT_BACKGROUND_ENTRIES_PER_SEC: u64 = 20_000;
+
+/// 后台扫描参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundScan {
+    /// 每秒最多读取的目录项数（至少为 1）
+    pub entries_per_sec: u64,
+}
+
+impl Default for BackgroundScan {
+    fn default() -> Self {
+        Self {
+            entries_per_sec: DEFAULT_BACKGROUND_ENTRIES_PER_SEC,
+        }
+    }
+}
+
+/// 令牌桶：以 `rate` 个/秒补充令牌，最多积攒 `burst` 个。
+/// 令牌不足时允许透支，透支部分按速率换算为等待时长，多个线程共用时总速率仍受限
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+    throttled: AtomicBool,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// 新建时桶是满的
+    pub fn new(rate_per_sec: u64, burst: u64) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            rate: rate_per_sec.max(1) as f64,
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                last: Instant::now(),
+            }),
+            throttled: AtomicBool::new(false),
+        }
+    }
+
+    /// 在 `now` 时刻取 `n` 个令牌，返回调用方需要等待的时长
+    pub fn reserve_at(&self, n: u64, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(state.last).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+        state.last = state.last.max(now);
+        state.tokens -= n as f64;
+        if state.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        self.throttled.store(true, Ordering::Relaxed);
+        Duration::from_secs_f64(-state.tokens / self.rate)
+    }
+
+    /// 取 `n` 个令牌，不足时阻塞等待
+    pub fn acquire(&self, n: u64) {
+        let wait = self.reserve_at(n, Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// 是否曾因令牌不足而让调用方等待
+    pub fn has_throttled(&self) -> bool {
+        self.throttled.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static THREAD_THROTTLE: RefCell<Option<Arc<TokenBucket>>> = const { RefCell::new(None) };
+}
+
+/// 在扫描线程池的启动回调中调用：降低当前线程优先级并挂上限速器
+#[cfg(feature = "parallel")]
+pub(crate) fn enter_background(bucket: Arc<TokenBucket>) {
+    priority::lower_current_thread();
+    THREAD_THROTTLE.with(|t| *t.borrow_mut() = Some(bucket));
+}
+
+/// 未启用 `parallel` 时扫描在调用线程上进行：只在 `f` 执行期间挂上限速器，不改动调用线程的优先级
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn with_throttle<R>(bucket: Arc<TokenBucket>, f: impl FnOnce() -> R) -> R {
+    let previous = THREAD_THROTTLE.with(|t| t.borrow_mut().replace(bucket));
+    let result = f();
+    THREAD_THROTTLE.with(|t| *t.borrow_mut() = previous);
+    result
+}
+
+/// 读完一个目录（`entries` 个目录项）后调用；扫描已暂停时阻塞到继续为止，后台扫描时按速率等待并让出 CPU
+pub(crate) fn on_dir_listed(entries: usize) {
+    crate::pause::checkpoint();
+    THREAD_THROTTLE.with(|t| {
+        if let Some(bucket) = t.borrow().as_ref() {
+            // 空目录也计一次读取
+            bucket.acquire(entries.max(1) as u64);
+            std::thread::yield_now();
+        }
+    });
+}
+
+/// 降低当前线程的调度优先级，供执行计划等在专用线程上进行的后台任务调用；线程结束前不会恢复
+pub fn lower_current_thread_priority() {
+    priority::lower_current_thread();
+}
+
+/// 降低当前线程的调度优先级（扫描线程池的工作线程与后台任务的专用线程调用）
+mod priority {
+    /// Linux：nice 19 + IO 空闲调度类（均按线程 id 设置，只影响当前线程）
+    #[cfg(target_os = "linux")]
+    #[allow(unsafe_code)]
+    pub(super) fn lower_current_thread() {
+        const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+        // SAFETY: 只修改当前线程的调度参数；失败时保持原优先级
+        unsafe {
+            let tid = libc::syscall(libc::SYS_gettid);
+            libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, 19);
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                tid,
+                IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+            );
+        }
+    }
+
+    /// macOS：把当前线程置为后台（同时降低 CPU 与 IO 优先级）
+    #[cfg(target_os = "macos")]
+    #[allow(unsafe_code)]
+    pub(super) fn lower_current_thread() {
+        // SAFETY: 只修改当前线程的调度参数；失败时保持原优先级
+        unsafe {
+            libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG);
+        }
+    }
+
+    /// Windows：THREAD_MODE_BACKGROUND_BEGIN 同时降低 CPU、IO 与内存优先级；线程结束后无需恢复
+    #[cfg(all(windows, feature = "windows-native"))]
+    #[allow(unsafe_code)]
+    pub(super) fn lower_current_thread() {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+        };
+        // SAFETY: GetCurrentThread 返回的伪句柄无需关闭
+        unsafe {
+            SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+        }
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "macos",
+        all(windows, feature = "windows-native")
+    )))]
+    pub(super) fn lower_current_thread() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 等待时长（毫秒，四舍五入，避免浮点换算的纳秒误差）
+    fn ms(d: Duration) -> u128 {
+        (d.as_secs_f64() * 1000.0).round() as u128
+    }
+
+    #[test]
+    fn test_token_bucket_math() {
+        let bucket = TokenBucket::new(10, 5);
+        let t0 = Instant::now();
+        // 满桶时 5 个令牌无需等待
+        assert_eq!(ms(bucket.reserve_at(5, t0)), 0);
+        assert!(!bucket.has_throttled());
+        // 透支 5 个，按 10 个/秒需要等 0.5 秒
+        assert_eq!(ms(bucket.reserve_at(5, t0)), 500);
+        assert!(bucket.has_throttled());
+        // 1 秒后补充 10 个：偿还透支后剩 5 个（不超过 burst）
+        assert_eq!(ms(bucket.reserve_at(5, t0 + Duration::from_secs(1))), 0);
+        // 很久之后也最多积攒 burst 个
+        let later = t0 + Duration::from_secs(60);
+        assert_eq!(ms(bucket.reserve_at(5, later)), 0);
+        assert_eq!(ms(bucket.reserve_at(1, later)), 100);
+        // 早于上次记录的时刻不会补充令牌
+        assert_eq!(ms(bucket.reserve_at(1, t0)), 200);
+    }
+
+    #[test]
+    fn test_zero_rate_is_clamped() {
+        let bucket = TokenBucket::new(0, 0);
+        let t0 = Instant::now();
+        assert_eq!(ms(bucket.reserve_at(1, t0)), 0);
+        assert_eq!(ms(bucket.reserve_at(1, t0)), 1000);
+    }
+
+    #[test]
+    fn test_on_dir_listed_without_bucket_is_noop() {
+        let start = Instant::now();
+        for _ in 0..1_000 {
+            on_dir_listed(1_000);
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
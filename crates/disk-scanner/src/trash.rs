@@ -0,0 +1,194 @@
+//! 用户废纸篓：macOS 为 `~/.Trash`，Linux 为 XDG 规范的 `$XDG_DATA_HOME/Trash`（默认 `~/.local/share/Trash`，
+//! 其下 `files/` 存放被删除的内容，`info/` 存放对应的 `.trashinfo`）。
+//!
+//! 废纸篓在树中只是普通目录，容易被忽略。扫描范围包含废纸篓时（扫描家目录或卷根），
+//! 将对应节点标记为 [`FileCategory::Trash`]，并把其大小记入 `ScanResult::trash_bytes`。
+//!
+//! 按卷存放的回收站（Windows `$Recycle.Bin`、macOS 外接卷 `.Trashes`、XDG 卷废纸篓 `.Trash-<uid>`）
+//! 按目录名识别，建树时与 shallow 目录一样只统计总大小、不展开子项；用户废纸篓在标记时去掉子项。
+//! 废纸篓在树中始终是不含子项的单个节点，只能整体清空，不会被逐个文件提出建议。
+
+use std::path::{Path, PathBuf};
+
+use ai_disk_domain::{FileCategory, FileNode};
+
+use crate::cleanup_targets::{KnownFolders, Platform};
+use crate::display_path::DisplayPath;
+
+/// 某平台下当前用户的废纸篓目录；Windows 回收站按卷存放，不在此列
+pub fn trash_dirs(platform: Platform, folders: &KnownFolders) -> Vec<PathBuf> {
+    match platform {
+        Platform::MacOs => folders.home.iter().map(|h| h.join(".Trash")).collect(),
+        Platform::Linux => folders.xdg_data.iter().map(|d| d.join("Trash")).collect(),
+        Platform::Windows => Vec::new(),
+    }
+}
+
+/// 当前系统与用户的废纸篓目录
+pub fn user_trash_dirs() -> Vec<PathBuf> {
+    trash_dirs(Platform::current(), &KnownFolders::from_env())
+}
+
+/// 是否为按卷存放的回收站目录名：`$Recycle.Bin`（大小写不敏感）、`.Trashes` 或 `.Trash-<uid>`
+pub fn is_volume_trash_name(name: &str) -> bool {
+    name.eq_ignore_ascii_case("$Recycle.Bin")
+        || name == ".Trashes"
+        || name
+            .strip_prefix(".Trash-")
+            .is_some_and(|uid| !uid.is_empty() && uid.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// 在已整理的扫描树中标记位于其中的废纸篓节点（用户废纸篓与按卷存放的回收站），
+/// 去掉其子项，返回它们的大小之和；扫描范围不包含任何存在的废纸篓时返回 None
+pub(crate) fn tag_trash(root: &mut FileNode, trash_dirs: &[PathBuf]) -> Option<u64> {
+    let mut total = None;
+    for dir in trash_dirs {
+        // 树中的路径为 canonicalize 后的 DisplayPath，家目录是符号链接时也能对上
+        let Ok(canonical) = std::fs::canonicalize(dir) else {
+            continue;
+        };
+        let target = DisplayPath::new(&canonical.to_string_lossy()).into_string();
+        if let Some(node) = find_node_mut(root, Path::new(&target)) {
+            collapse_trash(node, &mut total);
+        }
+    }
+    tag_volume_trash(root, &mut total);
+    total
+}
+
+fn collapse_trash(node: &mut FileNode, total: &mut Option<u64>) {
+    node.category = Some(FileCategory::Trash);
+    node.children = Vec::new();
+    node.partial_children = true;
+    *total = Some(total.unwrap_or(0) + node.size);
+}
+
+fn tag_volume_trash(node: &mut FileNode, total: &mut Option<u64>) {
+    if node.category == Some(FileCategory::Trash) {
+        return;
+    }
+    if node.is_dir && is_volume_trash_name(&node.name) {
+        collapse_trash(node, total);
+        return;
+    }
+    for child in &mut node.children {
+        tag_volume_trash(child, total);
+    }
+}
+
+/// 沿路径前缀向下查找节点；路径位于未展开的目录下时返回 None
+pub(crate) fn find_node_mut<'a>(node: &'a mut FileNode, target: &Path) -> Option<&'a mut FileNode> {
+    if Path::new(&node.path) == target {
+        return Some(node);
+    }
+    if !target.starts_with(&node.path) {
+        return None;
+    }
+    node.children
+        .iter_mut()
+        .find(|child| target.starts_with(&child.path))
+        .and_then(|child| find_node_mut(child, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+    use std::fs;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+    }
+
+    fn find<'a>(node: &'a FileNode, name: &str) -> Option<&'a FileNode> {
+        if node.name == name {
+            return Some(node);
+        }
+        node.children.iter().find_map(|c| find(c, name))
+    }
+
+    #[test]
+    fn test_trash_locations_per_platform() {
+        let home = OsString::from("/home/u");
+        let lookup = |key: &str| (key == "HOME").then(|| home.clone());
+        let linux = KnownFolders::from_lookup(Platform::Linux, lookup);
+        assert_eq!(
+            trash_dirs(Platform::Linux, &linux),
+            vec![PathBuf::from("/home/u/.local/share/Trash")]
+        );
+        let mac = KnownFolders::from_lookup(Platform::MacOs, lookup);
+        assert_eq!(
+            trash_dirs(Platform::MacOs, &mac),
+            vec![PathBuf::from("/home/u/.Trash")]
+        );
+
+        let xdg = OsString::from("/data/xdg");
+        let overridden = KnownFolders::from_lookup(Platform::Linux, |key| match key {
+            "HOME" => Some(home.clone()),
+            "XDG_DATA_HOME" => Some(xdg.clone()),
+            _ => None,
+        });
+        assert_eq!(
+            trash_dirs(Platform::Linux, &overridden),
+            vec![PathBuf::from("/data/xdg/Trash")]
+        );
+        assert!(trash_dirs(Platform::Windows, &linux).is_empty());
+    }
+
+    #[test]
+    fn test_scan_measures_xdg_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = fs::canonicalize(dir.path()).unwrap();
+        let trash = home.join(".local").join("share").join("Trash");
+        write(&trash.join("files").join("old.iso"), 3000);
+        write(&trash.join("files").join("photos").join("a.jpg"), 500);
+        write(&trash.join("info").join("old.iso.trashinfo"), 60);
+        write(&trash.join("info").join("photos.trashinfo"), 40);
+        write(&home.join("notes.txt"), 7);
+        let folders = KnownFolders::from_lookup(Platform::Linux, |key| {
+            (key == "HOME").then(|| home.clone().into_os_string())
+        });
+        let dirs = trash_dirs(Platform::Linux, &folders);
+
+        let mut result = crate::scan_path(&home.to_string_lossy()).unwrap();
+        assert_eq!(tag_trash(&mut result.root, &dirs), Some(3600));
+        let node = find(&result.root, "Trash").unwrap();
+        assert_eq!(node.category, Some(FileCategory::Trash));
+        assert!(node.children.is_empty());
+        assert!(find(&result.root, "notes.txt").unwrap().category.is_none());
+
+        // 扫描范围不包含废纸篓时不计
+        write(&home.join("docs").join("a.txt"), 9);
+        let mut other = crate::scan_path(&home.join("docs").to_string_lossy()).unwrap();
+        assert_eq!(tag_trash(&mut other.root, &dirs), None);
+        assert_eq!(tag_trash(&mut result.root, &[home.join("missing")]), None);
+    }
+
+    #[test]
+    fn test_volume_recycle_bins_are_single_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let sid = root.join("$Recycle.Bin").join("S-1-5-21-1000");
+        write(&sid.join("$RAB12CD.iso"), 4000);
+        write(&sid.join("$IAB12CD.iso"), 100);
+        write(&sid.join("$RXY34EF").join("a.txt"), 500);
+        write(&root.join(".Trash-1000").join("files").join("b.bin"), 250);
+        write(&root.join("data").join(".Trash-x").join("keep.txt"), 9);
+
+        let result = crate::scan_path(&root.to_string_lossy()).unwrap();
+        let recycle_bin = find(&result.root, "$Recycle.Bin").unwrap();
+        assert_eq!(recycle_bin.category, Some(FileCategory::Trash));
+        assert_eq!(recycle_bin.size, 4600);
+        assert!(recycle_bin.children.is_empty());
+        let xdg = find(&result.root, ".Trash-1000").unwrap();
+        assert_eq!((xdg.size, xdg.category), (250, Some(FileCategory::Trash)));
+        assert!(xdg.children.is_empty());
+        assert_eq!(result.trash_bytes, Some(4850));
+        // 只按目录名识别回收站，名称相近的普通目录照常展开
+        let other = find(&result.root, ".Trash-x").unwrap();
+        assert!(other.category.is_none() && !other.children.is_empty());
+        assert!(!is_volume_trash_name(".Trash-"));
+        assert!(is_volume_trash_name("$RECYCLE.BIN"));
+    }
+}
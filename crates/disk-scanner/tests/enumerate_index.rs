@@ -0,0 +1,163 @@
+//! MFT 枚举阶段的子节点索引：RecordArenaBuilder（父记录下标 + 名称哈希）与原先按父路径字符串
+//! 建立的 `HashMap<String, Vec<usize>>` 结果一致性，以及两者的枚举耗时对比。
+//!
+//! 运行（耗时对比需 `--nocapture`，建议 release）：
+//!   cargo test -p ai-disk-scanner --release --test enumerate_index -- --nocapture
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ai_disk_scanner::{RecordArena, RecordArenaBuilder, RecordMeta};
+
+const ROOT: &str = r"C:\";
+
+fn file(size: u64) -> RecordMeta {
+    RecordMeta {
+        size,
+        ..RecordMeta::default()
+    }
+}
+
+fn dir() -> RecordMeta {
+    RecordMeta {
+        is_dir: true,
+        ..RecordMeta::default()
+    }
+}
+
+/// 原 scan_volume_mft 的做法：按父路径字符串索引子记录，按路径累加直接大小
+struct StringIndex {
+    paths: Vec<String>,
+    child_index: HashMap<String, Vec<usize>>,
+    direct_sizes: HashMap<String, u64>,
+}
+
+fn build_string_index(records: &[(String, RecordMeta)]) -> StringIndex {
+    let mut index = StringIndex {
+        paths: Vec::with_capacity(records.len()),
+        child_index: HashMap::new(),
+        direct_sizes: HashMap::new(),
+    };
+    for (path, meta) in records {
+        let idx = index.paths.len();
+        index.paths.push(path.clone());
+        let path_trim = path.trim_end_matches('\\');
+        if let Some(i) = path_trim.rfind('\\') {
+            index
+                .child_index
+                .entry(path[..i].to_string())
+                .or_default()
+                .push(idx);
+        }
+        *index.direct_sizes.entry(path_trim.to_string()).or_insert(0) += meta.size;
+    }
+    index
+}
+
+fn build_arena(records: &[(String, RecordMeta)]) -> RecordArena {
+    let mut builder = RecordArenaBuilder::new(ROOT, '\\');
+    for (path, meta) in records {
+        builder.insert(path, *meta).unwrap();
+    }
+    builder.finish()
+}
+
+/// 父路径（不带末尾分隔符）-> 排序后的子记录路径；子节点顺序由 finalize_tree 统一决定，这里只比较集合
+fn arena_children_by_path(arena: &RecordArena) -> HashMap<String, Vec<String>> {
+    (0..arena.len() as u32)
+        .filter(|&i| !arena.children(i).is_empty())
+        .map(|i| {
+            let parent = arena.path(i).trim_end_matches('\\').to_string();
+            let mut children: Vec<String> =
+                arena.children(i).iter().map(|&c| arena.path(c)).collect();
+            children.sort();
+            (parent, children)
+        })
+        .collect()
+}
+
+/// 乱序的夹具：文件先于父目录出现、根目录下有文件、目录记录晚到、同名不同级
+fn fixture() -> Vec<(String, RecordMeta)> {
+    [
+        (r"C:\Users\me\Downloads\setup.exe", file(700)),
+        (r"C:\pagefile.sys", file(4096)),
+        (r"C:\Users", dir()),
+        (r"C:\Users\me\Downloads\a.zip", file(300)),
+        (r"C:\Users\me", dir()),
+        (r"C:\Windows\Temp\me", dir()),
+        (r"C:\Windows\Temp\me\x.log", file(12)),
+        (r"C:\Users\me\Downloads", dir()),
+        (r"C:\Windows", dir()),
+        (r"C:\Windows\Temp", dir()),
+        (r"C:\Users\me\notes.txt", file(5)),
+    ]
+    .into_iter()
+    .map(|(p, m)| (p.to_string(), m))
+    .collect()
+}
+
+#[test]
+fn test_arena_index_matches_string_index() {
+    let records = fixture();
+    let old = build_string_index(&records);
+    let arena = build_arena(&records);
+
+    let expected: HashMap<String, Vec<String>> = old
+        .child_index
+        .iter()
+        .map(|(parent, indices)| {
+            let mut children: Vec<String> = indices.iter().map(|&i| old.paths[i].clone()).collect();
+            children.sort();
+            (parent.clone(), children)
+        })
+        .collect();
+    assert_eq!(arena_children_by_path(&arena), expected);
+
+    let sizes = arena.recursive_sizes();
+    for i in 0..arena.len() as u32 {
+        let path = arena.path(i);
+        let direct = old
+            .direct_sizes
+            .get(path.trim_end_matches('\\'))
+            .copied()
+            .unwrap_or(0);
+        assert_eq!(arena.meta(i).size, direct, "{}", path);
+    }
+    assert_eq!(sizes[0], records.iter().map(|(_, m)| m.size).sum::<u64>());
+}
+
+/// 合成的枚举记录：按 MFT 常见顺序（目录与文件交错）生成
+fn synthetic_records(dirs: usize, files_per_dir: usize) -> Vec<(String, RecordMeta)> {
+    let mut records = Vec::with_capacity(dirs * (files_per_dir + 1));
+    for d in 0..dirs {
+        let dir_path = format!(r"C:\Users\user_{:02}\AppData\Local\cache_{:05}", d % 16, d);
+        records.push((dir_path.clone(), dir()));
+        for f in 0..files_per_dir {
+            records.push((format!(r"{}\entry_{:06}.bin", dir_path, f), file(f as u64)));
+        }
+    }
+    records
+}
+
+#[test]
+fn bench_enumerate_index() {
+    let records = synthetic_records(2_000, 250);
+
+    let start = Instant::now();
+    let old = build_string_index(&records);
+    let string_ms = start.elapsed().as_millis();
+
+    let start = Instant::now();
+    let arena = build_arena(&records);
+    let arena_ms = start.elapsed().as_millis();
+
+    assert_eq!(old.paths.len(), records.len());
+    // 中间目录（user_xx、AppData、Local）由构建器补建
+    assert!(arena.len() > records.len());
+    eprintln!(
+        "[enumerate_index] {} records: string-keyed HashMap {} ms, RecordArenaBuilder {} ms",
+        records.len(),
+        string_ms,
+        arena_ms
+    );
+}
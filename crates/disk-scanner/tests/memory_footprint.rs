@@ -0,0 +1,153 @@
+//! 扫描记录内存占用测试：用计数分配器比较 100 万条合成记录在两种表示下的峰值分配字节数。
+//!
+//! - **完整路径**：每条记录持有绝对路径，另有按父路径索引的子节点表与按路径索引的大小表（原 MFT 扫描的做法）。
+//! - **RecordArena**：名称 + 父记录下标，完整路径按需拼出。
+//!
+//! 运行：
+//!   cargo test -p ai-disk-scanner --test memory_footprint -- --nocapture
+
+// 统计分配量需要实现 GlobalAlloc，这是 unsafe trait；实现只把调用转发给系统分配器
+#![allow(unsafe_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ai_disk_scanner::{RecordArenaBuilder, RecordMeta};
+
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: 直接转发给系统分配器，调用方保证 layout 合法
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: ptr 由本分配器以相同 layout 分配
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const DIRS: usize = 1_000;
+const FILES_PER_DIR: usize = 999;
+const ROOT: &str = r"C:\Users\someone\Documents\projects";
+
+/// 合成记录：1000 个目录、每个目录 999 个文件，共 100 万条
+fn synthetic_records() -> impl Iterator<Item = (String, RecordMeta)> {
+    (0..DIRS).flat_map(|d| {
+        let dir = format!(r"{}\module_{:04}\src", ROOT, d);
+        let dir_record = (
+            dir.clone(),
+            RecordMeta {
+                is_dir: true,
+                ..RecordMeta::default()
+            },
+        );
+        let files = (0..FILES_PER_DIR).map(move |f| {
+            (
+                format!(r"{}\source_file_{:06}.rs", dir, f),
+                RecordMeta {
+                    size: (d * FILES_PER_DIR + f) as u64,
+                    modified: Some(1_700_000_000),
+                    ..RecordMeta::default()
+                },
+            )
+        });
+        std::iter::once(dir_record).chain(files)
+    })
+}
+
+/// 测量 `build` 执行期间（含其返回值仍存活时）的峰值分配字节数
+fn peak_bytes<T>(build: impl FnOnce() -> T) -> (usize, T) {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let value = build();
+    (PEAK.load(Ordering::Relaxed) - base, value)
+}
+
+/// 原 MFT 扫描保存的记录形式，时间字段与 RecordMeta 相同
+#[allow(dead_code)]
+struct FullPathRecord {
+    full_path: String,
+    size: u64,
+    is_dir: bool,
+    modified: Option<u64>,
+    created: Option<NonZeroU64>,
+    accessed: Option<NonZeroU64>,
+}
+
+#[test]
+fn test_arena_uses_less_memory_than_full_paths() {
+    let (full_path_bytes, full_path_state) = peak_bytes(|| {
+        let mut records: Vec<FullPathRecord> = Vec::new();
+        let mut child_index: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut direct_sizes: HashMap<String, u64> = HashMap::new();
+        for (path, meta) in synthetic_records() {
+            let idx = records.len();
+            if let Some(i) = path.rfind('\\') {
+                child_index
+                    .entry(path[..i].to_string())
+                    .or_default()
+                    .push(idx);
+            }
+            *direct_sizes.entry(path.clone()).or_insert(0) += meta.size;
+            records.push(FullPathRecord {
+                full_path: path,
+                size: meta.size,
+                is_dir: meta.is_dir,
+                modified: meta.modified,
+                created: meta.created,
+                accessed: meta.accessed,
+            });
+        }
+        (records, child_index, direct_sizes)
+    });
+    assert_eq!(full_path_state.0.len(), DIRS * (FILES_PER_DIR + 1));
+    drop(full_path_state);
+
+    let (arena_bytes, arena) = peak_bytes(|| {
+        let mut builder = RecordArenaBuilder::new(ROOT, '\\');
+        for (path, meta) in synthetic_records() {
+            builder.insert(&path, meta).unwrap();
+        }
+        let arena = builder.finish();
+        let sizes = arena.recursive_sizes();
+        (arena, sizes)
+    });
+    let (arena, sizes) = arena;
+    // 根 + 中间的 module_xxxx 目录 + 100 万条记录
+    assert_eq!(arena.len(), 1 + DIRS + DIRS * (FILES_PER_DIR + 1));
+    let n = (DIRS * FILES_PER_DIR) as u64;
+    assert_eq!(sizes[0], n * (n - 1) / 2);
+    let largest = arena.largest_files(1)[0];
+    assert_eq!(
+        arena.path(largest),
+        format!(r"{}\module_0999\src\source_file_000998.rs", ROOT)
+    );
+
+    eprintln!(
+        "[memory_footprint] full paths: {} MiB, arena: {} MiB",
+        full_path_bytes >> 20,
+        arena_bytes >> 20
+    );
+    assert!(
+        arena_bytes * 10 <= full_path_bytes * 6,
+        "arena peak {} bytes is not 40% below full-path peak {} bytes",
+        arena_bytes,
+        full_path_bytes
+    );
+}
@@ -12,7 +12,7 @@
 //! 修复说明：曾因在消费者循环中对每块做 fixup 再在 from_raw 中二次 fixup，导致部分卷上
 //! "corrupt MFT record 0"。现改为仅在 from_raw 中做一次 fixup，消费者仅用 bitmap+is_valid 统计数量。
 
-#![cfg(windows)]
+#![cfg(all(windows, feature = "windows-native"))]
 
 use std::io::{Read, Seek, SeekFrom};
 
@@ -227,7 +227,7 @@ fn mft_scan_volume_mft_with_progress() {
         path.trim_end_matches(':').trim_start_matches(r"\\.\")
     );
     eprintln!(
-        "[mft_scan] 调用 scan_volume_mft({:?}, progress, true) 共 2 次",
+        "[mft_scan] 调用 scan_volume_mft({:?}, progress, true, false) 共 2 次",
         path_str
     );
 
@@ -237,7 +237,7 @@ fn mft_scan_volume_mft_with_progress() {
 
     for iter in 0..2 {
         eprintln!("[mft_scan] ---------- iter {} ----------", iter);
-        match scan_volume_mft(path_str.as_str(), Some(progress.clone()), true) {
+        match scan_volume_mft(path_str.as_str(), Some(progress.clone()), true, false) {
             Ok(result) => eprintln!(
                 "[mft_scan] iter {} 成功: file_count={}",
                 iter, result.file_count
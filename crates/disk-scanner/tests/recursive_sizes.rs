@@ -0,0 +1,190 @@
+//! 递归大小的差分测试：RecordArena::recursive_sizes（按记录下标倒序累加）与原 MFT 扫描中
+//! 按路径深度排序、逐路径查表的 compute_recursive_sizes 在随机合成树上结果一致，并输出两者耗时。
+//!
+//! 运行（耗时对比需 `--nocapture`，建议 release）：
+//!   cargo test -p ai-disk-scanner --release --test recursive_sizes -- --nocapture
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ai_disk_scanner::{RecordArenaBuilder, RecordMeta};
+
+const VOLUME_ROOT_TRIM: &str = "C:";
+const VOLUME_ROOT_KEY: &str = r"C:\";
+
+/// xorshift64*，避免为测试引入随机数依赖
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// 随机树的全部记录（每个目录都有自己的记录，与真实 MFT 一致），顺序打乱；
+/// 根记录的盘符随机大小写
+fn random_records(rng: &mut Rng, nodes: usize) -> Vec<(String, RecordMeta)> {
+    let root = if rng.below(2) == 0 { r"C:\" } else { r"c:\" };
+    let mut records = vec![(
+        root.to_string(),
+        RecordMeta {
+            size: rng.below(100) as u64,
+            is_dir: true,
+            ..RecordMeta::default()
+        },
+    )];
+    let mut dirs = vec![VOLUME_ROOT_TRIM.to_string()];
+    for i in 0..nodes {
+        let parent = dirs[rng.below(dirs.len())].clone();
+        let is_dir = rng.below(4) == 0;
+        // 不同目录下会出现同名项
+        let path = format!(r"{}\n{}_{}", parent, rng.below(8), i);
+        let size = if is_dir {
+            rng.below(4) as u64
+        } else {
+            rng.next() % 1_000_000
+        };
+        if is_dir {
+            dirs.push(path.clone());
+        }
+        records.push((
+            path,
+            RecordMeta {
+                size,
+                is_dir,
+                ..RecordMeta::default()
+            },
+        ));
+    }
+    for i in (1..records.len()).rev() {
+        records.swap(i, rng.below(i + 1));
+    }
+    records
+}
+
+/// 原 scan_volume_mft 的 child_index / direct_sizes 构建与 compute_recursive_sizes（逐字保留）
+fn reference_recursive_sizes(records: &[(String, RecordMeta)]) -> HashMap<String, u64> {
+    let mut child_index: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut direct_sizes: HashMap<String, u64> = HashMap::new();
+    for (idx, (full_path, meta)) in records.iter().enumerate() {
+        let path_trim = full_path.trim_end_matches('\\');
+        if !path_trim.eq_ignore_ascii_case(VOLUME_ROOT_TRIM) {
+            if let Some(i) = full_path.rfind('\\') {
+                let parent = full_path[..i].to_string();
+                child_index.entry(parent).or_default().push(idx);
+            }
+        }
+        let s = meta.size;
+        direct_sizes
+            .entry(path_trim.to_string())
+            .and_modify(|v| *v = v.saturating_add(s))
+            .or_insert(s);
+    }
+
+    let mut paths: Vec<String> = records
+        .iter()
+        .map(|r| r.0.trim_end_matches('\\').to_string())
+        .collect();
+    if !paths
+        .iter()
+        .any(|p| p.eq_ignore_ascii_case(VOLUME_ROOT_TRIM))
+    {
+        paths.push(VOLUME_ROOT_TRIM.to_string());
+    }
+    paths.sort();
+    paths.dedup();
+    paths.sort_by_cached_key(|p| std::cmp::Reverse(p.matches('\\').count()));
+    let mut recursive_sizes: HashMap<String, u64> = HashMap::new();
+    for path in paths {
+        let direct = direct_sizes.get(&path).copied().unwrap_or(0);
+        let child_sum: u64 = {
+            let key = if path.eq_ignore_ascii_case(VOLUME_ROOT_TRIM) {
+                VOLUME_ROOT_KEY
+            } else {
+                &path
+            };
+            child_index
+                .get(key)
+                .map(|indices| {
+                    indices
+                        .iter()
+                        .map(|&i| {
+                            let c = records[i].0.trim_end_matches('\\').to_string();
+                            recursive_sizes.get(&c).copied().unwrap_or(0)
+                        })
+                        .sum()
+                })
+                .unwrap_or(0)
+        };
+        recursive_sizes.insert(path, direct.saturating_add(child_sum));
+    }
+    recursive_sizes
+}
+
+#[test]
+fn test_recursive_sizes_match_reference() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for round in 0..50 {
+        let nodes = 1 + rng.below(2_000);
+        let records = random_records(&mut rng, nodes);
+        let reference = reference_recursive_sizes(&records);
+
+        let mut builder = RecordArenaBuilder::new(VOLUME_ROOT_KEY, '\\');
+        for (path, meta) in &records {
+            builder.insert(path, *meta).unwrap();
+        }
+        let arena = builder.finish();
+        let sizes = arena.recursive_sizes();
+        assert_eq!(arena.len(), records.len(), "round {}", round);
+
+        for idx in 1..arena.len() as u32 {
+            let path = arena.path(idx);
+            assert_eq!(
+                Some(sizes[idx as usize]),
+                reference.get(&path).copied(),
+                "round {}: {}",
+                round,
+                path
+            );
+        }
+        // 原实现按 `C:\` 查找根的子项，而子项是以 `C:` 为键记录的，因此根只含自身大小
+        // （该值未被使用：根的大小由建树时累加子节点得到）；新实现的根为全部记录之和
+        let total: u64 = records.iter().map(|(_, m)| m.size).sum();
+        assert_eq!(sizes[0], total, "round {}", round);
+    }
+}
+
+#[test]
+fn bench_recursive_sizes() {
+    let mut rng = Rng(42);
+    let records = random_records(&mut rng, 300_000);
+
+    let start = Instant::now();
+    let reference = reference_recursive_sizes(&records);
+    let reference_ms = start.elapsed().as_millis();
+
+    let mut builder = RecordArenaBuilder::new(VOLUME_ROOT_KEY, '\\');
+    for (path, meta) in &records {
+        builder.insert(path, *meta).unwrap();
+    }
+    let arena = builder.finish();
+    let start = Instant::now();
+    let sizes = arena.recursive_sizes();
+    let arena_ms = start.elapsed().as_millis();
+
+    assert_eq!(reference.len(), records.len());
+    assert_eq!(sizes.len(), records.len());
+    eprintln!(
+        "[recursive_sizes] {} records: path sort + lookups {} ms, index accumulation {} ms",
+        records.len(),
+        reference_ms,
+        arena_ms
+    );
+}
@@ -1,4 +1,4 @@
-#![cfg(windows)]
+#![cfg(all(windows, feature = "windows-native"))]
 //! 扫描耗时测试：对指定卷（**默认 F 盘** `F:\`）分别使用 MFT 与普通遍历扫描，统计并输出耗时。
 //!
 //! - **MFT 扫描**：使用已实现的 `scan_path_with_progress(..., use_mft: true)`（卷根时走 MFT，需管理员权限）。
@@ -39,7 +39,7 @@ use std::time::Instant;
 
 #[cfg(windows)]
 use ai_disk_scanner::scan_volume_mft_top_files;
-use ai_disk_scanner::{scan_path_with_progress, FileNode, ScanResult};
+use ai_disk_scanner::{scan_path_with_progress, FileNode, ScanResult, SizeSource, WalkOptions};
 
 /// 默认扫描盘符：F 盘
 const DEFAULT_SCAN_PATH: &str = "F:\\";
@@ -82,7 +82,8 @@ fn scan_timing_mft_vs_normal() {
 
     // 1) 使用 MFT 扫描
     let t0 = Instant::now();
-    let result_mft = scan_path_with_progress(&path, None, true, true);
+    let result_mft =
+        scan_path_with_progress(&path, None, true, true, None, None, &WalkOptions::default());
     let elapsed_mft = t0.elapsed();
 
     match &result_mft {
@@ -110,7 +111,15 @@ fn scan_timing_mft_vs_normal() {
     let run_normal = std::env::var("SCAN_NORMAL").map_or(true, |v| v != "0" && v != "false");
     let (result_normal, elapsed_normal) = if run_normal {
         let t1 = Instant::now();
-        let res = scan_path_with_progress(&path, None, true, false);
+        let res = scan_path_with_progress(
+            &path,
+            None,
+            true,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        );
         let elapsed = t1.elapsed();
         match &res {
             Ok((r, _)) => {
@@ -137,13 +146,13 @@ fn scan_timing_mft_vs_normal() {
         (
             Ok((
                 ScanResult {
+                    scan_id: None,
                     root: FileNode {
                         path: String::new(),
                         name: String::new(),
                         size: 0,
                         is_dir: true,
-                        modified: None,
-                        children: vec![],
+                        ..Default::default()
                     },
                     scan_time_ms: 0,
                     file_count: 0,
@@ -152,6 +161,13 @@ fn scan_timing_mft_vs_normal() {
                     volume_total_bytes: None,
                     volume_free_bytes: None,
                     top_files: None,
+                    owner_usage: None,
+                    throttled: false,
+                    ignored_bytes: None,
+                    trash_bytes: None,
+                    paused_ms: 0,
+                    physical_usage: None,
+                    recent_activity: Vec::new(),
                 },
                 false,
             )),
@@ -202,7 +218,8 @@ fn scan_timing_c_and_f() {
         }
         eprintln!("[scan_timing_c_and_f] ---------- {} ----------", path);
         let t0 = std::time::Instant::now();
-        let res = scan_path_with_progress(path, None, true, true);
+        let res =
+            scan_path_with_progress(path, None, true, true, None, None, &WalkOptions::default());
         let elapsed_ms = t0.elapsed().as_millis();
         match &res {
             Ok((r, used_mft)) => {
@@ -271,7 +288,8 @@ fn scan_timing_mft_vs_normal_c_and_f() {
         eprintln!("[MFT_vs_normal] ---------- {} ----------", path);
 
         let t0 = std::time::Instant::now();
-        let res_mft = scan_path_with_progress(path, None, true, true);
+        let res_mft =
+            scan_path_with_progress(path, None, true, true, None, None, &WalkOptions::default());
         let mft_ms = t0.elapsed().as_millis() as u64;
         match &res_mft {
             Ok((r, used_mft)) => {
@@ -291,7 +309,8 @@ fn scan_timing_mft_vs_normal_c_and_f() {
         }
 
         let t1 = std::time::Instant::now();
-        let res_normal = scan_path_with_progress(path, None, true, false);
+        let res_normal =
+            scan_path_with_progress(path, None, true, false, None, None, &WalkOptions::default());
         let normal_ms = t1.elapsed().as_millis() as u64;
         match &res_normal {
             Ok((r, _)) => {
@@ -417,7 +436,8 @@ fn scan_timing_top500_c_and_f() {
         }
 
         let t1 = Instant::now();
-        let res_normal = scan_path_with_progress(path, None, true, false);
+        let res_normal =
+            scan_path_with_progress(path, None, true, false, None, None, &WalkOptions::default());
         let normal_ms = t1.elapsed().as_millis() as u64;
         match &res_normal {
             Ok((r, _)) => {
@@ -1,8 +1,41 @@
 use serde::{Deserialize, Serialize};
 
 /// 执行动作
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
-    Delete { path: String },
-    Move { from: String, to: String },
+    Delete {
+        path: String,
+    },
+    Move {
+        from: String,
+        to: String,
+    },
+    /// 上传到云存储并校验通过后，将本地文件移入回收站
+    Offload {
+        path: String,
+        provider: String,
+        account_id: String,
+        target_path: String,
+    },
+    /// 清空用户废纸篓 `path`（XDG 布局下连同 info/ 中对应的 .trashinfo 一并删除）
+    EmptyTrash {
+        path: String,
+    },
+    /// 压缩 WSL 发行版或 Docker Desktop 的虚拟磁盘 `path`（.vhdx），不删除其中的文件
+    CompactVhd {
+        path: String,
+    },
+}
+
+impl Action {
+    /// 动作作用的本地路径：移动为源路径，其余为 `path`
+    pub fn source_path(&self) -> &str {
+        match self {
+            Action::Delete { path }
+            | Action::Offload { path, .. }
+            | Action::EmptyTrash { path }
+            | Action::CompactVhd { path } => path,
+            Action::Move { from, .. } => from,
+        }
+    }
 }
@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 use crate::action::Action;
+use crate::TargetState;
 
 /// 清理计划
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleanupPlan {
     pub actions: Vec<Action>,
     pub estimated_space: u64,
+    /// 校验时按所依据的扫描记录的各动作目标状态，与 `actions` 一一对应；未经校验的计划为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<Option<TargetState>>,
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::FileCategory;
+
+/// 系统中已知的可回收位置（临时目录、浏览器缓存、包管理器缓存等）及其当前占用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanupTarget {
+    pub path: String,
+    /// 展示用名称，例如「Chrome 缓存 (Default)」
+    pub name: String,
+    pub category: FileCategory,
+    /// 占用字节数；不包含同时被列为其他目标的子目录，各目标相加不会重复计算
+    pub bytes: u64,
+    pub exists: bool,
+    /// 清理需要管理员权限（系统目录，或当前用户无法读取）
+    pub requires_admin: bool,
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// 云端文件浏览中的一项，各服务商的列表结果统一成此结构
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudFileEntry {
+    pub id: String,
+    pub name: String,
+    /// 文件夹为 0
+    pub size: u64,
+    /// Unix 时间戳（秒），最近修改时间
+    pub modified: Option<u64>,
+    pub is_dir: bool,
+    pub provider: String,
+}
+
+/// 本地扫描中已有云端副本的文件：文件名（不区分大小写）与大小都唯一对应一个云端文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackedUpMatch {
+    pub local_path: String,
+    pub size: u64,
+    pub remote: CloudFileEntry,
+    /// 已比对云端校验和与本地文件内容一致
+    #[serde(default)]
+    pub verified: bool,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// 目录的快速统计（不做完整扫描），用于在目录选择器中悬停时即时展示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickDirStats {
+    pub path: String,
+    /// 直接子项数量（文件与目录）
+    pub child_count: u64,
+    /// 直接子文件数量
+    pub file_count: u64,
+    /// 直接子目录数量
+    pub dir_count: u64,
+    /// 直接子文件的大小之和
+    pub direct_size: u64,
+    /// 递归大小；`partial` 为 true 时只包含已统计的部分，是实际大小的下限
+    pub recursive_size: u64,
+    /// 本次统计访问过的条目数
+    pub scanned_entries: u64,
+    /// 因时间或条目数上限提前停止
+    pub partial: bool,
+    pub elapsed_ms: u64,
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{FreeSpaceProjection, RiskLevel};
+
+/// 磁盘分析归类的文件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FileCategory {
+    /// 浏览器缓存（Chrome/Edge/Firefox 等的 Cache、Code Cache 目录）
+    BrowserCache,
+    /// 其他应用缓存目录
+    AppCache,
+    /// 包管理器缓存与依赖目录（node_modules、.npm 等）
+    PackageCache,
+    /// 临时文件目录
+    Temp,
+    /// 下载目录中长时间未修改的文件
+    StaleDownloads,
+    /// 下载目录中的安装包
+    Installer,
+    /// 用户废纸篓中的内容
+    Trash,
+    /// 可从原来源重新下载的内容（Steam 游戏、模型权重、Docker/WSL 虚拟磁盘等）
+    Redownloadable,
+    /// 视频文件（按扩展名识别，仅用于 Treemap 按类型着色，下同）
+    Video,
+    /// 音频文件
+    Audio,
+    /// 图片
+    Image,
+    /// 文档（Office、PDF、纯文本等）
+    Document,
+    /// 压缩包与磁盘镜像
+    Archive,
+    /// 未归入以上类别的内容
+    Other,
+}
+
+/// 单条分析结论，例如「浏览器缓存共 37.0 GB」
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub category: FileCategory,
+    pub headline: String,
+    pub bytes: u64,
+    pub risk: RiskLevel,
+    /// 支撑该结论的路径（按大小降序，最多保留若干条）
+    pub paths: Vec<String>,
+}
+
+/// 某一类别的字节总数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryTotal {
+    pub category: FileCategory,
+    pub bytes: u64,
+    /// 归入该类别的节点数（目录整体归类时计为 1）
+    pub entry_count: u64,
+}
+
+/// 一处可重新下载的内容及其恢复方法
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedownloadableItem {
+    pub path: String,
+    pub bytes: u64,
+    /// 所属应用，例如「Steam」
+    pub app: String,
+    /// 删除后如何恢复
+    pub restore_hint: String,
+}
+
+/// 可重新下载内容的汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedownloadableSummary {
+    pub total_bytes: u64,
+    /// 按字节数降序排列
+    pub items: Vec<RedownloadableItem>,
+}
+
+/// 虚拟磁盘所属的程序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualDiskKind {
+    /// 从应用商店安装的 WSL 发行版（`%LOCALAPPDATA%\Packages\<包名>\LocalState\ext4.vhdx`）
+    WslDistro,
+    /// Docker Desktop 的 WSL 2 数据盘
+    DockerDesktop,
+}
+
+/// 只增不减的虚拟磁盘文件：在其中删除文件不会缩小它，需要压缩才能释放空间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualDisk {
+    pub path: String,
+    pub bytes: u64,
+    pub kind: VirtualDiskKind,
+    /// 展示给用户的说明
+    pub note: String,
+}
+
+/// 对一次扫描结果的确定性分析
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskAnalysis {
+    pub root_path: String,
+    pub total_size: u64,
+    /// 按字节数降序排列的结论
+    pub findings: Vec<Finding>,
+    pub category_totals: Vec<CategoryTotal>,
+    /// 仅清理低风险类别可回收的空间；按实际占用计（NTFS 压缩、重复数据删除后的大小）
+    pub reclaimable_low_risk: u64,
+    /// 清理低风险与中风险类别可回收的空间（包含 reclaimable_low_risk）
+    pub reclaimable_medium_risk: u64,
+    /// 可重新下载的内容；也计入 reclaimable_medium_risk
+    #[serde(default)]
+    pub redownloadable: RedownloadableSummary,
+    /// WSL/Docker 虚拟磁盘，按字节数降序排列
+    #[serde(default)]
+    pub virtual_disks: Vec<VirtualDisk>,
+    /// 执行所选动作后的剩余空间预测；分析时没有指定动作则为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<FreeSpaceProjection>,
+    /// 配置了 LLM 时生成的自然语言总结
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub narrative: Option<String>,
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// 存储介质类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    Ssd,
+    Hdd,
+    Unknown,
+}
+
+/// 单个 SMART 属性
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmartAttribute {
+    pub id: u8,
+    pub name: String,
+    /// 归一化的当前值（越大越好）
+    pub current: u8,
+    pub worst: u8,
+    /// 当前值低于等于阈值时厂商认为即将失效；0 表示没有阈值
+    pub threshold: u8,
+    pub raw: u64,
+}
+
+/// 磁盘健康摘要。各字段尽力获取，取不到时为 None 并在 `warnings` 中说明原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskHealth {
+    /// 查询时传入的卷（如 `C:`、`/home`）
+    pub volume: String,
+    /// 卷所在的物理设备（如 `\\.\C:`、`/dev/nvme0n1`）
+    pub device: Option<String>,
+    pub model: Option<String>,
+    /// 只保留末 4 位的序列号
+    pub serial_redacted: Option<String>,
+    pub media_type: MediaType,
+    pub temperature_celsius: Option<u32>,
+    pub power_on_hours: Option<u64>,
+    pub reallocated_sectors: Option<u64>,
+    /// 设备自检或 SMART 阈值判断即将失效
+    pub predicted_failure: bool,
+    pub raw_attributes: Vec<SmartAttribute>,
+    /// 获取失败的部分，例如「smartctl 未安装」
+    pub warnings: Vec<String>,
+}
+
+impl DiskHealth {
+    pub fn empty(volume: impl Into<String>) -> Self {
+        Self {
+            volume: volume.into(),
+            device: None,
+            model: None,
+            serial_redacted: None,
+            media_type: MediaType::Unknown,
+            temperature_celsius: None,
+            power_on_hours: None,
+            reallocated_sectors: None,
+            predicted_failure: false,
+            raw_attributes: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
+/// 文件属性（隐藏/系统/只读/稀疏/压缩）。Windows 上取自文件属性位，其他平台仅以「.」开头视为隐藏
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAttributes {
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub system: bool,
+    #[serde(default)]
+    pub readonly: bool,
+    /// 稀疏文件：`size` 为逻辑大小，实际占用的簇可能少得多
+    #[serde(default)]
+    pub sparse: bool,
+    /// NTFS 压缩：`size` 为逻辑大小，实际占用见 `FileNode::physical_size`
+    #[serde(default)]
+    pub compressed: bool,
+}
+
+impl FileAttributes {
+    /// 由 Windows 的 FILE_ATTRIBUTE_* 位（GetFileAttributes 或 MFT $STANDARD_INFORMATION）转换
+    pub fn from_windows_bits(bits: u32) -> Self {
+        Self {
+            hidden: bits & FILE_ATTRIBUTE_HIDDEN != 0,
+            system: bits & FILE_ATTRIBUTE_SYSTEM != 0,
+            readonly: bits & FILE_ATTRIBUTE_READONLY != 0,
+            sparse: bits & FILE_ATTRIBUTE_SPARSE_FILE != 0,
+            compressed: bits & FILE_ATTRIBUTE_COMPRESSED != 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.hidden || self.system || self.readonly || self.sparse || self.compressed)
+    }
+
+    /// 没有任何标志时为 None，避免为绝大多数普通文件序列化该字段
+    pub fn non_empty(self) -> Option<Self> {
+        (!self.is_empty()).then_some(self)
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// 文本预览的来源编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// 预览内容：文本、图片缩略图，或只有元数据
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreviewContent {
+    /// 文件开头的文本，无法解码的字节以 U+FFFD 替换
+    Text {
+        text: String,
+        encoding: TextEncoding,
+        /// 只读取了文件的前一部分
+        truncated: bool,
+    },
+    /// 长边不超过 256 像素的 PNG 缩略图（base64）
+    Image {
+        width: u32,
+        height: u32,
+        thumbnail_base64: String,
+    },
+    /// 二进制或无法识别的文件，只返回元数据
+    Metadata,
+}
+
+/// 删除前查看文件内容用的预览
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub path: String,
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub modified: Option<u64>,
+    /// 按内容或扩展名猜测的 MIME 类型
+    pub mime: Option<String>,
+    pub content: PreviewContent,
+}
@@ -1,8 +1,33 @@
 use serde::{Deserialize, Serialize};
 
-/// 文件树节点
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::{FileAttributes, FileCategory, KnownFolder};
+
+/// 节点大小的来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeSource {
+    /// 遍历得到的实际大小
+    #[default]
+    Exact,
+    /// 无法读取时由其他来源（如同一卷的缓存 MFT 扫描）估算，或子树中含有估算值
+    Estimated,
+    /// 无法读取且没有可用的估算，大小记为 0
+    Unknown,
+}
+
+impl SizeSource {
+    pub fn is_exact(&self) -> bool {
+        *self == SizeSource::Exact
+    }
+}
+
+/// 文件树节点。构造时只填写需要的字段，其余用 `..Default::default()` 补齐
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileNode {
+    /// 由规范化路径计算的稳定 id，MFT 与目录遍历两种扫描方式下相同，供前端作为节点 key；
+    /// 扫描器在返回结果前统一填充
+    #[serde(default)]
+    pub id: u64,
     pub path: String,
     pub name: String,
     pub size: u64,
@@ -10,6 +35,97 @@ pub struct FileNode {
     /// Unix 时间戳（秒），最近修改时间
     #[serde(default)]
     pub modified: Option<u64>,
+    /// Unix 时间戳（秒），创建时间；文件系统不记录或为纪元零点时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Unix 时间戳（秒），最近访问时间；文件系统不记录或为纪元零点时为 None。
+    /// 以 noatime 挂载或关闭了访问时间更新（NTFS 默认）时可能早于实际的最近访问
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<u64>,
+    /// 隐藏/系统/只读属性；没有任何标志时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<FileAttributes>,
+    /// 其他卷或网络共享的挂载点：只统计同一文件系统时不展开，大小为 0
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_mount_point: bool,
+    /// 扫描时按位置识别出的类别（目前只标记废纸篓与回收站 `FileCategory::Trash`，其子项不展开）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<FileCategory>,
+    /// 大小的来源；无权限读取的目录为 Unknown，补上估算值后为 Estimated
+    #[serde(default, skip_serializing_if = "SizeSource::is_exact")]
+    pub size_source: SizeSource,
+    /// 命名数据流（NTFS 备用数据流，如 Zone.Identifier）的大小合计，不计入 `size`；
+    /// 目录为子树合计。只有 MFT 扫描统计，没有命名数据流时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streams_size: Option<u64>,
+    /// 实际占用的磁盘空间：NTFS 压缩、重复数据删除或稀疏文件的占用小于 `size` 时为 Some，
+    /// 目录为子树合计；与 `size` 相同时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical_size: Option<u64>,
+    /// 供 Treemap 按类型着色：文件为按扩展名识别的类型，目录为子树中占字节数最多的类型。
+    /// 仅在开启 `treemap_rollups` 时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dominant_category: Option<FileCategory>,
+    /// 供 Treemap 按时间着色：目录子树中文件最近的修改时间（Unix 秒）。仅在开启 `treemap_rollups` 时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newest_modified: Option<u64>,
+    /// 目录子树中文件最早的修改时间（Unix 秒）。仅在开启 `treemap_rollups` 时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oldest_modified: Option<u64>,
+    /// 目录子树中最近的修改时间（Unix 秒）：扫描到的所有后代（含只计大小的目录中的内容）自身修改时间的最大值。
+    /// Windows 上深层文件变化不会更新祖先目录的修改时间，目录自身的 `modified` 无法反映内容的新旧；
+    /// 文件与空目录为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_child_modified: Option<u64>,
+    /// 子节点没有列出目录的全部子项：子项超过每个目录的上限或被忽略规则过滤、只计大小或超过深度上限的目录、
+    /// 折叠为整体的废纸篓等。此时 `children` 的数量不是目录实际的子项数
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial_children: bool,
+    /// 子树中有内容没有计入 `size`：某一级目录的子项因上限被截断或被忽略规则过滤、
+    /// 超过深度上限的目录没有统计大小，或剔除了应用自身的占用。此时 `size` 小于磁盘上的实际内容
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial_size: bool,
+    /// 节点是系统解析出的某个已知目录（文档、图片、下载等）时为 Some，目录被移动后同样能识别
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_folder: Option<KnownFolder>,
     #[serde(default)]
     pub children: Vec<FileNode>,
 }
+
+impl FileNode {
+    /// 只含路径、大小与类型的节点，名称取路径的最后一段（`/` 与 `\\` 均视为分隔符），其余字段为默认值
+    pub fn new(path: impl Into<String>, size: u64, is_dir: bool) -> Self {
+        let path = path.into();
+        let name = path
+            .rsplit(['/', '\\'])
+            .find(|c| !c.is_empty())
+            .unwrap_or(&path)
+            .to_string();
+        Self {
+            path,
+            name,
+            size,
+            is_dir,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_modified(mut self, modified: Option<u64>) -> Self {
+        self.modified = modified;
+        self
+    }
+
+    pub fn with_children(mut self, children: Vec<FileNode>) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// 最近使用时间：访问时间与修改时间中较晚的一个。写入也会更新访问时间，
+    /// 但关闭了访问时间更新的文件系统上访问时间会停在过去，取较晚者避免把刚写入的文件当成久未使用。
+    /// 目录还计入其下最新的修改时间（[`Self::latest_child_modified`]）
+    pub fn last_used(&self) -> Option<u64> {
+        self.accessed
+            .max(self.modified)
+            .max(self.latest_child_modified)
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// 执行计划后扫描所在卷的剩余空间预测，如「执行后剩余 112 GB（当前 41 GB）」。
+/// 移入同一卷回收站的删除要等清空回收站后才释放空间，与立即释放的部分分开列出
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreeSpaceProjection {
+    /// 扫描时卷的剩余空间；扫描没有记录时为 None
+    pub current_free_bytes: Option<u64>,
+    /// 执行后立即释放的字节数：永久删除、清空废纸篓、转存与跨卷移动（校验通过后删除源文件）
+    pub immediate_bytes: u64,
+    /// 移入回收站的字节数，清空回收站后才释放
+    pub deferred_bytes: u64,
+    /// 执行后的剩余空间（当前剩余空间加立即释放的部分）；当前剩余空间未知时为 None
+    pub projected_free_bytes: Option<u64>,
+    /// 执行并清空回收站后的剩余空间；当前剩余空间未知时为 None
+    pub projected_free_after_trash_bytes: Option<u64>,
+}
+
+impl FreeSpaceProjection {
+    pub fn new(current_free_bytes: Option<u64>, immediate_bytes: u64, deferred_bytes: u64) -> Self {
+        let projected_free_bytes =
+            current_free_bytes.map(|free| free.saturating_add(immediate_bytes));
+        Self {
+            current_free_bytes,
+            immediate_bytes,
+            deferred_bytes,
+            projected_free_bytes,
+            projected_free_after_trash_bytes: projected_free_bytes
+                .map(|free| free.saturating_add(deferred_bytes)),
+        }
+    }
+}
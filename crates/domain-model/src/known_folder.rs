@@ -0,0 +1,33 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// 用户的已知目录（Windows Known Folders、XDG 用户目录、macOS 标准目录）。
+/// 这些目录可以被用户移动到其他位置，按名称或默认路径判断会认错，扫描时按系统解析出的实际位置标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnownFolder {
+    Desktop,
+    Documents,
+    Downloads,
+    Pictures,
+    Music,
+    Videos,
+    /// OneDrive 同步目录（仅 Windows）
+    OneDrive,
+}
+
+impl KnownFolder {
+    pub const ALL: [KnownFolder; 7] = [
+        KnownFolder::Desktop,
+        KnownFolder::Documents,
+        KnownFolder::Downloads,
+        KnownFolder::Pictures,
+        KnownFolder::Music,
+        KnownFolder::Videos,
+        KnownFolder::OneDrive,
+    ];
+}
+
+/// 已知目录 → 实际位置（DisplayPath 形式）；系统中不存在的目录不在其中
+pub type KnownFolderMap = BTreeMap<KnownFolder, String>;
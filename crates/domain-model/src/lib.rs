@@ -1,13 +1,63 @@
 pub mod action;
 pub mod cleanup_plan;
+pub mod cleanup_target;
+pub mod cloud_file;
+pub mod dir_stats;
+pub mod disk_analysis;
+pub mod disk_health;
+pub mod file_attributes;
+pub mod file_preview;
 pub mod file_tree;
+pub mod free_space_projection;
+pub mod known_folder;
+pub mod low_space;
+pub mod mft_availability;
+pub mod owner_usage;
+pub mod plan_file;
+pub mod plan_selection;
+pub mod plan_summary;
+pub mod planned_action;
+pub mod process_io;
+pub mod recent_activity;
 pub mod risk;
+pub mod scan_location;
+pub mod scan_preflight;
 pub mod scan_result;
+pub mod scan_staleness;
+pub mod scan_stream;
+pub mod scheduled_execution;
+pub mod session;
+pub mod target_state;
 pub mod top_file_entry;
 
 pub use action::*;
 pub use cleanup_plan::*;
+pub use cleanup_target::*;
+pub use cloud_file::*;
+pub use dir_stats::*;
+pub use disk_analysis::*;
+pub use disk_health::*;
+pub use file_attributes::*;
+pub use file_preview::*;
 pub use file_tree::*;
+pub use free_space_projection::*;
+pub use known_folder::*;
+pub use low_space::*;
+pub use mft_availability::*;
+pub use owner_usage::*;
+pub use plan_file::*;
+pub use plan_selection::*;
+pub use plan_summary::*;
+pub use planned_action::*;
+pub use process_io::*;
+pub use recent_activity::*;
 pub use risk::*;
+pub use scan_location::*;
+pub use scan_preflight::*;
 pub use scan_result::*;
+pub use scan_staleness::*;
+pub use scan_stream::*;
+pub use scheduled_execution::*;
+pub use session::*;
+pub use target_state::*;
 pub use top_file_entry::*;
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// 卷剩余空间跌破阈值的提醒，作为 `low-disk-space` 事件的负载
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LowSpaceAlert {
+    /// 卷根路径，如 `C:\` 或 `/`
+    pub volume: String,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    /// 剩余空间占总容量的百分比
+    pub free_percent: f64,
+    /// 开启 `low_space.auto_scan` 时，后台扫描完成后填入的扫描结果 id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_id: Option<String>,
+}
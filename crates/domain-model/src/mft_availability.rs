@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// 某路径能否使用 MFT 加速扫描；不能时 `reason` 说明原因（未以管理员运行、非 NTFS 等）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MftAvailability {
+    pub available: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// 某个所有者（用户/组账户）名下的占用字节数，用于多用户机器上的归属统计
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerUsage {
+    pub owner: String,
+    pub bytes: u64,
+}
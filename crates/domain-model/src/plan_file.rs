@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CleanupPlan, PlannedAction};
+
+/// 计划文件的当前 schema 版本。v1 为直接序列化的 [`CleanupPlan`]（只有动作本身）；
+/// v2 起每个动作带有预计释放量、风险与理由
+pub const PLAN_SCHEMA_VERSION: u32 = 2;
+
+/// 保存到磁盘、可分享并在之后重新执行的清理计划
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanFile {
+    pub schema_version: u32,
+    /// 计划名称，同时是 `plans/` 下的文件名（不含扩展名）
+    #[serde(default)]
+    pub name: String,
+    /// 保存时间（Unix 秒）；从 v1 迁移的计划为 0
+    #[serde(default)]
+    pub saved_at: u64,
+    pub estimated_space: u64,
+    pub actions: Vec<PlannedAction>,
+}
+
+impl PlanFile {
+    /// 供执行器使用的计划（丢弃每个动作的元数据，只保留目标的扫描时状态）
+    pub fn to_cleanup_plan(&self) -> CleanupPlan {
+        CleanupPlan {
+            actions: self.actions.iter().map(|a| a.action.clone()).collect(),
+            estimated_space: self.estimated_space,
+            targets: self.actions.iter().map(|a| a.scanned).collect(),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ScheduleTarget;
+
+/// 勾选的动作：计划中的下标，或动作的源路径（删除、转存的路径，移动的来源）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ActionRef {
+    Index(usize),
+    Path(String),
+}
+
+/// 只执行计划中勾选的部分动作时的选择记录，随执行结果保存，审计时可对照原计划
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSelection {
+    /// 原计划：已保存的计划或会话中的计划
+    pub source: ScheduleTarget,
+    /// 原计划中的动作数
+    pub total_actions: usize,
+    /// 勾选的动作在原计划中的下标（升序、去重）
+    pub selected: Vec<usize>,
+    /// 被同时勾选的祖先路径动作覆盖、不再单独执行的下标
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub covered: Vec<usize>,
+    /// 实际执行的动作预计释放的字节数之和（被覆盖的动作已含在祖先中，不重复计算）
+    pub bytes: u64,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CategoryTotal, RiskLevel};
+
+/// 某一风险等级下的动作数与预计释放量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskTotal {
+    pub risk: RiskLevel,
+    pub bytes: u64,
+    pub action_count: usize,
+}
+
+/// 按目录汇总的一组动作。`path` 是组内所有动作路径的最近公共祖先，
+/// 只有一个子分组且自身没有动作的中间目录会被合并，不单独成组
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanGroup {
+    pub path: String,
+    /// 组内（含各级子分组）动作的预计释放量之和
+    pub bytes: u64,
+    pub action_count: usize,
+    /// 路径恰为 `path` 的动作在计划中的下标
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<usize>,
+    /// 按字节数降序排列
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PlanGroup>,
+}
+
+/// 供界面预览的计划汇总；所有下标都指向生成汇总时传入的动作列表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanSummary {
+    pub total_bytes: u64,
+    pub action_count: usize,
+    /// 按 Low、Medium、High 的顺序，只包含有动作的等级
+    pub by_risk: Vec<RiskTotal>,
+    /// 按字节数降序；`entry_count` 为动作数。路径不在扫描树中的动作不计入
+    pub by_category: Vec<CategoryTotal>,
+    /// 扫描树中能找到的动作按目录汇总；没有这样的动作时为 None
+    pub tree: Option<PlanGroup>,
+    /// 路径不在扫描树中的动作（如计划生成后才出现的文件），`path` 为空
+    pub unknown: Option<PlanGroup>,
+    /// 预计释放量最大的若干个动作的下标，按字节数降序
+    pub largest: Vec<usize>,
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, RiskLevel, TargetState};
+
+/// 规划器提出的单个动作及其理由，供用户逐条确认
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedAction {
+    pub action: Action,
+    /// 执行后预计释放的字节数
+    pub bytes: u64,
+    pub risk: RiskLevel,
+    /// 展示给用户的理由，例如「Node.js 安装包的旧版本」
+    pub reason: String,
+    /// 扫描时目标的状态，执行前据此核对目标是否已改变；没有记录时不核对
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanned: Option<TargetState>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// 单个进程在两次采样之间的读写量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessIoDelta {
+    pub pid: u32,
+    pub name: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// 按进程名累计的读写量（自开始采样起）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessIoTotal {
+    pub name: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// 一次磁盘读写采样，作为 `disk-io-sample` 事件发送给前端
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskIoSample {
+    /// 距上次采样的实际间隔（毫秒）
+    pub interval_ms: u64,
+    /// 本次间隔内写入最多的进程（按 write_bytes 降序）
+    pub top: Vec<ProcessIoDelta>,
+    /// 按进程名累计的读写量（按 write_bytes 降序）
+    pub totals: Vec<ProcessIoTotal>,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// 某个顶层目录下最近修改的文件汇总，用于磁盘活动热度视图。
+/// `path` 为扫描根下的顶层目录；直接位于扫描根下的文件计入扫描根自身
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentActivityEntry {
+    pub path: String,
+    /// 最近 24 小时内修改的文件的总大小（字节）
+    pub bytes_24h: u64,
+    /// 最近 7 天内修改的文件的总大小（字节），包含 `bytes_24h`
+    pub bytes_7d: u64,
+    /// 最近 24 小时内修改的文件数
+    pub file_count_24h: u64,
+}
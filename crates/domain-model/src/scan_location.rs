@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// 定位链上的一个节点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeLocator {
+    pub id: u64,
+    pub name: String,
+    pub path: String,
+}
+
+/// 路径在扫描树中的位置，供前端逐级展开树定位到该项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanLocation {
+    /// 从根节点到目标（或最近的已扫描祖先）的节点链，包含两端
+    pub chain: Vec<NodeLocator>,
+    /// 目标本身在树中时为 true；位于浅扫描或被裁剪的子树中时为 false，链止于最近的祖先
+    pub exact: bool,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// 扫描方式：MFT 全量读取或目录遍历
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanMode {
+    Mft,
+    Walk,
+}
+
+/// 本次扫描不会使用 MFT 的原因，按用户最容易处理的顺序只给出第一个
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MftBlocker {
+    /// 未勾选「使用 MFT」
+    NotRequested,
+    PathNotFound,
+    /// 非 Windows 平台
+    UnsupportedPlatform,
+    /// Windows 构建未启用 `windows-native`
+    NativeBackendDisabled,
+    /// 不是磁盘根目录（如 `C:\`）
+    NotVolumeRoot,
+    /// 卷的文件系统不是 NTFS
+    NotNtfs,
+    NotElevated,
+}
+
+/// 扫描前的预检结果，供 UI 在开始扫描前说明将使用的扫描方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanPreflight {
+    pub will_use_mft: bool,
+    /// 不使用 MFT 时的原因代码与面向用户的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mft_blocker: Option<MftBlocker>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mft_reason: Option<String>,
+    pub is_volume_root: bool,
+    /// 卷的文件系统名（如 `NTFS`）；仅在能查询时填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filesystem: Option<String>,
+    pub is_elevated: bool,
+    /// 预计条目数，取自该路径最近一次扫描的文件数；没有扫描记录时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_entries: Option<u64>,
+    pub recommended_mode: ScanMode,
+}
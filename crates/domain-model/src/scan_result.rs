@@ -1,11 +1,31 @@
 use serde::{Deserialize, Serialize};
 
 use crate::FileNode;
+use crate::OwnerUsage;
+use crate::RecentActivityEntry;
 use crate::TopFileEntry;
 
+/// 扫描范围内文件的逻辑大小与实际占用（NTFS 压缩、重复数据删除、稀疏文件后）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhysicalUsage {
+    /// 与 `total_size` 相同
+    pub logical_bytes: u64,
+    pub physical_bytes: u64,
+}
+
+impl PhysicalUsage {
+    /// 压缩等节省的字节数
+    pub fn saved_bytes(&self) -> u64 {
+        self.logical_bytes.saturating_sub(self.physical_bytes)
+    }
+}
+
 /// 扫描结果，包含树结构与各项指标
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
+    /// 扫描结果标识，由桌面端在扫描完成后分配，供分析/计划命令按 id 复用结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_id: Option<String>,
     pub root: FileNode,
     pub scan_time_ms: u64,
     pub file_count: u64,
@@ -23,4 +43,29 @@ pub struct ScanResult {
     /// 按大小排序的前 N 个文件（MFT 扫描时填充），供前端摘要与 AI 分析使用，避免遍历整棵树
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub top_files: Option<Vec<TopFileEntry>>,
+    /// 按所有者汇总的占用（字节降序），仅在扫描时开启所有者归属后填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_usage: Option<Vec<OwnerUsage>>,
+    /// 后台扫描时目录读取是否被限速（scan_time_ms 因此变长）
+    #[serde(default)]
+    pub throttled: bool,
+    /// 按 .gitignore 跳过的文件与目录的总大小（字节），仅在目录遍历时开启忽略规则后为 Some；
+    /// 不计入 total_size 与 file_count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignored_bytes: Option<u64>,
+    /// 扫描范围内用户废纸篓（macOS `~/.Trash`、Linux `~/.local/share/Trash`）与按卷存放的回收站
+    /// （Windows `$Recycle.Bin` 等）的总大小（字节），
+    /// 已计入 total_size；扫描范围不包含废纸篓时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_bytes: Option<u64>,
+    /// 扫描期间被暂停的总时长（毫秒），不计入 scan_time_ms
+    #[serde(default)]
+    pub paused_ms: u64,
+    /// 扫描范围内有压缩、重复数据删除或稀疏文件时的逻辑大小与实际占用；没有时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical_usage: Option<PhysicalUsage>,
+    /// 各顶层目录最近 24 小时、7 天内修改的文件大小（按 24 小时字节数降序，最多 50 项），
+    /// 由扫描时已读取的修改时间汇总，没有修改时间的文件不计入
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_activity: Vec<RecentActivityEntry>,
 }
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// 扫描结果的过期情况：扫描后被删除、移动或转存的路径。
+/// 既是 `get_scan_staleness` 的返回值，也是 `scan-dirty` 事件的负载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanStaleness {
+    pub scan_id: String,
+    /// 已变化的路径，按字典序排列；某目录已在列表中时不再列出其下的路径
+    pub dirty_paths: Vec<String>,
+    /// 扫描树中这些路径原有的大小之和（扫描时未展开的路径不计入）
+    pub changed_bytes: u64,
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// 流式导出（JSON Lines）格式版本，记录结构有不兼容变化时递增
+pub const SCAN_STREAM_VERSION: u32 = 1;
+
+/// 流式导出的一行记录，按 `type` 字段区分：首行为 header，末行为 trailer，其间为 entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScanStreamRecord {
+    Header(ScanStreamHeader),
+    Entry(ScanStreamEntry),
+    Trailer(ScanStreamTotals),
+}
+
+/// 导出格式版本与本次扫描的参数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanStreamHeader {
+    pub version: u32,
+    /// 扫描根路径（规范化后）
+    pub path: String,
+    /// 是否先在内存中建完整棵树再输出
+    pub tree: bool,
+    pub shallow_dirs: bool,
+}
+
+/// 单个文件或目录。目录在其全部子项之后输出，`size` 为递归大小
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanStreamEntry {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    /// Unix 时间戳（秒），最近修改时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// 相对扫描根的深度，根为 0
+    pub depth: usize,
+    /// 无法读取时的原因（如「无权限」「损坏」），此时 size 为 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 其他文件系统的挂载点（未展开），此时 size 为 0
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_mount_point: bool,
+}
+
+/// 与 [`crate::ScanResult`] 同口径的合计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanStreamTotals {
+    pub file_count: u64,
+    pub total_size: u64,
+    pub scan_time_ms: u64,
+}
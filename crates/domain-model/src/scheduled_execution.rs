@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ExecutionReport;
+
+/// 定时执行的计划：到 `run_at` 后满足条件时在后台执行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledExecution {
+    pub schedule_id: String,
+    /// 创建时间（Unix 秒）
+    pub created_at: u64,
+    pub target: ScheduleTarget,
+    /// 最早执行时间（Unix 秒）
+    pub run_at: u64,
+    #[serde(default)]
+    pub conditions: ExecutionConditions,
+    pub status: ScheduleStatus,
+    /// 用户要求立即执行：不再等待 `run_at` 与条件
+    #[serde(default)]
+    pub run_now: bool,
+    /// 已到时间但最近一次检查时未满足的条件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_for: Option<WaitReason>,
+    /// 执行结束后的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<ExecutionReport>,
+}
+
+impl ScheduledExecution {
+    pub fn new(
+        schedule_id: String,
+        target: ScheduleTarget,
+        run_at: u64,
+        conditions: ExecutionConditions,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            schedule_id,
+            created_at,
+            target,
+            run_at,
+            conditions,
+            status: ScheduleStatus::Pending,
+            run_now: false,
+            waiting_for: None,
+            report: None,
+        }
+    }
+}
+
+/// 要执行的内容：已保存的计划，或会话中已批准的动作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleTarget {
+    Plan { name: String },
+    Session { session_id: String },
+}
+
+/// 定时执行的前提条件；无法检测的条件（如 Linux 上的输入空闲与全屏）视为满足
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionConditions {
+    /// 接通交流电源
+    #[serde(default)]
+    pub require_ac_power: bool,
+    /// 键盘与鼠标至少空闲的分钟数
+    #[serde(default)]
+    pub idle_minutes: Option<u32>,
+    /// 没有全屏运行的应用（游戏、演示等）
+    #[serde(default)]
+    pub no_fullscreen: bool,
+    /// 超过 `run_at` 这么多分钟仍未满足条件时放弃，0 表示一直等待
+    #[serde(default = "default_give_up_after_minutes")]
+    pub give_up_after_minutes: u32,
+}
+
+fn default_give_up_after_minutes() -> u32 {
+    360
+}
+
+impl Default for ExecutionConditions {
+    fn default() -> Self {
+        Self {
+            require_ac_power: false,
+            idle_minutes: None,
+            no_fullscreen: false,
+            give_up_after_minutes: default_give_up_after_minutes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    Pending,
+    Running,
+    Completed,
+    Cancelled,
+    /// 超过等待时限仍未满足条件
+    Expired,
+    /// 执行中应用退出；执行日志保留，可用 `resume_execution` 续做
+    Interrupted,
+}
+
+/// 未满足的条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitReason {
+    OnBattery,
+    UserActive,
+    FullscreenApp,
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ChangedTarget, CleanupPlan, DiskAnalysis, PlanSelection, ScanResult};
+
+/// 一次分析会话：把扫描、分析、计划与执行串起来，持久化后可在重启应用后接着处理，
+/// 也是完整的审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub session_id: String,
+    /// 创建时间（Unix 秒）
+    pub created_at: u64,
+    /// 最近一次更新时间（Unix 秒）
+    pub updated_at: u64,
+    pub scan: ScanReference,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<DiskAnalysis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<SessionPlan>,
+    /// 按执行顺序排列，续做中断的执行时追加新的一条
+    #[serde(default)]
+    pub executions: Vec<ExecutionReport>,
+}
+
+impl Session {
+    /// 会话进行到的阶段
+    pub fn stage(&self) -> SessionStage {
+        if !self.executions.is_empty() {
+            SessionStage::Executed
+        } else if self.plan.is_some() {
+            SessionStage::Planned
+        } else if self.analysis.is_some() {
+            SessionStage::Analyzed
+        } else {
+            SessionStage::Scanned
+        }
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            session_id: self.session_id.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            scan: self.scan.clone(),
+            stage: self.stage(),
+            freed_bytes: self.executions.iter().map(|e| e.freed).sum(),
+        }
+    }
+}
+
+/// 会话引用的扫描：扫描树只缓存在内存中，重启后按 `root_path` 重新扫描
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanReference {
+    pub scan_id: String,
+    pub root_path: String,
+    pub total_size: u64,
+    pub file_count: u64,
+    pub scan_time_ms: u64,
+}
+
+impl ScanReference {
+    pub fn new(scan_id: String, scan: &ScanResult) -> Self {
+        Self {
+            scan_id,
+            root_path: scan.root.path.clone(),
+            total_size: scan.total_size,
+            file_count: scan.file_count,
+            scan_time_ms: scan.scan_time_ms,
+        }
+    }
+}
+
+/// 通过校验的清理计划及用户对每个动作的决定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPlan {
+    pub plan: CleanupPlan,
+    /// 与 `plan.actions` 一一对应
+    pub decisions: Vec<ActionDecision>,
+}
+
+/// 用户对计划中单个动作的决定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActionDecision {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// 一次执行（或续做）的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub execution_id: String,
+    /// 结束时间（Unix 秒）
+    pub finished_at: u64,
+    /// 是否为续做中断的执行
+    #[serde(default)]
+    pub resumed: bool,
+    pub executed: usize,
+    pub skipped: usize,
+    pub freed: u64,
+    /// 展示给用户的执行汇总；出错时为错误信息，可用 `resume_execution` 续做
+    pub summary: String,
+    #[serde(default)]
+    pub failed: bool,
+    /// 只执行了计划中勾选的部分动作时为 Some，记录原计划与勾选
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<PlanSelection>,
+    /// 因目标在扫描后已改变而跳过的动作，不计入 `skipped`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<ChangedTarget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionStage {
+    Scanned,
+    Analyzed,
+    Planned,
+    Executed,
+}
+
+/// `list_sessions` 的列表项，不含分析与计划的内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub scan: ScanReference,
+    pub stage: SessionStage,
+    /// 各次执行释放的字节数之和
+    pub freed_bytes: u64,
+}
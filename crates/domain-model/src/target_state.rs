@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// 扫描时动作目标的状态，计划校验时由扫描结果填入。执行前与文件系统上的当前状态比较，
+/// 计划生成后被替换或修改过的目标不再执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetState {
+    pub is_dir: bool,
+    /// 文件大小；目录为其下所有文件大小之和
+    pub size: u64,
+    /// Unix 时间戳（秒），扫描时的修改时间；只用于文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// 目录的直接子项数；扫描没有完整列出子项（超过每个目录的上限、只计大小的目录）时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<usize>,
+}
+
+/// 因目标在扫描后已改变而跳过的动作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedTarget {
+    /// 动作在本次执行中的下标
+    pub index: usize,
+    pub path: String,
+    /// 展示给用户的说明，例如「大小由 120 字节变为 4096 字节」
+    pub reason: String,
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::FileAttributes;
+
 /// 按大小排序的前 N 大文件条目，用于前端摘要与 AI 分析，避免遍历整棵树
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TopFileEntry {
@@ -8,4 +10,16 @@ pub struct TopFileEntry {
     /// Unix 时间戳（秒），最近修改时间
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub modified: Option<u64>,
+    /// Unix 时间戳（秒），创建时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Unix 时间戳（秒），最近访问时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<u64>,
+    /// 隐藏/系统/只读属性；没有任何标志时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<FileAttributes>,
+    /// 文件所有者（如 `DESKTOP\alice` 或 `alice`），仅在扫描时开启所有者归属后填充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
 }
@@ -0,0 +1,181 @@
+//! 云存储服务商报告的校验和，以及按同一算法计算本地文件的校验和
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Dropbox content_hash 的分块大小
+const DROPBOX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// 服务商返回的文件校验和（十六进制）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", content = "value", rename_all = "snake_case")]
+pub enum RemoteChecksum {
+    /// Google Drive md5Checksum、百度网盘分片/整体 MD5
+    Md5(String),
+    /// 阿里云盘 content_hash
+    Sha1(String),
+    /// Dropbox content_hash：每 4MB 分块 SHA-256 拼接后再取 SHA-256
+    DropboxContentHash(String),
+}
+
+/// 服务商使用的校验算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    DropboxContentHash,
+}
+
+impl ChecksumAlgorithm {
+    /// 流式计算本地文件的校验和
+    pub fn compute(self, path: &Path) -> io::Result<RemoteChecksum> {
+        Ok(match self {
+            Self::Md5 => RemoteChecksum::Md5(md5_file(path)?),
+            Self::Sha1 => RemoteChecksum::Sha1(sha1_file(path)?),
+            Self::DropboxContentHash => {
+                RemoteChecksum::DropboxContentHash(dropbox_content_hash_file(path)?)
+            }
+        })
+    }
+}
+
+impl RemoteChecksum {
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Md5(v) | Self::Sha1(v) | Self::DropboxContentHash(v) => v,
+        }
+    }
+
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        match self {
+            Self::Md5(_) => ChecksumAlgorithm::Md5,
+            Self::Sha1(_) => ChecksumAlgorithm::Sha1,
+            Self::DropboxContentHash(_) => ChecksumAlgorithm::DropboxContentHash,
+        }
+    }
+
+    /// 算法相同且十六进制值一致（不区分大小写）
+    pub fn matches(&self, other: &RemoteChecksum) -> bool {
+        self.algorithm() == other.algorithm() && self.value().eq_ignore_ascii_case(other.value())
+    }
+
+    /// 本地文件是否与该校验和一致
+    pub fn matches_file(&self, path: &Path) -> io::Result<bool> {
+        Ok(self.matches(&self.algorithm().compute(path)?))
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 流式读取，避免把大文件整个读入内存
+fn read_blocks(path: &Path, block: usize, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; block];
+    loop {
+        // 填满整个块再回调，保证 Dropbox 的分块边界与文件偏移对齐
+        let mut filled = 0;
+        while filled < block {
+            let n = file.read(&mut buf[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        if filled == 0 {
+            return Ok(());
+        }
+        f(&buf[..filled]);
+        if filled < block {
+            return Ok(());
+        }
+    }
+}
+
+fn hash_file<D: Digest>(path: &Path) -> io::Result<String> {
+    let mut hasher = D::new();
+    read_blocks(path, 1024 * 1024, |chunk| hasher.update(chunk))?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub fn md5_file(path: &Path) -> io::Result<String> {
+    hash_file::<Md5>(path)
+}
+
+pub fn sha1_file(path: &Path) -> io::Result<String> {
+    hash_file::<Sha1>(path)
+}
+
+pub fn dropbox_content_hash_file(path: &Path) -> io::Result<String> {
+    let mut overall = Sha256::new();
+    read_blocks(path, DROPBOX_BLOCK_SIZE, |block| {
+        overall.update(Sha256::digest(block));
+    })?;
+    Ok(to_hex(&overall.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &tempfile::TempDir, data: &[u8]) -> std::path::PathBuf {
+        let path = dir.path().join("f.bin");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_known_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, b"abc");
+        assert_eq!(md5_file(&path).unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            sha1_file(&path).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            dropbox_content_hash_file(&path).unwrap(),
+            "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+        );
+
+        // 空文件没有分块，结果是空输入的 SHA-256
+        let path = write(&dir, b"");
+        assert_eq!(
+            dropbox_content_hash_file(&path).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_dropbox_hash_spans_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..DROPBOX_BLOCK_SIZE + 3)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let path = write(&dir, &data);
+        assert_eq!(
+            dropbox_content_hash_file(&path).unwrap(),
+            "d9a07742bea2c63e8f91388ca3fddc91db0070af2b6163e728b2e4fcfe1b1869"
+        );
+        assert_eq!(md5_file(&path).unwrap(), "4eb3cb40b29217875872fde6b100e432");
+    }
+
+    #[test]
+    fn test_matches_file_ignores_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, b"abc");
+        let remote = RemoteChecksum::Sha1("A9993E364706816ABA3E25717850C26C9CD0D89D".into());
+        assert!(remote.matches_file(&path).unwrap());
+        assert!(!RemoteChecksum::Md5("00".into())
+            .matches_file(&path)
+            .unwrap());
+    }
+}
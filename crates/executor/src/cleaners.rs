@@ -0,0 +1,736 @@
+//! 针对具体缓存的清理器：浏览器 Cache / Code Cache、npm `_cacache`、pip 缓存、
+//! Cargo registry 以及 Windows 更新下载缓存。
+//!
+//! 每个清理器只选择「确定可以删除」的文件（例如浏览器只删缓存目录下的文件，
+//! 从不触碰 History、Cookies 等配置文件数据库），而不是整体删除目录。
+//! 被占用的文件（浏览器正在运行）跳过并在报告中说明。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use ai_disk_common::DiskAnalyzerError;
+use ai_disk_domain::FileCategory;
+use serde::{Deserialize, Serialize};
+
+/// Chromium 系浏览器配置目录下的缓存目录名
+const CHROMIUM_CACHE_DIRS: &[&str] = &["cache", "code cache"];
+
+/// 浏览器配置文件中的数据库与设置文件，即使出现在缓存目录下也不删除
+const CHROMIUM_PROFILE_FILES: &[&str] = &[
+    "history",
+    "cookies",
+    "login data",
+    "web data",
+    "preferences",
+    "secure preferences",
+    "bookmarks",
+    "local state",
+    "favicons",
+    "top sites",
+];
+
+/// 路径中出现这些片段时视为 Chrome/Edge 的数据目录（已统一为小写、正斜杠）
+const CHROMIUM_PATH_MARKERS: &[&str] = &[
+    "/google/chrome",
+    "/microsoft/edge",
+    "/microsoft edge",
+    "/google-chrome",
+    "/microsoft-edge",
+];
+
+/// pip 缓存中可以删除的子目录（下载缓存与构建的 wheel）
+const PIP_CACHE_DIRS: &[&str] = &["http", "http-v2", "wheels"];
+
+/// Cargo 解压完成的标记文件；先删除它，中断后 Cargo 会重新解压而不是使用残缺的源码
+const CARGO_OK_MARKER: &str = ".cargo-ok";
+
+/// 清理选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanOptions {
+    /// 只统计将要删除的文件，不做任何修改
+    pub dry_run: bool,
+    /// 只删除超过该天数未修改的内容（目前用于 Cargo registry）
+    pub older_than_days: u64,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: true,
+            older_than_days: 30,
+        }
+    }
+}
+
+/// 文件被跳过的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// 文件正被其他进程占用
+    Locked,
+    PermissionDenied,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanedFile {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// 一次清理的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanReport {
+    pub cleaner: String,
+    pub root: String,
+    pub dry_run: bool,
+    /// dry-run 时为将要删除的文件，实际执行时为已删除的文件
+    pub files: Vec<CleanedFile>,
+    /// dry-run 时为可释放的字节数，实际执行时为已释放的字节数
+    pub bytes: u64,
+    pub skipped: Vec<SkippedFile>,
+    /// 给用户看的说明，例如「有文件被占用，关闭浏览器后重试」
+    pub notes: Vec<String>,
+}
+
+/// 清理器选出的内容
+#[derive(Debug, Default)]
+struct Selection {
+    /// (路径, 大小)，按删除顺序排列
+    files: Vec<(PathBuf, u64)>,
+    /// 删除后如已为空则一并移除的目录
+    prune: Vec<PathBuf>,
+}
+
+/// 一个清理器：知道某类缓存目录里哪些文件可以安全删除
+pub struct Cleaner {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub category: FileCategory,
+    /// 文件被占用时给用户的提示
+    locked_hint: &'static str,
+    applies_to: fn(&Path) -> bool,
+    select: fn(&Path, &CleanOptions, SystemTime) -> Selection,
+}
+
+impl std::fmt::Debug for Cleaner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cleaner")
+            .field("id", &self.id)
+            .field("category", &self.category)
+            .finish()
+    }
+}
+
+/// 所有清理器；启发式规划按类别或路径从这里查找
+pub static CLEANERS: &[Cleaner] = &[
+    Cleaner {
+        id: "chromium_cache",
+        name: "Chrome / Edge 缓存",
+        category: FileCategory::BrowserCache,
+        locked_hint: "部分缓存文件正被占用，浏览器可能正在运行；关闭 Chrome / Edge 后重试",
+        applies_to: is_chromium_dir,
+        select: select_chromium,
+    },
+    Cleaner {
+        id: "npm_cache",
+        name: "npm 缓存",
+        category: FileCategory::PackageCache,
+        locked_hint: "部分文件正被占用，可能有 npm 进程正在运行",
+        applies_to: is_npm_dir,
+        select: select_npm,
+    },
+    Cleaner {
+        id: "pip_cache",
+        name: "pip 缓存",
+        category: FileCategory::PackageCache,
+        locked_hint: "部分文件正被占用，可能有 pip 进程正在运行",
+        applies_to: is_pip_dir,
+        select: select_pip,
+    },
+    Cleaner {
+        id: "cargo_registry",
+        name: "Cargo registry 缓存",
+        category: FileCategory::PackageCache,
+        locked_hint: "部分文件正被占用，可能有 cargo 正在构建",
+        applies_to: is_cargo_dir,
+        select: select_cargo,
+    },
+    Cleaner {
+        id: "windows_update_download",
+        name: "Windows 更新下载缓存",
+        category: FileCategory::AppCache,
+        locked_hint: "部分文件正被 Windows 更新服务占用，更新完成后重试",
+        applies_to: is_windows_update_dir,
+        select: select_all_files,
+    },
+];
+
+/// 某一类别下的清理器
+pub fn cleaners_for(category: FileCategory) -> impl Iterator<Item = &'static Cleaner> {
+    CLEANERS.iter().filter(move |c| c.category == category)
+}
+
+pub fn cleaner_by_id(id: &str) -> Option<&'static Cleaner> {
+    CLEANERS.iter().find(|c| c.id == id)
+}
+
+/// 能处理该目录的清理器
+pub fn cleaner_for_path(path: &Path) -> Option<&'static Cleaner> {
+    CLEANERS.iter().find(|c| (c.applies_to)(path))
+}
+
+impl Cleaner {
+    pub fn applies_to(&self, path: &Path) -> bool {
+        (self.applies_to)(path)
+    }
+
+    /// 清理 `root`；`root` 必须是该清理器认识的目录
+    pub fn run(
+        &self,
+        root: &Path,
+        options: &CleanOptions,
+    ) -> Result<CleanReport, DiskAnalyzerError> {
+        self.run_with(root, options, SystemTime::now(), |p| fs::remove_file(p))
+    }
+
+    fn run_with(
+        &self,
+        root: &Path,
+        options: &CleanOptions,
+        now: SystemTime,
+        mut remove: impl FnMut(&Path) -> io::Result<()>,
+    ) -> Result<CleanReport, DiskAnalyzerError> {
+        if !root.is_dir() {
+            return Err(DiskAnalyzerError::InvalidPath(format!(
+                "目录不存在: {}",
+                root.display()
+            )));
+        }
+        if !self.applies_to(root) {
+            return Err(DiskAnalyzerError::InvalidPath(format!(
+                "{} 不处理该目录: {}",
+                self.name,
+                root.display()
+            )));
+        }
+
+        let selection = (self.select)(root, options, now);
+        let mut report = CleanReport {
+            cleaner: self.id.to_string(),
+            root: root.display().to_string(),
+            dry_run: options.dry_run,
+            files: Vec::new(),
+            bytes: 0,
+            skipped: Vec::new(),
+            notes: Vec::new(),
+        };
+        for (path, bytes) in selection.files {
+            let outcome = if options.dry_run {
+                if is_locked(&path) {
+                    Err(SkipReason::Locked)
+                } else {
+                    Ok(())
+                }
+            } else {
+                remove(&path).map_err(|e| skip_reason(&e))
+            };
+            match outcome {
+                Ok(()) => {
+                    report.bytes = report.bytes.saturating_add(bytes);
+                    report.files.push(CleanedFile {
+                        path: path.display().to_string(),
+                        bytes,
+                    });
+                }
+                Err(reason) => report.skipped.push(SkippedFile {
+                    path: path.display().to_string(),
+                    reason,
+                }),
+            }
+        }
+        if !options.dry_run {
+            for dir in &selection.prune {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+
+        let locked = count_skipped(&report, |r| *r == SkipReason::Locked);
+        if locked > 0 {
+            report
+                .notes
+                .push(format!("已跳过 {} 个文件：{}", locked, self.locked_hint));
+        }
+        let denied = count_skipped(&report, |r| *r == SkipReason::PermissionDenied);
+        if denied > 0 {
+            report.notes.push(format!(
+                "已跳过 {} 个无权限删除的文件，可能需要管理员权限",
+                denied
+            ));
+        }
+        Ok(report)
+    }
+}
+
+fn count_skipped(report: &CleanReport, pred: impl Fn(&SkipReason) -> bool) -> usize {
+    report.skipped.iter().filter(|s| pred(&s.reason)).count()
+}
+
+/// 删除失败时的原因；Windows 共享冲突与 Unix 的 EBUSY/ETXTBSY 视为被占用
+pub(crate) fn skip_reason(e: &io::Error) -> SkipReason {
+    #[cfg(windows)]
+    const LOCKED_CODES: &[i32] = &[32, 33];
+    #[cfg(not(windows))]
+    const LOCKED_CODES: &[i32] = &[16, 26];
+
+    if e.raw_os_error()
+        .is_some_and(|code| LOCKED_CODES.contains(&code))
+    {
+        SkipReason::Locked
+    } else if e.kind() == io::ErrorKind::PermissionDenied {
+        SkipReason::PermissionDenied
+    } else {
+        SkipReason::Failed(e.to_string())
+    }
+}
+
+/// dry-run 时判断文件是否被占用：Windows 上以独占方式打开，失败说明有其他进程持有；
+/// 其他平台没有强制锁，删除总能成功
+#[cfg(windows)]
+fn is_locked(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+    match fs::OpenOptions::new().read(true).share_mode(0).open(path) {
+        Ok(_) => false,
+        Err(e) => skip_reason(&e) == SkipReason::Locked,
+    }
+}
+
+#[cfg(not(windows))]
+fn is_locked(_path: &Path) -> bool {
+    false
+}
+
+/// 路径统一为小写、正斜杠，便于匹配
+fn normalized(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase().replace('\\', "/")
+}
+
+fn lower_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// 递归列出 `dir` 下的文件（不跟随符号链接），回调参数为相对 `root` 的小写路径组件
+fn walk_files(root: &Path, dir: &Path, visit: &mut dyn FnMut(&[String], &Path, &fs::Metadata)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let path = entry.path();
+        if metadata.is_dir() {
+            walk_files(root, &path, visit);
+        } else if metadata.is_file() {
+            let components: Vec<String> = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+                .collect();
+            visit(&components, &path, &metadata);
+        }
+    }
+}
+
+/// 选出位于某个指定名称目录（或 `root` 本身即为该目录）之下的文件
+fn select_under_dirs(root: &Path, dir_names: &[&str], protected: &[&str]) -> Selection {
+    let root_matches = dir_names.contains(&lower_name(root).as_str());
+    let mut selection = Selection::default();
+    walk_files(root, root, &mut |components, path, metadata| {
+        let Some((file_name, parents)) = components.split_last() else {
+            return;
+        };
+        let in_dir = root_matches || parents.iter().any(|p| dir_names.contains(&p.as_str()));
+        if in_dir && !protected.contains(&file_name.as_str()) {
+            selection.files.push((path.to_path_buf(), metadata.len()));
+        }
+    });
+    selection.files.sort();
+    selection
+}
+
+fn is_chromium_dir(path: &Path) -> bool {
+    let path = normalized(path);
+    CHROMIUM_PATH_MARKERS.iter().any(|m| path.contains(m))
+}
+
+/// 只删除 Cache / Code Cache 下的文件；`root` 可以是整个数据目录、某个配置文件目录或缓存目录本身
+fn select_chromium(root: &Path, _options: &CleanOptions, _now: SystemTime) -> Selection {
+    select_under_dirs(root, CHROMIUM_CACHE_DIRS, CHROMIUM_PROFILE_FILES)
+}
+
+fn is_npm_dir(path: &Path) -> bool {
+    matches!(lower_name(path).as_str(), "_cacache" | ".npm" | "npm-cache")
+}
+
+/// 只删除内容寻址缓存 `_cacache`，保留 `_logs` 等其他内容
+fn select_npm(root: &Path, _options: &CleanOptions, _now: SystemTime) -> Selection {
+    select_under_dirs(root, &["_cacache"], &[])
+}
+
+fn is_pip_dir(path: &Path) -> bool {
+    path.components()
+        .any(|c| c.as_os_str().to_string_lossy().eq_ignore_ascii_case("pip"))
+}
+
+/// 只删除下载缓存与构建的 wheel，保留 selfcheck 等状态文件
+fn select_pip(root: &Path, _options: &CleanOptions, _now: SystemTime) -> Selection {
+    select_under_dirs(root, PIP_CACHE_DIRS, &[])
+}
+
+fn is_cargo_dir(path: &Path) -> bool {
+    let name = lower_name(path);
+    name == ".cargo" || name == "registry" || path.join("registry").is_dir()
+}
+
+fn is_older(modified: Option<SystemTime>, now: SystemTime, options: &CleanOptions) -> bool {
+    let age = Duration::from_secs(options.older_than_days.saturating_mul(24 * 3600));
+    modified
+        .and_then(|m| now.duration_since(m).ok())
+        .is_some_and(|elapsed| elapsed >= age)
+}
+
+/// 删除超过期限的 `registry/cache/*/*.crate` 与解压后的 `registry/src/*/<crate>` 目录；
+/// 从不触碰 `registry/index`、`bin`、配置与凭据文件
+fn select_cargo(root: &Path, options: &CleanOptions, now: SystemTime) -> Selection {
+    let registry = if lower_name(root) == "registry" {
+        root.to_path_buf()
+    } else {
+        root.join("registry")
+    };
+    let mut selection = Selection::default();
+    let subdirs = |dir: PathBuf| -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|e| e.path())
+            .collect();
+        dirs.sort();
+        dirs
+    };
+
+    for index in subdirs(registry.join("cache")) {
+        walk_files(&index, &index, &mut |components, path, metadata| {
+            let is_crate = components.len() == 1 && components[0].ends_with(".crate");
+            if is_crate && is_older(metadata.modified().ok(), now, options) {
+                selection.files.push((path.to_path_buf(), metadata.len()));
+            }
+        });
+    }
+
+    // 解压的源码以 crate 目录为单位：要么整个删除，要么不动
+    for index in subdirs(registry.join("src")) {
+        for krate in subdirs(index) {
+            let marker = krate.join(CARGO_OK_MARKER);
+            let modified = fs::metadata(&marker)
+                .or_else(|_| fs::metadata(&krate))
+                .and_then(|m| m.modified())
+                .ok();
+            if !is_older(modified, now, options) {
+                continue;
+            }
+            let mut files = Vec::new();
+            walk_files(&krate, &krate, &mut |_, path, metadata| {
+                files.push((path.to_path_buf(), metadata.len()));
+            });
+            // 标记文件排在最前
+            files.sort_by_key(|(p, _)| (p != &marker, p.clone()));
+            selection.files.extend(files);
+            let mut dirs = Vec::new();
+            collect_dirs(&krate, &mut dirs);
+            // 先子目录后父目录
+            dirs.reverse();
+            selection.prune.extend(dirs);
+        }
+    }
+    selection
+}
+
+/// 按先序收集 `dir` 及其下所有目录
+fn collect_dirs(dir: &Path, out: &mut Vec<PathBuf>) {
+    out.push(dir.to_path_buf());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut children: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect();
+    children.sort();
+    for child in children {
+        collect_dirs(&child, out);
+    }
+}
+
+fn is_windows_update_dir(path: &Path) -> bool {
+    normalized(path).ends_with("softwaredistribution/download")
+}
+
+fn select_all_files(root: &Path, _options: &CleanOptions, _now: SystemTime) -> Selection {
+    let mut selection = Selection::default();
+    walk_files(root, root, &mut |_, path, metadata| {
+        selection.files.push((path.to_path_buf(), metadata.len()));
+    });
+    selection.files.sort();
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![1u8; len]).unwrap();
+    }
+
+    fn set_age(path: &Path, days: u64) {
+        let time = SystemTime::now() - Duration::from_secs(days * 24 * 3600);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    fn real() -> CleanOptions {
+        CleanOptions {
+            dry_run: false,
+            older_than_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_chromium_keeps_profile_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_data = dir.path().join("Google").join("Chrome").join("User Data");
+        let profile = user_data.join("Default");
+        write(
+            &profile.join("Cache").join("Cache_Data").join("data_0"),
+            100,
+        );
+        write(&profile.join("Code Cache").join("js").join("abc"), 50);
+        write(&profile.join("Cache").join("Preferences"), 7);
+        for db in [
+            "History",
+            "Cookies",
+            "Login Data",
+            "Preferences",
+            "Bookmarks",
+        ] {
+            write(&profile.join(db), 10);
+        }
+        write(
+            &profile
+                .join("Service Worker")
+                .join("CacheStorage")
+                .join("x"),
+            10,
+        );
+        write(&user_data.join("Local State"), 10);
+
+        let cleaner = cleaner_by_id("chromium_cache").unwrap();
+        let preview = cleaner.run(&user_data, &CleanOptions::default()).unwrap();
+        assert!(preview.dry_run);
+        assert_eq!(preview.bytes, 150);
+        assert_eq!(preview.files.len(), 2);
+        assert!(profile
+            .join("Cache")
+            .join("Cache_Data")
+            .join("data_0")
+            .exists());
+
+        let report = cleaner.run(&profile, &real()).unwrap();
+        assert_eq!(report.bytes, 150);
+        assert!(!profile
+            .join("Cache")
+            .join("Cache_Data")
+            .join("data_0")
+            .exists());
+        assert!(!profile.join("Code Cache").join("js").join("abc").exists());
+        for db in [
+            "History",
+            "Cookies",
+            "Login Data",
+            "Preferences",
+            "Bookmarks",
+        ] {
+            assert!(profile.join(db).exists(), "{} 被删除", db);
+        }
+        assert!(profile.join("Cache").join("Preferences").exists());
+        assert!(profile
+            .join("Service Worker")
+            .join("CacheStorage")
+            .join("x")
+            .exists());
+        assert!(user_data.join("Local State").exists());
+    }
+
+    #[test]
+    fn test_locked_files_are_skipped_with_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir
+            .path()
+            .join("Microsoft")
+            .join("Edge")
+            .join("User Data")
+            .join("Default")
+            .join("Cache");
+        write(&cache.join("data_0"), 10);
+        write(&cache.join("data_1"), 20);
+
+        #[cfg(windows)]
+        const LOCKED: i32 = 32;
+        #[cfg(not(windows))]
+        const LOCKED: i32 = 16;
+        let cleaner = cleaner_by_id("chromium_cache").unwrap();
+        let report = cleaner
+            .run_with(&cache, &real(), SystemTime::now(), |p| {
+                if p.ends_with("data_0") {
+                    Err(io::Error::from_raw_os_error(LOCKED))
+                } else {
+                    fs::remove_file(p)
+                }
+            })
+            .unwrap();
+        assert_eq!(report.bytes, 20);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, SkipReason::Locked);
+        assert!(report.notes[0].contains("浏览器可能正在运行"));
+        assert!(cache.join("data_0").exists());
+    }
+
+    #[test]
+    fn test_npm_and_pip_keep_non_cache_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let npm = dir.path().join(".npm");
+        write(
+            &npm.join("_cacache")
+                .join("content-v2")
+                .join("sha512")
+                .join("ab"),
+            30,
+        );
+        write(&npm.join("_cacache").join("index-v5").join("cd"), 5);
+        write(&npm.join("_logs").join("debug.log"), 9);
+        let report = cleaner_by_id("npm_cache")
+            .unwrap()
+            .run(&npm, &real())
+            .unwrap();
+        assert_eq!(report.bytes, 35);
+        assert!(npm.join("_logs").join("debug.log").exists());
+
+        let pip = dir.path().join("cache").join("pip");
+        write(&pip.join("http-v2").join("a").join("b"), 40);
+        write(&pip.join("wheels").join("x.whl"), 60);
+        write(&pip.join("selfcheck").join("state.json"), 3);
+        let report = cleaner_by_id("pip_cache")
+            .unwrap()
+            .run(&pip, &real())
+            .unwrap();
+        assert_eq!(report.bytes, 100);
+        assert!(pip.join("selfcheck").join("state.json").exists());
+    }
+
+    #[test]
+    fn test_cargo_removes_only_old_crates_and_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo = dir.path().join(".cargo");
+        let cache = cargo
+            .join("registry")
+            .join("cache")
+            .join("index.crates.io-6f17d22bba15001f");
+        let src = cargo
+            .join("registry")
+            .join("src")
+            .join("index.crates.io-6f17d22bba15001f");
+        write(&cache.join("old-1.0.0.crate"), 100);
+        write(&cache.join("new-1.0.0.crate"), 100);
+        set_age(&cache.join("old-1.0.0.crate"), 90);
+        write(&src.join("old-1.0.0").join("src").join("lib.rs"), 20);
+        write(&src.join("old-1.0.0").join(CARGO_OK_MARKER), 0);
+        set_age(&src.join("old-1.0.0").join(CARGO_OK_MARKER), 90);
+        write(&src.join("new-1.0.0").join("src").join("lib.rs"), 20);
+        write(&src.join("new-1.0.0").join(CARGO_OK_MARKER), 0);
+        write(&cargo.join("registry").join("index").join("config.json"), 5);
+        set_age(
+            &cargo.join("registry").join("index").join("config.json"),
+            365,
+        );
+        write(&cargo.join("config.toml"), 5);
+        write(&cargo.join("credentials.toml"), 5);
+        write(&cargo.join("bin").join("cargo-foo"), 5);
+
+        let cleaner = cleaner_by_id("cargo_registry").unwrap();
+        let preview = cleaner.run(&cargo, &CleanOptions::default()).unwrap();
+        assert_eq!(preview.bytes, 120);
+        assert!(preview.files[1].path.ends_with(CARGO_OK_MARKER));
+
+        let report = cleaner.run(&cargo, &real()).unwrap();
+        assert_eq!(report.bytes, 120);
+        assert!(!cache.join("old-1.0.0.crate").exists());
+        assert!(!src.join("old-1.0.0").exists());
+        assert!(cache.join("new-1.0.0.crate").exists());
+        assert!(src.join("new-1.0.0").join("src").join("lib.rs").exists());
+        for kept in ["config.toml", "credentials.toml"] {
+            assert!(cargo.join(kept).exists());
+        }
+        assert!(cargo.join("bin").join("cargo-foo").exists());
+        assert!(cargo
+            .join("registry")
+            .join("index")
+            .join("config.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_registry_lookup_and_unknown_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let download = dir
+            .path()
+            .join("Windows")
+            .join("SoftwareDistribution")
+            .join("Download");
+        write(&download.join("abc").join("update.cab"), 70);
+        let cleaner = cleaner_for_path(&download).unwrap();
+        assert_eq!(cleaner.id, "windows_update_download");
+        assert_eq!(cleaner.run(&download, &real()).unwrap().bytes, 70);
+
+        assert!(cleaners_for(FileCategory::BrowserCache).all(|c| c.id == "chromium_cache"));
+        assert_eq!(cleaners_for(FileCategory::PackageCache).count(), 3);
+
+        let other = dir.path().join("Documents");
+        fs::create_dir_all(&other).unwrap();
+        assert!(cleaner_for_path(&other).is_none());
+        let err = cleaner_by_id("chromium_cache")
+            .unwrap()
+            .run(&other, &real())
+            .unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::InvalidPath(_)));
+    }
+}
@@ -1,7 +1,311 @@
+//! 永久删除文件或目录。
+//!
+//! 旧程序残留中常有带只读属性（以及系统属性）的文件，Windows 上直接删除会被拒绝（os error 5），
+//! 计划恰恰在要清理的文件上失败。遇到拒绝访问时清除属性后重试一次：Windows 上清除只读属性
+//! （开启 [`DeleteOptions::force_attributes`] 时连同系统属性），Unix 上删除条目需要所在目录可写，
+//! 为所在目录加上写权限。每次调整都记录在 [`DeleteOutcome`] 中，供报告说明。
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
 use ai_disk_common::DiskAnalyzerError;
+use serde::{Deserialize, Serialize};
+
+/// 删除选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeleteOptions {
+    /// 拒绝访问时也清除系统属性（仅 Windows）
+    pub force_attributes: bool,
+}
+
+/// 为重试删除而做的属性调整
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeFallback {
+    /// 清除了只读属性（Windows）
+    ReadOnlyCleared,
+    /// 清除了系统属性（Windows）
+    SystemCleared,
+    /// 为所在目录加上了写权限（Unix）
+    ParentMadeWritable,
+}
+
+/// 一次属性调整：`path` 为被调整的条目（Unix 上为所在目录）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeChange {
+    pub path: String,
+    pub fallback: AttributeFallback,
+}
+
+/// 删除结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeleteOutcome {
+    pub is_dir: bool,
+    /// 拒绝访问后为重试所做的属性调整，按发生顺序
+    pub changes: Vec<AttributeChange>,
+}
+
+impl DeleteOutcome {
+    /// 报告中的说明，见 [`attribute_note`]
+    pub fn note(&self) -> Option<String> {
+        attribute_note(&self.changes)
+    }
+}
+
+/// 一次或多次删除中属性调整的说明，如「已清除 2 项的只读属性」；没有调整时为 None
+pub fn attribute_note(changes: &[AttributeChange]) -> Option<String> {
+    let parts: Vec<String> = [
+        (AttributeFallback::ReadOnlyCleared, "项的只读属性"),
+        (AttributeFallback::SystemCleared, "项的系统属性"),
+        (AttributeFallback::ParentMadeWritable, "个目录的写保护"),
+    ]
+    .into_iter()
+    .filter_map(|(fallback, what)| {
+        match changes.iter().filter(|c| c.fallback == fallback).count() {
+            0 => None,
+            n => Some(format!("{} {}", n, what)),
+        }
+    })
+    .collect();
+    (!parts.is_empty()).then(|| format!("已清除 {}", parts.join("、")))
+}
+
+/// 永久删除 `path`（目录连同其下所有内容），不跟随符号链接
+pub fn delete_entry(
+    path: &Path,
+    options: DeleteOptions,
+) -> Result<DeleteOutcome, DiskAnalyzerError> {
+    delete_entry_with(path, options, remove_one)
+}
+
+fn remove_one(path: &Path, is_dir: bool) -> io::Result<()> {
+    if is_dir {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn delete_entry_with(
+    path: &Path,
+    options: DeleteOptions,
+    mut remove: impl FnMut(&Path, bool) -> io::Result<()>,
+) -> Result<DeleteOutcome, DiskAnalyzerError> {
+    let is_dir = fs::symlink_metadata(path)?.is_dir();
+    let mut outcome = DeleteOutcome {
+        is_dir,
+        changes: Vec::new(),
+    };
+    // 所在目录不随本次删除消失，调整过的权限在删除后恢复
+    let parent_permissions = path
+        .parent()
+        .and_then(|parent| fs::metadata(parent).ok())
+        .map(|m| m.permissions());
+    let result = remove_tree(path, is_dir, options, &mut remove, &mut outcome);
+    let parent_changed = outcome.changes.iter().any(|c| {
+        c.fallback == AttributeFallback::ParentMadeWritable
+            && path.parent() == Some(Path::new(&c.path))
+    });
+    if let (true, Some(parent), Some(permissions)) =
+        (parent_changed, path.parent(), parent_permissions)
+    {
+        let _ = fs::set_permissions(parent, permissions);
+    }
+    result?;
+    Ok(outcome)
+}
+
+fn remove_tree(
+    path: &Path,
+    is_dir: bool,
+    options: DeleteOptions,
+    remove: &mut impl FnMut(&Path, bool) -> io::Result<()>,
+    outcome: &mut DeleteOutcome,
+) -> io::Result<()> {
+    if is_dir {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let child_is_dir = entry.file_type()?.is_dir();
+            remove_tree(&entry.path(), child_is_dir, options, remove, outcome)?;
+        }
+    }
+    match remove(path, is_dir) {
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            let changes = clear_protection(path, options)?;
+            if changes.is_empty() {
+                return Err(e);
+            }
+            outcome.changes.extend(changes);
+            remove(path, is_dir)
+        }
+        result => result,
+    }
+}
+
+/// 清除妨碍删除 `path` 的属性；没有可清除的属性时返回空
+#[cfg(windows)]
+fn clear_protection(path: &Path, options: DeleteOptions) -> io::Result<Vec<AttributeChange>> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileAttributesW, SetFileAttributesW, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM,
+        INVALID_FILE_ATTRIBUTES,
+    };
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    // SAFETY: wide 是以 NUL 结尾的宽字符串，调用期间有效
+    let attributes = unsafe { GetFileAttributesW(wide.as_ptr()) };
+    if attributes == INVALID_FILE_ATTRIBUTES {
+        return Err(io::Error::last_os_error());
+    }
+    let mut clear = 0;
+    let mut changes = Vec::new();
+    let mut record = |fallback| {
+        changes.push(AttributeChange {
+            path: path.display().to_string(),
+            fallback,
+        })
+    };
+    if attributes & FILE_ATTRIBUTE_READONLY != 0 {
+        clear |= FILE_ATTRIBUTE_READONLY;
+        record(AttributeFallback::ReadOnlyCleared);
+    }
+    if options.force_attributes && attributes & FILE_ATTRIBUTE_SYSTEM != 0 {
+        clear |= FILE_ATTRIBUTE_SYSTEM;
+        record(AttributeFallback::SystemCleared);
+    }
+    // SAFETY: 同上
+    if clear != 0 && unsafe { SetFileAttributesW(wide.as_ptr(), attributes & !clear) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(changes)
+}
+
+/// 清除妨碍删除 `path` 的属性；没有可清除的属性时返回空
+#[cfg(unix)]
+fn clear_protection(path: &Path, _options: DeleteOptions) -> io::Result<Vec<AttributeChange>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let mut permissions = fs::metadata(parent)?.permissions();
+    if permissions.mode() & 0o200 != 0 {
+        return Ok(Vec::new());
+    }
+    permissions.set_mode(permissions.mode() | 0o200);
+    fs::set_permissions(parent, permissions)?;
+    Ok(vec![AttributeChange {
+        path: parent.display().to_string(),
+        fallback: AttributeFallback::ParentMadeWritable,
+    }])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![1u8; len]).unwrap();
+    }
+
+    fn make_readonly(path: &Path) {
+        let mut permissions = fs::metadata(path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(path, permissions).unwrap();
+    }
+
+    fn readonly(path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|m| m.permissions().readonly())
+    }
+
+    /// 按当前平台的规则拒绝删除：Windows 上条目带只读属性，Unix 上所在目录不可写。
+    /// 以 root 运行测试时系统不做权限检查，这里模拟普通用户
+    fn strict_remove(path: &Path, is_dir: bool) -> io::Result<()> {
+        let protected = if cfg!(windows) {
+            readonly(path)
+        } else {
+            path.parent().is_some_and(readonly)
+        };
+        if protected {
+            return Err(io::Error::from(ErrorKind::PermissionDenied));
+        }
+        remove_one(path, is_dir)
+    }
+
+    #[test]
+    fn test_read_only_file_is_deleted_with_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("old").join("setup.log");
+        write(&file, 10);
+        let protected = if cfg!(windows) {
+            &file
+        } else {
+            file.parent().unwrap()
+        };
+        make_readonly(protected);
+
+        let outcome = delete_entry_with(&file, DeleteOptions::default(), strict_remove).unwrap();
+        assert!(!file.exists());
+        assert!(!outcome.is_dir);
+        assert_eq!(outcome.changes.len(), 1);
+        let expected = if cfg!(windows) {
+            AttributeFallback::ReadOnlyCleared
+        } else {
+            AttributeFallback::ParentMadeWritable
+        };
+        assert_eq!(outcome.changes[0].fallback, expected);
+        assert!(outcome.note().is_some());
+        // 保留下来的所在目录恢复原有权限
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert!(readonly(protected));
+            fs::set_permissions(protected, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_directory_with_read_only_entries_is_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("OldProgram");
+        write(&root.join("bin").join("tool.exe"), 30);
+        write(&root.join("bin").join("tool.dll"), 20);
+        write(&root.join("readme.txt"), 5);
+        make_readonly(&root.join("bin").join("tool.exe"));
+        make_readonly(&root.join("bin").join("tool.dll"));
+        make_readonly(&root.join("bin"));
+
+        let outcome = delete_entry_with(&root, DeleteOptions::default(), strict_remove).unwrap();
+        assert!(!root.exists());
+        assert!(outcome.is_dir);
+        let note = outcome.note().unwrap();
+        if cfg!(windows) {
+            // 两个文件与 bin 目录本身
+            assert_eq!(outcome.changes.len(), 3);
+            assert_eq!(note, "已清除 3 项的只读属性");
+        } else {
+            // bin 目录只需加一次写权限
+            assert_eq!(outcome.changes.len(), 1);
+            assert_eq!(note, "已清除 1 个目录的写保护");
+        }
+    }
 
-/// 删除执行（预留）
-pub async fn delete_file(path: &str) -> Result<(), DiskAnalyzerError> {
-    let _ = path;
-    Ok(())
+    #[test]
+    fn test_plain_delete_records_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("cache");
+        write(&root.join("a").join("b.bin"), 10);
+        let outcome = delete_entry(&root, DeleteOptions::default()).unwrap();
+        assert!(!root.exists());
+        assert_eq!(outcome.note(), None);
+        // 其他错误不重试，原样返回
+        let missing = delete_entry(&root, DeleteOptions::default()).unwrap_err();
+        assert!(matches!(missing, DiskAnalyzerError::Io(e) if e.kind() == ErrorKind::NotFound));
+    }
 }
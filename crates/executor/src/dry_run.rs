@@ -1,4 +1,213 @@
+use std::collections::BTreeMap;
+
+use ai_disk_common::{ExecutionMode, ExecutionPolicy};
+use ai_disk_domain::{Action, CleanupPlan, FreeSpaceProjection, PlannedAction};
+use serde::{Deserialize, Serialize};
+
+use crate::policy::resolve_action_mode;
+
 /// 模拟执行（预留）
 pub fn simulate_actions(_dry_run: bool) -> bool {
     true
 }
+
+/// 计划的模拟执行结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSimulation {
+    pub delete_count: usize,
+    pub move_count: usize,
+    pub offload_count: usize,
+    /// 压缩虚拟磁盘的动作数，不释放也不上传任何文件
+    pub compact_count: usize,
+    /// 各云存储提供商需要上传的字节数
+    pub upload_bytes: BTreeMap<String, u64>,
+    /// 已不存在的路径；转存动作还包括不是文件、无法读取大小的路径。
+    /// 保存的计划重新加载时据此发现计划之后发生变化的文件
+    pub missing: Vec<String>,
+    /// 按执行策略解析出的每个动作的执行方式，与传入的动作一一对应；只模拟裸动作时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previews: Vec<ActionPreview>,
+    /// 执行后的剩余空间预测，分为立即释放与清空回收站后才释放的部分；
+    /// 由持有扫描结果的调用方填写，没有覆盖这些动作的扫描时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projection: Option<FreeSpaceProjection>,
+}
+
+/// 单个动作将以何种方式执行，供确认前逐条展示「将永久删除 / 将移入回收站 / 跳过」
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionPreview {
+    pub path: String,
+    pub mode: ExecutionMode,
+}
+
+/// 不修改任何文件，统计计划各类动作的数量以及每个提供商的上传量
+pub fn simulate_plan(plan: &CleanupPlan) -> PlanSimulation {
+    let mut simulation = PlanSimulation::default();
+    for action in &plan.actions {
+        match action {
+            // 清空废纸篓按一次删除计
+            Action::Delete { path } | Action::EmptyTrash { path } => {
+                simulation.delete_count += 1;
+                if std::fs::symlink_metadata(path).is_err() {
+                    simulation.missing.push(path.clone());
+                }
+            }
+            Action::CompactVhd { path } => {
+                simulation.compact_count += 1;
+                if std::fs::symlink_metadata(path).is_err() {
+                    simulation.missing.push(path.clone());
+                }
+            }
+            Action::Move { from, .. } => {
+                simulation.move_count += 1;
+                if std::fs::symlink_metadata(from).is_err() {
+                    simulation.missing.push(from.clone());
+                }
+            }
+            Action::Offload { path, provider, .. } => {
+                simulation.offload_count += 1;
+                match std::fs::metadata(path) {
+                    Ok(metadata) if metadata.is_file() => {
+                        *simulation.upload_bytes.entry(provider.clone()).or_default() +=
+                            metadata.len();
+                    }
+                    _ => simulation.missing.push(path.clone()),
+                }
+            }
+        }
+    }
+    simulation
+}
+
+/// 按执行策略模拟计划：统计只包含将要执行的动作，被跳过的动作只出现在 `previews` 中
+pub fn simulate_planned(actions: &[PlannedAction], policy: &ExecutionPolicy) -> PlanSimulation {
+    let previews: Vec<ActionPreview> = actions
+        .iter()
+        .map(|planned| ActionPreview {
+            path: planned.action.source_path().to_string(),
+            mode: resolve_action_mode(policy, planned),
+        })
+        .collect();
+    let executable = CleanupPlan {
+        actions: actions
+            .iter()
+            .zip(&previews)
+            .filter(|(_, preview)| preview.mode != ExecutionMode::Skip)
+            .map(|(planned, _)| planned.action.clone())
+            .collect(),
+        ..Default::default()
+    };
+    PlanSimulation {
+        previews,
+        ..simulate_plan(&executable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::RiskLevel;
+
+    #[test]
+    fn test_simulate_plan_sums_upload_bytes_per_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, len: usize| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, vec![0u8; len]).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let offload = |path: String, provider: &str| Action::Offload {
+            path,
+            provider: provider.into(),
+            account_id: "acct".into(),
+            target_path: "/backup".into(),
+        };
+        let plan = CleanupPlan {
+            actions: vec![
+                offload(file("a", 10), "dropbox"),
+                offload(file("b", 5), "dropbox"),
+                offload(file("c", 7), "google_drive"),
+                offload(
+                    dir.path().join("gone").to_string_lossy().into_owned(),
+                    "dropbox",
+                ),
+                Action::Delete { path: file("d", 1) },
+            ],
+            ..Default::default()
+        };
+
+        let simulation = simulate_plan(&plan);
+        assert_eq!(simulation.offload_count, 4);
+        assert_eq!(simulation.delete_count, 1);
+        assert_eq!(simulation.upload_bytes["dropbox"], 15);
+        assert_eq!(simulation.upload_bytes["google_drive"], 7);
+        assert_eq!(simulation.missing.len(), 1);
+    }
+
+    #[test]
+    fn test_simulate_plan_reports_vanished_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = dir.path().join("kept");
+        std::fs::create_dir(&kept).unwrap();
+        let path = |p: &std::path::Path| p.to_string_lossy().into_owned();
+        let gone = path(&dir.path().join("gone.log"));
+        let moved = path(&dir.path().join("moved.iso"));
+        let plan = CleanupPlan {
+            actions: vec![
+                Action::Delete { path: path(&kept) },
+                Action::Delete { path: gone.clone() },
+                Action::Move {
+                    from: moved.clone(),
+                    to: path(&dir.path().join("archive")),
+                },
+                Action::EmptyTrash { path: path(&kept) },
+            ],
+            ..Default::default()
+        };
+
+        let simulation = simulate_plan(&plan);
+        assert_eq!(simulation.delete_count, 3);
+        assert_eq!(simulation.move_count, 1);
+        assert_eq!(simulation.missing, vec![gone, moved]);
+    }
+
+    #[test]
+    fn test_simulate_planned_reports_resolved_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"x").unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let delete = |path: String, risk: RiskLevel| PlannedAction {
+            action: Action::Delete { path },
+            bytes: 1,
+            risk,
+            reason: String::new(),
+            scanned: None,
+        };
+        let actions = vec![
+            delete(file("cache.bin"), RiskLevel::Low),
+            delete(file("old.zip"), RiskLevel::Medium),
+            delete(file("pagefile.sys"), RiskLevel::High),
+        ];
+        let policy = ExecutionPolicy {
+            fast_mode: true,
+            ..Default::default()
+        };
+
+        let simulation = simulate_planned(&actions, &policy);
+        let modes: Vec<_> = simulation.previews.iter().map(|p| p.mode).collect();
+        assert_eq!(
+            modes,
+            [
+                ExecutionMode::Permanent,
+                ExecutionMode::Trash,
+                ExecutionMode::Skip
+            ]
+        );
+        assert_eq!(simulation.previews[2].path, actions[2].action.source_path());
+        // 跳过的高风险动作不计入将要执行的删除
+        assert_eq!(simulation.delete_count, 2);
+    }
+}
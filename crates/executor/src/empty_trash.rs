@@ -0,0 +1,256 @@
+//! 清空用户废纸篓。
+//!
+//! XDG 布局（Linux `~/.local/share/Trash`）下，`files/` 中的每个条目在 `info/` 中有同名的
+//! `<名称>.trashinfo` 记录原路径：先删除条目，成功后再删除其 `.trashinfo`，删除失败的条目保留记录，
+//! 仍可从文件管理器中恢复；随后清理没有对应条目的孤立记录、`expunged/` 与 `directorysizes` 缓存。
+//! macOS `~/.Trash` 与按卷存放的回收站（Windows `$Recycle.Bin`、macOS 外接卷 `.Trashes`）没有这样的记录，
+//! 直接删除其中的所有条目；其他用户的条目通常无权删除，记为跳过。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::DiskAnalyzerError;
+
+use crate::cleaners::{skip_reason, CleanReport, CleanedFile, SkippedFile};
+
+/// 清空废纸篓报告中的 `cleaner` 字段
+pub const EMPTY_TRASH_ID: &str = "empty_trash";
+
+const TRASHINFO_EXTENSION: &str = "trashinfo";
+
+/// 清空废纸篓 `trash_dir`；`dry_run` 时只统计将要删除的条目。
+/// 报告中每个条目的字节数包含其 `.trashinfo`
+pub fn empty_trash(trash_dir: &Path, dry_run: bool) -> Result<CleanReport, DiskAnalyzerError> {
+    empty_trash_with(trash_dir, dry_run, remove_entry)
+}
+
+/// 是否为废纸篓目录：`Trash`（XDG 家目录废纸篓）、`.Trash`（macOS）、`.Trash-<uid>`（XDG 卷废纸篓）、
+/// `.Trashes`（macOS 外接卷）或 `$Recycle.Bin`（Windows 回收站，大小写不敏感）
+pub fn is_trash_dir(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+        matches!(n, "Trash" | ".Trash" | ".Trashes")
+            || n.starts_with(".Trash-")
+            || n.eq_ignore_ascii_case("$Recycle.Bin")
+    })
+}
+
+fn empty_trash_with(
+    trash_dir: &Path,
+    dry_run: bool,
+    mut remove: impl FnMut(&Path) -> io::Result<()>,
+) -> Result<CleanReport, DiskAnalyzerError> {
+    if !trash_dir.is_dir() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "目录不存在: {}",
+            trash_dir.display()
+        )));
+    }
+    if !is_trash_dir(trash_dir) {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "不是废纸篓目录: {}",
+            trash_dir.display()
+        )));
+    }
+
+    let mut report = CleanReport {
+        cleaner: EMPTY_TRASH_ID.to_string(),
+        root: trash_dir.display().to_string(),
+        dry_run,
+        files: Vec::new(),
+        bytes: 0,
+        skipped: Vec::new(),
+        notes: Vec::new(),
+    };
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    let xdg = files_dir.is_dir();
+    let content_dir = if xdg { &files_dir } else { trash_dir };
+
+    for entry in list_dir(content_dir) {
+        let info = xdg.then(|| info_path(&info_dir, &entry)).flatten();
+        let bytes = entry_size(&entry) + info.as_deref().map_or(0, entry_size);
+        let outcome = if dry_run {
+            Ok(())
+        } else {
+            remove(&entry).and_then(|()| match &info {
+                Some(info) if info.exists() => remove(info),
+                _ => Ok(()),
+            })
+        };
+        match outcome {
+            Ok(()) => {
+                report.bytes = report.bytes.saturating_add(bytes);
+                report.files.push(CleanedFile {
+                    path: entry.display().to_string(),
+                    bytes,
+                });
+            }
+            Err(e) => report.skipped.push(SkippedFile {
+                path: entry.display().to_string(),
+                reason: skip_reason(&e),
+            }),
+        }
+    }
+
+    if xdg && !dry_run {
+        // 条目已不存在的孤立记录
+        for info in list_dir(&info_dir) {
+            let orphan = info
+                .file_stem()
+                .is_some_and(|stem| fs::symlink_metadata(files_dir.join(stem)).is_err());
+            if orphan && info.extension().is_some_and(|e| e == TRASHINFO_EXTENSION) {
+                let _ = remove(&info);
+            }
+        }
+        for leftover in list_dir(&trash_dir.join("expunged")) {
+            let _ = remove(&leftover);
+        }
+        let _ = fs::remove_file(trash_dir.join("directorysizes"));
+    }
+
+    if !report.skipped.is_empty() {
+        report.notes.push(format!(
+            "已跳过 {} 个无法删除的条目，它们仍保留在废纸篓中",
+            report.skipped.len()
+        ));
+    }
+    Ok(report)
+}
+
+/// `files/` 中条目对应的 `.trashinfo` 路径
+fn info_path(info_dir: &Path, entry: &Path) -> Option<PathBuf> {
+    let mut name = entry.file_name()?.to_os_string();
+    name.push(".");
+    name.push(TRASHINFO_EXTENSION);
+    Some(info_dir.join(name))
+}
+
+/// 目录下的条目，按路径排序；目录不存在时为空
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// 条目占用的字节数（不跟随符号链接）
+fn entry_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => list_dir(path).iter().map(|p| entry_size(p)).sum(),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+fn remove_entry(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaners::SkipReason;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![1u8; len]).unwrap();
+    }
+
+    /// XDG 废纸篓：两个条目及其记录、一个孤立记录与 directorysizes 缓存
+    fn xdg_trash(root: &Path) -> PathBuf {
+        let trash = root.join(".local").join("share").join("Trash");
+        write(&trash.join("files").join("old.iso"), 3000);
+        write(&trash.join("files").join("photos").join("a.jpg"), 500);
+        write(&trash.join("info").join("old.iso.trashinfo"), 60);
+        write(&trash.join("info").join("photos.trashinfo"), 40);
+        write(&trash.join("info").join("gone.txt.trashinfo"), 30);
+        write(&trash.join("directorysizes"), 20);
+        trash
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        list_dir(dir)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_dry_run_measures_without_removing() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = xdg_trash(dir.path());
+        let report = empty_trash(&trash, true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.bytes, 3000 + 60 + 500 + 40);
+        assert_eq!(names(&trash.join("files")), ["old.iso", "photos"]);
+        assert_eq!(names(&trash.join("info")).len(), 3);
+    }
+
+    #[test]
+    fn test_purge_removes_files_with_their_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = xdg_trash(dir.path());
+        let report = empty_trash(&trash, false).unwrap();
+        assert_eq!(report.cleaner, EMPTY_TRASH_ID);
+        assert_eq!(report.bytes, 3600);
+        assert!(report.skipped.is_empty());
+        assert!(names(&trash.join("files")).is_empty());
+        assert!(names(&trash.join("info")).is_empty());
+        assert!(!trash.join("directorysizes").exists());
+        // 废纸篓目录结构本身保留
+        assert!(trash.join("files").is_dir() && trash.join("info").is_dir());
+    }
+
+    #[test]
+    fn test_failed_entry_keeps_its_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = xdg_trash(dir.path());
+        let report = empty_trash_with(&trash, false, |path| {
+            if path.ends_with("old.iso") {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            } else {
+                remove_entry(path)
+            }
+        })
+        .unwrap();
+        assert_eq!(report.bytes, 540);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, SkipReason::PermissionDenied);
+        assert_eq!(report.notes.len(), 1);
+        assert_eq!(names(&trash.join("files")), ["old.iso"]);
+        assert_eq!(names(&trash.join("info")), ["old.iso.trashinfo"]);
+    }
+
+    #[test]
+    fn test_macos_trash_and_non_trash_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join(".Trash");
+        write(&trash.join("a.dmg"), 700);
+        write(&trash.join("Folder").join("b.txt"), 30);
+        let report = empty_trash(&trash, false).unwrap();
+        assert_eq!(report.bytes, 730);
+        assert!(names(&trash).is_empty());
+
+        // 回收站按用户 SID 分目录存放，整体清空
+        let recycle_bin = dir.path().join("$RECYCLE.BIN");
+        write(&recycle_bin.join("S-1-5-21-1000").join("$RABC123.iso"), 400);
+        assert_eq!(empty_trash(&recycle_bin, false).unwrap().bytes, 400);
+        assert!(names(&recycle_bin).is_empty());
+
+        let documents = dir.path().join("Documents");
+        write(&documents.join("keep.txt"), 5);
+        assert!(empty_trash(&documents, false).is_err());
+        assert!(documents.join("keep.txt").exists());
+        assert!(empty_trash(&dir.path().join("Trash"), false).is_err());
+    }
+}
@@ -0,0 +1,1124 @@
+//! 计划执行日志与中断后的续做。
+//!
+//! 每次执行在日志目录下写一个 `<execution_id>.jsonl`：开头记录全部动作及解析出的执行方式，
+//! 每完成一个动作追加一行，全部结束后追加完成标记。进程崩溃或被强制关闭时日志没有完成标记，
+//! 重启后对照文件系统核对每个未记录完成的动作（已完成 / 进行到一半 / 未开始），
+//! 再从第一个未完成的动作继续，逐个动作的执行方式与首次执行相同。
+//!
+//! 删除动作执行前按配置为小文件做快照（见 [`crate::snapshots`]），快照存放在 `<execution_id>/` 子目录下，
+//! 日志中记录每个动作的快照，[`rollback_execution`] 据此恢复已删除的文件。
+//!
+//! 每个动作执行前按 [`crate::target_check`] 核对目标是否在扫描后改变，已改变的动作跳过并记入日志，
+//! 续做时同样跳过；`ignore_changes` 时不核对。
+
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use ai_disk_common::{
+    metrics, DiskAnalyzerError, ExecutionMode, SnapshotConfig, METRIC_EXECUTOR_ACTIONS,
+};
+use ai_disk_domain::{Action, ChangedTarget, PlannedAction};
+use serde::{Deserialize, Serialize};
+
+use crate::r#move::{complete_move, rollback_move};
+use crate::snapshots::SnapshotBudget;
+use crate::target_check::{checked_path, target_change};
+
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+/// 执行日志中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEntry {
+    Started {
+        execution_id: String,
+        started_at: u64,
+        actions: Vec<PlannedAction>,
+        modes: Vec<ExecutionMode>,
+    },
+    /// 删除动作执行前做的快照，路径相对该次执行的目录
+    ActionSnapshot {
+        index: usize,
+        snapshot: String,
+        bytes: u64,
+    },
+    ActionDone {
+        index: usize,
+        freed: u64,
+    },
+    /// 续做时放弃的动作（回滚了进行到一半的移动）
+    ActionAbandoned {
+        index: usize,
+    },
+    /// 目标在扫描后已改变而跳过的动作
+    ActionChanged {
+        index: usize,
+        reason: String,
+    },
+    Completed {
+        finished_at: u64,
+    },
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 单个动作相对于文件系统的进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActionProgress {
+    Done,
+    /// 跨卷移动复制到一半
+    Partial,
+    NotStarted,
+    /// 执行方式为 Skip，目标已改变，或续做时被放弃
+    Skipped,
+}
+
+/// 需要执行的一个动作
+pub struct ExecutionStep<'a> {
+    pub index: usize,
+    pub planned: &'a PlannedAction,
+    pub mode: ExecutionMode,
+    /// 移动动作跨卷时使用的移动清单路径
+    pub move_manifest: PathBuf,
+}
+
+/// 逐个动作的执行实现，返回释放的字节数
+pub trait ActionExecutor {
+    type Error: From<DiskAnalyzerError>;
+
+    fn execute(
+        &mut self,
+        step: &ExecutionStep<'_>,
+    ) -> impl Future<Output = Result<u64, Self::Error>> + Send;
+}
+
+/// 正在写入的执行日志
+pub struct ExecutionJournal {
+    dir: PathBuf,
+    execution_id: String,
+    file: File,
+}
+
+impl ExecutionJournal {
+    /// 新建一次执行的日志
+    pub fn create(
+        dir: &Path,
+        execution_id: &str,
+        actions: &[PlannedAction],
+        modes: &[ExecutionMode],
+    ) -> Result<Self, DiskAnalyzerError> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(journal_path(dir, execution_id))?;
+        let mut journal = Self {
+            dir: dir.to_path_buf(),
+            execution_id: execution_id.to_string(),
+            file,
+        };
+        journal.append(&JournalEntry::Started {
+            execution_id: execution_id.to_string(),
+            started_at: now_secs(),
+            actions: actions.to_vec(),
+            modes: modes.to_vec(),
+        })?;
+        Ok(journal)
+    }
+
+    fn open(dir: &Path, execution_id: &str) -> Result<Self, DiskAnalyzerError> {
+        let file = OpenOptions::new()
+            .append(true)
+            .open(journal_path(dir, execution_id))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            execution_id: execution_id.to_string(),
+            file,
+        })
+    }
+
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    /// 追加一行并落盘：崩溃后日志中的每一行都对应已真实发生的进度
+    fn append(&mut self, entry: &JournalEntry) -> Result<(), DiskAnalyzerError> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| DiskAnalyzerError::Config(format!("序列化执行日志失败: {}", e)))?;
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn move_manifest(&self, index: usize) -> PathBuf {
+        move_manifest_path(&self.dir, &self.execution_id, index)
+    }
+
+    /// 删除动作执行前按预算做快照并记入日志；快照失败只记录日志，不阻止删除
+    fn snapshot(
+        &mut self,
+        index: usize,
+        planned: &PlannedAction,
+        budget: &mut SnapshotBudget,
+    ) -> Result<(), DiskAnalyzerError> {
+        let Action::Delete { path } = &planned.action else {
+            return Ok(());
+        };
+        let execution_dir = execution_dir(&self.dir, &self.execution_id);
+        match budget.take(index, Path::new(path), &execution_dir) {
+            Ok(Some((snapshot, bytes))) => self.append(&JournalEntry::ActionSnapshot {
+                index,
+                snapshot: snapshot.to_string_lossy().into_owned(),
+                bytes,
+            }),
+            Ok(None) => Ok(()),
+            Err(e) => {
+                tracing::warn!(path, error = %e, "执行前快照失败");
+                Ok(())
+            }
+        }
+    }
+}
+
+fn journal_path(dir: &Path, execution_id: &str) -> PathBuf {
+    dir.join(format!("{}.{}", execution_id, JOURNAL_EXTENSION))
+}
+
+/// 一次执行的附属文件（快照）所在目录
+fn execution_dir(dir: &Path, execution_id: &str) -> PathBuf {
+    dir.join(execution_id)
+}
+
+fn move_manifest_path(dir: &Path, execution_id: &str, index: usize) -> PathBuf {
+    dir.join(format!("{}.move-{}.json", execution_id, index))
+}
+
+/// 从日志中读出的一次执行
+#[derive(Debug, Clone)]
+struct JournalState {
+    execution_id: String,
+    started_at: u64,
+    actions: Vec<PlannedAction>,
+    modes: Vec<ExecutionMode>,
+    done: Vec<bool>,
+    abandoned: Vec<bool>,
+    /// (动作下标, 快照相对执行目录的路径, 字节数)，按记录顺序
+    snapshots: Vec<(usize, String, u64)>,
+    completed: bool,
+}
+
+/// 读取日志；崩溃时写了一半的最后一行忽略
+fn read_journal(path: &Path) -> Result<JournalState, DiskAnalyzerError> {
+    let text = fs::read_to_string(path)?;
+    let mut entries = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok());
+    let Some(JournalEntry::Started {
+        execution_id,
+        started_at,
+        actions,
+        modes,
+    }) = entries.next()
+    else {
+        return Err(DiskAnalyzerError::Config(format!(
+            "执行日志缺少开头记录: {}",
+            path.display()
+        )));
+    };
+    if modes.len() != actions.len() {
+        return Err(DiskAnalyzerError::Config(format!(
+            "执行日志中的动作与执行方式数量不一致: {}",
+            path.display()
+        )));
+    }
+    let mut state = JournalState {
+        execution_id,
+        started_at,
+        done: vec![false; actions.len()],
+        abandoned: vec![false; actions.len()],
+        actions,
+        modes,
+        snapshots: Vec::new(),
+        completed: false,
+    };
+    for entry in entries {
+        match entry {
+            JournalEntry::ActionDone { index, .. } => {
+                if let Some(done) = state.done.get_mut(index) {
+                    *done = true;
+                }
+            }
+            JournalEntry::ActionAbandoned { index } | JournalEntry::ActionChanged { index, .. } => {
+                if let Some(abandoned) = state.abandoned.get_mut(index) {
+                    *abandoned = true;
+                }
+            }
+            JournalEntry::ActionSnapshot {
+                index,
+                snapshot,
+                bytes,
+            } => state.snapshots.push((index, snapshot, bytes)),
+            JournalEntry::Completed { .. } => state.completed = true,
+            JournalEntry::Started { .. } => {}
+        }
+    }
+    Ok(state)
+}
+
+/// 对照文件系统判断未记录完成的动作进行到哪一步
+fn reconcile_action(
+    planned: &PlannedAction,
+    mode: ExecutionMode,
+    recorded_done: bool,
+    move_manifest: &Path,
+) -> ActionProgress {
+    if mode == ExecutionMode::Skip {
+        return ActionProgress::Skipped;
+    }
+    if recorded_done {
+        return ActionProgress::Done;
+    }
+    let exists = |path: &str| fs::symlink_metadata(path).is_ok();
+    match &planned.action {
+        // 删除与转存完成后本地路径不再存在
+        Action::Delete { path } | Action::Offload { path, .. } => {
+            if exists(path) {
+                ActionProgress::NotStarted
+            } else {
+                ActionProgress::Done
+            }
+        }
+        Action::Move { from, to } => {
+            if move_manifest.exists() {
+                ActionProgress::Partial
+            } else if !exists(from) && exists(to) {
+                ActionProgress::Done
+            } else {
+                ActionProgress::NotStarted
+            }
+        }
+        // 清空废纸篓与压缩虚拟磁盘可以重复执行，无法也无需判断是否做过
+        Action::EmptyTrash { .. } | Action::CompactVhd { .. } => ActionProgress::NotStarted,
+    }
+}
+
+/// 没有完成标记的一次执行及其各动作的进度
+#[derive(Debug, Clone, Serialize)]
+pub struct InterruptedExecution {
+    pub execution_id: String,
+    /// Unix 秒
+    pub started_at: u64,
+    pub actions: Vec<PlannedAction>,
+    pub progress: Vec<ActionProgress>,
+    /// 续做时的第一个动作；全部已完成时为 None
+    pub resume_from: Option<usize>,
+}
+
+fn inspect(dir: &Path, state: JournalState) -> InterruptedExecution {
+    let progress: Vec<ActionProgress> = (0..state.actions.len())
+        .map(|i| {
+            if state.abandoned[i] {
+                return ActionProgress::Skipped;
+            }
+            reconcile_action(
+                &state.actions[i],
+                state.modes[i],
+                state.done[i],
+                &move_manifest_path(dir, &state.execution_id, i),
+            )
+        })
+        .collect();
+    let resume_from = progress
+        .iter()
+        .position(|p| matches!(p, ActionProgress::Partial | ActionProgress::NotStarted));
+    InterruptedExecution {
+        execution_id: state.execution_id,
+        started_at: state.started_at,
+        actions: state.actions,
+        progress,
+        resume_from,
+    }
+}
+
+/// 列出日志目录中没有完成标记的执行，按开始时间排序；无法解析的日志跳过
+pub fn list_interrupted_executions(
+    dir: &Path,
+) -> Result<Vec<InterruptedExecution>, DiskAnalyzerError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut interrupted = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+            continue;
+        }
+        match read_journal(&path) {
+            Ok(state) if !state.completed => interrupted.push(inspect(dir, state)),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "跳过无法读取的执行日志"),
+        }
+    }
+    interrupted
+        .sort_by(|a, b| (a.started_at, &a.execution_id).cmp(&(b.started_at, &b.execution_id)));
+    Ok(interrupted)
+}
+
+/// 执行结果汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExecutionOutcome {
+    /// 本次执行完成的动作数（续做时不含之前已完成的）
+    pub executed: usize,
+    /// 执行方式为 Skip 或被放弃的动作数
+    pub skipped: usize,
+    pub freed: u64,
+    /// 本次执行中因目标在扫描后已改变而跳过的动作，不计入 `skipped`
+    pub changed: Vec<ChangedTarget>,
+}
+
+/// 按 progress 依次执行未完成的动作，未开始的动作先核对目标（`ignore_changes` 时不核对）；
+/// 任一动作出错即停止，日志保持未完成，之后可以续做
+async fn run_steps<E: ActionExecutor>(
+    journal: &mut ExecutionJournal,
+    actions: &[PlannedAction],
+    modes: &[ExecutionMode],
+    progress: &[ActionProgress],
+    ignore_changes: bool,
+    budget: &mut SnapshotBudget,
+    executor: &mut E,
+) -> Result<ExecutionOutcome, E::Error> {
+    let mut outcome = ExecutionOutcome::default();
+    for (index, planned) in actions.iter().enumerate() {
+        match progress[index] {
+            ActionProgress::Done => continue,
+            ActionProgress::Skipped => {
+                outcome.skipped += 1;
+                metrics().increment(METRIC_EXECUTOR_ACTIONS, "skipped", 1);
+                continue;
+            }
+            ActionProgress::NotStarted if !ignore_changes => {
+                if let Some(reason) = target_change(actions, index) {
+                    let path = checked_path(&planned.action).unwrap_or_default();
+                    tracing::warn!(path, %reason, "目标在扫描后已改变，跳过该动作");
+                    journal.append(&JournalEntry::ActionChanged {
+                        index,
+                        reason: reason.clone(),
+                    })?;
+                    metrics().increment(METRIC_EXECUTOR_ACTIONS, "changed", 1);
+                    outcome.changed.push(ChangedTarget {
+                        index,
+                        path: path.to_string(),
+                        reason,
+                    });
+                    continue;
+                }
+            }
+            ActionProgress::Partial | ActionProgress::NotStarted => {}
+        }
+        journal.snapshot(index, planned, budget)?;
+        let step = ExecutionStep {
+            index,
+            planned,
+            mode: modes[index],
+            move_manifest: journal.move_manifest(index),
+        };
+        let result = executor.execute(&step).await;
+        let label = if result.is_ok() {
+            "succeeded"
+        } else {
+            "failed"
+        };
+        metrics().increment(METRIC_EXECUTOR_ACTIONS, label, 1);
+        let freed = result?;
+        journal.append(&JournalEntry::ActionDone { index, freed })?;
+        outcome.executed += 1;
+        outcome.freed += freed;
+    }
+    journal.append(&JournalEntry::Completed {
+        finished_at: now_secs(),
+    })?;
+    Ok(outcome)
+}
+
+/// 执行计划并写日志；modes 与 actions 一一对应（通常由执行策略解析得到），
+/// 删除动作执行前按 `snapshots` 做快照。目标在扫描后已改变的动作跳过，`ignore_changes` 时照常执行
+pub async fn run_execution<E: ActionExecutor>(
+    dir: &Path,
+    execution_id: &str,
+    actions: &[PlannedAction],
+    modes: &[ExecutionMode],
+    snapshots: &SnapshotConfig,
+    ignore_changes: bool,
+    executor: &mut E,
+) -> Result<ExecutionOutcome, E::Error> {
+    if modes.len() != actions.len() {
+        return Err(DiskAnalyzerError::Config("动作与执行方式数量不一致".to_string()).into());
+    }
+    let mut journal = ExecutionJournal::create(dir, execution_id, actions, modes)?;
+    let progress: Vec<ActionProgress> = modes
+        .iter()
+        .map(|mode| {
+            if *mode == ExecutionMode::Skip {
+                ActionProgress::Skipped
+            } else {
+                ActionProgress::NotStarted
+            }
+        })
+        .collect();
+    let mut budget = SnapshotBudget::new(snapshots, &[]);
+    run_steps(
+        &mut journal,
+        actions,
+        modes,
+        &progress,
+        ignore_changes,
+        &mut budget,
+        executor,
+    )
+    .await
+}
+
+/// 从第一个未完成的动作继续中断的执行。进行到一半的移动按 complete_partial_moves 补完，
+/// 或回滚并放弃该动作；文件系统上已完成但未来得及记录的动作补记为完成。
+/// 之前的快照计入本次的快照预算；未开始的动作与首次执行一样先核对目标
+pub async fn resume_execution<E: ActionExecutor>(
+    dir: &Path,
+    execution_id: &str,
+    complete_partial_moves: bool,
+    snapshots: &SnapshotConfig,
+    ignore_changes: bool,
+    executor: &mut E,
+) -> Result<ExecutionOutcome, E::Error> {
+    let state = read_journal(&journal_path(dir, execution_id))?;
+    if state.completed {
+        return Err(
+            DiskAnalyzerError::Config(format!("执行 {} 已完成，无需续做", execution_id)).into(),
+        );
+    }
+    let (actions, modes, recorded) = (
+        state.actions.clone(),
+        state.modes.clone(),
+        state.done.clone(),
+    );
+    let mut budget = SnapshotBudget::new(snapshots, &state.snapshots);
+    let mut progress = inspect(dir, state).progress;
+    let mut journal = ExecutionJournal::open(dir, execution_id)?;
+    for (index, p) in progress.iter_mut().enumerate() {
+        match p {
+            ActionProgress::Done if !recorded[index] => {
+                journal.append(&JournalEntry::ActionDone { index, freed: 0 })?;
+            }
+            ActionProgress::Partial => {
+                let manifest = journal.move_manifest(index);
+                if complete_partial_moves {
+                    complete_move(&manifest)?;
+                    journal.append(&JournalEntry::ActionDone {
+                        index,
+                        freed: actions[index].bytes,
+                    })?;
+                    *p = ActionProgress::Done;
+                } else if rollback_move(&manifest)? {
+                    journal.append(&JournalEntry::ActionAbandoned { index })?;
+                    *p = ActionProgress::Skipped;
+                } else {
+                    // 复制已完成，源可能已删除一部分，回滚不可能，已补完移动
+                    journal.append(&JournalEntry::ActionDone { index, freed: 0 })?;
+                    *p = ActionProgress::Done;
+                }
+            }
+            _ => {}
+        }
+    }
+    run_steps(
+        &mut journal,
+        &actions,
+        &modes,
+        &progress,
+        ignore_changes,
+        &mut budget,
+        executor,
+    )
+    .await
+}
+
+/// 回滚结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RollbackOutcome {
+    /// 由快照恢复的文件
+    pub restored: Vec<String>,
+    /// 原位置已有文件（如已从回收站还原）而未恢复的路径
+    pub conflicts: Vec<String>,
+}
+
+/// 用执行前快照恢复一次执行删除的文件，已完成或中断的执行均可；原位置已有文件时不覆盖。
+/// 没有快照的动作（大文件、媒体文件、移动等）不在回滚范围内
+pub fn rollback_execution(
+    dir: &Path,
+    execution_id: &str,
+) -> Result<RollbackOutcome, DiskAnalyzerError> {
+    let state = read_journal(&journal_path(dir, execution_id))?;
+    let execution_dir = execution_dir(dir, execution_id);
+    let mut outcome = RollbackOutcome::default();
+    for (index, snapshot, _) in state.snapshots.iter().rev() {
+        let Some(Action::Delete { path }) = state.actions.get(*index).map(|p| &p.action) else {
+            continue;
+        };
+        let original = Path::new(path);
+        if fs::symlink_metadata(original).is_ok() {
+            outcome.conflicts.push(path.clone());
+            continue;
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(execution_dir.join(snapshot), original)?;
+        outcome.restored.push(path.clone());
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#move::{copy_then_remove, move_path, MoveManifest};
+    use ai_disk_domain::{RiskLevel, TargetState};
+    use std::collections::BTreeMap;
+
+    /// 直接操作文件系统的执行器；fail_at 处注入错误模拟中途崩溃，
+    /// partial_move_at 处模拟跨卷移动复制到一半时崩溃
+    struct FsExecutor {
+        fail_at: Option<usize>,
+        partial_move_at: Option<usize>,
+        executed: Vec<usize>,
+    }
+
+    impl FsExecutor {
+        fn new() -> Self {
+            Self {
+                fail_at: None,
+                partial_move_at: None,
+                executed: Vec::new(),
+            }
+        }
+    }
+
+    impl ActionExecutor for FsExecutor {
+        type Error = DiskAnalyzerError;
+
+        async fn execute(&mut self, step: &ExecutionStep<'_>) -> Result<u64, DiskAnalyzerError> {
+            if self.fail_at == Some(step.index) {
+                return Err(DiskAnalyzerError::Io(std::io::Error::other("injected")));
+            }
+            match &step.planned.action {
+                Action::Delete { path } => fs::remove_file(path)?,
+                Action::Move { from, to } => {
+                    if self.partial_move_at == Some(step.index) {
+                        // 写清单、只复制一个文件后「崩溃」
+                        let manifest = MoveManifest {
+                            from: from.clone(),
+                            to: to.clone(),
+                            copied: false,
+                        };
+                        fs::write(
+                            &step.move_manifest,
+                            serde_json::to_string(&manifest).unwrap(),
+                        )?;
+                        fs::create_dir_all(to)?;
+                        fs::copy(Path::new(from).join("1.txt"), Path::new(to).join("1.txt"))?;
+                        return Err(DiskAnalyzerError::Io(std::io::Error::other("crash")));
+                    }
+                    if step.index % 2 == 1 {
+                        copy_then_remove(Path::new(from), Path::new(to), &step.move_manifest)?;
+                    } else {
+                        move_path(Path::new(from), Path::new(to), &step.move_manifest)?;
+                    }
+                }
+                Action::Offload { .. } | Action::EmptyTrash { .. } | Action::CompactVhd { .. } => {}
+            }
+            self.executed.push(step.index);
+            Ok(step.planned.bytes)
+        }
+    }
+
+    fn planned(action: Action) -> PlannedAction {
+        PlannedAction {
+            action,
+            bytes: 10,
+            risk: RiskLevel::Low,
+            reason: String::new(),
+            scanned: None,
+        }
+    }
+
+    /// 在 root 下建立测试文件并返回计划：删除 a、移动 photos、删除 b、移动 docs
+    fn setup(root: &Path) -> Vec<PlannedAction> {
+        let data = root.join("data");
+        for dir in ["photos", "docs"] {
+            fs::create_dir_all(data.join(dir)).unwrap();
+            fs::write(data.join(dir).join("1.txt"), dir).unwrap();
+            fs::write(data.join(dir).join("2.txt"), b"22").unwrap();
+        }
+        fs::write(data.join("a.tmp"), b"a").unwrap();
+        fs::write(data.join("b.tmp"), b"b").unwrap();
+        let p = |name: &str| data.join(name).to_string_lossy().into_owned();
+        let backup = |name: &str| {
+            root.join("backup")
+                .join(name)
+                .to_string_lossy()
+                .into_owned()
+        };
+        vec![
+            planned(Action::Delete { path: p("a.tmp") }),
+            planned(Action::Move {
+                from: p("photos"),
+                to: backup("photos"),
+            }),
+            planned(Action::Delete { path: p("b.tmp") }),
+            planned(Action::Move {
+                from: p("docs"),
+                to: backup("docs"),
+            }),
+        ]
+    }
+
+    /// 相对路径 -> 文件内容，不含执行日志目录
+    fn snapshot(root: &Path) -> BTreeMap<String, Vec<u8>> {
+        fn walk(root: &Path, dir: &Path, out: &mut BTreeMap<String, Vec<u8>>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                let rel = path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                if rel == "journal" {
+                    continue;
+                }
+                if path.is_dir() {
+                    out.insert(format!("{}/", rel), Vec::new());
+                    walk(root, &path, out);
+                } else {
+                    out.insert(rel, fs::read(&path).unwrap());
+                }
+            }
+        }
+        let mut out = BTreeMap::new();
+        walk(root, root, &mut out);
+        out
+    }
+
+    fn modes(n: usize) -> Vec<ExecutionMode> {
+        vec![ExecutionMode::Permanent; n]
+    }
+
+    fn clean_run() -> BTreeMap<String, Vec<u8>> {
+        let dir = tempfile::tempdir().unwrap();
+        let actions = setup(dir.path());
+        let journal = dir.path().join("journal");
+        let outcome = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-clean",
+            &actions,
+            &modes(actions.len()),
+            &SnapshotConfig::default(),
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        assert_eq!(outcome.executed, 4);
+        assert_eq!(outcome.freed, 40);
+        assert!(list_interrupted_executions(&journal).unwrap().is_empty());
+        snapshot(dir.path())
+    }
+
+    #[test]
+    fn test_resume_after_injected_error_matches_clean_run() {
+        let expected = clean_run();
+        let dir = tempfile::tempdir().unwrap();
+        let actions = setup(dir.path());
+        let journal = dir.path().join("journal");
+        let mut executor = FsExecutor::new();
+        executor.fail_at = Some(2);
+        assert!(futures::executor::block_on(run_execution(
+            &journal,
+            "exec-1",
+            &actions,
+            &modes(actions.len()),
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ))
+        .is_err());
+
+        let interrupted = list_interrupted_executions(&journal).unwrap();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].execution_id, "exec-1");
+        assert_eq!(
+            interrupted[0].progress,
+            [
+                ActionProgress::Done,
+                ActionProgress::Done,
+                ActionProgress::NotStarted,
+                ActionProgress::NotStarted
+            ]
+        );
+        assert_eq!(interrupted[0].resume_from, Some(2));
+
+        let mut executor = FsExecutor::new();
+        let outcome = futures::executor::block_on(resume_execution(
+            &journal,
+            "exec-1",
+            true,
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ))
+        .unwrap();
+        // 只执行剩下的动作
+        assert_eq!(executor.executed, vec![2, 3]);
+        assert_eq!(outcome.executed, 2);
+        assert!(list_interrupted_executions(&journal).unwrap().is_empty());
+        assert_eq!(snapshot(dir.path()), expected);
+        assert!(futures::executor::block_on(resume_execution(
+            &journal,
+            "exec-1",
+            true,
+            &SnapshotConfig::default(),
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_resume_completes_partial_move() {
+        let expected = clean_run();
+        let dir = tempfile::tempdir().unwrap();
+        let actions = setup(dir.path());
+        let journal = dir.path().join("journal");
+        let mut executor = FsExecutor::new();
+        executor.partial_move_at = Some(1);
+        assert!(futures::executor::block_on(run_execution(
+            &journal,
+            "exec-2",
+            &actions,
+            &modes(actions.len()),
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ))
+        .is_err());
+        // 删除 b.tmp 发生在崩溃之前但未来得及写日志
+        fs::remove_file(dir.path().join("data").join("b.tmp")).unwrap();
+
+        let interrupted = list_interrupted_executions(&journal).unwrap();
+        assert_eq!(
+            interrupted[0].progress,
+            [
+                ActionProgress::Done,
+                ActionProgress::Partial,
+                ActionProgress::Done,
+                ActionProgress::NotStarted
+            ]
+        );
+
+        let mut executor = FsExecutor::new();
+        futures::executor::block_on(resume_execution(
+            &journal,
+            "exec-2",
+            true,
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ))
+        .unwrap();
+        assert_eq!(executor.executed, vec![3]);
+        assert_eq!(snapshot(dir.path()), expected);
+    }
+
+    #[test]
+    fn test_resume_rolls_back_partial_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let actions = setup(dir.path());
+        let journal = dir.path().join("journal");
+        let mut executor = FsExecutor::new();
+        executor.partial_move_at = Some(1);
+        let _ = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-3",
+            &actions,
+            &modes(actions.len()),
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ));
+
+        let outcome = futures::executor::block_on(resume_execution(
+            &journal,
+            "exec-3",
+            false,
+            &SnapshotConfig::default(),
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        assert_eq!(outcome.skipped, 1);
+        let data = dir.path().join("data");
+        // 回滚后 photos 原样留在源位置，其余动作照常完成
+        assert_eq!(fs::read(data.join("photos").join("2.txt")).unwrap(), b"22");
+        assert!(!dir.path().join("backup").join("photos").exists());
+        assert!(dir
+            .path()
+            .join("backup")
+            .join("docs")
+            .join("1.txt")
+            .exists());
+        assert!(!data.join("b.tmp").exists());
+        assert!(list_interrupted_executions(&journal).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_skip_mode_and_torn_last_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let actions = setup(dir.path());
+        let journal = dir.path().join("journal");
+        let mut modes = modes(actions.len());
+        modes[0] = ExecutionMode::Skip;
+        let mut executor = FsExecutor::new();
+        executor.fail_at = Some(1);
+        let _ = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-4",
+            &actions,
+            &modes,
+            &SnapshotConfig::default(),
+            false,
+            &mut executor,
+        ));
+        // 崩溃时写了一半的一行
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal_path(&journal, "exec-4"))
+            .unwrap();
+        write!(file, "{{\"event\":\"action_do").unwrap();
+
+        let interrupted = list_interrupted_executions(&journal).unwrap();
+        assert_eq!(interrupted[0].progress[0], ActionProgress::Skipped);
+        assert_eq!(interrupted[0].resume_from, Some(1));
+        assert!(dir.path().join("data").join("a.tmp").exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_snapshots_of_permanent_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        fs::create_dir_all(data.join("project")).unwrap();
+        let files: [(&str, Vec<u8>); 5] = [
+            (".bashrc", b"export PATH=$HOME/bin:$PATH\n".to_vec()),
+            ("notes.txt", b"do not lose".to_vec()),
+            ("project/config.toml", b"[server]\nport = 8080\n".to_vec()),
+            // 超过大小上限，以及不做快照的类型
+            ("big.log", vec![b'x'; 2048]),
+            ("clip.mp4", b"video".to_vec()),
+        ];
+        let mut actions = Vec::new();
+        for (name, content) in &files {
+            fs::write(data.join(name), content).unwrap();
+            actions.push(planned(Action::Delete {
+                path: data.join(name).to_string_lossy().into_owned(),
+            }));
+        }
+        let journal = dir.path().join("journal");
+        let config = SnapshotConfig {
+            max_file_bytes: 1024,
+            ..SnapshotConfig::default()
+        };
+        futures::executor::block_on(run_execution(
+            &journal,
+            "exec-5",
+            &actions,
+            &modes(actions.len()),
+            &config,
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        for (name, _) in &files {
+            assert!(!data.join(name).exists());
+        }
+        let state = read_journal(&journal_path(&journal, "exec-5")).unwrap();
+        let indexes: Vec<usize> = state.snapshots.iter().map(|(i, _, _)| *i).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+
+        // 回滚前用户已自行恢复了 notes.txt，不覆盖
+        fs::write(data.join("notes.txt"), b"rewritten").unwrap();
+        let outcome = rollback_execution(&journal, "exec-5").unwrap();
+        assert_eq!(outcome.restored.len(), 2);
+        assert_eq!(
+            outcome.conflicts,
+            vec![data.join("notes.txt").to_string_lossy().into_owned()]
+        );
+        for (name, content) in &files[..3] {
+            let expected: &[u8] = if *name == "notes.txt" {
+                b"rewritten"
+            } else {
+                content
+            };
+            assert_eq!(fs::read(data.join(name)).unwrap(), expected);
+        }
+        assert!(!data.join("big.log").exists());
+        assert!(!data.join("clip.mp4").exists());
+    }
+
+    #[test]
+    fn test_snapshot_budget_and_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut actions = Vec::new();
+        for i in 0..4 {
+            let path = dir.path().join(format!("{}.txt", i));
+            fs::write(&path, vec![b'a'; 100]).unwrap();
+            actions.push(planned(Action::Delete {
+                path: path.to_string_lossy().into_owned(),
+            }));
+        }
+        let journal = dir.path().join("journal");
+        let budget = SnapshotConfig {
+            budget_bytes: 350,
+            ..SnapshotConfig::default()
+        };
+        let mut executor = FsExecutor::new();
+        executor.fail_at = Some(1);
+        let _ = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-6",
+            &actions,
+            &modes(actions.len()),
+            &budget,
+            false,
+            &mut executor,
+        ));
+        // 续做时之前的快照计入预算，已做过快照的动作不再重复：只剩一个文件的额度
+        futures::executor::block_on(resume_execution(
+            &journal,
+            "exec-6",
+            true,
+            &budget,
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        let state = read_journal(&journal_path(&journal, "exec-6")).unwrap();
+        let indexes: Vec<usize> = state.snapshots.iter().map(|(i, _, _)| *i).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+
+        let disabled = SnapshotConfig {
+            enabled: false,
+            ..SnapshotConfig::default()
+        };
+        let path = dir.path().join("x.txt");
+        fs::write(&path, b"x").unwrap();
+        let actions = vec![planned(Action::Delete {
+            path: path.to_string_lossy().into_owned(),
+        })];
+        futures::executor::block_on(run_execution(
+            &journal,
+            "exec-7",
+            &actions,
+            &modes(1),
+            &disabled,
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        assert!(read_journal(&journal_path(&journal, "exec-7"))
+            .unwrap()
+            .snapshots
+            .is_empty());
+        assert!(!journal.join("exec-7").exists());
+    }
+
+    /// 按文件当前的大小与修改时间记录扫描时状态，模拟计划校验
+    fn scanned_now(mut planned: PlannedAction) -> PlannedAction {
+        let metadata = fs::metadata(planned.action.source_path()).unwrap();
+        planned.scanned = Some(TargetState {
+            is_dir: false,
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            child_count: None,
+        });
+        planned
+    }
+
+    #[test]
+    fn test_targets_changed_after_planning_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.log"), dir.path().join("b.log"));
+        fs::write(&a, b"old log").unwrap();
+        fs::write(&b, b"old log").unwrap();
+        let actions: Vec<PlannedAction> = [&a, &b]
+            .iter()
+            .map(|path| {
+                scanned_now(planned(Action::Delete {
+                    path: path.to_string_lossy().into_owned(),
+                }))
+            })
+            .collect();
+        // 生成计划后用户在 b.log 中写入了新内容
+        fs::write(&b, b"important new notes").unwrap();
+
+        let journal = dir.path().join("journal");
+        let outcome = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-changed",
+            &actions,
+            &modes(2),
+            &SnapshotConfig::default(),
+            false,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        assert!(!a.exists());
+        assert_eq!(fs::read(&b).unwrap(), b"important new notes");
+        assert_eq!((outcome.executed, outcome.skipped), (1, 0));
+        assert_eq!(
+            outcome.changed,
+            vec![ChangedTarget {
+                index: 1,
+                path: b.to_string_lossy().into_owned(),
+                reason: "大小由 7 字节变为 19 字节".to_string(),
+            }]
+        );
+        // 日志中记为跳过，续做时不会再执行
+        let state = read_journal(&journal_path(&journal, "exec-changed")).unwrap();
+        assert!(state.completed && state.abandoned[1]);
+
+        // 用户确认后忽略改变照常执行
+        let outcome = futures::executor::block_on(run_execution(
+            &journal,
+            "exec-ignored",
+            &actions[1..],
+            &modes(1),
+            &SnapshotConfig::default(),
+            true,
+            &mut FsExecutor::new(),
+        ))
+        .unwrap();
+        assert_eq!(outcome.executed, 1);
+        assert!(outcome.changed.is_empty());
+        assert!(!b.exists());
+    }
+}
@@ -0,0 +1,448 @@
+//! 本地文件哈希（BLAKE3 / SHA-256），供重复文件检测、转存校验与已备份匹配共用。
+//!
+//! 结果缓存在存储根目录下的 [`HASH_CACHE_FILE_NAME`] 中，按（路径, 算法）记录计算时的大小与修改时间；
+//! 再次请求时两者都未变化即直接返回缓存的摘要，不再读取文件，任一变化时重新计算并覆盖。
+//! 多个文件并行计算，并发数按存储介质选取（见 [`hash_concurrency`]），机械硬盘上逐个读取以免来回寻道。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use ai_disk_common::{write_atomic, DiskAnalyzerError};
+use ai_disk_domain::MediaType;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::checksum::to_hex;
+
+/// 哈希缓存文件名，位于存储根目录下
+pub const HASH_CACHE_FILE_NAME: &str = "hash-cache.json";
+/// 缓存最多保留的条目数，超出时丢弃最早计算的条目
+pub const HASH_CACHE_MAX_ENTRIES: usize = 200_000;
+
+/// 每次读取的块大小
+const READ_BLOCK: usize = 1024 * 1024;
+
+/// 文件哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+/// 单个文件的哈希结果；读取失败时 `digest` 为 None、`error` 为原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHash {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    /// 十六进制小写摘要
+    pub digest: Option<String>,
+    pub size: u64,
+    /// 是否命中缓存（未读取文件内容）
+    pub cached: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 按存储介质选取的并发数：机械硬盘逐个读取，SSD 4 个，未知介质 2 个
+pub fn hash_concurrency(media: MediaType) -> usize {
+    match media {
+        MediaType::Hdd => 1,
+        MediaType::Ssd => 4,
+        MediaType::Unknown => 2,
+    }
+}
+
+/// 流式计算摘要，返回十六进制小写字符串
+pub fn hash_reader(mut reader: impl Read, algorithm: HashAlgorithm) -> io::Result<String> {
+    let mut buf = vec![0u8; READ_BLOCK];
+    match algorithm {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            Ok(to_hex(&hasher.finalize()))
+        }
+    }
+}
+
+/// 缓存中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    path: String,
+    algorithm: HashAlgorithm,
+    size: u64,
+    /// 计算时文件的修改时间（Unix 纳秒）
+    modified_ns: u64,
+    digest: String,
+    /// 计算时刻（Unix 秒），超出条目上限时先丢弃最早的
+    hashed_at: u64,
+}
+
+/// 文件当前的大小与修改时间，作为缓存是否有效的依据
+fn file_stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "不能对目录计算哈希",
+        ));
+    }
+    let modified_ns = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    Ok((metadata.len(), modified_ns))
+}
+
+/// 持久化的哈希缓存，可在多个线程间共享
+#[derive(Debug)]
+pub struct HashCache {
+    file: PathBuf,
+    entries: Mutex<HashMap<(String, HashAlgorithm), CacheEntry>>,
+}
+
+impl HashCache {
+    /// 从缓存文件加载；文件不存在或已损坏时从空缓存开始
+    pub fn load(file: PathBuf) -> Self {
+        let entries: Vec<CacheEntry> = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            file,
+            entries: Mutex::new(
+                entries
+                    .into_iter()
+                    .map(|e| ((e.path.clone(), e.algorithm), e))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, HashAlgorithm), CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 原子写回缓存文件；超出 [`HASH_CACHE_MAX_ENTRIES`] 时丢弃最早计算的条目
+    pub fn save(&self) -> Result<(), DiskAnalyzerError> {
+        let mut entries: Vec<CacheEntry> = {
+            let mut map = self.lock();
+            if map.len() > HASH_CACHE_MAX_ENTRIES {
+                let mut by_age: Vec<_> = map
+                    .values()
+                    .map(|e| (e.hashed_at, e.path.clone(), e.algorithm))
+                    .collect();
+                by_age.sort();
+                for (_, path, algorithm) in
+                    by_age.into_iter().take(map.len() - HASH_CACHE_MAX_ENTRIES)
+                {
+                    map.remove(&(path, algorithm));
+                }
+            }
+            map.values().cloned().collect()
+        };
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let json = serde_json::to_vec(&entries)
+            .map_err(|e| DiskAnalyzerError::Config(format!("序列化哈希缓存失败: {}", e)))?;
+        write_atomic(&self.file, &json)?;
+        Ok(())
+    }
+
+    /// 大小与修改时间都未变化时返回缓存的摘要
+    fn lookup(
+        &self,
+        path: &str,
+        algorithm: HashAlgorithm,
+        size: u64,
+        modified_ns: u64,
+    ) -> Option<String> {
+        self.lock()
+            .get(&(path.to_string(), algorithm))
+            .filter(|e| e.size == size && e.modified_ns == modified_ns)
+            .map(|e| e.digest.clone())
+    }
+
+    fn store(
+        &self,
+        path: &str,
+        algorithm: HashAlgorithm,
+        size: u64,
+        modified_ns: u64,
+        digest: &str,
+    ) {
+        let hashed_at = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.lock().insert(
+            (path.to_string(), algorithm),
+            CacheEntry {
+                path: path.to_string(),
+                algorithm,
+                size,
+                modified_ns,
+                digest: digest.to_string(),
+                hashed_at,
+            },
+        );
+    }
+
+    /// 计算单个文件的摘要，命中缓存时不打开文件；`open` 负责打开文件供读取
+    pub fn hash_with<R: Read>(
+        &self,
+        path: &Path,
+        algorithm: HashAlgorithm,
+        open: impl FnOnce(&Path) -> io::Result<R>,
+    ) -> FileHash {
+        let key = path.to_string_lossy().into_owned();
+        let mut result = FileHash {
+            path: key.clone(),
+            algorithm,
+            digest: None,
+            size: 0,
+            cached: false,
+            error: None,
+        };
+        let (size, modified_ns) = match file_stamp(path) {
+            Ok(stamp) => stamp,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        };
+        result.size = size;
+        if let Some(digest) = self.lookup(&key, algorithm, size, modified_ns) {
+            result.digest = Some(digest);
+            result.cached = true;
+            return result;
+        }
+        match open(path).and_then(|reader| hash_reader(reader, algorithm)) {
+            Ok(digest) => {
+                // 读取期间被修改的文件不写入缓存，下次重新计算
+                if file_stamp(path).ok() == Some((size, modified_ns)) {
+                    self.store(&key, algorithm, size, modified_ns, &digest);
+                }
+                result.digest = Some(digest);
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result
+    }
+
+    /// 计算单个文件的摘要
+    pub fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> FileHash {
+        self.hash_with(path, algorithm, |p| File::open(p))
+    }
+}
+
+/// 以 `concurrency` 个线程计算多个文件的摘要，结果按输入顺序返回；
+/// 每完成一个文件调用一次 `on_file(已完成数, 结果)`（调用顺序为完成顺序）
+pub fn hash_files(
+    cache: &HashCache,
+    paths: &[PathBuf],
+    algorithm: HashAlgorithm,
+    concurrency: usize,
+    on_file: &(dyn Fn(usize, &FileHash) + Sync),
+) -> Vec<FileHash> {
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<FileHash>>> = Mutex::new(vec![None; paths.len()]);
+    let workers = concurrency.clamp(1, paths.len().max(1));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(index) else {
+                    break;
+                };
+                let hash = cache.hash(path, algorithm);
+                on_file(done.fetch_add(1, Ordering::Relaxed) + 1, &hash);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(hash);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const ABC_BLAKE3: &str = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+    const EMPTY_BLAKE3: &str = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+
+    /// 记录读取了多少字节的读取器
+    struct CountingReader<'a> {
+        inner: File,
+        bytes: &'a AtomicU64,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_known_vectors() {
+        assert_eq!(
+            hash_reader(&b"abc"[..], HashAlgorithm::Sha256).unwrap(),
+            ABC_SHA256
+        );
+        assert_eq!(
+            hash_reader(&b"abc"[..], HashAlgorithm::Blake3).unwrap(),
+            ABC_BLAKE3
+        );
+        assert_eq!(
+            hash_reader(&b""[..], HashAlgorithm::Blake3).unwrap(),
+            EMPTY_BLAKE3
+        );
+        assert_eq!(
+            hash_reader(&b""[..], HashAlgorithm::Sha256).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_cache_hit_skips_reading_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        std::fs::write(&path, b"abc").unwrap();
+        let cache_file = dir.path().join(HASH_CACHE_FILE_NAME);
+        let bytes_read = AtomicU64::new(0);
+        let open = |p: &Path| {
+            File::open(p).map(|inner| CountingReader {
+                inner,
+                bytes: &bytes_read,
+            })
+        };
+
+        let cache = HashCache::load(cache_file.clone());
+        let first = cache.hash_with(&path, HashAlgorithm::Sha256, open);
+        assert_eq!(first.digest.as_deref(), Some(ABC_SHA256));
+        assert!(!first.cached);
+        assert_eq!(bytes_read.load(Ordering::Relaxed), 3);
+
+        let second = cache.hash_with(&path, HashAlgorithm::Sha256, open);
+        assert!(second.cached);
+        assert_eq!(second.digest.as_deref(), Some(ABC_SHA256));
+        assert_eq!(bytes_read.load(Ordering::Relaxed), 3);
+
+        // 不同算法分开缓存
+        let blake = cache.hash_with(&path, HashAlgorithm::Blake3, open);
+        assert_eq!(blake.digest.as_deref(), Some(ABC_BLAKE3));
+        assert!(!blake.cached);
+
+        // 写回后重新加载仍命中
+        cache.save().unwrap();
+        let reloaded = HashCache::load(cache_file);
+        assert_eq!(reloaded.len(), 2);
+        let third = reloaded.hash_with(&path, HashAlgorithm::Sha256, open);
+        assert!(third.cached);
+        assert_eq!(bytes_read.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn test_size_or_mtime_change_invalidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        let cache = HashCache::load(dir.path().join(HASH_CACHE_FILE_NAME));
+        assert!(!cache.hash(&path, HashAlgorithm::Sha256).cached);
+
+        // 内容与大小都变化
+        std::fs::write(&path, b"abcd").unwrap();
+        let changed = cache.hash(&path, HashAlgorithm::Sha256);
+        assert!(!changed.cached);
+        assert_eq!(changed.size, 4);
+        assert_ne!(changed.digest.as_deref(), Some(ABC_SHA256));
+
+        // 大小不变、只有修改时间变化也重新计算
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1_000_000))
+            .unwrap();
+        drop(file);
+        assert!(!cache.hash(&path, HashAlgorithm::Sha256).cached);
+        assert!(cache.hash(&path, HashAlgorithm::Sha256).cached);
+    }
+
+    #[test]
+    fn test_hash_files_keeps_order_and_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for i in 0..8 {
+            let path = dir.path().join(format!("{}.txt", i));
+            std::fs::write(&path, if i % 2 == 0 { &b"abc"[..] } else { &b""[..] }).unwrap();
+            paths.push(path);
+        }
+        paths.push(dir.path().join("missing.txt"));
+        let cache = HashCache::load(dir.path().join(HASH_CACHE_FILE_NAME));
+        let progress = Mutex::new(Vec::new());
+        let results = hash_files(&cache, &paths, HashAlgorithm::Blake3, 3, &|done, hash| {
+            progress.lock().unwrap().push((done, hash.path.clone()));
+        });
+
+        assert_eq!(results.len(), 9);
+        for (i, result) in results.iter().take(8).enumerate() {
+            assert_eq!(result.path, paths[i].to_string_lossy());
+            let expected = if i % 2 == 0 { ABC_BLAKE3 } else { EMPTY_BLAKE3 };
+            assert_eq!(result.digest.as_deref(), Some(expected));
+        }
+        assert!(results[8].digest.is_none());
+        assert!(results[8].error.is_some());
+        let mut done: Vec<_> = progress
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|(d, _)| d)
+            .collect();
+        done.sort();
+        assert_eq!(done, (1..=9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_concurrency_by_media() {
+        assert_eq!(hash_concurrency(MediaType::Hdd), 1);
+        assert!(hash_concurrency(MediaType::Ssd) > hash_concurrency(MediaType::Unknown));
+    }
+}
@@ -1,9 +1,35 @@
+pub mod checksum;
+pub mod cleaners;
 pub mod delete;
 pub mod dry_run;
+pub mod empty_trash;
+pub mod execution;
+pub mod hashing;
 pub mod r#move;
+pub mod offload;
 pub mod permission;
+pub mod policy;
+pub mod schedule;
+pub mod snapshots;
+pub mod system_conditions;
+pub mod target_check;
+pub mod vhd;
+pub mod windows_cleanup;
 
+pub use checksum::*;
+pub use cleaners::*;
 pub use delete::*;
 pub use dry_run::*;
+pub use empty_trash::*;
+pub use execution::*;
+pub use hashing::*;
+pub use offload::*;
 pub use permission::*;
+pub use policy::*;
 pub use r#move::*;
+pub use schedule::*;
+pub use snapshots::*;
+pub use system_conditions::*;
+pub use target_check::*;
+pub use vhd::*;
+pub use windows_cleanup::*;
@@ -1,7 +1,220 @@
-use ai_disk_common::DiskAnalyzerError;
+//! 移动文件或目录。
+//!
+//! 同一卷内直接重命名；跨卷时先复制再删除源。复制开始前写入移动清单（manifest），
+//! 复制完成、开始删除源之前改写清单记下复制已完成，全部完成后删除清单。
+//! 进程中途退出时清单仍在，据此判断移动进行到一半，之后可以补完（[`complete_move`]）
+//! 或回滚（[`rollback_move`]）；复制完成后源可能已被删除一部分，只能补完。
 
-/// 移动执行（预留）
-pub async fn move_file(from: &str, to: &str) -> Result<(), DiskAnalyzerError> {
-    let _ = (from, to);
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use ai_disk_common::{write_atomic, DiskAnalyzerError};
+use serde::{Deserialize, Serialize};
+
+use crate::delete::{delete_entry, DeleteOptions};
+
+/// 跨卷移动进行中的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveManifest {
+    pub from: String,
+    pub to: String,
+    /// 复制已完成、开始删除源：此后目标是唯一完整的副本，不能再回滚
+    #[serde(default)]
+    pub copied: bool,
+}
+
+impl MoveManifest {
+    pub fn read(path: &Path) -> Result<Self, DiskAnalyzerError> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|e| DiskAnalyzerError::Config(format!("无效的移动清单: {}", e)))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), DiskAnalyzerError> {
+        let text = serde_json::to_string(self)
+            .map_err(|e| DiskAnalyzerError::Config(format!("序列化移动清单失败: {}", e)))?;
+        Ok(write_atomic(path, text.as_bytes())?)
+    }
+}
+
+/// 把 `from` 移动到 `to`，`to` 不能已存在；跨卷时在 `manifest` 处记录移动清单
+pub fn move_path(from: &Path, to: &Path, manifest: &Path) -> Result<(), DiskAnalyzerError> {
+    fs::symlink_metadata(from)?;
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "移动目标已存在: {}",
+            to.display()
+        )));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => copy_then_remove(from, to, manifest),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 跨卷移动：写清单、复制、记下复制完成、删除源、删除清单
+pub(crate) fn copy_then_remove(
+    from: &Path,
+    to: &Path,
+    manifest: &Path,
+) -> Result<(), DiskAnalyzerError> {
+    let mut record = MoveManifest {
+        from: from.to_string_lossy().into_owned(),
+        to: to.to_string_lossy().into_owned(),
+        copied: false,
+    };
+    record.write(manifest)?;
+    copy_tree(from, to)?;
+    record.copied = true;
+    record.write(manifest)?;
+    remove_tree(from)?;
+    fs::remove_file(manifest)?;
+    Ok(())
+}
+
+/// 按清单补完中断的移动：复制未完成时重新复制（覆盖已复制的部分），再删除源中剩下的内容
+pub fn complete_move(manifest: &Path) -> Result<(), DiskAnalyzerError> {
+    let MoveManifest { from, to, copied } = MoveManifest::read(manifest)?;
+    let (from, to) = (PathBuf::from(from), PathBuf::from(to));
+    // 源已删除说明复制已完成，只差删除清单
+    if fs::symlink_metadata(&from).is_ok() {
+        if !copied {
+            copy_tree(&from, &to)?;
+        }
+        remove_tree(&from)?;
+    }
+    fs::remove_file(manifest)?;
     Ok(())
 }
+
+/// 按清单回滚中断的移动：删除已复制的部分，源保持不变。
+/// 复制已完成时源可能已被删除一部分，目标是唯一完整的副本，无法回滚，按补完处理。返回是否真正回滚
+pub fn rollback_move(manifest: &Path) -> Result<bool, DiskAnalyzerError> {
+    let record = MoveManifest::read(manifest)?;
+    if record.copied || fs::symlink_metadata(&record.from).is_err() {
+        complete_move(manifest)?;
+        return Ok(false);
+    }
+    // 清单写于复制之前且 move_path 要求目标不存在，目标中的内容都是这次复制产生的
+    if fs::symlink_metadata(&record.to).is_ok() {
+        remove_tree(Path::new(&record.to))?;
+    }
+    fs::remove_file(manifest)?;
+    Ok(true)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<(), DiskAnalyzerError> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// 删除源或目标；只读文件同样能删除（见 [`delete_entry`]）
+fn remove_tree(path: &Path) -> Result<(), DiskAnalyzerError> {
+    delete_entry(path, DeleteOptions::default()).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(dir: &Path) -> (PathBuf, PathBuf, PathBuf) {
+        let from = dir.join("src");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("a.txt"), b"aaa").unwrap();
+        fs::write(from.join("sub").join("b.txt"), b"bb").unwrap();
+        (from, dir.join("dst").join("moved"), dir.join("move.json"))
+    }
+
+    fn assert_moved(from: &Path, to: &Path, manifest: &Path) {
+        assert!(!from.exists());
+        assert!(!manifest.exists());
+        assert_eq!(fs::read(to.join("a.txt")).unwrap(), b"aaa");
+        assert_eq!(fs::read(to.join("sub").join("b.txt")).unwrap(), b"bb");
+    }
+
+    #[test]
+    fn test_move_and_copy_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to, manifest) = setup(dir.path());
+        move_path(&from, &to, &manifest).unwrap();
+        assert_moved(&from, &to, &manifest);
+
+        // 目标已存在时拒绝
+        let (from, _, _) = setup(dir.path());
+        assert!(move_path(&from, &to, &manifest).is_err());
+        assert!(from.exists());
+
+        let other = dir.path().join("other");
+        copy_then_remove(&from, &other, &manifest).unwrap();
+        assert_moved(&from, &other, &manifest);
+    }
+
+    /// 模拟复制到一半时进程退出：清单已写、只复制了部分文件、源仍在
+    fn interrupted_copy(dir: &Path) -> (PathBuf, PathBuf, PathBuf) {
+        let (from, to, manifest) = setup(dir);
+        MoveManifest {
+            from: from.to_string_lossy().into_owned(),
+            to: to.to_string_lossy().into_owned(),
+            copied: false,
+        }
+        .write(&manifest)
+        .unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(to.join("a.txt"), b"a").unwrap();
+        (from, to, manifest)
+    }
+
+    #[test]
+    fn test_complete_interrupted_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to, manifest) = interrupted_copy(dir.path());
+        complete_move(&manifest).unwrap();
+        assert_moved(&from, &to, &manifest);
+    }
+
+    #[test]
+    fn test_rollback_interrupted_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to, manifest) = interrupted_copy(dir.path());
+        assert!(rollback_move(&manifest).unwrap());
+        assert!(!to.exists());
+        assert!(!manifest.exists());
+        assert_eq!(fs::read(from.join("a.txt")).unwrap(), b"aaa");
+
+        // 源已删除、只差删除清单时无法回滚
+        let (from, to, manifest) = interrupted_copy(dir.path());
+        copy_tree(&from, &to).unwrap();
+        remove_tree(&from).unwrap();
+        assert!(!rollback_move(&manifest).unwrap());
+        assert_moved(&from, &to, &manifest);
+    }
+
+    #[test]
+    fn test_rollback_after_copy_finishes_the_move() {
+        let dir = tempfile::tempdir().unwrap();
+        // 复制完成、删除源到一半时进程退出：源中已删除的文件只剩目标中的副本
+        let (from, to, manifest) = interrupted_copy(dir.path());
+        copy_tree(&from, &to).unwrap();
+        let mut record = MoveManifest::read(&manifest).unwrap();
+        record.copied = true;
+        record.write(&manifest).unwrap();
+        fs::remove_file(from.join("a.txt")).unwrap();
+
+        assert!(!rollback_move(&manifest).unwrap());
+        assert_moved(&from, &to, &manifest);
+    }
+}
@@ -0,0 +1,272 @@
+//! 上传到云存储后删除本地文件（「转存到云端」）。
+//!
+//! 上传由调用方通过 [`CloudUploader`] 提供，执行器只负责校验与本地删除，
+//! 因此不依赖具体的云存储实现。只有云端报告的大小与校验和都与本地一致时
+//! 才删除本地文件，任何不一致都保留本地文件。
+
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use ai_disk_common::DiskAnalyzerError;
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::RemoteChecksum;
+
+/// 上传完成后云端报告的文件信息
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub remote_id: String,
+    /// 云端记录的文件大小；服务商未返回时为 None
+    pub size: Option<u64>,
+    /// 云端记录的校验和；服务商未返回时为 None
+    pub checksum: Option<RemoteChecksum>,
+}
+
+/// 执行 Offload 动作时使用的上传实现
+pub trait CloudUploader {
+    fn upload(
+        &self,
+        path: &str,
+        provider: &str,
+        account_id: &str,
+        target_path: &str,
+    ) -> impl Future<Output = Result<UploadedFile, DiskAnalyzerError>> + Send;
+}
+
+/// 转存日志中的一条记录，用于之后从云端找回文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffloadRecord {
+    pub path: String,
+    pub provider: String,
+    pub account_id: String,
+    pub target_path: String,
+    pub remote_id: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<RemoteChecksum>,
+    /// Unix 时间戳（秒）
+    pub offloaded_at: u64,
+}
+
+/// 本地文件的大小与修改时间，上传前后不一致说明文件在上传期间被修改
+fn file_identity(path: &Path) -> Result<(u64, Option<std::time::SystemTime>), DiskAnalyzerError> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_file() {
+        return Err(DiskAnalyzerError::InvalidPath(format!(
+            "只能转存单个文件: {}",
+            path.display()
+        )));
+    }
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+/// 上传 `path` 并校验云端大小与校验和，通过后调用 `remove_local` 删除本地文件。
+///
+/// 校验失败或上传失败时本地文件保持不变。
+pub async fn offload_file<U: CloudUploader>(
+    uploader: &U,
+    path: &str,
+    provider: &str,
+    account_id: &str,
+    target_path: &str,
+    remove_local: impl FnOnce(&Path) -> Result<(), DiskAnalyzerError>,
+) -> Result<OffloadRecord, DiskAnalyzerError> {
+    let local = Path::new(path);
+    let before = file_identity(local)?;
+    let uploaded = uploader
+        .upload(path, provider, account_id, target_path)
+        .await?;
+
+    if file_identity(local)? != before {
+        return Err(DiskAnalyzerError::VerificationFailed(format!(
+            "本地文件在上传期间被修改: {}",
+            path
+        )));
+    }
+    let size = before.0;
+    match uploaded.size {
+        Some(remote) if remote == size => {}
+        Some(remote) => {
+            return Err(DiskAnalyzerError::VerificationFailed(format!(
+                "云端大小 {} 与本地大小 {} 不一致: {}",
+                remote, size, path
+            )))
+        }
+        None => {
+            return Err(DiskAnalyzerError::VerificationFailed(format!(
+                "{} 未返回文件大小，无法确认上传完整: {}",
+                provider, path
+            )))
+        }
+    }
+    let Some(checksum) = &uploaded.checksum else {
+        return Err(DiskAnalyzerError::VerificationFailed(format!(
+            "{} 未返回校验和，无法确认上传完整: {}",
+            provider, path
+        )));
+    };
+    if !checksum.matches_file(local)? {
+        return Err(DiskAnalyzerError::VerificationFailed(format!(
+            "云端校验和与本地文件不一致: {}",
+            path
+        )));
+    }
+
+    remove_local(local)?;
+    Ok(OffloadRecord {
+        path: path.to_string(),
+        provider: provider.to_string(),
+        account_id: account_id.to_string(),
+        target_path: target_path.to_string(),
+        remote_id: uploaded.remote_id,
+        size,
+        checksum: uploaded.checksum,
+        offloaded_at: std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    })
+}
+
+/// 将文件移入系统回收站
+pub fn move_to_trash(path: &Path) -> Result<(), DiskAnalyzerError> {
+    trash::delete(path).map_err(|e| {
+        DiskAnalyzerError::Io(std::io::Error::other(format!(
+            "移入回收站失败 {}: {}",
+            path.display(),
+            e
+        )))
+    })
+}
+
+/// 向转存日志（JSON Lines）追加一条记录
+pub fn append_journal(journal: &Path, record: &OffloadRecord) -> Result<(), DiskAnalyzerError> {
+    if let Some(dir) = journal.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let line = serde_json::to_string(record)
+        .map_err(|e| DiskAnalyzerError::Config(format!("序列化转存记录失败: {}", e)))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(journal)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// 把转存日志落盘；应用被强制关闭前调用，日志不存在时什么也不做
+pub fn sync_journal(journal: &Path) -> Result<(), DiskAnalyzerError> {
+    match OpenOptions::new().append(true).open(journal) {
+        Ok(file) => Ok(file.sync_all()?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockUploader {
+        size: Option<u64>,
+        checksum: Option<RemoteChecksum>,
+        fail: bool,
+    }
+
+    impl CloudUploader for MockUploader {
+        async fn upload(
+            &self,
+            _path: &str,
+            _provider: &str,
+            _account_id: &str,
+            _target_path: &str,
+        ) -> Result<UploadedFile, DiskAnalyzerError> {
+            if self.fail {
+                return Err(DiskAnalyzerError::Upload("network down".into()));
+            }
+            Ok(UploadedFile {
+                remote_id: "remote-1".into(),
+                size: self.size,
+                checksum: self.checksum.clone(),
+            })
+        }
+    }
+
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    fn offload(
+        dir: &tempfile::TempDir,
+        uploader: &MockUploader,
+    ) -> Result<OffloadRecord, DiskAnalyzerError> {
+        let path = dir.path().join("video.mp4");
+        fs::write(&path, b"abc").unwrap();
+        futures::executor::block_on(offload_file(
+            uploader,
+            path.to_str().unwrap(),
+            "google_drive",
+            "acct",
+            "/backup",
+            |p| fs::remove_file(p).map_err(Into::into),
+        ))
+    }
+
+    #[test]
+    fn test_offload_removes_local_after_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploader = MockUploader {
+            size: Some(3),
+            checksum: Some(RemoteChecksum::Md5(ABC_MD5.into())),
+            fail: false,
+        };
+        let record = offload(&dir, &uploader).unwrap();
+        assert!(!dir.path().join("video.mp4").exists());
+        assert_eq!(record.remote_id, "remote-1");
+        assert_eq!(record.size, 3);
+
+        let journal = dir.path().join("logs").join("offload.jsonl");
+        append_journal(&journal, &record).unwrap();
+        append_journal(&journal, &record).unwrap();
+        sync_journal(&journal).unwrap();
+        sync_journal(&dir.path().join("missing.jsonl")).unwrap();
+        let text = fs::read_to_string(&journal).unwrap();
+        let lines: Vec<OffloadRecord> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines, vec![record.clone(), record]);
+    }
+
+    #[test]
+    fn test_verification_mismatch_keeps_local_file() {
+        let cases = [
+            (Some(4), Some(RemoteChecksum::Md5(ABC_MD5.into()))),
+            (Some(3), Some(RemoteChecksum::Md5("00".into()))),
+            (None, Some(RemoteChecksum::Md5(ABC_MD5.into()))),
+            (Some(3), None),
+        ];
+        for (size, checksum) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            let uploader = MockUploader {
+                size,
+                checksum,
+                fail: false,
+            };
+            let err = offload(&dir, &uploader).unwrap_err();
+            assert!(matches!(err, DiskAnalyzerError::VerificationFailed(_)));
+            assert_eq!(fs::read(dir.path().join("video.mp4")).unwrap(), b"abc");
+        }
+    }
+
+    #[test]
+    fn test_upload_failure_keeps_local_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploader = MockUploader {
+            size: None,
+            checksum: None,
+            fail: true,
+        };
+        let err = offload(&dir, &uploader).unwrap_err();
+        assert!(matches!(err, DiskAnalyzerError::Upload(_)));
+        assert!(dir.path().join("video.mp4").exists());
+    }
+}
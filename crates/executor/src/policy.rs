@@ -0,0 +1,114 @@
+//! 按 [`ExecutionPolicy`] 决定计划中每个动作的实际执行方式。
+
+use ai_disk_common::{ExecutionMode, ExecutionPolicy};
+use ai_disk_domain::{Action, PlannedAction, RiskLevel};
+
+/// 某一风险等级的执行方式。中风险只会移入回收站或跳过，高风险始终为 Skip，不受覆盖与快速模式影响
+pub fn resolve_execution_mode(policy: &ExecutionPolicy, risk: RiskLevel) -> ExecutionMode {
+    match risk {
+        RiskLevel::Low => policy.low.unwrap_or(if policy.fast_mode {
+            ExecutionMode::Permanent
+        } else {
+            ExecutionMode::Trash
+        }),
+        RiskLevel::Medium => match policy.medium {
+            Some(ExecutionMode::Skip) => ExecutionMode::Skip,
+            _ => ExecutionMode::Trash,
+        },
+        RiskLevel::High => ExecutionMode::Skip,
+    }
+}
+
+/// 单个动作的执行方式：在风险等级的方式之上，按动作本身的性质修正。
+/// 清空废纸篓无法再移入回收站，只能永久删除；转存在上传后总是把本地文件移入回收站；
+/// 移动与压缩虚拟磁盘不删除任何文件，Permanent 与 Trash 都表示照常执行
+pub fn resolve_action_mode(policy: &ExecutionPolicy, planned: &PlannedAction) -> ExecutionMode {
+    let mode = resolve_execution_mode(policy, planned.risk);
+    if mode == ExecutionMode::Skip {
+        return mode;
+    }
+    match planned.action {
+        Action::Delete { .. } | Action::Move { .. } | Action::CompactVhd { .. } => mode,
+        Action::EmptyTrash { .. } => ExecutionMode::Permanent,
+        Action::Offload { .. } => ExecutionMode::Trash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ExecutionMode::{Permanent, Skip, Trash};
+
+    fn planned(action: Action, risk: RiskLevel) -> PlannedAction {
+        PlannedAction {
+            action,
+            bytes: 0,
+            risk,
+            reason: String::new(),
+            scanned: None,
+        }
+    }
+
+    fn modes(policy: &ExecutionPolicy) -> [ExecutionMode; 3] {
+        [RiskLevel::Low, RiskLevel::Medium, RiskLevel::High]
+            .map(|risk| resolve_execution_mode(policy, risk))
+    }
+
+    #[test]
+    fn test_default_and_fast_mode() {
+        let mut policy = ExecutionPolicy::default();
+        assert_eq!(modes(&policy), [Trash, Trash, Skip]);
+        policy.fast_mode = true;
+        assert_eq!(modes(&policy), [Permanent, Trash, Skip]);
+    }
+
+    #[test]
+    fn test_overrides_never_execute_high_risk() {
+        let mut policy = ExecutionPolicy {
+            fast_mode: true,
+            low: Some(Trash),
+            medium: Some(Permanent),
+            ..Default::default()
+        };
+        // 覆盖优先于快速模式；中风险即使配置为 Permanent 也只移入回收站
+        assert_eq!(modes(&policy), [Trash, Trash, Skip]);
+
+        for high in [Permanent, Trash, Skip] {
+            policy.high = Some(high);
+            assert_eq!(resolve_execution_mode(&policy, RiskLevel::High), Skip);
+        }
+        policy.low = Some(Skip);
+        policy.medium = Some(Skip);
+        assert_eq!(modes(&policy), [Skip, Skip, Skip]);
+    }
+
+    #[test]
+    fn test_action_kind_adjusts_mode() {
+        let path = || "/home/u/x".to_string();
+        let fast = ExecutionPolicy {
+            fast_mode: true,
+            ..Default::default()
+        };
+        let default = ExecutionPolicy::default();
+        let empty_trash = planned(Action::EmptyTrash { path: path() }, RiskLevel::Low);
+        let offload = planned(
+            Action::Offload {
+                path: path(),
+                provider: "dropbox".into(),
+                account_id: "acct".into(),
+                target_path: "/backup".into(),
+            },
+            RiskLevel::Low,
+        );
+        let delete = planned(Action::Delete { path: path() }, RiskLevel::Low);
+
+        assert_eq!(resolve_action_mode(&default, &delete), Trash);
+        assert_eq!(resolve_action_mode(&fast, &delete), Permanent);
+        assert_eq!(resolve_action_mode(&default, &empty_trash), Permanent);
+        assert_eq!(resolve_action_mode(&fast, &offload), Trash);
+
+        let mut risky = empty_trash;
+        risky.risk = RiskLevel::High;
+        assert_eq!(resolve_action_mode(&fast, &risky), Skip);
+    }
+}
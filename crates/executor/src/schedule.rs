@@ -0,0 +1,465 @@
+//! 计划的定时执行：任务保存在一个 JSON 文件中，后台循环定期调用 [`ExecutionScheduler::tick`]，
+//! 到时间且条件满足（或用户要求立即执行）的任务按 `run_at` 顺序逐个交给 [`ScheduleRunner`] 执行。
+//! 时间与条件经 [`Clock`] 与 [`ConditionProvider`] 注入，系统实现见 [`crate::SystemConditions`]。
+
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ai_disk_common::{write_atomic, DiskAnalyzerError};
+use ai_disk_domain::{
+    ExecutionConditions, ExecutionReport, ScheduleStatus, ScheduledExecution, WaitReason,
+};
+
+/// 当前时间（Unix 秒）
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// 系统状态；无法检测时返回 None，对应的条件视为满足
+pub trait ConditionProvider {
+    /// 是否接通交流电源
+    fn on_ac_power(&self) -> Option<bool>;
+    /// 距最近一次键盘或鼠标输入的秒数
+    fn idle_secs(&self) -> Option<u64>;
+    /// 是否有全屏运行的应用
+    fn fullscreen_active(&self) -> Option<bool>;
+}
+
+/// 第一个未满足的条件，全部满足时为 None
+pub fn unmet_condition(
+    conditions: &ExecutionConditions,
+    provider: &impl ConditionProvider,
+) -> Option<WaitReason> {
+    if conditions.require_ac_power && provider.on_ac_power() == Some(false) {
+        return Some(WaitReason::OnBattery);
+    }
+    if let Some(minutes) = conditions.idle_minutes {
+        if provider
+            .idle_secs()
+            .is_some_and(|idle| idle < u64::from(minutes) * 60)
+        {
+            return Some(WaitReason::UserActive);
+        }
+    }
+    if conditions.no_fullscreen && provider.fullscreen_active() == Some(true) {
+        return Some(WaitReason::FullscreenApp);
+    }
+    None
+}
+
+/// 执行到期的任务
+pub trait ScheduleRunner {
+    /// 执行任务的目标并返回结果；出错时返回 `failed` 的结果
+    fn run(
+        &mut self,
+        schedule: &ScheduledExecution,
+    ) -> impl Future<Output = ExecutionReport> + Send;
+}
+
+/// 定时执行的任务列表，按 `run_at` 排序并持久化到 `path`
+pub struct ExecutionScheduler {
+    path: PathBuf,
+    schedules: Mutex<Vec<ScheduledExecution>>,
+}
+
+impl ExecutionScheduler {
+    /// 读取任务文件。上次退出时仍在执行的任务标记为 Interrupted；
+    /// 文件无法读取或解析时记录日志后从空列表开始
+    pub fn open(path: &Path) -> Self {
+        let mut schedules: Vec<ScheduledExecution> = match fs::read(path) {
+            Ok(raw) => serde_json::from_slice(&raw).unwrap_or_else(|e| {
+                tracing::warn!(path = %path.display(), error = %e, "定时执行文件无效，已忽略");
+                Vec::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "读取定时执行文件失败");
+                Vec::new()
+            }
+        };
+        let mut interrupted = false;
+        for schedule in &mut schedules {
+            if schedule.status == ScheduleStatus::Running {
+                schedule.status = ScheduleStatus::Interrupted;
+                interrupted = true;
+            }
+        }
+        let scheduler = Self {
+            path: path.to_path_buf(),
+            schedules: Mutex::new(Vec::new()),
+        };
+        if interrupted {
+            scheduler.save_logged(&schedules);
+        }
+        *scheduler.lock() = schedules;
+        scheduler
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ScheduledExecution>> {
+        self.schedules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, schedules: &[ScheduledExecution]) -> Result<(), DiskAnalyzerError> {
+        let text = serde_json::to_vec_pretty(schedules)
+            .map_err(|e| DiskAnalyzerError::Config(format!("序列化定时执行失败: {}", e)))?;
+        write_atomic(&self.path, &text)?;
+        Ok(())
+    }
+
+    /// 执行过程中的状态变化写入失败时只记录日志，内存中的状态仍然有效
+    fn save_logged(&self, schedules: &[ScheduledExecution]) {
+        if let Err(e) = self.save(schedules) {
+            tracing::warn!(path = %self.path.display(), error = %e, "写入定时执行文件失败");
+        }
+    }
+
+    pub fn list(&self) -> Vec<ScheduledExecution> {
+        self.lock().clone()
+    }
+
+    pub fn add(&self, schedule: ScheduledExecution) -> Result<(), DiskAnalyzerError> {
+        let mut schedules = self.lock();
+        let index = schedules.partition_point(|s| s.run_at <= schedule.run_at);
+        schedules.insert(index, schedule);
+        if let Err(e) = self.save(&schedules) {
+            schedules.remove(index);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// 取消等待中的任务；任务不存在或已不在等待时返回 None
+    pub fn cancel(
+        &self,
+        schedule_id: &str,
+    ) -> Result<Option<ScheduledExecution>, DiskAnalyzerError> {
+        self.update_pending(schedule_id, |s| s.status = ScheduleStatus::Cancelled)
+    }
+
+    /// 让等待中的任务在下一次 [`Self::tick`] 时执行，不再等待时间与条件；
+    /// 任务不存在或已不在等待时返回 None
+    pub fn run_now(
+        &self,
+        schedule_id: &str,
+    ) -> Result<Option<ScheduledExecution>, DiskAnalyzerError> {
+        self.update_pending(schedule_id, |s| s.run_now = true)
+    }
+
+    fn update_pending(
+        &self,
+        schedule_id: &str,
+        update: impl FnOnce(&mut ScheduledExecution),
+    ) -> Result<Option<ScheduledExecution>, DiskAnalyzerError> {
+        let mut schedules = self.lock();
+        let Some(index) = schedules
+            .iter()
+            .position(|s| s.schedule_id == schedule_id && s.status == ScheduleStatus::Pending)
+        else {
+            return Ok(None);
+        };
+        let previous = schedules[index].clone();
+        update(&mut schedules[index]);
+        if let Err(e) = self.save(&schedules) {
+            schedules[index] = previous;
+            return Err(e);
+        }
+        Ok(Some(schedules[index].clone()))
+    }
+
+    /// 检查等待中的任务：到期且条件满足的逐个执行（每次执行前重新检查时间与条件），
+    /// 超过等待时限仍未满足条件的标记为 Expired。返回执行的任务数
+    pub async fn tick(
+        &self,
+        clock: &impl Clock,
+        conditions: &impl ConditionProvider,
+        runner: &mut impl ScheduleRunner,
+    ) -> usize {
+        let mut executed = 0;
+        while let Some(schedule) = self.next_due(clock.now(), conditions) {
+            let report = runner.run(&schedule).await;
+            self.finish(&schedule.schedule_id, report);
+            executed += 1;
+        }
+        executed
+    }
+
+    /// 取出下一个可以执行的任务并标记为 Running，同时更新其余到期任务的等待原因
+    fn next_due(
+        &self,
+        now: u64,
+        conditions: &impl ConditionProvider,
+    ) -> Option<ScheduledExecution> {
+        let mut schedules = self.lock();
+        let mut changed = false;
+        let mut due = None;
+        for schedule in schedules
+            .iter_mut()
+            .filter(|s| s.status == ScheduleStatus::Pending)
+        {
+            if !schedule.run_now {
+                if now < schedule.run_at {
+                    continue;
+                }
+                let waiting_for = unmet_condition(&schedule.conditions, conditions);
+                if waiting_for.is_some() {
+                    let give_up = u64::from(schedule.conditions.give_up_after_minutes) * 60;
+                    if give_up > 0 && now >= schedule.run_at.saturating_add(give_up) {
+                        schedule.status = ScheduleStatus::Expired;
+                        changed = true;
+                    }
+                    changed |= schedule.waiting_for != waiting_for;
+                    schedule.waiting_for = waiting_for;
+                    continue;
+                }
+            }
+            schedule.status = ScheduleStatus::Running;
+            schedule.waiting_for = None;
+            due = Some(schedule.clone());
+            changed = true;
+            break;
+        }
+        if changed {
+            self.save_logged(&schedules);
+        }
+        due
+    }
+
+    fn finish(&self, schedule_id: &str, report: ExecutionReport) {
+        let mut schedules = self.lock();
+        if let Some(schedule) = schedules.iter_mut().find(|s| s.schedule_id == schedule_id) {
+            schedule.status = ScheduleStatus::Completed;
+            schedule.report = Some(report);
+        }
+        self.save_logged(&schedules);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_disk_domain::ScheduleTarget;
+    use std::cell::Cell;
+
+    struct FakeClock(Cell<u64>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeConditions {
+        on_ac_power: Option<bool>,
+        idle_secs: Option<u64>,
+        fullscreen_active: Option<bool>,
+    }
+
+    impl ConditionProvider for FakeConditions {
+        fn on_ac_power(&self) -> Option<bool> {
+            self.on_ac_power
+        }
+        fn idle_secs(&self) -> Option<u64> {
+            self.idle_secs
+        }
+        fn fullscreen_active(&self) -> Option<bool> {
+            self.fullscreen_active
+        }
+    }
+
+    /// 记录执行过的任务，每次释放 100 字节
+    #[derive(Default)]
+    struct FakeRunner {
+        ran: Vec<String>,
+    }
+
+    impl ScheduleRunner for FakeRunner {
+        async fn run(&mut self, schedule: &ScheduledExecution) -> ExecutionReport {
+            self.ran.push(schedule.schedule_id.clone());
+            ExecutionReport {
+                execution_id: format!("exec_{}", schedule.schedule_id),
+                finished_at: 0,
+                resumed: false,
+                executed: 1,
+                skipped: 0,
+                freed: 100,
+                summary: "done".to_string(),
+                failed: false,
+                selection: None,
+                changed: Vec::new(),
+            }
+        }
+    }
+
+    fn schedule(id: &str, run_at: u64, conditions: ExecutionConditions) -> ScheduledExecution {
+        ScheduledExecution::new(
+            id.to_string(),
+            ScheduleTarget::Plan {
+                name: "tonight".to_string(),
+            },
+            run_at,
+            conditions,
+            0,
+        )
+    }
+
+    fn tick_at(
+        scheduler: &ExecutionScheduler,
+        now: u64,
+        conditions: &FakeConditions,
+        runner: &mut FakeRunner,
+    ) -> usize {
+        futures::executor::block_on(scheduler.tick(&FakeClock(Cell::new(now)), conditions, runner))
+    }
+
+    fn get(scheduler: &ExecutionScheduler, id: &str) -> ScheduledExecution {
+        scheduler
+            .list()
+            .into_iter()
+            .find(|s| s.schedule_id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_waits_for_time_and_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedules.json");
+        let scheduler = ExecutionScheduler::open(&path);
+        let conditions = ExecutionConditions {
+            require_ac_power: true,
+            idle_minutes: Some(10),
+            no_fullscreen: true,
+            ..Default::default()
+        };
+        scheduler.add(schedule("night", 1000, conditions)).unwrap();
+        // 条件都无法检测时视为满足
+        scheduler.add(schedule("anyway", 2000, conditions)).unwrap();
+        let mut runner = FakeRunner::default();
+
+        let mut system = FakeConditions {
+            on_ac_power: Some(false),
+            idle_secs: Some(60),
+            fullscreen_active: Some(true),
+        };
+        assert_eq!(tick_at(&scheduler, 999, &system, &mut runner), 0);
+        assert_eq!(get(&scheduler, "night").waiting_for, None);
+
+        assert_eq!(tick_at(&scheduler, 1000, &system, &mut runner), 0);
+        assert_eq!(
+            get(&scheduler, "night").waiting_for,
+            Some(WaitReason::OnBattery)
+        );
+        system.on_ac_power = Some(true);
+        tick_at(&scheduler, 1060, &system, &mut runner);
+        assert_eq!(
+            get(&scheduler, "night").waiting_for,
+            Some(WaitReason::UserActive)
+        );
+        system.idle_secs = Some(600);
+        tick_at(&scheduler, 1120, &system, &mut runner);
+        assert_eq!(
+            get(&scheduler, "night").waiting_for,
+            Some(WaitReason::FullscreenApp)
+        );
+        assert!(runner.ran.is_empty());
+
+        system.fullscreen_active = Some(false);
+        assert_eq!(tick_at(&scheduler, 1180, &system, &mut runner), 1);
+        let night = get(&scheduler, "night");
+        assert_eq!(night.status, ScheduleStatus::Completed);
+        assert_eq!(night.waiting_for, None);
+        assert_eq!(night.report.as_ref().unwrap().freed, 100);
+
+        let unknown = FakeConditions::default();
+        assert_eq!(tick_at(&scheduler, 2000, &unknown, &mut runner), 1);
+        assert_eq!(runner.ran, ["night", "anyway"]);
+
+        // 结果已持久化
+        let reopened = ExecutionScheduler::open(&path);
+        assert_eq!(reopened.list(), scheduler.list());
+    }
+
+    #[test]
+    fn test_cancel_run_now_and_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = ExecutionScheduler::open(&dir.path().join("schedules.json"));
+        let on_ac = ExecutionConditions {
+            require_ac_power: true,
+            give_up_after_minutes: 60,
+            ..Default::default()
+        };
+        scheduler.add(schedule("cancelled", 100, on_ac)).unwrap();
+        scheduler.add(schedule("later", 10_000, on_ac)).unwrap();
+        scheduler.add(schedule("expires", 100, on_ac)).unwrap();
+        let never = ExecutionConditions {
+            give_up_after_minutes: 0,
+            ..on_ac
+        };
+        scheduler.add(schedule("waits", 100, never)).unwrap();
+
+        let cancelled = scheduler.cancel("cancelled").unwrap().unwrap();
+        assert_eq!(cancelled.status, ScheduleStatus::Cancelled);
+        assert_eq!(scheduler.cancel("cancelled").unwrap(), None);
+        assert_eq!(scheduler.run_now("missing").unwrap(), None);
+        assert!(scheduler.run_now("later").unwrap().unwrap().run_now);
+
+        // 立即执行不等待时间与条件
+        let battery = FakeConditions {
+            on_ac_power: Some(false),
+            ..Default::default()
+        };
+        let mut runner = FakeRunner::default();
+        assert_eq!(tick_at(&scheduler, 200, &battery, &mut runner), 1);
+        assert_eq!(runner.ran, ["later"]);
+        assert_eq!(scheduler.run_now("later").unwrap(), None);
+
+        assert_eq!(tick_at(&scheduler, 100 + 3600, &battery, &mut runner), 0);
+        assert_eq!(get(&scheduler, "expires").status, ScheduleStatus::Expired);
+        assert_eq!(get(&scheduler, "waits").status, ScheduleStatus::Pending);
+        assert_eq!(
+            get(&scheduler, "cancelled").status,
+            ScheduleStatus::Cancelled
+        );
+        assert_eq!(runner.ran, ["later"]);
+    }
+
+    #[test]
+    fn test_running_schedules_are_interrupted_on_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedules.json");
+        let scheduler = ExecutionScheduler::open(&path);
+        scheduler
+            .add(schedule("crashed", 0, ExecutionConditions::default()))
+            .unwrap();
+        let running = scheduler.next_due(10, &FakeConditions::default()).unwrap();
+        assert_eq!(running.status, ScheduleStatus::Running);
+        assert_eq!(scheduler.cancel("crashed").unwrap(), None);
+
+        let reopened = ExecutionScheduler::open(&path);
+        assert_eq!(
+            get(&reopened, "crashed").status,
+            ScheduleStatus::Interrupted
+        );
+        assert_eq!(
+            get(&ExecutionScheduler::open(&path), "crashed").status,
+            ScheduleStatus::Interrupted
+        );
+
+        fs::write(&path, "not json").unwrap();
+        assert!(ExecutionScheduler::open(&path).list().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! 执行前快照：误批准删除一个小而重要的文件（如 `.bashrc`、项目配置）时，永久删除后回收站里也找不回。
+//! 执行删除动作前，把不超过大小上限的文档、代码、配置与未知类型的文件复制到该次执行的日志目录
+//! `<日志目录>/<execution_id>/snapshots/` 下（保留原绝对路径的层级），并在执行日志中逐个动作记录，
+//! 之后可用 [`crate::rollback_execution`] 恢复内容。所有快照共享一个总大小预算，超出后不再做快照。
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf, Prefix};
+
+use ai_disk_common::{DiskAnalyzerError, SnapshotConfig};
+
+/// 执行目录下存放快照的子目录名
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// 不做快照的扩展名：媒体、压缩包与镜像、安装包与可执行文件。
+/// 其余文件（文档、代码、配置与无法识别的类型）都做快照
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mp3", "wav", "flac", "aac", "ogg",
+    "m4a", "wma", "jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "tif", "tiff", "raw", "zip",
+    "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "iso", "img", "dmg", "cab", "exe", "msi",
+    "dll", "so", "dylib", "pkg", "deb", "rpm", "appimage",
+];
+
+/// 文件是否属于需要快照的类型（文档、代码、配置或未知类型）
+pub fn is_snapshot_candidate(path: &Path) -> bool {
+    let Some(ext) = path.extension() else {
+        return true;
+    };
+    let ext = ext.to_string_lossy().to_ascii_lowercase();
+    !SKIPPED_EXTENSIONS.contains(&ext.as_str())
+}
+
+/// 原绝对路径在快照目录下的相对路径：盘符、UNC 共享名作为第一级目录，
+/// 如 `C:\Users\me\.gitconfig` → `C/Users/me/.gitconfig`。含 `..` 的路径返回 None
+pub fn snapshot_relative_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    relative.push((letter as char).to_string());
+                }
+                Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                    relative.push("UNC");
+                    relative.push(server);
+                    relative.push(share);
+                }
+                Prefix::Verbatim(name) | Prefix::DeviceNS(name) => relative.push(name),
+            },
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => return None,
+            Component::Normal(name) => relative.push(name),
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// 一次执行中的快照预算
+pub(crate) struct SnapshotBudget {
+    config: SnapshotConfig,
+    /// 已用字节数（续做时包含之前的快照）
+    used: u64,
+    /// 已做过快照的动作下标
+    taken: HashSet<usize>,
+}
+
+impl SnapshotBudget {
+    /// `previous` 为续做前日志中已有的快照：(动作下标, 路径, 字节数)
+    pub(crate) fn new(config: &SnapshotConfig, previous: &[(usize, String, u64)]) -> Self {
+        Self {
+            config: config.clone(),
+            used: previous.iter().map(|(_, _, bytes)| bytes).sum(),
+            taken: previous.iter().map(|(index, _, _)| *index).collect(),
+        }
+    }
+
+    /// 在动作 `index` 删除 `path` 之前做快照，返回快照相对执行目录的路径与字节数。
+    /// 未开启、该动作已有快照、不是普通文件、类型或大小不符、超出预算时返回 None
+    pub(crate) fn take(
+        &mut self,
+        index: usize,
+        path: &Path,
+        execution_dir: &Path,
+    ) -> Result<Option<(PathBuf, u64)>, DiskAnalyzerError> {
+        if !self.config.enabled || self.taken.contains(&index) || !is_snapshot_candidate(path) {
+            return Ok(None);
+        }
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return Ok(None);
+        };
+        let bytes = metadata.len();
+        if !metadata.is_file() || bytes > self.config.max_file_bytes {
+            return Ok(None);
+        }
+        if self.used.saturating_add(bytes) > self.config.budget_bytes {
+            tracing::info!(path = %path.display(), used = self.used, "快照预算已用完，跳过");
+            return Ok(None);
+        }
+        let Some(relative) = snapshot_relative_path(path) else {
+            return Ok(None);
+        };
+        let relative = Path::new(SNAPSHOTS_DIR).join(relative);
+        let target = execution_dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(path, &target)?;
+        // 删除紧随其后，快照须先落盘
+        File::open(&target)?.sync_all()?;
+        self.used += bytes;
+        self.taken.insert(index);
+        Ok(Some((relative, bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_relative_path() {
+        assert_eq!(
+            snapshot_relative_path(Path::new("/home/me/.bashrc")),
+            Some(PathBuf::from("home/me/.bashrc"))
+        );
+        assert_eq!(snapshot_relative_path(Path::new("/a/../etc/passwd")), None);
+        assert_eq!(snapshot_relative_path(Path::new("/")), None);
+        assert!(is_snapshot_candidate(Path::new("/home/me/.bashrc")));
+        assert!(is_snapshot_candidate(Path::new("notes.TXT")));
+        assert!(is_snapshot_candidate(Path::new("main.rs")));
+        assert!(is_snapshot_candidate(Path::new("Makefile")));
+        assert!(!is_snapshot_candidate(Path::new("movie.MKV")));
+        assert!(!is_snapshot_candidate(Path::new("setup.exe")));
+    }
+}
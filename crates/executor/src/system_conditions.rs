@@ -0,0 +1,297 @@
+//! 定时执行检查的系统状态：电源、输入空闲时间与全屏应用。
+//!
+//! - Windows：GetSystemPowerStatus、GetLastInputInfo 与 SHQueryUserNotificationState
+//! - macOS：`pmset -g batt` 与 IOHIDSystem 的 HIDIdleTime；无法判断全屏
+//! - Linux：/sys/class/power_supply；输入空闲与全屏依赖具体的显示服务器，无法判断
+
+use crate::schedule::ConditionProvider;
+
+/// 当前系统的状态
+pub struct SystemConditions;
+
+impl ConditionProvider for SystemConditions {
+    fn on_ac_power(&self) -> Option<bool> {
+        #[cfg(windows)]
+        {
+            windows::on_ac_power()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            macos::on_ac_power()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            linux::on_ac_power(std::path::Path::new(linux::POWER_SUPPLY_DIR))
+        }
+        #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+        {
+            None
+        }
+    }
+
+    fn idle_secs(&self) -> Option<u64> {
+        #[cfg(windows)]
+        {
+            windows::idle_secs()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            macos::idle_secs()
+        }
+        #[cfg(not(any(windows, target_os = "macos")))]
+        {
+            None
+        }
+    }
+
+    fn fullscreen_active(&self) -> Option<bool> {
+        #[cfg(windows)]
+        {
+            windows::fullscreen_active()
+        }
+        #[cfg(not(windows))]
+        {
+            None
+        }
+    }
+}
+
+#[cfg(any(windows, test))]
+mod windows {
+    /// SYSTEM_POWER_STATUS.ACLineStatus：0 为电池，1 为交流电源，255 为未知
+    pub(super) fn ac_line_status(status: u8) -> Option<bool> {
+        match status {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// GetTickCount 与 LASTINPUTINFO.dwTime 均为开机后的毫秒数，约 49.7 天回绕一次
+    pub(super) fn idle_secs_between(now_ticks: u32, last_input_ticks: u32) -> u64 {
+        u64::from(now_ticks.wrapping_sub(last_input_ticks)) / 1000
+    }
+
+    /// QUERY_USER_NOTIFICATION_STATE 中表示全屏应用的状态：
+    /// QUNS_BUSY（2）、QUNS_RUNNING_D3D_FULL_SCREEN（3）与 QUNS_PRESENTATION_MODE（4）
+    pub(super) fn is_fullscreen_state(state: i32) -> bool {
+        matches!(state, 2..=4)
+    }
+
+    #[cfg(windows)]
+    pub(super) fn on_ac_power() -> Option<bool> {
+        use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+        // SAFETY: status 为可写的 SYSTEM_POWER_STATUS
+        unsafe {
+            let mut status: SYSTEM_POWER_STATUS = std::mem::zeroed();
+            if GetSystemPowerStatus(&mut status) == 0 {
+                return None;
+            }
+            ac_line_status(status.ACLineStatus)
+        }
+    }
+
+    #[cfg(windows)]
+    pub(super) fn idle_secs() -> Option<u64> {
+        use windows_sys::Win32::System::SystemInformation::GetTickCount;
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        // SAFETY: info 已设置 cbSize 且可写
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // SAFETY: 无参数
+        let now = unsafe { GetTickCount() };
+        Some(idle_secs_between(now, info.dwTime))
+    }
+
+    #[cfg(windows)]
+    pub(super) fn fullscreen_active() -> Option<bool> {
+        use windows_sys::Win32::UI::Shell::SHQueryUserNotificationState;
+        let mut state = 0;
+        // SAFETY: state 可写
+        if unsafe { SHQueryUserNotificationState(&mut state) } < 0 {
+            return None;
+        }
+        Some(is_fullscreen_state(state))
+    }
+}
+
+#[cfg(any(target_os = "linux", test))]
+mod linux {
+    use std::fs;
+    use std::path::Path;
+
+    #[cfg(target_os = "linux")]
+    pub(super) const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+    fn read_trimmed(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    /// 有外接电源（Mains、USB）时看其 `online`；没有外接电源但有电池时看电池是否在放电；
+    /// 两者都没有的（台式机）视为接通电源
+    pub(super) fn on_ac_power(dir: &Path) -> Option<bool> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Some(true);
+        };
+        let mut external = None;
+        let mut battery = None;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match read_trimmed(&path.join("type")).as_deref() {
+                Some("Mains" | "USB") => {
+                    let online = read_trimmed(&path.join("online")).as_deref() == Some("1");
+                    external = Some(external.unwrap_or(false) || online);
+                }
+                Some("Battery") => {
+                    let discharging =
+                        read_trimmed(&path.join("status")).as_deref() == Some("Discharging");
+                    battery = Some(battery.unwrap_or(false) || discharging);
+                }
+                _ => {}
+            }
+        }
+        match (external, battery) {
+            (Some(online), _) => Some(online),
+            (None, Some(discharging)) => Some(!discharging),
+            (None, None) => Some(true),
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", test))]
+mod macos {
+    /// `pmset -g batt` 的第一行为 `Now drawing from 'AC Power'` 或 `'Battery Power'`
+    pub(super) fn parse_power_source(output: &str) -> Option<bool> {
+        let line = output.lines().next()?;
+        if line.contains("'AC Power'") {
+            Some(true)
+        } else if line.contains("'Battery Power'") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// `ioreg -c IOHIDSystem` 输出中的 `"HIDIdleTime" = <纳秒>`
+    pub(super) fn parse_hid_idle_secs(output: &str) -> Option<u64> {
+        output.lines().find_map(|line| {
+            let (_, value) = line.split_once("\"HIDIdleTime\" = ")?;
+            let nanos: u64 = value.trim().parse().ok()?;
+            Some(nanos / 1_000_000_000)
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    fn command_output(program: &str, args: &[&str]) -> Option<String> {
+        let output = std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    #[cfg(target_os = "macos")]
+    pub(super) fn on_ac_power() -> Option<bool> {
+        parse_power_source(&command_output("pmset", &["-g", "batt"])?)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub(super) fn idle_secs() -> Option<u64> {
+        parse_hid_idle_secs(&command_output("ioreg", &["-c", "IOHIDSystem", "-d", "4"])?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_windows_status_values() {
+        assert_eq!(windows::ac_line_status(0), Some(false));
+        assert_eq!(windows::ac_line_status(1), Some(true));
+        assert_eq!(windows::ac_line_status(255), None);
+        assert_eq!(windows::idle_secs_between(125_000, 5_000), 120);
+        // 开机约 49.7 天后计数回绕
+        assert_eq!(windows::idle_secs_between(1_000, u32::MAX - 8_999), 10);
+        assert!(windows::is_fullscreen_state(3));
+        assert!(windows::is_fullscreen_state(4));
+        // QUNS_ACCEPTS_NOTIFICATIONS、QUNS_QUIET_TIME
+        assert!(!windows::is_fullscreen_state(5));
+        assert!(!windows::is_fullscreen_state(6));
+    }
+
+    fn supply(dir: &Path, name: &str, files: &[(&str, &str)]) {
+        let path = dir.join(name);
+        fs::create_dir_all(&path).unwrap();
+        for (file, content) in files {
+            fs::write(path.join(file), format!("{}\n", content)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_linux_power_supply() {
+        let laptop = tempfile::tempdir().unwrap();
+        supply(laptop.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            laptop.path(),
+            "BAT0",
+            &[("type", "Battery"), ("status", "Discharging")],
+        );
+        assert_eq!(linux::on_ac_power(laptop.path()), Some(false));
+        supply(
+            laptop.path(),
+            "ucsi-source-psy",
+            &[("type", "USB"), ("online", "1")],
+        );
+        assert_eq!(linux::on_ac_power(laptop.path()), Some(true));
+
+        // 只有电池信息
+        let battery_only = tempfile::tempdir().unwrap();
+        supply(
+            battery_only.path(),
+            "BAT1",
+            &[("type", "Battery"), ("status", "Charging")],
+        );
+        assert_eq!(linux::on_ac_power(battery_only.path()), Some(true));
+
+        let desktop = tempfile::tempdir().unwrap();
+        assert_eq!(linux::on_ac_power(desktop.path()), Some(true));
+        assert_eq!(
+            linux::on_ac_power(&desktop.path().join("missing")),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_macos_output_parsing() {
+        let ac = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t100%; charged; 0:00 remaining present: true\n";
+        let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t87%; discharging; 5:12 remaining present: true\n";
+        assert_eq!(macos::parse_power_source(ac), Some(true));
+        assert_eq!(macos::parse_power_source(battery), Some(false));
+        assert_eq!(macos::parse_power_source(""), None);
+
+        let ioreg = "+-o IOHIDSystem  <class IOHIDSystem>\n    {\n      \"HIDIdleTime\" = 754000000000\n      \"HIDPointerAcceleration\" = 45056\n    }\n";
+        assert_eq!(macos::parse_hid_idle_secs(ioreg), Some(754));
+        assert_eq!(macos::parse_hid_idle_secs("{}"), None);
+    }
+
+    #[test]
+    fn test_system_conditions_do_not_panic() {
+        let system = SystemConditions;
+        let _ = (
+            system.on_ac_power(),
+            system.idle_secs(),
+            system.fullscreen_active(),
+        );
+    }
+}
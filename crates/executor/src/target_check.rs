@@ -0,0 +1,363 @@
+//! 执行前核对动作目标。
+//!
+//! 从生成计划到执行之间用户可能移动、替换或修改了目标，按扫描时的状态删除同一路径下更新的内容很危险。
+//! 执行每个动作前把目标的当前状态与计划中记录的扫描时状态（[`PlannedAction::scanned`]）比较：
+//! 文件比较大小，以及修改时间是否晚于扫描时（超过 [`MODIFIED_TOLERANCE_SECS`] 才算）；
+//! 目录比较直接子项数，以及有限遍历得到的递归大小，遍历的条目超过 [`WALK_LIMIT`] 时只比较子项数。
+//! 目标已不存在时不算改变，交由执行本身报错。计划中还有其他动作作用于目标之下时，这些子树的改变
+//! 来自计划本身：核对时不计入它们，子项数与大小都按扫描时的值减去它们所占的部分比较（见 [`PlanSubtrees`]）。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use ai_disk_domain::{Action, PlannedAction, TargetState};
+
+/// 修改时间的容差：FAT 等文件系统只记录到 2 秒
+pub const MODIFIED_TOLERANCE_SECS: u64 = 2;
+/// 核对目录时最多遍历的条目数
+pub const WALK_LIMIT: usize = 20_000;
+
+/// 需要核对的源路径：删除、移动与转存；清空废纸篓与压缩虚拟磁盘的目标本就在变化，不核对
+pub fn checked_path(action: &Action) -> Option<&str> {
+    match action {
+        Action::Delete { path } | Action::Offload { path, .. } => Some(path),
+        Action::Move { from, .. } => Some(from),
+        Action::EmptyTrash { .. } | Action::CompactVhd { .. } => None,
+    }
+}
+
+/// 第 `index` 个动作的目标相对扫描时的改变，返回展示给用户的说明；
+/// 没有记录扫描时状态、目标不存在、不需要核对或没有改变时为 None
+pub fn target_change(actions: &[PlannedAction], index: usize) -> Option<String> {
+    let planned = &actions[index];
+    let scanned = planned.scanned.as_ref()?;
+    let path = Path::new(checked_path(&planned.action)?);
+    let mut subtrees = PlanSubtrees::default();
+    for (i, other) in actions.iter().enumerate() {
+        if i == index {
+            continue;
+        }
+        let source = Path::new(other.action.source_path());
+        if source != path && source.starts_with(path) {
+            subtrees.add_source(source, other.scanned.as_ref().map(|s| s.size));
+        }
+        if let Action::Move { to, .. } = &other.action {
+            let to = Path::new(to);
+            if to != path && to.starts_with(path) {
+                subtrees.destinations.push(to.to_path_buf());
+            }
+        }
+    }
+    compare(scanned, path, &subtrees)
+}
+
+/// 计划中其他动作作用于目标之下的路径。核对目录时跳过这些子树，并从扫描时的子项数与大小中减去源路径所占的部分
+#[derive(Debug, Default)]
+struct PlanSubtrees {
+    /// 其他动作的源路径与其扫描时的大小：扫描时存在，计入了目标扫描时的子项数与大小
+    sources: Vec<(PathBuf, Option<u64>)>,
+    /// 移动到目标之下的目的路径：扫描时不存在
+    destinations: Vec<PathBuf>,
+}
+
+impl PlanSubtrees {
+    fn add_source(&mut self, path: &Path, size: Option<u64>) {
+        if !self.sources.iter().any(|(p, _)| p == path) {
+            self.sources.push((path.to_path_buf(), size));
+        }
+    }
+
+    fn skips(&self, path: &Path) -> bool {
+        self.sources.iter().any(|(p, _)| p == path) || self.destinations.iter().any(|p| p == path)
+    }
+
+    /// 源路径中是 `dir` 直接子项的个数
+    fn direct_sources(&self, dir: &Path) -> usize {
+        self.sources
+            .iter()
+            .filter(|(p, _)| p.parent() == Some(dir))
+            .count()
+    }
+
+    /// 源路径扫描时的大小合计（嵌套的源路径只计最外层）；有源路径没有记录扫描时状态时为 None
+    fn scanned_bytes(&self) -> Option<u64> {
+        self.sources
+            .iter()
+            .filter(|(p, _)| !self.sources.iter().any(|(q, _)| q != p && p.starts_with(q)))
+            .map(|(_, size)| *size)
+            .sum()
+    }
+}
+
+fn compare(scanned: &TargetState, path: &Path, subtrees: &PlanSubtrees) -> Option<String> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    match (scanned.is_dir, metadata.is_dir()) {
+        (false, true) => return Some("扫描时是文件，现在是目录".to_string()),
+        (true, false) => return Some("扫描时是目录，现在是文件".to_string()),
+        (false, false) => {
+            if metadata.len() != scanned.size {
+                return Some(size_changed(scanned.size, metadata.len()));
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+            return match (scanned.modified, modified) {
+                (Some(then), Some(now)) if now > then.saturating_add(MODIFIED_TOLERANCE_SECS) => {
+                    Some("扫描后被修改过".to_string())
+                }
+                _ => None,
+            };
+        }
+        (true, true) => {}
+    }
+    // 计划内的子树按扫描时的状态计入，说明中的数字与扫描结果一致
+    let children = fs::read_dir(path)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| !subtrees.skips(&e.path()))
+        .count()
+        + subtrees.direct_sources(path);
+    if let Some(count) = scanned.child_count.filter(|&count| count != children) {
+        return Some(format!("子项数由 {} 变为 {}", count, children));
+    }
+    let planned_bytes = subtrees.scanned_bytes()?;
+    match bounded_size(path, WALK_LIMIT, subtrees) {
+        Some(size) if size.saturating_add(planned_bytes) != scanned.size => Some(size_changed(
+            scanned.size,
+            size.saturating_add(planned_bytes),
+        )),
+        _ => None,
+    }
+}
+
+fn size_changed(before: u64, after: u64) -> String {
+    format!("大小由 {} 字节变为 {} 字节", before, after)
+}
+
+/// 目录下所有文件大小之和，不跟随符号链接，跳过计划内的子树；条目超过 `limit` 时为 None
+fn bounded_size(dir: &Path, limit: usize, subtrees: &PlanSubtrees) -> Option<u64> {
+    let mut stack = vec![dir.to_path_buf()];
+    let (mut total, mut seen) = (0u64, 0usize);
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if subtrees.skips(&entry.path()) {
+                continue;
+            }
+            seen += 1;
+            if seen > limit {
+                return None;
+            }
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => stack.push(entry.path()),
+                Ok(metadata) => total = total.saturating_add(metadata.len()),
+                Err(_) => {}
+            }
+        }
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![1u8; len]).unwrap();
+    }
+
+    fn set_modified(path: &Path, secs: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    fn now_secs() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn test_file_changes_beyond_tolerance() {
+        let none = PlanSubtrees::default();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("setup.exe");
+        write(&file, 100);
+        let scanned_at = now_secs() - 3600;
+        set_modified(&file, scanned_at);
+        let scanned = TargetState {
+            is_dir: false,
+            size: 100,
+            modified: Some(scanned_at),
+            child_count: None,
+        };
+        assert_eq!(compare(&scanned, &file, &none), None);
+        // 文件系统的时间精度内不算修改
+        set_modified(&file, scanned_at + MODIFIED_TOLERANCE_SECS);
+        assert_eq!(compare(&scanned, &file, &none), None);
+        // 比扫描时旧的修改时间（如从备份还原）同样不算
+        set_modified(&file, scanned_at - 60);
+        assert_eq!(compare(&scanned, &file, &none), None);
+
+        set_modified(&file, scanned_at + 60);
+        assert_eq!(compare(&scanned, &file, &none).unwrap(), "扫描后被修改过");
+        write(&file, 120);
+        assert_eq!(
+            compare(&scanned, &file, &none).unwrap(),
+            "大小由 100 字节变为 120 字节"
+        );
+        fs::remove_file(&file).unwrap();
+        assert_eq!(compare(&scanned, &file, &none), None);
+        fs::create_dir(&file).unwrap();
+        assert_eq!(
+            compare(&scanned, &file, &none).unwrap(),
+            "扫描时是文件，现在是目录"
+        );
+    }
+
+    #[test]
+    fn test_directory_compares_children_and_total_size() {
+        let none = PlanSubtrees::default();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("OldProject");
+        write(&root.join("build").join("out.bin"), 300);
+        write(&root.join("notes.txt"), 20);
+        let scanned = TargetState {
+            is_dir: true,
+            size: 320,
+            modified: None,
+            child_count: Some(2),
+        };
+        assert_eq!(compare(&scanned, &root, &none), None);
+        // 目录自身的修改时间不参与比较
+        set_modified(&root.join("notes.txt"), now_secs() + 3600);
+        assert_eq!(compare(&scanned, &root, &none), None);
+
+        write(&root.join("build").join("new.bin"), 5);
+        assert_eq!(
+            compare(&scanned, &root, &none).unwrap(),
+            "大小由 320 字节变为 325 字节"
+        );
+        write(&root.join("draft.docx"), 0);
+        assert_eq!(
+            compare(&scanned, &root, &none).unwrap(),
+            "子项数由 2 变为 3"
+        );
+        // 扫描没有列出子项时只比较大小
+        let unlisted = TargetState {
+            child_count: None,
+            size: 325,
+            ..scanned
+        };
+        assert_eq!(compare(&unlisted, &root, &none), None);
+        // 条目过多时不计算大小
+        assert_eq!(bounded_size(&root, 4, &none), None);
+        assert_eq!(bounded_size(&root, 5, &none), Some(325));
+    }
+
+    #[test]
+    fn test_only_source_paths_are_checked() {
+        let planned = |action| PlannedAction {
+            action,
+            bytes: 0,
+            risk: ai_disk_domain::RiskLevel::Low,
+            reason: String::new(),
+            scanned: Some(TargetState {
+                is_dir: false,
+                size: 1,
+                modified: None,
+                child_count: None,
+            }),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let trash = dir.path().join(".Trash");
+        write(&trash.join("a.dmg"), 700);
+        let path = trash.to_string_lossy().into_owned();
+        let inner = trash.join("a.dmg").to_string_lossy().into_owned();
+        let actions = vec![
+            planned(Action::EmptyTrash { path: path.clone() }),
+            planned(Action::Delete { path: path.clone() }),
+        ];
+        assert_eq!(target_change(&actions, 0), None);
+        assert!(target_change(&actions, 1).is_some());
+        // 计划中另有动作作用于目标之下时，这些子树的改变来自计划本身
+        let dir_state = |size, children| {
+            Some(TargetState {
+                is_dir: true,
+                size,
+                modified: None,
+                child_count: Some(children),
+            })
+        };
+        let nested = vec![
+            PlannedAction {
+                scanned: Some(TargetState {
+                    is_dir: false,
+                    size: 700,
+                    modified: None,
+                    child_count: None,
+                }),
+                ..planned(Action::Delete { path: inner })
+            },
+            PlannedAction {
+                scanned: dir_state(700, 1),
+                ..planned(Action::Delete { path })
+            },
+        ];
+        assert_eq!(target_change(&nested, 1), None);
+
+        // 删除 proj/node_modules 与 proj、并把另一个目录移入 proj 的计划：
+        // 计划内的子树不算改变，用户新增的同级文件仍能发现
+        let proj = dir.path().join("proj");
+        let node_modules = proj.join("node_modules");
+        write(&node_modules.join("lib.js"), 50);
+        write(&proj.join("main.rs"), 10);
+        let outside = dir.path().join("assets");
+        write(&outside.join("logo.png"), 7);
+        let moved = proj.join("assets");
+        let plan = vec![
+            PlannedAction {
+                scanned: dir_state(50, 1),
+                ..planned(Action::Delete {
+                    path: node_modules.to_string_lossy().into_owned(),
+                })
+            },
+            planned(Action::Move {
+                from: outside.to_string_lossy().into_owned(),
+                to: moved.to_string_lossy().into_owned(),
+            }),
+            PlannedAction {
+                scanned: dir_state(60, 2),
+                ..planned(Action::Delete {
+                    path: proj.to_string_lossy().into_owned(),
+                })
+            },
+        ];
+        assert_eq!(target_change(&plan, 2), None);
+        // 前面的动作执行后
+        fs::remove_dir_all(&node_modules).unwrap();
+        fs::rename(&outside, &moved).unwrap();
+        assert_eq!(target_change(&plan, 2), None);
+
+        write(&proj.join("draft.txt"), 5);
+        assert_eq!(target_change(&plan, 2).unwrap(), "子项数由 2 变为 3");
+        fs::remove_file(proj.join("draft.txt")).unwrap();
+        write(&proj.join("main.rs"), 15);
+        assert_eq!(
+            target_change(&plan, 2).unwrap(),
+            "大小由 60 字节变为 65 字节"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! 压缩 WSL 发行版与 Docker Desktop 的虚拟磁盘（`CompactVhd` 动作）。
+//!
+//! - 虚拟磁盘属于已注册的 WSL 发行版（Docker Desktop 的数据盘也注册为发行版）时，
+//!   `wsl --shutdown` 后执行 `wsl --manage <发行版> --set-sparse true`，之后 WSL 会自动归还已释放的空间；
+//! - 找不到对应发行版时改用 Hyper-V 的 `Optimize-VHD -Mode Full`，需要管理员权限并启用 Hyper-V。
+//!
+//! 发行版名从注册表 `HKCU\Software\Microsoft\Windows\CurrentVersion\Lxss` 中按 BasePath 查找。
+//! 命令行的构造与注册表输出的解析不依赖平台，可在任意平台测试；执行部分只在 Windows 上编译，
+//! 真正压缩的测试需要显式开启（见测试中的说明）。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// 注册表中 WSL 发行版列表所在的键
+pub const LXSS_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Lxss";
+
+/// 压缩方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum CompactMethod {
+    /// 把发行版的虚拟磁盘设为稀疏，由 WSL 自动回收空间
+    SetSparse { distro: String },
+    /// Hyper-V 的 Optimize-VHD
+    OptimizeVhd,
+}
+
+/// 注册表中的一个 WSL 发行版
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WslDistro {
+    pub name: String,
+    /// 发行版虚拟磁盘所在的目录
+    pub base_path: String,
+}
+
+/// 压缩结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactReport {
+    pub method: CompactMethod,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// 解析 `reg query <LXSS_KEY> /s` 的输出
+pub fn parse_lxss_distros(output: &str) -> Vec<WslDistro> {
+    let mut distros = Vec::new();
+    let mut name = None;
+    let mut base_path = None;
+    let mut flush = |name: &mut Option<String>, base_path: &mut Option<String>| {
+        if let (Some(name), Some(base_path)) = (name.take(), base_path.take()) {
+            distros.push(WslDistro { name, base_path });
+        }
+    };
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("HKEY_") {
+            flush(&mut name, &mut base_path);
+            continue;
+        }
+        let Some((key, value)) = line.split_once("REG_SZ") else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "DistributionName" => name = Some(value),
+            "BasePath" => base_path = Some(value),
+            _ => {}
+        }
+    }
+    flush(&mut name, &mut base_path);
+    distros
+}
+
+/// 统一路径写法用于比较：去掉 `\\?\` 前缀与末尾分隔符，转为小写
+fn normalize_dir(path: &str) -> String {
+    path.trim_start_matches(r"\\?\")
+        .trim_end_matches(['\\', '/'])
+        .replace('/', "\\")
+        .to_lowercase()
+}
+
+/// 虚拟磁盘所在目录与 BasePath 一致的发行版
+pub fn distro_for_vhd<'a>(vhd: &Path, distros: &'a [WslDistro]) -> Option<&'a WslDistro> {
+    let dir = normalize_dir(&vhd.parent()?.to_string_lossy());
+    distros
+        .iter()
+        .find(|distro| normalize_dir(&distro.base_path) == dir)
+}
+
+/// 按方式选择要依次执行的命令（每条为程序名加参数）
+pub fn compact_commands(vhd: &str, method: &CompactMethod) -> Vec<Vec<String>> {
+    let shutdown = vec!["wsl".to_string(), "--shutdown".to_string()];
+    let compact = match method {
+        CompactMethod::SetSparse { distro } => vec![
+            "wsl".to_string(),
+            "--manage".to_string(),
+            distro.clone(),
+            "--set-sparse".to_string(),
+            "true".to_string(),
+        ],
+        CompactMethod::OptimizeVhd => vec![
+            "powershell".to_string(),
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            // PowerShell 单引号字符串中的 ' 写作 ''
+            format!(
+                "Optimize-VHD -Path '{}' -Mode Full",
+                vhd.replace('\'', "''")
+            ),
+        ],
+    };
+    vec![shutdown, compact]
+}
+
+/// 拼成可直接粘贴到命令提示符中运行的一行：含空格或引号的参数加双引号
+pub fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains([' ', '\t', '"']) {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    use ai_disk_common::DiskAnalyzerError;
+
+    use super::*;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    /// 未提权时返回的错误码
+    const ERROR_ELEVATION_REQUIRED: i32 = 740;
+
+    fn run(argv: &[String]) -> Result<(), DiskAnalyzerError> {
+        let output = Command::new(&argv[0])
+            .args(&argv[1..])
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdin(Stdio::null())
+            .output()?;
+        match output.status.code() {
+            Some(0) => Ok(()),
+            Some(ERROR_ELEVATION_REQUIRED) => Err(DiskAnalyzerError::NeedsElevation(format!(
+                "{} 需要管理员权限",
+                command_line(argv)
+            ))),
+            code => {
+                // wsl.exe 以 UTF-16 输出，按字节解码会夹杂 NUL
+                let stderr = String::from_utf8_lossy(&output.stderr).replace('\0', "");
+                let stdout = String::from_utf8_lossy(&output.stdout).replace('\0', "");
+                let message = if stderr.trim().is_empty() {
+                    stdout
+                } else {
+                    stderr
+                };
+                Err(DiskAnalyzerError::Io(std::io::Error::other(format!(
+                    "{} 退出码 {:?}: {}",
+                    command_line(argv),
+                    code,
+                    message.trim()
+                ))))
+            }
+        }
+    }
+
+    /// 注册表中的 WSL 发行版；没有安装 WSL 时为空
+    pub fn list_wsl_distros() -> Vec<WslDistro> {
+        Command::new("reg.exe")
+            .args(["query", LXSS_KEY, "/s"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdin(Stdio::null())
+            .output()
+            .map(|output| parse_lxss_distros(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    }
+
+    /// 压缩方式：能找到对应发行版时设为稀疏，否则用 Optimize-VHD
+    pub fn compact_method_for(vhd: &Path) -> CompactMethod {
+        match distro_for_vhd(vhd, &list_wsl_distros()) {
+            Some(distro) => CompactMethod::SetSparse {
+                distro: distro.name.clone(),
+            },
+            None => CompactMethod::OptimizeVhd,
+        }
+    }
+
+    /// 关闭 WSL 后压缩虚拟磁盘 `vhd`
+    pub fn compact_vhd(vhd: &Path) -> Result<CompactReport, DiskAnalyzerError> {
+        let bytes_before = std::fs::metadata(vhd)?.len();
+        let method = compact_method_for(vhd);
+        for argv in compact_commands(&vhd.to_string_lossy(), &method) {
+            run(&argv).map_err(|e| match (&method, e) {
+                (CompactMethod::OptimizeVhd, DiskAnalyzerError::Io(e)) => {
+                    DiskAnalyzerError::Io(std::io::Error::other(format!(
+                        "{}（Optimize-VHD 需要以管理员身份运行并启用 Hyper-V）",
+                        e
+                    )))
+                }
+                (_, e) => e,
+            })?;
+        }
+        Ok(CompactReport {
+            method,
+            bytes_before,
+            bytes_after: std::fs::metadata(vhd)?.len(),
+        })
+    }
+}
+
+#[cfg(windows)]
+pub use imp::{compact_method_for, compact_vhd, list_wsl_distros};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REG_OUTPUT: &str = r"
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Lxss
+    DefaultDistribution    REG_SZ    {a1b2c3d4-0000-0000-0000-000000000001}
+
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Lxss\{a1b2c3d4-0000-0000-0000-000000000001}
+    State    REG_DWORD    0x1
+    DistributionName    REG_SZ    Ubuntu-22.04
+    Version    REG_DWORD    0x2
+    BasePath    REG_SZ    C:\Users\u\AppData\Local\Packages\CanonicalGroupLimited.Ubuntu22.04LTS_79rhkp1fndgsc\LocalState
+    Flags    REG_DWORD    0xf
+
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Lxss\{a1b2c3d4-0000-0000-0000-000000000002}
+    BasePath    REG_SZ    \\?\C:\Users\u\AppData\Local\Docker\wsl\disk
+    DistributionName    REG_SZ    docker-desktop
+";
+
+    #[test]
+    fn test_parse_lxss_and_match_distro() {
+        let distros = parse_lxss_distros(REG_OUTPUT);
+        assert_eq!(distros.len(), 2);
+        assert_eq!(distros[0].name, "Ubuntu-22.04");
+
+        let ubuntu = Path::new(
+            r"C:\Users\u\AppData\Local\Packages\CanonicalGroupLimited.Ubuntu22.04LTS_79rhkp1fndgsc\LocalState\ext4.vhdx",
+        );
+        let docker = Path::new(r"c:\users\u\appdata\local\docker\wsl\disk\docker_data.vhdx");
+        let other = Path::new(r"D:\VMs\ext4.vhdx");
+        // Path::parent 只在 Windows 上按反斜杠拆分，其他平台用正斜杠写法验证
+        let (ubuntu, docker, other) = if cfg!(windows) {
+            (
+                ubuntu.to_path_buf(),
+                docker.to_path_buf(),
+                other.to_path_buf(),
+            )
+        } else {
+            let slash = |p: &Path| Path::new(&p.to_string_lossy().replace('\\', "/")).to_path_buf();
+            (slash(ubuntu), slash(docker), slash(other))
+        };
+        assert_eq!(
+            distro_for_vhd(&ubuntu, &distros).unwrap().name,
+            "Ubuntu-22.04"
+        );
+        assert_eq!(
+            distro_for_vhd(&docker, &distros).unwrap().name,
+            "docker-desktop"
+        );
+        assert!(distro_for_vhd(&other, &distros).is_none());
+    }
+
+    #[test]
+    fn test_set_sparse_command_lines() {
+        let commands = compact_commands(
+            r"C:\x\ext4.vhdx",
+            &CompactMethod::SetSparse {
+                distro: "Ubuntu-22.04".to_string(),
+            },
+        );
+        let lines: Vec<String> = commands.iter().map(|argv| command_line(argv)).collect();
+        assert_eq!(
+            lines,
+            [
+                "wsl --shutdown",
+                "wsl --manage Ubuntu-22.04 --set-sparse true"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optimize_vhd_quotes_path() {
+        let vhd = r"C:\Users\O'Brien\AppData\Local\Docker\wsl\data\ext4.vhdx";
+        let commands = compact_commands(vhd, &CompactMethod::OptimizeVhd);
+        assert_eq!(commands[0], ["wsl", "--shutdown"]);
+        assert_eq!(
+            commands[1][4],
+            r"Optimize-VHD -Path 'C:\Users\O''Brien\AppData\Local\Docker\wsl\data\ext4.vhdx' -Mode Full"
+        );
+        assert_eq!(
+            command_line(&commands[1]),
+            r#"powershell -NoProfile -NonInteractive -Command "Optimize-VHD -Path 'C:\Users\O''Brien\AppData\Local\Docker\wsl\data\ext4.vhdx' -Mode Full""#
+        );
+    }
+
+    /// 真正压缩一个虚拟磁盘：会关闭所有 WSL 发行版。
+    /// 运行：set AI_DISK_VHD_TEST_PATH=<vhdx 路径> 后 cargo test -p ai-disk-executor vhd -- --ignored
+    #[cfg(windows)]
+    #[test]
+    #[ignore]
+    fn integration_compact_vhd() {
+        let Ok(path) = std::env::var("AI_DISK_VHD_TEST_PATH") else {
+            return;
+        };
+        let report = compact_vhd(Path::new(&path)).unwrap();
+        assert!(report.bytes_after <= report.bytes_before);
+    }
+}
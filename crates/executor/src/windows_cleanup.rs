@@ -0,0 +1,602 @@
+//! Windows 组件清理：旧的 Windows 更新文件、传递优化缓存、以前的 Windows 安装等
+//! 不能直接删除，需要交给系统工具处理。
+//!
+//! - 估算：`DISM /Online /Cleanup-Image /AnalyzeComponentStore`，解析输出中的各项大小
+//! - 清理：组件存储交给 `DISM /StartComponentCleanup`；其他类别通过 `VolumeCaches` 下的
+//!   `StateFlags` 注册表值勾选后执行 `cleanmgr /sagerun`
+//!
+//! 以上操作都需要管理员权限，由调用方在执行前检查。输出解析不依赖界面语言，
+//! 可在任意平台测试；执行部分只在 Windows 上编译。
+
+use serde::{Deserialize, Serialize};
+
+/// 可清理的 Windows 组件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowsCleanupCategory {
+    /// 组件存储（WinSxS）中被替代的组件，通过 DISM 清理
+    ComponentStore,
+    UpdateCleanup,
+    DeliveryOptimization,
+    PreviousInstallations,
+    TemporarySetupFiles,
+    UpgradeLogs,
+    ErrorReports,
+    TemporaryFiles,
+    ThumbnailCache,
+}
+
+impl WindowsCleanupCategory {
+    pub const ALL: &'static [WindowsCleanupCategory] = &[
+        WindowsCleanupCategory::ComponentStore,
+        WindowsCleanupCategory::UpdateCleanup,
+        WindowsCleanupCategory::DeliveryOptimization,
+        WindowsCleanupCategory::PreviousInstallations,
+        WindowsCleanupCategory::TemporarySetupFiles,
+        WindowsCleanupCategory::UpgradeLogs,
+        WindowsCleanupCategory::ErrorReports,
+        WindowsCleanupCategory::TemporaryFiles,
+        WindowsCleanupCategory::ThumbnailCache,
+    ];
+
+    /// 磁盘清理（cleanmgr）在 `VolumeCaches` 下对应的处理程序名；组件存储不经过 cleanmgr
+    pub fn volume_cache_handler(self) -> Option<&'static str> {
+        match self {
+            WindowsCleanupCategory::ComponentStore => None,
+            WindowsCleanupCategory::UpdateCleanup => Some("Update Cleanup"),
+            WindowsCleanupCategory::DeliveryOptimization => Some("Delivery Optimization Files"),
+            WindowsCleanupCategory::PreviousInstallations => Some("Previous Installations"),
+            WindowsCleanupCategory::TemporarySetupFiles => Some("Temporary Setup Files"),
+            WindowsCleanupCategory::UpgradeLogs => Some("Windows Upgrade Log Files"),
+            WindowsCleanupCategory::ErrorReports => Some("Windows Error Reporting Files"),
+            WindowsCleanupCategory::TemporaryFiles => Some("Temporary Files"),
+            WindowsCleanupCategory::ThumbnailCache => Some("Thumbnail Cache"),
+        }
+    }
+}
+
+/// `DISM /AnalyzeComponentStore` 的解析结果（字节）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentStoreAnalysis {
+    pub explorer_reported_bytes: u64,
+    pub actual_bytes: u64,
+    pub shared_with_windows_bytes: u64,
+    pub backups_and_disabled_bytes: u64,
+    pub cache_and_temporary_bytes: u64,
+    pub reclaimable_packages: Option<u32>,
+    pub cleanup_recommended: Option<bool>,
+    /// 组件清理预计可释放的空间：备份与已禁用功能 + 缓存与临时数据
+    pub reclaimable_bytes: u64,
+}
+
+/// 清理过程中的进度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowsCleanupProgress {
+    /// 当前步骤：`component_store` 或 `cleanmgr`
+    pub stage: String,
+    /// 从工具输出中识别出的百分比
+    pub percent: Option<f32>,
+    /// 工具输出的原始行
+    pub message: String,
+}
+
+/// 一次清理的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowsCleanupReport {
+    pub completed: Vec<WindowsCleanupCategory>,
+    /// 当前系统上不存在对应处理程序的类别（例如没有以前的 Windows 安装）
+    pub unavailable: Vec<WindowsCleanupCategory>,
+}
+
+/// 各语言 DISM 输出中表示「是 / 否」的值（小写）
+const YES_VALUES: &[&str] = &[
+    "yes", "ja", "oui", "sí", "si", "sim", "tak", "да", "是", "はい", "예",
+];
+const NO_VALUES: &[&str] = &[
+    "no",
+    "nein",
+    "non",
+    "não",
+    "nie",
+    "нет",
+    "否",
+    "いいえ",
+    "아니요",
+];
+
+/// 解析带单位的大小，例如 `269.41 MB`、`8,35 GB`、`2,23 Go`、`1,5 ГБ`
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().replace('\u{a0}', " ");
+    let (number, unit) = value.split_once(' ')?;
+    let number = if number.contains('.') {
+        number.replace(',', "")
+    } else {
+        number.replace(',', ".")
+    };
+    let number: f64 = number.parse().ok()?;
+    let unit = unit.trim().to_lowercase();
+    let multiplier: u64 = match unit.chars().next()? {
+        'b' | 'o' | 'б' | '字' => 1,
+        'k' | 'к' => 1 << 10,
+        'm' | 'м' => 1 << 20,
+        'g' | 'г' => 1 << 30,
+        't' | 'т' => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+/// 解析 `DISM /AnalyzeComponentStore` 的输出。
+///
+/// 各项标签随系统语言变化，但顺序固定：资源管理器报告的大小、实际大小、与 Windows 共享、
+/// 备份和已禁用的功能、缓存和临时数据，然后是上次清理日期、可回收包数、是否建议清理。
+/// 因此按「标签 : 值」行的值类型与出现顺序解析，而不匹配标签文字
+pub fn parse_component_store_analysis(output: &str) -> Option<ComponentStoreAnalysis> {
+    let mut sizes = Vec::new();
+    let mut reclaimable_packages = None;
+    let mut cleanup_recommended = None;
+    for line in output.lines() {
+        let Some((_, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        let value = value.trim();
+        if let Some(size) = parse_size(value) {
+            sizes.push(size);
+        } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+            reclaimable_packages = reclaimable_packages.or(value.parse().ok());
+        } else {
+            let lower = value.to_lowercase();
+            if YES_VALUES.contains(&lower.as_str()) {
+                cleanup_recommended = Some(true);
+            } else if NO_VALUES.contains(&lower.as_str()) {
+                cleanup_recommended = Some(false);
+            }
+        }
+    }
+    let [explorer, actual, shared, backups, cache] = sizes.get(..5)? else {
+        return None;
+    };
+    Some(ComponentStoreAnalysis {
+        explorer_reported_bytes: *explorer,
+        actual_bytes: *actual,
+        shared_with_windows_bytes: *shared,
+        backups_and_disabled_bytes: *backups,
+        cache_and_temporary_bytes: *cache,
+        reclaimable_packages,
+        cleanup_recommended,
+        reclaimable_bytes: backups.saturating_add(*cache),
+    })
+}
+
+/// 从 DISM 进度条 `[=====  45.2%  ====]` 中取出百分比
+pub fn parse_progress_percent(line: &str) -> Option<f32> {
+    let end = line.find('%')?;
+    let start = line[..end]
+        .rfind(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let percent: f32 = line[start..end].replace(',', ".").parse().ok()?;
+    (0.0..=100.0).contains(&percent).then_some(percent)
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io::Read;
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    use ai_disk_common::DiskAnalyzerError;
+
+    use super::*;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    /// DISM 在未提权时返回的错误码
+    const ERROR_ELEVATION_REQUIRED: i32 = 740;
+    /// `cleanmgr /sagerun:N` 使用的配置编号，对应注册表值 `StateFlags0042`
+    const SAGE_ID: u32 = 42;
+    const VOLUME_CACHES_KEY: &str =
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer\VolumeCaches";
+
+    fn command(program: &str) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+
+    /// 运行命令并逐行回调输出（DISM 用 `\r` 刷新进度条，因此 `\r` 也视为换行）
+    fn run_streaming(
+        mut cmd: Command,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(i32, String), DiskAnalyzerError> {
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut output = Vec::new();
+        let mut line = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stdout.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            for &byte in &buf[..n] {
+                output.push(byte);
+                if byte == b'\r' || byte == b'\n' {
+                    if !line.is_empty() {
+                        on_line(String::from_utf8_lossy(&line).trim());
+                        line.clear();
+                    }
+                } else {
+                    line.push(byte);
+                }
+            }
+        }
+        if !line.is_empty() {
+            on_line(String::from_utf8_lossy(&line).trim());
+        }
+        let status = child.wait()?;
+        Ok((
+            status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output).into_owned(),
+        ))
+    }
+
+    fn check_exit(tool: &str, code: i32, output: &str) -> Result<(), DiskAnalyzerError> {
+        match code {
+            0 => Ok(()),
+            ERROR_ELEVATION_REQUIRED => Err(DiskAnalyzerError::NeedsElevation(format!(
+                "{} 需要管理员权限",
+                tool
+            ))),
+            _ => {
+                let tail: Vec<&str> = output.lines().rev().take(5).collect();
+                let tail: Vec<&str> = tail.into_iter().rev().collect();
+                Err(DiskAnalyzerError::Io(std::io::Error::other(format!(
+                    "{} 退出码 {}: {}",
+                    tool,
+                    code,
+                    tail.join(" ").trim()
+                ))))
+            }
+        }
+    }
+
+    fn dism(args: &[&str]) -> Command {
+        let mut cmd = command("dism.exe");
+        // /English 让输出不随系统语言变化；解析器仍兼容本地化输出
+        cmd.args(["/Online", "/English", "/Cleanup-Image"])
+            .args(args);
+        cmd
+    }
+
+    /// 运行 `DISM /AnalyzeComponentStore` 并解析可回收空间
+    pub fn analyze_component_store() -> Result<ComponentStoreAnalysis, DiskAnalyzerError> {
+        let (code, output) = run_streaming(dism(&["/AnalyzeComponentStore"]), &mut |_| {})?;
+        check_exit("DISM", code, &output)?;
+        parse_component_store_analysis(&output).ok_or_else(|| {
+            DiskAnalyzerError::Io(std::io::Error::other("无法解析 DISM 组件存储分析结果"))
+        })
+    }
+
+    fn handler_key(handler: &str) -> String {
+        format!(r"{}\{}", VOLUME_CACHES_KEY, handler)
+    }
+
+    fn reg(args: &[&str]) -> Result<bool, DiskAnalyzerError> {
+        let status = command("reg.exe")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+
+    fn state_flags_value() -> String {
+        format!("StateFlags{:04}", SAGE_ID)
+    }
+
+    /// 删除本程序写入的 StateFlags，避免影响之后的 cleanmgr 运行
+    fn clear_state_flags() {
+        for category in WindowsCleanupCategory::ALL {
+            if let Some(handler) = category.volume_cache_handler() {
+                let _ = reg(&[
+                    "delete",
+                    &handler_key(handler),
+                    "/v",
+                    &state_flags_value(),
+                    "/f",
+                ]);
+            }
+        }
+    }
+
+    /// 执行所选类别的清理，通过 `on_progress` 报告工具输出
+    pub fn run_windows_cleanup(
+        categories: &[WindowsCleanupCategory],
+        on_progress: &mut dyn FnMut(WindowsCleanupProgress),
+    ) -> Result<WindowsCleanupReport, DiskAnalyzerError> {
+        let mut report = WindowsCleanupReport {
+            completed: Vec::new(),
+            unavailable: Vec::new(),
+        };
+
+        if categories.contains(&WindowsCleanupCategory::ComponentStore) {
+            let (code, output) = run_streaming(dism(&["/StartComponentCleanup"]), &mut |line| {
+                on_progress(WindowsCleanupProgress {
+                    stage: "component_store".into(),
+                    percent: parse_progress_percent(line),
+                    message: line.to_string(),
+                });
+            })?;
+            check_exit("DISM", code, &output)?;
+            report
+                .completed
+                .push(WindowsCleanupCategory::ComponentStore);
+        }
+
+        // 先清掉上次可能残留的勾选，只勾选本次选择且在本机存在的处理程序
+        clear_state_flags();
+        let mut selected = Vec::new();
+        for &category in categories {
+            let Some(handler) = category.volume_cache_handler() else {
+                continue;
+            };
+            let key = handler_key(handler);
+            // 不存在的处理程序不能用 reg add 创建，否则会留下无效的注册表项
+            if !reg(&["query", &key])? {
+                report.unavailable.push(category);
+                continue;
+            }
+            if !reg(&[
+                "add",
+                &key,
+                "/v",
+                &state_flags_value(),
+                "/t",
+                "REG_DWORD",
+                "/d",
+                "2",
+                "/f",
+            ])? {
+                clear_state_flags();
+                return Err(DiskAnalyzerError::NeedsElevation(
+                    "写入磁盘清理配置需要管理员权限".into(),
+                ));
+            }
+            selected.push(category);
+        }
+
+        if !selected.is_empty() {
+            on_progress(WindowsCleanupProgress {
+                stage: "cleanmgr".into(),
+                percent: Some(0.0),
+                message: "正在运行磁盘清理".into(),
+            });
+            let mut cmd = command("cleanmgr.exe");
+            cmd.arg(format!("/sagerun:{}", SAGE_ID));
+            let result = run_streaming(cmd, &mut |_| {});
+            clear_state_flags();
+            let (code, output) = result?;
+            check_exit("cleanmgr", code, &output)?;
+            on_progress(WindowsCleanupProgress {
+                stage: "cleanmgr".into(),
+                percent: Some(100.0),
+                message: "磁盘清理完成".into(),
+            });
+            report.completed.extend(selected);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(windows)]
+pub use imp::{analyze_component_store, run_windows_cleanup};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: f64 = (1u64 << 30) as f64;
+    const MB: f64 = (1u64 << 20) as f64;
+
+    const EN_US: &str = "\
+Deployment Image Servicing and Management tool
+Version: 10.0.19041.844
+
+Image Version: 10.0.19045.3693
+
+[==========================100.0%==========================]
+
+Component Store (WinSxS) information:
+
+Windows Explorer Reported Size of Component Store : 8.35 GB
+
+Actual Size of Component Store : 8.10 GB
+
+    Shared with Windows : 5.60 GB
+    Backups and Disabled Features : 2.23 GB
+    Cache and Temporary Data :  269.41 MB
+
+Date of Last Cleanup : 2023-11-20 10:15:42
+
+Number of Reclaimable Packages : 2
+Component Store Cleanup Recommended : Yes
+
+The operation completed successfully.
+";
+
+    const DE_DE: &str = "\
+Tool zur Imageverwaltung für die Bereitstellung
+Version: 10.0.22621.1
+
+Abbildversion: 10.0.22631.2861
+
+[==========================100.0%==========================]
+
+Informationen zum Komponentenspeicher (WinSxS):
+
+Vom Windows-Explorer gemeldete Größe des Komponentenspeichers : 7,91 GB
+
+Tatsächliche Größe des Komponentenspeichers : 7,62 GB
+
+    Mit Windows gemeinsam genutzt : 5,48 GB
+    Sicherungen und deaktivierte Features : 1,87 GB
+    Cache und temporäre Daten : 0 Bytes
+
+Datum der letzten Bereinigung : 2024-01-09 08:02:11
+
+Anzahl der freigebbaren Pakete : 0
+Komponentenspeicherbereinigung empfohlen : Nein
+
+Der Vorgang wurde erfolgreich beendet.
+";
+
+    const FR_FR: &str = "\
+Outil Gestion et maintenance des images de déploiement
+Version : 10.0.19041.3636
+
+Version de l’image : 10.0.19045.3803
+
+[==========================100.0%==========================]
+
+Informations sur le magasin de composants (WinSxS) :
+
+Taille du magasin de composants signalée par l’Explorateur Windows : 9,12 Go
+
+Taille réelle du magasin de composants : 8,80 Go
+
+    Partagé avec Windows : 5,91 Go
+    Sauvegardes et fonctionnalités désactivées : 2,57 Go
+    Cache et données temporaires : 321,55 Mo
+
+Date du dernier nettoyage : 2023-12-02 19:44:03
+
+Nombre de packages récupérables : 3
+Nettoyage du magasin de composants recommandé : Oui
+
+L’opération a réussi.
+";
+
+    const ZH_CN: &str = "\
+部署映像服务和管理工具
+版本: 10.0.22621.2792
+
+映像版本: 10.0.22631.2861
+
+[==========================100.0%==========================]
+
+组件存储(WinSxS)信息:
+
+Windows 资源管理器报告的组件存储大小 : 10.21 GB
+
+组件存储的实际大小 : 9.87 GB
+
+    已与 Windows 共享 : 6.02 GB
+    备份和已禁用的功能 : 3.41 GB
+    缓存和临时数据 : 448.30 MB
+
+上次清理的日期 : 2023-10-15 21:30:07
+
+可回收的程序包数 : 4
+推荐使用组件存储清理 : 是
+
+操作成功完成。
+";
+
+    fn approx(bytes: u64, expected: f64) {
+        let diff = (bytes as f64 - expected).abs();
+        assert!(diff < 1.0, "{} != {}", bytes, expected);
+    }
+
+    #[test]
+    fn test_parse_english_output() {
+        let analysis = parse_component_store_analysis(EN_US).unwrap();
+        approx(analysis.explorer_reported_bytes, 8.35 * GB);
+        approx(analysis.actual_bytes, 8.10 * GB);
+        approx(analysis.shared_with_windows_bytes, 5.60 * GB);
+        approx(analysis.backups_and_disabled_bytes, 2.23 * GB);
+        approx(analysis.cache_and_temporary_bytes, 269.41 * MB);
+        assert_eq!(
+            analysis.reclaimable_bytes,
+            analysis.backups_and_disabled_bytes + analysis.cache_and_temporary_bytes
+        );
+        assert_eq!(analysis.reclaimable_packages, Some(2));
+        assert_eq!(analysis.cleanup_recommended, Some(true));
+    }
+
+    #[test]
+    fn test_parse_localized_outputs() {
+        let de = parse_component_store_analysis(DE_DE).unwrap();
+        approx(de.actual_bytes, 7.62 * GB);
+        approx(de.backups_and_disabled_bytes, 1.87 * GB);
+        assert_eq!(de.cache_and_temporary_bytes, 0);
+        assert_eq!(de.reclaimable_packages, Some(0));
+        assert_eq!(de.cleanup_recommended, Some(false));
+
+        let fr = parse_component_store_analysis(FR_FR).unwrap();
+        approx(fr.explorer_reported_bytes, 9.12 * GB);
+        approx(fr.cache_and_temporary_bytes, 321.55 * MB);
+        assert_eq!(fr.reclaimable_packages, Some(3));
+        assert_eq!(fr.cleanup_recommended, Some(true));
+
+        let zh = parse_component_store_analysis(ZH_CN).unwrap();
+        approx(zh.backups_and_disabled_bytes, 3.41 * GB);
+        approx(zh.cache_and_temporary_bytes, 448.30 * MB);
+        assert_eq!(zh.reclaimable_packages, Some(4));
+        assert_eq!(zh.cleanup_recommended, Some(true));
+    }
+
+    #[test]
+    fn test_parse_rejects_failed_runs() {
+        let elevation = "\
+Deployment Image Servicing and Management tool
+Version: 10.0.19041.844
+
+Error: 740
+
+Elevated permissions are required to run DISM.
+Use an elevated command prompt to complete these tasks.
+";
+        assert_eq!(parse_component_store_analysis(elevation), None);
+        assert_eq!(parse_component_store_analysis(""), None);
+    }
+
+    #[test]
+    fn test_parse_size_and_progress() {
+        assert_eq!(parse_size("0 bytes"), Some(0));
+        assert_eq!(parse_size("1,5 KB"), Some(1536));
+        assert_eq!(parse_size("1.5 ГБ"), Some(3 << 29));
+        assert_eq!(parse_size("2023-11-20 10:15:42"), None);
+        assert_eq!(parse_size("Yes"), None);
+
+        assert_eq!(
+            parse_progress_percent("[=====            10.0%                ]"),
+            Some(10.0)
+        );
+        assert_eq!(
+            parse_progress_percent("[==========================100.0%==========================]"),
+            Some(100.0)
+        );
+        assert_eq!(parse_progress_percent("[==  45,2%  ]"), Some(45.2));
+        assert_eq!(
+            parse_progress_percent("The operation completed successfully."),
+            None
+        );
+    }
+
+    #[test]
+    fn test_every_category_but_component_store_uses_cleanmgr() {
+        for category in WindowsCleanupCategory::ALL {
+            let handler = category.volume_cache_handler();
+            assert_eq!(
+                handler.is_none(),
+                *category == WindowsCleanupCategory::ComponentStore
+            );
+        }
+        let json = serde_json::to_string(&WindowsCleanupCategory::DeliveryOptimization).unwrap();
+        assert_eq!(json, "\"delivery_optimization\"");
+    }
+}
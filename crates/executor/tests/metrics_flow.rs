@@ -0,0 +1,120 @@
+//! 运行指标：扫描 + 执行一遍后，全局注册表中的计数按次数增加。
+//! 全局注册表在进程内共享，本文件只放这一个测试，计数不受其他测试影响。
+
+use std::fs;
+
+use ai_disk_common::{
+    metrics, DiskAnalyzerError, ExecutionMode, MetricsSnapshot, SnapshotConfig,
+    METRIC_EXECUTOR_ACTIONS, METRIC_SCAN_DURATION_MS,
+};
+use ai_disk_domain::{Action, PlannedAction, RiskLevel};
+use ai_disk_executor::{run_execution, ActionExecutor, ExecutionStep};
+use ai_disk_scanner::{scan_path_with_progress, WalkOptions};
+
+/// 删除文件；路径以 `fail` 结尾的动作失败
+struct DeleteExecutor;
+
+impl ActionExecutor for DeleteExecutor {
+    type Error = DiskAnalyzerError;
+
+    async fn execute(&mut self, step: &ExecutionStep<'_>) -> Result<u64, DiskAnalyzerError> {
+        let path = step.planned.action.source_path();
+        if path.ends_with("fail") {
+            return Err(DiskAnalyzerError::Io(std::io::Error::other("injected")));
+        }
+        fs::remove_file(path)?;
+        Ok(step.planned.bytes)
+    }
+}
+
+fn delete(path: String) -> PlannedAction {
+    PlannedAction {
+        action: Action::Delete { path },
+        bytes: 1,
+        risk: RiskLevel::Low,
+        reason: String::new(),
+        scanned: None,
+    }
+}
+
+fn actions_with(outcome: &str) -> u64 {
+    metrics()
+        .snapshot()
+        .counters
+        .get(METRIC_EXECUTOR_ACTIONS)
+        .and_then(|labels| labels.get(outcome))
+        .copied()
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_scan_and_execute_increment_metrics() {
+    metrics().reset();
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a.tmp", "b.tmp", "c.tmp"] {
+        fs::write(dir.path().join(name), b"x").unwrap();
+    }
+    for _ in 0..2 {
+        scan_path_with_progress(
+            &dir.path().to_string_lossy(),
+            None,
+            false,
+            false,
+            None,
+            None,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+    }
+    let scans = &metrics().snapshot().histograms[METRIC_SCAN_DURATION_MS];
+    assert_eq!(scans["walk"].count, 2);
+    assert!(!scans.contains_key("mft"));
+
+    let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+    let actions = [
+        delete(path("a.tmp")),
+        delete(path("b.tmp")),
+        delete(path("c.tmp")),
+    ];
+    let modes = [
+        ExecutionMode::Permanent,
+        ExecutionMode::Skip,
+        ExecutionMode::Permanent,
+    ];
+    let journal = dir.path().join("journal");
+    futures::executor::block_on(run_execution(
+        &journal,
+        "exec-1",
+        &actions,
+        &modes,
+        &SnapshotConfig::default(),
+        false,
+        &mut DeleteExecutor,
+    ))
+    .unwrap();
+    assert_eq!(actions_with("succeeded"), 2);
+    assert_eq!(actions_with("skipped"), 1);
+
+    let failing = [delete(path("fail"))];
+    assert!(futures::executor::block_on(run_execution(
+        &journal,
+        "exec-2",
+        &failing,
+        &[ExecutionMode::Permanent],
+        &SnapshotConfig::default(),
+        false,
+        &mut DeleteExecutor,
+    ))
+    .is_err());
+    assert_eq!(actions_with("succeeded"), 2);
+    assert_eq!(actions_with("failed"), 1);
+
+    // 没有新的记录时快照的 JSON 不变，且能原样读回
+    let snapshot = metrics().snapshot();
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(serde_json::to_string(&metrics().snapshot()).unwrap(), json);
+    assert_eq!(
+        serde_json::from_str::<MetricsSnapshot>(&json).unwrap(),
+        snapshot
+    );
+}
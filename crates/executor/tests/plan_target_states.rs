@@ -0,0 +1,87 @@
+//! 计划校验时按所依据的扫描记录目标状态：生成计划后被修改的文件在执行时跳过并列为已改变。
+
+use std::fs;
+
+use ai_disk_common::{DiskAnalyzerError, ExecutionMode, SnapshotConfig};
+use ai_disk_domain::{Action, CleanupPlan, PlannedAction, RiskLevel};
+use ai_disk_executor::{run_execution, ActionExecutor, ExecutionStep};
+use ai_disk_scanner::{scan_path_with_progress, WalkOptions};
+
+struct DeleteExecutor;
+
+impl ActionExecutor for DeleteExecutor {
+    type Error = DiskAnalyzerError;
+
+    async fn execute(&mut self, step: &ExecutionStep<'_>) -> Result<u64, DiskAnalyzerError> {
+        fs::remove_file(step.planned.action.source_path())?;
+        Ok(step.planned.bytes)
+    }
+}
+
+#[test]
+fn test_files_modified_after_planning_are_reported_as_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = fs::canonicalize(dir.path()).unwrap();
+    let (old_log, notes) = (root.join("old.log"), root.join("notes.txt"));
+    fs::write(&old_log, b"rotated log").unwrap();
+    fs::write(&notes, b"draft").unwrap();
+    let (scan, _) = scan_path_with_progress(
+        &root.to_string_lossy(),
+        None,
+        false,
+        false,
+        None,
+        None,
+        &WalkOptions::default(),
+    )
+    .unwrap();
+
+    let path = |p: &std::path::Path| p.to_string_lossy().into_owned();
+    let plan = CleanupPlan {
+        actions: vec![
+            Action::Delete {
+                path: path(&old_log),
+            },
+            Action::Delete { path: path(&notes) },
+        ],
+        ..Default::default()
+    };
+    let (plan, rejected) = ai_disk_engine::validate_plan(&plan, &scan);
+    assert!(rejected.is_empty());
+    let actions: Vec<PlannedAction> = plan
+        .actions
+        .into_iter()
+        .zip(plan.targets)
+        .map(|(action, scanned)| PlannedAction {
+            action,
+            bytes: 0,
+            risk: RiskLevel::Low,
+            reason: String::new(),
+            scanned,
+        })
+        .collect();
+    assert!(actions.iter().all(|a| a.scanned.is_some()));
+
+    // 生成计划后用户继续编辑了 notes.txt
+    fs::write(&notes, b"draft with important changes").unwrap();
+
+    let outcome = futures::executor::block_on(run_execution(
+        &root.join("journal"),
+        "exec-plan",
+        &actions,
+        &[ExecutionMode::Permanent; 2],
+        &SnapshotConfig::default(),
+        false,
+        &mut DeleteExecutor,
+    ))
+    .unwrap();
+    assert!(!old_log.exists());
+    assert_eq!(fs::read(&notes).unwrap(), b"draft with important changes");
+    assert_eq!(outcome.executed, 1);
+    let changed: Vec<(usize, &str)> = outcome
+        .changed
+        .iter()
+        .map(|c| (c.index, c.path.as_str()))
+        .collect();
+    assert_eq!(changed, vec![(1, path(&notes).as_str())]);
+}